@@ -0,0 +1,178 @@
+//! Server access logging of buckets
+//!
+//! See the methods
+//! [`get_access_logging`](crate::bucket_config::BucketConfigExt::get_access_logging),
+//! [`put_access_logging`](crate::bucket_config::BucketConfigExt::put_access_logging) and
+//! [`enable_access_logging`](crate::bucket_config::BucketConfigExt::enable_access_logging) of
+//! [`BucketConfigExt`](crate::bucket_config::BucketConfigExt).
+//!
+//! # Log delivery permission
+//!
+//! S3 must be allowed to write to the target bucket. Traditionally the
+//! [`LOG_DELIVERY`](crate::acl::LOG_DELIVERY) group is granted `WRITE` and
+//! `READ_ACP` in the bucket ACL, which fails for buckets with ACLs disabled,
+//! the default for new buckets. `enable_access_logging` adds a statement for
+//! the logging service principal to the policy of the target bucket instead,
+//! see [`Statement::allow_log_delivery`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::bucket_config::BucketConfigExt;
+//!
+//! client
+//!     .enable_access_logging("bucket", "audit-logs", "bucket/")
+//!     .await?;
+//! let logging = client.get_access_logging("bucket").await?;
+//! assert_eq!(logging.unwrap().target_bucket, "audit-logs");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    acl::{grantee_from_rusoto, grantee_to_rusoto, Grant},
+    backend::Backend,
+    bucket_config::BucketConfigExt,
+    error::S3ExtResult,
+    policy::Statement,
+};
+use rusoto_s3::{
+    BucketLoggingStatus, GetBucketLoggingRequest, LoggingEnabled, PutBucketLoggingRequest,
+    TargetGrant,
+};
+
+/// Server access logging configuration of a bucket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessLogging {
+    /// Bucket the logs are written to
+    pub target_bucket: String,
+    /// Prefix of the keys of the log objects, e.g. `logs/`
+    pub target_prefix: String,
+    /// Grants applied to the log objects
+    ///
+    /// Only `FULL_CONTROL`, `READ` and `WRITE` are accepted, and not at all
+    /// if ACLs are disabled on the target bucket.
+    pub target_grants: Vec<Grant>,
+}
+
+impl AccessLogging {
+    /// Write logs to `target_bucket` below `target_prefix`
+    pub fn new(target_bucket: impl Into<String>, target_prefix: impl Into<String>) -> Self {
+        AccessLogging {
+            target_bucket: target_bucket.into(),
+            target_prefix: target_prefix.into(),
+            target_grants: Vec::new(),
+        }
+    }
+
+    /// Add `grant` to the log objects
+    pub fn grant(mut self, grant: Grant) -> Self {
+        self.target_grants.push(grant);
+        self
+    }
+
+    /// Convert into a Rusoto `LoggingEnabled`
+    pub fn to_configuration(&self) -> LoggingEnabled {
+        let target_grants = self
+            .target_grants
+            .iter()
+            .map(|grant| TargetGrant {
+                grantee: Some(grantee_to_rusoto(&grant.grantee)),
+                permission: Some(grant.permission.as_str().to_owned()),
+            })
+            .collect::<Vec<_>>();
+        LoggingEnabled {
+            target_bucket: self.target_bucket.clone(),
+            target_prefix: self.target_prefix.clone(),
+            target_grants: if target_grants.is_empty() {
+                None
+            } else {
+                Some(target_grants)
+            },
+        }
+    }
+
+    /// Convert a Rusoto `LoggingEnabled`
+    ///
+    /// Grants with a missing grantee or unknown permission are skipped.
+    pub fn from_configuration(configuration: LoggingEnabled) -> Self {
+        AccessLogging {
+            target_bucket: configuration.target_bucket,
+            target_prefix: configuration.target_prefix,
+            target_grants: configuration
+                .target_grants
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|grant| {
+                    Some(Grant {
+                        grantee: grantee_from_rusoto(grant.grantee?)?,
+                        permission: grant.permission?.parse().ok()?,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+// Statement IDs may only contain letters and digits
+fn delivery_sid(bucket: &str) -> String {
+    let mut sid = "S3ServerAccessLogs".to_owned();
+    sid.extend(bucket.chars().filter(char::is_ascii_alphanumeric));
+    sid
+}
+
+pub(crate) async fn get_access_logging(
+    client: &Backend,
+    bucket: String,
+) -> S3ExtResult<Option<AccessLogging>> {
+    let request = GetBucketLoggingRequest {
+        bucket,
+        ..Default::default()
+    };
+    let output = client.get_bucket_logging(request).await?;
+    Ok(output
+        .logging_enabled
+        .map(AccessLogging::from_configuration))
+}
+
+pub(crate) async fn put_access_logging(
+    client: &Backend,
+    bucket: String,
+    logging: Option<&AccessLogging>,
+) -> S3ExtResult<()> {
+    let request = PutBucketLoggingRequest {
+        bucket,
+        bucket_logging_status: BucketLoggingStatus {
+            logging_enabled: logging.map(AccessLogging::to_configuration),
+        },
+        ..Default::default()
+    };
+    Ok(client.put_bucket_logging(request).await?)
+}
+
+pub(crate) async fn enable_access_logging<C>(
+    client: &C,
+    bucket: String,
+    target_bucket: String,
+    prefix: String,
+) -> S3ExtResult<()>
+where
+    C: BucketConfigExt + Sync + ?Sized,
+{
+    let statement =
+        Statement::allow_log_delivery(&target_bucket, &prefix, &bucket).sid(delivery_sid(&bucket));
+    let mut policy = client
+        .get_bucket_policy(target_bucket.clone())
+        .await?
+        .unwrap_or_default();
+    if !policy.statements.contains(&statement) {
+        policy.statements.retain(|s| s.sid != statement.sid);
+        policy.statements.push(statement);
+        client
+            .put_bucket_policy(target_bucket.clone(), &policy)
+            .await?;
+    }
+    let logging = AccessLogging::new(target_bucket, prefix);
+    client.put_access_logging(bucket, Some(&logging)).await
+}
@@ -0,0 +1,354 @@
+//! Object ACLs as plain grants
+//!
+//! See [`S3Ext::get_acl`](crate::S3Ext::get_acl),
+//! [`S3Ext::put_acl`](crate::S3Ext::put_acl) and
+//! [`S3Ext::put_canned_acl`](crate::S3Ext::put_canned_acl).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::{
+//!     acl::{Grant, Grantee, Permission},
+//!     S3Ext,
+//! };
+//!
+//! let mut acl = client.get_acl("bucket", "key").await?;
+//! if acl.is_public() {
+//!     acl.grants.retain(|grant| !grant.grantee.is_public());
+//!     acl.grants.push(Grant {
+//!         grantee: Grantee::email("audit@example.com"),
+//!         permission: Permission::Read,
+//!     });
+//!     client.put_acl("bucket", "key", &acl).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    error::{S3ExtError, S3ExtResult},
+};
+use rusoto_s3::{AccessControlPolicy, GetObjectAclRequest, Owner, PutObjectAclRequest};
+use std::{fmt, str::FromStr};
+
+/// URI of the group of all users, including anonymous ones
+pub const ALL_USERS: &str = "http://acs.amazonaws.com/groups/global/AllUsers";
+
+/// URI of the group of all authenticated AWS users, of any account
+pub const AUTHENTICATED_USERS: &str = "http://acs.amazonaws.com/groups/global/AuthenticatedUsers";
+
+/// URI of the group used for server access logging
+pub const LOG_DELIVERY: &str = "http://acs.amazonaws.com/groups/s3/LogDelivery";
+
+/// Predefined ACL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CannedAcl {
+    /// Owner gets full control, nobody else has access
+    Private,
+    /// Owner gets full control, everybody may read
+    PublicRead,
+    /// Owner gets full control, everybody may read and write
+    PublicReadWrite,
+    /// Owner gets full control, authenticated users may read
+    AuthenticatedRead,
+    /// Owner gets full control, EC2 may read AMI bundles
+    AwsExecRead,
+    /// Object owner gets full control, bucket owner may read
+    BucketOwnerRead,
+    /// Object and bucket owner get full control
+    BucketOwnerFullControl,
+}
+
+impl CannedAcl {
+    /// Value of the `x-amz-acl` header
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CannedAcl::Private => "private",
+            CannedAcl::PublicRead => "public-read",
+            CannedAcl::PublicReadWrite => "public-read-write",
+            CannedAcl::AuthenticatedRead => "authenticated-read",
+            CannedAcl::AwsExecRead => "aws-exec-read",
+            CannedAcl::BucketOwnerRead => "bucket-owner-read",
+            CannedAcl::BucketOwnerFullControl => "bucket-owner-full-control",
+        }
+    }
+}
+
+impl fmt::Display for CannedAcl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Permission granted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Permission {
+    /// All of the permissions below
+    FullControl,
+    /// Read the object
+    Read,
+    /// Read the ACL
+    ReadAcp,
+    /// Write the object, only meaningful for buckets
+    Write,
+    /// Write the ACL
+    WriteAcp,
+}
+
+impl Permission {
+    /// Name of the permission as used by S3, e.g. `FULL_CONTROL`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Permission::FullControl => "FULL_CONTROL",
+            Permission::Read => "READ",
+            Permission::ReadAcp => "READ_ACP",
+            Permission::Write => "WRITE",
+            Permission::WriteAcp => "WRITE_ACP",
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Permission {
+    type Err = S3ExtError;
+
+    fn from_str(s: &str) -> S3ExtResult<Self> {
+        match s {
+            "FULL_CONTROL" => Ok(Permission::FullControl),
+            "READ" => Ok(Permission::Read),
+            "READ_ACP" => Ok(Permission::ReadAcp),
+            "WRITE" => Ok(Permission::Write),
+            "WRITE_ACP" => Ok(Permission::WriteAcp),
+            _ => Err(S3ExtError::Other("unknown permission")),
+        }
+    }
+}
+
+/// Receiver of a grant
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Grantee {
+    /// AWS account, by canonical user ID
+    CanonicalUser {
+        /// Canonical user ID
+        id: String,
+        /// Display name, only returned by some regions
+        display_name: Option<String>,
+    },
+    /// AWS account, by email address of the root user
+    Email(String),
+    /// Predefined group, by URI, e.g. [`ALL_USERS`]
+    Group(String),
+}
+
+impl Grantee {
+    /// Account with canonical user ID `id`
+    pub fn canonical_user(id: impl Into<String>) -> Self {
+        Grantee::CanonicalUser {
+            id: id.into(),
+            display_name: None,
+        }
+    }
+
+    /// Account with root user email address `email`
+    pub fn email(email: impl Into<String>) -> Self {
+        Grantee::Email(email.into())
+    }
+
+    /// Group of all users, including anonymous ones
+    pub fn all_users() -> Self {
+        Grantee::Group(ALL_USERS.to_owned())
+    }
+
+    /// Group of all authenticated AWS users
+    pub fn authenticated_users() -> Self {
+        Grantee::Group(AUTHENTICATED_USERS.to_owned())
+    }
+
+    /// Group delivering server access logs, see [`LOG_DELIVERY`]
+    pub fn log_delivery() -> Self {
+        Grantee::Group(LOG_DELIVERY.to_owned())
+    }
+
+    /// Grantee is [`ALL_USERS`] or [`AUTHENTICATED_USERS`]
+    pub fn is_public(&self) -> bool {
+        matches!(self, Grantee::Group(uri) if uri == ALL_USERS || uri == AUTHENTICATED_USERS)
+    }
+}
+
+/// Permission granted to a grantee
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Grant {
+    /// Receiver of the permission
+    pub grantee: Grantee,
+    /// Permission granted
+    pub permission: Permission,
+}
+
+/// ACL of an object
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Acl {
+    /// Canonical user ID of the owner
+    ///
+    /// Required by S3 when putting an ACL, use the owner returned by
+    /// `get_acl`.
+    pub owner: Option<String>,
+    /// Grants, in the order returned by S3
+    pub grants: Vec<Grant>,
+}
+
+impl Acl {
+    /// Whether anybody or any authenticated AWS user is granted a
+    /// permission
+    pub fn is_public(&self) -> bool {
+        self.grants.iter().any(|grant| grant.grantee.is_public())
+    }
+
+    /// Permissions granted to `grantee`
+    pub fn permissions_of<'a>(
+        &'a self,
+        grantee: &'a Grantee,
+    ) -> impl Iterator<Item = Permission> + 'a {
+        self.grants
+            .iter()
+            .filter(move |grant| &grant.grantee == grantee)
+            .map(|grant| grant.permission)
+    }
+
+    /// Convert a Rusoto `AccessControlPolicy` into an ACL
+    ///
+    /// Grants with a missing grantee or unknown permission are skipped.
+    pub fn from_policy(policy: AccessControlPolicy) -> Self {
+        Acl {
+            owner: policy.owner.and_then(|owner| owner.id),
+            grants: policy
+                .grants
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(grant_from_rusoto)
+                .collect(),
+        }
+    }
+
+    /// Convert the ACL into a Rusoto `AccessControlPolicy`
+    pub fn to_policy(&self) -> AccessControlPolicy {
+        AccessControlPolicy {
+            owner: self.owner.as_ref().map(|id| Owner {
+                id: Some(id.clone()),
+                display_name: None,
+            }),
+            grants: Some(self.grants.iter().map(grant_to_rusoto).collect()),
+        }
+    }
+}
+
+pub(crate) fn grantee_from_rusoto(grantee: rusoto_s3::Grantee) -> Option<Grantee> {
+    Some(match grantee.type_.as_str() {
+        "CanonicalUser" => Grantee::CanonicalUser {
+            id: grantee.id?,
+            display_name: grantee.display_name,
+        },
+        "AmazonCustomerByEmail" => Grantee::Email(grantee.email_address?),
+        "Group" => Grantee::Group(grantee.uri?),
+        _ => return None,
+    })
+}
+
+fn grant_from_rusoto(grant: rusoto_s3::Grant) -> Option<Grant> {
+    let grantee = grantee_from_rusoto(grant.grantee?)?;
+    let permission = grant.permission?.parse().ok()?;
+    Some(Grant {
+        grantee,
+        permission,
+    })
+}
+
+pub(crate) fn grantee_to_rusoto(grantee: &Grantee) -> rusoto_s3::Grantee {
+    match grantee {
+        Grantee::CanonicalUser { id, display_name } => rusoto_s3::Grantee {
+            type_: "CanonicalUser".to_owned(),
+            id: Some(id.clone()),
+            display_name: display_name.clone(),
+            ..Default::default()
+        },
+        Grantee::Email(email) => rusoto_s3::Grantee {
+            type_: "AmazonCustomerByEmail".to_owned(),
+            email_address: Some(email.clone()),
+            ..Default::default()
+        },
+        Grantee::Group(uri) => rusoto_s3::Grantee {
+            type_: "Group".to_owned(),
+            uri: Some(uri.clone()),
+            ..Default::default()
+        },
+    }
+}
+
+fn grant_to_rusoto(grant: &Grant) -> rusoto_s3::Grant {
+    rusoto_s3::Grant {
+        grantee: Some(grantee_to_rusoto(&grant.grantee)),
+        permission: Some(grant.permission.as_str().to_owned()),
+    }
+}
+
+pub(crate) async fn get_acl(client: &Backend, bucket: String, key: String) -> S3ExtResult<Acl> {
+    let request = GetObjectAclRequest {
+        bucket: bucket.clone(),
+        key: key.clone(),
+        ..Default::default()
+    };
+    client
+        .get_object_acl(request)
+        .await
+        .map(|output| {
+            Acl::from_policy(AccessControlPolicy {
+                owner: output.owner,
+                grants: output.grants,
+            })
+        })
+        .map_err(|e| S3ExtError::from(e).with_object(bucket, key))
+}
+
+pub(crate) async fn put_acl(
+    client: &Backend,
+    bucket: String,
+    key: String,
+    acl: &Acl,
+) -> S3ExtResult<()> {
+    let request = PutObjectAclRequest {
+        bucket: bucket.clone(),
+        key: key.clone(),
+        access_control_policy: Some(acl.to_policy()),
+        ..Default::default()
+    };
+    client
+        .put_object_acl(request)
+        .await
+        .map(drop)
+        .map_err(|e| S3ExtError::from(e).with_object(bucket, key))
+}
+
+pub(crate) async fn put_canned_acl(
+    client: &Backend,
+    bucket: String,
+    key: String,
+    acl: CannedAcl,
+) -> S3ExtResult<()> {
+    let request = PutObjectAclRequest {
+        bucket: bucket.clone(),
+        key: key.clone(),
+        acl: Some(acl.as_str().to_owned()),
+        ..Default::default()
+    };
+    client
+        .put_object_acl(request)
+        .await
+        .map(drop)
+        .map_err(|e| S3ExtError::from(e).with_object(bucket, key))
+}
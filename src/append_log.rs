@@ -0,0 +1,324 @@
+//! Append-only logs stored as numbered segment objects
+//!
+//! S3 can't append to objects. An [`AppendLog`] buffers appended records and
+//! writes them as a new segment object once [`AppendLog::flush_size`] bytes
+//! have accumulated or on [`AppendLog::flush`]. Once there are more than
+//! [`AppendLog::max_segments`] segments, they are compacted into a single
+//! one using [`S3Ext::compose_object`]: segments of at least 5 MiB are copied
+//! within S3, smaller ones are downloaded and uploaded again.
+//!
+//! # Segments
+//!
+//! Segments are named `<prefix><first>-<last>`, both numbers zero-padded to
+//! 20 digits, and hold the records of segments `first` to `last`. Readers
+//! pick the widest segment at every position, so a compacted segment hides
+//! the segments it replaces even before they are deleted.
+//!
+//! Records are prefixed by their length as 32-bit big-endian integer, see
+//! [`tokio_util::codec::LengthDelimitedCodec`].
+//!
+//! A log must have a single writer, concurrent writers overwrite each
+//! other's segments.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use futures::TryStreamExt;
+//! use s3_ext::append_log::AppendLog;
+//!
+//! let mut log = AppendLog::open(client, "bucket", "audit/").await?;
+//! log.append(b"user logged in").await?;
+//! log.flush().await?;
+//!
+//! let mut records = Box::pin(log.records());
+//! while let Some(record) = records.try_next().await? {
+//!     println!("{}", String::from_utf8_lossy(&record));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    compose::ComposePart,
+    error::{S3ExtError, S3ExtResult},
+    upload::{MAX_PART_SIZE, MIN_PART_SIZE},
+    S3Ext,
+};
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+use std::mem;
+use tokio_util::codec::LengthDelimitedCodec;
+
+/// Default number of buffered bytes written as a segment, 5 MiB
+pub const DEFAULT_FLUSH_SIZE: usize = MIN_PART_SIZE;
+
+/// Default number of segments kept before compacting them, 32
+pub const DEFAULT_MAX_SEGMENTS: usize = 32;
+
+/// Segment object of an [`AppendLog`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// Number of the first segment included
+    pub first: u64,
+    /// Number of the last segment included
+    pub last: u64,
+    /// Key of the object
+    pub key: String,
+    /// Size of the object
+    pub size: u64,
+}
+
+impl Segment {
+    fn new(prefix: &str, first: u64, last: u64, size: u64) -> Self {
+        Segment {
+            first,
+            last,
+            key: format!("{}{:020}-{:020}", prefix, first, last),
+            size,
+        }
+    }
+
+    // Segment stored as `key`, `None` for other objects below the prefix
+    fn parse(prefix: &str, key: &str, size: u64) -> Option<Self> {
+        let (first, last) = key.strip_prefix(prefix)?.split_once('-')?;
+        if first.len() != 20 || last.len() != 20 {
+            return None;
+        }
+        let (first, last) = (first.parse().ok()?, last.parse().ok()?);
+        if first > last {
+            return None;
+        }
+        Some(Segment {
+            first,
+            last,
+            key: key.to_owned(),
+            size,
+        })
+    }
+}
+
+/// Log of records below a prefix of a bucket
+pub struct AppendLog<C> {
+    client: C,
+    bucket: String,
+    prefix: String,
+    segments: Vec<Segment>,
+    // keys of segments included in others
+    superseded: Vec<String>,
+    next: u64,
+    buffer: Vec<u8>,
+    flush_size: usize,
+    max_segments: usize,
+}
+
+impl<C> AppendLog<C>
+where
+    C: S3Ext,
+{
+    /// Open the log stored in `bucket` below `prefix`, e.g. `logs/audit/`
+    ///
+    /// The log is empty if there are no segments.
+    pub async fn open(
+        client: C,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> S3ExtResult<Self> {
+        let (bucket, prefix) = (bucket.into(), prefix.into());
+        let mut found = Vec::new();
+        let mut objects = client.stream_objects_with_prefix(bucket.clone(), prefix.clone());
+        while let Some(object) = objects.next().await {
+            let object = object?;
+            let size = object.size.unwrap_or_default() as u64;
+            if let Some(segment) = object
+                .key
+                .as_deref()
+                .and_then(|key| Segment::parse(&prefix, key, size))
+            {
+                found.push(segment);
+            }
+        }
+        let next = found
+            .iter()
+            .map(|segment| segment.last + 1)
+            .max()
+            .unwrap_or(0);
+
+        // widest segment first at every position
+        found.sort_by(|a, b| a.first.cmp(&b.first).then(b.last.cmp(&a.last)));
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut superseded = Vec::new();
+        for segment in found {
+            match segments.last() {
+                Some(previous) if segment.first <= previous.last => superseded.push(segment.key),
+                _ => segments.push(segment),
+            }
+        }
+
+        Ok(AppendLog {
+            client,
+            bucket,
+            prefix,
+            segments,
+            superseded,
+            next,
+            buffer: Vec::new(),
+            flush_size: DEFAULT_FLUSH_SIZE,
+            max_segments: DEFAULT_MAX_SEGMENTS,
+        })
+    }
+
+    /// Write a segment once `bytes` bytes of records are buffered
+    pub fn flush_size(mut self, bytes: usize) -> Self {
+        self.flush_size = bytes;
+        self
+    }
+
+    /// Compact the segments once there are more than `segments`
+    pub fn max_segments(mut self, segments: usize) -> Self {
+        self.max_segments = segments;
+        self
+    }
+
+    /// Underlying client
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    /// Segments of the log, in order
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Append `record`, writing a segment if enough records are buffered
+    pub async fn append(&mut self, record: &[u8]) -> S3ExtResult<()> {
+        if record.len() > u32::MAX as usize {
+            return Err(S3ExtError::Other("records must be smaller than 4 GiB"));
+        }
+        self.buffer
+            .extend_from_slice(&(record.len() as u32).to_be_bytes());
+        self.buffer.extend_from_slice(record);
+        if self.buffer.len() >= self.flush_size {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Write the buffered records as a new segment
+    ///
+    /// The segments are compacted if there are more than
+    /// [`max_segments`](Self::max_segments) afterwards.
+    pub async fn flush(&mut self) -> S3ExtResult<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let segment = Segment::new(&self.prefix, self.next, self.next, self.buffer.len() as u64);
+        let request = PutObjectRequest {
+            bucket: self.bucket.clone(),
+            key: segment.key.clone(),
+            content_length: Some(self.buffer.len() as i64),
+            ..Default::default()
+        };
+        self.client
+            .upload(&mut self.buffer.as_slice(), request)
+            .await?;
+        self.buffer.clear();
+        self.segments.push(segment);
+        self.next += 1;
+        if self.segments.len() > self.max_segments {
+            self.compact().await?;
+        }
+        Ok(())
+    }
+
+    /// Replace all segments and the buffered records by a single segment
+    pub async fn compact(&mut self) -> S3ExtResult<()> {
+        if self.buffer.is_empty() && self.segments.len() <= 1 {
+            return Ok(());
+        }
+        let mut parts = Vec::new();
+        let mut pending = Vec::new();
+        let mut size = self.buffer.len() as u64;
+        for segment in &self.segments {
+            size += segment.size;
+            if pending.is_empty() && segment.size >= MIN_PART_SIZE as u64 {
+                parts.extend(self.copy_parts(segment));
+                continue;
+            }
+            let request = GetObjectRequest {
+                bucket: self.bucket.clone(),
+                key: segment.key.clone(),
+                ..Default::default()
+            };
+            self.client.download(request, &mut pending).await?;
+            if pending.len() >= MIN_PART_SIZE {
+                parts.push(ComposePart::Data(mem::take(&mut pending)));
+            }
+        }
+        pending.extend_from_slice(&self.buffer);
+        if !pending.is_empty() {
+            parts.push(ComposePart::Data(pending));
+        }
+
+        let first = self
+            .segments
+            .first()
+            .map_or(self.next, |segment| segment.first);
+        let last = if self.buffer.is_empty() {
+            self.next - 1
+        } else {
+            self.next
+        };
+        let compacted = Segment::new(&self.prefix, first, last, size);
+        self.client
+            .compose_object(self.bucket.clone(), compacted.key.clone(), parts)
+            .await?;
+        self.buffer.clear();
+        self.next = last + 1;
+        let segments = mem::replace(&mut self.segments, vec![compacted]);
+        self.superseded
+            .extend(segments.into_iter().map(|segment| segment.key));
+
+        while let Some(key) = self.superseded.last() {
+            self.client.delete(self.bucket.clone(), key.clone()).await?;
+            self.superseded.pop();
+        }
+        Ok(())
+    }
+
+    // Parts copying `segment`, split into equal ranges if larger than a part
+    fn copy_parts(&self, segment: &Segment) -> Vec<ComposePart> {
+        let count = (segment.size - 1) / MAX_PART_SIZE as u64 + 1;
+        if count == 1 {
+            return vec![ComposePart::copy(&self.bucket, &segment.key)];
+        }
+        let chunk = (segment.size - 1) / count + 1;
+        (0..count)
+            .map(|i| {
+                let range = i * chunk..segment.size.min((i + 1) * chunk);
+                ComposePart::copy_range(&self.bucket, &segment.key, range)
+            })
+            .collect()
+    }
+
+    /// Stream over the records of the segments, in order
+    ///
+    /// Buffered records aren't included, see [`flush`](Self::flush).
+    pub fn records(&self) -> impl Stream<Item = S3ExtResult<Vec<u8>>> + '_ {
+        let keys: Vec<String> = self
+            .segments
+            .iter()
+            .map(|segment| segment.key.clone())
+            .collect();
+        stream::iter(keys)
+            .then(move |key| {
+                let codec = LengthDelimitedCodec::builder()
+                    .max_frame_length(u32::MAX as usize)
+                    .new_codec();
+                self.client.get_framed(self.bucket.clone(), key, codec)
+            })
+            .map_ok(|frames| frames.map_err(S3ExtError::from))
+            .try_flatten()
+            .map_ok(|record| record.to_vec())
+    }
+}
@@ -0,0 +1,540 @@
+//! Tar and zip archives of objects
+//!
+//! See [`S3Ext::archive_prefix`](crate::S3Ext::archive_prefix) and, for the
+//! inverse, [`S3Ext::upload_tar`](crate::S3Ext::upload_tar).
+//!
+//! Objects are streamed into the archive one after another, nothing is
+//! staged on disk. Entries are named by the key relative to the last `/` of
+//! the prefix, keys ending in `/` (folder markers) are skipped.
+//!
+//! # Formats
+//!
+//! Tar archives use the GNU format: names longer than 100 bytes are written
+//! as `././@LongLink` entries and sizes of 8 GiB or more are base-256
+//! encoded.
+//!
+//! Zip entries are stored without compression. The CRC-32 of an entry is only
+//! known once it has been written and follows it in a data descriptor. Zip64
+//! isn't supported, objects and archives of 4 GiB or more are rejected; use
+//! tar for those.
+//!
+//! # Uploading tar archives
+//!
+//! Regular files of a tar archive are uploaded as objects named by the
+//! prefix followed by the path in the archive, without a leading `./`. Long
+//! names are read from GNU `././@LongLink` and PAX `path` records, other
+//! entry types such as directories and links are skipped. Entries larger than
+//! [`MULTIPART_THRESHOLD`] are uploaded using multipart upload.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::{archive::ArchiveFormat, S3Ext};
+//!
+//! let mut file = tokio::fs::File::create("photos.zip").await?;
+//! let count = client
+//!     .archive_prefix("bucket", "photos/2019/", &mut file, ArchiveFormat::Zip)
+//!     .await?;
+//! println!("archived {} photos", count);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::{S3ExtError, S3ExtResult},
+    timestamps::Timestamps,
+    upload::{MAX_PARTS, MIN_PART_SIZE},
+    S3Ext,
+};
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use crc32fast::Hasher;
+use futures::StreamExt;
+use rusoto_s3::PutObjectRequest;
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Tar entries larger than this are uploaded using multipart upload
+pub const MULTIPART_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+const BLOCK_SIZE: usize = 512;
+
+// Largest PAX extended header read into memory
+const MAX_EXTENDED_HEADER: u64 = 1024 * 1024;
+
+// Largest size or offset representable without zip64
+const ZIP_LIMIT: u64 = u32::MAX as u64;
+
+/// Format of an archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// GNU tar
+    Tar,
+    /// Zip, entries are stored uncompressed
+    Zip,
+}
+
+pub(crate) async fn archive_prefix<C, W>(
+    client: &C,
+    bucket: String,
+    prefix: String,
+    target: &mut W,
+    format: ArchiveFormat,
+) -> S3ExtResult<usize>
+where
+    C: S3Ext + ?Sized,
+    W: AsyncWrite + Unpin + Send,
+{
+    let strip = prefix.rfind('/').map_or(0, |i| i + 1);
+    let mut objects = client.stream_get_objects_with_prefix(bucket.clone(), prefix);
+    let mut archive = Archive::new(format, target);
+    let mut count = 0;
+    while let Some(object) = objects.next().await {
+        let (key, mut output) = object?;
+        if key.ends_with('/') || key.len() <= strip {
+            continue;
+        }
+        let modified = output.last_modified_at();
+        let size = output.content_length.unwrap_or_default() as u64;
+        let result = match output.body.take() {
+            Some(body) => {
+                let mut body = body.into_async_read();
+                archive
+                    .entry(&key[strip..], size, modified, &mut body)
+                    .await
+            }
+            None => {
+                archive
+                    .entry(&key[strip..], size, modified, &mut io::empty())
+                    .await
+            }
+        };
+        result.map_err(|e| e.with_object(&bucket, &key))?;
+        count += 1;
+    }
+    archive.finish().await?;
+    Ok(count)
+}
+
+// Central directory record of a zip entry
+struct ZipEntry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+    time: u16,
+    date: u16,
+}
+
+struct Archive<'a, W> {
+    format: ArchiveFormat,
+    target: &'a mut W,
+    offset: u64,
+    entries: Vec<ZipEntry>,
+}
+
+impl<'a, W> Archive<'a, W>
+where
+    W: AsyncWrite + Unpin + Send,
+{
+    fn new(format: ArchiveFormat, target: &'a mut W) -> Self {
+        Archive {
+            format,
+            target,
+            offset: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    async fn write(&mut self, buf: &[u8]) -> S3ExtResult<()> {
+        self.target.write_all(buf).await?;
+        self.offset += buf.len() as u64;
+        Ok(())
+    }
+
+    // Copy exactly `size` bytes of `body`, returning their CRC-32
+    async fn copy<R>(&mut self, body: &mut R, size: u64) -> S3ExtResult<u32>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        let mut crc = Hasher::new();
+        let mut buf = vec![0; 64 * 1024];
+        let mut copied = 0;
+        loop {
+            let n = body.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            copied += n as u64;
+            if copied > size {
+                break;
+            }
+            crc.update(&buf[..n]);
+            self.write(&buf[..n]).await?;
+        }
+        if copied != size {
+            return Err(S3ExtError::Other(
+                "object size doesn't match its Content-Length",
+            ));
+        }
+        Ok(crc.finalize())
+    }
+
+    async fn entry<R>(
+        &mut self,
+        name: &str,
+        size: u64,
+        modified: Option<DateTime<Utc>>,
+        body: &mut R,
+    ) -> S3ExtResult<()>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        match self.format {
+            ArchiveFormat::Tar => self.tar_entry(name, size, modified, body).await,
+            ArchiveFormat::Zip => self.zip_entry(name, size, modified, body).await,
+        }
+    }
+
+    async fn tar_entry<R>(
+        &mut self,
+        name: &str,
+        size: u64,
+        modified: Option<DateTime<Utc>>,
+        body: &mut R,
+    ) -> S3ExtResult<()>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        let mtime = modified.map_or(0, |modified| modified.timestamp().max(0) as u64);
+        if name.len() > 100 {
+            let long_name = name.len() as u64 + 1;
+            self.write(&tar_header("././@LongLink", long_name, 0, b'L'))
+                .await?;
+            self.write(name.as_bytes()).await?;
+            self.write(&[0]).await?;
+            self.pad(long_name).await?;
+        }
+        self.write(&tar_header(name, size, mtime, b'0')).await?;
+        self.copy(body, size).await?;
+        self.pad(size).await
+    }
+
+    // Fill the last block of an entry of `size` bytes with zeros
+    async fn pad(&mut self, size: u64) -> S3ExtResult<()> {
+        let rem = (size % BLOCK_SIZE as u64) as usize;
+        if rem > 0 {
+            self.write(&[0; BLOCK_SIZE][rem..]).await?;
+        }
+        Ok(())
+    }
+
+    async fn zip_entry<R>(
+        &mut self,
+        name: &str,
+        size: u64,
+        modified: Option<DateTime<Utc>>,
+        body: &mut R,
+    ) -> S3ExtResult<()>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        if size >= ZIP_LIMIT || self.offset >= ZIP_LIMIT || self.entries.len() >= 0xffff {
+            return Err(S3ExtError::Other(
+                "archive too large for zip without zip64, use tar",
+            ));
+        }
+        let (time, date) = dos_date_time(modified);
+        let offset = self.offset as u32;
+
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend_from_slice(&0x0403_4b50_u32.to_le_bytes());
+        header.extend_from_slice(&20_u16.to_le_bytes()); // version needed
+        header.extend_from_slice(&ZIP_FLAGS.to_le_bytes());
+        header.extend_from_slice(&0_u16.to_le_bytes()); // stored
+        header.extend_from_slice(&time.to_le_bytes());
+        header.extend_from_slice(&date.to_le_bytes());
+        // CRC-32 and sizes follow in the data descriptor
+        header.extend_from_slice(&[0; 12]);
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0_u16.to_le_bytes()); // extra field
+        header.extend_from_slice(name.as_bytes());
+        self.write(&header).await?;
+
+        let crc = self.copy(body, size).await?;
+
+        let mut descriptor = Vec::with_capacity(16);
+        descriptor.extend_from_slice(&0x0807_4b50_u32.to_le_bytes());
+        descriptor.extend_from_slice(&crc.to_le_bytes());
+        descriptor.extend_from_slice(&(size as u32).to_le_bytes());
+        descriptor.extend_from_slice(&(size as u32).to_le_bytes());
+        self.write(&descriptor).await?;
+
+        self.entries.push(ZipEntry {
+            name: name.to_owned(),
+            crc,
+            size: size as u32,
+            offset,
+            time,
+            date,
+        });
+        Ok(())
+    }
+
+    async fn finish(mut self) -> S3ExtResult<()> {
+        match self.format {
+            ArchiveFormat::Tar => self.write(&[0; 2 * BLOCK_SIZE]).await?,
+            ArchiveFormat::Zip => self.zip_central_directory().await?,
+        }
+        Ok(self.target.flush().await?)
+    }
+
+    async fn zip_central_directory(&mut self) -> S3ExtResult<()> {
+        let start = self.offset;
+        let mut directory = Vec::new();
+        for entry in &self.entries {
+            directory.extend_from_slice(&0x0201_4b50_u32.to_le_bytes());
+            directory.extend_from_slice(&20_u16.to_le_bytes()); // version made by
+            directory.extend_from_slice(&20_u16.to_le_bytes()); // version needed
+            directory.extend_from_slice(&ZIP_FLAGS.to_le_bytes());
+            directory.extend_from_slice(&0_u16.to_le_bytes()); // stored
+            directory.extend_from_slice(&entry.time.to_le_bytes());
+            directory.extend_from_slice(&entry.date.to_le_bytes());
+            directory.extend_from_slice(&entry.crc.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            // extra field, comment, disk, internal and external attributes
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&entry.offset.to_le_bytes());
+            directory.extend_from_slice(entry.name.as_bytes());
+        }
+        let end = start + directory.len() as u64;
+        if start >= ZIP_LIMIT || end >= ZIP_LIMIT {
+            return Err(S3ExtError::Other(
+                "archive too large for zip without zip64, use tar",
+            ));
+        }
+        let entries = self.entries.len() as u16;
+        directory.extend_from_slice(&0x0605_4b50_u32.to_le_bytes());
+        directory.extend_from_slice(&[0; 4]); // disk numbers
+        directory.extend_from_slice(&entries.to_le_bytes());
+        directory.extend_from_slice(&entries.to_le_bytes());
+        directory.extend_from_slice(&((end - start) as u32).to_le_bytes());
+        directory.extend_from_slice(&(start as u32).to_le_bytes());
+        directory.extend_from_slice(&0_u16.to_le_bytes()); // comment
+        self.write(&directory).await
+    }
+}
+
+// Data descriptor present, names encoded as UTF-8
+const ZIP_FLAGS: u16 = 0x0808;
+
+// GNU tar header of an entry of `size` bytes
+fn tar_header(name: &str, size: u64, mtime: u64, typeflag: u8) -> [u8; BLOCK_SIZE] {
+    let mut header = [0; BLOCK_SIZE];
+    let name = &name.as_bytes()[..name.len().min(100)];
+    header[..name.len()].copy_from_slice(name);
+    octal(&mut header[100..108], 0o644);
+    octal(&mut header[108..116], 0);
+    octal(&mut header[116..124], 0);
+    if size < 1 << 33 {
+        octal(&mut header[124..136], size);
+    } else {
+        header[124] = 0x80;
+        header[128..136].copy_from_slice(&size.to_be_bytes());
+    }
+    octal(&mut header[136..148], mtime);
+    header[156] = typeflag;
+    header[257..265].copy_from_slice(b"ustar  \0");
+
+    // computed with the checksum field filled with spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    header
+}
+
+// Zero-padded octal number terminated by NUL
+fn octal(field: &mut [u8], value: u64) {
+    let digits = field.len() - 1;
+    field[..digits].copy_from_slice(format!("{:0width$o}", value, width = digits).as_bytes());
+}
+
+// MS-DOS time and date used by zip, 1980-01-01 if unknown or earlier
+fn dos_date_time(modified: Option<DateTime<Utc>>) -> (u16, u16) {
+    match modified {
+        Some(modified) if modified.year() >= 1980 => {
+            let time = (modified.hour() << 11) | (modified.minute() << 5) | (modified.second() / 2);
+            let date = ((modified.year() - 1980).min(127) as u32) << 9
+                | modified.month() << 5
+                | modified.day();
+            (time as u16, date as u16)
+        }
+        _ => (0, 1 << 5 | 1),
+    }
+}
+
+pub(crate) async fn upload_tar<C, R>(
+    client: &C,
+    source: &mut R,
+    bucket: String,
+    prefix: String,
+) -> S3ExtResult<usize>
+where
+    C: S3Ext + ?Sized,
+    R: AsyncRead + Unpin + Send,
+{
+    let mut count = 0;
+    let mut long_name = None;
+    let mut header = [0; BLOCK_SIZE];
+    loop {
+        source.read_exact(&mut header).await?;
+        if header.iter().all(|&b| b == 0) {
+            // end of archive
+            return Ok(count);
+        }
+        let checksum: u64 = header[..148]
+            .iter()
+            .chain(&[b' '; 8])
+            .chain(&header[156..])
+            .map(|&b| u64::from(b))
+            .sum();
+        if parse_number(&header[148..156])? != checksum {
+            return Err(S3ExtError::Other("invalid tar header checksum"));
+        }
+        let size = parse_number(&header[124..136])?;
+        match header[156] {
+            typeflag @ b'L' | typeflag @ b'x' => {
+                if size > MAX_EXTENDED_HEADER {
+                    return Err(S3ExtError::Other("tar extended header too large"));
+                }
+                let mut content = Vec::new();
+                (&mut *source).take(size).read_to_end(&mut content).await?;
+                if content.len() as u64 != size {
+                    return Err(S3ExtError::Other("truncated tar archive"));
+                }
+                if typeflag == b'L' {
+                    let end = content
+                        .iter()
+                        .position(|&b| b == 0)
+                        .unwrap_or(content.len());
+                    long_name = Some(String::from_utf8_lossy(&content[..end]).into_owned());
+                } else if let Some(path) = pax_path(&content) {
+                    long_name = Some(path);
+                }
+            }
+            b'0' | b'7' | 0 => {
+                let name = long_name.take().unwrap_or_else(|| header_name(&header));
+                let name = name.trim_start_matches("./").trim_start_matches('/');
+                if name.is_empty() || name.ends_with('/') {
+                    skip(source, size).await?;
+                } else {
+                    let key = format!("{}{}", prefix, name);
+                    upload_entry(client, source, bucket.clone(), key, size).await?;
+                    count += 1;
+                }
+            }
+            _ => {
+                long_name = None;
+                skip(source, size).await?;
+            }
+        }
+        let rem = size % BLOCK_SIZE as u64;
+        if rem > 0 {
+            skip(source, BLOCK_SIZE as u64 - rem).await?;
+        }
+    }
+}
+
+async fn upload_entry<C, R>(
+    client: &C,
+    source: &mut R,
+    bucket: String,
+    key: String,
+    size: u64,
+) -> S3ExtResult<()>
+where
+    C: S3Ext + ?Sized,
+    R: AsyncRead + Unpin + Send,
+{
+    let mut entry = source.take(size);
+    let mut target = PutObjectRequest {
+        bucket,
+        key,
+        ..Default::default()
+    };
+    if size > MULTIPART_THRESHOLD {
+        let part_size = MIN_PART_SIZE.max(((size - 1) / MAX_PARTS as u64 + 1) as usize);
+        client
+            .upload_multipart(&mut entry, target, part_size)
+            .await?;
+    } else {
+        target.content_length = Some(size as i64);
+        client.upload(&mut entry, target).await?;
+    }
+    if entry.limit() > 0 {
+        return Err(S3ExtError::Other("truncated tar archive"));
+    }
+    Ok(())
+}
+
+// Discard `len` bytes of `source`
+async fn skip<R>(source: &mut R, len: u64) -> S3ExtResult<()>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    if io::copy(&mut source.take(len), &mut io::sink()).await? != len {
+        return Err(S3ExtError::Other("truncated tar archive"));
+    }
+    Ok(())
+}
+
+// Path of an entry, joining the prefix and name fields of POSIX archives
+fn header_name(header: &[u8; BLOCK_SIZE]) -> String {
+    let field = |range: &[u8]| {
+        let end = range.iter().position(|&b| b == 0).unwrap_or(range.len());
+        String::from_utf8_lossy(&range[..end]).into_owned()
+    };
+    let name = field(&header[..100]);
+    if &header[257..263] == b"ustar\0" {
+        let prefix = field(&header[345..500]);
+        if !prefix.is_empty() {
+            return format!("{}/{}", prefix, name);
+        }
+    }
+    name
+}
+
+// Value of the `path` record of a PAX extended header, records are
+// `<length> <keyword>=<value>\n`
+fn pax_path(mut content: &[u8]) -> Option<String> {
+    while !content.is_empty() {
+        let space = content.iter().position(|&b| b == b' ')?;
+        let len: usize = std::str::from_utf8(&content[..space]).ok()?.parse().ok()?;
+        if len <= space + 1 || len > content.len() {
+            return None;
+        }
+        let record = &content[space + 1..len - 1];
+        if let Some(path) = record.strip_prefix(b"path=") {
+            return Some(String::from_utf8_lossy(path).into_owned());
+        }
+        content = &content[len..];
+    }
+    None
+}
+
+// Octal number, or base-256 if the high bit of the first byte is set
+fn parse_number(field: &[u8]) -> S3ExtResult<u64> {
+    if field[0] & 0x80 != 0 {
+        return Ok(field[1..]
+            .iter()
+            .fold(u64::from(field[0] & 0x7f), |n, &b| n << 8 | u64::from(b)));
+    }
+    let digits = std::str::from_utf8(field)
+        .map_err(|_| S3ExtError::Other("invalid number in tar header"))?
+        .trim_matches(|c: char| c == ' ' || c == '\0');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(digits, 8).map_err(|_| S3ExtError::Other("invalid number in tar header"))
+}
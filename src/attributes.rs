@@ -0,0 +1,178 @@
+//! Size, entity tag and part layout of objects
+//!
+//! See [`S3Ext::object_attributes`](crate::S3Ext::object_attributes) and
+//! [`S3Ext::head_many`](crate::S3Ext::head_many), which gets the attributes
+//! of many objects concurrently, e.g. to check which files of a sync exist
+//! already.
+//!
+//! # Checksums
+//!
+//! Rusoto doesn't model the `x-amz-checksum-*` headers, the only checksum
+//! available is the MD5 digest S3 uses as entity tag of objects uploaded in a
+//! single request without SSE-C or SSE-KMS, see
+//! [`ObjectAttributes::content_md5`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::S3Ext;
+//!
+//! let attributes = client.object_attributes("bucket", "dataset.parquet").await?;
+//! if let (Some(parts), Some(part_size)) = (attributes.parts_count, attributes.part_size) {
+//!     println!("{} parts of {} bytes", parts, part_size);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    error::{S3ExtError, S3ExtResult},
+    S3Ext,
+};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use rusoto_s3::HeadObjectRequest;
+
+/// Attributes of an object
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObjectAttributes {
+    /// Size of the object
+    pub size: i64,
+    /// Entity tag, including the surrounding quotes
+    pub e_tag: Option<String>,
+    /// Number of parts of objects written by multipart uploads, `None` for
+    /// objects uploaded in a single request
+    pub parts_count: Option<i64>,
+    /// Size of the first part, all but the last part have this size if the
+    /// object was uploaded using fixed size parts
+    pub part_size: Option<i64>,
+    /// Storage class
+    pub storage_class: String,
+    /// Time of the last modification, in HTTP date format
+    pub last_modified: Option<String>,
+    /// Version ID, if versioning is enabled
+    pub version_id: Option<String>,
+    /// Server-side encryption, `AES256` or `aws:kms`
+    pub server_side_encryption: Option<String>,
+    /// Algorithm of the customer-provided key (SSE-C)
+    pub sse_customer_algorithm: Option<String>,
+}
+
+impl ObjectAttributes {
+    /// Whether the object was written by a multipart upload
+    pub fn is_multipart(&self) -> bool {
+        self.parts_count.is_some() || multipart_parts(self.e_tag.as_deref()).is_some()
+    }
+
+    /// Hex encoded MD5 digest of the content, if the entity tag is one
+    ///
+    /// That's the case for objects uploaded in a single request and
+    /// encrypted with SSE-S3 or not at all.
+    pub fn content_md5(&self) -> Option<&str> {
+        if self.is_multipart()
+            || self.sse_customer_algorithm.is_some()
+            || self.server_side_encryption.as_deref() == Some("aws:kms")
+        {
+            return None;
+        }
+        let e_tag = self.e_tag.as_deref()?.trim_matches('"');
+        if e_tag.len() == 32 && e_tag.bytes().all(|b| b.is_ascii_hexdigit()) {
+            Some(e_tag)
+        } else {
+            None
+        }
+    }
+}
+
+// Number of parts encoded in the entity tag of multipart objects, `"<md5>-<parts>"`
+fn multipart_parts(e_tag: Option<&str>) -> Option<i64> {
+    let (_, parts) = e_tag?.trim_matches('"').rsplit_once('-')?;
+    parts.parse().ok()
+}
+
+pub(crate) async fn object_attributes(
+    client: &Backend,
+    bucket: String,
+    key: String,
+    get_object_attributes: bool,
+) -> S3ExtResult<ObjectAttributes> {
+    let head = |part_number| HeadObjectRequest {
+        bucket: bucket.clone(),
+        key: key.clone(),
+        part_number,
+        ..Default::default()
+    };
+    let output = client
+        .head_object(head(None))
+        .await
+        .map_err(|e| S3ExtError::from(e).with_object(&bucket, &key))?;
+    let mut attributes = ObjectAttributes {
+        size: output.content_length.unwrap_or_default(),
+        parts_count: multipart_parts(output.e_tag.as_deref()),
+        e_tag: output.e_tag,
+        part_size: None,
+        storage_class: output
+            .storage_class
+            .unwrap_or_else(|| "STANDARD".to_owned()),
+        last_modified: output.last_modified,
+        version_id: output.version_id,
+        server_side_encryption: output.server_side_encryption,
+        sse_customer_algorithm: output.sse_customer_algorithm,
+    };
+    if attributes.parts_count.is_none() {
+        return Ok(attributes);
+    }
+    if get_object_attributes {
+        let mut request = head(None);
+        request.version_id = attributes.version_id.clone();
+        match client.first_part(request).await {
+            Ok(Some((parts_count, part_size))) => {
+                attributes.parts_count = Some(parts_count);
+                attributes.part_size = Some(part_size);
+                return Ok(attributes);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                let e = S3ExtError::from(e);
+                if !e.is_not_implemented() {
+                    return Err(e.with_object(&bucket, &key));
+                }
+            }
+        }
+    }
+    // the first part has to be requested to learn the part layout
+    let mut request = head(Some(1));
+    request.version_id = attributes.version_id.clone();
+    let part = client
+        .head_object(request)
+        .await
+        .map_err(|e| S3ExtError::from(e).with_object(&bucket, &key))?;
+    attributes.part_size = part.content_length;
+    attributes.parts_count = part.parts_count.or(attributes.parts_count);
+    Ok(attributes)
+}
+
+pub(crate) async fn head_many<C>(
+    client: &C,
+    bucket: String,
+    keys: Vec<String>,
+    concurrency: usize,
+) -> S3ExtResult<Vec<(String, Option<ObjectAttributes>)>>
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    let bucket = &bucket;
+    stream::iter(keys)
+        .map(|key| async move {
+            match client.object_attributes(bucket.clone(), key.clone()).await {
+                Ok(attributes) => Ok((key, Some(attributes))),
+                Err(e) if e.is_not_found() => Ok((key, None)),
+                Err(e) => Err(e),
+            }
+        })
+        // `buffered` doesn't make progress with a limit of 0
+        .buffered(concurrency.max(1))
+        .try_collect()
+        .await
+}
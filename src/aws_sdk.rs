@@ -0,0 +1,2189 @@
+//! `S3Ext` for the client of the official AWS SDK
+//!
+//! Requests and responses are translated from and to their Rusoto
+//! counterparts so the same high-level API, including its error type, is
+//! available for `aws_sdk_s3::Client`. Errors S3 reports are mapped to
+//! `RusotoError::Unknown` carrying the raw response, except for the missing
+//! bucket, key and upload errors Rusoto models.
+
+use crate::{
+    backend::{Backend, BackendResult},
+    body::ObjectBody,
+    compose::ComposePart,
+    diff::DiffStream,
+    error::S3ExtResult,
+    framed::FramedObject,
+    iter::{GetObjectStream, ObjectStream, SortedObjectStream},
+    lines::LineStream,
+    metadata::ObjectMetadata,
+    part_size::PartSize,
+    watch::WatchStream,
+    S3Ext,
+};
+use async_trait::async_trait;
+use aws_sdk_s3::{
+    config::http::HttpResponse,
+    error::{DisplayErrorContext, ProvideErrorMetadata, SdkError},
+    primitives::{ByteStream, DateTime, DateTimeFormat},
+    types::{
+        AccessControlPolicy, BucketLoggingStatus as SdkBucketLoggingStatus, BucketLogsPermission,
+        CompletedMultipartUpload, CompletedPart, Condition as SdkCondition,
+        DeleteMarkerReplication as SdkDeleteMarkerReplication, DeleteMarkerReplicationStatus,
+        Destination as SdkDestination, EncodingType, ErrorDocument as SdkErrorDocument,
+        ExistingObjectReplication as SdkExistingObjectReplication, ExistingObjectReplicationStatus,
+        GlacierJobParameters, Grant as SdkGrant, Grantee as SdkGrantee,
+        IndexDocument as SdkIndexDocument, IntelligentTieringAccessTier,
+        IntelligentTieringAndOperator as SdkIntelligentTieringAndOperator,
+        IntelligentTieringConfiguration as SdkIntelligentTieringConfiguration,
+        IntelligentTieringFilter as SdkIntelligentTieringFilter, IntelligentTieringStatus,
+        LoggingEnabled as SdkLoggingEnabled, MetadataDirective,
+        ObjectAttributes as SdkObjectAttributes, ObjectCannedAcl, ObjectLockLegalHoldStatus,
+        ObjectLockMode, Owner as SdkOwner, Permission, Protocol, Redirect as SdkRedirect,
+        RedirectAllRequestsTo as SdkRedirectAllRequestsTo,
+        ReplicationConfiguration as SdkReplicationConfiguration,
+        ReplicationRule as SdkReplicationRule,
+        ReplicationRuleAndOperator as SdkReplicationRuleAndOperator,
+        ReplicationRuleFilter as SdkReplicationRuleFilter, ReplicationRuleStatus, RequestPayer,
+        RestoreRequest, RoutingRule as SdkRoutingRule, ServerSideEncryption,
+        ServerSideEncryptionByDefault as SdkServerSideEncryptionByDefault,
+        ServerSideEncryptionConfiguration as SdkServerSideEncryptionConfiguration,
+        ServerSideEncryptionRule as SdkServerSideEncryptionRule, StorageClass, Tag as SdkTag,
+        Tagging as SdkTagging, TaggingDirective, TargetGrant as SdkTargetGrant, Tier,
+        Tiering as SdkTiering, Type, WebsiteConfiguration as SdkWebsiteConfiguration,
+    },
+    Client,
+};
+use futures::stream::{self, TryStreamExt};
+use hyper::{body::Bytes, header::HeaderName, StatusCode};
+use rusoto_core::{request::BufferedHttpResponse, HttpDispatchError, RusotoError};
+use rusoto_s3::{
+    AbortMultipartUploadError, AbortMultipartUploadOutput, AbortMultipartUploadRequest,
+    CommonPrefix, CompleteMultipartUploadError, CompleteMultipartUploadOutput,
+    CompleteMultipartUploadRequest, Condition, CopyObjectError, CopyObjectOutput,
+    CopyObjectRequest, CopyObjectResult, CopyPartResult, CreateMultipartUploadError,
+    CreateMultipartUploadOutput, CreateMultipartUploadRequest,
+    DeleteBucketIntelligentTieringConfigurationError,
+    DeleteBucketIntelligentTieringConfigurationRequest, DeleteBucketPolicyError,
+    DeleteBucketPolicyRequest, DeleteBucketReplicationError, DeleteBucketReplicationRequest,
+    DeleteBucketTaggingError, DeleteBucketTaggingRequest, DeleteBucketWebsiteError,
+    DeleteBucketWebsiteRequest, DeleteMarkerReplication, DeleteObjectError, DeleteObjectOutput,
+    DeleteObjectRequest, DeleteObjectTaggingError, DeleteObjectTaggingOutput,
+    DeleteObjectTaggingRequest, Destination, ErrorDocument, ExistingObjectReplication,
+    GetBucketEncryptionError, GetBucketEncryptionOutput, GetBucketEncryptionRequest,
+    GetBucketIntelligentTieringConfigurationError, GetBucketIntelligentTieringConfigurationOutput,
+    GetBucketIntelligentTieringConfigurationRequest, GetBucketLoggingError, GetBucketLoggingOutput,
+    GetBucketLoggingRequest, GetBucketPolicyError, GetBucketPolicyOutput, GetBucketPolicyRequest,
+    GetBucketReplicationError, GetBucketReplicationOutput, GetBucketReplicationRequest,
+    GetBucketTaggingError, GetBucketTaggingOutput, GetBucketTaggingRequest, GetBucketWebsiteError,
+    GetBucketWebsiteOutput, GetBucketWebsiteRequest, GetObjectAclError, GetObjectAclOutput,
+    GetObjectAclRequest, GetObjectError, GetObjectOutput, GetObjectRequest, GetObjectTaggingError,
+    GetObjectTaggingOutput, GetObjectTaggingRequest, Grant, Grantee, HeadObjectError,
+    HeadObjectOutput, HeadObjectRequest, IndexDocument, Initiator, IntelligentTieringAndOperator,
+    IntelligentTieringConfiguration, IntelligentTieringFilter,
+    ListBucketIntelligentTieringConfigurationsError,
+    ListBucketIntelligentTieringConfigurationsOutput,
+    ListBucketIntelligentTieringConfigurationsRequest, ListMultipartUploadsError,
+    ListMultipartUploadsOutput, ListMultipartUploadsRequest, ListObjectsV2Error,
+    ListObjectsV2Output, ListObjectsV2Request, ListPartsError, ListPartsOutput, ListPartsRequest,
+    LoggingEnabled, MultipartUpload, Object, Owner, Part, PutBucketEncryptionError,
+    PutBucketEncryptionRequest, PutBucketIntelligentTieringConfigurationError,
+    PutBucketIntelligentTieringConfigurationRequest, PutBucketLoggingError,
+    PutBucketLoggingRequest, PutBucketPolicyError, PutBucketPolicyRequest,
+    PutBucketReplicationError, PutBucketReplicationRequest, PutBucketTaggingError,
+    PutBucketTaggingRequest, PutBucketWebsiteError, PutBucketWebsiteRequest, PutObjectAclError,
+    PutObjectAclOutput, PutObjectAclRequest, PutObjectError, PutObjectOutput, PutObjectRequest,
+    PutObjectTaggingError, PutObjectTaggingOutput, PutObjectTaggingRequest, Redirect,
+    RedirectAllRequestsTo, ReplicationConfiguration, ReplicationRule, ReplicationRuleAndOperator,
+    ReplicationRuleFilter, RestoreObjectError, RestoreObjectOutput, RestoreObjectRequest,
+    RoutingRule, ServerSideEncryptionByDefault, ServerSideEncryptionConfiguration,
+    ServerSideEncryptionRule, StreamingBody, Tag, TargetGrant, Tiering, UploadPartCopyError,
+    UploadPartCopyOutput, UploadPartCopyRequest, UploadPartError, UploadPartOutput,
+    UploadPartRequest,
+};
+use std::{
+    convert::{AsRef, TryFrom},
+    future::Future,
+    io,
+    path::Path,
+    time::Duration,
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::codec::Decoder;
+
+#[async_trait]
+impl S3Ext for Client {
+    async fn send_request<T, F, Fut>(
+        &self,
+        _operation: &'static str,
+        bucket: String,
+        key: String,
+        request: F,
+    ) -> S3ExtResult<T>
+    where
+        T: Send,
+        F: Fn(Backend, String, String) -> Fut + Send + Sync,
+        Fut: Future<Output = S3ExtResult<T>> + Send,
+    {
+        request(Backend::from(self), bucket, key).await
+    }
+
+    async fn download_to_file<F>(
+        &self,
+        source: GetObjectRequest,
+        target: F,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        Backend::from(self).download_to_file(source, target).await
+    }
+
+    async fn upload_from_file<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        Backend::from(self).upload_from_file(source, target).await
+    }
+
+    async fn upload_from_file_multipart_with<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        Backend::from(self)
+            .upload_from_file_multipart_with(source, target, part_size)
+            .await
+    }
+
+    async fn download<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        Backend::from(self).download(source, target).await
+    }
+
+    async fn download_decompressed<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        Backend::from(self)
+            .download_decompressed(source, target)
+            .await
+    }
+
+    async fn get_framed<D>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        decoder: D,
+    ) -> S3ExtResult<FramedObject<D>>
+    where
+        D: Decoder + Send,
+    {
+        Backend::from(self).get_framed(bucket, key, decoder).await
+    }
+
+    async fn get_body_with(&self, source: GetObjectRequest) -> S3ExtResult<ObjectBody> {
+        Backend::from(self).get_body_with(source).await
+    }
+
+    async fn upload<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        Backend::from(self).upload(source, target).await
+    }
+
+    async fn upload_multipart_with<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        Backend::from(self)
+            .upload_multipart_with(source, target, part_size)
+            .await
+    }
+
+    async fn upload_multipart_resumable<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        Backend::from(self)
+            .upload_multipart_resumable(source, target, part_size)
+            .await
+    }
+
+    #[inline]
+    fn stream_objects(&self, bucket: impl Into<String>) -> ObjectStream {
+        Backend::from(self).stream_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> ObjectStream {
+        Backend::from(self).stream_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_objects_sorted_by_mtime(&self, bucket: impl Into<String>) -> SortedObjectStream {
+        Backend::from(self).stream_objects_sorted_by_mtime(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix_sorted_by_mtime(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> SortedObjectStream {
+        Backend::from(self).stream_objects_with_prefix_sorted_by_mtime(bucket, prefix)
+    }
+
+    #[inline]
+    fn diff(
+        &self,
+        source_bucket: impl Into<String>,
+        source_prefix: impl Into<String>,
+        dest_bucket: impl Into<String>,
+        dest_prefix: impl Into<String>,
+    ) -> DiffStream {
+        Backend::from(self).diff(source_bucket, source_prefix, dest_bucket, dest_prefix)
+    }
+
+    #[inline]
+    fn watch_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        period: Duration,
+    ) -> WatchStream {
+        Backend::from(self).watch_prefix(bucket, prefix, period)
+    }
+
+    #[inline]
+    fn stream_get_objects(&self, bucket: impl Into<String>) -> GetObjectStream {
+        Backend::from(self).stream_get_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_get_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> GetObjectStream {
+        Backend::from(self).stream_get_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_lines(&self, bucket: impl Into<String>, key: impl Into<String>) -> LineStream {
+        Backend::from(self).stream_lines(bucket, key)
+    }
+
+    async fn delete(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        Backend::from(self).delete(bucket, key).await
+    }
+
+    async fn compose_object(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        parts: Vec<ComposePart>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput> {
+        Backend::from(self).compose_object(bucket, key, parts).await
+    }
+
+    async fn object_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectMetadata> {
+        Backend::from(self).object_metadata(bucket, key).await
+    }
+
+    async fn replace_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        metadata: ObjectMetadata,
+    ) -> S3ExtResult<()> {
+        Backend::from(self)
+            .replace_metadata(bucket, key, metadata)
+            .await
+    }
+
+    async fn copy(
+        &self,
+        source_bucket: impl Into<String> + Send,
+        source_key: impl Into<String> + Send,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        Backend::from(self)
+            .copy(source_bucket, source_key, bucket, key)
+            .await
+    }
+}
+
+// Translate an SDK error, `service` picks out the errors Rusoto models
+fn rusoto_error<E, T>(
+    error: SdkError<E, HttpResponse>,
+    service: impl FnOnce(&E) -> Option<T>,
+) -> Box<RusotoError<T>>
+where
+    E: ProvideErrorMetadata + std::error::Error + Send + Sync + 'static,
+{
+    let message = DisplayErrorContext(&error).to_string();
+    let error = match error {
+        SdkError::ServiceError(context) => {
+            if let Some(error) = service(context.err()) {
+                return Box::new(RusotoError::Service(error));
+            }
+            let raw = context.raw();
+            RusotoError::Unknown(BufferedHttpResponse {
+                status: StatusCode::from_u16(raw.status().as_u16())
+                    .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                body: Bytes::copy_from_slice(raw.body().bytes().unwrap_or_default()),
+                headers: raw
+                    .headers()
+                    .into_iter()
+                    .filter_map(|(name, value)| {
+                        Some((
+                            HeaderName::from_bytes(name.as_bytes()).ok()?,
+                            value.to_owned(),
+                        ))
+                    })
+                    .collect(),
+            })
+        }
+        SdkError::ConstructionFailure(_) => RusotoError::Validation(message),
+        SdkError::ResponseError(_) => RusotoError::ParseError(message),
+        _ => RusotoError::HttpDispatch(HttpDispatchError::new(message)),
+    };
+    Box::new(error)
+}
+
+// Requests the SDK rejects before sending them
+fn invalid<T>(message: impl ToString) -> Box<RusotoError<T>> {
+    Box::new(RusotoError::Validation(message.to_string()))
+}
+
+fn message<E: ProvideErrorMetadata>(error: &E) -> String {
+    error.message().unwrap_or_default().to_owned()
+}
+
+// Rusoto passes dates as strings, in the format of the header or XML element
+fn parse_date<E>(value: Option<String>) -> BackendResult<Option<DateTime>, E> {
+    value
+        .map(|value| {
+            DateTime::from_str(&value, DateTimeFormat::HttpDate)
+                .or_else(|_| DateTime::from_str(&value, DateTimeFormat::DateTime))
+                .map_err(|_| invalid(format!("invalid date {:?}", value)))
+        })
+        .transpose()
+}
+
+fn http_date(value: Option<DateTime>) -> Option<String> {
+    value.and_then(|value| value.fmt(DateTimeFormat::HttpDate).ok())
+}
+
+fn iso_date(value: Option<DateTime>) -> Option<String> {
+    value.and_then(|value| value.fmt(DateTimeFormat::DateTime).ok())
+}
+
+fn as_string<T: AsRef<str>>(value: Option<T>) -> Option<String> {
+    value.map(|value| value.as_ref().to_owned())
+}
+
+fn as_i64(value: Option<i32>) -> Option<i64> {
+    value.map(i64::from)
+}
+
+fn as_i32<E>(value: Option<i64>) -> BackendResult<Option<i32>, E> {
+    value
+        .map(|value| i32::try_from(value).map_err(|_| invalid(format!("{} out of range", value))))
+        .transpose()
+}
+
+// Bodies are buffered in memory by the callers already
+async fn byte_stream<E>(body: Option<StreamingBody>) -> BackendResult<ByteStream, E> {
+    let body = match body {
+        Some(body) => body
+            .try_fold(Vec::new(), |mut buf, chunk| async move {
+                buf.extend_from_slice(&chunk);
+                Ok(buf)
+            })
+            .await
+            .map_err(|e| {
+                Box::new(RusotoError::HttpDispatch(HttpDispatchError::new(
+                    e.to_string(),
+                )))
+            })?,
+        None => Vec::new(),
+    };
+    Ok(ByteStream::from(body))
+}
+
+fn streaming_body(body: ByteStream) -> StreamingBody {
+    StreamingBody::new(stream::unfold(body, |mut body| async move {
+        let chunk = body
+            .try_next()
+            .await
+            .map_err(io::Error::other)
+            .transpose()?;
+        Some((chunk, body))
+    }))
+}
+
+pub(crate) async fn get_object(
+    client: &Client,
+    request: GetObjectRequest,
+) -> BackendResult<GetObjectOutput, GetObjectError> {
+    let resp = client
+        .get_object()
+        .bucket(request.bucket)
+        .key(request.key)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_if_match(request.if_match)
+        .set_if_modified_since(parse_date(request.if_modified_since)?)
+        .set_if_none_match(request.if_none_match)
+        .set_if_unmodified_since(parse_date(request.if_unmodified_since)?)
+        .set_part_number(as_i32(request.part_number)?)
+        .set_range(request.range)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .set_response_cache_control(request.response_cache_control)
+        .set_response_content_disposition(request.response_content_disposition)
+        .set_response_content_encoding(request.response_content_encoding)
+        .set_response_content_language(request.response_content_language)
+        .set_response_content_type(request.response_content_type)
+        .set_response_expires(parse_date(request.response_expires)?)
+        .set_sse_customer_algorithm(request.sse_customer_algorithm)
+        .set_sse_customer_key(request.sse_customer_key)
+        .set_sse_customer_key_md5(request.sse_customer_key_md5)
+        .set_version_id(request.version_id)
+        .send()
+        .await
+        .map_err(|e| {
+            rusoto_error(e, |e| {
+                e.is_no_such_key()
+                    .then(|| GetObjectError::NoSuchKey(message(e)))
+            })
+        })?;
+    Ok(GetObjectOutput {
+        accept_ranges: resp.accept_ranges,
+        body: Some(streaming_body(resp.body)),
+        bucket_key_enabled: resp.bucket_key_enabled,
+        cache_control: resp.cache_control,
+        content_disposition: resp.content_disposition,
+        content_encoding: resp.content_encoding,
+        content_language: resp.content_language,
+        content_length: resp.content_length,
+        content_range: resp.content_range,
+        content_type: resp.content_type,
+        delete_marker: resp.delete_marker,
+        e_tag: resp.e_tag,
+        expiration: resp.expiration,
+        expires: resp.expires_string,
+        last_modified: http_date(resp.last_modified),
+        metadata: resp.metadata,
+        missing_meta: as_i64(resp.missing_meta),
+        object_lock_legal_hold_status: as_string(resp.object_lock_legal_hold_status),
+        object_lock_mode: as_string(resp.object_lock_mode),
+        object_lock_retain_until_date: iso_date(resp.object_lock_retain_until_date),
+        parts_count: as_i64(resp.parts_count),
+        replication_status: as_string(resp.replication_status),
+        request_charged: as_string(resp.request_charged),
+        restore: resp.restore,
+        sse_customer_algorithm: resp.sse_customer_algorithm,
+        sse_customer_key_md5: resp.sse_customer_key_md5,
+        ssekms_key_id: resp.ssekms_key_id,
+        server_side_encryption: as_string(resp.server_side_encryption),
+        storage_class: as_string(resp.storage_class),
+        tag_count: as_i64(resp.tag_count),
+        version_id: resp.version_id,
+        website_redirect_location: resp.website_redirect_location,
+    })
+}
+
+pub(crate) async fn put_object(
+    client: &Client,
+    request: PutObjectRequest,
+) -> BackendResult<PutObjectOutput, PutObjectError> {
+    let resp = client
+        .put_object()
+        .bucket(request.bucket)
+        .key(request.key)
+        .body(byte_stream(request.body).await?)
+        .set_acl(request.acl.as_deref().map(ObjectCannedAcl::from))
+        .set_bucket_key_enabled(request.bucket_key_enabled)
+        .set_cache_control(request.cache_control)
+        .set_content_disposition(request.content_disposition)
+        .set_content_encoding(request.content_encoding)
+        .set_content_language(request.content_language)
+        .set_content_length(request.content_length)
+        .set_content_md5(request.content_md5)
+        .set_content_type(request.content_type)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_expires(parse_date(request.expires)?)
+        .set_grant_full_control(request.grant_full_control)
+        .set_grant_read(request.grant_read)
+        .set_grant_read_acp(request.grant_read_acp)
+        .set_grant_write_acp(request.grant_write_acp)
+        .set_metadata(request.metadata)
+        .set_object_lock_legal_hold_status(
+            request
+                .object_lock_legal_hold_status
+                .as_deref()
+                .map(ObjectLockLegalHoldStatus::from),
+        )
+        .set_object_lock_mode(
+            request
+                .object_lock_mode
+                .as_deref()
+                .map(ObjectLockMode::from),
+        )
+        .set_object_lock_retain_until_date(parse_date(request.object_lock_retain_until_date)?)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .set_sse_customer_algorithm(request.sse_customer_algorithm)
+        .set_sse_customer_key(request.sse_customer_key)
+        .set_sse_customer_key_md5(request.sse_customer_key_md5)
+        .set_ssekms_encryption_context(request.ssekms_encryption_context)
+        .set_ssekms_key_id(request.ssekms_key_id)
+        .set_server_side_encryption(
+            request
+                .server_side_encryption
+                .as_deref()
+                .map(ServerSideEncryption::from),
+        )
+        .set_storage_class(request.storage_class.as_deref().map(StorageClass::from))
+        .set_tagging(request.tagging)
+        .set_website_redirect_location(request.website_redirect_location)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(PutObjectOutput {
+        bucket_key_enabled: resp.bucket_key_enabled,
+        e_tag: resp.e_tag,
+        expiration: resp.expiration,
+        request_charged: as_string(resp.request_charged),
+        sse_customer_algorithm: resp.sse_customer_algorithm,
+        sse_customer_key_md5: resp.sse_customer_key_md5,
+        ssekms_encryption_context: resp.ssekms_encryption_context,
+        ssekms_key_id: resp.ssekms_key_id,
+        server_side_encryption: as_string(resp.server_side_encryption),
+        version_id: resp.version_id,
+    })
+}
+
+pub(crate) async fn list_objects_v2(
+    client: &Client,
+    request: ListObjectsV2Request,
+) -> BackendResult<ListObjectsV2Output, ListObjectsV2Error> {
+    let resp = client
+        .list_objects_v2()
+        .bucket(request.bucket)
+        .set_continuation_token(request.continuation_token)
+        .set_delimiter(request.delimiter)
+        .set_encoding_type(request.encoding_type.as_deref().map(EncodingType::from))
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_fetch_owner(request.fetch_owner)
+        .set_max_keys(as_i32(request.max_keys)?)
+        .set_prefix(request.prefix)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .set_start_after(request.start_after)
+        .send()
+        .await
+        .map_err(|e| {
+            rusoto_error(e, |e| {
+                e.is_no_such_bucket()
+                    .then(|| ListObjectsV2Error::NoSuchBucket(message(e)))
+            })
+        })?;
+    Ok(ListObjectsV2Output {
+        common_prefixes: resp.common_prefixes.map(|prefixes| {
+            prefixes
+                .into_iter()
+                .map(|prefix| CommonPrefix {
+                    prefix: prefix.prefix,
+                })
+                .collect()
+        }),
+        contents: resp.contents.map(|objects| {
+            objects
+                .into_iter()
+                .map(|object| Object {
+                    e_tag: object.e_tag,
+                    key: object.key,
+                    last_modified: iso_date(object.last_modified),
+                    owner: object.owner.map(|owner| Owner {
+                        display_name: owner.display_name,
+                        id: owner.id,
+                    }),
+                    size: object.size,
+                    storage_class: as_string(object.storage_class),
+                })
+                .collect()
+        }),
+        continuation_token: resp.continuation_token,
+        delimiter: resp.delimiter,
+        encoding_type: as_string(resp.encoding_type),
+        is_truncated: resp.is_truncated,
+        key_count: as_i64(resp.key_count),
+        max_keys: as_i64(resp.max_keys),
+        name: resp.name,
+        next_continuation_token: resp.next_continuation_token,
+        prefix: resp.prefix,
+        start_after: resp.start_after,
+    })
+}
+
+pub(crate) async fn create_multipart_upload(
+    client: &Client,
+    request: CreateMultipartUploadRequest,
+) -> BackendResult<CreateMultipartUploadOutput, CreateMultipartUploadError> {
+    let resp = client
+        .create_multipart_upload()
+        .bucket(request.bucket)
+        .key(request.key)
+        .set_acl(request.acl.as_deref().map(ObjectCannedAcl::from))
+        .set_bucket_key_enabled(request.bucket_key_enabled)
+        .set_cache_control(request.cache_control)
+        .set_content_disposition(request.content_disposition)
+        .set_content_encoding(request.content_encoding)
+        .set_content_language(request.content_language)
+        .set_content_type(request.content_type)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_expires(parse_date(request.expires)?)
+        .set_grant_full_control(request.grant_full_control)
+        .set_grant_read(request.grant_read)
+        .set_grant_read_acp(request.grant_read_acp)
+        .set_grant_write_acp(request.grant_write_acp)
+        .set_metadata(request.metadata)
+        .set_object_lock_legal_hold_status(
+            request
+                .object_lock_legal_hold_status
+                .as_deref()
+                .map(ObjectLockLegalHoldStatus::from),
+        )
+        .set_object_lock_mode(
+            request
+                .object_lock_mode
+                .as_deref()
+                .map(ObjectLockMode::from),
+        )
+        .set_object_lock_retain_until_date(parse_date(request.object_lock_retain_until_date)?)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .set_sse_customer_algorithm(request.sse_customer_algorithm)
+        .set_sse_customer_key(request.sse_customer_key)
+        .set_sse_customer_key_md5(request.sse_customer_key_md5)
+        .set_ssekms_encryption_context(request.ssekms_encryption_context)
+        .set_ssekms_key_id(request.ssekms_key_id)
+        .set_server_side_encryption(
+            request
+                .server_side_encryption
+                .as_deref()
+                .map(ServerSideEncryption::from),
+        )
+        .set_storage_class(request.storage_class.as_deref().map(StorageClass::from))
+        .set_tagging(request.tagging)
+        .set_website_redirect_location(request.website_redirect_location)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(CreateMultipartUploadOutput {
+        abort_date: http_date(resp.abort_date),
+        abort_rule_id: resp.abort_rule_id,
+        bucket: resp.bucket,
+        bucket_key_enabled: resp.bucket_key_enabled,
+        key: resp.key,
+        request_charged: as_string(resp.request_charged),
+        sse_customer_algorithm: resp.sse_customer_algorithm,
+        sse_customer_key_md5: resp.sse_customer_key_md5,
+        ssekms_encryption_context: resp.ssekms_encryption_context,
+        ssekms_key_id: resp.ssekms_key_id,
+        server_side_encryption: as_string(resp.server_side_encryption),
+        upload_id: resp.upload_id,
+    })
+}
+
+pub(crate) async fn upload_part(
+    client: &Client,
+    request: UploadPartRequest,
+) -> BackendResult<UploadPartOutput, UploadPartError> {
+    let resp = client
+        .upload_part()
+        .bucket(request.bucket)
+        .key(request.key)
+        .upload_id(request.upload_id)
+        .set_part_number(as_i32(Some(request.part_number))?)
+        .body(byte_stream(request.body).await?)
+        .set_content_length(request.content_length)
+        .set_content_md5(request.content_md5)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .set_sse_customer_algorithm(request.sse_customer_algorithm)
+        .set_sse_customer_key(request.sse_customer_key)
+        .set_sse_customer_key_md5(request.sse_customer_key_md5)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(UploadPartOutput {
+        bucket_key_enabled: resp.bucket_key_enabled,
+        e_tag: resp.e_tag,
+        request_charged: as_string(resp.request_charged),
+        sse_customer_algorithm: resp.sse_customer_algorithm,
+        sse_customer_key_md5: resp.sse_customer_key_md5,
+        ssekms_key_id: resp.ssekms_key_id,
+        server_side_encryption: as_string(resp.server_side_encryption),
+    })
+}
+
+pub(crate) async fn upload_part_copy(
+    client: &Client,
+    request: UploadPartCopyRequest,
+) -> BackendResult<UploadPartCopyOutput, UploadPartCopyError> {
+    let resp = client
+        .upload_part_copy()
+        .bucket(request.bucket)
+        .key(request.key)
+        .upload_id(request.upload_id)
+        .set_part_number(as_i32(Some(request.part_number))?)
+        .copy_source(request.copy_source)
+        .set_copy_source_if_match(request.copy_source_if_match)
+        .set_copy_source_if_modified_since(parse_date(request.copy_source_if_modified_since)?)
+        .set_copy_source_if_none_match(request.copy_source_if_none_match)
+        .set_copy_source_if_unmodified_since(parse_date(request.copy_source_if_unmodified_since)?)
+        .set_copy_source_range(request.copy_source_range)
+        .set_copy_source_sse_customer_algorithm(request.copy_source_sse_customer_algorithm)
+        .set_copy_source_sse_customer_key(request.copy_source_sse_customer_key)
+        .set_copy_source_sse_customer_key_md5(request.copy_source_sse_customer_key_md5)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_expected_source_bucket_owner(request.expected_source_bucket_owner)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .set_sse_customer_algorithm(request.sse_customer_algorithm)
+        .set_sse_customer_key(request.sse_customer_key)
+        .set_sse_customer_key_md5(request.sse_customer_key_md5)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(UploadPartCopyOutput {
+        bucket_key_enabled: resp.bucket_key_enabled,
+        copy_part_result: resp.copy_part_result.map(|result| CopyPartResult {
+            e_tag: result.e_tag,
+            last_modified: iso_date(result.last_modified),
+        }),
+        copy_source_version_id: resp.copy_source_version_id,
+        request_charged: as_string(resp.request_charged),
+        sse_customer_algorithm: resp.sse_customer_algorithm,
+        sse_customer_key_md5: resp.sse_customer_key_md5,
+        ssekms_key_id: resp.ssekms_key_id,
+        server_side_encryption: as_string(resp.server_side_encryption),
+    })
+}
+
+pub(crate) async fn complete_multipart_upload(
+    client: &Client,
+    request: CompleteMultipartUploadRequest,
+) -> BackendResult<CompleteMultipartUploadOutput, CompleteMultipartUploadError> {
+    let parts = request
+        .multipart_upload
+        .and_then(|upload| upload.parts)
+        .map(|parts| {
+            parts
+                .into_iter()
+                .map(|part| {
+                    Ok(CompletedPart::builder()
+                        .set_e_tag(part.e_tag)
+                        .set_part_number(as_i32(part.part_number)?)
+                        .build())
+                })
+                .collect::<BackendResult<Vec<_>, _>>()
+        })
+        .transpose()?;
+    let resp = client
+        .complete_multipart_upload()
+        .bucket(request.bucket)
+        .key(request.key)
+        .upload_id(request.upload_id)
+        .multipart_upload(CompletedMultipartUpload::builder().set_parts(parts).build())
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(CompleteMultipartUploadOutput {
+        bucket: resp.bucket,
+        bucket_key_enabled: resp.bucket_key_enabled,
+        e_tag: resp.e_tag,
+        expiration: resp.expiration,
+        key: resp.key,
+        location: resp.location,
+        request_charged: as_string(resp.request_charged),
+        ssekms_key_id: resp.ssekms_key_id,
+        server_side_encryption: as_string(resp.server_side_encryption),
+        version_id: resp.version_id,
+    })
+}
+
+pub(crate) async fn abort_multipart_upload(
+    client: &Client,
+    request: AbortMultipartUploadRequest,
+) -> BackendResult<AbortMultipartUploadOutput, AbortMultipartUploadError> {
+    let resp = client
+        .abort_multipart_upload()
+        .bucket(request.bucket)
+        .key(request.key)
+        .upload_id(request.upload_id)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .send()
+        .await
+        .map_err(|e| {
+            rusoto_error(e, |e| {
+                e.is_no_such_upload()
+                    .then(|| AbortMultipartUploadError::NoSuchUpload(message(e)))
+            })
+        })?;
+    Ok(AbortMultipartUploadOutput {
+        request_charged: as_string(resp.request_charged),
+    })
+}
+
+pub(crate) async fn list_multipart_uploads(
+    client: &Client,
+    request: ListMultipartUploadsRequest,
+) -> BackendResult<ListMultipartUploadsOutput, ListMultipartUploadsError> {
+    let resp = client
+        .list_multipart_uploads()
+        .bucket(request.bucket)
+        .set_delimiter(request.delimiter)
+        .set_encoding_type(request.encoding_type.as_deref().map(EncodingType::from))
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_key_marker(request.key_marker)
+        .set_max_uploads(as_i32(request.max_uploads)?)
+        .set_prefix(request.prefix)
+        .set_upload_id_marker(request.upload_id_marker)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(ListMultipartUploadsOutput {
+        bucket: resp.bucket,
+        common_prefixes: resp.common_prefixes.map(|prefixes| {
+            prefixes
+                .into_iter()
+                .map(|prefix| CommonPrefix {
+                    prefix: prefix.prefix,
+                })
+                .collect()
+        }),
+        delimiter: resp.delimiter,
+        encoding_type: as_string(resp.encoding_type),
+        is_truncated: resp.is_truncated,
+        key_marker: resp.key_marker,
+        max_uploads: as_i64(resp.max_uploads),
+        next_key_marker: resp.next_key_marker,
+        next_upload_id_marker: resp.next_upload_id_marker,
+        prefix: resp.prefix,
+        upload_id_marker: resp.upload_id_marker,
+        uploads: resp.uploads.map(|uploads| {
+            uploads
+                .into_iter()
+                .map(|upload| MultipartUpload {
+                    initiated: iso_date(upload.initiated),
+                    initiator: upload.initiator.map(|initiator| Initiator {
+                        display_name: initiator.display_name,
+                        id: initiator.id,
+                    }),
+                    key: upload.key,
+                    owner: upload.owner.map(|owner| Owner {
+                        display_name: owner.display_name,
+                        id: owner.id,
+                    }),
+                    storage_class: as_string(upload.storage_class),
+                    upload_id: upload.upload_id,
+                })
+                .collect()
+        }),
+    })
+}
+
+pub(crate) async fn list_parts(
+    client: &Client,
+    request: ListPartsRequest,
+) -> BackendResult<ListPartsOutput, ListPartsError> {
+    let resp = client
+        .list_parts()
+        .bucket(request.bucket)
+        .key(request.key)
+        .upload_id(request.upload_id)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_max_parts(as_i32(request.max_parts)?)
+        .set_part_number_marker(request.part_number_marker.map(|marker| marker.to_string()))
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(ListPartsOutput {
+        abort_date: http_date(resp.abort_date),
+        abort_rule_id: resp.abort_rule_id,
+        bucket: resp.bucket,
+        initiator: resp.initiator.map(|initiator| Initiator {
+            display_name: initiator.display_name,
+            id: initiator.id,
+        }),
+        is_truncated: resp.is_truncated,
+        key: resp.key,
+        max_parts: as_i64(resp.max_parts),
+        next_part_number_marker: resp
+            .next_part_number_marker
+            .and_then(|marker| marker.parse().ok()),
+        owner: resp.owner.map(|owner| Owner {
+            display_name: owner.display_name,
+            id: owner.id,
+        }),
+        part_number_marker: resp
+            .part_number_marker
+            .and_then(|marker| marker.parse().ok()),
+        parts: resp.parts.map(|parts| {
+            parts
+                .into_iter()
+                .map(|part| Part {
+                    e_tag: part.e_tag,
+                    last_modified: iso_date(part.last_modified),
+                    part_number: as_i64(part.part_number),
+                    size: part.size,
+                })
+                .collect()
+        }),
+        request_charged: as_string(resp.request_charged),
+        storage_class: as_string(resp.storage_class),
+        upload_id: resp.upload_id,
+    })
+}
+
+pub(crate) async fn get_object_tagging(
+    client: &Client,
+    request: GetObjectTaggingRequest,
+) -> BackendResult<GetObjectTaggingOutput, GetObjectTaggingError> {
+    let resp = client
+        .get_object_tagging()
+        .bucket(request.bucket)
+        .key(request.key)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .set_version_id(request.version_id)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(GetObjectTaggingOutput {
+        tag_set: resp
+            .tag_set
+            .into_iter()
+            .map(|tag| Tag {
+                key: tag.key,
+                value: tag.value,
+            })
+            .collect(),
+        version_id: resp.version_id,
+    })
+}
+
+pub(crate) async fn put_object_tagging(
+    client: &Client,
+    request: PutObjectTaggingRequest,
+) -> BackendResult<PutObjectTaggingOutput, PutObjectTaggingError> {
+    let tag_set = request
+        .tagging
+        .tag_set
+        .into_iter()
+        .map(|tag| SdkTag::builder().key(tag.key).value(tag.value).build())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(invalid)?;
+    let tagging = SdkTagging::builder()
+        .set_tag_set(Some(tag_set))
+        .build()
+        .map_err(invalid)?;
+    let resp = client
+        .put_object_tagging()
+        .bucket(request.bucket)
+        .key(request.key)
+        .tagging(tagging)
+        .set_content_md5(request.content_md5)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .set_version_id(request.version_id)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(PutObjectTaggingOutput {
+        version_id: resp.version_id,
+    })
+}
+
+pub(crate) async fn delete_object_tagging(
+    client: &Client,
+    request: DeleteObjectTaggingRequest,
+) -> BackendResult<DeleteObjectTaggingOutput, DeleteObjectTaggingError> {
+    let resp = client
+        .delete_object_tagging()
+        .bucket(request.bucket)
+        .key(request.key)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_version_id(request.version_id)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(DeleteObjectTaggingOutput {
+        version_id: resp.version_id,
+    })
+}
+
+pub(crate) async fn delete_object(
+    client: &Client,
+    request: DeleteObjectRequest,
+) -> BackendResult<DeleteObjectOutput, DeleteObjectError> {
+    let resp = client
+        .delete_object()
+        .bucket(request.bucket)
+        .key(request.key)
+        .set_bypass_governance_retention(request.bypass_governance_retention)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_mfa(request.mfa)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .set_version_id(request.version_id)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(DeleteObjectOutput {
+        delete_marker: resp.delete_marker,
+        request_charged: as_string(resp.request_charged),
+        version_id: resp.version_id,
+    })
+}
+
+fn grantee_to_sdk<E>(grantee: Grantee) -> BackendResult<SdkGrantee, E> {
+    SdkGrantee::builder()
+        .r#type(Type::from(grantee.type_.as_str()))
+        .set_display_name(grantee.display_name)
+        .set_email_address(grantee.email_address)
+        .set_id(grantee.id)
+        .set_uri(grantee.uri)
+        .build()
+        .map_err(invalid)
+}
+
+fn grantee_from_sdk(grantee: SdkGrantee) -> Grantee {
+    Grantee {
+        display_name: grantee.display_name,
+        email_address: grantee.email_address,
+        id: grantee.id,
+        type_: grantee.r#type.as_str().to_owned(),
+        uri: grantee.uri,
+    }
+}
+
+fn grant_to_sdk<E>(grant: Grant) -> BackendResult<SdkGrant, E> {
+    let grantee = grant.grantee.map(grantee_to_sdk::<E>).transpose()?;
+    Ok(SdkGrant::builder()
+        .set_grantee(grantee)
+        .set_permission(grant.permission.as_deref().map(Permission::from))
+        .build())
+}
+
+fn grant_from_sdk(grant: SdkGrant) -> Grant {
+    Grant {
+        grantee: grant.grantee.map(grantee_from_sdk),
+        permission: as_string(grant.permission),
+    }
+}
+
+pub(crate) async fn get_object_acl(
+    client: &Client,
+    request: GetObjectAclRequest,
+) -> BackendResult<GetObjectAclOutput, GetObjectAclError> {
+    let resp = client
+        .get_object_acl()
+        .bucket(request.bucket)
+        .key(request.key)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .set_version_id(request.version_id)
+        .send()
+        .await
+        .map_err(|e| {
+            rusoto_error(e, |e| {
+                e.is_no_such_key()
+                    .then(|| GetObjectAclError::NoSuchKey(message(e)))
+            })
+        })?;
+    Ok(GetObjectAclOutput {
+        grants: resp
+            .grants
+            .map(|grants| grants.into_iter().map(grant_from_sdk).collect()),
+        owner: resp.owner.map(|owner| Owner {
+            display_name: owner.display_name,
+            id: owner.id,
+        }),
+        request_charged: as_string(resp.request_charged),
+    })
+}
+
+pub(crate) async fn put_object_acl(
+    client: &Client,
+    request: PutObjectAclRequest,
+) -> BackendResult<PutObjectAclOutput, PutObjectAclError> {
+    let policy = match request.access_control_policy {
+        Some(policy) => Some(
+            AccessControlPolicy::builder()
+                .set_grants(
+                    policy
+                        .grants
+                        .map(|grants| {
+                            grants
+                                .into_iter()
+                                .map(grant_to_sdk)
+                                .collect::<BackendResult<Vec<_>, PutObjectAclError>>()
+                        })
+                        .transpose()?,
+                )
+                .set_owner(policy.owner.map(|owner| {
+                    SdkOwner::builder()
+                        .set_display_name(owner.display_name)
+                        .set_id(owner.id)
+                        .build()
+                }))
+                .build(),
+        ),
+        None => None,
+    };
+    let resp = client
+        .put_object_acl()
+        .bucket(request.bucket)
+        .key(request.key)
+        .set_access_control_policy(policy)
+        .set_acl(request.acl.as_deref().map(ObjectCannedAcl::from))
+        .set_content_md5(request.content_md5)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_grant_full_control(request.grant_full_control)
+        .set_grant_read(request.grant_read)
+        .set_grant_read_acp(request.grant_read_acp)
+        .set_grant_write(request.grant_write)
+        .set_grant_write_acp(request.grant_write_acp)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .set_version_id(request.version_id)
+        .send()
+        .await
+        .map_err(|e| {
+            rusoto_error(e, |e| {
+                e.is_no_such_key()
+                    .then(|| PutObjectAclError::NoSuchKey(message(e)))
+            })
+        })?;
+    Ok(PutObjectAclOutput {
+        request_charged: as_string(resp.request_charged),
+    })
+}
+
+pub(crate) async fn get_bucket_policy(
+    client: &Client,
+    request: GetBucketPolicyRequest,
+) -> BackendResult<GetBucketPolicyOutput, GetBucketPolicyError> {
+    let resp = client
+        .get_bucket_policy()
+        .bucket(request.bucket)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(GetBucketPolicyOutput {
+        policy: resp.policy,
+    })
+}
+
+pub(crate) async fn put_bucket_policy(
+    client: &Client,
+    request: PutBucketPolicyRequest,
+) -> BackendResult<(), PutBucketPolicyError> {
+    client
+        .put_bucket_policy()
+        .bucket(request.bucket)
+        .policy(request.policy)
+        .set_confirm_remove_self_bucket_access(request.confirm_remove_self_bucket_access)
+        .set_content_md5(request.content_md5)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(())
+}
+
+pub(crate) async fn delete_bucket_policy(
+    client: &Client,
+    request: DeleteBucketPolicyRequest,
+) -> BackendResult<(), DeleteBucketPolicyError> {
+    client
+        .delete_bucket_policy()
+        .bucket(request.bucket)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(())
+}
+
+pub(crate) async fn get_bucket_encryption(
+    client: &Client,
+    request: GetBucketEncryptionRequest,
+) -> BackendResult<GetBucketEncryptionOutput, GetBucketEncryptionError> {
+    let resp = client
+        .get_bucket_encryption()
+        .bucket(request.bucket)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(GetBucketEncryptionOutput {
+        server_side_encryption_configuration: resp.server_side_encryption_configuration.map(
+            |configuration| ServerSideEncryptionConfiguration {
+                rules: configuration
+                    .rules
+                    .into_iter()
+                    .map(|rule| ServerSideEncryptionRule {
+                        apply_server_side_encryption_by_default: rule
+                            .apply_server_side_encryption_by_default
+                            .map(|default| ServerSideEncryptionByDefault {
+                                kms_master_key_id: default.kms_master_key_id,
+                                sse_algorithm: default.sse_algorithm.as_str().to_owned(),
+                            }),
+                        bucket_key_enabled: rule.bucket_key_enabled,
+                    })
+                    .collect(),
+            },
+        ),
+    })
+}
+
+pub(crate) async fn put_bucket_encryption(
+    client: &Client,
+    request: PutBucketEncryptionRequest,
+) -> BackendResult<(), PutBucketEncryptionError> {
+    let rules = request
+        .server_side_encryption_configuration
+        .rules
+        .into_iter()
+        .map(|rule| {
+            let default = rule
+                .apply_server_side_encryption_by_default
+                .map(|default| {
+                    SdkServerSideEncryptionByDefault::builder()
+                        .sse_algorithm(ServerSideEncryption::from(default.sse_algorithm.as_str()))
+                        .set_kms_master_key_id(default.kms_master_key_id)
+                        .build()
+                        .map_err(invalid)
+                })
+                .transpose()?;
+            Ok(SdkServerSideEncryptionRule::builder()
+                .set_apply_server_side_encryption_by_default(default)
+                .set_bucket_key_enabled(rule.bucket_key_enabled)
+                .build())
+        })
+        .collect::<BackendResult<Vec<_>, PutBucketEncryptionError>>()?;
+    let configuration = SdkServerSideEncryptionConfiguration::builder()
+        .set_rules(Some(rules))
+        .build()
+        .map_err(invalid)?;
+    client
+        .put_bucket_encryption()
+        .bucket(request.bucket)
+        .server_side_encryption_configuration(configuration)
+        .set_content_md5(request.content_md5)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(())
+}
+
+fn tag_to_sdk<E>(tag: Tag) -> BackendResult<SdkTag, E> {
+    SdkTag::builder()
+        .key(tag.key)
+        .value(tag.value)
+        .build()
+        .map_err(invalid)
+}
+
+fn tag_from_sdk(tag: SdkTag) -> Tag {
+    Tag {
+        key: tag.key,
+        value: tag.value,
+    }
+}
+
+// Encryption, replication time control and metrics of the destination
+// aren't translated
+fn replication_rule_to_sdk<E>(rule: ReplicationRule) -> BackendResult<SdkReplicationRule, E> {
+    let filter = match rule.filter {
+        Some(filter) => Some(
+            SdkReplicationRuleFilter::builder()
+                .set_prefix(filter.prefix)
+                .set_tag(filter.tag.map(tag_to_sdk).transpose()?)
+                .set_and(
+                    filter
+                        .and
+                        .map(|and| -> BackendResult<_, E> {
+                            let tags = and
+                                .tags
+                                .map(|tags| {
+                                    tags.into_iter()
+                                        .map(tag_to_sdk)
+                                        .collect::<BackendResult<Vec<_>, E>>()
+                                })
+                                .transpose()?;
+                            Ok(SdkReplicationRuleAndOperator::builder()
+                                .set_prefix(and.prefix)
+                                .set_tags(tags)
+                                .build())
+                        })
+                        .transpose()?,
+                )
+                .build(),
+        ),
+        None => None,
+    };
+    let destination = SdkDestination::builder()
+        .bucket(rule.destination.bucket)
+        .set_account(rule.destination.account)
+        .set_storage_class(
+            rule.destination
+                .storage_class
+                .as_deref()
+                .map(StorageClass::from),
+        )
+        .build()
+        .map_err(invalid)?;
+    let existing_object_replication = rule
+        .existing_object_replication
+        .map(|replication| {
+            SdkExistingObjectReplication::builder()
+                .status(ExistingObjectReplicationStatus::from(
+                    replication.status.as_str(),
+                ))
+                .build()
+                .map_err(invalid)
+        })
+        .transpose()?;
+    SdkReplicationRule::builder()
+        .set_id(rule.id)
+        .set_priority(as_i32(rule.priority)?)
+        .set_prefix(rule.prefix)
+        .set_filter(filter)
+        .status(ReplicationRuleStatus::from(rule.status.as_str()))
+        .set_delete_marker_replication(rule.delete_marker_replication.map(|replication| {
+            SdkDeleteMarkerReplication::builder()
+                .set_status(
+                    replication
+                        .status
+                        .as_deref()
+                        .map(DeleteMarkerReplicationStatus::from),
+                )
+                .build()
+        }))
+        .set_existing_object_replication(existing_object_replication)
+        .destination(destination)
+        .build()
+        .map_err(invalid)
+}
+
+fn replication_rule_from_sdk(rule: SdkReplicationRule) -> ReplicationRule {
+    ReplicationRule {
+        id: rule.id,
+        priority: as_i64(rule.priority),
+        prefix: rule.prefix,
+        filter: rule.filter.map(|filter| ReplicationRuleFilter {
+            prefix: filter.prefix,
+            tag: filter.tag.map(tag_from_sdk),
+            and: filter.and.map(|and| ReplicationRuleAndOperator {
+                prefix: and.prefix,
+                tags: and
+                    .tags
+                    .map(|tags| tags.into_iter().map(tag_from_sdk).collect()),
+            }),
+        }),
+        status: rule.status.as_str().to_owned(),
+        delete_marker_replication: rule.delete_marker_replication.map(|replication| {
+            DeleteMarkerReplication {
+                status: as_string(replication.status),
+            }
+        }),
+        existing_object_replication: rule.existing_object_replication.map(|replication| {
+            ExistingObjectReplication {
+                status: replication.status.as_str().to_owned(),
+            }
+        }),
+        destination: rule
+            .destination
+            .map(|destination| Destination {
+                bucket: destination.bucket,
+                account: destination.account,
+                storage_class: as_string(destination.storage_class),
+                ..Default::default()
+            })
+            .unwrap_or_default(),
+        ..Default::default()
+    }
+}
+
+pub(crate) async fn get_bucket_replication(
+    client: &Client,
+    request: GetBucketReplicationRequest,
+) -> BackendResult<GetBucketReplicationOutput, GetBucketReplicationError> {
+    let resp = client
+        .get_bucket_replication()
+        .bucket(request.bucket)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(GetBucketReplicationOutput {
+        replication_configuration: resp.replication_configuration.map(|configuration| {
+            ReplicationConfiguration {
+                role: configuration.role,
+                rules: configuration
+                    .rules
+                    .into_iter()
+                    .map(replication_rule_from_sdk)
+                    .collect(),
+            }
+        }),
+    })
+}
+
+pub(crate) async fn put_bucket_replication(
+    client: &Client,
+    request: PutBucketReplicationRequest,
+) -> BackendResult<(), PutBucketReplicationError> {
+    let configuration = request.replication_configuration;
+    let rules = configuration
+        .rules
+        .into_iter()
+        .map(replication_rule_to_sdk)
+        .collect::<BackendResult<Vec<_>, PutBucketReplicationError>>()?;
+    let configuration = SdkReplicationConfiguration::builder()
+        .role(configuration.role)
+        .set_rules(Some(rules))
+        .build()
+        .map_err(invalid)?;
+    client
+        .put_bucket_replication()
+        .bucket(request.bucket)
+        .replication_configuration(configuration)
+        .set_content_md5(request.content_md5)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_token(request.token)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(())
+}
+
+pub(crate) async fn delete_bucket_replication(
+    client: &Client,
+    request: DeleteBucketReplicationRequest,
+) -> BackendResult<(), DeleteBucketReplicationError> {
+    client
+        .delete_bucket_replication()
+        .bucket(request.bucket)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(())
+}
+
+fn intelligent_tiering_to_sdk<E>(
+    configuration: IntelligentTieringConfiguration,
+) -> BackendResult<SdkIntelligentTieringConfiguration, E> {
+    let filter = match configuration.filter {
+        Some(filter) => Some(
+            SdkIntelligentTieringFilter::builder()
+                .set_prefix(filter.prefix)
+                .set_tag(filter.tag.map(tag_to_sdk).transpose()?)
+                .set_and(
+                    filter
+                        .and
+                        .map(|and| -> BackendResult<_, E> {
+                            let tags = and
+                                .tags
+                                .map(|tags| {
+                                    tags.into_iter()
+                                        .map(tag_to_sdk)
+                                        .collect::<BackendResult<Vec<_>, E>>()
+                                })
+                                .transpose()?;
+                            Ok(SdkIntelligentTieringAndOperator::builder()
+                                .set_prefix(and.prefix)
+                                .set_tags(tags)
+                                .build())
+                        })
+                        .transpose()?,
+                )
+                .build(),
+        ),
+        None => None,
+    };
+    let tierings = configuration
+        .tierings
+        .into_iter()
+        .map(|tiering| {
+            SdkTiering::builder()
+                .set_days(as_i32(Some(tiering.days))?)
+                .access_tier(IntelligentTieringAccessTier::from(
+                    tiering.access_tier.as_str(),
+                ))
+                .build()
+                .map_err(invalid)
+        })
+        .collect::<BackendResult<Vec<_>, E>>()?;
+    SdkIntelligentTieringConfiguration::builder()
+        .id(configuration.id)
+        .set_filter(filter)
+        .status(IntelligentTieringStatus::from(
+            configuration.status.as_str(),
+        ))
+        .set_tierings(Some(tierings))
+        .build()
+        .map_err(invalid)
+}
+
+fn intelligent_tiering_from_sdk(
+    configuration: SdkIntelligentTieringConfiguration,
+) -> IntelligentTieringConfiguration {
+    IntelligentTieringConfiguration {
+        id: configuration.id,
+        filter: configuration.filter.map(|filter| IntelligentTieringFilter {
+            prefix: filter.prefix,
+            tag: filter.tag.map(tag_from_sdk),
+            and: filter.and.map(|and| IntelligentTieringAndOperator {
+                prefix: and.prefix,
+                tags: and
+                    .tags
+                    .map(|tags| tags.into_iter().map(tag_from_sdk).collect()),
+            }),
+        }),
+        status: configuration.status.as_str().to_owned(),
+        tierings: configuration
+            .tierings
+            .into_iter()
+            .map(|tiering| Tiering {
+                access_tier: tiering.access_tier.as_str().to_owned(),
+                days: i64::from(tiering.days),
+            })
+            .collect(),
+    }
+}
+
+pub(crate) async fn get_bucket_intelligent_tiering_configuration(
+    client: &Client,
+    request: GetBucketIntelligentTieringConfigurationRequest,
+) -> BackendResult<
+    GetBucketIntelligentTieringConfigurationOutput,
+    GetBucketIntelligentTieringConfigurationError,
+> {
+    let resp = client
+        .get_bucket_intelligent_tiering_configuration()
+        .bucket(request.bucket)
+        .id(request.id)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(GetBucketIntelligentTieringConfigurationOutput {
+        intelligent_tiering_configuration: resp
+            .intelligent_tiering_configuration
+            .map(intelligent_tiering_from_sdk),
+    })
+}
+
+pub(crate) async fn list_bucket_intelligent_tiering_configurations(
+    client: &Client,
+    request: ListBucketIntelligentTieringConfigurationsRequest,
+) -> BackendResult<
+    ListBucketIntelligentTieringConfigurationsOutput,
+    ListBucketIntelligentTieringConfigurationsError,
+> {
+    let resp = client
+        .list_bucket_intelligent_tiering_configurations()
+        .bucket(request.bucket)
+        .set_continuation_token(request.continuation_token)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(ListBucketIntelligentTieringConfigurationsOutput {
+        continuation_token: resp.continuation_token,
+        intelligent_tiering_configuration_list: resp.intelligent_tiering_configuration_list.map(
+            |configurations| {
+                configurations
+                    .into_iter()
+                    .map(intelligent_tiering_from_sdk)
+                    .collect()
+            },
+        ),
+        is_truncated: resp.is_truncated,
+        next_continuation_token: resp.next_continuation_token,
+    })
+}
+
+pub(crate) async fn put_bucket_intelligent_tiering_configuration(
+    client: &Client,
+    request: PutBucketIntelligentTieringConfigurationRequest,
+) -> BackendResult<(), PutBucketIntelligentTieringConfigurationError> {
+    let configuration = intelligent_tiering_to_sdk(request.intelligent_tiering_configuration)?;
+    client
+        .put_bucket_intelligent_tiering_configuration()
+        .bucket(request.bucket)
+        .id(request.id)
+        .intelligent_tiering_configuration(configuration)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(())
+}
+
+pub(crate) async fn delete_bucket_intelligent_tiering_configuration(
+    client: &Client,
+    request: DeleteBucketIntelligentTieringConfigurationRequest,
+) -> BackendResult<(), DeleteBucketIntelligentTieringConfigurationError> {
+    client
+        .delete_bucket_intelligent_tiering_configuration()
+        .bucket(request.bucket)
+        .id(request.id)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(())
+}
+
+fn routing_rule_to_sdk(rule: RoutingRule) -> SdkRoutingRule {
+    let redirect = rule.redirect;
+    SdkRoutingRule::builder()
+        .set_condition(rule.condition.map(|condition| {
+            SdkCondition::builder()
+                .set_http_error_code_returned_equals(condition.http_error_code_returned_equals)
+                .set_key_prefix_equals(condition.key_prefix_equals)
+                .build()
+        }))
+        .redirect(
+            SdkRedirect::builder()
+                .set_host_name(redirect.host_name)
+                .set_http_redirect_code(redirect.http_redirect_code)
+                .set_protocol(redirect.protocol.as_deref().map(Protocol::from))
+                .set_replace_key_prefix_with(redirect.replace_key_prefix_with)
+                .set_replace_key_with(redirect.replace_key_with)
+                .build(),
+        )
+        .build()
+}
+
+fn routing_rule_from_sdk(rule: SdkRoutingRule) -> RoutingRule {
+    RoutingRule {
+        condition: rule.condition.map(|condition| Condition {
+            http_error_code_returned_equals: condition.http_error_code_returned_equals,
+            key_prefix_equals: condition.key_prefix_equals,
+        }),
+        redirect: rule
+            .redirect
+            .map(|redirect| Redirect {
+                host_name: redirect.host_name,
+                http_redirect_code: redirect.http_redirect_code,
+                protocol: as_string(redirect.protocol),
+                replace_key_prefix_with: redirect.replace_key_prefix_with,
+                replace_key_with: redirect.replace_key_with,
+            })
+            .unwrap_or_default(),
+    }
+}
+
+pub(crate) async fn get_bucket_website(
+    client: &Client,
+    request: GetBucketWebsiteRequest,
+) -> BackendResult<GetBucketWebsiteOutput, GetBucketWebsiteError> {
+    let resp = client
+        .get_bucket_website()
+        .bucket(request.bucket)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(GetBucketWebsiteOutput {
+        error_document: resp
+            .error_document
+            .map(|document| ErrorDocument { key: document.key }),
+        index_document: resp.index_document.map(|document| IndexDocument {
+            suffix: document.suffix,
+        }),
+        redirect_all_requests_to: resp.redirect_all_requests_to.map(|redirect| {
+            RedirectAllRequestsTo {
+                host_name: redirect.host_name,
+                protocol: as_string(redirect.protocol),
+            }
+        }),
+        routing_rules: resp
+            .routing_rules
+            .map(|rules| rules.into_iter().map(routing_rule_from_sdk).collect()),
+    })
+}
+
+pub(crate) async fn put_bucket_website(
+    client: &Client,
+    request: PutBucketWebsiteRequest,
+) -> BackendResult<(), PutBucketWebsiteError> {
+    let configuration = request.website_configuration;
+    let error_document = configuration
+        .error_document
+        .map(|document| SdkErrorDocument::builder().key(document.key).build())
+        .transpose()
+        .map_err(invalid)?;
+    let index_document = configuration
+        .index_document
+        .map(|document| SdkIndexDocument::builder().suffix(document.suffix).build())
+        .transpose()
+        .map_err(invalid)?;
+    let redirect_all_requests_to = configuration
+        .redirect_all_requests_to
+        .map(|redirect| {
+            SdkRedirectAllRequestsTo::builder()
+                .host_name(redirect.host_name)
+                .set_protocol(redirect.protocol.as_deref().map(Protocol::from))
+                .build()
+        })
+        .transpose()
+        .map_err(invalid)?;
+    let configuration = SdkWebsiteConfiguration::builder()
+        .set_error_document(error_document)
+        .set_index_document(index_document)
+        .set_redirect_all_requests_to(redirect_all_requests_to)
+        .set_routing_rules(
+            configuration
+                .routing_rules
+                .map(|rules| rules.into_iter().map(routing_rule_to_sdk).collect()),
+        )
+        .build();
+    client
+        .put_bucket_website()
+        .bucket(request.bucket)
+        .website_configuration(configuration)
+        .set_content_md5(request.content_md5)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(())
+}
+
+pub(crate) async fn delete_bucket_website(
+    client: &Client,
+    request: DeleteBucketWebsiteRequest,
+) -> BackendResult<(), DeleteBucketWebsiteError> {
+    client
+        .delete_bucket_website()
+        .bucket(request.bucket)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(())
+}
+
+pub(crate) async fn head_object(
+    client: &Client,
+    request: HeadObjectRequest,
+) -> BackendResult<HeadObjectOutput, HeadObjectError> {
+    let resp = client
+        .head_object()
+        .bucket(request.bucket)
+        .key(request.key)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_if_match(request.if_match)
+        .set_if_modified_since(parse_date(request.if_modified_since)?)
+        .set_if_none_match(request.if_none_match)
+        .set_if_unmodified_since(parse_date(request.if_unmodified_since)?)
+        .set_part_number(as_i32(request.part_number)?)
+        .set_range(request.range)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .set_sse_customer_algorithm(request.sse_customer_algorithm)
+        .set_sse_customer_key(request.sse_customer_key)
+        .set_sse_customer_key_md5(request.sse_customer_key_md5)
+        .set_version_id(request.version_id)
+        .send()
+        .await
+        .map_err(|e| {
+            rusoto_error(e, |e| {
+                e.is_not_found()
+                    .then(|| HeadObjectError::NoSuchKey(message(e)))
+            })
+        })?;
+    Ok(HeadObjectOutput {
+        accept_ranges: resp.accept_ranges,
+        archive_status: as_string(resp.archive_status),
+        bucket_key_enabled: resp.bucket_key_enabled,
+        cache_control: resp.cache_control,
+        content_disposition: resp.content_disposition,
+        content_encoding: resp.content_encoding,
+        content_language: resp.content_language,
+        content_length: resp.content_length,
+        content_type: resp.content_type,
+        delete_marker: resp.delete_marker,
+        e_tag: resp.e_tag,
+        expiration: resp.expiration,
+        expires: resp.expires_string,
+        last_modified: http_date(resp.last_modified),
+        metadata: resp.metadata,
+        missing_meta: as_i64(resp.missing_meta),
+        object_lock_legal_hold_status: as_string(resp.object_lock_legal_hold_status),
+        object_lock_mode: as_string(resp.object_lock_mode),
+        object_lock_retain_until_date: iso_date(resp.object_lock_retain_until_date),
+        parts_count: as_i64(resp.parts_count),
+        replication_status: as_string(resp.replication_status),
+        request_charged: as_string(resp.request_charged),
+        restore: resp.restore,
+        sse_customer_algorithm: resp.sse_customer_algorithm,
+        sse_customer_key_md5: resp.sse_customer_key_md5,
+        ssekms_key_id: resp.ssekms_key_id,
+        server_side_encryption: as_string(resp.server_side_encryption),
+        storage_class: as_string(resp.storage_class),
+        version_id: resp.version_id,
+        website_redirect_location: resp.website_redirect_location,
+    })
+}
+
+// Number of parts and size of the first part of a multipart object using
+// `GetObjectAttributes`, `None` if the response doesn't list the parts, as for
+// objects uploaded without checksums
+pub(crate) async fn first_part(
+    client: &Client,
+    request: HeadObjectRequest,
+) -> BackendResult<Option<(i64, i64)>, HeadObjectError> {
+    let resp = client
+        .get_object_attributes()
+        .bucket(request.bucket)
+        .key(request.key)
+        .object_attributes(SdkObjectAttributes::ObjectParts)
+        .max_parts(1)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .set_sse_customer_algorithm(request.sse_customer_algorithm)
+        .set_sse_customer_key(request.sse_customer_key)
+        .set_sse_customer_key_md5(request.sse_customer_key_md5)
+        .set_version_id(request.version_id)
+        .send()
+        .await
+        .map_err(|e| {
+            rusoto_error(e, |e| {
+                e.is_no_such_key()
+                    .then(|| HeadObjectError::NoSuchKey(message(e)))
+            })
+        })?;
+    Ok(resp.object_parts.and_then(|parts| {
+        let size = parts.parts().first()?.size()?;
+        Some((i64::from(parts.total_parts_count()?), size))
+    }))
+}
+
+// Only the retrieval of archived objects is translated, select restores
+// aren't
+pub(crate) async fn restore_object(
+    client: &Client,
+    request: RestoreObjectRequest,
+) -> BackendResult<RestoreObjectOutput, RestoreObjectError> {
+    let restore_request = match request.restore_request {
+        Some(restore_request) => {
+            let glacier_job_parameters = restore_request
+                .glacier_job_parameters
+                .map(|parameters| {
+                    GlacierJobParameters::builder()
+                        .tier(Tier::from(parameters.tier.as_str()))
+                        .build()
+                })
+                .transpose()
+                .map_err(invalid)?;
+            Some(
+                RestoreRequest::builder()
+                    .set_days(as_i32(restore_request.days)?)
+                    .set_description(restore_request.description)
+                    .set_glacier_job_parameters(glacier_job_parameters)
+                    .set_tier(restore_request.tier.as_deref().map(Tier::from))
+                    .build(),
+            )
+        }
+        None => None,
+    };
+    let resp = client
+        .restore_object()
+        .bucket(request.bucket)
+        .key(request.key)
+        .set_restore_request(restore_request)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .set_version_id(request.version_id)
+        .send()
+        .await
+        .map_err(|e| {
+            rusoto_error(e, |e| {
+                e.is_object_already_in_active_tier_error()
+                    .then(|| RestoreObjectError::ObjectAlreadyInActiveTierError(message(e)))
+            })
+        })?;
+    Ok(RestoreObjectOutput {
+        request_charged: as_string(resp.request_charged),
+        restore_output_path: resp.restore_output_path,
+    })
+}
+
+pub(crate) async fn copy_object(
+    client: &Client,
+    request: CopyObjectRequest,
+) -> BackendResult<CopyObjectOutput, CopyObjectError> {
+    let resp = client
+        .copy_object()
+        .bucket(request.bucket)
+        .key(request.key)
+        .copy_source(request.copy_source)
+        .set_acl(request.acl.as_deref().map(ObjectCannedAcl::from))
+        .set_bucket_key_enabled(request.bucket_key_enabled)
+        .set_cache_control(request.cache_control)
+        .set_content_disposition(request.content_disposition)
+        .set_content_encoding(request.content_encoding)
+        .set_content_language(request.content_language)
+        .set_content_type(request.content_type)
+        .set_copy_source_if_match(request.copy_source_if_match)
+        .set_copy_source_if_modified_since(parse_date(request.copy_source_if_modified_since)?)
+        .set_copy_source_if_none_match(request.copy_source_if_none_match)
+        .set_copy_source_if_unmodified_since(parse_date(request.copy_source_if_unmodified_since)?)
+        .set_copy_source_sse_customer_algorithm(request.copy_source_sse_customer_algorithm)
+        .set_copy_source_sse_customer_key(request.copy_source_sse_customer_key)
+        .set_copy_source_sse_customer_key_md5(request.copy_source_sse_customer_key_md5)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .set_expected_source_bucket_owner(request.expected_source_bucket_owner)
+        .set_expires(parse_date(request.expires)?)
+        .set_metadata(request.metadata)
+        .set_metadata_directive(
+            request
+                .metadata_directive
+                .as_deref()
+                .map(MetadataDirective::from),
+        )
+        .set_request_payer(request.request_payer.as_deref().map(RequestPayer::from))
+        .set_sse_customer_algorithm(request.sse_customer_algorithm)
+        .set_sse_customer_key(request.sse_customer_key)
+        .set_sse_customer_key_md5(request.sse_customer_key_md5)
+        .set_ssekms_encryption_context(request.ssekms_encryption_context)
+        .set_ssekms_key_id(request.ssekms_key_id)
+        .set_server_side_encryption(
+            request
+                .server_side_encryption
+                .as_deref()
+                .map(ServerSideEncryption::from),
+        )
+        .set_storage_class(request.storage_class.as_deref().map(StorageClass::from))
+        .set_tagging(request.tagging)
+        .set_tagging_directive(
+            request
+                .tagging_directive
+                .as_deref()
+                .map(TaggingDirective::from),
+        )
+        .set_website_redirect_location(request.website_redirect_location)
+        .send()
+        .await
+        .map_err(|e| {
+            rusoto_error(e, |e| {
+                e.is_object_not_in_active_tier_error()
+                    .then(|| CopyObjectError::ObjectNotInActiveTierError(message(e)))
+            })
+        })?;
+    Ok(CopyObjectOutput {
+        bucket_key_enabled: resp.bucket_key_enabled,
+        copy_object_result: resp.copy_object_result.map(|result| CopyObjectResult {
+            e_tag: result.e_tag,
+            last_modified: iso_date(result.last_modified),
+        }),
+        copy_source_version_id: resp.copy_source_version_id,
+        expiration: resp.expiration,
+        request_charged: as_string(resp.request_charged),
+        sse_customer_algorithm: resp.sse_customer_algorithm,
+        sse_customer_key_md5: resp.sse_customer_key_md5,
+        ssekms_encryption_context: resp.ssekms_encryption_context,
+        ssekms_key_id: resp.ssekms_key_id,
+        server_side_encryption: as_string(resp.server_side_encryption),
+        version_id: resp.version_id,
+    })
+}
+
+pub(crate) async fn get_bucket_tagging(
+    client: &Client,
+    request: GetBucketTaggingRequest,
+) -> BackendResult<GetBucketTaggingOutput, GetBucketTaggingError> {
+    let resp = client
+        .get_bucket_tagging()
+        .bucket(request.bucket)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(GetBucketTaggingOutput {
+        tag_set: resp.tag_set.into_iter().map(tag_from_sdk).collect(),
+    })
+}
+
+pub(crate) async fn put_bucket_tagging(
+    client: &Client,
+    request: PutBucketTaggingRequest,
+) -> BackendResult<(), PutBucketTaggingError> {
+    let tag_set = request
+        .tagging
+        .tag_set
+        .into_iter()
+        .map(tag_to_sdk)
+        .collect::<BackendResult<Vec<_>, PutBucketTaggingError>>()?;
+    let tagging = SdkTagging::builder()
+        .set_tag_set(Some(tag_set))
+        .build()
+        .map_err(invalid)?;
+    client
+        .put_bucket_tagging()
+        .bucket(request.bucket)
+        .tagging(tagging)
+        .set_content_md5(request.content_md5)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(())
+}
+
+pub(crate) async fn delete_bucket_tagging(
+    client: &Client,
+    request: DeleteBucketTaggingRequest,
+) -> BackendResult<(), DeleteBucketTaggingError> {
+    client
+        .delete_bucket_tagging()
+        .bucket(request.bucket)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(())
+}
+
+fn logging_enabled_to_sdk<E>(logging: LoggingEnabled) -> BackendResult<SdkLoggingEnabled, E> {
+    let target_grants = logging
+        .target_grants
+        .map(|grants| {
+            grants
+                .into_iter()
+                .map(|grant| -> BackendResult<_, E> {
+                    Ok(SdkTargetGrant::builder()
+                        .set_grantee(grant.grantee.map(grantee_to_sdk::<E>).transpose()?)
+                        .set_permission(grant.permission.as_deref().map(BucketLogsPermission::from))
+                        .build())
+                })
+                .collect::<BackendResult<Vec<_>, E>>()
+        })
+        .transpose()?;
+    SdkLoggingEnabled::builder()
+        .target_bucket(logging.target_bucket)
+        .target_prefix(logging.target_prefix)
+        .set_target_grants(target_grants)
+        .build()
+        .map_err(invalid)
+}
+
+fn logging_enabled_from_sdk(logging: SdkLoggingEnabled) -> LoggingEnabled {
+    LoggingEnabled {
+        target_bucket: logging.target_bucket,
+        target_prefix: logging.target_prefix,
+        target_grants: logging.target_grants.map(|grants| {
+            grants
+                .into_iter()
+                .map(|grant| TargetGrant {
+                    grantee: grant.grantee.map(grantee_from_sdk),
+                    permission: as_string(grant.permission),
+                })
+                .collect()
+        }),
+    }
+}
+
+pub(crate) async fn get_bucket_logging(
+    client: &Client,
+    request: GetBucketLoggingRequest,
+) -> BackendResult<GetBucketLoggingOutput, GetBucketLoggingError> {
+    let resp = client
+        .get_bucket_logging()
+        .bucket(request.bucket)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(GetBucketLoggingOutput {
+        logging_enabled: resp.logging_enabled.map(logging_enabled_from_sdk),
+    })
+}
+
+pub(crate) async fn put_bucket_logging(
+    client: &Client,
+    request: PutBucketLoggingRequest,
+) -> BackendResult<(), PutBucketLoggingError> {
+    let logging_enabled = request
+        .bucket_logging_status
+        .logging_enabled
+        .map(logging_enabled_to_sdk::<PutBucketLoggingError>)
+        .transpose()?;
+    client
+        .put_bucket_logging()
+        .bucket(request.bucket)
+        .bucket_logging_status(
+            SdkBucketLoggingStatus::builder()
+                .set_logging_enabled(logging_enabled)
+                .build(),
+        )
+        .set_content_md5(request.content_md5)
+        .set_expected_bucket_owner(request.expected_bucket_owner)
+        .send()
+        .await
+        .map_err(|e| rusoto_error(e, |_| None))?;
+    Ok(())
+}
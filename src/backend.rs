@@ -0,0 +1,577 @@
+//! Clients the high-level API can be built on
+//!
+//! The implementation of `S3Ext` and the streams are written against the
+//! handful of S3 operations below, in terms of Rusoto's request and response
+//! types. Other clients translate to and from these types.
+
+#[cfg(feature = "aws-sdk")]
+use crate::aws_sdk;
+use rusoto_core::RusotoError;
+use rusoto_s3::{
+    AbortMultipartUploadError, AbortMultipartUploadOutput, AbortMultipartUploadRequest,
+    CompleteMultipartUploadError, CompleteMultipartUploadOutput, CompleteMultipartUploadRequest,
+    CopyObjectError, CopyObjectOutput, CopyObjectRequest, CreateMultipartUploadError,
+    CreateMultipartUploadOutput, CreateMultipartUploadRequest,
+    DeleteBucketIntelligentTieringConfigurationError,
+    DeleteBucketIntelligentTieringConfigurationRequest, DeleteBucketPolicyError,
+    DeleteBucketPolicyRequest, DeleteBucketReplicationError, DeleteBucketReplicationRequest,
+    DeleteBucketTaggingError, DeleteBucketTaggingRequest, DeleteBucketWebsiteError,
+    DeleteBucketWebsiteRequest, DeleteObjectError, DeleteObjectOutput, DeleteObjectRequest,
+    DeleteObjectTaggingError, DeleteObjectTaggingOutput, DeleteObjectTaggingRequest,
+    GetBucketEncryptionError, GetBucketEncryptionOutput, GetBucketEncryptionRequest,
+    GetBucketIntelligentTieringConfigurationError, GetBucketIntelligentTieringConfigurationOutput,
+    GetBucketIntelligentTieringConfigurationRequest, GetBucketLoggingError, GetBucketLoggingOutput,
+    GetBucketLoggingRequest, GetBucketPolicyError, GetBucketPolicyOutput, GetBucketPolicyRequest,
+    GetBucketReplicationError, GetBucketReplicationOutput, GetBucketReplicationRequest,
+    GetBucketTaggingError, GetBucketTaggingOutput, GetBucketTaggingRequest, GetBucketWebsiteError,
+    GetBucketWebsiteOutput, GetBucketWebsiteRequest, GetObjectAclError, GetObjectAclOutput,
+    GetObjectAclRequest, GetObjectError, GetObjectOutput, GetObjectRequest, GetObjectTaggingError,
+    GetObjectTaggingOutput, GetObjectTaggingRequest, HeadObjectError, HeadObjectOutput,
+    HeadObjectRequest, ListBucketIntelligentTieringConfigurationsError,
+    ListBucketIntelligentTieringConfigurationsOutput,
+    ListBucketIntelligentTieringConfigurationsRequest, ListMultipartUploadsError,
+    ListMultipartUploadsOutput, ListMultipartUploadsRequest, ListObjectsV2Error,
+    ListObjectsV2Output, ListObjectsV2Request, ListPartsError, ListPartsOutput, ListPartsRequest,
+    PutBucketEncryptionError, PutBucketEncryptionRequest,
+    PutBucketIntelligentTieringConfigurationError, PutBucketIntelligentTieringConfigurationRequest,
+    PutBucketLoggingError, PutBucketLoggingRequest, PutBucketPolicyError, PutBucketPolicyRequest,
+    PutBucketReplicationError, PutBucketReplicationRequest, PutBucketTaggingError,
+    PutBucketTaggingRequest, PutBucketWebsiteError, PutBucketWebsiteRequest, PutObjectAclError,
+    PutObjectAclOutput, PutObjectAclRequest, PutObjectError, PutObjectOutput, PutObjectRequest,
+    PutObjectTaggingError, PutObjectTaggingOutput, PutObjectTaggingRequest, RestoreObjectError,
+    RestoreObjectOutput, RestoreObjectRequest, S3Client, UploadPartCopyError, UploadPartCopyOutput,
+    UploadPartCopyRequest, UploadPartError, UploadPartOutput, UploadPartRequest, S3,
+};
+
+// Rusoto's errors are boxed, as in `S3ExtError`, they would make every result
+// as large as the buffered HTTP response they contain
+pub(crate) type BackendResult<T, E> = Result<T, Box<RusotoError<E>>>;
+
+/// Client sending the requests
+#[derive(Clone)]
+pub enum Backend {
+    Rusoto(S3Client),
+    #[cfg(feature = "aws-sdk")]
+    AwsSdk(aws_sdk_s3::Client),
+}
+
+impl From<&S3Client> for Backend {
+    fn from(client: &S3Client) -> Self {
+        Backend::Rusoto(client.clone())
+    }
+}
+
+#[cfg(feature = "aws-sdk")]
+impl From<&aws_sdk_s3::Client> for Backend {
+    fn from(client: &aws_sdk_s3::Client) -> Self {
+        Backend::AwsSdk(client.clone())
+    }
+}
+
+impl Backend {
+    pub(crate) async fn get_object(
+        &self,
+        request: GetObjectRequest,
+    ) -> BackendResult<GetObjectOutput, GetObjectError> {
+        match self {
+            Backend::Rusoto(client) => client.get_object(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::get_object(client, request).await,
+        }
+    }
+
+    pub(crate) async fn put_object(
+        &self,
+        request: PutObjectRequest,
+    ) -> BackendResult<PutObjectOutput, PutObjectError> {
+        match self {
+            Backend::Rusoto(client) => client.put_object(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::put_object(client, request).await,
+        }
+    }
+
+    pub(crate) async fn list_objects_v2(
+        &self,
+        request: ListObjectsV2Request,
+    ) -> BackendResult<ListObjectsV2Output, ListObjectsV2Error> {
+        match self {
+            Backend::Rusoto(client) => client.list_objects_v2(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::list_objects_v2(client, request).await,
+        }
+    }
+
+    pub(crate) async fn create_multipart_upload(
+        &self,
+        request: CreateMultipartUploadRequest,
+    ) -> BackendResult<CreateMultipartUploadOutput, CreateMultipartUploadError> {
+        match self {
+            Backend::Rusoto(client) => client
+                .create_multipart_upload(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::create_multipart_upload(client, request).await,
+        }
+    }
+
+    pub(crate) async fn upload_part(
+        &self,
+        request: UploadPartRequest,
+    ) -> BackendResult<UploadPartOutput, UploadPartError> {
+        match self {
+            Backend::Rusoto(client) => client.upload_part(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::upload_part(client, request).await,
+        }
+    }
+
+    pub(crate) async fn complete_multipart_upload(
+        &self,
+        request: CompleteMultipartUploadRequest,
+    ) -> BackendResult<CompleteMultipartUploadOutput, CompleteMultipartUploadError> {
+        match self {
+            Backend::Rusoto(client) => client
+                .complete_multipart_upload(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::complete_multipart_upload(client, request).await,
+        }
+    }
+
+    pub(crate) async fn abort_multipart_upload(
+        &self,
+        request: AbortMultipartUploadRequest,
+    ) -> BackendResult<AbortMultipartUploadOutput, AbortMultipartUploadError> {
+        match self {
+            Backend::Rusoto(client) => client
+                .abort_multipart_upload(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::abort_multipart_upload(client, request).await,
+        }
+    }
+
+    pub(crate) async fn get_object_tagging(
+        &self,
+        request: GetObjectTaggingRequest,
+    ) -> BackendResult<GetObjectTaggingOutput, GetObjectTaggingError> {
+        match self {
+            Backend::Rusoto(client) => client.get_object_tagging(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::get_object_tagging(client, request).await,
+        }
+    }
+
+    pub(crate) async fn put_object_tagging(
+        &self,
+        request: PutObjectTaggingRequest,
+    ) -> BackendResult<PutObjectTaggingOutput, PutObjectTaggingError> {
+        match self {
+            Backend::Rusoto(client) => client.put_object_tagging(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::put_object_tagging(client, request).await,
+        }
+    }
+
+    pub(crate) async fn delete_object_tagging(
+        &self,
+        request: DeleteObjectTaggingRequest,
+    ) -> BackendResult<DeleteObjectTaggingOutput, DeleteObjectTaggingError> {
+        match self {
+            Backend::Rusoto(client) => client
+                .delete_object_tagging(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::delete_object_tagging(client, request).await,
+        }
+    }
+
+    pub(crate) async fn get_object_acl(
+        &self,
+        request: GetObjectAclRequest,
+    ) -> BackendResult<GetObjectAclOutput, GetObjectAclError> {
+        match self {
+            Backend::Rusoto(client) => client.get_object_acl(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::get_object_acl(client, request).await,
+        }
+    }
+
+    pub(crate) async fn put_object_acl(
+        &self,
+        request: PutObjectAclRequest,
+    ) -> BackendResult<PutObjectAclOutput, PutObjectAclError> {
+        match self {
+            Backend::Rusoto(client) => client.put_object_acl(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::put_object_acl(client, request).await,
+        }
+    }
+
+    pub(crate) async fn get_bucket_policy(
+        &self,
+        request: GetBucketPolicyRequest,
+    ) -> BackendResult<GetBucketPolicyOutput, GetBucketPolicyError> {
+        match self {
+            Backend::Rusoto(client) => client.get_bucket_policy(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::get_bucket_policy(client, request).await,
+        }
+    }
+
+    pub(crate) async fn put_bucket_policy(
+        &self,
+        request: PutBucketPolicyRequest,
+    ) -> BackendResult<(), PutBucketPolicyError> {
+        match self {
+            Backend::Rusoto(client) => client.put_bucket_policy(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::put_bucket_policy(client, request).await,
+        }
+    }
+
+    pub(crate) async fn delete_bucket_policy(
+        &self,
+        request: DeleteBucketPolicyRequest,
+    ) -> BackendResult<(), DeleteBucketPolicyError> {
+        match self {
+            Backend::Rusoto(client) => client.delete_bucket_policy(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::delete_bucket_policy(client, request).await,
+        }
+    }
+
+    pub(crate) async fn get_bucket_encryption(
+        &self,
+        request: GetBucketEncryptionRequest,
+    ) -> BackendResult<GetBucketEncryptionOutput, GetBucketEncryptionError> {
+        match self {
+            Backend::Rusoto(client) => client
+                .get_bucket_encryption(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::get_bucket_encryption(client, request).await,
+        }
+    }
+
+    pub(crate) async fn put_bucket_encryption(
+        &self,
+        request: PutBucketEncryptionRequest,
+    ) -> BackendResult<(), PutBucketEncryptionError> {
+        match self {
+            Backend::Rusoto(client) => client
+                .put_bucket_encryption(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::put_bucket_encryption(client, request).await,
+        }
+    }
+
+    pub(crate) async fn get_bucket_replication(
+        &self,
+        request: GetBucketReplicationRequest,
+    ) -> BackendResult<GetBucketReplicationOutput, GetBucketReplicationError> {
+        match self {
+            Backend::Rusoto(client) => client
+                .get_bucket_replication(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::get_bucket_replication(client, request).await,
+        }
+    }
+
+    pub(crate) async fn put_bucket_replication(
+        &self,
+        request: PutBucketReplicationRequest,
+    ) -> BackendResult<(), PutBucketReplicationError> {
+        match self {
+            Backend::Rusoto(client) => client
+                .put_bucket_replication(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::put_bucket_replication(client, request).await,
+        }
+    }
+
+    pub(crate) async fn delete_bucket_replication(
+        &self,
+        request: DeleteBucketReplicationRequest,
+    ) -> BackendResult<(), DeleteBucketReplicationError> {
+        match self {
+            Backend::Rusoto(client) => client
+                .delete_bucket_replication(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::delete_bucket_replication(client, request).await,
+        }
+    }
+
+    pub(crate) async fn get_bucket_website(
+        &self,
+        request: GetBucketWebsiteRequest,
+    ) -> BackendResult<GetBucketWebsiteOutput, GetBucketWebsiteError> {
+        match self {
+            Backend::Rusoto(client) => client.get_bucket_website(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::get_bucket_website(client, request).await,
+        }
+    }
+
+    pub(crate) async fn put_bucket_website(
+        &self,
+        request: PutBucketWebsiteRequest,
+    ) -> BackendResult<(), PutBucketWebsiteError> {
+        match self {
+            Backend::Rusoto(client) => client.put_bucket_website(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::put_bucket_website(client, request).await,
+        }
+    }
+
+    pub(crate) async fn delete_bucket_website(
+        &self,
+        request: DeleteBucketWebsiteRequest,
+    ) -> BackendResult<(), DeleteBucketWebsiteError> {
+        match self {
+            Backend::Rusoto(client) => client
+                .delete_bucket_website(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::delete_bucket_website(client, request).await,
+        }
+    }
+
+    pub(crate) async fn head_object(
+        &self,
+        request: HeadObjectRequest,
+    ) -> BackendResult<HeadObjectOutput, HeadObjectError> {
+        match self {
+            Backend::Rusoto(client) => client.head_object(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::head_object(client, request).await,
+        }
+    }
+
+    // Number of parts and size of the first part of a multipart object,
+    // `None` if unknown
+    #[cfg_attr(not(feature = "aws-sdk"), allow(unused_variables))]
+    pub(crate) async fn first_part(
+        &self,
+        request: HeadObjectRequest,
+    ) -> BackendResult<Option<(i64, i64)>, HeadObjectError> {
+        match self {
+            // Rusoto doesn't implement `GetObjectAttributes`
+            Backend::Rusoto(_) => Ok(None),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::first_part(client, request).await,
+        }
+    }
+
+    pub(crate) async fn restore_object(
+        &self,
+        request: RestoreObjectRequest,
+    ) -> BackendResult<RestoreObjectOutput, RestoreObjectError> {
+        match self {
+            Backend::Rusoto(client) => client.restore_object(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::restore_object(client, request).await,
+        }
+    }
+
+    pub(crate) async fn copy_object(
+        &self,
+        request: CopyObjectRequest,
+    ) -> BackendResult<CopyObjectOutput, CopyObjectError> {
+        match self {
+            Backend::Rusoto(client) => client.copy_object(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::copy_object(client, request).await,
+        }
+    }
+
+    pub(crate) async fn get_bucket_tagging(
+        &self,
+        request: GetBucketTaggingRequest,
+    ) -> BackendResult<GetBucketTaggingOutput, GetBucketTaggingError> {
+        match self {
+            Backend::Rusoto(client) => client.get_bucket_tagging(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::get_bucket_tagging(client, request).await,
+        }
+    }
+
+    pub(crate) async fn put_bucket_tagging(
+        &self,
+        request: PutBucketTaggingRequest,
+    ) -> BackendResult<(), PutBucketTaggingError> {
+        match self {
+            Backend::Rusoto(client) => client.put_bucket_tagging(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::put_bucket_tagging(client, request).await,
+        }
+    }
+
+    pub(crate) async fn delete_bucket_tagging(
+        &self,
+        request: DeleteBucketTaggingRequest,
+    ) -> BackendResult<(), DeleteBucketTaggingError> {
+        match self {
+            Backend::Rusoto(client) => client
+                .delete_bucket_tagging(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::delete_bucket_tagging(client, request).await,
+        }
+    }
+
+    pub(crate) async fn get_bucket_logging(
+        &self,
+        request: GetBucketLoggingRequest,
+    ) -> BackendResult<GetBucketLoggingOutput, GetBucketLoggingError> {
+        match self {
+            Backend::Rusoto(client) => client.get_bucket_logging(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::get_bucket_logging(client, request).await,
+        }
+    }
+
+    pub(crate) async fn put_bucket_logging(
+        &self,
+        request: PutBucketLoggingRequest,
+    ) -> BackendResult<(), PutBucketLoggingError> {
+        match self {
+            Backend::Rusoto(client) => client.put_bucket_logging(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::put_bucket_logging(client, request).await,
+        }
+    }
+
+    pub(crate) async fn delete_object(
+        &self,
+        request: DeleteObjectRequest,
+    ) -> BackendResult<DeleteObjectOutput, DeleteObjectError> {
+        match self {
+            Backend::Rusoto(client) => client.delete_object(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::delete_object(client, request).await,
+        }
+    }
+
+    pub(crate) async fn upload_part_copy(
+        &self,
+        request: UploadPartCopyRequest,
+    ) -> BackendResult<UploadPartCopyOutput, UploadPartCopyError> {
+        match self {
+            Backend::Rusoto(client) => client.upload_part_copy(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::upload_part_copy(client, request).await,
+        }
+    }
+
+    pub(crate) async fn list_multipart_uploads(
+        &self,
+        request: ListMultipartUploadsRequest,
+    ) -> BackendResult<ListMultipartUploadsOutput, ListMultipartUploadsError> {
+        match self {
+            Backend::Rusoto(client) => client
+                .list_multipart_uploads(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::list_multipart_uploads(client, request).await,
+        }
+    }
+
+    pub(crate) async fn list_parts(
+        &self,
+        request: ListPartsRequest,
+    ) -> BackendResult<ListPartsOutput, ListPartsError> {
+        match self {
+            Backend::Rusoto(client) => client.list_parts(request).await.map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => aws_sdk::list_parts(client, request).await,
+        }
+    }
+
+    pub(crate) async fn get_bucket_intelligent_tiering_configuration(
+        &self,
+        request: GetBucketIntelligentTieringConfigurationRequest,
+    ) -> BackendResult<
+        GetBucketIntelligentTieringConfigurationOutput,
+        GetBucketIntelligentTieringConfigurationError,
+    > {
+        match self {
+            Backend::Rusoto(client) => client
+                .get_bucket_intelligent_tiering_configuration(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => {
+                aws_sdk::get_bucket_intelligent_tiering_configuration(client, request).await
+            }
+        }
+    }
+
+    pub(crate) async fn list_bucket_intelligent_tiering_configurations(
+        &self,
+        request: ListBucketIntelligentTieringConfigurationsRequest,
+    ) -> BackendResult<
+        ListBucketIntelligentTieringConfigurationsOutput,
+        ListBucketIntelligentTieringConfigurationsError,
+    > {
+        match self {
+            Backend::Rusoto(client) => client
+                .list_bucket_intelligent_tiering_configurations(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => {
+                aws_sdk::list_bucket_intelligent_tiering_configurations(client, request).await
+            }
+        }
+    }
+
+    pub(crate) async fn put_bucket_intelligent_tiering_configuration(
+        &self,
+        request: PutBucketIntelligentTieringConfigurationRequest,
+    ) -> BackendResult<(), PutBucketIntelligentTieringConfigurationError> {
+        match self {
+            Backend::Rusoto(client) => client
+                .put_bucket_intelligent_tiering_configuration(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => {
+                aws_sdk::put_bucket_intelligent_tiering_configuration(client, request).await
+            }
+        }
+    }
+
+    pub(crate) async fn delete_bucket_intelligent_tiering_configuration(
+        &self,
+        request: DeleteBucketIntelligentTieringConfigurationRequest,
+    ) -> BackendResult<(), DeleteBucketIntelligentTieringConfigurationError> {
+        match self {
+            Backend::Rusoto(client) => client
+                .delete_bucket_intelligent_tiering_configuration(request)
+                .await
+                .map_err(Box::new),
+            #[cfg(feature = "aws-sdk")]
+            Backend::AwsSdk(client) => {
+                aws_sdk::delete_bucket_intelligent_tiering_configuration(client, request).await
+            }
+        }
+    }
+}
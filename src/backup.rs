@@ -0,0 +1,395 @@
+//! Incremental backups of local directories as named snapshots
+//!
+//! A [`Backup`] stores the files of a directory in a bucket below a prefix:
+//!
+//! * `<prefix>data/<digest>` holds the content of files, named by their
+//!   SHA-256 digest as in [`cas`](crate::cas). Content shared by several
+//!   files or snapshots is stored once.
+//! * `<prefix>snapshots/<name>.json` is the [`Manifest`] of a snapshot,
+//!   listing path, object key, size, checksum, version, modification time
+//!   and permissions of every file.
+//!
+//! Taking a snapshot hashes all files but uploads only content that isn't
+//! part of the latest snapshot or stored already. Restoring a snapshot
+//! verifies the content of every file against its checksum.
+//!
+//! Only regular files and directories are included, symbolic links are
+//! skipped. Files must not be modified while a snapshot is taken.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::backup::Backup;
+//!
+//! let backup = Backup::new(client, "backups", "home/");
+//! let report = backup.snapshot("2024-06-01", "/home/user").await?;
+//! println!("{} files uploaded", report.uploaded.len());
+//!
+//! backup.restore("2024-06-01", "/tmp/restored").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    cas::ContentHashKey,
+    error::{S3ExtError, S3ExtResult},
+    file_attributes::FileAttributes,
+    S3Ext,
+};
+use chrono::Utc;
+use futures::TryStreamExt;
+use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+use tokio::fs;
+
+/// Manifest of a snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    /// Name of the snapshot
+    pub name: String,
+    /// Time the snapshot was taken, RFC 3339 formatted
+    pub created: String,
+    /// Files of the snapshot, sorted by path
+    pub files: Vec<ManifestEntry>,
+}
+
+/// File of a snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// Path relative to the backed up directory, separated by `/`
+    pub path: String,
+    /// Key of the object holding the content
+    pub key: String,
+    /// Size in bytes
+    pub size: u64,
+    /// SHA-256 digest of the content
+    pub sha256: ContentHashKey,
+    /// Version of the object, if the bucket is versioned
+    pub version_id: Option<String>,
+    /// Modification time and permissions, restored along with the content
+    pub attributes: FileAttributes,
+}
+
+impl Manifest {
+    fn to_json(&self) -> Value {
+        let files: Vec<Value> = self
+            .files
+            .iter()
+            .map(|entry| {
+                json!({
+                    "Path": entry.path,
+                    "Key": entry.key,
+                    "Size": entry.size,
+                    "Sha256": entry.sha256.to_string(),
+                    "VersionId": entry.version_id,
+                    "Mtime": entry.attributes.mtime,
+                    "Mode": entry.attributes.mode,
+                })
+            })
+            .collect();
+        json!({
+            "Name": self.name,
+            "Created": self.created,
+            "Files": files,
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let string = |value: &Value, name| value.get(name)?.as_str().map(str::to_owned);
+        let files = value
+            .get("Files")?
+            .as_array()?
+            .iter()
+            .map(|file| {
+                Some(ManifestEntry {
+                    path: string(file, "Path")?,
+                    key: string(file, "Key")?,
+                    size: file.get("Size")?.as_u64()?,
+                    sha256: file.get("Sha256")?.as_str()?.parse().ok()?,
+                    version_id: string(file, "VersionId"),
+                    // missing in manifests of older versions
+                    attributes: FileAttributes {
+                        mtime: file.get("Mtime").and_then(Value::as_i64),
+                        mode: file
+                            .get("Mode")
+                            .and_then(Value::as_u64)
+                            .map(|mode| mode as u32),
+                    },
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(Manifest {
+            name: string(value, "Name")?,
+            created: string(value, "Created")?,
+            files,
+        })
+    }
+}
+
+/// Outcome of taking a snapshot
+#[derive(Debug)]
+pub struct SnapshotReport {
+    /// Manifest written
+    pub manifest: Manifest,
+    /// Paths of the files whose content was uploaded
+    pub uploaded: Vec<String>,
+    /// Paths of the files whose content was stored already
+    pub skipped: Vec<String>,
+}
+
+/// Snapshots of local directories stored in `bucket` below `prefix`
+pub struct Backup<C> {
+    client: C,
+    bucket: String,
+    prefix: String,
+}
+
+impl<C> Backup<C>
+where
+    C: S3Ext + Sync,
+{
+    /// Store snapshots in `bucket` below `prefix`, e.g. `backups/laptop/`
+    pub fn new(client: C, bucket: impl Into<String>, prefix: impl Into<String>) -> Self {
+        Backup {
+            client,
+            bucket: bucket.into(),
+            prefix: prefix.into(),
+        }
+    }
+
+    /// Underlying client
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    fn manifest_key(&self, name: &str) -> String {
+        format!("{}snapshots/{}.json", self.prefix, name)
+    }
+
+    /// Names of the snapshots, oldest first
+    pub async fn snapshots(&self) -> S3ExtResult<Vec<String>> {
+        let prefix = format!("{}snapshots/", self.prefix);
+        let strip = prefix.len();
+        let mut snapshots: Vec<(String, String)> = self
+            .client
+            .stream_objects_with_prefix(self.bucket.clone(), prefix)
+            .map_err(S3ExtError::from)
+            .try_filter_map(|object| async move {
+                let name = object
+                    .key
+                    .as_deref()
+                    .and_then(|key| key[strip..].strip_suffix(".json"))
+                    .filter(|name| !name.contains('/'))
+                    .map(str::to_owned);
+                Ok(name.map(|name| (object.last_modified.unwrap_or_default(), name)))
+            })
+            .try_collect()
+            .await?;
+        snapshots.sort();
+        Ok(snapshots.into_iter().map(|(_, name)| name).collect())
+    }
+
+    /// Manifest of snapshot `name`
+    pub async fn manifest(&self, name: &str) -> S3ExtResult<Manifest> {
+        let key = self.manifest_key(name);
+        let request = GetObjectRequest {
+            bucket: self.bucket.clone(),
+            key: key.clone(),
+            ..Default::default()
+        };
+        let mut json = Vec::new();
+        self.client.download(request, &mut json).await?;
+        serde_json::from_slice(&json)
+            .ok()
+            .and_then(|value| Manifest::from_json(&value))
+            .ok_or_else(|| {
+                S3ExtError::Other("invalid snapshot manifest").with_object(&self.bucket, key)
+            })
+    }
+
+    /// Take snapshot `name` of the files in directory `source`
+    ///
+    /// Fails if a snapshot of that name exists already.
+    pub async fn snapshot(
+        &self,
+        name: &str,
+        source: impl AsRef<Path>,
+    ) -> S3ExtResult<SnapshotReport> {
+        if name.is_empty() || name.contains('/') {
+            return Err(S3ExtError::Other(
+                "snapshot names must be non-empty and not contain `/`",
+            ));
+        }
+        let manifest_key = self.manifest_key(name);
+        match self
+            .client
+            .object_attributes(self.bucket.clone(), manifest_key.clone())
+            .await
+        {
+            Ok(_) => return Err(S3ExtError::Other("snapshot exists already")),
+            Err(e) if e.is_not_found() => (),
+            Err(e) => return Err(e),
+        }
+
+        // content of the latest snapshot, stored already
+        let mut stored = HashMap::new();
+        if let Some(latest) = self.snapshots().await?.pop() {
+            for entry in self.manifest(&latest).await?.files {
+                stored.insert(entry.sha256, (entry.key, entry.version_id));
+            }
+        }
+
+        let mut report = SnapshotReport {
+            manifest: Manifest {
+                name: name.to_owned(),
+                created: Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                files: Vec::new(),
+            },
+            uploaded: Vec::new(),
+            skipped: Vec::new(),
+        };
+        for (path, file) in list_files(source.as_ref()).await? {
+            let metadata = fs::metadata(&file).await?;
+            let (size, attributes) = (metadata.len(), FileAttributes::from(&metadata));
+            let sha256 = ContentHashKey::of_file(&file).await?;
+            let (key, version_id) = match stored.get(&sha256) {
+                Some(stored) => {
+                    report.skipped.push(path.clone());
+                    stored.clone()
+                }
+                None => {
+                    let key = sha256.key(&format!("{}data/", self.prefix));
+                    let (uploaded, version_id) = self.store(&file, &key).await?;
+                    if uploaded {
+                        report.uploaded.push(path.clone());
+                    } else {
+                        report.skipped.push(path.clone());
+                    }
+                    (key, version_id)
+                }
+            };
+            stored.insert(sha256, (key.clone(), version_id.clone()));
+            report.manifest.files.push(ManifestEntry {
+                path,
+                key,
+                size,
+                sha256,
+                version_id,
+                attributes,
+            });
+        }
+
+        let json = serde_json::to_vec_pretty(&report.manifest.to_json())
+            .map_err(|_| S3ExtError::Other("failed to serialize snapshot manifest"))?;
+        let request = PutObjectRequest {
+            bucket: self.bucket.clone(),
+            key: manifest_key,
+            content_type: Some("application/json".to_owned()),
+            content_length: Some(json.len() as i64),
+            ..Default::default()
+        };
+        self.client.upload(&mut json.as_slice(), request).await?;
+        Ok(report)
+    }
+
+    // Upload `file` as `key` unless stored already, returns whether it was
+    // uploaded and the version of the object
+    async fn store(&self, file: &Path, key: &str) -> S3ExtResult<(bool, Option<String>)> {
+        match self
+            .client
+            .object_attributes(self.bucket.clone(), key.to_owned())
+            .await
+        {
+            Ok(attributes) => return Ok((false, attributes.version_id)),
+            Err(e) if e.is_not_found() => (),
+            Err(e) => return Err(e),
+        }
+        let request = PutObjectRequest {
+            bucket: self.bucket.clone(),
+            key: key.to_owned(),
+            ..Default::default()
+        };
+        let output = self.client.upload_from_file(file, request).await?;
+        Ok((true, output.version_id))
+    }
+
+    /// Restore the files of snapshot `name` into directory `target`
+    ///
+    /// Existing files are overwritten, other files in `target` are kept.
+    /// Modification times and permissions of files are restored.
+    /// Fails with `S3ExtError::ChecksumMismatch` if the content of a file
+    /// doesn't match the manifest.
+    pub async fn restore(&self, name: &str, target: impl AsRef<Path>) -> S3ExtResult<Manifest> {
+        let manifest = self.manifest(name).await?;
+        for entry in &manifest.files {
+            let path = local_path(target.as_ref(), &entry.path).ok_or_else(|| {
+                S3ExtError::Other("invalid path in snapshot manifest")
+                    .with_object(&self.bucket, self.manifest_key(name))
+            })?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            let request = GetObjectRequest {
+                bucket: self.bucket.clone(),
+                key: entry.key.clone(),
+                version_id: entry.version_id.clone(),
+                ..Default::default()
+            };
+            self.client.download_to_file(request, &path).await?;
+            let actual = ContentHashKey::of_file(&path).await?;
+            if actual != entry.sha256 {
+                return Err(S3ExtError::ChecksumMismatch {
+                    expected: entry.sha256.to_string(),
+                    actual: actual.to_string(),
+                }
+                .with_object(&self.bucket, &entry.key));
+            }
+            entry.attributes.apply_to_file(path).await?;
+        }
+        Ok(manifest)
+    }
+}
+
+// Regular files below `dir` with their path relative to `dir`, sorted
+async fn list_files(dir: &Path) -> S3ExtResult<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_owned()];
+    while let Some(current) = dirs.pop() {
+        let mut entries = fs::read_dir(&current).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                dirs.push(entry.path());
+            } else if file_type.is_file() {
+                let path = entry.path();
+                let relative = path
+                    .strip_prefix(dir)
+                    .ok()
+                    .and_then(Path::to_str)
+                    .ok_or(S3ExtError::Other("file names must be valid UTF-8"))?
+                    .replace(std::path::MAIN_SEPARATOR, "/");
+                files.push((relative, path));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// Location of `path` of a manifest below `dir`, `None` if it would be
+// outside of `dir`
+pub(crate) fn local_path(dir: &Path, path: &str) -> Option<PathBuf> {
+    let mut local = dir.to_owned();
+    for name in path.split('/') {
+        match Path::new(name).components().collect::<Vec<_>>().as_slice() {
+            [Component::Normal(name)] => local.push(name),
+            _ => return None,
+        }
+    }
+    Some(local)
+}
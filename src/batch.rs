@@ -0,0 +1,408 @@
+//! Operations on many objects listed in a manifest
+//!
+//! Similar to S3 Batch Operations, but run locally: a [`Manifest`] lists
+//! objects along with an [`Operation`] to apply to each of them,
+//! [`S3Ext::run_batch`](crate::S3Ext::run_batch) runs the operations
+//! concurrently, retries failed ones according to a
+//! [`RetryPolicy`](crate::retry::RetryPolicy) and reports the outcome of
+//! every operation in a [`BatchReport`].
+//!
+//! # Manifest formats
+//!
+//! CSV manifests hold one operation per line, bucket and key of the object
+//! followed by the operation and its arguments. Fields containing commas or
+//! quotes are quoted as usual, blank lines are skipped:
+//!
+//! ```text
+//! photos,2023/beach.jpg,copy,archive,photos/2023/beach.jpg
+//! photos,2023/tmp.jpg,delete
+//! photos,2023/city.jpg,tag,project=travel,reviewed=yes
+//! archive,2019/lake.jpg,restore,Bulk,7
+//! ```
+//!
+//! JSON manifests are arrays of objects with the same content:
+//!
+//! ```text
+//! [
+//!   {"Bucket": "photos", "Key": "2023/beach.jpg", "Operation": "copy",
+//!    "TargetBucket": "archive", "TargetKey": "photos/2023/beach.jpg"},
+//!   {"Bucket": "photos", "Key": "2023/tmp.jpg", "Operation": "delete"},
+//!   {"Bucket": "photos", "Key": "2023/city.jpg", "Operation": "tag",
+//!    "Tags": {"project": "travel", "reviewed": "yes"}},
+//!   {"Bucket": "archive", "Key": "2019/lake.jpg", "Operation": "restore",
+//!    "Tier": "Bulk", "Days": 7}
+//! ]
+//! ```
+//!
+//! Tagging replaces all existing tags of an object, copies include metadata
+//! and tags of the source object.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::{batch::Manifest, retry::RetryPolicy, S3Ext};
+//!
+//! let manifest = Manifest::from_csv(&std::fs::read_to_string("manifest.csv")?)?;
+//! let report = client
+//!     .run_batch(&manifest, 32, &RetryPolicy::default())
+//!     .await;
+//! std::fs::write("report.json", report.to_json().to_string())?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::{S3ExtError, S3ExtResult},
+    restore::RestoreTier,
+    retry::RetryPolicy,
+    tagging::Tags,
+    S3Ext,
+};
+use futures::stream::{self, StreamExt};
+use serde_json::{json, Value};
+
+/// Operation applied to an object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// Copy the object to `key` in `bucket`
+    Copy {
+        /// Bucket of the copy
+        bucket: String,
+        /// Key of the copy
+        key: String,
+    },
+    /// Delete the object
+    Delete,
+    /// Replace the tags of the object
+    Tag(Tags),
+    /// Restore an archived object for `days` days
+    Restore {
+        /// Retrieval tier
+        tier: RestoreTier,
+        /// Number of days the restored copy is kept
+        days: i64,
+    },
+}
+
+impl Operation {
+    /// Name of the operation as used in manifests
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Copy { .. } => "copy",
+            Operation::Delete => "delete",
+            Operation::Tag(_) => "tag",
+            Operation::Restore { .. } => "restore",
+        }
+    }
+}
+
+/// Operation on object `key` in `bucket`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    /// Bucket of the object
+    pub bucket: String,
+    /// Key of the object
+    pub key: String,
+    /// Operation applied to the object
+    pub operation: Operation,
+}
+
+/// Operations run by [`S3Ext::run_batch`](crate::S3Ext::run_batch)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    /// Operations, in the order they are started
+    pub tasks: Vec<Task>,
+}
+
+impl Manifest {
+    /// Parse a CSV manifest, see the [module documentation](self)
+    pub fn from_csv(csv: &str) -> S3ExtResult<Self> {
+        let mut tasks = Vec::new();
+        for (i, line) in csv.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = |reason: &'static str| S3ExtError::InvalidManifest {
+                entry: i + 1,
+                reason,
+            };
+            let fields = split_csv(line).ok_or_else(|| invalid("unterminated quote"))?;
+            tasks.push(task_from_fields(fields).map_err(invalid)?);
+        }
+        Ok(Manifest { tasks })
+    }
+
+    /// Parse a JSON manifest, see the [module documentation](self)
+    pub fn from_json(json: &str) -> S3ExtResult<Self> {
+        let value: Value = serde_json::from_str(json).map_err(|_| S3ExtError::InvalidManifest {
+            entry: 0,
+            reason: "not valid JSON",
+        })?;
+        let entries = value.as_array().ok_or(S3ExtError::InvalidManifest {
+            entry: 0,
+            reason: "not an array",
+        })?;
+        let tasks = entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                task_from_json(entry).map_err(|reason| S3ExtError::InvalidManifest {
+                    entry: i + 1,
+                    reason,
+                })
+            })
+            .collect::<S3ExtResult<_>>()?;
+        Ok(Manifest { tasks })
+    }
+}
+
+/// Outcome of an operation
+#[derive(Debug)]
+pub struct TaskResult {
+    /// Operation run
+    pub task: Task,
+    /// Number of attempts, including the retries
+    pub attempts: u32,
+    /// Error of the last attempt, `None` if the operation succeeded
+    pub error: Option<S3ExtError>,
+}
+
+impl TaskResult {
+    /// Whether the operation succeeded
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Outcome of running a batch
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    /// Outcome of every operation, in manifest order
+    pub results: Vec<TaskResult>,
+}
+
+impl BatchReport {
+    /// Whether all operations succeeded
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(TaskResult::is_success)
+    }
+
+    /// Operations that failed
+    pub fn failed(&self) -> impl Iterator<Item = &TaskResult> {
+        self.results.iter().filter(|result| !result.is_success())
+    }
+
+    /// Report as JSON, counting succeeded and failed operations and listing
+    /// the outcome of each of them
+    pub fn to_json(&self) -> Value {
+        let results: Vec<Value> = self
+            .results
+            .iter()
+            .map(|result| {
+                json!({
+                    "Bucket": result.task.bucket,
+                    "Key": result.task.key,
+                    "Operation": result.task.operation.name(),
+                    "Attempts": result.attempts,
+                    "Error": result.error.as_ref().map(ToString::to_string),
+                })
+            })
+            .collect();
+        let failed = self.failed().count();
+        json!({
+            "Succeeded": self.results.len() - failed,
+            "Failed": failed,
+            "Results": results,
+        })
+    }
+}
+
+pub(crate) async fn run_batch<C>(
+    client: &C,
+    manifest: &Manifest,
+    concurrency: usize,
+    policy: &RetryPolicy,
+) -> BatchReport
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    let results = stream::iter(&manifest.tasks)
+        .map(|task| run_task(client, task, policy))
+        // `buffered` doesn't make progress with a limit of 0
+        .buffered(concurrency.max(1))
+        .collect()
+        .await;
+    BatchReport { results }
+}
+
+async fn run_task<C>(client: &C, task: &Task, policy: &RetryPolicy) -> TaskResult
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    let mut retries = 0;
+    let error = loop {
+        match execute(client, task).await {
+            Ok(()) => break None,
+            Err(e) => {
+                if !policy.wait_for_retry(&e, &mut retries).await {
+                    break Some(e);
+                }
+            }
+        }
+    };
+    TaskResult {
+        task: task.clone(),
+        attempts: retries + 1,
+        error,
+    }
+}
+
+async fn execute<C>(client: &C, task: &Task) -> S3ExtResult<()>
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    let (bucket, key) = (task.bucket.as_str(), task.key.as_str());
+    match &task.operation {
+        Operation::Copy {
+            bucket: target_bucket,
+            key: target_key,
+        } => {
+            client
+                .copy(bucket, key, target_bucket.as_str(), target_key.as_str())
+                .await
+        }
+        Operation::Delete => client.delete(bucket, key).await,
+        Operation::Tag(tags) => client.put_tags(bucket, key, tags).await,
+        Operation::Restore { tier, days } => client.restore_object(bucket, key, *tier, *days).await,
+    }
+}
+
+fn task_from_fields(fields: Vec<String>) -> Result<Task, &'static str> {
+    let mut fields = fields.into_iter();
+    let bucket = next_field(&mut fields, "missing bucket")?;
+    let key = next_field(&mut fields, "missing key")?;
+    let operation = match next_field(&mut fields, "missing operation")?.as_str() {
+        "copy" => Operation::Copy {
+            bucket: next_field(&mut fields, "missing target bucket")?,
+            key: next_field(&mut fields, "missing target key")?,
+        },
+        "delete" => Operation::Delete,
+        "tag" => Operation::Tag(
+            fields
+                .by_ref()
+                .map(|tag| match tag.split_once('=') {
+                    Some((name, value)) if !name.is_empty() => {
+                        Ok((name.to_owned(), value.to_owned()))
+                    }
+                    _ => Err("tags must be given as name=value"),
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        "restore" => Operation::Restore {
+            tier: parse_tier(&next_field(&mut fields, "missing restore tier")?)?,
+            days: next_field(&mut fields, "missing restore days")?
+                .parse()
+                .map_err(|_| "restore days must be a number")?,
+        },
+        _ => return Err("unknown operation"),
+    };
+    if fields.next().is_some() {
+        return Err("too many fields");
+    }
+    Ok(Task {
+        bucket,
+        key,
+        operation,
+    })
+}
+
+fn next_field(
+    fields: &mut impl Iterator<Item = String>,
+    missing: &'static str,
+) -> Result<String, &'static str> {
+    fields
+        .next()
+        .filter(|field| !field.is_empty())
+        .ok_or(missing)
+}
+
+fn task_from_json(entry: &Value) -> Result<Task, &'static str> {
+    let string = |name: &str, missing: &'static str| {
+        entry
+            .get(name)
+            .and_then(Value::as_str)
+            .filter(|s| !s.is_empty())
+            .map(str::to_owned)
+            .ok_or(missing)
+    };
+    let operation = match string("Operation", "missing operation")?.as_str() {
+        "copy" => Operation::Copy {
+            bucket: string("TargetBucket", "missing target bucket")?,
+            key: string("TargetKey", "missing target key")?,
+        },
+        "delete" => Operation::Delete,
+        "tag" => Operation::Tag(
+            entry
+                .get("Tags")
+                .and_then(Value::as_object)
+                .ok_or("missing tags")?
+                .iter()
+                .map(|(name, value)| match value.as_str() {
+                    Some(value) => Ok((name.clone(), value.to_owned())),
+                    None => Err("tag values must be strings"),
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        "restore" => Operation::Restore {
+            tier: parse_tier(&string("Tier", "missing restore tier")?)?,
+            days: entry
+                .get("Days")
+                .and_then(Value::as_i64)
+                .ok_or("missing restore days")?,
+        },
+        _ => return Err("unknown operation"),
+    };
+    Ok(Task {
+        bucket: string("Bucket", "missing bucket")?,
+        key: string("Key", "missing key")?,
+        operation,
+    })
+}
+
+fn parse_tier(tier: &str) -> Result<RestoreTier, &'static str> {
+    [
+        RestoreTier::Expedited,
+        RestoreTier::Standard,
+        RestoreTier::Bulk,
+    ]
+    .iter()
+    .copied()
+    .find(|t| t.as_str().eq_ignore_ascii_case(tier))
+    .ok_or("unknown restore tier")
+}
+
+// Fields of a CSV line, `None` if a quote isn't terminated
+fn split_csv(line: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    fields.push(field);
+    Some(fields)
+}
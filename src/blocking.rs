@@ -0,0 +1,194 @@
+//! Blocking API for synchronous code
+//!
+//! [`BlockingS3Ext`] owns a single-threaded Tokio runtime and drives the
+//! asynchronous [`S3Ext`] methods of the wrapped client to completion, for
+//! CLI tools and build scripts that don't want to deal with async.
+//!
+//! Requires the `blocking` feature.
+//!
+//! # Panics
+//!
+//! Like [`Runtime::block_on`], all methods panic when called from within an
+//! asynchronous execution context.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rusoto_core::Region;
+//! use rusoto_s3::{GetObjectRequest, S3Client};
+//! use s3_ext::blocking::BlockingS3Ext;
+//!
+//! # fn main() -> Result<(), s3_ext::error::S3ExtError> {
+//! let client = BlockingS3Ext::new(S3Client::new(Region::EuWest1))?;
+//! for object in client.list_objects("bucket") {
+//!     println!("{:?}", object?.key);
+//! }
+//! let request = GetObjectRequest {
+//!     bucket: "bucket".to_owned(),
+//!     key: "key".to_owned(),
+//!     ..Default::default()
+//! };
+//! client.download_to_file(request, "target")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{error::S3ExtResult, iter::ObjectStream, S3Ext};
+use futures::stream::{Stream, StreamExt};
+use rusoto_s3::{
+    CompleteMultipartUploadOutput, GetObjectOutput, GetObjectRequest, PutObjectOutput,
+    PutObjectRequest, S3Client,
+};
+use std::{
+    convert::AsRef,
+    io::{Read, Write},
+    path::Path,
+    pin::Pin,
+};
+use tokio::runtime::{Builder, Runtime};
+
+/// Client running the operations of an [`S3Ext`] client to completion
+pub struct BlockingS3Ext<C = S3Client> {
+    inner: C,
+    runtime: Runtime,
+}
+
+impl<C> BlockingS3Ext<C>
+where
+    C: S3Ext + Sync,
+{
+    /// Wrap `inner`, creating a runtime for it
+    pub fn new(inner: C) -> S3ExtResult<Self> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// Wrapped client
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Runtime the operations are run on
+    pub fn runtime(&self) -> &Runtime {
+        &self.runtime
+    }
+
+    /// See [`S3Ext::download_to_file`]
+    pub fn download_to_file<F>(
+        &self,
+        source: GetObjectRequest,
+        target: F,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.runtime
+            .block_on(self.inner.download_to_file(source, target))
+    }
+
+    /// See [`S3Ext::upload_from_file`]
+    pub fn upload_from_file<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.runtime
+            .block_on(self.inner.upload_from_file(source, target))
+    }
+
+    /// See [`S3Ext::upload_from_file_multipart`]
+    pub fn upload_from_file_multipart<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+        part_size: usize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.runtime.block_on(
+            self.inner
+                .upload_from_file_multipart(source, target, part_size),
+        )
+    }
+
+    /// Get object and write it to `target`
+    ///
+    /// # Caveats
+    ///
+    /// The full content of the object is copied into memory before it's
+    /// written to `target`.
+    pub fn download<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: Write,
+    {
+        let mut buf = Vec::new();
+        let output = self
+            .runtime
+            .block_on(self.inner.download(source, &mut buf))?;
+        target.write_all(&buf)?;
+        Ok(output)
+    }
+
+    /// Read `source` and upload it to S3
+    ///
+    /// # Caveats
+    ///
+    /// The full content of `source` is copied into memory.
+    pub fn upload<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        R: Read,
+    {
+        let mut buf = Vec::new();
+        source.read_to_end(&mut buf)?;
+        self.runtime
+            .block_on(self.inner.upload(&mut buf.as_slice(), target))
+    }
+
+    /// Iterate over all objects, see [`S3Ext::stream_objects`]
+    pub fn list_objects(&self, bucket: impl Into<String>) -> BlockingIter<'_, ObjectStream> {
+        self.iter(self.inner.stream_objects(bucket))
+    }
+
+    /// Iterate over objects with given `prefix`, see
+    /// [`S3Ext::stream_objects_with_prefix`]
+    pub fn list_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> BlockingIter<'_, ObjectStream> {
+        self.iter(self.inner.stream_objects_with_prefix(bucket, prefix))
+    }
+
+    fn iter<S: Stream>(&self, stream: S) -> BlockingIter<'_, S> {
+        BlockingIter {
+            runtime: &self.runtime,
+            stream: Box::pin(stream),
+        }
+    }
+}
+
+/// Iterator over the items of a stream, fetched as needed
+pub struct BlockingIter<'a, S> {
+    runtime: &'a Runtime,
+    stream: Pin<Box<S>>,
+}
+
+impl<'a, S: Stream> Iterator for BlockingIter<'a, S> {
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
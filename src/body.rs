@@ -0,0 +1,202 @@
+//! Bodies of downloaded objects
+//!
+//! Rusoto returns the body of an object as `Option<StreamingBody>` inside a
+//! `GetObjectOutput`, to be unwrapped and converted before it can be read.
+//! [`ObjectBody`], returned by [`S3Ext::get_body`](crate::S3Ext::get_body)
+//! and [`S3Ext::get_body_with`](crate::S3Ext::get_body_with), reads the body
+//! in one go, copies it to a writer or streams it, and gives typed access to
+//! the most used fields of the response. Errors include bucket and key of
+//! the object.
+//!
+//! [`BodyStream`], returned by
+//! [`S3Ext::get_byte_stream`](crate::S3Ext::get_byte_stream), yields the
+//! chunks of a body as `io::Result<Bytes>` along with its length, ready to be
+//! used as the body of a hyper or axum response or to be fed to a hasher.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::S3Ext;
+//!
+//! let body = client.get_body("bucket", "config.toml").await?;
+//! println!("{:?} bytes, ETag {:?}", body.content_length(), body.e_tag());
+//! let config = body.read_to_string().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    error::{S3ExtError, S3ExtResult},
+    framed::ObjectReader,
+    timestamps::Timestamps,
+};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use rusoto_s3::{GetObjectOutput, GetObjectRequest, StreamingBody};
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{self, AsyncReadExt, AsyncWrite};
+
+/// Body of an object along with the response it was returned with
+#[derive(Debug)]
+pub struct ObjectBody {
+    bucket: String,
+    key: String,
+    body: Option<StreamingBody>,
+    output: GetObjectOutput,
+}
+
+impl ObjectBody {
+    /// Wrap `output` returned for object `key` in `bucket`
+    pub fn new(
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        mut output: GetObjectOutput,
+    ) -> Self {
+        ObjectBody {
+            bucket: bucket.into(),
+            key: key.into(),
+            body: output.body.take(),
+            output,
+        }
+    }
+
+    /// Response, without the body
+    pub fn output(&self) -> &GetObjectOutput {
+        &self.output
+    }
+
+    /// Response, including the body
+    pub fn into_output(self) -> GetObjectOutput {
+        GetObjectOutput {
+            body: self.body,
+            ..self.output
+        }
+    }
+
+    /// Size of the body in bytes, `None` if S3 didn't send it
+    pub fn content_length(&self) -> Option<u64> {
+        self.output.content_length.map(|length| length as u64)
+    }
+
+    /// Entity tag, including the surrounding quotes
+    pub fn e_tag(&self) -> Option<&str> {
+        self.output.e_tag.as_deref()
+    }
+
+    /// Time of the last modification, `None` if missing or malformed
+    pub fn last_modified(&self) -> Option<DateTime<Utc>> {
+        self.output.last_modified_at()
+    }
+
+    /// `Content-Type` the object was stored with
+    pub fn content_type(&self) -> Option<&str> {
+        self.output.content_type.as_deref()
+    }
+
+    /// Read the whole body into memory
+    pub async fn read_to_vec(self) -> S3ExtResult<Vec<u8>> {
+        let mut content = Vec::with_capacity(self.content_length().unwrap_or_default() as usize);
+        let (bucket, key, mut reader) = self.into_reader();
+        reader
+            .read_to_end(&mut content)
+            .await
+            .map_err(|e| S3ExtError::from(e).with_object(bucket, key))?;
+        Ok(content)
+    }
+
+    /// Read the whole body into memory, failing with
+    /// `io::ErrorKind::InvalidData` unless it is UTF-8
+    pub async fn read_to_string(self) -> S3ExtResult<String> {
+        let (bucket, key) = (self.bucket.clone(), self.key.clone());
+        String::from_utf8(self.read_to_vec().await?).map_err(|e| {
+            S3ExtError::from(io::Error::new(io::ErrorKind::InvalidData, e)).with_object(bucket, key)
+        })
+    }
+
+    /// Copy the body to `writer`, returning the number of bytes copied
+    ///
+    /// `writer` isn't flushed.
+    pub async fn copy_to<W>(self, writer: &mut W) -> S3ExtResult<u64>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let (bucket, key, mut reader) = self.into_reader();
+        io::copy(&mut reader, writer)
+            .await
+            .map_err(|e| S3ExtError::from(e).with_object(bucket, key))
+    }
+
+    /// Stream the body as chunks of bytes as they arrive
+    pub fn bytes_stream(self) -> impl Stream<Item = S3ExtResult<Bytes>> + Send {
+        let (bucket, key) = (self.bucket, self.key);
+        stream::iter(self.body)
+            .flatten()
+            .map_err(move |e| S3ExtError::from(e).with_object(&bucket, &key))
+    }
+
+    /// Stream the body as chunks of bytes, keeping its length
+    pub fn into_byte_stream(self) -> BodyStream {
+        BodyStream {
+            content_length: self.content_length(),
+            body: self.body,
+        }
+    }
+
+    /// Body as [`AsyncRead`](tokio::io::AsyncRead)
+    pub fn into_async_read(self) -> ObjectReader {
+        self.into_reader().2
+    }
+
+    fn into_reader(self) -> (String, String, ObjectReader) {
+        let reader: ObjectReader = match self.body {
+            Some(body) => Box::pin(body.into_async_read()),
+            None => Box::pin(&[][..]),
+        };
+        (self.bucket, self.key, reader)
+    }
+}
+
+/// Chunks of the body of an object, along with its length
+///
+/// Errors are plain `io::Error`s, as expected by most consumers of byte
+/// streams.
+#[derive(Debug)]
+pub struct BodyStream {
+    body: Option<StreamingBody>,
+    content_length: Option<u64>,
+}
+
+impl BodyStream {
+    /// Size of the body in bytes, `None` if S3 didn't send it
+    pub fn content_length(&self) -> Option<u64> {
+        self.content_length
+    }
+}
+
+impl Stream for BodyStream {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.body.as_mut() {
+            Some(body) => Pin::new(body).poll_next(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+pub(crate) async fn get_body(
+    client: &Backend,
+    source: GetObjectRequest,
+) -> S3ExtResult<ObjectBody> {
+    let (bucket, key) = (source.bucket.clone(), source.key.clone());
+    match client.get_object(source).await {
+        Ok(output) => Ok(ObjectBody::new(bucket, key, output)),
+        Err(e) => Err(S3ExtError::from(e).with_object(bucket, key)),
+    }
+}
@@ -0,0 +1,409 @@
+//! Configuration of buckets
+//!
+//! [`BucketConfigExt`] gets and sets the policy, default encryption,
+//! replication, website, tags, server access logging and Intelligent-Tiering
+//! configurations of buckets. It is implemented for every client
+//! implementing [`S3Ext`], wrappers like
+//! [`RetryingS3Client`](crate::retry::RetryingS3Client) apply to its requests
+//! as well.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::{bucket_config::BucketConfigExt, encryption::BucketEncryption};
+//!
+//! client
+//!     .ensure_encryption("bucket", &BucketEncryption::SseS3)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    access_log::{self, AccessLogging},
+    encryption::{self, BucketEncryption},
+    error::S3ExtResult,
+    intelligent_tiering::{self, IntelligentTiering},
+    policy::{self, BucketPolicy},
+    replication::{self, Replication},
+    tagging::{self, Tags},
+    website::{self, Website},
+    S3Ext,
+};
+use async_trait::async_trait;
+
+/// Bucket configuration methods, implemented for every [`S3Ext`] client
+#[async_trait]
+pub trait BucketConfigExt: S3Ext {
+    /// Get the policy of `bucket`, `None` if it has no policy
+    async fn get_bucket_policy(
+        &self,
+        bucket: impl Into<String> + Send,
+    ) -> S3ExtResult<Option<BucketPolicy>> {
+        self.send_request(
+            "get_bucket_policy",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move { policy::get_bucket_policy(&client, bucket).await },
+        )
+        .await
+    }
+
+    /// Replace the policy of `bucket` by `policy`
+    async fn put_bucket_policy(
+        &self,
+        bucket: impl Into<String> + Send,
+        policy: &BucketPolicy,
+    ) -> S3ExtResult<()> {
+        self.send_request(
+            "put_bucket_policy",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move {
+                policy::put_bucket_policy(&client, bucket, policy).await
+            },
+        )
+        .await
+    }
+
+    /// Remove the policy of `bucket`
+    async fn delete_bucket_policy(&self, bucket: impl Into<String> + Send) -> S3ExtResult<()> {
+        self.send_request(
+            "delete_bucket_policy",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move { policy::delete_bucket_policy(&client, bucket).await },
+        )
+        .await
+    }
+
+    /// Get the default encryption of `bucket`, `None` if it has none
+    async fn get_bucket_encryption(
+        &self,
+        bucket: impl Into<String> + Send,
+    ) -> S3ExtResult<Option<BucketEncryption>> {
+        self.send_request(
+            "get_bucket_encryption",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move {
+                encryption::get_bucket_encryption(&client, bucket).await
+            },
+        )
+        .await
+    }
+
+    /// Set the default encryption of `bucket`
+    async fn put_bucket_encryption(
+        &self,
+        bucket: impl Into<String> + Send,
+        encryption: &BucketEncryption,
+    ) -> S3ExtResult<()> {
+        self.send_request(
+            "put_bucket_encryption",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move {
+                encryption::put_bucket_encryption(&client, bucket, encryption).await
+            },
+        )
+        .await
+    }
+
+    /// Set the default encryption of `bucket` to `encryption` unless it is
+    /// set to it already
+    ///
+    /// Returns whether the configuration was written.
+    async fn ensure_encryption(
+        &self,
+        bucket: impl Into<String> + Send,
+        encryption: &BucketEncryption,
+    ) -> S3ExtResult<bool> {
+        encryption::ensure_encryption(self, bucket.into(), encryption).await
+    }
+
+    /// Get the replication configuration of `bucket`, `None` if it has none
+    async fn get_bucket_replication(
+        &self,
+        bucket: impl Into<String> + Send,
+    ) -> S3ExtResult<Option<Replication>> {
+        self.send_request(
+            "get_bucket_replication",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move {
+                replication::get_bucket_replication(&client, bucket).await
+            },
+        )
+        .await
+    }
+
+    /// Replace the replication configuration of `bucket` by `replication`
+    ///
+    /// Versioning must be enabled on both the source and destination buckets.
+    async fn put_bucket_replication(
+        &self,
+        bucket: impl Into<String> + Send,
+        replication: &Replication,
+    ) -> S3ExtResult<()> {
+        self.send_request(
+            "put_bucket_replication",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move {
+                replication::put_bucket_replication(&client, bucket, replication).await
+            },
+        )
+        .await
+    }
+
+    /// Remove the replication configuration of `bucket`
+    async fn delete_bucket_replication(&self, bucket: impl Into<String> + Send) -> S3ExtResult<()> {
+        self.send_request(
+            "delete_bucket_replication",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move {
+                replication::delete_bucket_replication(&client, bucket).await
+            },
+        )
+        .await
+    }
+
+    /// Get the website configuration of `bucket`, `None` if it isn't
+    /// configured as a website
+    async fn get_website(&self, bucket: impl Into<String> + Send) -> S3ExtResult<Option<Website>> {
+        self.send_request(
+            "get_website",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move { website::get_website(&client, bucket).await },
+        )
+        .await
+    }
+
+    /// Replace the website configuration of `bucket` by `website`
+    async fn put_website(
+        &self,
+        bucket: impl Into<String> + Send,
+        website: &Website,
+    ) -> S3ExtResult<()> {
+        self.send_request(
+            "put_website",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move { website::put_website(&client, bucket, website).await },
+        )
+        .await
+    }
+
+    /// Remove the website configuration of `bucket`
+    async fn delete_website(&self, bucket: impl Into<String> + Send) -> S3ExtResult<()> {
+        self.send_request(
+            "delete_website",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move { website::delete_website(&client, bucket).await },
+        )
+        .await
+    }
+
+    /// Serve `bucket` as a static website using `index_document` as index
+    /// and `error_document` as the page returned on 4XX errors
+    ///
+    /// Replaces any existing website configuration, use
+    /// [`put_website`](Self::put_website) to set redirect rules.
+    async fn enable_website(
+        &self,
+        bucket: impl Into<String> + Send,
+        index_document: impl Into<String> + Send,
+        error_document: Option<String>,
+    ) -> S3ExtResult<()> {
+        let mut website = Website::new(index_document);
+        if let Some(error_document) = error_document {
+            website = website.error_document(error_document);
+        }
+        self.put_website(bucket, &website).await
+    }
+
+    /// Get the tags of `bucket`, empty if it has none
+    async fn get_bucket_tags(&self, bucket: impl Into<String> + Send) -> S3ExtResult<Tags> {
+        self.send_request(
+            "get_bucket_tags",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move { tagging::get_bucket_tags(&client, bucket).await },
+        )
+        .await
+    }
+
+    /// Replace all tags of `bucket` by `tags`
+    ///
+    /// Fails with `S3ExtError::InvalidTag` if `tags` exceed the limits of S3,
+    /// which allows up to [`MAX_BUCKET_TAGS`](crate::tagging::MAX_BUCKET_TAGS) tags per bucket.
+    async fn put_bucket_tags(
+        &self,
+        bucket: impl Into<String> + Send,
+        tags: &Tags,
+    ) -> S3ExtResult<()> {
+        self.send_request(
+            "put_bucket_tags",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move {
+                tagging::put_bucket_tags(&client, bucket, tags).await
+            },
+        )
+        .await
+    }
+
+    /// Remove all tags from `bucket`
+    async fn delete_bucket_tags(&self, bucket: impl Into<String> + Send) -> S3ExtResult<()> {
+        self.send_request(
+            "delete_bucket_tags",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move { tagging::delete_bucket_tags(&client, bucket).await },
+        )
+        .await
+    }
+
+    /// Get the server access logging configuration of `bucket`, `None` if
+    /// logging is disabled
+    async fn get_access_logging(
+        &self,
+        bucket: impl Into<String> + Send,
+    ) -> S3ExtResult<Option<AccessLogging>> {
+        self.send_request(
+            "get_access_logging",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move {
+                access_log::get_access_logging(&client, bucket).await
+            },
+        )
+        .await
+    }
+
+    /// Write the server access logs of `bucket` as configured by `logging`
+    ///
+    /// The target bucket must allow S3 to deliver the logs, see
+    /// [`enable_access_logging`](Self::enable_access_logging).
+    async fn put_access_logging(
+        &self,
+        bucket: impl Into<String> + Send,
+        logging: &AccessLogging,
+    ) -> S3ExtResult<()> {
+        self.send_request(
+            "put_access_logging",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move {
+                access_log::put_access_logging(&client, bucket, Some(logging)).await
+            },
+        )
+        .await
+    }
+
+    /// Stop writing server access logs of `bucket`
+    async fn disable_access_logging(&self, bucket: impl Into<String> + Send) -> S3ExtResult<()> {
+        self.send_request(
+            "disable_access_logging",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move {
+                access_log::put_access_logging(&client, bucket, None).await
+            },
+        )
+        .await
+    }
+
+    /// Write the server access logs of `bucket` to `target_bucket` below
+    /// `prefix`
+    ///
+    /// S3 is allowed to deliver the logs by a statement added to the policy
+    /// of `target_bucket`, see [`access_log`](crate::access_log).
+    async fn enable_access_logging(
+        &self,
+        bucket: impl Into<String> + Send,
+        target_bucket: impl Into<String> + Send,
+        prefix: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        access_log::enable_access_logging(self, bucket.into(), target_bucket.into(), prefix.into())
+            .await
+    }
+
+    /// Get the Intelligent-Tiering configuration `id` of `bucket`, `None` if
+    /// it doesn't exist
+    async fn get_intelligent_tiering(
+        &self,
+        bucket: impl Into<String> + Send,
+        id: impl Into<String> + Send,
+    ) -> S3ExtResult<Option<IntelligentTiering>> {
+        self.send_request(
+            "get_intelligent_tiering",
+            bucket.into(),
+            id.into(),
+            |client, bucket, id| async move {
+                intelligent_tiering::get_intelligent_tiering(&client, bucket, id).await
+            },
+        )
+        .await
+    }
+
+    /// List the Intelligent-Tiering configurations of `bucket`
+    async fn list_intelligent_tiering(
+        &self,
+        bucket: impl Into<String> + Send,
+    ) -> S3ExtResult<Vec<IntelligentTiering>> {
+        self.send_request(
+            "list_intelligent_tiering",
+            bucket.into(),
+            String::new(),
+            |client, bucket, _| async move {
+                intelligent_tiering::list_intelligent_tiering(&client, bucket).await
+            },
+        )
+        .await
+    }
+
+    /// Create or replace the Intelligent-Tiering configuration of `bucket`
+    /// with the ID of `tiering`
+    ///
+    /// Only affects objects stored in the `INTELLIGENT_TIERING` storage class.
+    async fn put_intelligent_tiering(
+        &self,
+        bucket: impl Into<String> + Send,
+        tiering: &IntelligentTiering,
+    ) -> S3ExtResult<()> {
+        self.send_request(
+            "put_intelligent_tiering",
+            bucket.into(),
+            tiering.id.clone(),
+            |client, bucket, _| async move {
+                intelligent_tiering::put_intelligent_tiering(&client, bucket, tiering).await
+            },
+        )
+        .await
+    }
+
+    /// Remove the Intelligent-Tiering configuration `id` of `bucket`
+    async fn delete_intelligent_tiering(
+        &self,
+        bucket: impl Into<String> + Send,
+        id: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.send_request(
+            "delete_intelligent_tiering",
+            bucket.into(),
+            id.into(),
+            |client, bucket, id| async move {
+                intelligent_tiering::delete_intelligent_tiering(&client, bucket, id).await
+            },
+        )
+        .await
+    }
+}
+
+impl<C> BucketConfigExt for C where C: S3Ext + Sync {}
@@ -0,0 +1,1193 @@
+//! Caches for downloads
+//!
+//! [`DiskCache`] keeps the objects it downloads in a local directory,
+//! [`MemoryCache`] keeps small objects in memory. Reading an object again
+//! sends a conditional request (`If-None-Match`) and answers from the cache
+//! if S3 reports the object unchanged, saving the transfer of the body. A
+//! `MemoryCache` skips the request for objects validated recently. Once a
+//! cache exceeds its limits, the least recently read objects are removed.
+//!
+//! [`download`](crate::S3Ext::download) and the methods built on it, e.g.
+//! `get_bytes` and `get_json`, use the caches, as does
+//! [`download_to_file`](crate::S3Ext::download_to_file) of `DiskCache`.
+//! Requests for ranges or parts, with conditions, response header overrides
+//! or customer-provided keys (SSE-C) bypass them, as do all other operations.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rusoto_core::Region;
+//! use rusoto_s3::{GetObjectRequest, S3Client};
+//! use s3_ext::{cache::DiskCache, S3Ext};
+//!
+//! # async fn example() -> Result<(), s3_ext::error::S3ExtError> {
+//! let client = DiskCache::new(S3Client::new(Region::EuWest1), "/var/cache/datasets")
+//!     .max_size(10 << 30);
+//! let request = GetObjectRequest {
+//!     bucket: "bucket".to_owned(),
+//!     key: "reference.csv".to_owned(),
+//!     ..Default::default()
+//! };
+//! let mut body = Vec::new();
+//! client.download(request.clone(), &mut body).await?;
+//! // revalidated, the body is read from the cache
+//! client.download(request, &mut body).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    body::ObjectBody,
+    compose::ComposePart,
+    diff::DiffStream,
+    error::{S3ExtError, S3ExtResult},
+    framed::FramedObject,
+    iter::{GetObjectStream, ObjectStream, SortedObjectStream},
+    lines::LineStream,
+    metadata::ObjectMetadata,
+    part_size::PartSize,
+    watch::WatchStream,
+    S3Ext,
+};
+use async_trait::async_trait;
+use futures::ready;
+use parking_lot::Mutex;
+use rusoto_s3::{
+    CompleteMultipartUploadOutput, GetObjectOutput, GetObjectRequest, PutObjectOutput,
+    PutObjectRequest, S3Client,
+};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    convert::AsRef,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::{self, AsyncWriteExt},
+};
+use tokio_util::codec::Decoder;
+
+/// Default size limit of a [`DiskCache`], 1 GiB
+pub const DEFAULT_MAX_SIZE: u64 = 1 << 30;
+
+/// Client caching downloaded objects in a local directory
+///
+/// The size and last read of the cached objects are indexed in memory, the
+/// directory is listed once, when the first object is read. Clones share the
+/// index. See the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct DiskCache<C = S3Client> {
+    inner: C,
+    dir: PathBuf,
+    max_size: u64,
+    // `None` until the directory is listed
+    index: Arc<Mutex<Option<DiskIndex>>>,
+}
+
+#[derive(Debug, Default)]
+struct DiskIndex {
+    // last read and size of the cached objects, by entry name
+    entries: HashMap<String, (u64, u64)>,
+    size: u64,
+}
+
+impl DiskIndex {
+    fn insert(&mut self, name: &str, entry: &Entry) {
+        self.size += entry.size;
+        if let Some((_, size)) = self
+            .entries
+            .insert(name.to_owned(), (entry.accessed, entry.size))
+        {
+            self.size -= size;
+        }
+    }
+
+    // Remove the least recently read objects but `keep` until the size
+    // limit is met, returning their names
+    fn evict(&mut self, max_size: u64, keep: &str) -> Vec<String> {
+        let mut evicted = Vec::new();
+        while self.size > max_size {
+            let lru = self
+                .entries
+                .iter()
+                .filter(|(name, _)| name.as_str() != keep)
+                .min_by_key(|(_, (accessed, _))| *accessed)
+                .map(|(name, _)| name.clone());
+            match lru {
+                Some(name) => {
+                    if let Some((_, size)) = self.entries.remove(&name) {
+                        self.size -= size;
+                    }
+                    evicted.push(name);
+                }
+                None => break,
+            }
+        }
+        evicted
+    }
+}
+
+// Cached object, stored as JSON next to the body
+#[derive(Debug)]
+struct Entry {
+    e_tag: Option<String>,
+    content_type: Option<String>,
+    last_modified: Option<String>,
+    version_id: Option<String>,
+    size: u64,
+    // nanoseconds since the epoch of the last read
+    accessed: u64,
+}
+
+impl Entry {
+    fn to_json(&self) -> Value {
+        json!({
+            "ETag": self.e_tag,
+            "ContentType": self.content_type,
+            "LastModified": self.last_modified,
+            "VersionId": self.version_id,
+            "Size": self.size,
+            "Accessed": self.accessed,
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let string = |name| value.get(name)?.as_str().map(str::to_owned);
+        Some(Entry {
+            e_tag: string("ETag"),
+            content_type: string("ContentType"),
+            last_modified: string("LastModified"),
+            version_id: string("VersionId"),
+            size: value.get("Size")?.as_u64()?,
+            accessed: value.get("Accessed")?.as_u64()?,
+        })
+    }
+
+    fn output(&self) -> GetObjectOutput {
+        GetObjectOutput {
+            content_length: Some(self.size as i64),
+            content_type: self.content_type.clone(),
+            e_tag: self.e_tag.clone(),
+            last_modified: self.last_modified.clone(),
+            version_id: self.version_id.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl<C> DiskCache<C> {
+    /// Wrap `inner`, caching objects in `dir`
+    ///
+    /// `dir` is created on first use. It should not be shared with anything
+    /// but other `DiskCache`s.
+    pub fn new(inner: C, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            dir: dir.into(),
+            max_size: DEFAULT_MAX_SIZE,
+            index: Arc::default(),
+        }
+    }
+
+    /// Limit the total size of the cached objects to `max_size` bytes
+    ///
+    /// Defaults to [`DEFAULT_MAX_SIZE`]. An object larger than the limit is
+    /// kept until the next one is cached.
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Wrapped client
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Directory the objects are cached in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Remove all cached objects
+    pub async fn clear(&self) -> S3ExtResult<()> {
+        let result = match fs::remove_dir_all(&self.dir).await {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+        *self.index.lock() = None;
+        result
+    }
+}
+
+impl<C> DiskCache<C>
+where
+    C: S3Ext + Send + Sync,
+{
+    // Revalidate or download the object, returning the cached body
+    async fn open(&self, source: GetObjectRequest) -> S3ExtResult<(File, GetObjectOutput)> {
+        fs::create_dir_all(&self.dir).await?;
+        let name = entry_name(&source);
+        let body = self.dir.join(format!("{}.body", name));
+        let meta = self.dir.join(format!("{}.json", name));
+        let tmp = self
+            .dir
+            .join(format!("{}.{:08x}.tmp", name, rand::random::<u32>()));
+
+        let cached = match read_entry(&meta).await {
+            Some(entry) if fs::metadata(&body).await.is_ok() => Some(entry),
+            _ => None,
+        };
+        let request = GetObjectRequest {
+            if_none_match: cached.as_ref().and_then(|entry| entry.e_tag.clone()),
+            ..source
+        };
+        let (entry, downloaded) = match self.inner.download_to_file(request, &tmp).await {
+            Ok(output) => {
+                fs::rename(&tmp, &body).await?;
+                let entry = Entry {
+                    e_tag: output.e_tag,
+                    content_type: output.content_type,
+                    last_modified: output.last_modified,
+                    version_id: output.version_id,
+                    size: fs::metadata(&body).await?.len(),
+                    accessed: now(),
+                };
+                (entry, true)
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&tmp).await;
+                match cached {
+                    Some(entry) if e.is_not_modified() => {
+                        let entry = Entry {
+                            accessed: now(),
+                            ..entry
+                        };
+                        (entry, false)
+                    }
+                    _ => return Err(e),
+                }
+            }
+        };
+        fs::write(&meta, entry.to_json().to_string()).await?;
+        // opened before evicting, the body stays readable if it's removed
+        let file = File::open(&body).await?;
+        self.record(&name, &entry, downloaded).await?;
+        Ok((file, entry.output()))
+    }
+
+    // Index the read of `entry`, evicting the least recently read objects if
+    // it was downloaded
+    async fn record(&self, name: &str, entry: &Entry, downloaded: bool) -> S3ExtResult<()> {
+        let listed = self.index.lock().is_some();
+        if !listed {
+            let index = list_entries(&self.dir).await?;
+            self.index.lock().get_or_insert(index);
+        }
+        let evicted = {
+            let mut index = self.index.lock();
+            let index = index.get_or_insert_with(DiskIndex::default);
+            index.insert(name, entry);
+            if downloaded {
+                index.evict(self.max_size, name)
+            } else {
+                Vec::new()
+            }
+        };
+        for name in evicted {
+            let _ = fs::remove_file(self.dir.join(format!("{}.body", name))).await;
+            let _ = fs::remove_file(self.dir.join(format!("{}.json", name))).await;
+        }
+        Ok(())
+    }
+}
+
+// Index the objects cached in `dir`
+async fn list_entries(dir: &Path) -> S3ExtResult<DiskIndex> {
+    let mut index = DiskIndex::default();
+    let mut items = fs::read_dir(dir).await?;
+    while let Some(item) = items.next_entry().await? {
+        let path = item.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let name = path.file_stem().and_then(|stem| stem.to_str());
+        if let (Some(name), Some(entry)) = (name, read_entry(&path).await) {
+            index.insert(name, &entry);
+        }
+    }
+    Ok(index)
+}
+
+// Requests whose response can be served from the cache
+fn is_cacheable(request: &GetObjectRequest) -> bool {
+    request.range.is_none()
+        && request.part_number.is_none()
+        && request.if_match.is_none()
+        && request.if_none_match.is_none()
+        && request.if_modified_since.is_none()
+        && request.if_unmodified_since.is_none()
+        && request.response_cache_control.is_none()
+        && request.response_content_disposition.is_none()
+        && request.response_content_encoding.is_none()
+        && request.response_content_language.is_none()
+        && request.response_content_type.is_none()
+        && request.response_expires.is_none()
+        && request.sse_customer_key.is_none()
+}
+
+// File name of the cached object, without extension
+fn entry_name(request: &GetObjectRequest) -> String {
+    let mut hasher = Sha256::new();
+    for part in &[
+        request.bucket.as_str(),
+        request.key.as_str(),
+        request.version_id.as_deref().unwrap_or_default(),
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hex::encode(hasher.finalize())
+}
+
+async fn read_entry(path: &Path) -> Option<Entry> {
+    let json = fs::read(path).await.ok()?;
+    Entry::from_json(&serde_json::from_slice(&json).ok()?)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+}
+
+/// Default number of objects kept by a [`MemoryCache`]
+pub const DEFAULT_MAX_ENTRIES: usize = 1024;
+
+/// Default size limit of a [`MemoryCache`], 64 MiB
+pub const DEFAULT_MAX_BYTES: usize = 64 << 20;
+
+/// Default time a [`MemoryCache`] serves objects without revalidating them,
+/// one minute
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+/// Client keeping small downloaded objects in memory
+///
+/// Objects validated within the [`ttl`](Self::ttl) are served without a
+/// request, older ones are revalidated like those of a [`DiskCache`]. Bodies
+/// are kept by bucket, key and ETag, versions with the same content share
+/// one. Only [`download`](S3Ext::download) and the methods built on it, e.g.
+/// `get_bytes` and `get_json`, use the cache. Objects larger than the byte
+/// budget or without an ETag are never cached. Clones share the cached
+/// objects.
+#[derive(Debug, Clone)]
+pub struct MemoryCache<C = S3Client> {
+    inner: C,
+    max_entries: usize,
+    max_bytes: usize,
+    ttl: Duration,
+    state: Arc<Mutex<MemoryState>>,
+}
+
+// Bucket, key and version of a request
+type Location = (String, String, Option<String>);
+
+// Body and output of a cached object, and whether it's fresh
+type CachedBody = (Arc<[u8]>, GetObjectOutput, bool);
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    // bodies by bucket, key and ETag
+    entries: HashMap<(String, String, String), MemoryEntry>,
+    // ETag of the objects requested and when it was validated
+    current: HashMap<Location, (String, Instant)>,
+    bytes: usize,
+    // incremented on each read, orders the entries by recency
+    clock: u64,
+}
+
+impl MemoryState {
+    // Cached body and output of the object at `location`, and whether it was
+    // validated within `ttl`
+    fn get(&mut self, location: &Location, ttl: Duration) -> Option<CachedBody> {
+        let (e_tag, validated) = self.current.get(location)?;
+        let fresh = validated.elapsed() < ttl;
+        let id = (location.0.clone(), location.1.clone(), e_tag.clone());
+        self.clock += 1;
+        let entry = self.entries.get_mut(&id)?;
+        entry.used = self.clock;
+        Some((entry.body.clone(), without_body(&entry.output), fresh))
+    }
+
+    fn validated(&mut self, location: &Location) {
+        if let Some((_, validated)) = self.current.get_mut(location) {
+            *validated = Instant::now();
+        }
+    }
+
+    // Whether a request refers to the body `id`
+    fn is_current(&self, id: &(String, String, String)) -> bool {
+        self.current
+            .iter()
+            .any(|((bucket, key, _), (e_tag, _))| (bucket, key, e_tag) == (&id.0, &id.1, &id.2))
+    }
+
+    fn remove(&mut self, id: &(String, String, String)) {
+        if let Some(entry) = self.entries.remove(id) {
+            self.bytes -= entry.body.len();
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MemoryEntry {
+    body: Arc<[u8]>,
+    output: GetObjectOutput,
+    used: u64,
+}
+
+impl<C> MemoryCache<C> {
+    /// Wrap `inner`, caching up to [`DEFAULT_MAX_ENTRIES`] objects taking
+    /// up to [`DEFAULT_MAX_BYTES`] bytes
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_bytes: DEFAULT_MAX_BYTES,
+            ttl: DEFAULT_TTL,
+            state: Arc::default(),
+        }
+    }
+
+    /// Keep at most `max_entries` objects
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Keep objects taking at most `max_bytes` bytes in total
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Serve objects without revalidating them for `ttl` after they were
+    /// downloaded or validated
+    ///
+    /// Defaults to [`DEFAULT_TTL`]. A `ttl` of zero revalidates every read.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Wrapped client
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Number of cached objects and their total size
+    pub fn usage(&self) -> (usize, usize) {
+        let state = self.state.lock();
+        (state.entries.len(), state.bytes)
+    }
+
+    /// Remove all cached objects
+    pub fn clear(&self) {
+        let mut state = self.state.lock();
+        state.entries.clear();
+        state.current.clear();
+        state.bytes = 0;
+    }
+
+    fn insert(&self, location: Location, e_tag: String, body: Vec<u8>, output: GetObjectOutput) {
+        let mut state = self.state.lock();
+        let id = (location.0.clone(), location.1.clone(), e_tag.clone());
+        let previous = state.current.insert(location, (e_tag, Instant::now()));
+        // drop the body the request referred to unless others still do
+        if let Some((previous, _)) = previous {
+            let previous = (id.0.clone(), id.1.clone(), previous);
+            if previous != id && !state.is_current(&previous) {
+                state.remove(&previous);
+            }
+        }
+        state.clock += 1;
+        let entry = MemoryEntry {
+            body: body.into(),
+            output,
+            used: state.clock,
+        };
+        state.bytes += entry.body.len();
+        if let Some(old) = state.entries.insert(id, entry) {
+            state.bytes -= old.body.len();
+        }
+        while state.entries.len() > self.max_entries || state.bytes > self.max_bytes {
+            let lru = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(id, _)| id.clone());
+            match lru {
+                Some(id) => {
+                    state.remove(&id);
+                    state.current.retain(|(bucket, key, _), (e_tag, _)| {
+                        (bucket, key, &*e_tag) != (&id.0, &id.1, &id.2)
+                    });
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+// Write the cached `body` of the object at `location` to `target`
+async fn write_cached<W>(
+    target: &mut W,
+    location: Location,
+    body: &[u8],
+    output: GetObjectOutput,
+) -> S3ExtResult<GetObjectOutput>
+where
+    W: io::AsyncWrite + Unpin + Send,
+{
+    let (bucket, key, _) = location;
+    target
+        .write_all(body)
+        .await
+        .map_err(|e| S3ExtError::from(e).with_object(bucket, key))?;
+    Ok(output)
+}
+
+// Writes to `target`, keeping a copy of the first `limit` bytes
+struct Tee<'a, W> {
+    target: &'a mut W,
+    copy: Option<Vec<u8>>,
+    limit: usize,
+}
+
+impl<W> io::AsyncWrite for Tee<'_, W>
+where
+    W: io::AsyncWrite + Unpin,
+{
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = &mut *self;
+        let written = ready!(Pin::new(&mut *this.target).poll_write(cx, buf))?;
+        if let Some(copy) = &mut this.copy {
+            copy.extend_from_slice(&buf[..written]);
+            if copy.len() > this.limit {
+                this.copy = None;
+            }
+        }
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut *self.target).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut *self.target).poll_shutdown(cx)
+    }
+}
+
+// Copy of `output` without the body, as kept by a `MemoryCache`
+pub(crate) fn without_body(output: &GetObjectOutput) -> GetObjectOutput {
+    GetObjectOutput {
+        cache_control: output.cache_control.clone(),
+        content_disposition: output.content_disposition.clone(),
+        content_encoding: output.content_encoding.clone(),
+        content_language: output.content_language.clone(),
+        content_length: output.content_length,
+        content_type: output.content_type.clone(),
+        e_tag: output.e_tag.clone(),
+        expires: output.expires.clone(),
+        last_modified: output.last_modified.clone(),
+        metadata: output.metadata.clone(),
+        storage_class: output.storage_class.clone(),
+        version_id: output.version_id.clone(),
+        ..Default::default()
+    }
+}
+
+#[async_trait]
+impl<C> S3Ext for DiskCache<C>
+where
+    C: S3Ext + Send + Sync,
+{
+    async fn send_request<T, F, Fut>(
+        &self,
+        operation: &'static str,
+        bucket: String,
+        key: String,
+        request: F,
+    ) -> S3ExtResult<T>
+    where
+        T: Send,
+        F: Fn(Backend, String, String) -> Fut + Send + Sync,
+        Fut: Future<Output = S3ExtResult<T>> + Send,
+    {
+        self.inner
+            .send_request(operation, bucket, key, request)
+            .await
+    }
+
+    async fn download_to_file<F>(
+        &self,
+        source: GetObjectRequest,
+        target: F,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        if !is_cacheable(&source) {
+            return self.inner.download_to_file(source, target).await;
+        }
+        let (bucket, key) = (source.bucket.clone(), source.key.clone());
+        let (mut body, output) = self.open(source).await?;
+        let result: S3ExtResult<_> = async {
+            let mut target = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(target)
+                .await?;
+            io::copy(&mut body, &mut target).await?;
+            target.flush().await?;
+            Ok(output)
+        }
+        .await;
+        result.map_err(|e| e.with_object(bucket, key))
+    }
+
+    async fn upload_from_file<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.inner.upload_from_file(source, target).await
+    }
+
+    async fn upload_from_file_multipart_with<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.inner
+            .upload_from_file_multipart_with(source, target, part_size)
+            .await
+    }
+
+    async fn download<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        if !is_cacheable(&source) {
+            return self.inner.download(source, target).await;
+        }
+        let (bucket, key) = (source.bucket.clone(), source.key.clone());
+        let (mut body, output) = self.open(source).await?;
+        io::copy(&mut body, target)
+            .await
+            .map_err(|e| S3ExtError::from(e).with_object(bucket, key))?;
+        Ok(output)
+    }
+
+    async fn download_decompressed<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        // decompressed bodies aren't cached
+        self.inner.download_decompressed(source, target).await
+    }
+
+    async fn get_framed<D>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        decoder: D,
+    ) -> S3ExtResult<FramedObject<D>>
+    where
+        D: Decoder + Send,
+    {
+        self.inner.get_framed(bucket, key, decoder).await
+    }
+
+    async fn get_body_with(&self, source: GetObjectRequest) -> S3ExtResult<ObjectBody> {
+        // bodies are streamed, not cached
+        self.inner.get_body_with(source).await
+    }
+
+    async fn upload<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.inner.upload(source, target).await
+    }
+
+    async fn upload_multipart_with<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.inner
+            .upload_multipart_with(source, target, part_size)
+            .await
+    }
+
+    async fn upload_multipart_resumable<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.inner
+            .upload_multipart_resumable(source, target, part_size)
+            .await
+    }
+
+    #[inline]
+    fn stream_objects(&self, bucket: impl Into<String>) -> ObjectStream {
+        self.inner.stream_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> ObjectStream {
+        self.inner.stream_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_objects_sorted_by_mtime(&self, bucket: impl Into<String>) -> SortedObjectStream {
+        self.inner.stream_objects_sorted_by_mtime(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix_sorted_by_mtime(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> SortedObjectStream {
+        self.inner
+            .stream_objects_with_prefix_sorted_by_mtime(bucket, prefix)
+    }
+
+    #[inline]
+    fn diff(
+        &self,
+        source_bucket: impl Into<String>,
+        source_prefix: impl Into<String>,
+        dest_bucket: impl Into<String>,
+        dest_prefix: impl Into<String>,
+    ) -> DiffStream {
+        self.inner
+            .diff(source_bucket, source_prefix, dest_bucket, dest_prefix)
+    }
+
+    #[inline]
+    fn watch_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        period: Duration,
+    ) -> WatchStream {
+        self.inner.watch_prefix(bucket, prefix, period)
+    }
+
+    #[inline]
+    fn stream_get_objects(&self, bucket: impl Into<String>) -> GetObjectStream {
+        self.inner.stream_get_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_get_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> GetObjectStream {
+        self.inner.stream_get_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_lines(&self, bucket: impl Into<String>, key: impl Into<String>) -> LineStream {
+        self.inner.stream_lines(bucket, key)
+    }
+
+    async fn delete(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.inner.delete(bucket, key).await
+    }
+
+    async fn compose_object(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        parts: Vec<ComposePart>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput> {
+        self.inner.compose_object(bucket, key, parts).await
+    }
+
+    async fn object_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectMetadata> {
+        self.inner.object_metadata(bucket, key).await
+    }
+
+    async fn replace_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        metadata: ObjectMetadata,
+    ) -> S3ExtResult<()> {
+        self.inner.replace_metadata(bucket, key, metadata).await
+    }
+
+    async fn copy(
+        &self,
+        source_bucket: impl Into<String> + Send,
+        source_key: impl Into<String> + Send,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.inner
+            .copy(source_bucket, source_key, bucket, key)
+            .await
+    }
+}
+
+#[async_trait]
+impl<C> S3Ext for MemoryCache<C>
+where
+    C: S3Ext + Send + Sync,
+{
+    async fn send_request<T, F, Fut>(
+        &self,
+        operation: &'static str,
+        bucket: String,
+        key: String,
+        request: F,
+    ) -> S3ExtResult<T>
+    where
+        T: Send,
+        F: Fn(Backend, String, String) -> Fut + Send + Sync,
+        Fut: Future<Output = S3ExtResult<T>> + Send,
+    {
+        self.inner
+            .send_request(operation, bucket, key, request)
+            .await
+    }
+
+    async fn download_to_file<F>(
+        &self,
+        source: GetObjectRequest,
+        target: F,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.inner.download_to_file(source, target).await
+    }
+
+    async fn upload_from_file<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.inner.upload_from_file(source, target).await
+    }
+
+    async fn upload_from_file_multipart_with<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.inner
+            .upload_from_file_multipart_with(source, target, part_size)
+            .await
+    }
+
+    async fn download<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        if !is_cacheable(&source) {
+            return self.inner.download(source, target).await;
+        }
+        let location = (
+            source.bucket.clone(),
+            source.key.clone(),
+            source.version_id.clone(),
+        );
+        let cached = self.state.lock().get(&location, self.ttl);
+        let cached = match cached {
+            Some((body, output, true)) => {
+                return write_cached(target, location, &body, output).await;
+            }
+            cached => cached,
+        };
+        let request = GetObjectRequest {
+            if_none_match: cached
+                .as_ref()
+                .and_then(|(_, output, _)| output.e_tag.clone()),
+            ..source
+        };
+        let mut tee = Tee {
+            target: &mut *target,
+            copy: Some(Vec::new()),
+            limit: self.max_bytes,
+        };
+        match self.inner.download(request, &mut tee).await {
+            Ok(output) => {
+                if let (Some(body), Some(e_tag)) = (tee.copy, output.e_tag.clone()) {
+                    self.insert(location, e_tag, body, without_body(&output));
+                }
+                Ok(output)
+            }
+            Err(e) => match cached {
+                Some((body, output, _)) if e.is_not_modified() => {
+                    self.state.lock().validated(&location);
+                    write_cached(target, location, &body, output).await
+                }
+                _ => Err(e),
+            },
+        }
+    }
+
+    async fn download_decompressed<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        // decompressed bodies aren't cached
+        self.inner.download_decompressed(source, target).await
+    }
+
+    async fn get_framed<D>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        decoder: D,
+    ) -> S3ExtResult<FramedObject<D>>
+    where
+        D: Decoder + Send,
+    {
+        self.inner.get_framed(bucket, key, decoder).await
+    }
+
+    async fn get_body_with(&self, source: GetObjectRequest) -> S3ExtResult<ObjectBody> {
+        // bodies are streamed, not cached
+        self.inner.get_body_with(source).await
+    }
+
+    async fn upload<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.inner.upload(source, target).await
+    }
+
+    async fn upload_multipart_with<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.inner
+            .upload_multipart_with(source, target, part_size)
+            .await
+    }
+
+    async fn upload_multipart_resumable<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.inner
+            .upload_multipart_resumable(source, target, part_size)
+            .await
+    }
+
+    #[inline]
+    fn stream_objects(&self, bucket: impl Into<String>) -> ObjectStream {
+        self.inner.stream_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> ObjectStream {
+        self.inner.stream_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_objects_sorted_by_mtime(&self, bucket: impl Into<String>) -> SortedObjectStream {
+        self.inner.stream_objects_sorted_by_mtime(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix_sorted_by_mtime(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> SortedObjectStream {
+        self.inner
+            .stream_objects_with_prefix_sorted_by_mtime(bucket, prefix)
+    }
+
+    #[inline]
+    fn diff(
+        &self,
+        source_bucket: impl Into<String>,
+        source_prefix: impl Into<String>,
+        dest_bucket: impl Into<String>,
+        dest_prefix: impl Into<String>,
+    ) -> DiffStream {
+        self.inner
+            .diff(source_bucket, source_prefix, dest_bucket, dest_prefix)
+    }
+
+    #[inline]
+    fn watch_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        period: Duration,
+    ) -> WatchStream {
+        self.inner.watch_prefix(bucket, prefix, period)
+    }
+
+    #[inline]
+    fn stream_get_objects(&self, bucket: impl Into<String>) -> GetObjectStream {
+        self.inner.stream_get_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_get_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> GetObjectStream {
+        self.inner.stream_get_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_lines(&self, bucket: impl Into<String>, key: impl Into<String>) -> LineStream {
+        self.inner.stream_lines(bucket, key)
+    }
+
+    async fn delete(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.inner.delete(bucket, key).await
+    }
+
+    async fn compose_object(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        parts: Vec<ComposePart>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput> {
+        self.inner.compose_object(bucket, key, parts).await
+    }
+
+    async fn object_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectMetadata> {
+        self.inner.object_metadata(bucket, key).await
+    }
+
+    async fn replace_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        metadata: ObjectMetadata,
+    ) -> S3ExtResult<()> {
+        self.inner.replace_metadata(bucket, key, metadata).await
+    }
+
+    async fn copy(
+        &self,
+        source_bucket: impl Into<String> + Send,
+        source_key: impl Into<String> + Send,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.inner
+            .copy(source_bucket, source_key, bucket, key)
+            .await
+    }
+}
@@ -0,0 +1,144 @@
+//! Content-addressable storage
+//!
+//! Objects are named by the SHA-256 digest of their content, below a prefix.
+//! Storing the same content twice uploads it once, and content read back is
+//! verified against its name. See
+//! [`S3Ext::put_content`](crate::S3Ext::put_content) and
+//! [`S3Ext::get_content`](crate::S3Ext::get_content).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::S3Ext;
+//!
+//! let hash = client
+//!     .put_content("artifacts", "sha256/", b"build output")
+//!     .await?;
+//! println!("stored as sha256/{}", hash);
+//! let content = client.get_content("artifacts", "sha256/", &hash).await?;
+//! assert_eq!(content, b"build output");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::{S3ExtError, S3ExtResult},
+    S3Ext,
+};
+use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+use sha2::{Digest, Sha256};
+use std::{fmt, path::Path, str::FromStr};
+use tokio::{fs::File, io::AsyncReadExt};
+
+/// SHA-256 digest of the content of an object, naming it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ContentHashKey([u8; 32]);
+
+impl ContentHashKey {
+    /// Digest of `content`
+    pub fn of(content: &[u8]) -> Self {
+        let mut hash = [0; 32];
+        hash.copy_from_slice(&Sha256::digest(content));
+        ContentHashKey(hash)
+    }
+
+    /// Digest of the content of the file at `path`, read in chunks
+    pub async fn of_file(path: impl AsRef<Path>) -> S3ExtResult<Self> {
+        let mut file = File::open(path).await?;
+        let mut sha256 = Sha256::new();
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            match file.read(&mut buf).await? {
+                0 => break,
+                n => sha256.update(&buf[..n]),
+            }
+        }
+        let mut hash = [0; 32];
+        hash.copy_from_slice(&sha256.finalize());
+        Ok(ContentHashKey(hash))
+    }
+
+    /// Digest as bytes
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Key of the object below `prefix`, the prefix followed by the hex
+    /// encoded digest
+    pub fn key(&self, prefix: &str) -> String {
+        format!("{}{}", prefix, self)
+    }
+}
+
+impl fmt::Display for ContentHashKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+impl FromStr for ContentHashKey {
+    type Err = S3ExtError;
+
+    /// Parse a hex encoded digest
+    fn from_str(s: &str) -> S3ExtResult<Self> {
+        let mut hash = [0; 32];
+        hex::decode_to_slice(s, &mut hash)
+            .map_err(|_| S3ExtError::Other("invalid SHA-256 digest"))?;
+        Ok(ContentHashKey(hash))
+    }
+}
+
+pub(crate) async fn put_content<C>(
+    client: &C,
+    bucket: String,
+    prefix: String,
+    content: &[u8],
+) -> S3ExtResult<ContentHashKey>
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    let hash = ContentHashKey::of(content);
+    let key = hash.key(&prefix);
+    match client.object_attributes(bucket.clone(), key.clone()).await {
+        Ok(_) => return Ok(hash),
+        Err(e) if e.is_not_found() => (),
+        Err(e) => return Err(e),
+    }
+    let request = PutObjectRequest {
+        bucket,
+        key,
+        content_length: Some(content.len() as i64),
+        ..Default::default()
+    };
+    client.upload(&mut &content[..], request).await?;
+    Ok(hash)
+}
+
+pub(crate) async fn get_content<C>(
+    client: &C,
+    bucket: String,
+    prefix: String,
+    hash: &ContentHashKey,
+) -> S3ExtResult<Vec<u8>>
+where
+    C: S3Ext + ?Sized,
+{
+    let key = hash.key(&prefix);
+    let request = GetObjectRequest {
+        bucket: bucket.clone(),
+        key: key.clone(),
+        ..Default::default()
+    };
+    let mut content = Vec::new();
+    client.download(request, &mut content).await?;
+    let actual = ContentHashKey::of(&content);
+    if actual != *hash {
+        return Err(S3ExtError::ChecksumMismatch {
+            expected: hash.to_string(),
+            actual: actual.to_string(),
+        }
+        .with_object(bucket, key));
+    }
+    Ok(content)
+}
@@ -0,0 +1,259 @@
+//! Record and replay HTTP interactions with S3
+//!
+//! Tests of code built on `S3Ext` can record their interactions with a real
+//! S3 or MinIO once, using
+//! [`S3ExtClientBuilder::record`](crate::client::S3ExtClientBuilder::record),
+//! and replay them afterwards without network access, using
+//! [`S3ExtClientBuilder::replay`](crate::client::S3ExtClientBuilder::replay).
+//!
+//! Cassettes are JSON files listing the interactions in the order they
+//! occurred. During replay, requests are matched to the first unused
+//! interaction with the same method, path and query parameters; headers and
+//! bodies of requests are ignored.
+//!
+//! Requires the `cassette` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use s3_ext::client::S3ExtClientBuilder;
+//!
+//! # fn main() -> Result<(), s3_ext::error::S3ExtError> {
+//! let builder = S3ExtClientBuilder::new().endpoint("eu-west-1", "http://localhost:9000");
+//! let client = if std::env::var_os("RECORD").is_some() {
+//!     builder.record("tests/cassettes/upload.json").build()?
+//! } else {
+//!     builder.replay("tests/cassettes/upload.json").build()?
+//! };
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{S3ExtError, S3ExtResult};
+use futures::stream::TryStreamExt;
+use hyper::{header::HeaderName, HeaderMap, StatusCode};
+use log::debug;
+use parking_lot::Mutex;
+use rusoto_core::{
+    request::{DispatchSignedRequestFuture, HttpResponse},
+    signature::SignedRequest,
+    ByteStream, DispatchSignedRequest, HttpDispatchError,
+};
+use serde_json::{json, Map, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Recorded request and response
+#[derive(Debug, Clone)]
+struct Interaction {
+    method: String,
+    path: String,
+    query: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Interaction {
+    fn matches(&self, request: &SignedRequest) -> bool {
+        self.method == request.method && self.path == request.path && self.query == query(request)
+    }
+
+    fn to_json(&self) -> Value {
+        let headers: Map<String, Value> = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), Value::from(value.as_str())))
+            .collect();
+        json!({
+            "method": self.method,
+            "path": self.path,
+            "query": self.query,
+            "status": self.status,
+            "headers": headers,
+            "body": base64::encode(&self.body),
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let string = |field: &str| value.get(field)?.as_str().map(str::to_owned);
+        Some(Self {
+            method: string("method")?,
+            path: string("path")?,
+            query: string("query")?,
+            status: value.get("status")?.as_u64()? as u16,
+            headers: value
+                .get("headers")?
+                .as_object()?
+                .iter()
+                .map(|(name, value)| Some((name.clone(), value.as_str()?.to_owned())))
+                .collect::<Option<_>>()?,
+            body: base64::decode(string("body")?).ok()?,
+        })
+    }
+
+    fn to_response(&self) -> Result<HttpResponse, HttpDispatchError> {
+        let mut headers = HeaderMap::<String>::default();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| HttpDispatchError::new(format!("invalid header {}", name)))?;
+            headers.append(name, value.clone());
+        }
+        Ok(HttpResponse {
+            status: StatusCode::from_u16(self.status)
+                .map_err(|_| HttpDispatchError::new(format!("invalid status {}", self.status)))?,
+            body: ByteStream::from(self.body.clone()),
+            headers,
+        })
+    }
+}
+
+// Query parameters in a canonical order
+fn query(request: &SignedRequest) -> String {
+    request
+        .params
+        .iter()
+        .map(|(name, value)| match value {
+            Some(value) => format!("{}={}", name, value),
+            None => name.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn describe(request: &SignedRequest) -> String {
+    format!("{} {}?{}", request.method, request.path, query(request))
+}
+
+/// HTTP client recording interactions to a cassette
+pub(crate) struct RecordingHttpClient {
+    inner: Arc<dyn DispatchSignedRequest + Send + Sync>,
+    path: PathBuf,
+    interactions: Arc<Mutex<Vec<Interaction>>>,
+}
+
+impl RecordingHttpClient {
+    pub(crate) fn new(
+        inner: Arc<dyn DispatchSignedRequest + Send + Sync>,
+        path: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+            interactions: Arc::default(),
+        }
+    }
+}
+
+async fn record(
+    inner: Arc<dyn DispatchSignedRequest + Send + Sync>,
+    path: PathBuf,
+    interactions: Arc<Mutex<Vec<Interaction>>>,
+    request: SignedRequest,
+    timeout: Option<Duration>,
+) -> Result<HttpResponse, HttpDispatchError> {
+    let (method, request_path, query) = (
+        request.method.clone(),
+        request.path.clone(),
+        query(&request),
+    );
+    let response = inner.dispatch(request, timeout).await?;
+    let body = response
+        .body
+        .map_ok(|chunk| chunk.to_vec())
+        .try_concat()
+        .await
+        .map_err(|e| HttpDispatchError::new(format!("failed to read response body: {}", e)))?;
+    let interaction = Interaction {
+        method,
+        path: request_path,
+        query,
+        status: response.status.as_u16(),
+        headers: response
+            .headers
+            .iter()
+            .map(|(name, value)| (name.as_str().to_owned(), value.clone()))
+            .collect(),
+        body,
+    };
+    let response = interaction.to_response()?;
+    let cassette = {
+        let mut interactions = interactions.lock();
+        interactions.push(interaction);
+        Value::Array(interactions.iter().map(Interaction::to_json).collect())
+    };
+    debug!("recording interaction to {}", path.display());
+    let contents = serde_json::to_vec_pretty(&cassette)
+        .map_err(|e| HttpDispatchError::new(format!("failed to encode cassette: {}", e)))?;
+    fs::write(&path, contents)
+        .map_err(|e| HttpDispatchError::new(format!("failed to write cassette: {}", e)))?;
+    Ok(response)
+}
+
+impl DispatchSignedRequest for RecordingHttpClient {
+    fn dispatch(
+        &self,
+        request: SignedRequest,
+        timeout: Option<Duration>,
+    ) -> DispatchSignedRequestFuture {
+        Box::pin(record(
+            self.inner.clone(),
+            self.path.clone(),
+            self.interactions.clone(),
+            request,
+            timeout,
+        ))
+    }
+}
+
+/// HTTP client serving responses from a cassette
+pub(crate) struct ReplayingHttpClient {
+    interactions: Mutex<Vec<Option<Interaction>>>,
+}
+
+impl ReplayingHttpClient {
+    /// Load the cassette at `path`
+    pub(crate) fn load(path: &Path) -> S3ExtResult<Self> {
+        let contents = fs::read(path)?;
+        let cassette: Value =
+            serde_json::from_slice(&contents).map_err(|_| S3ExtError::Other("invalid cassette"))?;
+        let interactions = cassette
+            .as_array()
+            .ok_or(S3ExtError::Other("invalid cassette"))?
+            .iter()
+            .map(|value| Interaction::from_json(value).map(Some))
+            .collect::<Option<_>>()
+            .ok_or(S3ExtError::Other("invalid cassette"))?;
+        Ok(Self {
+            interactions: Mutex::new(interactions),
+        })
+    }
+
+    fn replay(&self, request: &SignedRequest) -> Result<HttpResponse, HttpDispatchError> {
+        let mut interactions = self.interactions.lock();
+        let interaction = interactions
+            .iter_mut()
+            .find(|i| i.as_ref().is_some_and(|i| i.matches(request)))
+            .and_then(Option::take)
+            .ok_or_else(|| {
+                HttpDispatchError::new(format!("no recorded interaction for {}", describe(request)))
+            })?;
+        debug!("replaying {}", describe(request));
+        interaction.to_response()
+    }
+}
+
+impl DispatchSignedRequest for ReplayingHttpClient {
+    fn dispatch(
+        &self,
+        request: SignedRequest,
+        _timeout: Option<Duration>,
+    ) -> DispatchSignedRequestFuture {
+        let response = self.replay(&request);
+        Box::pin(async move { response })
+    }
+}
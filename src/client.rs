@@ -0,0 +1,512 @@
+//! Construction of configured `S3Client`s
+//!
+//! # Example
+//!
+//! ```
+//! use s3_ext::client::{Credentials, S3ExtClientBuilder};
+//! use std::time::Duration;
+//!
+//! # fn main() -> Result<(), s3_ext::error::S3ExtError> {
+//! let client = S3ExtClientBuilder::new()
+//!     .endpoint("eu-west-1", "http://localhost:9000")
+//!     .credentials(Credentials::Static {
+//!         access_key: "ANTN35UAENTS5UIAEATD".to_owned(),
+//!         secret_key: "TtnuieannGt2rGuie2t8Tt7urarg5nauedRndrur".to_owned(),
+//!         token: None,
+//!     })
+//!     .connect_timeout(Duration::from_secs(5))
+//!     .request_timeout(Duration::from_secs(30))
+//!     .max_retries(3)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "cassette")]
+use crate::cassette::{RecordingHttpClient, ReplayingHttpClient};
+use crate::{
+    check_web_identity_env,
+    cost::{CostTracker, CostTrackingHttpClient},
+    error::{S3ExtError, S3ExtResult},
+    fault::{FaultInjector, FaultyHttpClient},
+    rate_limit::{RateLimitedHttpClient, RateLimiter},
+    select::SelectClient,
+};
+use hyper::client::{connect::Connect, HttpConnector};
+use hyper_rustls::HttpsConnectorBuilder;
+use log::debug;
+use rusoto_core::{
+    request::{DispatchSignedRequestFuture, HttpResponse},
+    signature::{SignedRequest, SignedRequestPayload},
+    Client, DispatchSignedRequest, HttpClient, HttpDispatchError, Region,
+};
+use rusoto_credential::{
+    AutoRefreshingProvider, DefaultCredentialsProvider, EnvironmentProvider, ProfileProvider,
+    StaticProvider,
+};
+use rusoto_s3::S3Client;
+use rusoto_sts::{StsAssumeRoleSessionCredentialsProvider, StsClient, WebIdentityProvider};
+#[cfg(feature = "cassette")]
+use std::path::PathBuf;
+use std::{fmt, sync::Arc, time::Duration};
+
+/// Source of the credentials used to sign requests
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Credentials {
+    /// Fixed access key, secret key and optional session token
+    Static {
+        /// Access key ID
+        access_key: String,
+        /// Secret access key
+        secret_key: String,
+        /// Session token of temporary credentials
+        token: Option<String>,
+    },
+    /// `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`
+    Environment,
+    /// Profile from the shared credentials file (`~/.aws/credentials`),
+    /// `None` selects the profile named by `AWS_PROFILE` or `default`
+    Profile(Option<String>),
+    /// Environment, profile, container and instance metadata, in that order
+    #[default]
+    Chain,
+    /// Temporary credentials obtained by assuming `role_arn` using the
+    /// [`Chain`](Self::Chain) credentials, refreshed automatically
+    AssumeRole {
+        /// ARN of the role to assume
+        role_arn: String,
+        /// Identifier of the session, shows up in CloudTrail logs
+        session_name: String,
+    },
+    /// Don't sign requests, for access to public buckets
+    Anonymous,
+    /// Temporary credentials obtained by exchanging the web identity token
+    /// found in `AWS_WEB_IDENTITY_TOKEN_FILE` for credentials of the role
+    /// `AWS_ROLE_ARN` (EKS IRSA, GitHub OIDC), refreshed automatically
+    WebIdentity,
+}
+
+/// Builder for `S3Client`s
+///
+/// Without further configuration, the region is taken from the environment
+/// (see `Region::default()`), credentials are looked up using
+/// [`Credentials::Chain`] and requests neither time out nor are retried.
+///
+/// Rusoto addresses buckets using path-style requests
+/// (`https://host/bucket/key`), which S3-compatible servers without wildcard
+/// DNS records and bucket names containing dots require.
+#[derive(Debug, Clone, Default)]
+pub struct S3ExtClientBuilder {
+    region: Region,
+    credentials: Credentials,
+    request_timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    max_retries: u32,
+    fips: bool,
+    sts_region: Option<Region>,
+    http_client: Option<SharedHttpClient>,
+    faults: Option<FaultInjector>,
+    rate_limit: Option<RateLimiter>,
+    cost_tracker: Option<CostTracker>,
+    #[cfg(feature = "cassette")]
+    cassette: Option<Cassette>,
+}
+
+impl S3ExtClientBuilder {
+    /// Create a builder using the defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send requests to `region`
+    pub fn region(mut self, region: Region) -> Self {
+        self.region = region;
+        self
+    }
+
+    /// Send requests to a custom `endpoint`, e.g. a MinIO server
+    ///
+    /// `name` is the region name used for signing requests.
+    pub fn endpoint(mut self, name: impl Into<String>, endpoint: impl Into<String>) -> Self {
+        self.region = Region::Custom {
+            name: name.into(),
+            endpoint: endpoint.into(),
+        };
+        self
+    }
+
+    /// Sign requests using `credentials`
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// Time allowed for receiving the response headers of a request
+    ///
+    /// The response body isn't covered by this timeout.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Time allowed for establishing a connection
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Retry requests failing due to connection errors or a `500`, `502`,
+    /// `503` or `504` response up to `max_retries` times
+    ///
+    /// Requests with a streaming body, e.g. `upload_part`, are never retried.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Send requests to the FIPS 140 validated endpoint of the region
+    ///
+    /// FIPS endpoints are only available in some US and Canadian regions
+    /// including GovCloud (see [`fips_region`]), [`build`](Self::build)
+    /// fails for other regions and custom endpoints.
+    pub fn fips(mut self, fips: bool) -> Self {
+        self.fips = fips;
+        self
+    }
+
+    /// Send the STS requests of [`Credentials::AssumeRole`] to `region`
+    ///
+    /// Defaults to the region requests are sent to. For a custom endpoint,
+    /// the standard endpoint of the region named for signing is used, or
+    /// `us-east-1` if the name isn't an AWS region: S3-compatible servers
+    /// generally don't serve STS.
+    pub fn sts_region(mut self, region: Region) -> Self {
+        self.sts_region = Some(region);
+        self
+    }
+
+    /// Establish connections using `connector` rather than the default
+    /// HTTPS connector, e.g. to tunnel requests through a proxy
+    ///
+    /// The [`connect_timeout`](Self::connect_timeout) doesn't apply to custom
+    /// connectors, configure the timeout on `connector` itself.
+    pub fn connector<C>(mut self, connector: C) -> Self
+    where
+        C: Connect + Clone + Send + Sync + 'static,
+    {
+        self.http_client = Some(SharedHttpClient(Arc::new(HttpClient::from_connector(
+            connector,
+        ))));
+        self
+    }
+
+    /// Inject the faults configured on `faults` into requests, for testing
+    /// how applications cope with failures
+    ///
+    /// Faults are injected below the retries configured using
+    /// [`max_retries`](Self::max_retries).
+    pub fn fault_injection(mut self, faults: FaultInjector) -> Self {
+        self.faults = Some(faults);
+        self
+    }
+
+    /// Delay requests exceeding the limits configured on `limiter`
+    ///
+    /// Limits apply to every request sent, including retries. See
+    /// [`rate_limit`](crate::rate_limit).
+    pub fn rate_limit(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limit = Some(limiter);
+        self
+    }
+
+    /// Count requests and transferred bytes on `tracker`
+    ///
+    /// Every request sent is counted, including retries. See
+    /// [`cost`](crate::cost).
+    pub fn cost_tracker(mut self, tracker: CostTracker) -> Self {
+        self.cost_tracker = Some(tracker);
+        self
+    }
+
+    /// Record all interactions to the cassette at `path`, overwriting it
+    ///
+    /// See [`cassette`](crate::cassette) for details.
+    #[cfg(feature = "cassette")]
+    pub fn record(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cassette = Some(Cassette::Record(path.into()));
+        self
+    }
+
+    /// Serve all requests from the cassette at `path` rather than sending
+    /// them
+    ///
+    /// See [`cassette`](crate::cassette) for details. [`build`](Self::build)
+    /// fails if the cassette can't be read.
+    #[cfg(feature = "cassette")]
+    pub fn replay(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cassette = Some(Cassette::Replay(path.into()));
+        self
+    }
+
+    /// Create the client
+    pub fn build(self) -> S3ExtResult<S3Client> {
+        let (client, region) = self.build_core()?;
+        Ok(S3Client::new_with_client(client, region))
+    }
+
+    /// Create a client for S3 Select
+    ///
+    /// See [`SelectClient`] for why a separate client is needed.
+    pub fn build_select(self) -> S3ExtResult<SelectClient> {
+        let (client, region) = self.build_core()?;
+        Ok(SelectClient::new(client, region))
+    }
+
+    // Client signing and dispatching requests and the region to send them to
+    fn build_core(self) -> S3ExtResult<(Client, Region)> {
+        let dispatcher = self.dispatcher()?;
+        // only S3 requests go to the FIPS endpoint, STS keeps the region
+        let sts_region = self.get_sts_region();
+        let region = if self.fips {
+            fips_region(&self.region)
+                .ok_or(S3ExtError::Other("no FIPS endpoint available for region"))?
+        } else {
+            self.region.clone()
+        };
+        let client = match self.credentials {
+            Credentials::Static {
+                access_key,
+                secret_key,
+                token,
+            } => Client::new_with(
+                StaticProvider::new(access_key, secret_key, token, None),
+                dispatcher,
+            ),
+            Credentials::Environment => {
+                Client::new_with(EnvironmentProvider::default(), dispatcher)
+            }
+            Credentials::Profile(name) => {
+                let provider = match name {
+                    Some(name) => ProfileProvider::with_default_credentials(name)?,
+                    None => ProfileProvider::new()?,
+                };
+                Client::new_with(provider, dispatcher)
+            }
+            Credentials::Chain => Client::new_with(DefaultCredentialsProvider::new()?, dispatcher),
+            Credentials::AssumeRole {
+                role_arn,
+                session_name,
+            } => {
+                let sts = StsClient::new_with(
+                    dispatcher.clone(),
+                    DefaultCredentialsProvider::new()?,
+                    sts_region,
+                );
+                let provider = StsAssumeRoleSessionCredentialsProvider::new(
+                    sts,
+                    role_arn,
+                    session_name,
+                    None,
+                    None,
+                    None,
+                    None,
+                );
+                Client::new_with(AutoRefreshingProvider::new(provider)?, dispatcher)
+            }
+            Credentials::Anonymous => Client::new_not_signing(dispatcher),
+            Credentials::WebIdentity => {
+                check_web_identity_env()?;
+                let provider = WebIdentityProvider::from_k8s_env();
+                Client::new_with(AutoRefreshingProvider::new(provider)?, dispatcher)
+            }
+        };
+        Ok((client, region))
+    }
+
+    pub(crate) fn get_region(&self) -> &Region {
+        &self.region
+    }
+
+    fn get_sts_region(&self) -> Region {
+        match (&self.sts_region, &self.region) {
+            (Some(region), _) => region.clone(),
+            (None, Region::Custom { name, .. }) => name.parse().unwrap_or(Region::UsEast1),
+            (None, region) => region.clone(),
+        }
+    }
+
+    fn dispatcher(&self) -> S3ExtResult<Dispatcher> {
+        let mut inner = match &self.http_client {
+            Some(http_client) => http_client.clone(),
+            None => self.default_http_client(),
+        };
+        #[cfg(feature = "cassette")]
+        match &self.cassette {
+            Some(Cassette::Record(path)) => {
+                inner = SharedHttpClient(Arc::new(RecordingHttpClient::new(inner.0, path)));
+            }
+            Some(Cassette::Replay(path)) => {
+                inner = SharedHttpClient(Arc::new(ReplayingHttpClient::load(path)?));
+            }
+            None => {}
+        }
+        if let Some(faults) = &self.faults {
+            inner = SharedHttpClient(Arc::new(FaultyHttpClient {
+                inner: inner.0,
+                faults: faults.clone(),
+            }));
+        }
+        if let Some(limiter) = &self.rate_limit {
+            inner = SharedHttpClient(Arc::new(RateLimitedHttpClient {
+                inner: inner.0,
+                limiter: limiter.clone(),
+            }));
+        }
+        if let Some(tracker) = &self.cost_tracker {
+            inner = SharedHttpClient(Arc::new(CostTrackingHttpClient {
+                inner: inner.0,
+                tracker: tracker.clone(),
+            }));
+        }
+        Ok(Dispatcher {
+            inner,
+            timeout: self.request_timeout,
+            max_retries: self.max_retries,
+        })
+    }
+
+    fn default_http_client(&self) -> SharedHttpClient {
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_connect_timeout(self.connect_timeout);
+        let connector = HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http()
+            .enable_http1()
+            .enable_http2()
+            .wrap_connector(http);
+        SharedHttpClient(Arc::new(HttpClient::from_connector(connector)))
+    }
+}
+
+/// Cassette to record to or replay from
+#[cfg(feature = "cassette")]
+#[derive(Debug, Clone)]
+enum Cassette {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// `HttpClient` with its connector type erased
+#[derive(Clone)]
+struct SharedHttpClient(Arc<dyn DispatchSignedRequest + Send + Sync>);
+
+impl fmt::Debug for SharedHttpClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("HttpClient")
+    }
+}
+
+/// FIPS 140 validated counterpart of `region`, `None` if S3 doesn't offer a
+/// FIPS endpoint in `region`
+pub fn fips_region(region: &Region) -> Option<Region> {
+    match region {
+        Region::UsEast1
+        | Region::UsEast2
+        | Region::UsWest1
+        | Region::UsWest2
+        | Region::CaCentral1
+        | Region::UsGovEast1
+        | Region::UsGovWest1 => Some(Region::Custom {
+            name: region.name().to_owned(),
+            endpoint: format!("https://s3-fips.{}.amazonaws.com", region.name()),
+        }),
+        _ => None,
+    }
+}
+
+/// Dispatcher applying timeouts and retries
+#[derive(Clone)]
+struct Dispatcher {
+    inner: SharedHttpClient,
+    timeout: Option<Duration>,
+    max_retries: u32,
+}
+
+impl Dispatcher {
+    async fn dispatch_with_retries(
+        inner: SharedHttpClient,
+        request: SignedRequest,
+        timeout: Option<Duration>,
+        max_retries: u32,
+    ) -> Result<HttpResponse, HttpDispatchError> {
+        let mut request = request;
+        let mut attempt = 0;
+        loop {
+            let retry = if attempt < max_retries {
+                try_clone(&request)
+            } else {
+                None
+            };
+            let result = inner.0.dispatch(request, timeout).await;
+            let retry = match retry {
+                Some(retry) if is_retryable(&result) => retry,
+                _ => return result,
+            };
+            attempt += 1;
+            let delay = Duration::from_millis(100 * 2_u64.pow(attempt.min(10)));
+            debug!(
+                "retrying request (attempt {} of {}) in {:?}",
+                attempt, max_retries, delay
+            );
+            tokio::time::sleep(delay).await;
+            request = retry;
+        }
+    }
+}
+
+impl DispatchSignedRequest for Dispatcher {
+    fn dispatch(
+        &self,
+        request: SignedRequest,
+        timeout: Option<Duration>,
+    ) -> DispatchSignedRequestFuture {
+        Box::pin(Self::dispatch_with_retries(
+            self.inner.clone(),
+            request,
+            timeout.or(self.timeout),
+            self.max_retries,
+        ))
+    }
+}
+
+fn is_retryable(result: &Result<HttpResponse, HttpDispatchError>) -> bool {
+    match result {
+        Ok(response) => matches!(response.status.as_u16(), 500 | 502 | 503 | 504),
+        Err(_) => true,
+    }
+}
+
+// Requests with a streaming body can't be sent twice
+fn try_clone(request: &SignedRequest) -> Option<SignedRequest> {
+    let payload = match &request.payload {
+        None => None,
+        Some(SignedRequestPayload::Buffer(buffer)) => {
+            Some(SignedRequestPayload::Buffer(buffer.clone()))
+        }
+        Some(SignedRequestPayload::Stream(_)) => return None,
+    };
+    Some(SignedRequest {
+        method: request.method.clone(),
+        service: request.service.clone(),
+        region: request.region.clone(),
+        path: request.path.clone(),
+        headers: request.headers.clone(),
+        params: request.params.clone(),
+        scheme: request.scheme.clone(),
+        hostname: request.hostname.clone(),
+        payload,
+        canonical_query_string: request.canonical_query_string.clone(),
+        canonical_uri: request.canonical_uri.clone(),
+    })
+}
@@ -0,0 +1,424 @@
+//! Coalescing of concurrent identical downloads
+//!
+//! Services fanning out requests for the same hot object send many identical
+//! `GET` requests at once. A [`CoalescingS3Client`] sends only the first of
+//! concurrent downloads of the same bucket, key, version and range and
+//! hands its result, including failures, to all of them. Downloads starting
+//! after the request completed send a new one, nothing is cached.
+//!
+//! [`download`](crate::S3Ext::download) and the methods built on it, e.g.
+//! `get_json`, are coalesced. The shared body is buffered in memory.
+//! Requests for parts, with conditions, response header overrides or
+//! customer-provided keys (SSE-C) are sent as they are, as are all other
+//! operations. Errors shared by several downloads are returned as
+//! [`S3ExtError::Shared`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::future::try_join_all;
+//! use rusoto_core::Region;
+//! use rusoto_s3::{GetObjectRequest, S3Client};
+//! use s3_ext::{coalesce::CoalescingS3Client, S3Ext};
+//!
+//! # async fn example() -> Result<(), s3_ext::error::S3ExtError> {
+//! let client = CoalescingS3Client::new(S3Client::new(Region::EuWest1));
+//! let request = GetObjectRequest {
+//!     bucket: "bucket".to_owned(),
+//!     key: "config.json".to_owned(),
+//!     ..Default::default()
+//! };
+//! // a single request is sent
+//! let downloads = (0..100).map(|_| async {
+//!     let mut body = Vec::new();
+//!     client.download(request.clone(), &mut body).await.map(|_| body)
+//! });
+//! try_join_all(downloads).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    body::ObjectBody,
+    cache::without_body,
+    compose::ComposePart,
+    diff::DiffStream,
+    error::{S3ExtError, S3ExtResult},
+    framed::FramedObject,
+    iter::{GetObjectStream, ObjectStream, SortedObjectStream},
+    lines::LineStream,
+    metadata::ObjectMetadata,
+    part_size::PartSize,
+    watch::WatchStream,
+    S3Ext,
+};
+use async_trait::async_trait;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use parking_lot::Mutex;
+use rusoto_s3::{
+    CompleteMultipartUploadOutput, GetObjectOutput, GetObjectRequest, PutObjectOutput,
+    PutObjectRequest, S3Client,
+};
+use std::{
+    collections::HashMap, convert::AsRef, future::Future, path::Path, sync::Arc, time::Duration,
+};
+use tokio::io::{self, AsyncWriteExt};
+use tokio_util::codec::Decoder;
+
+// Bucket, key, version and range of a download
+type DownloadId = (String, String, Option<String>, Option<String>);
+
+// Body and output of a download, shared by the coalesced downloads
+type SharedDownload =
+    Shared<BoxFuture<'static, Result<Arc<(Vec<u8>, GetObjectOutput)>, Arc<S3ExtError>>>>;
+
+/// Client sending concurrent identical downloads once
+///
+/// See the [module documentation](self). Clones share the requests in
+/// flight.
+#[derive(Clone)]
+pub struct CoalescingS3Client<C = S3Client> {
+    inner: C,
+    in_flight: Arc<Mutex<HashMap<DownloadId, SharedDownload>>>,
+}
+
+impl<C> CoalescingS3Client<C> {
+    /// Wrap `inner`
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            in_flight: Arc::default(),
+        }
+    }
+
+    /// Wrapped client
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Number of downloads in flight
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.lock().len()
+    }
+}
+
+impl<C> CoalescingS3Client<C>
+where
+    C: S3Ext + Clone + Send + Sync + 'static,
+{
+    // Download in flight for `request`, started unless there is one
+    fn join(&self, request: GetObjectRequest) -> SharedDownload {
+        let id = (
+            request.bucket.clone(),
+            request.key.clone(),
+            request.version_id.clone(),
+            request.range.clone(),
+        );
+        let mut in_flight = self.in_flight.lock();
+        if let Some(download) = in_flight.get(&id) {
+            return download.clone();
+        }
+        let inner = self.inner.clone();
+        let requests = Arc::clone(&self.in_flight);
+        let key = id.clone();
+        let download = async move {
+            let mut body = Vec::new();
+            let result = inner.download(request, &mut body).await;
+            // later downloads send a new request
+            requests.lock().remove(&key);
+            match result {
+                Ok(output) => Ok(Arc::new((body, without_body(&output)))),
+                Err(e) => Err(Arc::new(e)),
+            }
+        }
+        .boxed()
+        .shared();
+        in_flight.insert(id, download.clone());
+        download
+    }
+}
+
+fn is_coalescable(request: &GetObjectRequest) -> bool {
+    request.part_number.is_none()
+        && request.if_match.is_none()
+        && request.if_none_match.is_none()
+        && request.if_modified_since.is_none()
+        && request.if_unmodified_since.is_none()
+        && request.response_cache_control.is_none()
+        && request.response_content_disposition.is_none()
+        && request.response_content_encoding.is_none()
+        && request.response_content_language.is_none()
+        && request.response_content_type.is_none()
+        && request.response_expires.is_none()
+        && request.sse_customer_key.is_none()
+}
+
+#[async_trait]
+impl<C> S3Ext for CoalescingS3Client<C>
+where
+    C: S3Ext + Clone + Send + Sync + 'static,
+{
+    async fn send_request<T, F, Fut>(
+        &self,
+        operation: &'static str,
+        bucket: String,
+        key: String,
+        request: F,
+    ) -> S3ExtResult<T>
+    where
+        T: Send,
+        F: Fn(Backend, String, String) -> Fut + Send + Sync,
+        Fut: Future<Output = S3ExtResult<T>> + Send,
+    {
+        self.inner
+            .send_request(operation, bucket, key, request)
+            .await
+    }
+
+    async fn download_to_file<F>(
+        &self,
+        source: GetObjectRequest,
+        target: F,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.inner.download_to_file(source, target).await
+    }
+
+    async fn upload_from_file<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.inner.upload_from_file(source, target).await
+    }
+
+    async fn upload_from_file_multipart_with<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.inner
+            .upload_from_file_multipart_with(source, target, part_size)
+            .await
+    }
+
+    async fn download<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        if !is_coalescable(&source) {
+            return self.inner.download(source, target).await;
+        }
+        let (bucket, key) = (source.bucket.clone(), source.key.clone());
+        let download = self.join(source);
+        match download.await {
+            Ok(download) => {
+                let (body, output) = &*download;
+                target
+                    .write_all(body)
+                    .await
+                    .map_err(|e| S3ExtError::from(e).with_object(bucket, key))?;
+                Ok(without_body(output))
+            }
+            Err(e) => Err(Arc::try_unwrap(e).unwrap_or_else(S3ExtError::Shared)),
+        }
+    }
+
+    async fn download_decompressed<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        // decompressed bodies aren't shared
+        self.inner.download_decompressed(source, target).await
+    }
+
+    async fn get_framed<D>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        decoder: D,
+    ) -> S3ExtResult<FramedObject<D>>
+    where
+        D: Decoder + Send,
+    {
+        self.inner.get_framed(bucket, key, decoder).await
+    }
+
+    async fn get_body_with(&self, source: GetObjectRequest) -> S3ExtResult<ObjectBody> {
+        // streamed bodies can't be shared
+        self.inner.get_body_with(source).await
+    }
+
+    async fn upload<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.inner.upload(source, target).await
+    }
+
+    async fn upload_multipart_with<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.inner
+            .upload_multipart_with(source, target, part_size)
+            .await
+    }
+
+    async fn upload_multipart_resumable<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.inner
+            .upload_multipart_resumable(source, target, part_size)
+            .await
+    }
+
+    #[inline]
+    fn stream_objects(&self, bucket: impl Into<String>) -> ObjectStream {
+        self.inner.stream_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> ObjectStream {
+        self.inner.stream_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_objects_sorted_by_mtime(&self, bucket: impl Into<String>) -> SortedObjectStream {
+        self.inner.stream_objects_sorted_by_mtime(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix_sorted_by_mtime(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> SortedObjectStream {
+        self.inner
+            .stream_objects_with_prefix_sorted_by_mtime(bucket, prefix)
+    }
+
+    #[inline]
+    fn diff(
+        &self,
+        source_bucket: impl Into<String>,
+        source_prefix: impl Into<String>,
+        dest_bucket: impl Into<String>,
+        dest_prefix: impl Into<String>,
+    ) -> DiffStream {
+        self.inner
+            .diff(source_bucket, source_prefix, dest_bucket, dest_prefix)
+    }
+
+    #[inline]
+    fn watch_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        period: Duration,
+    ) -> WatchStream {
+        self.inner.watch_prefix(bucket, prefix, period)
+    }
+
+    #[inline]
+    fn stream_get_objects(&self, bucket: impl Into<String>) -> GetObjectStream {
+        self.inner.stream_get_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_get_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> GetObjectStream {
+        self.inner.stream_get_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_lines(&self, bucket: impl Into<String>, key: impl Into<String>) -> LineStream {
+        self.inner.stream_lines(bucket, key)
+    }
+
+    async fn delete(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.inner.delete(bucket, key).await
+    }
+
+    async fn compose_object(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        parts: Vec<ComposePart>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput> {
+        self.inner.compose_object(bucket, key, parts).await
+    }
+
+    async fn object_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectMetadata> {
+        self.inner.object_metadata(bucket, key).await
+    }
+
+    async fn replace_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        metadata: ObjectMetadata,
+    ) -> S3ExtResult<()> {
+        self.inner.replace_metadata(bucket, key, metadata).await
+    }
+
+    async fn copy(
+        &self,
+        source_bucket: impl Into<String> + Send,
+        source_key: impl Into<String> + Send,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.inner
+            .copy(source_bucket, source_key, bucket, key)
+            .await
+    }
+}
@@ -0,0 +1,254 @@
+//! Typed objects, serialized using a [`Codec`]
+//!
+//! See [`S3Ext::get_as`] and [`S3Ext::put_as`]. Codecs are available behind
+//! features:
+//!
+//! | Codec           | Feature      | Content type               |
+//! |-----------------|--------------|----------------------------|
+//! | [`Json`]        | `serde_json` | `application/json`         |
+//! | [`Cbor`]        | `cbor`       | `application/cbor`         |
+//! | [`MessagePack`] | `msgpack`    | `application/vnd.msgpack`  |
+//! | [`Bincode`]     | `bincode`    | `application/octet-stream` |
+//!
+//! # Example
+//!
+//! ```no_run
+//! # #[cfg(feature = "serde_json")]
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::{codec::Json, S3Ext};
+//! use std::collections::BTreeMap;
+//!
+//! let mut config = BTreeMap::new();
+//! config.insert("retries".to_owned(), 3);
+//! client.put_as::<_, Json>("bucket", "config.json", &config).await?;
+//! let config: BTreeMap<String, u32> = client.get_as::<_, Json>("bucket", "config.json").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::{S3ExtError, S3ExtResult},
+    S3Ext,
+};
+use rusoto_s3::{GetObjectOutput, GetObjectRequest, PutObjectOutput, PutObjectRequest};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::AsyncWrite;
+
+/// Largest object read or written, 16 MiB
+pub const MAX_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Serialization format of typed objects
+pub trait Codec {
+    /// Content type of uploaded objects
+    const CONTENT_TYPE: &'static str;
+
+    /// Serialize `value`
+    fn encode<T>(value: &T) -> S3ExtResult<Vec<u8>>
+    where
+        T: Serialize + ?Sized;
+
+    /// Deserialize a value from `bytes`
+    fn decode<T>(bytes: &[u8]) -> S3ExtResult<T>
+    where
+        T: DeserializeOwned;
+}
+
+/// JSON, using [`serde_json`](https://crates.io/crates/serde_json)
+#[cfg(feature = "serde_json")]
+#[derive(Debug, Clone, Copy)]
+pub struct Json;
+
+#[cfg(feature = "serde_json")]
+impl Codec for Json {
+    const CONTENT_TYPE: &'static str = "application/json";
+
+    fn encode<T>(value: &T) -> S3ExtResult<Vec<u8>>
+    where
+        T: Serialize + ?Sized,
+    {
+        Ok(serde_json::to_vec(value)?)
+    }
+
+    fn decode<T>(bytes: &[u8]) -> S3ExtResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+/// CBOR, using [`ciborium`](https://crates.io/crates/ciborium)
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, Copy)]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl Codec for Cbor {
+    const CONTENT_TYPE: &'static str = "application/cbor";
+
+    fn encode<T>(value: &T) -> S3ExtResult<Vec<u8>>
+    where
+        T: Serialize + ?Sized,
+    {
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(value, &mut bytes).map_err(|e| S3ExtError::Codec(e.into()))?;
+        Ok(bytes)
+    }
+
+    fn decode<T>(bytes: &[u8]) -> S3ExtResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        ciborium::de::from_reader(bytes).map_err(|e| S3ExtError::Codec(e.into()))
+    }
+}
+
+/// MessagePack, using [`rmp-serde`](https://crates.io/crates/rmp-serde)
+///
+/// Structs are encoded as maps, so fields can be added and reordered.
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Clone, Copy)]
+pub struct MessagePack;
+
+#[cfg(feature = "msgpack")]
+impl Codec for MessagePack {
+    const CONTENT_TYPE: &'static str = "application/vnd.msgpack";
+
+    fn encode<T>(value: &T) -> S3ExtResult<Vec<u8>>
+    where
+        T: Serialize + ?Sized,
+    {
+        rmp_serde::to_vec_named(value).map_err(|e| S3ExtError::Codec(e.into()))
+    }
+
+    fn decode<T>(bytes: &[u8]) -> S3ExtResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        rmp_serde::from_slice(bytes).map_err(|e| S3ExtError::Codec(e.into()))
+    }
+}
+
+/// Bincode, using [`bincode`](https://crates.io/crates/bincode)
+#[cfg(feature = "bincode")]
+#[derive(Debug, Clone, Copy)]
+pub struct Bincode;
+
+#[cfg(feature = "bincode")]
+impl Codec for Bincode {
+    const CONTENT_TYPE: &'static str = "application/octet-stream";
+
+    fn encode<T>(value: &T) -> S3ExtResult<Vec<u8>>
+    where
+        T: Serialize + ?Sized,
+    {
+        bincode::serialize(value).map_err(|e| S3ExtError::Codec(e))
+    }
+
+    fn decode<T>(bytes: &[u8]) -> S3ExtResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        bincode::deserialize(bytes).map_err(|e| S3ExtError::Codec(e))
+    }
+}
+
+// Download the object at `bucket`/`key`, refusing objects larger than
+// `MAX_SIZE`
+pub(crate) async fn download_limited<C>(
+    client: &C,
+    bucket: String,
+    key: String,
+) -> S3ExtResult<(GetObjectOutput, Vec<u8>)>
+where
+    C: S3Ext + ?Sized,
+{
+    let request = GetObjectRequest {
+        bucket: bucket.clone(),
+        key: key.clone(),
+        ..Default::default()
+    };
+    let mut target = Limited {
+        buf: Vec::new(),
+        limit: MAX_SIZE,
+        exceeded: false,
+    };
+    let result = client.download(request, &mut target).await;
+    if target.exceeded {
+        return Err(S3ExtError::TooLarge { limit: MAX_SIZE }.with_object(bucket, key));
+    }
+    Ok((result?, target.buf))
+}
+
+pub(crate) async fn get<C, T, K>(client: &C, bucket: String, key: String) -> S3ExtResult<T>
+where
+    C: S3Ext + ?Sized,
+    T: DeserializeOwned,
+    K: Codec,
+{
+    let (_, bytes) = download_limited(client, bucket.clone(), key.clone()).await?;
+    K::decode(&bytes).map_err(|e| e.with_object(bucket, key))
+}
+
+pub(crate) async fn put<C, T, K>(
+    client: &C,
+    bucket: String,
+    key: String,
+    value: &T,
+) -> S3ExtResult<PutObjectOutput>
+where
+    C: S3Ext + ?Sized,
+    T: Serialize + ?Sized,
+    K: Codec,
+{
+    let body = match K::encode(value) {
+        Ok(body) if body.len() as u64 > MAX_SIZE => Err(S3ExtError::TooLarge { limit: MAX_SIZE }),
+        result => result,
+    }
+    .map_err(|e| e.with_object(bucket.clone(), key.clone()))?;
+    let request = PutObjectRequest {
+        bucket,
+        key,
+        content_type: Some(K::CONTENT_TYPE.to_owned()),
+        ..Default::default()
+    };
+    client.upload(&mut body.as_slice(), request).await
+}
+
+// Buffer refusing writes beyond `limit`
+struct Limited {
+    buf: Vec<u8>,
+    limit: u64,
+    exceeded: bool,
+}
+
+impl AsyncWrite for Limited {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if (self.buf.len() + buf.len()) as u64 > self.limit {
+            self.exceeded = true;
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "object exceeds size limit",
+            )));
+        }
+        self.buf.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,633 @@
+//! Compatibility profiles for S3-compatible storage providers
+//!
+//! Most providers implement a subset of the S3 API and some of them reject
+//! parameters they don't know about instead of ignoring them. A
+//! [`Compatibility`] profile describes what a provider supports so that
+//! requests can be adjusted before they are sent.
+//!
+//! [`CompatibleS3Client`] applies a profile to the requests of the upload
+//! and download helpers and to listings, and falls back to `HEAD` requests
+//! for the part layout of objects where `GetObjectAttributes` is missing.
+//!
+//! Rusoto predates flexible checksums and never sends `x-amz-checksum-*`
+//! headers. The official AWS SDK computes them for uploads by default, use
+//! [`Compatibility::configure_sdk`] when building its client for providers
+//! rejecting them.
+//!
+//! # Example
+//!
+//! ```
+//! use rusoto_core::Region;
+//! use s3_ext::compat::{Compatibility, Provider};
+//!
+//! let region = Region::Custom {
+//!     name: "auto".to_owned(),
+//!     endpoint: "https://0123456789.r2.cloudflarestorage.com".to_owned(),
+//! };
+//! let compat = Compatibility::detect(&region);
+//! assert_eq!(compat.provider, Provider::CloudflareR2);
+//! assert!(!compat.object_lock);
+//! ```
+//!
+//! Wrapping a client:
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client, region: rusoto_core::Region) -> Result<(), s3_ext::error::S3ExtError> {
+//! use rusoto_s3::PutObjectRequest;
+//! use s3_ext::{
+//!     compat::{Compatibility, CompatibleS3Client},
+//!     S3Ext,
+//! };
+//!
+//! let client = CompatibleS3Client::new(client, Compatibility::detect(&region));
+//! let request = PutObjectRequest {
+//!     bucket: "bucket".to_owned(),
+//!     key: "report.csv".to_owned(),
+//!     // dropped if the provider doesn't support tagging
+//!     tagging: Some("project=travel".to_owned()),
+//!     ..Default::default()
+//! };
+//! client.upload_from_file("report.csv", request).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    attributes::{self, ObjectAttributes},
+    backend::Backend,
+    body::ObjectBody,
+    compose::ComposePart,
+    diff::DiffStream,
+    error::S3ExtResult,
+    framed::FramedObject,
+    iter::{GetObjectStream, ObjectStream, SortedObjectStream},
+    lines::LineStream,
+    metadata::ObjectMetadata,
+    part_size::PartSize,
+    watch::WatchStream,
+    S3Ext,
+};
+use async_trait::async_trait;
+#[cfg(feature = "aws-sdk")]
+use aws_sdk_s3::config::{
+    Builder as SdkConfigBuilder, RequestChecksumCalculation, ResponseChecksumValidation,
+};
+use rusoto_core::Region;
+use rusoto_s3::{
+    CompleteMultipartUploadOutput, GetObjectOutput, GetObjectRequest, PutObjectOutput,
+    PutObjectRequest, S3Client,
+};
+use std::{convert::AsRef, future::Future, path::Path, time::Duration};
+use tokio::io;
+use tokio_util::codec::Decoder;
+
+/// Known S3 implementations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    /// Amazon S3
+    Aws,
+    /// MinIO
+    Minio,
+    /// Cloudflare R2
+    CloudflareR2,
+    /// Backblaze B2 (S3-compatible API)
+    BackblazeB2,
+    /// Google Cloud Storage (XML / interoperability API)
+    Gcs,
+    /// Any other S3-compatible service
+    Other,
+}
+
+impl Provider {
+    /// Guess the provider from the endpoint of `region`
+    pub fn detect(region: &Region) -> Self {
+        let endpoint = match region {
+            Region::Custom { endpoint, .. } => endpoint.to_lowercase(),
+            _ => return Provider::Aws,
+        };
+        if endpoint.contains("amazonaws.com") {
+            Provider::Aws
+        } else if endpoint.contains("r2.cloudflarestorage.com") {
+            Provider::CloudflareR2
+        } else if endpoint.contains("backblazeb2.com") {
+            Provider::BackblazeB2
+        } else if endpoint.contains("storage.googleapis.com") {
+            Provider::Gcs
+        } else if endpoint.contains("minio")
+            || endpoint.contains("localhost")
+            || endpoint.contains("127.0.0.1")
+            || endpoint.ends_with(":9000")
+        {
+            Provider::Minio
+        } else {
+            Provider::Other
+        }
+    }
+}
+
+/// Features of the S3 API supported by a provider
+///
+/// Use [`Compatibility::for_provider`] or [`Compatibility::detect`] to obtain
+/// a preset, individual fields can be adjusted afterwards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compatibility {
+    /// Provider this profile was created for
+    pub provider: Provider,
+    /// Maximum number of keys requested per `ListObjectsV2` call
+    pub max_keys: i64,
+    /// Continue a truncated listing using `start_after` if the provider does
+    /// not return a continuation token
+    pub start_after_fallback: bool,
+    /// Object lock parameters are accepted
+    pub object_lock: bool,
+    /// SSE-KMS parameters (key id, encryption context, bucket key) are
+    /// accepted
+    pub sse_kms: bool,
+    /// SSE-C parameters are accepted
+    pub sse_customer: bool,
+    /// The `x-amz-request-payer` header is accepted
+    pub request_payer: bool,
+    /// The `x-amz-expected-bucket-owner` header is accepted
+    pub expected_bucket_owner: bool,
+    /// Object tagging is supported
+    pub tagging: bool,
+    /// Canned and explicit ACLs are supported
+    pub acl: bool,
+    /// `GetObjectAttributes` is supported, it's used for the part layout of
+    /// multipart objects instead of a second `HEAD` request where possible
+    pub get_object_attributes: bool,
+    /// Flexible checksums (`x-amz-checksum-*` and
+    /// `x-amz-sdk-checksum-algorithm` headers) are accepted
+    pub checksums: bool,
+}
+
+impl Default for Compatibility {
+    fn default() -> Self {
+        Self::for_provider(Provider::Aws)
+    }
+}
+
+impl Compatibility {
+    /// Preset for the given `provider`
+    pub fn for_provider(provider: Provider) -> Self {
+        let aws = Self {
+            provider,
+            max_keys: 1000,
+            start_after_fallback: false,
+            object_lock: true,
+            sse_kms: true,
+            sse_customer: true,
+            request_payer: true,
+            expected_bucket_owner: true,
+            tagging: true,
+            acl: true,
+            get_object_attributes: true,
+            checksums: true,
+        };
+        match provider {
+            Provider::Aws => aws,
+            Provider::Minio => Self {
+                request_payer: false,
+                expected_bucket_owner: false,
+                ..aws
+            },
+            Provider::CloudflareR2 => Self {
+                object_lock: false,
+                sse_kms: false,
+                request_payer: false,
+                expected_bucket_owner: false,
+                tagging: false,
+                acl: false,
+                get_object_attributes: false,
+                checksums: false,
+                ..aws
+            },
+            Provider::BackblazeB2 => Self {
+                sse_kms: false,
+                request_payer: false,
+                expected_bucket_owner: false,
+                tagging: false,
+                acl: false,
+                get_object_attributes: false,
+                checksums: false,
+                ..aws
+            },
+            Provider::Gcs => Self {
+                start_after_fallback: true,
+                object_lock: false,
+                sse_kms: false,
+                request_payer: false,
+                expected_bucket_owner: false,
+                tagging: false,
+                get_object_attributes: false,
+                checksums: false,
+                ..aws
+            },
+            Provider::Other => Self {
+                start_after_fallback: true,
+                object_lock: false,
+                sse_kms: false,
+                request_payer: false,
+                expected_bucket_owner: false,
+                get_object_attributes: false,
+                checksums: false,
+                ..aws
+            },
+        }
+    }
+
+    /// Preset for the provider detected from the endpoint of `region`
+    pub fn detect(region: &Region) -> Self {
+        Self::for_provider(Provider::detect(region))
+    }
+
+    /// Adjust the configuration of an AWS SDK client to the provider
+    ///
+    /// Checksums are only computed and validated for operations requiring
+    /// them if the provider doesn't accept flexible checksums.
+    #[cfg(feature = "aws-sdk")]
+    pub fn configure_sdk(&self, config: SdkConfigBuilder) -> SdkConfigBuilder {
+        if self.checksums {
+            return config;
+        }
+        config
+            .request_checksum_calculation(RequestChecksumCalculation::WhenRequired)
+            .response_checksum_validation(ResponseChecksumValidation::WhenRequired)
+    }
+
+    /// Remove parameters from `request` the provider does not support
+    pub fn sanitize_put_request(&self, request: &mut PutObjectRequest) {
+        if !self.object_lock {
+            request.object_lock_legal_hold_status = None;
+            request.object_lock_mode = None;
+            request.object_lock_retain_until_date = None;
+        }
+        if !self.sse_kms {
+            if request.server_side_encryption.as_deref() == Some("aws:kms") {
+                request.server_side_encryption = None;
+            }
+            request.ssekms_key_id = None;
+            request.ssekms_encryption_context = None;
+            request.bucket_key_enabled = None;
+        }
+        if !self.sse_customer {
+            request.sse_customer_algorithm = None;
+            request.sse_customer_key = None;
+            request.sse_customer_key_md5 = None;
+        }
+        if !self.request_payer {
+            request.request_payer = None;
+        }
+        if !self.expected_bucket_owner {
+            request.expected_bucket_owner = None;
+        }
+        if !self.tagging {
+            request.tagging = None;
+        }
+        if !self.acl {
+            request.acl = None;
+            request.grant_full_control = None;
+            request.grant_read = None;
+            request.grant_read_acp = None;
+            request.grant_write_acp = None;
+        }
+    }
+
+    /// Remove parameters from `request` the provider does not support
+    pub fn sanitize_get_request(&self, request: &mut GetObjectRequest) {
+        if !self.sse_customer {
+            request.sse_customer_algorithm = None;
+            request.sse_customer_key = None;
+            request.sse_customer_key_md5 = None;
+        }
+        if !self.request_payer {
+            request.request_payer = None;
+        }
+        if !self.expected_bucket_owner {
+            request.expected_bucket_owner = None;
+        }
+    }
+}
+
+/// Client adjusting requests to the quirks of a provider
+///
+/// Parameters the provider doesn't support are removed from the requests of
+/// the upload and download methods, listings, including those behind
+/// [`diff`](S3Ext::diff) and [`watch_prefix`](S3Ext::watch_prefix), are
+/// adjusted using [`ObjectStream::with_compatibility`], and
+/// [`object_attributes`](S3Ext::object_attributes) only uses
+/// `GetObjectAttributes` if the provider supports it.
+#[derive(Debug, Clone)]
+pub struct CompatibleS3Client<C = S3Client> {
+    inner: C,
+    compat: Compatibility,
+}
+
+impl<C> CompatibleS3Client<C> {
+    /// Wrap `inner`, adjusting requests to `compat`
+    pub fn new(inner: C, compat: Compatibility) -> Self {
+        Self { inner, compat }
+    }
+
+    /// Wrapped client
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Profile requests are adjusted to
+    pub fn compatibility(&self) -> &Compatibility {
+        &self.compat
+    }
+
+    fn put_request(&self, mut request: PutObjectRequest) -> PutObjectRequest {
+        self.compat.sanitize_put_request(&mut request);
+        request
+    }
+
+    fn get_request(&self, mut request: GetObjectRequest) -> GetObjectRequest {
+        self.compat.sanitize_get_request(&mut request);
+        request
+    }
+}
+
+#[async_trait]
+impl<C> S3Ext for CompatibleS3Client<C>
+where
+    C: S3Ext + Send + Sync,
+{
+    async fn send_request<T, F, Fut>(
+        &self,
+        operation: &'static str,
+        bucket: String,
+        key: String,
+        request: F,
+    ) -> S3ExtResult<T>
+    where
+        T: Send,
+        F: Fn(Backend, String, String) -> Fut + Send + Sync,
+        Fut: Future<Output = S3ExtResult<T>> + Send,
+    {
+        self.inner
+            .send_request(operation, bucket, key, request)
+            .await
+    }
+
+    async fn download_to_file<F>(
+        &self,
+        source: GetObjectRequest,
+        target: F,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.inner
+            .download_to_file(self.get_request(source), target)
+            .await
+    }
+
+    async fn upload_from_file<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.inner
+            .upload_from_file(source, self.put_request(target))
+            .await
+    }
+
+    async fn upload_from_file_multipart_with<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.inner
+            .upload_from_file_multipart_with(source, self.put_request(target), part_size)
+            .await
+    }
+
+    async fn download<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        self.inner.download(self.get_request(source), target).await
+    }
+
+    async fn download_decompressed<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        self.inner
+            .download_decompressed(self.get_request(source), target)
+            .await
+    }
+
+    async fn get_framed<D>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        decoder: D,
+    ) -> S3ExtResult<FramedObject<D>>
+    where
+        D: Decoder + Send,
+    {
+        self.inner.get_framed(bucket, key, decoder).await
+    }
+
+    async fn get_body_with(&self, source: GetObjectRequest) -> S3ExtResult<ObjectBody> {
+        self.inner.get_body_with(self.get_request(source)).await
+    }
+
+    async fn upload<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.inner.upload(source, self.put_request(target)).await
+    }
+
+    async fn upload_multipart_with<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.inner
+            .upload_multipart_with(source, self.put_request(target), part_size)
+            .await
+    }
+
+    async fn upload_multipart_resumable<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.inner
+            .upload_multipart_resumable(source, self.put_request(target), part_size)
+            .await
+    }
+
+    fn stream_objects(&self, bucket: impl Into<String>) -> ObjectStream {
+        self.inner
+            .stream_objects(bucket)
+            .with_compatibility(&self.compat)
+    }
+
+    fn stream_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> ObjectStream {
+        self.inner
+            .stream_objects_with_prefix(bucket, prefix)
+            .with_compatibility(&self.compat)
+    }
+
+    fn stream_objects_sorted_by_mtime(&self, bucket: impl Into<String>) -> SortedObjectStream {
+        self.inner
+            .stream_objects_sorted_by_mtime(bucket)
+            .with_compatibility(&self.compat)
+    }
+
+    fn stream_objects_with_prefix_sorted_by_mtime(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> SortedObjectStream {
+        self.inner
+            .stream_objects_with_prefix_sorted_by_mtime(bucket, prefix)
+            .with_compatibility(&self.compat)
+    }
+
+    fn diff(
+        &self,
+        source_bucket: impl Into<String>,
+        source_prefix: impl Into<String>,
+        dest_bucket: impl Into<String>,
+        dest_prefix: impl Into<String>,
+    ) -> DiffStream {
+        self.inner
+            .diff(source_bucket, source_prefix, dest_bucket, dest_prefix)
+            .with_compatibility(&self.compat)
+    }
+
+    fn watch_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        period: Duration,
+    ) -> WatchStream {
+        self.inner
+            .watch_prefix(bucket, prefix, period)
+            .with_compatibility(&self.compat)
+    }
+
+    fn stream_get_objects(&self, bucket: impl Into<String>) -> GetObjectStream {
+        self.inner
+            .stream_get_objects(bucket)
+            .with_compatibility(&self.compat)
+    }
+
+    fn stream_get_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> GetObjectStream {
+        self.inner
+            .stream_get_objects_with_prefix(bucket, prefix)
+            .with_compatibility(&self.compat)
+    }
+
+    #[inline]
+    fn stream_lines(&self, bucket: impl Into<String>, key: impl Into<String>) -> LineStream {
+        self.inner.stream_lines(bucket, key)
+    }
+
+    async fn object_attributes(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectAttributes> {
+        let get_object_attributes = self.compat.get_object_attributes;
+        self.inner
+            .send_request(
+                "object_attributes",
+                bucket.into(),
+                key.into(),
+                |client, bucket, key| async move {
+                    attributes::object_attributes(&client, bucket, key, get_object_attributes).await
+                },
+            )
+            .await
+    }
+
+    async fn delete(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.inner.delete(bucket, key).await
+    }
+
+    async fn compose_object(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        parts: Vec<ComposePart>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput> {
+        self.inner.compose_object(bucket, key, parts).await
+    }
+
+    async fn object_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectMetadata> {
+        self.inner.object_metadata(bucket, key).await
+    }
+
+    async fn replace_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        metadata: ObjectMetadata,
+    ) -> S3ExtResult<()> {
+        self.inner.replace_metadata(bucket, key, metadata).await
+    }
+
+    async fn copy(
+        &self,
+        source_bucket: impl Into<String> + Send,
+        source_key: impl Into<String> + Send,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.inner
+            .copy(source_bucket, source_key, bucket, key)
+            .await
+    }
+}
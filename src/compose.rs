@@ -0,0 +1,256 @@
+//! Assemble objects from parts of existing objects and new data
+//!
+//! See [`S3Ext::compose_object`](crate::S3Ext::compose_object).
+//!
+//! The object is written by a multipart upload: [`ComposePart::Copy`] parts
+//! are copied by S3 using `UploadPartCopy` without being downloaded,
+//! [`ComposePart::Data`] and [`ComposePart::File`] parts are uploaded, one
+//! at a time. All parts but the last must be at least 5 MiB, S3 rejects the
+//! upload otherwise. The upload is aborted if any part fails.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::{compose::ComposePart, S3Ext};
+//!
+//! // append a line to a large object
+//! let parts = vec![
+//!     ComposePart::copy("bucket", "events.log"),
+//!     ComposePart::Data(b"late event\n".to_vec()),
+//! ];
+//! client.compose_object("bucket", "events.log", parts).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    error::{S3ExtError, S3ExtResult},
+    storage_class::copy_source,
+    upload::{self, MAX_PARTS, MIN_PART_SIZE},
+};
+use rusoto_s3::{
+    AbortMultipartUploadRequest, CompleteMultipartUploadOutput, CompleteMultipartUploadRequest,
+    CompletedMultipartUpload, CompletedPart, PutObjectRequest, UploadPartCopyRequest,
+    UploadPartRequest,
+};
+use std::{
+    io::SeekFrom,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
+
+/// Part of an object assembled by
+/// [`S3Ext::compose_object`](crate::S3Ext::compose_object)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComposePart {
+    /// Bytes of object `key` in `bucket`, copied by S3
+    Copy {
+        /// Bucket of the source object
+        bucket: String,
+        /// Key of the source object
+        key: String,
+        /// Byte range copied, the whole object if `None`
+        range: Option<Range<u64>>,
+    },
+    /// Bytes uploaded as part
+    Data(Vec<u8>),
+    /// Bytes `range` of the local file at `path`, read when the part is
+    /// uploaded
+    File {
+        /// Path of the file
+        path: PathBuf,
+        /// Byte range uploaded
+        range: Range<u64>,
+    },
+}
+
+impl ComposePart {
+    /// Copy the whole object `key` in `bucket`
+    pub fn copy(bucket: impl Into<String>, key: impl Into<String>) -> Self {
+        ComposePart::Copy {
+            bucket: bucket.into(),
+            key: key.into(),
+            range: None,
+        }
+    }
+
+    /// Copy bytes `range` of object `key` in `bucket`
+    pub fn copy_range(
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        range: Range<u64>,
+    ) -> Self {
+        ComposePart::Copy {
+            bucket: bucket.into(),
+            key: key.into(),
+            range: Some(range),
+        }
+    }
+
+    /// Upload bytes `range` of the file at `path`
+    pub fn file_range(path: impl Into<PathBuf>, range: Range<u64>) -> Self {
+        ComposePart::File {
+            path: path.into(),
+            range,
+        }
+    }
+}
+
+pub(crate) async fn compose_object(
+    client: &Backend,
+    bucket: String,
+    key: String,
+    parts: Vec<ComposePart>,
+) -> S3ExtResult<CompleteMultipartUploadOutput> {
+    let result = async {
+        validate(&parts)?;
+        let target = PutObjectRequest {
+            bucket: bucket.clone(),
+            key: key.clone(),
+            ..Default::default()
+        };
+        let upload_id = upload::create_multipart_upload(client, &target).await?;
+        let abort = AbortMultipartUploadRequest {
+            bucket: bucket.clone(),
+            key: key.clone(),
+            upload_id: upload_id.clone(),
+            ..Default::default()
+        };
+        let result = compose_needs_abort_on_error(client, &target, parts, &upload_id).await;
+        upload::abort_on_error(client, abort, result).await
+    }
+    .await;
+    result.map_err(|e| e.with_object(bucket, key))
+}
+
+fn validate(parts: &[ComposePart]) -> S3ExtResult<()> {
+    if parts.is_empty() || parts.len() > MAX_PARTS {
+        return Err(S3ExtError::Other(
+            "objects are composed of 1 to 10,000 parts",
+        ));
+    }
+    for (i, part) in parts.iter().enumerate() {
+        match part {
+            ComposePart::Data(data) if i + 1 < parts.len() && data.len() < MIN_PART_SIZE => {
+                return Err(S3ExtError::InvalidPartSize {
+                    part_size: data.len(),
+                    reason: "parts must be at least 5 MiB",
+                });
+            }
+            ComposePart::File { range, .. }
+                if i + 1 < parts.len()
+                    && range.end.saturating_sub(range.start) < MIN_PART_SIZE as u64 =>
+            {
+                return Err(S3ExtError::InvalidPartSize {
+                    part_size: range.end.saturating_sub(range.start) as usize,
+                    reason: "parts must be at least 5 MiB",
+                });
+            }
+            ComposePart::Copy {
+                range: Some(range), ..
+            }
+            | ComposePart::File { range, .. }
+                if range.start >= range.end =>
+            {
+                return Err(S3ExtError::Other("ranges must not be empty"));
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+// Upload needs to be aborted if this function fails
+async fn compose_needs_abort_on_error(
+    client: &Backend,
+    target: &PutObjectRequest,
+    parts: Vec<ComposePart>,
+    upload_id: &str,
+) -> S3ExtResult<CompleteMultipartUploadOutput> {
+    let mut completed = Vec::new();
+    for (part_number, part) in (1..).zip(parts) {
+        let e_tag = match part {
+            ComposePart::Copy { bucket, key, range } => {
+                let request = UploadPartCopyRequest {
+                    bucket: target.bucket.clone(),
+                    key: target.key.clone(),
+                    upload_id: upload_id.to_owned(),
+                    part_number,
+                    copy_source: copy_source(&bucket, &key),
+                    copy_source_range: range
+                        .map(|range| format!("bytes={}-{}", range.start, range.end - 1)),
+                    sse_customer_algorithm: target.sse_customer_algorithm.clone(),
+                    sse_customer_key: target.sse_customer_key.clone(),
+                    sse_customer_key_md5: target.sse_customer_key_md5.clone(),
+                    ..Default::default()
+                };
+                client
+                    .upload_part_copy(request)
+                    .await
+                    .map_err(|e| S3ExtError::from(e).with_object(bucket, key))?
+                    .copy_part_result
+                    .and_then(|result| result.e_tag)
+            }
+            ComposePart::File { path, range } => {
+                let data = read_range(&path, range).await?;
+                let request = UploadPartRequest {
+                    bucket: target.bucket.clone(),
+                    key: target.key.clone(),
+                    upload_id: upload_id.to_owned(),
+                    part_number,
+                    content_length: Some(data.len() as i64),
+                    body: Some(data.into()),
+                    sse_customer_algorithm: target.sse_customer_algorithm.clone(),
+                    sse_customer_key: target.sse_customer_key.clone(),
+                    sse_customer_key_md5: target.sse_customer_key_md5.clone(),
+                    ..Default::default()
+                };
+                client.upload_part(request).await?.e_tag
+            }
+            ComposePart::Data(data) => {
+                let request = UploadPartRequest {
+                    bucket: target.bucket.clone(),
+                    key: target.key.clone(),
+                    upload_id: upload_id.to_owned(),
+                    part_number,
+                    content_length: Some(data.len() as i64),
+                    body: Some(data.into()),
+                    sse_customer_algorithm: target.sse_customer_algorithm.clone(),
+                    sse_customer_key: target.sse_customer_key.clone(),
+                    sse_customer_key_md5: target.sse_customer_key_md5.clone(),
+                    ..Default::default()
+                };
+                client.upload_part(request).await?.e_tag
+            }
+        };
+        completed.push(CompletedPart {
+            e_tag,
+            part_number: Some(part_number),
+        });
+    }
+    let request = CompleteMultipartUploadRequest {
+        bucket: target.bucket.clone(),
+        key: target.key.clone(),
+        multipart_upload: Some(CompletedMultipartUpload {
+            parts: Some(completed),
+        }),
+        upload_id: upload_id.to_owned(),
+        ..Default::default()
+    };
+    Ok(client.complete_multipart_upload(request).await?)
+}
+
+// Bytes `range` of the file at `path`
+async fn read_range(path: &Path, range: Range<u64>) -> S3ExtResult<Vec<u8>> {
+    let mut file = File::open(path).await?;
+    file.seek(SeekFrom::Start(range.start)).await?;
+    let mut data = vec![0; (range.end - range.start) as usize];
+    file.read_exact(&mut data).await?;
+    Ok(data)
+}
@@ -0,0 +1,174 @@
+//! Compression of bodies as they are uploaded
+//!
+//! See [`S3Ext::upload_compressed`](crate::S3Ext::upload_compressed).
+//!
+//! Logs and other text compress well, uploading them compressed saves both
+//! transfer and storage. The source is compressed as it is read and uploaded
+//! using multipart upload with [`PartSize::Auto`], so it is never held in
+//! memory as a whole. The encoding is appended to the `content_encoding` of
+//! the object, clients such as browsers and
+//! [`S3Ext::download_decompressed`](crate::S3Ext::download_decompressed)
+//! decompress it transparently.
+//!
+//! As the size of the object is that of the compressed body, the size of the
+//! source can be recorded in the user metadata entry
+//! [`ORIGINAL_SIZE_METADATA`] if it is known in advance.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use rusoto_s3::PutObjectRequest;
+//! use s3_ext::{compress::Compression, S3Ext};
+//! use tokio::fs::File;
+//!
+//! let mut file = File::open("/var/log/syslog").await?;
+//! let size = file.metadata().await?.len();
+//! let request = PutObjectRequest {
+//!     bucket: "logs".to_owned(),
+//!     key: "syslog".to_owned(),
+//!     content_type: Some("text/plain".to_owned()),
+//!     ..Default::default()
+//! };
+//! client
+//!     .upload_compressed(&mut file, request, Compression::Zstd(3), Some(size))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{error::S3ExtResult, part_size::PartSize, S3Ext};
+use rusoto_s3::{CompleteMultipartUploadOutput, PutObjectRequest};
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// User metadata entry holding the size of the uncompressed body
+pub const ORIGINAL_SIZE_METADATA: &str = "uncompressed-size";
+
+/// Compression applied to uploaded bodies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// gzip at the default level, requires feature `gzip`
+    Gzip,
+    /// Zstandard at the given level, 1 to 22, requires feature `zstd`
+    Zstd(i32),
+}
+
+impl Compression {
+    /// Value of the `Content-Encoding` header
+    pub fn content_encoding(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd(_) => "zstd",
+        }
+    }
+}
+
+type Reader<'a> = Pin<Box<dyn AsyncRead + Send + 'a>>;
+
+pub(crate) async fn upload_compressed<C, R>(
+    client: &C,
+    source: &mut R,
+    mut target: PutObjectRequest,
+    compression: Compression,
+    original_size: Option<u64>,
+) -> S3ExtResult<CompleteMultipartUploadOutput>
+where
+    C: S3Ext + ?Sized,
+    R: AsyncRead + Unpin + Send,
+{
+    let source = Box::pin(ExpectSize {
+        inner: source,
+        read: 0,
+        expected: original_size,
+    });
+    let mut compressed = compress(source, compression)?;
+    target.content_encoding = Some(match target.content_encoding.take() {
+        Some(encoding) => format!("{}, {}", encoding, compression.content_encoding()),
+        None => compression.content_encoding().to_owned(),
+    });
+    if let Some(size) = original_size {
+        target
+            .metadata
+            .get_or_insert_with(Default::default)
+            .insert(ORIGINAL_SIZE_METADATA.to_owned(), size.to_string());
+    }
+    client
+        .upload_multipart_with(&mut compressed, target, PartSize::Auto)
+        .await
+}
+
+fn compress(source: Reader<'_>, compression: Compression) -> S3ExtResult<Reader<'_>> {
+    match compression {
+        Compression::Gzip => gzip(source),
+        Compression::Zstd(level) => zstd(source, level),
+    }
+}
+
+#[cfg(feature = "gzip")]
+fn gzip(source: Reader<'_>) -> S3ExtResult<Reader<'_>> {
+    use async_compression::tokio::bufread::GzipEncoder;
+
+    Ok(Box::pin(GzipEncoder::new(tokio::io::BufReader::new(
+        source,
+    ))))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip(_source: Reader<'_>) -> S3ExtResult<Reader<'_>> {
+    Err(crate::error::S3ExtError::Other(
+        "feature `gzip` required to compress using gzip",
+    ))
+}
+
+#[cfg(feature = "zstd")]
+fn zstd(source: Reader<'_>, level: i32) -> S3ExtResult<Reader<'_>> {
+    use async_compression::{tokio::bufread::ZstdEncoder, Level};
+
+    Ok(Box::pin(ZstdEncoder::with_quality(
+        tokio::io::BufReader::new(source),
+        Level::Precise(level),
+    )))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd(_source: Reader<'_>, _level: i32) -> S3ExtResult<Reader<'_>> {
+    Err(crate::error::S3ExtError::Other(
+        "feature `zstd` required to compress using zstd",
+    ))
+}
+
+// Reader failing at the end of `inner` unless `expected` bytes were read,
+// aborting the upload rather than recording a wrong original size
+struct ExpectSize<'a, R> {
+    inner: &'a mut R,
+    read: u64,
+    expected: Option<u64>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for ExpectSize<'_, R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let (filled, remaining) = (buf.filled().len(), buf.remaining());
+        futures::ready!(Pin::new(&mut *self.inner).poll_read(cx, buf))?;
+        let read = (buf.filled().len() - filled) as u64;
+        self.read += read;
+        let eof = read == 0 && remaining > 0;
+        match self.expected {
+            Some(expected) if self.read > expected || (eof && self.read != expected) => {
+                Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "size of source differs from original size",
+                )))
+            }
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+}
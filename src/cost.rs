@@ -0,0 +1,237 @@
+//! Request and transfer accounting for cost estimates
+//!
+//! S3 bills requests by type and the bytes transferred out of AWS. A
+//! [`CostTracker`] installed using
+//! [`S3ExtClientBuilder::cost_tracker`](crate::client::S3ExtClientBuilder::cost_tracker)
+//! counts every HTTP request sent by the client per [`RequestClass`],
+//! including the pages of listings, the parts of multipart uploads and
+//! retries, along with the bytes of request and response bodies.
+//! [`Usage::estimate`] converts the counts to dollars given a [`Pricing`].
+//!
+//! Clones of a tracker share their counts. To attribute costs to jobs, build
+//! a client with a tracker of its own for every job.
+//!
+//! # Example
+//!
+//! ```
+//! use s3_ext::{
+//!     client::S3ExtClientBuilder,
+//!     cost::{CostTracker, Pricing},
+//! };
+//!
+//! # fn main() -> Result<(), s3_ext::error::S3ExtError> {
+//! let tracker = CostTracker::new();
+//! let client = S3ExtClientBuilder::new()
+//!     .endpoint("eu-west-1", "http://localhost:9000")
+//!     .cost_tracker(tracker.clone())
+//!     .build()?;
+//!
+//! // run the job using `client`
+//!
+//! let estimate = tracker.usage().estimate(&Pricing::s3_standard());
+//! println!("job cost ${:.4}", estimate.total());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::rate_limit::RequestClass;
+use futures::TryStreamExt;
+use parking_lot::Mutex;
+use rusoto_core::{
+    request::{DispatchSignedRequestFuture, HttpResponse},
+    signature::{SignedRequest, SignedRequestPayload},
+    ByteStream, DispatchSignedRequest, HttpDispatchError,
+};
+use std::{mem, sync::Arc, time::Duration};
+
+// Bytes per GB as billed by AWS
+const GB: f64 = (1u64 << 30) as f64;
+
+/// Requests and bytes counted by a [`CostTracker`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Requests of class [`RequestClass::List`]
+    pub list_requests: u64,
+    /// Requests of class [`RequestClass::Get`]
+    pub get_requests: u64,
+    /// Requests of class [`RequestClass::Put`]
+    pub put_requests: u64,
+    /// Requests of class [`RequestClass::Delete`]
+    pub delete_requests: u64,
+    /// Bytes of request bodies
+    pub bytes_sent: u64,
+    /// Bytes of response bodies
+    pub bytes_received: u64,
+}
+
+impl Usage {
+    /// Requests of `class`
+    pub fn requests(&self, class: RequestClass) -> u64 {
+        match class {
+            RequestClass::List => self.list_requests,
+            RequestClass::Get => self.get_requests,
+            RequestClass::Put => self.put_requests,
+            RequestClass::Delete => self.delete_requests,
+        }
+    }
+
+    fn requests_mut(&mut self, class: RequestClass) -> &mut u64 {
+        match class {
+            RequestClass::List => &mut self.list_requests,
+            RequestClass::Get => &mut self.get_requests,
+            RequestClass::Put => &mut self.put_requests,
+            RequestClass::Delete => &mut self.delete_requests,
+        }
+    }
+
+    /// Cost of the requests and transfers according to `pricing`
+    pub fn estimate(&self, pricing: &Pricing) -> CostEstimate {
+        let requests = [
+            RequestClass::List,
+            RequestClass::Get,
+            RequestClass::Put,
+            RequestClass::Delete,
+        ]
+        .iter()
+        .map(|&class| self.requests(class) as f64 / 1000.0 * pricing.per_1000_requests(class))
+        .sum();
+        let transfer = self.bytes_sent as f64 / GB * pricing.transfer_in
+            + self.bytes_received as f64 / GB * pricing.transfer_out;
+        CostEstimate { requests, transfer }
+    }
+}
+
+/// Prices in dollars, of requests per 1,000 requests and of transfers per GB
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pricing {
+    /// Price of 1,000 [`RequestClass::List`] requests
+    pub list: f64,
+    /// Price of 1,000 [`RequestClass::Get`] requests
+    pub get: f64,
+    /// Price of 1,000 [`RequestClass::Put`] requests
+    pub put: f64,
+    /// Price of 1,000 [`RequestClass::Delete`] requests
+    pub delete: f64,
+    /// Price of a GB sent to S3
+    pub transfer_in: f64,
+    /// Price of a GB received from S3
+    pub transfer_out: f64,
+}
+
+impl Pricing {
+    /// Prices of S3 Standard in `us-east-1` for transfers to the internet
+    ///
+    /// $0.005 per 1,000 `PUT`, `COPY`, `POST` and `LIST` requests, $0.0004
+    /// per 1,000 `GET` and other requests, free `DELETE` requests, free
+    /// transfers in and $0.09 per GB transferred out. Set `transfer_out` to
+    /// zero for clients running in the region of the bucket.
+    pub fn s3_standard() -> Self {
+        Pricing {
+            list: 0.005,
+            get: 0.0004,
+            put: 0.005,
+            delete: 0.0,
+            transfer_in: 0.0,
+            transfer_out: 0.09,
+        }
+    }
+
+    fn per_1000_requests(&self, class: RequestClass) -> f64 {
+        match class {
+            RequestClass::List => self.list,
+            RequestClass::Get => self.get,
+            RequestClass::Put => self.put,
+            RequestClass::Delete => self.delete,
+        }
+    }
+}
+
+/// Estimated cost in dollars
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CostEstimate {
+    /// Cost of the requests
+    pub requests: f64,
+    /// Cost of the transfers
+    pub transfer: f64,
+}
+
+impl CostEstimate {
+    /// Cost of requests and transfers
+    pub fn total(&self) -> f64 {
+        self.requests + self.transfer
+    }
+}
+
+/// Counter of the requests and bytes sent by a client
+#[derive(Debug, Clone, Default)]
+pub struct CostTracker {
+    usage: Arc<Mutex<Usage>>,
+}
+
+impl CostTracker {
+    /// Create a tracker without any requests counted
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests and bytes counted so far
+    pub fn usage(&self) -> Usage {
+        *self.usage.lock()
+    }
+
+    /// Reset the counts, returning the requests and bytes counted so far
+    pub fn reset(&self) -> Usage {
+        mem::take(&mut *self.usage.lock())
+    }
+
+    fn request(&self, request: &SignedRequest) {
+        let sent = request
+            .headers
+            .get("content-length")
+            .and_then(|values| values.first())
+            .and_then(|value| std::str::from_utf8(value).ok()?.parse().ok())
+            .or_else(|| match &request.payload {
+                Some(SignedRequestPayload::Buffer(buffer)) => Some(buffer.len() as u64),
+                _ => None,
+            })
+            .unwrap_or_default();
+        let mut usage = self.usage.lock();
+        *usage.requests_mut(RequestClass::of(request)) += 1;
+        usage.bytes_sent += sent;
+    }
+
+    async fn dispatch(
+        self,
+        inner: Arc<dyn DispatchSignedRequest + Send + Sync>,
+        request: SignedRequest,
+        timeout: Option<Duration>,
+    ) -> Result<HttpResponse, HttpDispatchError> {
+        self.request(&request);
+        let mut response = inner.dispatch(request, timeout).await?;
+        let body = mem::replace(&mut response.body, ByteStream::from(Vec::new()));
+        response.body = ByteStream::new(body.inspect_ok(move |chunk| {
+            self.usage.lock().bytes_received += chunk.len() as u64;
+        }));
+        Ok(response)
+    }
+}
+
+/// HTTP client counting requests on a `CostTracker`
+pub(crate) struct CostTrackingHttpClient {
+    pub(crate) inner: Arc<dyn DispatchSignedRequest + Send + Sync>,
+    pub(crate) tracker: CostTracker,
+}
+
+impl DispatchSignedRequest for CostTrackingHttpClient {
+    fn dispatch(
+        &self,
+        request: SignedRequest,
+        timeout: Option<Duration>,
+    ) -> DispatchSignedRequestFuture {
+        Box::pin(
+            self.tracker
+                .clone()
+                .dispatch(self.inner.clone(), request, timeout),
+        )
+    }
+}
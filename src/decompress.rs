@@ -0,0 +1,105 @@
+//! Decompression of bodies according to their `Content-Encoding`
+//!
+//! Objects uploaded with `Content-Encoding: gzip`, e.g. to be served
+//! compressed by CloudFront, are returned by S3 as stored. Rather than
+//! wiring a decoder for every encoding,
+//! [`S3Ext::download_decompressed`](crate::S3Ext::download_decompressed)
+//! decompresses bodies as they are downloaded. Encodings listed in
+//! `content_encoding` are removed in reverse order:
+//!
+//! * `gzip` and `x-gzip`, requiring feature `gzip`
+//! * `zstd`, requiring feature `zstd`
+//! * `identity` and `aws-chunked`, leaving the body as is
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use rusoto_s3::GetObjectRequest;
+//! use s3_ext::S3Ext;
+//!
+//! let request = GetObjectRequest {
+//!     bucket: "bucket".to_owned(),
+//!     key: "index.html".to_owned(),
+//!     ..Default::default()
+//! };
+//! let mut html = Vec::new();
+//! client.download_decompressed(request, &mut html).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::{S3ExtError, S3ExtResult},
+    framed::ObjectReader,
+};
+use rusoto_s3::StreamingBody;
+
+/// Wrap `body` to remove the encodings listed in `content_encoding`
+///
+/// Fails with `S3ExtError::Other` if an encoding isn't supported.
+pub fn decompress(body: ObjectReader, content_encoding: Option<&str>) -> S3ExtResult<ObjectReader> {
+    let encodings = content_encoding.unwrap_or_default().split(',').rev();
+    encodings.map(str::trim).try_fold(body, |body, encoding| {
+        match encoding.to_ascii_lowercase().as_str() {
+            "" | "identity" | "aws-chunked" => Ok(body),
+            "gzip" | "x-gzip" => gzip(body),
+            "zstd" => zstd(body),
+            _ => Err(S3ExtError::Other("unsupported content encoding")),
+        }
+    })
+}
+
+/// Whether `content_encoding` lists an encoding removed by [`decompress`]
+pub(crate) fn is_compressed(content_encoding: &str) -> bool {
+    content_encoding.split(',').map(str::trim).any(|encoding| {
+        !matches!(
+            encoding.to_ascii_lowercase().as_str(),
+            "" | "identity" | "aws-chunked"
+        )
+    })
+}
+
+/// Body of a `GetObject` response, decompressed according to
+/// `content_encoding`
+pub(crate) fn body(
+    body: Option<StreamingBody>,
+    content_encoding: Option<&str>,
+) -> S3ExtResult<ObjectReader> {
+    // Some S3-compatible servers omit the body of empty objects
+    let body: ObjectReader = match body {
+        Some(body) => Box::pin(body.into_async_read()),
+        None => return Ok(Box::pin(&[][..])),
+    };
+    decompress(body, content_encoding)
+}
+
+#[cfg(feature = "gzip")]
+fn gzip(body: ObjectReader) -> S3ExtResult<ObjectReader> {
+    let mut decoder =
+        async_compression::tokio::bufread::GzipDecoder::new(tokio::io::BufReader::new(body));
+    decoder.multiple_members(true);
+    Ok(Box::pin(decoder))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip(_body: ObjectReader) -> S3ExtResult<ObjectReader> {
+    Err(S3ExtError::Other(
+        "feature `gzip` required to decompress gzip bodies",
+    ))
+}
+
+#[cfg(feature = "zstd")]
+fn zstd(body: ObjectReader) -> S3ExtResult<ObjectReader> {
+    let mut decoder =
+        async_compression::tokio::bufread::ZstdDecoder::new(tokio::io::BufReader::new(body));
+    decoder.multiple_members(true);
+    Ok(Box::pin(decoder))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd(_body: ObjectReader) -> S3ExtResult<ObjectReader> {
+    Err(S3ExtError::Other(
+        "feature `zstd` required to decompress zstd bodies",
+    ))
+}
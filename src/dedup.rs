@@ -0,0 +1,143 @@
+//! Skip uploads of unchanged files
+//!
+//! See [`S3Ext::upload_if_changed`](crate::S3Ext::upload_if_changed).
+//!
+//! A file is unchanged if it has the size of the object and its entity tag
+//! computed as S3 does: the MD5 digest of the content for objects uploaded
+//! in a single request, the digest of the digests of all parts followed by
+//! the number of parts for multipart uploads. The part size is taken from
+//! the object, see [`ObjectAttributes`].
+//!
+//! Entity tags of objects encrypted using SSE-KMS or SSE-C aren't digests
+//! of the content, such objects are always uploaded again.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use rusoto_s3::PutObjectRequest;
+//! use s3_ext::S3Ext;
+//!
+//! let target = PutObjectRequest {
+//!     bucket: "backups".to_owned(),
+//!     key: "db.dump".to_owned(),
+//!     ..Default::default()
+//! };
+//! if client.upload_if_changed("/var/backups/db.dump", target).await? {
+//!     println!("uploaded");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    attributes::ObjectAttributes,
+    error::{S3ExtError, S3ExtResult},
+    S3Ext,
+};
+use md5::{Digest, Md5};
+use rusoto_s3::PutObjectRequest;
+use std::path::Path;
+use tokio::{fs::File, io::AsyncReadExt};
+
+/// Entity tag S3 assigns to the content of the file at `path`, without
+/// quotes
+///
+/// `part_size` is the size of the parts if uploaded using multipart upload,
+/// `None` if uploaded in a single request.
+pub async fn file_e_tag(path: impl AsRef<Path>, part_size: Option<u64>) -> S3ExtResult<String> {
+    let mut file = File::open(path).await?;
+    let part_size = match part_size {
+        None => {
+            let mut md5 = Md5::new();
+            let mut buf = vec![0; 64 * 1024];
+            loop {
+                match file.read(&mut buf).await? {
+                    0 => return Ok(hex::encode(md5.finalize())),
+                    n => md5.update(&buf[..n]),
+                }
+            }
+        }
+        Some(0) => return Err(S3ExtError::Other("part size must not be zero")),
+        Some(part_size) => part_size,
+    };
+    let mut digests = Vec::new();
+    let mut parts = 0;
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let mut md5 = Md5::new();
+        let mut read = 0;
+        while read < part_size {
+            let len = buf.len().min((part_size - read) as usize);
+            match file.read(&mut buf[..len]).await? {
+                0 => break,
+                n => {
+                    md5.update(&buf[..n]);
+                    read += n as u64;
+                }
+            }
+        }
+        // an empty file is still uploaded as one part
+        if read == 0 && parts > 0 {
+            break;
+        }
+        digests.extend_from_slice(&md5.finalize());
+        parts += 1;
+        if read < part_size {
+            break;
+        }
+    }
+    Ok(format!("{}-{}", hex::encode(Md5::digest(&digests)), parts))
+}
+
+// Whether the file at `path` has the content of the object
+async fn is_unchanged(path: &Path, size: u64, attributes: &ObjectAttributes) -> S3ExtResult<bool> {
+    let e_tag = match attributes.e_tag.as_deref() {
+        Some(e_tag) => e_tag.trim_matches('"'),
+        None => return Ok(false),
+    };
+    if attributes.size as u64 != size
+        || attributes.sse_customer_algorithm.is_some()
+        || attributes.server_side_encryption.as_deref() == Some("aws:kms")
+    {
+        return Ok(false);
+    }
+    let part_size = if attributes.is_multipart() {
+        match attributes.part_size {
+            Some(part_size) => Some(part_size as u64),
+            None => return Ok(false),
+        }
+    } else {
+        None
+    };
+    Ok(file_e_tag(path, part_size).await? == e_tag)
+}
+
+pub(crate) async fn upload_if_changed<C>(
+    client: &C,
+    source: &Path,
+    target: PutObjectRequest,
+) -> S3ExtResult<bool>
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    let (bucket, key) = (target.bucket.clone(), target.key.clone());
+    let size = tokio::fs::metadata(source)
+        .await
+        .map_err(|e| S3ExtError::from(e).with_object(&bucket, &key))?
+        .len();
+    match client.object_attributes(bucket.clone(), key.clone()).await {
+        Ok(attributes) => {
+            let unchanged = is_unchanged(source, size, &attributes)
+                .await
+                .map_err(|e| e.with_object(&bucket, &key))?;
+            if unchanged {
+                return Ok(false);
+            }
+        }
+        Err(e) if e.is_not_found() => (),
+        Err(e) => return Err(e),
+    }
+    client.upload_from_file(source, target).await?;
+    Ok(true)
+}
@@ -0,0 +1,425 @@
+//! Delta uploads reusing unchanged blocks of the previous version
+//!
+//! See [`S3Ext::upload_delta`](crate::S3Ext::upload_delta).
+//!
+//! Like rsync, a delta upload compares the file to a signature of the
+//! object it replaces: a weak rolling checksum and a SHA-256 digest of each
+//! block of the object. The file is scanned for blocks of the object at any
+//! offset, so insertions and deletions don't prevent reuse. Matching blocks
+//! are copied within S3 using `UploadPartCopy`, only the bytes in between are
+//! uploaded, see [`compose`](crate::compose).
+//!
+//! The signature is stored next to the object as `<key>.s3sig` and replaced
+//! after each delta upload. Without a signature, or if the object changed
+//! since it was written, the whole file is uploaded.
+//!
+//! Blocks are at least 8 MiB, as parts copied by `UploadPartCopy` must be
+//! at least 5 MiB. Changes are thus detected at a coarse granularity: files
+//! appended to or edited in a few places profit most.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::S3Ext;
+//!
+//! let report = client
+//!     .upload_delta("/var/lib/db/data.img", "backups", "data.img")
+//!     .await?;
+//! println!("uploaded {} bytes, copied {} bytes", report.uploaded, report.copied);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    compose::ComposePart,
+    error::{S3ExtError, S3ExtResult},
+    upload::{MAX_PARTS, MAX_PART_SIZE},
+    S3Ext,
+};
+use rusoto_s3::{CompleteMultipartUploadOutput, GetObjectRequest, PutObjectRequest};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, Read},
+    ops::Range,
+    path::Path,
+};
+
+/// Suffix of the key of the signature object
+pub const SIGNATURE_SUFFIX: &str = ".s3sig";
+
+/// Smallest block size used, 8 MiB
+pub const MIN_BLOCK_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Outcome of a delta upload
+#[derive(Debug)]
+pub struct DeltaReport {
+    /// Bytes uploaded
+    pub uploaded: u64,
+    /// Bytes copied from the previous version within S3
+    pub copied: u64,
+    /// Output of the multipart upload
+    pub output: CompleteMultipartUploadOutput,
+}
+
+// Checksums of the blocks of an object
+#[derive(Debug)]
+struct Signature {
+    e_tag: String,
+    block_size: u64,
+    // weak and strong checksum of each block, a shorter last block excluded
+    blocks: Vec<(u32, [u8; 32])>,
+}
+
+impl Signature {
+    fn to_json(&self) -> Value {
+        let blocks: Vec<Value> = self
+            .blocks
+            .iter()
+            .map(|(weak, strong)| json!([weak, hex::encode(strong)]))
+            .collect();
+        json!({
+            "ETag": self.e_tag,
+            "BlockSize": self.block_size,
+            "Blocks": blocks,
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let blocks = value
+            .get("Blocks")?
+            .as_array()?
+            .iter()
+            .map(|block| {
+                let mut strong = [0; 32];
+                hex::decode_to_slice(block.get(1)?.as_str()?, &mut strong).ok()?;
+                Some((block.get(0)?.as_u64()? as u32, strong))
+            })
+            .collect::<Option<_>>()?;
+        let block_size = value.get("BlockSize")?.as_u64()?;
+        if block_size < MIN_BLOCK_SIZE {
+            return None;
+        }
+        Some(Signature {
+            e_tag: value.get("ETag")?.as_str()?.to_owned(),
+            block_size,
+            blocks,
+        })
+    }
+}
+
+// Part of the new file
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Op {
+    // bytes of the file
+    Literal(Range<u64>),
+    // block of the object, found at offset `at` of the file
+    Copy { block: u64, at: u64 },
+}
+
+// rsync's rolling checksum of a window of bytes
+#[derive(Debug, Clone, Copy)]
+struct Rolling {
+    a: u32,
+    b: u32,
+    len: u32,
+}
+
+impl Rolling {
+    fn new(window: &[u8]) -> Self {
+        let len = window.len() as u32;
+        let (mut a, mut b) = (0u32, 0u32);
+        for (i, &byte) in window.iter().enumerate() {
+            a = a.wrapping_add(u32::from(byte));
+            b = b.wrapping_add((len - i as u32).wrapping_mul(u32::from(byte)));
+        }
+        Rolling { a, b, len }
+    }
+
+    // Move the window by one byte, dropping `out` and adding `into`
+    fn roll(&mut self, out: u8, into: u8) {
+        self.a = self
+            .a
+            .wrapping_sub(u32::from(out))
+            .wrapping_add(u32::from(into));
+        self.b = self
+            .b
+            .wrapping_sub(self.len.wrapping_mul(u32::from(out)))
+            .wrapping_add(self.a);
+    }
+
+    fn digest(&self) -> u32 {
+        (self.a & 0xffff) | (self.b << 16)
+    }
+}
+
+fn strong(block: &[u8]) -> [u8; 32] {
+    let mut hash = [0; 32];
+    hash.copy_from_slice(&Sha256::digest(block));
+    hash
+}
+
+// Block size for a file of `size` bytes, a multiple of 1 MiB keeping the
+// upload within 10,000 parts
+fn block_size_for(size: u64) -> u64 {
+    const MIB: u64 = 1024 * 1024;
+    let needed = size.saturating_sub(1) / MAX_PARTS as u64 + 1;
+    MIN_BLOCK_SIZE.max((needed + MIB - 1) / MIB * MIB)
+}
+
+// Checksums of the blocks of the file at `path`
+fn file_signature(path: &Path, block_size: u64) -> io::Result<Vec<(u32, [u8; 32])>> {
+    let mut file = BufReader::new(File::open(path)?);
+    let mut block = vec![0; block_size as usize];
+    let mut blocks = Vec::new();
+    loop {
+        let mut len = 0;
+        while len < block.len() {
+            match file.read(&mut block[len..])? {
+                0 => break,
+                n => len += n,
+            }
+        }
+        if len < block.len() {
+            return Ok(blocks);
+        }
+        blocks.push((Rolling::new(&block).digest(), strong(&block)));
+    }
+}
+
+// Split the file at `path` into blocks of `signature` and literal bytes
+fn diff(path: &Path, signature: &Signature) -> io::Result<Vec<Op>> {
+    let block_size = signature.block_size as usize;
+    let mut index: HashMap<u32, Vec<(u64, &[u8; 32])>> = HashMap::new();
+    for (i, (weak, strong)) in signature.blocks.iter().enumerate() {
+        index.entry(*weak).or_default().push((i as u64, strong));
+    }
+
+    let mut file = BufReader::new(File::open(path)?);
+    let mut ops = Vec::new();
+    // `buf` holds the bytes of the file from offset `base`
+    let mut buf = Vec::new();
+    let mut base = 0u64;
+    let mut eof = false;
+    let (mut pos, mut literal) = (0u64, 0u64);
+    let mut rolling: Option<Rolling> = None;
+    loop {
+        let start = (pos - base) as usize;
+        while !eof && buf.len() < start + block_size {
+            let len = buf.len();
+            buf.resize(len + block_size, 0);
+            let n = file.read(&mut buf[len..])?;
+            buf.truncate(len + n);
+            eof = n == 0;
+        }
+        if buf.len() < start + block_size {
+            break;
+        }
+        let window = &buf[start..start + block_size];
+        let weak = match &mut rolling {
+            Some(rolling) => {
+                rolling.roll(buf[start - 1], window[block_size - 1]);
+                rolling.digest()
+            }
+            None => {
+                let fresh = Rolling::new(window);
+                rolling = Some(fresh);
+                fresh.digest()
+            }
+        };
+        let found = index.get(&weak).and_then(|candidates| {
+            let digest = strong(window);
+            candidates
+                .iter()
+                .find(|(_, strong)| **strong == digest)
+                .map(|(block, _)| *block)
+        });
+        match found {
+            Some(block) => {
+                if literal < pos {
+                    ops.push(Op::Literal(literal..pos));
+                }
+                ops.push(Op::Copy { block, at: pos });
+                pos += block_size as u64;
+                literal = pos;
+                rolling = None;
+            }
+            None => pos += 1,
+        }
+        // now and then drop the bytes before the window, but the last one
+        let used = (pos - base) as usize;
+        if used > 2 * block_size {
+            buf.drain(..used - 1);
+            base += used as u64 - 1;
+        }
+    }
+    let size = base + buf.len() as u64;
+    if literal < size {
+        ops.push(Op::Literal(literal..size));
+    }
+    Ok(ops)
+}
+
+// Parts writing the file at `path` as described by `ops`, all but the last
+// at least one block long, and the bytes uploaded and copied
+fn parts(
+    path: &Path,
+    bucket: &str,
+    key: &str,
+    ops: Vec<Op>,
+    block_size: u64,
+) -> (Vec<ComposePart>, u64, u64) {
+    let (mut parts, mut uploaded, mut copied) = (Vec::new(), 0, 0);
+    // bytes of the file not part of a part yet
+    let mut pending: Option<Range<u64>> = None;
+    let mut flush = |parts: &mut Vec<ComposePart>, range: Range<u64>| {
+        uploaded += range.end - range.start;
+        parts.push(ComposePart::file_range(path, range));
+    };
+    for op in ops {
+        let (range, copy) = match op {
+            Op::Literal(range) => (range, None),
+            Op::Copy { block, at } => (at..at + block_size, Some(block)),
+        };
+        match (pending.take(), copy) {
+            (None, Some(block)) => {
+                copied += block_size;
+                parts.push(ComposePart::copy_range(
+                    bucket,
+                    key,
+                    block * block_size..(block + 1) * block_size,
+                ));
+            }
+            (Some(data), Some(block)) if data.end - data.start >= block_size => {
+                flush(&mut parts, data);
+                copied += block_size;
+                parts.push(ComposePart::copy_range(
+                    bucket,
+                    key,
+                    block * block_size..(block + 1) * block_size,
+                ));
+            }
+            (data, _) => {
+                let mut data = data.map_or(range.clone(), |data| data.start..range.end);
+                // parts must not exceed 5 GiB
+                while data.end - data.start >= 2 * block_size {
+                    flush(&mut parts, data.start..data.start + block_size);
+                    data.start += block_size;
+                }
+                pending = Some(data);
+            }
+        }
+    }
+    if let Some(data) = pending {
+        flush(&mut parts, data);
+    }
+    (parts, uploaded, copied)
+}
+
+// Signature of the object `key` if stored and up to date
+async fn stored_signature<C>(client: &C, bucket: &str, key: &str) -> S3ExtResult<Option<Signature>>
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    let e_tag = match client.object_attributes(bucket, key).await {
+        Ok(attributes) => attributes.e_tag,
+        Err(e) if e.is_not_found() => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let request = GetObjectRequest {
+        bucket: bucket.to_owned(),
+        key: format!("{}{}", key, SIGNATURE_SUFFIX),
+        ..Default::default()
+    };
+    let mut json = Vec::new();
+    match client.download(request, &mut json).await {
+        Ok(_) => (),
+        Err(e) if e.is_not_found() => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let signature = serde_json::from_slice(&json)
+        .ok()
+        .and_then(|value| Signature::from_json(&value));
+    Ok(signature.filter(|signature| Some(&signature.e_tag) == e_tag.as_ref()))
+}
+
+/// Upload file `source` as object `key`, copying blocks it shares with the
+/// current version of the object
+///
+/// See [`S3Ext::upload_delta`].
+pub(crate) async fn upload_delta<C>(
+    client: &C,
+    source: &Path,
+    bucket: String,
+    key: String,
+) -> S3ExtResult<DeltaReport>
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    let size = tokio::fs::metadata(source)
+        .await
+        .map_err(|e| S3ExtError::from(e).with_object(&bucket, &key))?
+        .len();
+    let needed = block_size_for(size);
+    let signature = stored_signature(client, &bucket, &key)
+        .await?
+        .filter(|signature| {
+            signature.block_size >= needed && signature.block_size <= MAX_PART_SIZE as u64 / 2
+        });
+    let block_size = signature
+        .as_ref()
+        .map_or(needed, |signature| signature.block_size);
+
+    let path = source.to_owned();
+    let ops = match signature {
+        Some(signature) => blocking(move || diff(&path, &signature)).await?,
+        None => vec![Op::Literal(0..size)],
+    };
+    let (mut parts, uploaded, copied) = parts(source, &bucket, &key, ops, block_size);
+    if parts.is_empty() {
+        parts.push(ComposePart::Data(Vec::new()));
+    }
+    let output = client
+        .compose_object(bucket.clone(), key.clone(), parts)
+        .await?;
+
+    let path = source.to_owned();
+    let blocks = blocking(move || file_signature(&path, block_size))
+        .await
+        .map_err(|e| e.with_object(&bucket, &key))?;
+    let signature = Signature {
+        e_tag: output.e_tag.clone().unwrap_or_default(),
+        block_size,
+        blocks,
+    };
+    let json = serde_json::to_vec(&signature.to_json())
+        .map_err(|_| S3ExtError::Other("failed to serialize signature"))?;
+    let request = PutObjectRequest {
+        bucket,
+        key: format!("{}{}", key, SIGNATURE_SUFFIX),
+        content_type: Some("application/json".to_owned()),
+        content_length: Some(json.len() as i64),
+        ..Default::default()
+    };
+    client.upload(&mut json.as_slice(), request).await?;
+    Ok(DeltaReport {
+        uploaded,
+        copied,
+        output,
+    })
+}
+
+// Run `f` on a thread allowed to block
+async fn blocking<T, F>(f: F) -> S3ExtResult<T>
+where
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|_| S3ExtError::Other("delta computation panicked"))?
+        .map_err(S3ExtError::from)
+}
@@ -0,0 +1,190 @@
+//! Compare the objects below two prefixes
+//!
+//! Both listings are sorted by key, hence they can be merged without
+//! buffering. Keys are compared relative to their prefix, i.e. `a/x` below
+//! prefix `a/` matches `b/x` below prefix `b/`.
+
+use crate::{
+    compat::Compatibility,
+    error::{S3ExtError, S3ExtResult},
+    iter::ObjectStream,
+};
+use futures::{
+    ready,
+    stream::Stream,
+    task::{Context, Poll},
+};
+use rusoto_s3::Object;
+use std::{cmp::Ordering, pin::Pin};
+
+/// Difference between source and destination
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffEntry {
+    /// Object exists below the source prefix only
+    OnlyInSource {
+        /// Key relative to the source prefix
+        key: String,
+        /// Source object
+        source: Object,
+    },
+    /// Object exists below the destination prefix only
+    OnlyInDest {
+        /// Key relative to the destination prefix
+        key: String,
+        /// Destination object
+        dest: Object,
+    },
+    /// Object exists on both sides but its ETag or size differs
+    Different {
+        /// Key relative to the prefixes
+        key: String,
+        /// Source object
+        source: Object,
+        /// Destination object
+        dest: Object,
+    },
+}
+
+impl DiffEntry {
+    /// Key relative to the compared prefixes
+    pub fn key(&self) -> &str {
+        match self {
+            DiffEntry::OnlyInSource { key, .. }
+            | DiffEntry::OnlyInDest { key, .. }
+            | DiffEntry::Different { key, .. } => key,
+        }
+    }
+}
+
+/// Stream of differences between two listings
+///
+/// # Caveats
+///
+/// Objects are compared by ETag and size. The ETag of an object uploaded
+/// using multi-part upload differs from the ETag of the same content uploaded
+/// in one piece.
+pub struct DiffStream {
+    source: ObjectStream,
+    source_prefix: String,
+    source_next: Option<Object>,
+    source_done: bool,
+    dest: ObjectStream,
+    dest_prefix: String,
+    dest_next: Option<Object>,
+    dest_done: bool,
+}
+
+impl DiffStream {
+    pub(crate) fn new(
+        source: ObjectStream,
+        source_prefix: String,
+        dest: ObjectStream,
+        dest_prefix: String,
+    ) -> Self {
+        Self {
+            source,
+            source_prefix,
+            source_next: None,
+            source_done: false,
+            dest,
+            dest_prefix,
+            dest_next: None,
+            dest_done: false,
+        }
+    }
+
+    /// Adjust both listings to the quirks described by `compat`
+    pub fn with_compatibility(mut self, compat: &Compatibility) -> Self {
+        self.source = self.source.with_compatibility(compat);
+        self.dest = self.dest.with_compatibility(compat);
+        self
+    }
+
+    /// Agree to pay for the listings, required to compare prefixes of
+    /// requester-pays buckets
+    pub fn with_requester_pays(mut self) -> Self {
+        self.source = self.source.with_requester_pays();
+        self.dest = self.dest.with_requester_pays();
+        self
+    }
+
+    fn relative_key(object: &Object, prefix: &str) -> S3ExtResult<String> {
+        let key = object
+            .key
+            .as_deref()
+            .ok_or(S3ExtError::Other("response is missing key"))?;
+        Ok(key.strip_prefix(prefix).unwrap_or(key).to_owned())
+    }
+}
+
+impl Stream for DiffStream {
+    type Item = S3ExtResult<DiffEntry>;
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.as_mut().get_mut();
+        loop {
+            if this.source_next.is_none() && !this.source_done {
+                match ready!(Pin::new(&mut this.source).poll_next(cx)) {
+                    Some(Ok(object)) => this.source_next = Some(object),
+                    Some(Err(e)) => return Poll::Ready(Some(Err(e.into()))),
+                    None => this.source_done = true,
+                }
+            }
+            if this.dest_next.is_none() && !this.dest_done {
+                match ready!(Pin::new(&mut this.dest).poll_next(cx)) {
+                    Some(Ok(object)) => this.dest_next = Some(object),
+                    Some(Err(e)) => return Poll::Ready(Some(Err(e.into()))),
+                    None => this.dest_done = true,
+                }
+            }
+
+            let entry = match (this.source_next.take(), this.dest_next.take()) {
+                (None, None) => return Poll::Ready(None),
+                (Some(source), None) => match Self::relative_key(&source, &this.source_prefix) {
+                    Ok(key) => Ok(DiffEntry::OnlyInSource { key, source }),
+                    Err(e) => Err(e),
+                },
+                (None, Some(dest)) => match Self::relative_key(&dest, &this.dest_prefix) {
+                    Ok(key) => Ok(DiffEntry::OnlyInDest { key, dest }),
+                    Err(e) => Err(e),
+                },
+                (Some(source), Some(dest)) => {
+                    let source_key = match Self::relative_key(&source, &this.source_prefix) {
+                        Ok(key) => key,
+                        Err(e) => return Poll::Ready(Some(Err(e))),
+                    };
+                    let dest_key = match Self::relative_key(&dest, &this.dest_prefix) {
+                        Ok(key) => key,
+                        Err(e) => return Poll::Ready(Some(Err(e))),
+                    };
+                    match source_key.cmp(&dest_key) {
+                        Ordering::Less => {
+                            this.dest_next = Some(dest);
+                            Ok(DiffEntry::OnlyInSource {
+                                key: source_key,
+                                source,
+                            })
+                        }
+                        Ordering::Greater => {
+                            this.source_next = Some(source);
+                            Ok(DiffEntry::OnlyInDest {
+                                key: dest_key,
+                                dest,
+                            })
+                        }
+                        Ordering::Equal => {
+                            if source.e_tag == dest.e_tag && source.size == dest.size {
+                                continue;
+                            }
+                            Ok(DiffEntry::Different {
+                                key: source_key,
+                                source,
+                                dest,
+                            })
+                        }
+                    }
+                }
+            };
+            return Poll::Ready(Some(entry));
+        }
+    }
+}
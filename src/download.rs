@@ -0,0 +1,179 @@
+//! Concurrent downloads of many objects
+//!
+//! See [`S3Ext::download_objects`](crate::S3Ext::download_objects), which
+//! writes objects to files below a directory, and
+//! [`S3Ext::download_objects_with`](crate::S3Ext::download_objects_with),
+//! which writes them to writers opened by a closure.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) {
+//! use s3_ext::S3Ext;
+//!
+//! let keys = vec!["2024/01.csv", "2024/02.csv", "2024/03.csv"];
+//! let report = client
+//!     .download_objects("reports", keys, "/tmp/reports", 8)
+//!     .await;
+//! println!("{} bytes in {:?}", report.stats.bytes, report.stats.duration);
+//! for (key, error) in report.failed() {
+//!     println!("{} failed: {}", key, error);
+//! }
+//! # }
+//! ```
+
+use crate::{
+    backup::local_path,
+    error::{S3ExtError, S3ExtResult},
+    stats::TransferStats,
+    S3Ext,
+};
+use futures::stream::{self, StreamExt};
+use rusoto_s3::GetObjectRequest;
+use std::{path::Path, time::Instant};
+use tokio::{
+    fs,
+    io::{AsyncWrite, AsyncWriteExt},
+};
+
+/// Outcome of downloading many objects
+#[derive(Debug)]
+pub struct DownloadReport {
+    /// Keys in the given order, with the statistics of their download or the
+    /// error
+    pub results: Vec<(String, S3ExtResult<TransferStats>)>,
+    /// Bytes, parts and retries of all successful downloads, along with the
+    /// wall-clock duration of the whole batch
+    pub stats: TransferStats,
+}
+
+impl DownloadReport {
+    /// Whether all objects were downloaded
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(|(_, result)| result.is_ok())
+    }
+
+    /// Keys of the objects that couldn't be downloaded, with the error
+    pub fn failed(&self) -> impl Iterator<Item = (&str, &S3ExtError)> {
+        self.results
+            .iter()
+            .filter_map(|(key, result)| match result {
+                Ok(_) => None,
+                Err(e) => Some((key.as_str(), e)),
+            })
+    }
+
+    fn new(results: Vec<(String, S3ExtResult<TransferStats>)>, started: Instant) -> Self {
+        let mut stats = TransferStats {
+            duration: started.elapsed(),
+            ..Default::default()
+        };
+        for transfer in results
+            .iter()
+            .filter_map(|(_, result)| result.as_ref().ok())
+        {
+            stats.bytes += transfer.bytes;
+            stats.parts += transfer.parts;
+            stats.retries += transfer.retries;
+        }
+        DownloadReport { results, stats }
+    }
+}
+
+fn request(bucket: &str, key: &str) -> GetObjectRequest {
+    GetObjectRequest {
+        bucket: bucket.to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    }
+}
+
+async fn download_to_dir<C>(
+    client: &C,
+    bucket: &str,
+    key: &str,
+    dir: &Path,
+) -> S3ExtResult<TransferStats>
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    let path = local_path(dir, key).ok_or_else(|| {
+        S3ExtError::Other("key can't be used as relative path").with_object(bucket, key)
+    })?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let (_, stats) = client
+        .download_to_file_with_stats(request(bucket, key), &path)
+        .await?;
+    Ok(stats)
+}
+
+async fn download_to_writer<C, F, W>(
+    client: &C,
+    bucket: &str,
+    key: &str,
+    target: &F,
+) -> S3ExtResult<TransferStats>
+where
+    C: S3Ext + Sync + ?Sized,
+    F: Fn(&str) -> S3ExtResult<W>,
+    W: AsyncWrite + Unpin + Send,
+{
+    let mut writer = target(key)?;
+    let (_, stats) = client
+        .download_with_stats(request(bucket, key), &mut writer)
+        .await?;
+    writer.flush().await?;
+    Ok(stats)
+}
+
+pub(crate) async fn download_objects<C>(
+    client: &C,
+    bucket: String,
+    keys: Vec<String>,
+    dir: &Path,
+    concurrency: usize,
+) -> DownloadReport
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    let bucket = &bucket;
+    let started = Instant::now();
+    let results = stream::iter(keys)
+        .map(|key| async move {
+            let result = download_to_dir(client, bucket, &key, dir).await;
+            (key, result)
+        })
+        // `buffered` doesn't make progress with a limit of 0
+        .buffered(concurrency.max(1))
+        .collect()
+        .await;
+    DownloadReport::new(results, started)
+}
+
+pub(crate) async fn download_objects_with<C, F, W>(
+    client: &C,
+    bucket: String,
+    keys: Vec<String>,
+    target: F,
+    concurrency: usize,
+) -> DownloadReport
+where
+    C: S3Ext + Sync + ?Sized,
+    F: Fn(&str) -> S3ExtResult<W> + Send + Sync,
+    W: AsyncWrite + Unpin + Send,
+{
+    let (bucket, target) = (&bucket, &target);
+    let started = Instant::now();
+    let results = stream::iter(keys)
+        .map(|key| async move {
+            let result = download_to_writer(client, bucket, &key, target).await;
+            (key, result)
+        })
+        // `buffered` doesn't make progress with a limit of 0
+        .buffered(concurrency.max(1))
+        .collect()
+        .await;
+    DownloadReport::new(results, started)
+}
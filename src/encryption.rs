@@ -0,0 +1,141 @@
+//! Default encryption of buckets
+//!
+//! See the methods
+//! [`get_bucket_encryption`](crate::bucket_config::BucketConfigExt::get_bucket_encryption),
+//! [`put_bucket_encryption`](crate::bucket_config::BucketConfigExt::put_bucket_encryption) and
+//! [`ensure_encryption`](crate::bucket_config::BucketConfigExt::ensure_encryption) of
+//! [`BucketConfigExt`](crate::bucket_config::BucketConfigExt).
+
+use crate::{
+    backend::Backend,
+    bucket_config::BucketConfigExt,
+    error::{S3ExtError, S3ExtResult},
+};
+use rusoto_s3::{
+    GetBucketEncryptionRequest, PutBucketEncryptionRequest, ServerSideEncryptionByDefault,
+    ServerSideEncryptionConfiguration, ServerSideEncryptionRule,
+};
+
+/// Encryption applied to objects uploaded without encryption headers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BucketEncryption {
+    /// Keys managed by S3 (SSE-S3, `AES256`)
+    SseS3,
+    /// Keys managed by KMS (SSE-KMS, `aws:kms`)
+    SseKms {
+        /// ID or ARN of the KMS key, the AWS managed key `aws/s3` is used if
+        /// not set
+        key_id: Option<String>,
+        /// Use an S3 Bucket Key to reduce KMS requests
+        bucket_key: bool,
+    },
+}
+
+impl BucketEncryption {
+    /// SSE-KMS using key `key_id` and an S3 Bucket Key
+    pub fn kms(key_id: impl Into<String>) -> Self {
+        BucketEncryption::SseKms {
+            key_id: Some(key_id.into()),
+            bucket_key: true,
+        }
+    }
+
+    /// Convert into a Rusoto `ServerSideEncryptionConfiguration`
+    pub fn to_configuration(&self) -> ServerSideEncryptionConfiguration {
+        let rule = match self {
+            BucketEncryption::SseS3 => ServerSideEncryptionRule {
+                apply_server_side_encryption_by_default: Some(ServerSideEncryptionByDefault {
+                    sse_algorithm: "AES256".to_owned(),
+                    kms_master_key_id: None,
+                }),
+                bucket_key_enabled: None,
+            },
+            BucketEncryption::SseKms { key_id, bucket_key } => ServerSideEncryptionRule {
+                apply_server_side_encryption_by_default: Some(ServerSideEncryptionByDefault {
+                    sse_algorithm: "aws:kms".to_owned(),
+                    kms_master_key_id: key_id.clone(),
+                }),
+                bucket_key_enabled: Some(*bucket_key),
+            },
+        };
+        ServerSideEncryptionConfiguration { rules: vec![rule] }
+    }
+
+    /// Convert a Rusoto `ServerSideEncryptionConfiguration`
+    ///
+    /// Returns `None` if no rule applies a default encryption. Algorithms
+    /// other than `AES256` and `aws:kms`, e.g. DSSE-KMS, are rejected.
+    pub fn from_configuration(
+        configuration: ServerSideEncryptionConfiguration,
+    ) -> S3ExtResult<Option<Self>> {
+        let rule = configuration.rules.into_iter().find_map(|rule| {
+            Some((
+                rule.apply_server_side_encryption_by_default?,
+                rule.bucket_key_enabled,
+            ))
+        });
+        let (default, bucket_key) = match rule {
+            Some(rule) => rule,
+            None => return Ok(None),
+        };
+        match default.sse_algorithm.as_str() {
+            "AES256" => Ok(Some(BucketEncryption::SseS3)),
+            "aws:kms" => Ok(Some(BucketEncryption::SseKms {
+                key_id: default.kms_master_key_id,
+                bucket_key: bucket_key.unwrap_or(false),
+            })),
+            _ => Err(S3ExtError::Other("unsupported encryption algorithm")),
+        }
+    }
+}
+
+pub(crate) async fn get_bucket_encryption(
+    client: &Backend,
+    bucket: String,
+) -> S3ExtResult<Option<BucketEncryption>> {
+    let request = GetBucketEncryptionRequest {
+        bucket,
+        ..Default::default()
+    };
+    match client.get_bucket_encryption(request).await {
+        Ok(output) => match output.server_side_encryption_configuration {
+            Some(configuration) => BucketEncryption::from_configuration(configuration),
+            None => Ok(None),
+        },
+        Err(e) => {
+            let e = S3ExtError::from(e);
+            match e.code() {
+                Some("ServerSideEncryptionConfigurationNotFoundError") => Ok(None),
+                _ => Err(e),
+            }
+        }
+    }
+}
+
+pub(crate) async fn put_bucket_encryption(
+    client: &Backend,
+    bucket: String,
+    encryption: &BucketEncryption,
+) -> S3ExtResult<()> {
+    let request = PutBucketEncryptionRequest {
+        bucket,
+        server_side_encryption_configuration: encryption.to_configuration(),
+        ..Default::default()
+    };
+    Ok(client.put_bucket_encryption(request).await?)
+}
+
+pub(crate) async fn ensure_encryption<C>(
+    client: &C,
+    bucket: String,
+    encryption: &BucketEncryption,
+) -> S3ExtResult<bool>
+where
+    C: BucketConfigExt + Sync + ?Sized,
+{
+    if client.get_bucket_encryption(bucket.clone()).await?.as_ref() == Some(encryption) {
+        return Ok(false);
+    }
+    client.put_bucket_encryption(bucket, encryption).await?;
+    Ok(true)
+}
@@ -1,9 +1,25 @@
-use rusoto_core::{request::TlsError, HttpDispatchError, RusotoError};
+use chrono::{DateTime, Utc};
+use rusoto_core::{
+    request::{BufferedHttpResponse, TlsError},
+    HttpDispatchError, RusotoError,
+};
+use rusoto_credential::CredentialsError;
 use rusoto_s3::{
-    CompleteMultipartUploadError, CreateBucketError, CreateMultipartUploadError, GetObjectError,
-    ListObjectsV2Error, PutObjectError, UploadPartError,
+    AbortMultipartUploadError, CompleteMultipartUploadError, CopyObjectError, CreateBucketError,
+    CreateMultipartUploadError, DeleteBucketError,
+    DeleteBucketIntelligentTieringConfigurationError, DeleteBucketPolicyError,
+    DeleteBucketReplicationError, DeleteBucketTaggingError, DeleteBucketWebsiteError,
+    DeleteObjectError, DeleteObjectTaggingError, DeleteObjectsError, GetBucketEncryptionError,
+    GetBucketIntelligentTieringConfigurationError, GetBucketLocationError, GetBucketLoggingError,
+    GetBucketPolicyError, GetBucketReplicationError, GetBucketTaggingError, GetBucketWebsiteError,
+    GetObjectAclError, GetObjectError, GetObjectTaggingError, HeadBucketError, HeadObjectError,
+    ListBucketIntelligentTieringConfigurationsError, ListMultipartUploadsError, ListObjectsV2Error,
+    ListPartsError, PutBucketEncryptionError, PutBucketIntelligentTieringConfigurationError,
+    PutBucketLoggingError, PutBucketPolicyError, PutBucketReplicationError, PutBucketTaggingError,
+    PutBucketWebsiteError, PutObjectAclError, PutObjectError, PutObjectTaggingError,
+    RestoreObjectError, SelectObjectContentError, UploadPartCopyError, UploadPartError,
 };
-use std::io::Error as IoError;
+use std::{io::Error as IoError, sync::Arc, time::Duration};
 use thiserror::Error;
 
 pub type S3ExtResult<T> = Result<T, S3ExtError>;
@@ -15,43 +31,698 @@ pub enum S3ExtError {
     #[error("Unknown error {0}")]
     Other(&'static str),
 
+    /// Operation didn't complete within the given duration
+    #[error("Operation timed out after {0:?}")]
+    Timeout(Duration),
+
+    /// Failure of a request shared by several operations, see
+    /// [`coalesce`](crate::coalesce)
+    #[error("{0}")]
+    Shared(Arc<S3ExtError>),
+
+    /// Operation on an object failed
+    #[error(
+        "{source} (bucket: {bucket}, key: {key}{})",
+        request_ids(.source)
+    )]
+    Object {
+        /// Bucket of the object
+        bucket: String,
+        /// Key of the object
+        key: String,
+        /// Error that occurred
+        source: Box<S3ExtError>,
+    },
+
+    /// Part size of a multi-part upload is out of range
+    #[error("Invalid part size {part_size}: {reason}")]
+    InvalidPartSize {
+        /// Requested part size
+        part_size: usize,
+        /// Limit violated
+        reason: &'static str,
+    },
+
+    /// Multi-part upload failed and aborting it failed as well
+    ///
+    /// The parts uploaded so far are stored (and billed) until the upload is
+    /// aborted, e.g. by a lifecycle rule or a later `AbortMultipartUpload`
+    /// request for `upload_id`.
+    #[error("{source} (aborting upload {upload_id} of {bucket}/{key} failed: {abort_error})")]
+    UploadLeaked {
+        /// Bucket of the upload
+        bucket: String,
+        /// Key of the upload
+        key: String,
+        /// ID of the upload which is still in progress
+        upload_id: String,
+        /// Error that made the upload fail
+        source: Box<S3ExtError>,
+        /// Error that occurred while aborting the upload
+        abort_error: Box<S3ExtError>,
+    },
+
+    /// Object or document exceeds the size limit of the operation
+    #[error("Object exceeds size limit of {limit} bytes")]
+    TooLarge {
+        /// Limit in bytes
+        limit: u64,
+    },
+
+    /// Content of an object doesn't match its checksum
+    #[error("Checksum mismatch, expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        /// Checksum the content was expected to have, hex encoded
+        expected: String,
+        /// Checksum of the content received, hex encoded
+        actual: String,
+    },
+
+    /// Bucket policy document is malformed
+    #[error("Invalid bucket policy: {0}")]
+    InvalidPolicy(String),
+
+    /// Presigned request would be valid for longer than S3 accepts, see
+    /// [`MAX_EXPIRES_IN`](crate::presign::MAX_EXPIRES_IN)
+    #[error("Presigned requests expire after at most seven days, got {0:?}")]
+    InvalidExpiry(Duration),
+
+    /// User metadata can't be stored by S3
+    #[error("Invalid user metadata {name:?}: {reason}")]
+    InvalidMetadata {
+        /// Name of the offending entry, empty if the size limit is exceeded
+        name: String,
+        /// Rule violated
+        reason: &'static str,
+    },
+
+    /// Tag can't be stored by S3
+    #[error("Invalid tag {key:?}: {reason}")]
+    InvalidTag {
+        /// Key of the offending tag, empty if there are too many tags
+        key: String,
+        /// Rule violated
+        reason: &'static str,
+    },
+
+    /// Entry of a batch manifest can't be parsed
+    #[error("Invalid manifest entry {entry}: {reason}")]
+    InvalidManifest {
+        /// Number of the offending entry, starting at 1, or 0 if the
+        /// manifest as a whole is malformed
+        entry: usize,
+        /// What's wrong with it
+        reason: &'static str,
+    },
+
+    /// Lease is held by someone else, see [`lease`](crate::lease)
+    #[error("Lease is held until {expires_at}")]
+    LeaseHeld {
+        /// Time the lease expires unless it is renewed
+        expires_at: DateTime<Utc>,
+    },
+
+    /// Lease expired and was taken over, or its marker was removed, see
+    /// [`lease`](crate::lease)
+    #[error("Lease was lost")]
+    LeaseLost,
+
+    /// JSON (de)serialization failed
+    #[cfg(feature = "serde_json")]
+    #[error("JSON Error {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// Serialization or deserialization using a codec failed
+    #[error("Codec Error {0}")]
+    Codec(Box<dyn std::error::Error + Send + Sync>),
+
     /// I/O Error
     #[error("I/O Error {0}")]
     IoError(#[from] IoError),
 
+    /// Rusoto AbortMultipartUploadError
+    #[error("Rusoto AbortMultipartUploadError {0}")]
+    AbortMultipartUploadError(#[source] Box<RusotoError<AbortMultipartUploadError>>),
+
     /// Rusoto CompleteMultipartUploadError
     #[error("Rusoto CompleteMultipartUploadError {0}")]
-    CompleteMultipartUploadError(#[from] RusotoError<CompleteMultipartUploadError>),
+    CompleteMultipartUploadError(#[source] Box<RusotoError<CompleteMultipartUploadError>>),
+
+    /// Rusoto CopyObjectError
+    #[error("Rusoto CopyObjectError {0}")]
+    CopyObjectError(#[source] Box<RusotoError<CopyObjectError>>),
 
     /// Rusoto CreateMultipartUploadError
     #[error("Rusoto CreateMultipartUploadError {0}")]
-    CreateMultipartUploadError(#[from] RusotoError<CreateMultipartUploadError>),
+    CreateMultipartUploadError(#[source] Box<RusotoError<CreateMultipartUploadError>>),
+
+    /// Rusoto DeleteBucketError
+    #[error("Rusoto DeleteBucketError {0}")]
+    DeleteBucketError(#[source] Box<RusotoError<DeleteBucketError>>),
+
+    /// Rusoto DeleteObjectError
+    #[error("Rusoto DeleteObjectError {0}")]
+    DeleteObjectError(#[source] Box<RusotoError<DeleteObjectError>>),
+
+    /// Rusoto DeleteObjectsError
+    #[error("Rusoto DeleteObjectsError {0}")]
+    DeleteObjectsError(#[source] Box<RusotoError<DeleteObjectsError>>),
+
+    /// Rusoto GetBucketLocationError
+    #[error("Rusoto GetBucketLocationError {0}")]
+    GetBucketLocationError(#[source] Box<RusotoError<GetBucketLocationError>>),
 
     /// Rusoto GetObjectError
     #[error("Rusoto GetObjectError {0}")]
-    GetObjectError(#[from] RusotoError<GetObjectError>),
+    GetObjectError(#[source] Box<RusotoError<GetObjectError>>),
+
+    /// Rusoto HeadBucketError
+    #[error("Rusoto HeadBucketError {0}")]
+    HeadBucketError(#[source] Box<RusotoError<HeadBucketError>>),
+
+    /// Rusoto HeadObjectError
+    #[error("Rusoto HeadObjectError {0}")]
+    HeadObjectError(#[source] Box<RusotoError<HeadObjectError>>),
 
     /// Rusoto HttpDispatchError
     #[error("Rusoto HttpDispatchError {0}")]
-    HttpDispatchError(#[from] RusotoError<HttpDispatchError>),
+    HttpDispatchError(#[source] Box<RusotoError<HttpDispatchError>>),
+
+    /// Rusoto ListMultipartUploadsError
+    #[error("Rusoto ListMultipartUploadsError {0}")]
+    ListMultipartUploadsError(#[source] Box<RusotoError<ListMultipartUploadsError>>),
 
     /// Rusoto ListObjectV2Error
     #[error("Rusoto ListObjectV2Error {0}")]
-    ListObjectV2Error(#[from] RusotoError<ListObjectsV2Error>),
+    ListObjectV2Error(#[source] Box<RusotoError<ListObjectsV2Error>>),
+
+    /// Rusoto ListPartsError
+    #[error("Rusoto ListPartsError {0}")]
+    ListPartsError(#[source] Box<RusotoError<ListPartsError>>),
 
     /// Rusoto PutObjectError
     #[error("Rusoto PutObjectError {0}")]
-    PutObjectError(#[from] RusotoError<PutObjectError>),
+    PutObjectError(#[source] Box<RusotoError<PutObjectError>>),
+
+    /// Rusoto SelectObjectContentError
+    #[error("Rusoto SelectObjectContentError {0}")]
+    SelectObjectContentError(#[source] Box<RusotoError<SelectObjectContentError>>),
+
+    /// Rusoto GetObjectTaggingError
+    #[error("Rusoto GetObjectTaggingError {0}")]
+    GetObjectTaggingError(#[source] Box<RusotoError<GetObjectTaggingError>>),
+
+    /// Rusoto PutObjectTaggingError
+    #[error("Rusoto PutObjectTaggingError {0}")]
+    PutObjectTaggingError(#[source] Box<RusotoError<PutObjectTaggingError>>),
+
+    /// Rusoto DeleteObjectTaggingError
+    #[error("Rusoto DeleteObjectTaggingError {0}")]
+    DeleteObjectTaggingError(#[source] Box<RusotoError<DeleteObjectTaggingError>>),
+
+    /// Rusoto GetObjectAclError
+    #[error("Rusoto GetObjectAclError {0}")]
+    GetObjectAclError(#[source] Box<RusotoError<GetObjectAclError>>),
+
+    /// Rusoto PutObjectAclError
+    #[error("Rusoto PutObjectAclError {0}")]
+    PutObjectAclError(#[source] Box<RusotoError<PutObjectAclError>>),
+
+    /// Rusoto GetBucketPolicyError
+    #[error("Rusoto GetBucketPolicyError {0}")]
+    GetBucketPolicyError(#[source] Box<RusotoError<GetBucketPolicyError>>),
+
+    /// Rusoto PutBucketPolicyError
+    #[error("Rusoto PutBucketPolicyError {0}")]
+    PutBucketPolicyError(#[source] Box<RusotoError<PutBucketPolicyError>>),
+
+    /// Rusoto DeleteBucketPolicyError
+    #[error("Rusoto DeleteBucketPolicyError {0}")]
+    DeleteBucketPolicyError(#[source] Box<RusotoError<DeleteBucketPolicyError>>),
+
+    /// Rusoto GetBucketEncryptionError
+    #[error("Rusoto GetBucketEncryptionError {0}")]
+    GetBucketEncryptionError(#[source] Box<RusotoError<GetBucketEncryptionError>>),
+
+    /// Rusoto PutBucketEncryptionError
+    #[error("Rusoto PutBucketEncryptionError {0}")]
+    PutBucketEncryptionError(#[source] Box<RusotoError<PutBucketEncryptionError>>),
+
+    /// Rusoto GetBucketReplicationError
+    #[error("Rusoto GetBucketReplicationError {0}")]
+    GetBucketReplicationError(#[source] Box<RusotoError<GetBucketReplicationError>>),
+
+    /// Rusoto PutBucketReplicationError
+    #[error("Rusoto PutBucketReplicationError {0}")]
+    PutBucketReplicationError(#[source] Box<RusotoError<PutBucketReplicationError>>),
+
+    /// Rusoto DeleteBucketReplicationError
+    #[error("Rusoto DeleteBucketReplicationError {0}")]
+    DeleteBucketReplicationError(#[source] Box<RusotoError<DeleteBucketReplicationError>>),
+
+    /// Rusoto GetBucketWebsiteError
+    #[error("Rusoto GetBucketWebsiteError {0}")]
+    GetBucketWebsiteError(#[source] Box<RusotoError<GetBucketWebsiteError>>),
+
+    /// Rusoto PutBucketWebsiteError
+    #[error("Rusoto PutBucketWebsiteError {0}")]
+    PutBucketWebsiteError(#[source] Box<RusotoError<PutBucketWebsiteError>>),
+
+    /// Rusoto DeleteBucketWebsiteError
+    #[error("Rusoto DeleteBucketWebsiteError {0}")]
+    DeleteBucketWebsiteError(#[source] Box<RusotoError<DeleteBucketWebsiteError>>),
+
+    /// Rusoto RestoreObjectError
+    #[error("Rusoto RestoreObjectError {0}")]
+    RestoreObjectError(#[source] Box<RusotoError<RestoreObjectError>>),
+
+    /// Rusoto GetBucketTaggingError
+    #[error("Rusoto GetBucketTaggingError {0}")]
+    GetBucketTaggingError(#[source] Box<RusotoError<GetBucketTaggingError>>),
+
+    /// Rusoto PutBucketTaggingError
+    #[error("Rusoto PutBucketTaggingError {0}")]
+    PutBucketTaggingError(#[source] Box<RusotoError<PutBucketTaggingError>>),
+
+    /// Rusoto DeleteBucketTaggingError
+    #[error("Rusoto DeleteBucketTaggingError {0}")]
+    DeleteBucketTaggingError(#[source] Box<RusotoError<DeleteBucketTaggingError>>),
+
+    /// Rusoto GetBucketLoggingError
+    #[error("Rusoto GetBucketLoggingError {0}")]
+    GetBucketLoggingError(#[source] Box<RusotoError<GetBucketLoggingError>>),
+
+    /// Rusoto PutBucketLoggingError
+    #[error("Rusoto PutBucketLoggingError {0}")]
+    PutBucketLoggingError(#[source] Box<RusotoError<PutBucketLoggingError>>),
+
+    /// Rusoto GetBucketIntelligentTieringConfigurationError
+    #[error("Rusoto GetBucketIntelligentTieringConfigurationError {0}")]
+    GetBucketIntelligentTieringConfigurationError(
+        #[source] Box<RusotoError<GetBucketIntelligentTieringConfigurationError>>,
+    ),
+
+    /// Rusoto ListBucketIntelligentTieringConfigurationsError
+    #[error("Rusoto ListBucketIntelligentTieringConfigurationsError {0}")]
+    ListBucketIntelligentTieringConfigurationsError(
+        #[source] Box<RusotoError<ListBucketIntelligentTieringConfigurationsError>>,
+    ),
+
+    /// Rusoto PutBucketIntelligentTieringConfigurationError
+    #[error("Rusoto PutBucketIntelligentTieringConfigurationError {0}")]
+    PutBucketIntelligentTieringConfigurationError(
+        #[source] Box<RusotoError<PutBucketIntelligentTieringConfigurationError>>,
+    ),
+
+    /// Rusoto DeleteBucketIntelligentTieringConfigurationError
+    #[error("Rusoto DeleteBucketIntelligentTieringConfigurationError {0}")]
+    DeleteBucketIntelligentTieringConfigurationError(
+        #[source] Box<RusotoError<DeleteBucketIntelligentTieringConfigurationError>>,
+    ),
 
     /// Rusoto UploadPartError
     #[error("Rusoto UploadPartError {0}")]
-    UploadPartError(#[from] RusotoError<UploadPartError>),
+    UploadPartError(#[source] Box<RusotoError<UploadPartError>>),
+
+    /// Rusoto UploadPartCopyError
+    #[error("Rusoto UploadPartCopyError {0}")]
+    UploadPartCopyError(#[source] Box<RusotoError<UploadPartCopyError>>),
 
     /// Rusoto CreateBucketError
     #[error("Rusoto CreateBucketError {0}")]
-    CreateBucketError(#[from] RusotoError<CreateBucketError>),
+    CreateBucketError(#[source] Box<RusotoError<CreateBucketError>>),
+
+    /// Rusoto CredentialsError
+    #[error("Rusoto CredentialsError {0}")]
+    CredentialsError(#[from] CredentialsError),
 
     /// Rusoto request TlsError
     #[error("Rusoto TlsError {0}")]
     TlsError(#[from] TlsError),
 }
+
+// Rusoto errors are boxed, they would make every `S3ExtResult` as large as
+// the buffered HTTP response they contain
+macro_rules! from_rusoto_error {
+    ($($variant:ident($error:ty)),* $(,)?) => {
+        $(
+            impl From<RusotoError<$error>> for S3ExtError {
+                fn from(error: RusotoError<$error>) -> Self {
+                    S3ExtError::$variant(Box::new(error))
+                }
+            }
+
+            impl From<Box<RusotoError<$error>>> for S3ExtError {
+                fn from(error: Box<RusotoError<$error>>) -> Self {
+                    S3ExtError::$variant(error)
+                }
+            }
+        )*
+    };
+}
+
+from_rusoto_error!(
+    AbortMultipartUploadError(AbortMultipartUploadError),
+    CompleteMultipartUploadError(CompleteMultipartUploadError),
+    CopyObjectError(CopyObjectError),
+    CreateMultipartUploadError(CreateMultipartUploadError),
+    DeleteBucketError(DeleteBucketError),
+    DeleteObjectError(DeleteObjectError),
+    DeleteObjectsError(DeleteObjectsError),
+    GetBucketLocationError(GetBucketLocationError),
+    GetObjectError(GetObjectError),
+    HeadBucketError(HeadBucketError),
+    HeadObjectError(HeadObjectError),
+    HttpDispatchError(HttpDispatchError),
+    ListMultipartUploadsError(ListMultipartUploadsError),
+    ListObjectV2Error(ListObjectsV2Error),
+    ListPartsError(ListPartsError),
+    PutObjectError(PutObjectError),
+    SelectObjectContentError(SelectObjectContentError),
+    GetObjectTaggingError(GetObjectTaggingError),
+    PutObjectTaggingError(PutObjectTaggingError),
+    DeleteObjectTaggingError(DeleteObjectTaggingError),
+    GetObjectAclError(GetObjectAclError),
+    PutObjectAclError(PutObjectAclError),
+    GetBucketPolicyError(GetBucketPolicyError),
+    PutBucketPolicyError(PutBucketPolicyError),
+    DeleteBucketPolicyError(DeleteBucketPolicyError),
+    GetBucketEncryptionError(GetBucketEncryptionError),
+    PutBucketEncryptionError(PutBucketEncryptionError),
+    GetBucketReplicationError(GetBucketReplicationError),
+    PutBucketReplicationError(PutBucketReplicationError),
+    DeleteBucketReplicationError(DeleteBucketReplicationError),
+    GetBucketWebsiteError(GetBucketWebsiteError),
+    PutBucketWebsiteError(PutBucketWebsiteError),
+    DeleteBucketWebsiteError(DeleteBucketWebsiteError),
+    RestoreObjectError(RestoreObjectError),
+    GetBucketTaggingError(GetBucketTaggingError),
+    PutBucketTaggingError(PutBucketTaggingError),
+    DeleteBucketTaggingError(DeleteBucketTaggingError),
+    GetBucketLoggingError(GetBucketLoggingError),
+    PutBucketLoggingError(PutBucketLoggingError),
+    UploadPartError(UploadPartError),
+    UploadPartCopyError(UploadPartCopyError),
+    CreateBucketError(CreateBucketError),
+    GetBucketIntelligentTieringConfigurationError(GetBucketIntelligentTieringConfigurationError),
+    ListBucketIntelligentTieringConfigurationsError(
+        ListBucketIntelligentTieringConfigurationsError
+    ),
+    PutBucketIntelligentTieringConfigurationError(PutBucketIntelligentTieringConfigurationError),
+    DeleteBucketIntelligentTieringConfigurationError(
+        DeleteBucketIntelligentTieringConfigurationError
+    ),
+);
+
+impl S3ExtError {
+    /// Failure is likely transient and the operation worth retrying
+    ///
+    /// This is the case for timeouts, connection errors, throttling and
+    /// `500`, `502`, `503` and `504` responses.
+    pub fn is_retryable(&self) -> bool {
+        match self.inner() {
+            S3ExtError::Timeout(_) => true,
+            e => e.failure().is_retryable(),
+        }
+    }
+
+    /// Request was rejected due to throttling (`503 SlowDown` or
+    /// `429 Too Many Requests`)
+    pub fn is_throttling(&self) -> bool {
+        match self.inner().failure() {
+            Failure::Response(response) => matches!(response.status.as_u16(), 429 | 503),
+            _ => false,
+        }
+    }
+
+    /// Bucket, object or multi-part upload doesn't exist
+    pub fn is_not_found(&self) -> bool {
+        let service = match self.inner() {
+            S3ExtError::AbortMultipartUploadError(e) => matches!(
+                **e,
+                RusotoError::Service(AbortMultipartUploadError::NoSuchUpload(_))
+            ),
+            S3ExtError::GetObjectError(e) => {
+                matches!(**e, RusotoError::Service(GetObjectError::NoSuchKey(_)))
+            }
+            S3ExtError::GetObjectAclError(e) => {
+                matches!(**e, RusotoError::Service(GetObjectAclError::NoSuchKey(_)))
+            }
+            S3ExtError::PutObjectAclError(e) => {
+                matches!(**e, RusotoError::Service(PutObjectAclError::NoSuchKey(_)))
+            }
+            S3ExtError::HeadObjectError(e) => {
+                matches!(**e, RusotoError::Service(HeadObjectError::NoSuchKey(_)))
+            }
+            S3ExtError::ListObjectV2Error(e) => matches!(
+                **e,
+                RusotoError::Service(ListObjectsV2Error::NoSuchBucket(_))
+            ),
+            _ => false,
+        };
+        service
+            || match self.inner().failure() {
+                Failure::Response(response) => response.status.as_u16() == 404,
+                _ => false,
+            }
+    }
+
+    /// Conditional request failed because the object still matches the
+    /// `If-None-Match` or `If-Modified-Since` condition (`304 Not Modified`)
+    pub fn is_not_modified(&self) -> bool {
+        match self.inner().failure() {
+            Failure::Response(response) => response.status.as_u16() == 304,
+            _ => false,
+        }
+    }
+
+    // Operation isn't implemented by the provider (`501 Not Implemented`)
+    pub(crate) fn is_not_implemented(&self) -> bool {
+        match self.inner().failure() {
+            Failure::Response(response) => {
+                response.status.as_u16() == 501
+                    || xml_element(response.body_as_str(), "Code") == Some("NotImplemented")
+            }
+            _ => false,
+        }
+    }
+
+    /// ID S3 assigned to the failed request (`x-amz-request-id`)
+    ///
+    /// Only available if the error response wasn't modelled by Rusoto,
+    /// which is the case for most failures except e.g. `NoSuchKey` and
+    /// `NoSuchBucket`.
+    pub fn request_id(&self) -> Option<&str> {
+        match self.failure() {
+            Failure::Response(response) => {
+                response_field(response, "x-amz-request-id", "RequestId")
+            }
+            _ => None,
+        }
+    }
+
+    /// Extended request ID (`x-amz-id-2`) of the failed request, required
+    /// along with the request ID by AWS support
+    ///
+    /// Available under the same conditions as the
+    /// [`request_id`](Self::request_id).
+    pub fn extended_request_id(&self) -> Option<&str> {
+        match self.failure() {
+            Failure::Response(response) => response_field(response, "x-amz-id-2", "HostId"),
+            _ => None,
+        }
+    }
+
+    /// Error code of the failed request, e.g. `NoSuchBucketPolicy`
+    ///
+    /// Available under the same conditions as the
+    /// [`request_id`](Self::request_id).
+    pub fn code(&self) -> Option<&str> {
+        match self.failure() {
+            Failure::Response(response) => xml_element(response.body_as_str(), "Code"),
+            _ => None,
+        }
+    }
+
+    fn failure(&self) -> Failure<'_> {
+        match self {
+            S3ExtError::AbortMultipartUploadError(e) => Failure::of(e),
+            S3ExtError::CompleteMultipartUploadError(e) => Failure::of(e),
+            S3ExtError::CopyObjectError(e) => Failure::of(e),
+            S3ExtError::CreateMultipartUploadError(e) => Failure::of(e),
+            S3ExtError::DeleteBucketError(e) => Failure::of(e),
+            S3ExtError::DeleteObjectError(e) => Failure::of(e),
+            S3ExtError::DeleteObjectsError(e) => Failure::of(e),
+            S3ExtError::GetBucketLocationError(e) => Failure::of(e),
+            S3ExtError::GetObjectError(e) => Failure::of(e),
+            S3ExtError::HeadBucketError(e) => Failure::of(e),
+            S3ExtError::HeadObjectError(e) => Failure::of(e),
+            S3ExtError::HttpDispatchError(e) => Failure::of(e),
+            S3ExtError::ListMultipartUploadsError(e) => Failure::of(e),
+            S3ExtError::ListObjectV2Error(e) => Failure::of(e),
+            S3ExtError::ListPartsError(e) => Failure::of(e),
+            S3ExtError::PutObjectError(e) => Failure::of(e),
+            S3ExtError::SelectObjectContentError(e) => Failure::of(e),
+            S3ExtError::GetObjectTaggingError(e) => Failure::of(e),
+            S3ExtError::PutObjectTaggingError(e) => Failure::of(e),
+            S3ExtError::DeleteObjectTaggingError(e) => Failure::of(e),
+            S3ExtError::GetObjectAclError(e) => Failure::of(e),
+            S3ExtError::PutObjectAclError(e) => Failure::of(e),
+            S3ExtError::GetBucketPolicyError(e) => Failure::of(e),
+            S3ExtError::PutBucketPolicyError(e) => Failure::of(e),
+            S3ExtError::DeleteBucketPolicyError(e) => Failure::of(e),
+            S3ExtError::GetBucketEncryptionError(e) => Failure::of(e),
+            S3ExtError::PutBucketEncryptionError(e) => Failure::of(e),
+            S3ExtError::GetBucketReplicationError(e) => Failure::of(e),
+            S3ExtError::PutBucketReplicationError(e) => Failure::of(e),
+            S3ExtError::DeleteBucketReplicationError(e) => Failure::of(e),
+            S3ExtError::GetBucketWebsiteError(e) => Failure::of(e),
+            S3ExtError::PutBucketWebsiteError(e) => Failure::of(e),
+            S3ExtError::DeleteBucketWebsiteError(e) => Failure::of(e),
+            S3ExtError::RestoreObjectError(e) => Failure::of(e),
+            S3ExtError::GetBucketTaggingError(e) => Failure::of(e),
+            S3ExtError::PutBucketTaggingError(e) => Failure::of(e),
+            S3ExtError::DeleteBucketTaggingError(e) => Failure::of(e),
+            S3ExtError::GetBucketLoggingError(e) => Failure::of(e),
+            S3ExtError::PutBucketLoggingError(e) => Failure::of(e),
+            S3ExtError::GetBucketIntelligentTieringConfigurationError(e) => Failure::of(e),
+            S3ExtError::ListBucketIntelligentTieringConfigurationsError(e) => Failure::of(e),
+            S3ExtError::PutBucketIntelligentTieringConfigurationError(e) => Failure::of(e),
+            S3ExtError::DeleteBucketIntelligentTieringConfigurationError(e) => Failure::of(e),
+            S3ExtError::UploadPartCopyError(e) => Failure::of(e),
+            S3ExtError::UploadPartError(e) => Failure::of(e),
+            S3ExtError::CreateBucketError(e) => Failure::of(e),
+            S3ExtError::Object { source, .. } | S3ExtError::UploadLeaked { source, .. } => {
+                source.failure()
+            }
+            S3ExtError::Shared(e) => e.failure(),
+            S3ExtError::Other(_)
+            | S3ExtError::Timeout(_)
+            | S3ExtError::InvalidPartSize { .. }
+            | S3ExtError::TooLarge { .. }
+            | S3ExtError::ChecksumMismatch { .. }
+            | S3ExtError::InvalidPolicy(_)
+            | S3ExtError::InvalidExpiry(_)
+            | S3ExtError::InvalidMetadata { .. }
+            | S3ExtError::InvalidTag { .. }
+            | S3ExtError::InvalidManifest { .. }
+            | S3ExtError::LeaseHeld { .. }
+            | S3ExtError::LeaseLost
+            | S3ExtError::Codec(_)
+            | S3ExtError::IoError(_)
+            | S3ExtError::CredentialsError(_)
+            | S3ExtError::TlsError(_) => Failure::Other,
+            #[cfg(feature = "serde_json")]
+            S3ExtError::Json(_) => Failure::Other,
+        }
+    }
+
+    /// Bucket of the object the failed operation was performed on
+    pub fn bucket(&self) -> Option<&str> {
+        match self {
+            S3ExtError::Object { bucket, .. } => Some(bucket),
+            S3ExtError::Shared(e) => e.bucket(),
+            _ => None,
+        }
+    }
+
+    /// Key of the object the failed operation was performed on
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            S3ExtError::Object { key, .. } => Some(key),
+            S3ExtError::Shared(e) => e.key(),
+            _ => None,
+        }
+    }
+
+    /// Error without the bucket and key attached
+    pub fn inner(&self) -> &S3ExtError {
+        match self {
+            S3ExtError::Object { source, .. } => source.inner(),
+            S3ExtError::Shared(e) => e.inner(),
+            e => e,
+        }
+    }
+
+    /// Consume the error and return it without the bucket and key attached
+    ///
+    /// Shared errors still referenced elsewhere are returned as they are.
+    pub fn into_inner(self) -> S3ExtError {
+        match self {
+            S3ExtError::Object { source, .. } => source.into_inner(),
+            S3ExtError::Shared(e) => match Arc::try_unwrap(e) {
+                Ok(e) => e.into_inner(),
+                Err(e) => S3ExtError::Shared(e),
+            },
+            e => e,
+        }
+    }
+
+    pub(crate) fn with_object(self, bucket: impl Into<String>, key: impl Into<String>) -> Self {
+        match self {
+            e @ S3ExtError::Object { .. } => e,
+            e @ S3ExtError::Shared(_) if e.key().is_some() => e,
+            e => S3ExtError::Object {
+                bucket: bucket.into(),
+                key: key.into(),
+                source: Box::new(e),
+            },
+        }
+    }
+}
+
+// What went wrong, independent of the operation
+pub(crate) enum Failure<'a> {
+    /// Request couldn't be sent or the response not be received
+    Dispatch,
+    /// Error response not modelled by Rusoto
+    Response(&'a BufferedHttpResponse),
+    Other,
+}
+
+impl<'a> Failure<'a> {
+    pub(crate) fn of<E>(error: &'a RusotoError<E>) -> Self {
+        match error {
+            RusotoError::HttpDispatch(_) => Failure::Dispatch,
+            RusotoError::Unknown(response) => Failure::Response(response),
+            _ => Failure::Other,
+        }
+    }
+
+    pub(crate) fn is_retryable(&self) -> bool {
+        match self {
+            Failure::Dispatch => true,
+            Failure::Response(response) => {
+                matches!(response.status.as_u16(), 429 | 500 | 502 | 503 | 504)
+            }
+            Failure::Other => false,
+        }
+    }
+}
+
+// Value of `header` or, for responses lacking it, element `element` of the
+// XML error document
+fn response_field<'a>(
+    response: &'a BufferedHttpResponse,
+    header: &str,
+    element: &str,
+) -> Option<&'a str> {
+    if let Some(value) = response.headers.get(header) {
+        return Some(value);
+    }
+    xml_element(response.body_as_str(), element)
+}
+
+fn xml_element<'a>(body: &'a str, element: &str) -> Option<&'a str> {
+    let start = body.find(&format!("<{}>", element))? + element.len() + 2;
+    let end = start + body[start..].find(&format!("</{}>", element))?;
+    Some(&body[start..end])
+}
+
+fn request_ids(error: &S3ExtError) -> String {
+    match (error.request_id(), error.extended_request_id()) {
+        (Some(id), Some(id2)) => format!(", request ID: {}, extended request ID: {}", id, id2),
+        (Some(id), None) => format!(", request ID: {}", id),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,90 @@
+//! Expiration of objects by lifecycle rules
+//!
+//! S3 reports when an object is going to be deleted by a lifecycle rule in
+//! the `x-amz-expiration` header of uploads, downloads and `HEAD` requests,
+//! e.g. `expiry-date="Fri, 23 Dec 2012 00:00:00 GMT", rule-id="cleanup"`.
+//! Rusoto hands over the raw value as `expiration`, [`ObjectExpiration`]
+//! parses it into an [`Expiration`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::{expiration::ObjectExpiration, S3Ext};
+//!
+//! let body = client.get_body("bucket", "upload.tmp").await?;
+//! if let Some(expiration) = body.output().expiry() {
+//!     println!("deleted by {} on {}", expiration.rule_id, expiration.expiry_date);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::restore::quoted;
+use chrono::{DateTime, Utc};
+use percent_encoding::percent_decode_str;
+use rusoto_s3::{
+    CompleteMultipartUploadOutput, CopyObjectOutput, GetObjectOutput, HeadObjectOutput,
+    PutObjectOutput,
+};
+
+/// Scheduled deletion of an object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expiration {
+    /// Date the object is deleted
+    pub expiry_date: DateTime<Utc>,
+    /// ID of the lifecycle rule deleting it
+    pub rule_id: String,
+}
+
+impl Expiration {
+    /// Parse the value of the `x-amz-expiration` header, e.g.
+    /// `expiry-date="Fri, 23 Dec 2012 00:00:00 GMT", rule-id="cleanup"`
+    ///
+    /// The rule ID is URL-decoded.
+    pub fn from_header(value: &str) -> Option<Self> {
+        let expiry_date = DateTime::parse_from_rfc2822(quoted(value, "expiry-date")?).ok()?;
+        let rule_id = percent_decode_str(quoted(value, "rule-id")?).decode_utf8_lossy();
+        Some(Expiration {
+            expiry_date: expiry_date.with_timezone(&Utc),
+            rule_id: rule_id.into_owned(),
+        })
+    }
+}
+
+/// Output of a request returning the `x-amz-expiration` header
+pub trait ObjectExpiration {
+    /// Scheduled deletion of the object, `None` if no lifecycle rule
+    /// applies or the header is malformed
+    fn expiry(&self) -> Option<Expiration>;
+}
+
+impl ObjectExpiration for PutObjectOutput {
+    fn expiry(&self) -> Option<Expiration> {
+        self.expiration.as_deref().and_then(Expiration::from_header)
+    }
+}
+
+impl ObjectExpiration for GetObjectOutput {
+    fn expiry(&self) -> Option<Expiration> {
+        self.expiration.as_deref().and_then(Expiration::from_header)
+    }
+}
+
+impl ObjectExpiration for HeadObjectOutput {
+    fn expiry(&self) -> Option<Expiration> {
+        self.expiration.as_deref().and_then(Expiration::from_header)
+    }
+}
+
+impl ObjectExpiration for CopyObjectOutput {
+    fn expiry(&self) -> Option<Expiration> {
+        self.expiration.as_deref().and_then(Expiration::from_header)
+    }
+}
+
+impl ObjectExpiration for CompleteMultipartUploadOutput {
+    fn expiry(&self) -> Option<Expiration> {
+        self.expiration.as_deref().and_then(Expiration::from_header)
+    }
+}
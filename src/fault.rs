@@ -0,0 +1,179 @@
+//! Fault injection for resilience testing
+//!
+//! A [`FaultInjector`] installed using
+//! [`S3ExtClientBuilder::fault_injection`](crate::client::S3ExtClientBuilder::fault_injection)
+//! sits between the client and the network and disturbs individual HTTP
+//! requests, including the parts of multi-part uploads and the pages of
+//! listings, while passing all other requests on to S3.
+//!
+//! # Example
+//!
+//! ```
+//! use s3_ext::{client::S3ExtClientBuilder, fault::FaultInjector};
+//! use std::time::Duration;
+//!
+//! # fn main() -> Result<(), s3_ext::error::S3ExtError> {
+//! let client = S3ExtClientBuilder::new()
+//!     .endpoint("eu-west-1", "http://localhost:9000")
+//!     .fault_injection(
+//!         FaultInjector::new()
+//!             .fail_nth(3)
+//!             .throttle(0.1)
+//!             .latency(Duration::from_millis(500)),
+//!     )
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use futures::{
+    future::FutureExt,
+    stream::{self, StreamExt},
+};
+use hyper::StatusCode;
+use log::debug;
+use rand::Rng;
+use rusoto_core::{
+    request::{DispatchSignedRequestFuture, HttpResponse},
+    signature::SignedRequest,
+    ByteStream, DispatchSignedRequest, HttpDispatchError,
+};
+use std::{
+    collections::HashSet,
+    io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+const SLOW_DOWN: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+    <Error><Code>SlowDown</Code><Message>Please reduce your request rate.</Message></Error>";
+
+/// Configuration of the faults to inject
+///
+/// Requests are numbered from 1 in the order they are sent, retries count
+/// as separate requests.
+#[derive(Debug, Clone, Default)]
+pub struct FaultInjector {
+    fail: HashSet<u64>,
+    throttle: f64,
+    max_latency: Option<Duration>,
+    truncate: f64,
+    requests: Arc<AtomicU64>,
+}
+
+impl FaultInjector {
+    /// Create an injector not injecting any faults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail the `n`th request with a dispatch error, without sending it
+    pub fn fail_nth(mut self, n: u64) -> Self {
+        self.fail.insert(n);
+        self
+    }
+
+    /// Respond to requests with `503 SlowDown` with the given `probability`,
+    /// without sending them
+    pub fn throttle(mut self, probability: f64) -> Self {
+        self.throttle = probability;
+        self
+    }
+
+    /// Delay requests by a random duration of up to `max`
+    pub fn latency(mut self, max: Duration) -> Self {
+        self.max_latency = Some(max);
+        self
+    }
+
+    /// Cut off the body of successful responses halfway through its first
+    /// chunk with the given `probability`, as if the connection was reset
+    pub fn truncate_bodies(mut self, probability: f64) -> Self {
+        self.truncate = probability;
+        self
+    }
+
+    /// Number of requests seen so far
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    async fn dispatch(
+        self,
+        inner: Arc<dyn DispatchSignedRequest + Send + Sync>,
+        request: SignedRequest,
+        timeout: Option<Duration>,
+    ) -> Result<HttpResponse, HttpDispatchError> {
+        let n = self.requests.fetch_add(1, Ordering::SeqCst) + 1;
+        let (delay, throttle, truncate) = {
+            let mut rng = rand::thread_rng();
+            (
+                self.max_latency
+                    .map(|max| rng.gen_range(Duration::ZERO..=max)),
+                rng.gen_bool(self.throttle.clamp(0.0, 1.0)),
+                rng.gen_bool(self.truncate.clamp(0.0, 1.0)),
+            )
+        };
+        if let Some(delay) = delay {
+            debug!("delaying request {} by {:?}", n, delay);
+            tokio::time::sleep(delay).await;
+        }
+        if self.fail.contains(&n) {
+            debug!("failing request {}", n);
+            return Err(HttpDispatchError::new(format!(
+                "injected failure of request {}",
+                n
+            )));
+        }
+        if throttle {
+            debug!("throttling request {}", n);
+            return Ok(HttpResponse {
+                status: StatusCode::SERVICE_UNAVAILABLE,
+                body: ByteStream::from(SLOW_DOWN.as_bytes().to_vec()),
+                headers: Default::default(),
+            });
+        }
+        let mut response = inner.dispatch(request, timeout).await?;
+        if truncate && response.status.is_success() {
+            debug!("truncating response body of request {}", n);
+            response.body = truncated(response.body);
+        }
+        Ok(response)
+    }
+}
+
+// Yield the first chunk, cut in half, and fail afterwards
+fn truncated(body: ByteStream) -> ByteStream {
+    let body = body.into_future().then(|(chunk, _)| async move {
+        let chunk = chunk.map(|chunk| chunk.map(|c| c.slice(..c.len() / 2)));
+        let reset = Err(io::Error::new(
+            io::ErrorKind::ConnectionReset,
+            "injected truncation of response body",
+        ));
+        stream::iter(chunk.into_iter().chain(Some(reset)))
+    });
+    ByteStream::new(body.flatten_stream())
+}
+
+/// HTTP client injecting the faults of a `FaultInjector`
+pub(crate) struct FaultyHttpClient {
+    pub(crate) inner: Arc<dyn DispatchSignedRequest + Send + Sync>,
+    pub(crate) faults: FaultInjector,
+}
+
+impl DispatchSignedRequest for FaultyHttpClient {
+    fn dispatch(
+        &self,
+        request: SignedRequest,
+        timeout: Option<Duration>,
+    ) -> DispatchSignedRequestFuture {
+        Box::pin(
+            self.faults
+                .clone()
+                .dispatch(self.inner.clone(), request, timeout),
+        )
+    }
+}
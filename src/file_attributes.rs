@@ -0,0 +1,188 @@
+//! Modification times and permissions of files stored in object metadata
+//!
+//! See
+//! [`S3Ext::upload_from_file_with_attributes`](crate::S3Ext::upload_from_file_with_attributes)
+//! and
+//! [`S3Ext::download_to_file_with_attributes`](crate::S3Ext::download_to_file_with_attributes).
+//!
+//! The modification time is stored in user metadata entry `mtime` as
+//! seconds since the Unix epoch, the Unix mode bits in entry `mode` as a
+//! decimal number, as done by s3fs. Objects written by s3fs thus restore
+//! their attributes and vice versa. Mode bits are ignored on platforms other
+//! than Unix.
+//!
+//! [`Backup`](crate::backup::Backup) records the attributes of files in its
+//! manifests and restores them along with their content.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+//! use s3_ext::S3Ext;
+//!
+//! let request = PutObjectRequest {
+//!     bucket: "bucket".to_owned(),
+//!     key: "backup.sh".to_owned(),
+//!     ..Default::default()
+//! };
+//! client
+//!     .upload_from_file_with_attributes("/usr/local/bin/backup.sh", request)
+//!     .await?;
+//!
+//! let request = GetObjectRequest {
+//!     bucket: "bucket".to_owned(),
+//!     key: "backup.sh".to_owned(),
+//!     ..Default::default()
+//! };
+//! client
+//!     .download_to_file_with_attributes(request, "/tmp/backup.sh")
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::{S3ExtError, S3ExtResult},
+    S3Ext,
+};
+use rusoto_s3::{GetObjectOutput, GetObjectRequest, PutObjectOutput, PutObjectRequest};
+use std::{
+    collections::HashMap,
+    fs::{self, Metadata},
+    path::{Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
+
+/// User metadata entry holding the modification time
+pub const MTIME_METADATA: &str = "mtime";
+
+/// User metadata entry holding the Unix mode bits
+pub const MODE_METADATA: &str = "mode";
+
+/// Attributes of a file preserved across uploads and downloads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileAttributes {
+    /// Modification time in seconds since the Unix epoch
+    pub mtime: Option<i64>,
+    /// Unix mode bits, including the file type
+    pub mode: Option<u32>,
+}
+
+impl FileAttributes {
+    /// Attributes of the file at `path`
+    pub async fn of_file(path: impl AsRef<Path>) -> S3ExtResult<Self> {
+        Ok(Self::from(&tokio::fs::metadata(path).await?))
+    }
+
+    /// Attributes stored in user `metadata`, entries failing to parse are
+    /// ignored
+    pub fn from_metadata(metadata: &HashMap<String, String>) -> Self {
+        FileAttributes {
+            mtime: metadata.get(MTIME_METADATA).and_then(|v| v.parse().ok()),
+            mode: metadata.get(MODE_METADATA).and_then(|v| v.parse().ok()),
+        }
+    }
+
+    /// Store the attributes in user `metadata`
+    pub fn insert_into(&self, metadata: &mut HashMap<String, String>) {
+        if let Some(mtime) = self.mtime {
+            metadata.insert(MTIME_METADATA.to_owned(), mtime.to_string());
+        }
+        if let Some(mode) = self.mode {
+            metadata.insert(MODE_METADATA.to_owned(), mode.to_string());
+        }
+    }
+
+    /// Set the modification time and permissions of the file at `path`
+    ///
+    /// Missing attributes are left unchanged.
+    pub async fn apply_to_file(&self, path: impl Into<PathBuf>) -> S3ExtResult<()> {
+        let (attributes, path) = (*self, path.into());
+        tokio::task::spawn_blocking(move || attributes.apply_blocking(&path))
+            .await
+            .map_err(|_| S3ExtError::Other("setting file attributes panicked"))?
+    }
+
+    fn apply_blocking(&self, path: &Path) -> S3ExtResult<()> {
+        // before the permissions, which may make the file read-only
+        if let Some(mtime) = self.mtime {
+            let time = if mtime >= 0 {
+                UNIX_EPOCH + Duration::from_secs(mtime as u64)
+            } else {
+                UNIX_EPOCH - Duration::from_secs(mtime.unsigned_abs())
+            };
+            fs::File::options()
+                .write(true)
+                .open(path)?
+                .set_modified(time)?;
+        }
+        #[cfg(unix)]
+        if let Some(mode) = self.mode {
+            use std::os::unix::fs::PermissionsExt;
+
+            fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o7777))?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&Metadata> for FileAttributes {
+    fn from(metadata: &Metadata) -> Self {
+        let mtime = metadata
+            .modified()
+            .ok()
+            .map(|time| match time.duration_since(UNIX_EPOCH) {
+                Ok(since) => since.as_secs() as i64,
+                Err(e) => -(e.duration().as_secs() as i64),
+            });
+        FileAttributes {
+            mtime,
+            mode: mode(metadata),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn mode(metadata: &Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+
+    Some(metadata.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn mode(_metadata: &Metadata) -> Option<u32> {
+    None
+}
+
+pub(crate) async fn upload_from_file<C>(
+    client: &C,
+    source: &Path,
+    mut target: PutObjectRequest,
+) -> S3ExtResult<PutObjectOutput>
+where
+    C: S3Ext + ?Sized,
+{
+    let attributes = FileAttributes::of_file(source).await?;
+    attributes.insert_into(target.metadata.get_or_insert_with(HashMap::new));
+    client.upload_from_file(source, target).await
+}
+
+pub(crate) async fn download_to_file<C>(
+    client: &C,
+    source: GetObjectRequest,
+    target: &Path,
+) -> S3ExtResult<GetObjectOutput>
+where
+    C: S3Ext + ?Sized,
+{
+    let (bucket, key) = (source.bucket.clone(), source.key.clone());
+    let output = client.download_to_file(source, target).await?;
+    if let Some(metadata) = &output.metadata {
+        FileAttributes::from_metadata(metadata)
+            .apply_to_file(target)
+            .await
+            .map_err(|e| e.with_object(bucket, key))?;
+    }
+    Ok(output)
+}
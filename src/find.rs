@@ -0,0 +1,126 @@
+//! Largest, oldest and newest objects below a prefix
+//!
+//! See [`S3Ext::find_largest`](crate::S3Ext::find_largest),
+//! [`S3Ext::find_oldest`](crate::S3Ext::find_oldest) and
+//! [`S3Ext::find_newest`](crate::S3Ext::find_newest). The listing is
+//! streamed, only the `n` objects found so far are kept in memory.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::S3Ext;
+//!
+//! for object in client.find_largest("logs", "2024/", 10).await? {
+//!     println!("{:?}: {:?} bytes", object.key, object.size);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{error::S3ExtResult, timestamps::Timestamps, S3Ext};
+use futures::TryStreamExt;
+use rusoto_s3::Object;
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+};
+
+// Object along with the value it is ranked by, ties are broken by key
+struct Ranked<R> {
+    rank: R,
+    object: Object,
+}
+
+impl<R: Ord> Ord for Ranked<R> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank
+            .cmp(&other.rank)
+            .then_with(|| other.object.key.cmp(&self.object.key))
+    }
+}
+
+impl<R: Ord> PartialOrd for Ranked<R> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<R: Ord> PartialEq for Ranked<R> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<R: Ord> Eq for Ranked<R> {}
+
+/// Objects with `prefix` in `bucket` ranked highest by `rank`, highest
+/// first, skipping objects `rank` returns `None` for
+async fn find_top<C, R, F>(
+    client: &C,
+    bucket: String,
+    prefix: String,
+    n: usize,
+    rank: F,
+) -> S3ExtResult<Vec<Object>>
+where
+    C: S3Ext + Sync + ?Sized,
+    R: Ord + Send,
+    F: Fn(&Object) -> Option<R> + Send,
+{
+    let mut objects = client.stream_objects_with_prefix(bucket, prefix);
+    // the lowest ranked object found so far is on top
+    let mut top = BinaryHeap::with_capacity(n.saturating_add(1));
+    while let Some(object) = objects.try_next().await? {
+        if let Some(rank) = rank(&object) {
+            top.push(Reverse(Ranked { rank, object }));
+            if top.len() > n {
+                top.pop();
+            }
+        }
+    }
+    Ok(top
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(ranked)| ranked.object)
+        .collect())
+}
+
+pub(crate) async fn find_largest<C>(
+    client: &C,
+    bucket: String,
+    prefix: String,
+    n: usize,
+) -> S3ExtResult<Vec<Object>>
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    find_top(client, bucket, prefix, n, |object| object.size).await
+}
+
+pub(crate) async fn find_oldest<C>(
+    client: &C,
+    bucket: String,
+    prefix: String,
+    n: usize,
+) -> S3ExtResult<Vec<Object>>
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    find_top(client, bucket, prefix, n, |object| {
+        object.last_modified_at().map(Reverse)
+    })
+    .await
+}
+
+pub(crate) async fn find_newest<C>(
+    client: &C,
+    bucket: String,
+    prefix: String,
+    n: usize,
+) -> S3ExtResult<Vec<Object>>
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    find_top(client, bucket, prefix, n, Timestamps::last_modified_at).await
+}
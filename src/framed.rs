@@ -0,0 +1,61 @@
+//! Decode object bodies with [`tokio_util::codec`] decoders
+//!
+//! See [`S3Ext::get_framed`](crate::S3Ext::get_framed).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use futures::stream::TryStreamExt;
+//! use s3_ext::S3Ext;
+//! use tokio_util::codec::LengthDelimitedCodec;
+//!
+//! let mut frames = client
+//!     .get_framed("bucket", "records", LengthDelimitedCodec::new())
+//!     .await?;
+//! while let Some(frame) = frames.try_next().await? {
+//!     println!("{} bytes", frame.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    error::{S3ExtError, S3ExtResult},
+};
+use rusoto_s3::GetObjectRequest;
+use std::pin::Pin;
+use tokio::io::AsyncRead;
+use tokio_util::codec::{Decoder, FramedRead};
+
+/// Body of an object being downloaded
+pub type ObjectReader = Pin<Box<dyn AsyncRead + Send>>;
+
+/// Frames decoded from the body of an object
+pub type FramedObject<D> = FramedRead<ObjectReader, D>;
+
+pub(crate) async fn get_framed<D>(
+    client: &Backend,
+    bucket: String,
+    key: String,
+    decoder: D,
+) -> S3ExtResult<FramedObject<D>>
+where
+    D: Decoder,
+{
+    let request = GetObjectRequest {
+        bucket: bucket.clone(),
+        key: key.clone(),
+        ..Default::default()
+    };
+    let output = client
+        .get_object(request)
+        .await
+        .map_err(|e| S3ExtError::from(e).with_object(bucket, key))?;
+    let body: ObjectReader = match output.body {
+        Some(body) => Box::pin(body.into_async_read()),
+        None => Box::pin(&[][..]),
+    };
+    Ok(FramedRead::new(body, decoder))
+}
@@ -0,0 +1,398 @@
+//! Filesystem-like access to a bucket
+//!
+//! [`S3Fs`] maps a subset of `std::fs` onto a bucket, so code written
+//! against files and directories can target S3 with few changes:
+//!
+//! * [`open`](S3Fs::open) and [`read`](S3Fs::read) get the object,
+//!   [`read_at`](S3Fs::read_at) a range of it
+//! * [`create`](S3Fs::create) streams a multipart upload,
+//!   [`write`](S3Fs::write) puts the object in one request
+//! * [`read_dir`](S3Fs::read_dir) lists with delimiter `/`
+//! * [`metadata`](S3Fs::metadata) sends a `HEAD` request, falling back to a
+//!   listing for directories
+//! * [`remove_file`](S3Fs::remove_file) deletes the object
+//! * [`rename`](S3Fs::rename) copies the object and deletes the original
+//!
+//! Paths are keys, a leading `/` is ignored. Directories aren't stored,
+//! they exist as long as there are keys below them.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use futures::TryStreamExt;
+//! use s3_ext::fs::S3Fs;
+//! use tokio::io::AsyncWriteExt;
+//!
+//! let fs = S3Fs::new(client, "bucket");
+//! let mut file = fs.create("/reports/2024.csv");
+//! file.write_all(b"month,total\n").await?;
+//! file.finish().await?;
+//!
+//! let mut entries = fs.read_dir("/reports");
+//! while let Some(entry) = entries.try_next().await? {
+//!     println!("{} {:?}", entry.file_name(), entry.metadata().len());
+//! }
+//! fs.rename("/reports/2024.csv", "/archive/2024.csv").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::{S3ExtError, S3ExtResult},
+    framed::ObjectReader,
+    part_size::PartSize,
+    stream_copy::{PipeReader, PIPE_SIZE},
+    timestamps::Timestamps,
+    S3Ext,
+};
+use chrono::{DateTime, Utc};
+use futures::{
+    future,
+    stream::{Stream, TryStreamExt},
+};
+use rusoto_s3::{CompleteMultipartUploadOutput, GetObjectRequest, Object, PutObjectRequest};
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+use tokio::{
+    io::{AsyncWrite, DuplexStream},
+    task::JoinHandle,
+};
+
+// Key of `path`
+fn key(path: &str) -> &str {
+    path.trim_start_matches('/')
+}
+
+// Prefix of the keys within directory `path`
+fn dir_prefix(path: &str) -> String {
+    match key(path).trim_end_matches('/') {
+        "" => String::new(),
+        dir => format!("{}/", dir),
+    }
+}
+
+/// Bucket accessed like a filesystem
+#[derive(Clone)]
+pub struct S3Fs<C> {
+    client: C,
+    bucket: String,
+}
+
+impl<C> S3Fs<C>
+where
+    C: S3Ext + Clone + Send + Sync + 'static,
+{
+    /// Access `bucket` using `client`
+    pub fn new(client: C, bucket: impl Into<String>) -> Self {
+        S3Fs {
+            client,
+            bucket: bucket.into(),
+        }
+    }
+
+    /// Client used to access the bucket
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    /// Name of the bucket
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    /// Open file `path` for reading
+    pub async fn open(&self, path: &str) -> S3ExtResult<ObjectReader> {
+        let body = self.client.get_body(&self.bucket, key(path)).await?;
+        Ok(body.into_async_read())
+    }
+
+    /// Read the whole content of file `path`
+    pub async fn read(&self, path: &str) -> S3ExtResult<Vec<u8>> {
+        let body = self.client.get_body(&self.bucket, key(path)).await?;
+        body.read_to_vec().await
+    }
+
+    /// Read up to `len` bytes of file `path` starting at `offset`
+    ///
+    /// Fewer bytes are returned if the file ends before, an `offset` past
+    /// the end fails with S3's `InvalidRange` error.
+    pub async fn read_at(&self, path: &str, offset: u64, len: u64) -> S3ExtResult<Vec<u8>> {
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        let request = GetObjectRequest {
+            bucket: self.bucket.clone(),
+            key: key(path).to_owned(),
+            range: Some(format!("bytes={}-{}", offset, offset + len - 1)),
+            ..Default::default()
+        };
+        self.client
+            .get_body_with(request)
+            .await?
+            .read_to_vec()
+            .await
+    }
+
+    /// Create file `path`, replacing an existing one, and return a writer
+    /// for its content
+    ///
+    /// The content is uploaded while it is written, using multipart upload.
+    /// The file appears once [`FileWriter::finish`] succeeds, dropping the
+    /// writer instead aborts the upload. S3 rejects multipart uploads
+    /// without any parts, use [`write`](Self::write) to create empty files.
+    ///
+    /// Must be called within a Tokio runtime, the upload runs as a task of
+    /// its own.
+    pub fn create(&self, path: &str) -> FileWriter {
+        let target = PutObjectRequest {
+            bucket: self.bucket.clone(),
+            key: key(path).to_owned(),
+            ..Default::default()
+        };
+        let (pipe, reader) = tokio::io::duplex(PIPE_SIZE);
+        let aborted = Arc::new(AtomicBool::new(false));
+        let mut reader = PipeReader::new(reader, aborted.clone());
+        let client = self.client.clone();
+        let upload = tokio::spawn(async move {
+            client
+                .upload_multipart_with(&mut reader, target, PartSize::Auto)
+                .await
+        });
+        FileWriter {
+            pipe: Some(pipe),
+            aborted,
+            upload: Some(upload),
+        }
+    }
+
+    /// Create file `path` with `content`, replacing an existing one
+    pub async fn write(&self, path: &str, content: impl AsRef<[u8]>) -> S3ExtResult<()> {
+        let target = PutObjectRequest {
+            bucket: self.bucket.clone(),
+            key: key(path).to_owned(),
+            ..Default::default()
+        };
+        self.client
+            .upload(&mut content.as_ref(), target)
+            .await
+            .map(drop)
+    }
+
+    /// Files and subdirectories in directory `path`, in key order
+    ///
+    /// Use an empty `path` or `/` for the root of the bucket.
+    pub fn read_dir(&self, path: &str) -> impl Stream<Item = S3ExtResult<DirEntry>> + Send {
+        let prefix = dir_prefix(path);
+        self.client
+            .stream_objects_with_prefix(&self.bucket, &prefix)
+            .with_delimiter("/")
+            .with_common_prefixes()
+            .map_err(S3ExtError::from)
+            .try_filter_map(move |object| future::ready(Ok(DirEntry::new(&prefix, object))))
+    }
+
+    /// Metadata of file or directory `path`
+    ///
+    /// Fails with the error of the `HEAD` request, see
+    /// [`S3ExtError::is_not_found`], if neither exists.
+    pub async fn metadata(&self, path: &str) -> S3ExtResult<Metadata> {
+        let key = key(path);
+        if key.is_empty() {
+            return Ok(Metadata::dir());
+        }
+        // keys ending in `/` are markers of directories
+        let error = match self.client.object_attributes(&self.bucket, key).await {
+            Ok(attributes) => {
+                return Ok(Metadata {
+                    is_dir: key.ends_with('/'),
+                    len: attributes.size as u64,
+                    modified: attributes.last_modified_at(),
+                })
+            }
+            Err(e) if e.is_not_found() => e,
+            Err(e) => return Err(e),
+        };
+        let mut objects = self
+            .client
+            .stream_objects_with_prefix(&self.bucket, dir_prefix(key));
+        match objects.try_next().await? {
+            Some(_) => Ok(Metadata::dir()),
+            None => Err(error),
+        }
+    }
+
+    /// Remove file `path`
+    ///
+    /// Unlike `std::fs::remove_file`, removing a missing file succeeds.
+    pub async fn remove_file(&self, path: &str) -> S3ExtResult<()> {
+        self.client.delete(&self.bucket, key(path)).await
+    }
+
+    /// Rename file `from` to `to`, replacing an existing one
+    ///
+    /// The file is copied and the original deleted afterwards, the rename
+    /// isn't atomic. Files larger than 5 GiB can't be renamed.
+    pub async fn rename(&self, from: &str, to: &str) -> S3ExtResult<()> {
+        self.client
+            .copy(&self.bucket, key(from), &self.bucket, key(to))
+            .await?;
+        self.client.delete(&self.bucket, key(from)).await
+    }
+}
+
+/// Writer returned by [`S3Fs::create`]
+///
+/// Writes wait for the upload to keep up. `shutdown` only flushes, call
+/// [`finish`](Self::finish) to complete the file.
+pub struct FileWriter {
+    pipe: Option<DuplexStream>,
+    aborted: Arc<AtomicBool>,
+    upload: Option<JoinHandle<S3ExtResult<CompleteMultipartUploadOutput>>>,
+}
+
+impl FileWriter {
+    /// Complete the upload, making the file visible
+    pub async fn finish(mut self) -> S3ExtResult<CompleteMultipartUploadOutput> {
+        // the end of the pipe ends the upload
+        self.pipe = None;
+        match self.upload.take() {
+            Some(upload) => upload
+                .await
+                .unwrap_or(Err(S3ExtError::Other("upload task failed"))),
+            None => Err(S3ExtError::Other("upload task failed")),
+        }
+    }
+
+    fn pipe(&mut self) -> io::Result<Pin<&mut DuplexStream>> {
+        match self.pipe.as_mut() {
+            Some(pipe) => Ok(Pin::new(pipe)),
+            None => Err(io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+}
+
+impl AsyncWrite for FileWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.pipe() {
+            Ok(pipe) => pipe.poll_write(cx, buf),
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.pipe() {
+            Ok(pipe) => pipe.poll_flush(cx),
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+impl Drop for FileWriter {
+    fn drop(&mut self) {
+        // set before the upload sees the end of the pipe
+        if self.upload.is_some() {
+            self.aborted.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Type and size of a file or directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    is_dir: bool,
+    len: u64,
+    modified: Option<DateTime<Utc>>,
+}
+
+impl Metadata {
+    fn dir() -> Self {
+        Metadata {
+            is_dir: true,
+            len: 0,
+            modified: None,
+        }
+    }
+
+    /// Whether this is a directory
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    /// Whether this is a file
+    pub fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+
+    /// Size of the file in bytes, 0 for directories
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the file is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Time of the last modification, `None` for directories
+    pub fn modified(&self) -> Option<DateTime<Utc>> {
+        self.modified
+    }
+}
+
+/// Entry of a directory listed by [`S3Fs::read_dir`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+    path: String,
+    metadata: Metadata,
+}
+
+impl DirEntry {
+    // Entry for `object` listed below `prefix`, skipping the marker of the
+    // directory itself
+    fn new(prefix: &str, object: Object) -> Option<Self> {
+        let path = object.key.clone().filter(|key| key != prefix)?;
+        // with delimiter `/`, keys ending in `/` are common prefixes
+        let metadata = if path.ends_with('/') {
+            Metadata::dir()
+        } else {
+            Metadata {
+                is_dir: false,
+                len: object.size.unwrap_or_default() as u64,
+                modified: object.last_modified_at(),
+            }
+        };
+        Some(DirEntry { path, metadata })
+    }
+
+    /// Full path of the entry, directories end in `/`
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Name of the entry within its directory, without trailing `/`
+    pub fn file_name(&self) -> &str {
+        let path = self.path.trim_end_matches('/');
+        path.rsplit('/').next().unwrap_or(path)
+    }
+
+    /// Type and size of the entry
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+}
@@ -0,0 +1,278 @@
+//! Intelligent-Tiering configurations of buckets
+//!
+//! Objects stored in the `INTELLIGENT_TIERING` storage class move between
+//! the frequent and infrequent access tiers automatically. The archive
+//! access tiers are opt-in: a configuration moves objects matching its
+//! filter to them once they haven't been accessed for a number of days.
+//! See the methods
+//! [`get_intelligent_tiering`](crate::bucket_config::BucketConfigExt::get_intelligent_tiering),
+//! [`list_intelligent_tiering`](crate::bucket_config::BucketConfigExt::list_intelligent_tiering),
+//! [`put_intelligent_tiering`](crate::bucket_config::BucketConfigExt::put_intelligent_tiering) and
+//! [`delete_intelligent_tiering`](crate::bucket_config::BucketConfigExt::delete_intelligent_tiering) of
+//! [`BucketConfigExt`](crate::bucket_config::BucketConfigExt).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::{
+//!     bucket_config::BucketConfigExt,
+//!     intelligent_tiering::{ArchiveTier, IntelligentTiering},
+//! };
+//!
+//! let tiering = IntelligentTiering::new("archive-logs")
+//!     .prefix("logs/")
+//!     .archive(ArchiveTier::ArchiveAccess, 90)
+//!     .archive(ArchiveTier::DeepArchiveAccess, 180);
+//! client.put_intelligent_tiering("bucket", &tiering).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    error::{S3ExtError, S3ExtResult},
+    tagging::{tag_set, tags_from_set, Tags},
+};
+use rusoto_s3::{
+    DeleteBucketIntelligentTieringConfigurationRequest,
+    GetBucketIntelligentTieringConfigurationRequest, IntelligentTieringAndOperator,
+    IntelligentTieringConfiguration, IntelligentTieringFilter,
+    ListBucketIntelligentTieringConfigurationsRequest,
+    PutBucketIntelligentTieringConfigurationRequest, Tag, Tiering,
+};
+use std::collections::BTreeMap;
+
+/// Archive access tier of the `INTELLIGENT_TIERING` storage class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ArchiveTier {
+    /// Restored within hours, after at least 90 days without access
+    ArchiveAccess,
+    /// Restored within 12 hours, after at least 180 days without access
+    DeepArchiveAccess,
+}
+
+impl ArchiveTier {
+    /// Name of the tier as used by S3
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ArchiveTier::ArchiveAccess => "ARCHIVE_ACCESS",
+            ArchiveTier::DeepArchiveAccess => "DEEP_ARCHIVE_ACCESS",
+        }
+    }
+
+    /// Minimum number of days without access S3 accepts for the tier
+    pub fn min_days(self) -> i64 {
+        match self {
+            ArchiveTier::ArchiveAccess => 90,
+            ArchiveTier::DeepArchiveAccess => 180,
+        }
+    }
+
+    /// Parse the name of a tier as used by S3
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ARCHIVE_ACCESS" => Some(ArchiveTier::ArchiveAccess),
+            "DEEP_ARCHIVE_ACCESS" => Some(ArchiveTier::DeepArchiveAccess),
+            _ => None,
+        }
+    }
+}
+
+/// Intelligent-Tiering configuration of a bucket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntelligentTiering {
+    /// ID of the configuration, unique within the bucket
+    pub id: String,
+    /// Whether the configuration is enabled
+    pub enabled: bool,
+    /// Only archive objects with this prefix
+    pub prefix: String,
+    /// Only archive objects with all of these tags
+    pub tags: Tags,
+    /// Days without access after which objects move to each archive tier
+    pub archive_days: BTreeMap<ArchiveTier, i64>,
+}
+
+impl IntelligentTiering {
+    /// Enabled configuration with ID `id` applying to all objects, without
+    /// archive tiers
+    pub fn new(id: impl Into<String>) -> Self {
+        IntelligentTiering {
+            id: id.into(),
+            enabled: true,
+            prefix: String::new(),
+            tags: Tags::new(),
+            archive_days: BTreeMap::new(),
+        }
+    }
+
+    /// Enable or disable the configuration
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Only archive objects with `prefix`
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Only archive objects tagged with `key` set to `value`
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.insert(key.into(), value.into());
+        self
+    }
+
+    /// Move objects to `tier` after `days` without access
+    ///
+    /// See [`ArchiveTier::min_days`] for the lowest value S3 accepts.
+    pub fn archive(mut self, tier: ArchiveTier, days: i64) -> Self {
+        self.archive_days.insert(tier, days);
+        self
+    }
+
+    /// Convert into a Rusoto `IntelligentTieringConfiguration`
+    pub fn to_configuration(&self) -> IntelligentTieringConfiguration {
+        let filter = if self.prefix.is_empty() && self.tags.is_empty() {
+            None
+        } else if self.tags.is_empty() {
+            Some(IntelligentTieringFilter {
+                prefix: Some(self.prefix.clone()),
+                ..Default::default()
+            })
+        } else if self.tags.len() == 1 && self.prefix.is_empty() {
+            Some(IntelligentTieringFilter {
+                tag: tag_set(&self.tags).pop(),
+                ..Default::default()
+            })
+        } else {
+            Some(IntelligentTieringFilter {
+                and: Some(IntelligentTieringAndOperator {
+                    prefix: Some(self.prefix.clone()).filter(|prefix| !prefix.is_empty()),
+                    tags: Some(tag_set(&self.tags)),
+                }),
+                ..Default::default()
+            })
+        };
+        IntelligentTieringConfiguration {
+            id: self.id.clone(),
+            status: if self.enabled { "Enabled" } else { "Disabled" }.to_owned(),
+            filter,
+            tierings: self
+                .archive_days
+                .iter()
+                .map(|(tier, days)| Tiering {
+                    access_tier: tier.as_str().to_owned(),
+                    days: *days,
+                })
+                .collect(),
+        }
+    }
+
+    /// Convert a Rusoto `IntelligentTieringConfiguration`
+    ///
+    /// Tierings of unknown access tiers are skipped.
+    pub fn from_configuration(configuration: IntelligentTieringConfiguration) -> Self {
+        let (prefix, tags) = match configuration.filter {
+            Some(IntelligentTieringFilter { and: Some(and), .. }) => {
+                (and.prefix, and.tags.unwrap_or_default())
+            }
+            Some(IntelligentTieringFilter { prefix, tag, .. }) => {
+                (prefix, tag.into_iter().collect())
+            }
+            None => (None, Vec::<Tag>::new()),
+        };
+        IntelligentTiering {
+            id: configuration.id,
+            enabled: configuration.status == "Enabled",
+            prefix: prefix.unwrap_or_default(),
+            tags: tags_from_set(tags),
+            archive_days: configuration
+                .tierings
+                .into_iter()
+                .filter_map(|tiering| {
+                    Some((ArchiveTier::from_name(&tiering.access_tier)?, tiering.days))
+                })
+                .collect(),
+        }
+    }
+}
+
+pub(crate) async fn get_intelligent_tiering(
+    client: &Backend,
+    bucket: String,
+    id: String,
+) -> S3ExtResult<Option<IntelligentTiering>> {
+    let request = GetBucketIntelligentTieringConfigurationRequest { bucket, id };
+    match client
+        .get_bucket_intelligent_tiering_configuration(request)
+        .await
+    {
+        Ok(output) => Ok(output
+            .intelligent_tiering_configuration
+            .map(IntelligentTiering::from_configuration)),
+        Err(e) => {
+            let e = S3ExtError::from(e);
+            match e.code() {
+                Some("NoSuchConfiguration") => Ok(None),
+                _ => Err(e),
+            }
+        }
+    }
+}
+
+pub(crate) async fn list_intelligent_tiering(
+    client: &Backend,
+    bucket: String,
+) -> S3ExtResult<Vec<IntelligentTiering>> {
+    let mut configurations = Vec::new();
+    let mut continuation_token = None;
+    loop {
+        let request = ListBucketIntelligentTieringConfigurationsRequest {
+            bucket: bucket.clone(),
+            continuation_token,
+        };
+        let output = client
+            .list_bucket_intelligent_tiering_configurations(request)
+            .await?;
+        configurations.extend(
+            output
+                .intelligent_tiering_configuration_list
+                .unwrap_or_default()
+                .into_iter()
+                .map(IntelligentTiering::from_configuration),
+        );
+        match output.next_continuation_token {
+            Some(token) if output.is_truncated == Some(true) => continuation_token = Some(token),
+            _ => return Ok(configurations),
+        }
+    }
+}
+
+pub(crate) async fn put_intelligent_tiering(
+    client: &Backend,
+    bucket: String,
+    tiering: &IntelligentTiering,
+) -> S3ExtResult<()> {
+    let request = PutBucketIntelligentTieringConfigurationRequest {
+        bucket,
+        id: tiering.id.clone(),
+        intelligent_tiering_configuration: tiering.to_configuration(),
+    };
+    Ok(client
+        .put_bucket_intelligent_tiering_configuration(request)
+        .await?)
+}
+
+pub(crate) async fn delete_intelligent_tiering(
+    client: &Backend,
+    bucket: String,
+    id: String,
+) -> S3ExtResult<()> {
+    let request = DeleteBucketIntelligentTieringConfigurationRequest { bucket, id };
+    Ok(client
+        .delete_bucket_intelligent_tiering_configuration(request)
+        .await?)
+}
@@ -0,0 +1,504 @@
+//! Hooks around the transfer methods of `S3Ext`
+//!
+//! [`Interceptor`]s installed on an [`InterceptingS3Client`] see, and may
+//! modify, every request before it is sent and are notified of the outcome,
+//! e.g. for auditing or to set metadata and headers such as
+//! `x-amz-server-side-encryption` on all uploads.
+//!
+//! # Example
+//!
+//! ```
+//! use rusoto_core::Region;
+//! use rusoto_s3::S3Client;
+//! use s3_ext::{
+//!     error::S3ExtResult,
+//!     intercept::{InterceptingS3Client, Interceptor, Request},
+//! };
+//!
+//! struct Encrypt;
+//!
+//! impl Interceptor for Encrypt {
+//!     fn before_request(&self, _operation: &'static str, request: &mut Request) -> S3ExtResult<()> {
+//!         if let Request::Put(put) = request {
+//!             put.server_side_encryption = Some("AES256".to_owned());
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let client = InterceptingS3Client::new(S3Client::new(Region::EuWest1)).interceptor(Encrypt);
+//! ```
+
+use crate::{
+    backend::Backend,
+    body::ObjectBody,
+    compose::ComposePart,
+    diff::DiffStream,
+    error::{S3ExtError, S3ExtResult},
+    framed::FramedObject,
+    iter::{GetObjectStream, ObjectStream, SortedObjectStream},
+    lines::LineStream,
+    metadata::ObjectMetadata,
+    part_size::PartSize,
+    watch::WatchStream,
+    S3Ext,
+};
+use async_trait::async_trait;
+use rusoto_s3::{
+    CompleteMultipartUploadOutput, GetObjectOutput, GetObjectRequest, PutObjectOutput,
+    PutObjectRequest, S3Client,
+};
+use std::{convert::AsRef, future::Future, path::Path, sync::Arc, time::Duration};
+use tokio::io;
+use tokio_util::codec::Decoder;
+
+/// Request about to be sent by a transfer method
+#[derive(Debug)]
+pub enum Request<'a> {
+    /// Request of a download
+    Get(&'a mut GetObjectRequest),
+    /// Request of an upload
+    Put(&'a mut PutObjectRequest),
+}
+
+impl Request<'_> {
+    /// Bucket of the request
+    pub fn bucket(&self) -> &str {
+        match self {
+            Request::Get(r) => &r.bucket,
+            Request::Put(r) => &r.bucket,
+        }
+    }
+
+    /// Key of the request
+    pub fn key(&self) -> &str {
+        match self {
+            Request::Get(r) => &r.key,
+            Request::Put(r) => &r.key,
+        }
+    }
+}
+
+/// Callbacks invoked by [`InterceptingS3Client`]
+///
+/// `operation` is the name of the `S3Ext` method, e.g. `"download"`. Both
+/// callbacks default to doing nothing.
+pub trait Interceptor: Send + Sync {
+    /// Called before `request` is sent
+    ///
+    /// Returning an error fails the operation without sending the request.
+    fn before_request(&self, _operation: &'static str, _request: &mut Request) -> S3ExtResult<()> {
+        Ok(())
+    }
+
+    /// Called after the operation on `key` in `bucket` completed, `error`
+    /// is set if it failed
+    fn after_response(
+        &self,
+        _operation: &'static str,
+        _bucket: &str,
+        _key: &str,
+        _error: Option<&S3ExtError>,
+    ) {
+    }
+}
+
+impl<I: Interceptor + ?Sized> Interceptor for Arc<I> {
+    fn before_request(&self, operation: &'static str, request: &mut Request) -> S3ExtResult<()> {
+        (**self).before_request(operation, request)
+    }
+
+    fn after_response(
+        &self,
+        operation: &'static str,
+        bucket: &str,
+        key: &str,
+        error: Option<&S3ExtError>,
+    ) {
+        (**self).after_response(operation, bucket, key, error);
+    }
+}
+
+/// Client passing the transfer methods of `S3Ext` through [`Interceptor`]s
+///
+/// Interceptors see requests in the order they were installed and outcomes
+/// in reverse order. Streams are passed through without being intercepted.
+#[derive(Clone)]
+pub struct InterceptingS3Client<C = S3Client> {
+    inner: C,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+}
+
+impl<C> InterceptingS3Client<C> {
+    /// Wrap `inner` without any interceptors
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            interceptors: Vec::new(),
+        }
+    }
+
+    /// Install `interceptor` after those installed before
+    pub fn interceptor(mut self, interceptor: impl Interceptor + 'static) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Wrapped client
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn before(&self, operation: &'static str, mut request: Request) -> S3ExtResult<()> {
+        for interceptor in &self.interceptors {
+            interceptor.before_request(operation, &mut request)?;
+        }
+        Ok(())
+    }
+
+    async fn around<F, T>(
+        &self,
+        operation: &'static str,
+        bucket: String,
+        key: String,
+        future: F,
+    ) -> S3ExtResult<T>
+    where
+        F: Future<Output = S3ExtResult<T>>,
+    {
+        let result = future.await;
+        for interceptor in self.interceptors.iter().rev() {
+            interceptor.after_response(operation, &bucket, &key, result.as_ref().err());
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl<C> S3Ext for InterceptingS3Client<C>
+where
+    C: S3Ext + Send + Sync,
+{
+    async fn send_request<T, F, Fut>(
+        &self,
+        operation: &'static str,
+        bucket: String,
+        key: String,
+        request: F,
+    ) -> S3ExtResult<T>
+    where
+        T: Send,
+        F: Fn(Backend, String, String) -> Fut + Send + Sync,
+        Fut: Future<Output = S3ExtResult<T>> + Send,
+    {
+        let future = self
+            .inner
+            .send_request(operation, bucket.clone(), key.clone(), request);
+        self.around(operation, bucket, key, future).await
+    }
+
+    async fn download_to_file<F>(
+        &self,
+        mut source: GetObjectRequest,
+        target: F,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        let operation = "download_to_file";
+        self.before(operation, Request::Get(&mut source))?;
+        let (bucket, key) = (source.bucket.clone(), source.key.clone());
+        self.around(
+            operation,
+            bucket,
+            key,
+            self.inner.download_to_file(source, target),
+        )
+        .await
+    }
+
+    async fn upload_from_file<F>(
+        &self,
+        source: F,
+        mut target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        let operation = "upload_from_file";
+        self.before(operation, Request::Put(&mut target))?;
+        let (bucket, key) = (target.bucket.clone(), target.key.clone());
+        self.around(
+            operation,
+            bucket,
+            key,
+            self.inner.upload_from_file(source, target),
+        )
+        .await
+    }
+
+    async fn upload_from_file_multipart_with<F>(
+        &self,
+        source: F,
+        mut target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        let operation = "upload_from_file_multipart";
+        self.before(operation, Request::Put(&mut target))?;
+        let (bucket, key) = (target.bucket.clone(), target.key.clone());
+        self.around(
+            operation,
+            bucket,
+            key,
+            self.inner
+                .upload_from_file_multipart_with(source, target, part_size),
+        )
+        .await
+    }
+
+    async fn download<W>(
+        &self,
+        mut source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        let operation = "download";
+        self.before(operation, Request::Get(&mut source))?;
+        let (bucket, key) = (source.bucket.clone(), source.key.clone());
+        self.around(operation, bucket, key, self.inner.download(source, target))
+            .await
+    }
+
+    async fn download_decompressed<W>(
+        &self,
+        mut source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        let operation = "download_decompressed";
+        self.before(operation, Request::Get(&mut source))?;
+        let (bucket, key) = (source.bucket.clone(), source.key.clone());
+        self.around(
+            operation,
+            bucket,
+            key,
+            self.inner.download_decompressed(source, target),
+        )
+        .await
+    }
+
+    async fn get_framed<D>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        decoder: D,
+    ) -> S3ExtResult<FramedObject<D>>
+    where
+        D: Decoder + Send,
+    {
+        let (bucket, key) = (bucket.into(), key.into());
+        let future = self.inner.get_framed(bucket.clone(), key.clone(), decoder);
+        self.around("get_framed", bucket, key, future).await
+    }
+
+    async fn get_body_with(&self, source: GetObjectRequest) -> S3ExtResult<ObjectBody> {
+        let (bucket, key) = (source.bucket.clone(), source.key.clone());
+        let future = self.inner.get_body_with(source);
+        self.around("get_body_with", bucket, key, future).await
+    }
+
+    async fn upload<R>(
+        &self,
+        source: &mut R,
+        mut target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        let operation = "upload";
+        self.before(operation, Request::Put(&mut target))?;
+        let (bucket, key) = (target.bucket.clone(), target.key.clone());
+        self.around(operation, bucket, key, self.inner.upload(source, target))
+            .await
+    }
+
+    async fn upload_multipart_with<R>(
+        &self,
+        source: &mut R,
+        mut target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        let operation = "upload_multipart";
+        self.before(operation, Request::Put(&mut target))?;
+        let (bucket, key) = (target.bucket.clone(), target.key.clone());
+        self.around(
+            operation,
+            bucket,
+            key,
+            self.inner.upload_multipart_with(source, target, part_size),
+        )
+        .await
+    }
+
+    async fn upload_multipart_resumable<R>(
+        &self,
+        source: &mut R,
+        mut target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        let operation = "upload_multipart_resumable";
+        self.before(operation, Request::Put(&mut target))?;
+        let (bucket, key) = (target.bucket.clone(), target.key.clone());
+        self.around(
+            operation,
+            bucket,
+            key,
+            self.inner
+                .upload_multipart_resumable(source, target, part_size),
+        )
+        .await
+    }
+
+    #[inline]
+    fn stream_objects(&self, bucket: impl Into<String>) -> ObjectStream {
+        self.inner.stream_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> ObjectStream {
+        self.inner.stream_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_objects_sorted_by_mtime(&self, bucket: impl Into<String>) -> SortedObjectStream {
+        self.inner.stream_objects_sorted_by_mtime(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix_sorted_by_mtime(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> SortedObjectStream {
+        self.inner
+            .stream_objects_with_prefix_sorted_by_mtime(bucket, prefix)
+    }
+
+    #[inline]
+    fn diff(
+        &self,
+        source_bucket: impl Into<String>,
+        source_prefix: impl Into<String>,
+        dest_bucket: impl Into<String>,
+        dest_prefix: impl Into<String>,
+    ) -> DiffStream {
+        self.inner
+            .diff(source_bucket, source_prefix, dest_bucket, dest_prefix)
+    }
+
+    #[inline]
+    fn watch_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        period: Duration,
+    ) -> WatchStream {
+        self.inner.watch_prefix(bucket, prefix, period)
+    }
+
+    #[inline]
+    fn stream_get_objects(&self, bucket: impl Into<String>) -> GetObjectStream {
+        self.inner.stream_get_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_get_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> GetObjectStream {
+        self.inner.stream_get_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_lines(&self, bucket: impl Into<String>, key: impl Into<String>) -> LineStream {
+        self.inner.stream_lines(bucket, key)
+    }
+
+    async fn delete(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        let (bucket, key) = (bucket.into(), key.into());
+        let future = self.inner.delete(bucket.clone(), key.clone());
+        self.around("delete", bucket, key, future).await
+    }
+
+    async fn compose_object(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        parts: Vec<ComposePart>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput> {
+        let (bucket, key) = (bucket.into(), key.into());
+        let future = self
+            .inner
+            .compose_object(bucket.clone(), key.clone(), parts);
+        self.around("compose_object", bucket, key, future).await
+    }
+
+    async fn object_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectMetadata> {
+        let (bucket, key) = (bucket.into(), key.into());
+        let future = self.inner.object_metadata(bucket.clone(), key.clone());
+        self.around("object_metadata", bucket, key, future).await
+    }
+
+    async fn replace_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        metadata: ObjectMetadata,
+    ) -> S3ExtResult<()> {
+        let (bucket, key) = (bucket.into(), key.into());
+        let future = self
+            .inner
+            .replace_metadata(bucket.clone(), key.clone(), metadata);
+        self.around("replace_metadata", bucket, key, future).await
+    }
+
+    async fn copy(
+        &self,
+        source_bucket: impl Into<String> + Send,
+        source_key: impl Into<String> + Send,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        let (bucket, key) = (bucket.into(), key.into());
+        let future = self
+            .inner
+            .copy(source_bucket, source_key, bucket.clone(), key.clone());
+        self.around("copy", bucket, key, future).await
+    }
+}
@@ -119,7 +119,14 @@
 //! }
 //! ```
 
-use crate::error::{S3ExtError, S3ExtResult};
+use crate::{
+    backend::{Backend, BackendResult},
+    compat::Compatibility,
+    error::{S3ExtError, S3ExtResult},
+    retry::RetryPolicy,
+    timestamps::Timestamps,
+    trace::{span, Instrument},
+};
 use futures::{
     ready,
     stream::Stream,
@@ -129,25 +136,41 @@ use futures::{
 use rusoto_core::{RusotoError, RusotoResult};
 use rusoto_s3::{
     GetObjectError, GetObjectOutput, GetObjectRequest, ListObjectsV2Error, ListObjectsV2Output,
-    ListObjectsV2Request, Object, S3Client, S3,
+    ListObjectsV2Request, Object,
 };
-use std::{future::Future, mem, pin::Pin, vec::IntoIter};
+use std::{cmp::Reverse, future::Future, mem, pin::Pin, vec::IntoIter};
+
+/// Value of the `x-amz-request-payer` header accepting the charges of a
+/// requester-pays bucket
+pub(crate) const REQUESTER: &str = "requester";
+
+/// Position within a listing, see [`ObjectIter::token`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListToken {
+    /// Continuation token of the next page, all objects before it have been
+    /// consumed
+    Continuation(String),
+    /// Key of the last object consumed
+    StartAfter(String),
+}
 
 /// Iterator-like objects, forms the basis of `ObjectStream`
 #[derive(Clone)]
 pub struct ObjectIter {
-    client: S3Client,
+    client: Backend,
     request: ListObjectsV2Request,
     objects: IntoIter<Object>,
+    last_key: Option<String>,
     exhausted: bool,
+    start_after_fallback: bool,
+    common_prefixes: bool,
+    start_at: Option<String>,
+    end_before: Option<String>,
+    retry: RetryPolicy,
 }
 
 impl ObjectIter {
-    fn new(
-        client: &S3Client,
-        bucket: impl Into<String>,
-        prefix: Option<impl Into<String>>,
-    ) -> Self {
+    fn new(client: &Backend, bucket: impl Into<String>, prefix: Option<impl Into<String>>) -> Self {
         let request = ListObjectsV2Request {
             bucket: bucket.into(),
             max_keys: Some(1000),
@@ -159,25 +182,181 @@ impl ObjectIter {
             client: client.clone(),
             request,
             objects: Vec::new().into_iter(),
+            last_key: None,
             exhausted: false,
+            start_after_fallback: false,
+            common_prefixes: false,
+            start_at: None,
+            end_before: None,
+            retry: RetryPolicy::disabled(),
         }
     }
 
-    async fn next_objects(&mut self) -> RusotoResult<(), ListObjectsV2Error> {
-        let resp = self.client.list_objects_v2(self.request.clone()).await?;
+    /// Adjust listing requests to the quirks described by `compat`
+    pub fn with_compatibility(mut self, compat: &Compatibility) -> Self {
+        self.request.max_keys = Some(compat.max_keys);
+        self.start_after_fallback = compat.start_after_fallback;
+        if !compat.request_payer {
+            self.request.request_payer = None;
+        }
+        if !compat.expected_bucket_owner {
+            self.request.expected_bucket_owner = None;
+        }
+        self
+    }
+
+    /// Retry failed listing requests according to `policy`
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Agree to pay for the requests, required to list requester-pays
+    /// buckets
+    pub fn with_requester_pays(mut self) -> Self {
+        self.request.request_payer = Some(REQUESTER.to_owned());
+        self
+    }
+
+    /// Group keys containing `delimiter` after the prefix into common
+    /// prefixes, which are skipped unless
+    /// [`with_common_prefixes`](Self::with_common_prefixes) is used
+    ///
+    /// With a delimiter of `/`, only the immediate children of the prefix
+    /// are listed.
+    pub fn with_delimiter(mut self, delimiter: impl Into<String>) -> Self {
+        self.request.delimiter = Some(delimiter.into());
+        self
+    }
+
+    /// Yield common prefixes along with the objects, in key order
+    ///
+    /// Common prefixes are yielded as objects with only `key` set, ending in
+    /// the delimiter. A [`token`](Self::token) taken right after a common
+    /// prefix lists it again.
+    pub fn with_common_prefixes(mut self) -> Self {
+        self.common_prefixes = true;
+        self
+    }
+
+    /// List only keys from `key` onward
+    ///
+    /// The listing starts server-side, just before `key`.
+    pub fn start_at(mut self, key: impl Into<String>) -> Self {
+        let key = key.into();
+        self.request.start_after = key_before(&key);
+        self.start_at = Some(key);
+        self
+    }
+
+    /// List only keys sorting before `key`
+    ///
+    /// The listing stops at the first page reaching `key`.
+    pub fn end_before(mut self, key: impl Into<String>) -> Self {
+        self.end_before = Some(key.into());
+        self
+    }
+
+    /// Position after the objects consumed so far, `None` if none have been
+    ///
+    /// Store the token to resume the listing later on using
+    /// [`set_token`](Self::set_token), e.g. after a crash. Objects already
+    /// fetched but not consumed yet are listed again.
+    pub fn token(&self) -> Option<ListToken> {
+        if self.objects.len() == 0 && !self.exhausted {
+            if let Some(token) = &self.request.continuation_token {
+                return Some(ListToken::Continuation(token.clone()));
+            }
+        }
+        self.last_key
+            .clone()
+            .or_else(|| self.request.start_after.clone())
+            .map(ListToken::StartAfter)
+    }
+
+    /// Continue the listing at `token`, or start over if `None`
+    ///
+    /// Objects fetched but not consumed yet are discarded.
+    pub fn set_token(&mut self, token: Option<ListToken>) {
+        let (continuation_token, start_after) = match token {
+            Some(ListToken::Continuation(token)) => (Some(token), None),
+            Some(ListToken::StartAfter(key)) => (None, Some(key)),
+            None => (None, self.start_at.as_deref().and_then(key_before)),
+        };
+        self.request.continuation_token = continuation_token;
+        self.request.start_after = start_after;
+        self.objects = Vec::new().into_iter();
+        self.last_key = None;
+        self.exhausted = false;
+    }
+
+    // Remember `object` as consumed
+    fn consume(&mut self, object: Option<Object>) -> Option<Object> {
+        if let Some(key) = object.as_ref().and_then(|o| o.key.as_ref()) {
+            self.last_key = Some(key.clone());
+        }
+        object
+    }
+
+    fn next_buffered(&mut self) -> Option<Object> {
+        let object = self.objects.next();
+        self.consume(object)
+    }
+
+    async fn next_objects(&mut self) -> BackendResult<(), ListObjectsV2Error> {
+        let resp = ObjectStream::get_objects(
+            self.client.clone(),
+            self.request.clone(),
+            self.retry.clone(),
+        )
+        .await?;
         self.update_objects(resp);
         Ok(())
     }
 
     fn update_objects(&mut self, resp: ListObjectsV2Output) {
-        self.objects = resp.contents.unwrap_or_default().into_iter();
+        let mut contents = resp.contents.unwrap_or_default();
         match resp.next_continuation_token {
             next @ Some(_) => self.request.continuation_token = next,
+            // some providers report a truncated listing without handing out a
+            // continuation token, continue after the last key instead
+            None if self.start_after_fallback && resp.is_truncated == Some(true) => {
+                match contents.last().and_then(|o| o.key.clone()) {
+                    last @ Some(_) => {
+                        self.request.continuation_token = None;
+                        self.request.start_after = last;
+                    }
+                    None => self.exhausted = true,
+                }
+            }
             None => self.exhausted = true,
         };
+        if self.common_prefixes {
+            let prefixes = resp.common_prefixes.unwrap_or_default();
+            contents.extend(prefixes.into_iter().map(|prefix| Object {
+                key: prefix.prefix,
+                ..Default::default()
+            }));
+            // S3 lists objects and common prefixes in one key order, each
+            // page holds a contiguous range of it
+            contents.sort_by(|a, b| a.key.cmp(&b.key));
+        }
+        if let Some(start) = &self.start_at {
+            contents.retain(|o| o.key.as_ref().map_or(true, |key| key >= start));
+        }
+        if let Some(end) = &self.end_before {
+            let past_end = contents
+                .iter()
+                .position(|o| o.key.as_ref().map_or(false, |key| key >= end));
+            if let Some(i) = past_end {
+                contents.truncate(i);
+                self.exhausted = true;
+            }
+        }
+        self.objects = contents.into_iter();
     }
 
-    async fn last_internal(&mut self) -> RusotoResult<Option<Object>, ListObjectsV2Error> {
+    async fn last_internal(&mut self) -> BackendResult<Option<Object>, ListObjectsV2Error> {
         let mut objects = mem::replace(&mut self.objects, Vec::new().into_iter());
         while !self.exhausted {
             self.next_objects().await?;
@@ -185,24 +364,28 @@ impl ObjectIter {
                 objects = mem::replace(&mut self.objects, Vec::new().into_iter());
             }
         }
-        Ok(objects.last())
+        let last = objects.last();
+        Ok(self.consume(last))
     }
 
     /// Get the next object (or None if there are no more objects), may return
     /// an error when fetching objects.
-    pub async fn next_object(&mut self) -> Result<Option<Object>, RusotoError<ListObjectsV2Error>> {
-        if let object @ Some(_) = self.objects.next() {
-            Ok(object)
-        } else if self.exhausted {
-            Ok(None)
-        } else {
+    pub async fn next_object(
+        &mut self,
+    ) -> Result<Option<Object>, Box<RusotoError<ListObjectsV2Error>>> {
+        loop {
+            if let object @ Some(_) = self.next_buffered() {
+                return Ok(object);
+            } else if self.exhausted {
+                return Ok(None);
+            }
+            // pages holding only skipped common prefixes are empty
             self.next_objects().await?;
-            Ok(self.objects.next())
         }
     }
 
     /// Consume the iterator and return the number of objects
-    pub async fn count(mut self) -> Result<usize, RusotoError<ListObjectsV2Error>> {
+    pub async fn count(mut self) -> Result<usize, Box<RusotoError<ListObjectsV2Error>>> {
         let mut count = self.objects.len();
         while !self.exhausted {
             self.next_objects().await?;
@@ -212,7 +395,7 @@ impl ObjectIter {
     }
 
     /// Consume the iterator and return the last object
-    pub async fn last(mut self) -> Result<Option<Object>, RusotoError<ListObjectsV2Error>> {
+    pub async fn last(mut self) -> Result<Option<Object>, Box<RusotoError<ListObjectsV2Error>>> {
         self.last_internal().await
     }
 
@@ -220,16 +403,45 @@ impl ObjectIter {
     pub async fn nth(
         &mut self,
         mut n: usize,
-    ) -> Result<Option<Object>, RusotoError<ListObjectsV2Error>> {
+    ) -> Result<Option<Object>, Box<RusotoError<ListObjectsV2Error>>> {
         while self.objects.len() <= n && !self.exhausted {
             n -= self.objects.len();
             self.next_objects().await?;
         }
-        Ok(self.objects.nth(n))
+        let object = self.objects.nth(n);
+        Ok(self.consume(object))
+    }
+
+    /// Consume the iterator and return an `ObjectStream` continuing where the
+    /// iterator left off
+    pub fn into_stream(self) -> ObjectStream {
+        ObjectStream::from(self)
+    }
+
+    /// Return an `ObjectStream` continuing where the iterator is, leaving the
+    /// iterator itself untouched
+    pub fn as_stream(&self) -> ObjectStream {
+        self.clone().into_stream()
+    }
+}
+
+// Key to list after to start at `key`, `None` to start at the beginning
+//
+// The last character is replaced by its predecessor followed by the largest
+// character, leaving only keys starting with that in between, which are
+// filtered client-side.
+fn key_before(key: &str) -> Option<String> {
+    let mut chars = key.chars();
+    let last = chars.next_back()?;
+    let mut before = chars.as_str().to_owned();
+    if let Some(c) = (last as u32).checked_sub(1).and_then(char::from_u32) {
+        before.push(c);
+        before.push(char::MAX);
     }
+    Some(before)
 }
 
-type ObjResult = RusotoResult<ListObjectsV2Output, ListObjectsV2Error>;
+type ObjResult = BackendResult<ListObjectsV2Output, ListObjectsV2Error>;
 type NextObjFuture = Pin<Box<dyn Future<Output = ObjResult> + Send>>;
 
 /// Stream over objects
@@ -240,31 +452,136 @@ pub struct ObjectStream {
 
 impl ObjectStream {
     pub(crate) fn new(
-        client: &S3Client,
+        client: &Backend,
         bucket: impl Into<String>,
         prefix: Option<impl Into<String>>,
     ) -> Self {
-        Self {
-            iter: ObjectIter::new(client, bucket, prefix),
-            fut: None,
+        ObjectIter::new(client, bucket, prefix).into()
+    }
+
+    /// Adjust listing requests to the quirks described by `compat`
+    pub fn with_compatibility(mut self, compat: &Compatibility) -> Self {
+        self.iter = self.iter.with_compatibility(compat);
+        self
+    }
+
+    /// Retry failed listing requests according to `policy`
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.iter = self.iter.with_retry_policy(policy);
+        self
+    }
+
+    /// Agree to pay for the requests, required to list requester-pays
+    /// buckets
+    pub fn with_requester_pays(mut self) -> Self {
+        self.iter = self.iter.with_requester_pays();
+        self
+    }
+
+    /// Group keys into common prefixes at `delimiter`, see
+    /// [`ObjectIter::with_delimiter`]
+    pub fn with_delimiter(mut self, delimiter: impl Into<String>) -> Self {
+        self.iter = self.iter.with_delimiter(delimiter);
+        self
+    }
+
+    /// Yield common prefixes along with the objects, see
+    /// [`ObjectIter::with_common_prefixes`]
+    pub fn with_common_prefixes(mut self) -> Self {
+        self.iter = self.iter.with_common_prefixes();
+        self
+    }
+
+    /// List only keys from `key` onward, see [`ObjectIter::start_at`]
+    pub fn start_at(mut self, key: impl Into<String>) -> Self {
+        self.iter = self.iter.start_at(key);
+        self
+    }
+
+    /// List only keys sorting before `key`, see [`ObjectIter::end_before`]
+    pub fn end_before(mut self, key: impl Into<String>) -> Self {
+        self.iter = self.iter.end_before(key);
+        self
+    }
+
+    /// Sort objects by their modification time, newest first
+    ///
+    /// # Caveats
+    ///
+    /// S3 only lists objects in lexicographic key order, hence the complete
+    /// listing is buffered in memory before the first object is yielded.
+    pub fn sorted_by_mtime(self) -> SortedObjectStream {
+        SortedObjectStream {
+            inner: self,
+            buffer: Vec::new(),
+            sorted: None,
         }
     }
 
+    /// Position after the objects yielded so far, see
+    /// [`ObjectIter::token`]
+    pub fn token(&self) -> Option<ListToken> {
+        self.iter.token()
+    }
+
+    /// Continue the listing at `token`, see [`ObjectIter::set_token`]
+    pub fn set_token(&mut self, token: Option<ListToken>) {
+        self.fut = None;
+        self.iter.set_token(token);
+    }
+
     /// Return a reference to `ObjectIter`
     pub fn get_iter(&self) -> &ObjectIter {
         &self.iter
     }
 
-    /// Consume the string and return the `ObjectIter`
+    /// Consume the stream and return the `ObjectIter`
     pub fn into_iter(self) -> ObjectIter {
         self.iter
     }
 
     async fn get_objects(
-        client: S3Client,
+        client: Backend,
         request: ListObjectsV2Request,
-    ) -> RusotoResult<ListObjectsV2Output, ListObjectsV2Error> {
-        client.list_objects_v2(request).await
+        retry: RetryPolicy,
+    ) -> BackendResult<ListObjectsV2Output, ListObjectsV2Error> {
+        let span = span!(
+            DEBUG,
+            "list_objects_v2",
+            bucket = %request.bucket,
+            prefix = request.prefix.as_deref().unwrap_or_default(),
+            objects = tracing::field::Empty,
+        );
+        async {
+            let mut attempt = 0;
+            loop {
+                let error = match client.list_objects_v2(request.clone()).await {
+                    Err(e) => e,
+                    Ok(resp) => {
+                        let objects = resp.contents.as_ref().map_or(0, Vec::len);
+                        span.record("objects", objects as u64);
+                        return Ok(resp);
+                    }
+                };
+                if !retry.wait_for_retry(&error, &mut attempt).await {
+                    return Err(error);
+                }
+            }
+        }
+        .instrument(span.clone())
+        .await
+    }
+}
+
+impl From<ObjectIter> for ObjectStream {
+    fn from(iter: ObjectIter) -> Self {
+        Self { iter, fut: None }
+    }
+}
+
+impl From<ObjectStream> for ObjectIter {
+    fn from(stream: ObjectStream) -> Self {
+        stream.into_iter()
     }
 }
 
@@ -274,16 +591,17 @@ impl Stream for ObjectStream {
     type Item = RusotoResult<Object, ListObjectsV2Error>;
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
         if self.as_mut().fut.is_none() {
-            if let Some(object) = self.as_mut().iter.objects.next() {
+            if let Some(object) = self.as_mut().iter.next_buffered() {
                 return Poll::Ready(Some(Ok(object)));
             } else if self.as_mut().iter.exhausted {
                 return Poll::Ready(None);
             }
             let client = self.as_mut().iter.client.clone();
             let request = self.as_mut().iter.request.clone();
+            let retry = self.as_mut().iter.retry.clone();
             self.as_mut()
                 .fut
-                .replace(Box::pin(Self::get_objects(client, request)));
+                .replace(Box::pin(Self::get_objects(client, request, retry)));
         }
 
         let result = ready!(self.as_mut().fut.as_mut().unwrap().poll_unpin(cx));
@@ -291,13 +609,57 @@ impl Stream for ObjectStream {
 
         match result {
             Ok(resp) => self.as_mut().iter.update_objects(resp),
-            Err(e) => return Poll::Ready(Some(Err(e))),
+            Err(e) => return Poll::Ready(Some(Err(*e))),
+        }
+        match self.as_mut().iter.next_buffered() {
+            Some(object) => Poll::Ready(Some(Ok(object))),
+            None if self.as_mut().iter.exhausted => Poll::Ready(None),
+            // pages holding only skipped common prefixes are empty, fetch
+            // the next one
+            None => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
         }
-        self.as_mut()
-            .iter
-            .objects
-            .next()
-            .map_or(Poll::Ready(None), |object| Poll::Ready(Some(Ok(object))))
+    }
+}
+
+/// Stream over objects sorted by modification time, newest first
+///
+/// Objects with the same modification time are sorted by key, objects
+/// without a valid modification time come last.
+pub struct SortedObjectStream {
+    inner: ObjectStream,
+    buffer: Vec<Object>,
+    sorted: Option<IntoIter<Object>>,
+}
+
+impl SortedObjectStream {
+    /// Adjust listing requests to the quirks described by `compat`
+    pub fn with_compatibility(mut self, compat: &Compatibility) -> Self {
+        self.inner = self.inner.with_compatibility(compat);
+        self
+    }
+}
+
+impl Stream for SortedObjectStream {
+    type Item = RusotoResult<Object, ListObjectsV2Error>;
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        while self.sorted.is_none() {
+            match ready!(Pin::new(&mut self.inner).poll_next(cx)) {
+                Some(Ok(object)) => self.buffer.push(object),
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => {
+                    let mut objects = mem::take(&mut self.buffer);
+                    // listing is sorted by key, a stable sort keeps it that way
+                    // for identical timestamps; providers format timestamps
+                    // differently, they are compared as dates
+                    objects.sort_by_cached_key(|object| Reverse(object.last_modified_at()));
+                    self.sorted = Some(objects.into_iter());
+                }
+            }
+        }
+        Poll::Ready(self.sorted.as_mut().and_then(Iterator::next).map(Ok))
     }
 }
 
@@ -311,11 +673,7 @@ pub struct GetObjectIter {
 }
 
 impl GetObjectIter {
-    fn new(
-        client: &S3Client,
-        bucket: impl Into<String>,
-        prefix: Option<impl Into<String>>,
-    ) -> Self {
+    fn new(client: &Backend, bucket: impl Into<String>, prefix: Option<impl Into<String>>) -> Self {
         let bucket = bucket.into();
         GetObjectIter {
             inner: ObjectIter::new(client, &bucket, prefix),
@@ -323,6 +681,55 @@ impl GetObjectIter {
         }
     }
 
+    /// Adjust listing requests to the quirks described by `compat`
+    pub fn with_compatibility(mut self, compat: &Compatibility) -> Self {
+        self.inner = self.inner.with_compatibility(compat);
+        self
+    }
+
+    /// Retry failed listing and `GetObject` requests according to `policy`
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.inner = self.inner.with_retry_policy(policy);
+        self
+    }
+
+    /// Agree to pay for the listing and `GetObject` requests, required to
+    /// read from requester-pays buckets
+    pub fn with_requester_pays(mut self) -> Self {
+        self.inner = self.inner.with_requester_pays();
+        self
+    }
+
+    /// Group keys into common prefixes at `delimiter`, which are skipped,
+    /// see [`ObjectIter::with_delimiter`]
+    pub fn with_delimiter(mut self, delimiter: impl Into<String>) -> Self {
+        self.inner = self.inner.with_delimiter(delimiter);
+        self
+    }
+
+    /// List only keys from `key` onward, see [`ObjectIter::start_at`]
+    pub fn start_at(mut self, key: impl Into<String>) -> Self {
+        self.inner = self.inner.start_at(key);
+        self
+    }
+
+    /// List only keys sorting before `key`, see [`ObjectIter::end_before`]
+    pub fn end_before(mut self, key: impl Into<String>) -> Self {
+        self.inner = self.inner.end_before(key);
+        self
+    }
+
+    /// Position after the objects retrieved so far, see
+    /// [`ObjectIter::token`]
+    pub fn token(&self) -> Option<ListToken> {
+        self.inner.token()
+    }
+
+    /// Continue the listing at `token`, see [`ObjectIter::set_token`]
+    pub fn set_token(&mut self, token: Option<ListToken>) {
+        self.inner.set_token(token);
+    }
+
     async fn retrieve(
         &mut self,
         object: Option<Object>,
@@ -335,14 +742,17 @@ impl GetObjectIter {
                 let request = GetObjectRequest {
                     bucket: self.bucket.clone(),
                     key,
+                    request_payer: self.inner.request.request_payer.clone(),
                     ..Default::default()
                 };
-                match self.inner.client.get_object(request.clone()).await {
+                let client = self.inner.client.clone();
+                let retry = self.inner.retry.clone();
+                match GetObjectStream::get_object(client, request.clone(), retry).await {
                     Ok(o) => {
                         let key = request.key;
                         Ok(Some((key, o)))
                     }
-                    Err(e) => Err(e.into()),
+                    Err(e) => Err(S3ExtError::from(e).with_object(request.bucket, request.key)),
                 }
             }
             None => Ok(None),
@@ -380,9 +790,21 @@ impl GetObjectIter {
         let nth = self.inner.nth(n).await?;
         self.retrieve(nth).await
     }
+
+    /// Consume the iterator and return a `GetObjectStream` continuing where
+    /// the iterator left off
+    pub fn into_stream(self) -> GetObjectStream {
+        GetObjectStream::from(self)
+    }
+
+    /// Return a `GetObjectStream` continuing where the iterator is, leaving
+    /// the iterator itself untouched
+    pub fn as_stream(&self) -> GetObjectStream {
+        self.clone().into_stream()
+    }
 }
 
-type GetObjResult = RusotoResult<GetObjectOutput, GetObjectError>;
+type GetObjResult = BackendResult<GetObjectOutput, GetObjectError>;
 type NextGetObjFuture = Pin<Box<dyn Future<Output = GetObjResult> + Send>>;
 
 /// Stream which retrieves objects
@@ -396,17 +818,64 @@ pub struct GetObjectStream {
 
 impl GetObjectStream {
     pub(crate) fn new(
-        client: &S3Client,
+        client: &Backend,
         bucket: impl Into<String>,
         prefix: Option<impl Into<String>>,
     ) -> Self {
-        Self {
-            iter: GetObjectIter::new(client, bucket, prefix),
-            next: None,
-            key: None,
-            fut0: None,
-            fut1: None,
-        }
+        GetObjectIter::new(client, bucket, prefix).into()
+    }
+
+    /// Adjust listing requests to the quirks described by `compat`
+    pub fn with_compatibility(mut self, compat: &Compatibility) -> Self {
+        self.iter = self.iter.with_compatibility(compat);
+        self
+    }
+
+    /// Retry failed listing and `GetObject` requests according to `policy`
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.iter = self.iter.with_retry_policy(policy);
+        self
+    }
+
+    /// Agree to pay for the listing and `GetObject` requests, required to
+    /// read from requester-pays buckets
+    pub fn with_requester_pays(mut self) -> Self {
+        self.iter = self.iter.with_requester_pays();
+        self
+    }
+
+    /// Group keys into common prefixes at `delimiter`, which are skipped,
+    /// see [`ObjectIter::with_delimiter`]
+    pub fn with_delimiter(mut self, delimiter: impl Into<String>) -> Self {
+        self.iter = self.iter.with_delimiter(delimiter);
+        self
+    }
+
+    /// List only keys from `key` onward, see [`ObjectIter::start_at`]
+    pub fn start_at(mut self, key: impl Into<String>) -> Self {
+        self.iter = self.iter.start_at(key);
+        self
+    }
+
+    /// List only keys sorting before `key`, see [`ObjectIter::end_before`]
+    pub fn end_before(mut self, key: impl Into<String>) -> Self {
+        self.iter = self.iter.end_before(key);
+        self
+    }
+
+    /// Position after the objects yielded so far, see
+    /// [`ObjectIter::token`]
+    pub fn token(&self) -> Option<ListToken> {
+        self.iter.token()
+    }
+
+    /// Continue the listing at `token`, see [`ObjectIter::set_token`]
+    pub fn set_token(&mut self, token: Option<ListToken>) {
+        self.next = None;
+        self.key = None;
+        self.fut0 = None;
+        self.fut1 = None;
+        self.iter.set_token(token);
     }
 
     /// Return a reference to our `GetObjectIter` object
@@ -430,10 +899,43 @@ impl GetObjectStream {
     }
 
     async fn get_object(
-        client: S3Client,
+        client: Backend,
         request: GetObjectRequest,
-    ) -> RusotoResult<GetObjectOutput, GetObjectError> {
-        client.get_object(request).await
+        retry: RetryPolicy,
+    ) -> BackendResult<GetObjectOutput, GetObjectError> {
+        let span = span!(DEBUG, "get_object", bucket = %request.bucket, key = %request.key);
+        async {
+            let mut attempt = 0;
+            loop {
+                let error = match client.get_object(request.clone()).await {
+                    Err(e) => e,
+                    ok => return ok,
+                };
+                if !retry.wait_for_retry(&error, &mut attempt).await {
+                    return Err(error);
+                }
+            }
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+impl From<GetObjectIter> for GetObjectStream {
+    fn from(iter: GetObjectIter) -> Self {
+        Self {
+            iter,
+            next: None,
+            key: None,
+            fut0: None,
+            fut1: None,
+        }
+    }
+}
+
+impl From<GetObjectStream> for GetObjectIter {
+    fn from(stream: GetObjectStream) -> Self {
+        stream.into_iter()
     }
 }
 
@@ -441,16 +943,17 @@ impl Stream for GetObjectStream {
     type Item = S3ExtResult<(String, GetObjectOutput)>;
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
         if self.as_mut().fut0.is_none() && self.as_mut().fut1.is_none() {
-            if let Some(object) = self.as_mut().iter.inner.objects.next() {
+            if let Some(object) = self.as_mut().iter.inner.next_buffered() {
                 self.as_mut().next.replace(object);
             } else if self.as_mut().iter.inner.exhausted {
                 return Poll::Ready(None);
             } else {
                 let client = self.as_mut().iter.inner.client.clone();
                 let request = self.as_mut().iter.inner.request.clone();
+                let retry = self.as_mut().iter.inner.retry.clone();
                 self.as_mut()
                     .fut0
-                    .replace(Box::pin(ObjectStream::get_objects(client, request)));
+                    .replace(Box::pin(ObjectStream::get_objects(client, request, retry)));
             }
         }
 
@@ -464,11 +967,17 @@ impl Stream for GetObjectStream {
                 Ok(resp) => self.as_mut().iter.inner.update_objects(resp),
                 Err(e) => return Poll::Ready(Some(Err(e.into()))),
             }
-            match self.as_mut().iter.inner.objects.next() {
+            match self.as_mut().iter.inner.next_buffered() {
                 Some(next) => {
                     self.as_mut().next.replace(next);
                 }
-                None => return Poll::Ready(None),
+                None if self.as_mut().iter.inner.exhausted => return Poll::Ready(None),
+                // pages holding only skipped common prefixes are empty,
+                // fetch the next one
+                None => {
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
             }
         }
 
@@ -480,14 +989,16 @@ impl Stream for GetObjectStream {
             };
             self.as_mut().key.replace(key.clone());
             let client = self.as_mut().iter.inner.client.clone();
+            let retry = self.as_mut().iter.inner.retry.clone();
             let request = GetObjectRequest {
                 bucket: self.as_mut().iter.bucket.clone(),
                 key,
+                request_payer: self.as_mut().iter.inner.request.request_payer.clone(),
                 ..Default::default()
             };
             self.as_mut()
                 .fut1
-                .replace(Box::pin(Self::get_object(client, request)));
+                .replace(Box::pin(Self::get_object(client, request, retry)));
         }
 
         assert!(self.as_mut().fut0.is_none());
@@ -495,9 +1006,13 @@ impl Stream for GetObjectStream {
         if self.as_mut().fut1.is_some() {
             let result = ready!(self.as_mut().fut1.as_mut().unwrap().poll_unpin(cx));
             self.as_mut().fut1.take();
+            let key = self.as_mut().key.take().unwrap();
             match result {
-                Ok(obj) => Poll::Ready(Some(Ok((self.as_mut().key.take().unwrap(), obj)))),
-                Err(e) => Poll::Ready(Some(Err(e.into()))),
+                Ok(obj) => Poll::Ready(Some(Ok((key, obj)))),
+                Err(e) => {
+                    let bucket = self.as_mut().iter.bucket.clone();
+                    Poll::Ready(Some(Err(S3ExtError::from(e).with_object(bucket, key))))
+                }
             }
         } else {
             panic!("We shouldn't ever get here...");
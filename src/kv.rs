@@ -0,0 +1,237 @@
+//! Typed key-value store on a bucket and prefix
+//!
+//! A [`KvStore`] keeps each value in its own object, named by the prefix of
+//! the store followed by the key, and serialized using a
+//! [`Codec`](crate::codec::Codec). Values are limited to
+//! [`codec::MAX_SIZE`](crate::codec::MAX_SIZE).
+//!
+//! # Compression
+//!
+//! Values written by a store created with [`KvStore::gzip`] are gzip
+//! compressed and uploaded with `Content-Encoding: gzip` (`gzip` feature).
+//! Values are decompressed according to their `Content-Encoding` when read,
+//! so stores with and without compression can share a prefix.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # #[cfg(feature = "serde_json")]
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use futures::StreamExt;
+//! use s3_ext::{codec::Json, kv::KvStore};
+//!
+//! let store: KvStore<_, u64, Json> = KvStore::new(client, "bucket", "counters/");
+//! store.put("visits", &1).await?;
+//! assert_eq!(store.get("visits").await?, Some(1));
+//!
+//! let mut counters = Box::pin(store.scan(""));
+//! while let Some(counter) = counters.next().await {
+//!     let (key, value) = counter?;
+//!     println!("{}: {}", key, value);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    codec::{self, Codec, MAX_SIZE},
+    error::{S3ExtError, S3ExtResult},
+    S3Ext,
+};
+use futures::{Stream, StreamExt, TryStreamExt};
+use rusoto_s3::PutObjectRequest;
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+
+/// Values of type `T` stored in `bucket` below `prefix`, serialized using
+/// codec `K`
+pub struct KvStore<C, T, K> {
+    client: C,
+    bucket: String,
+    prefix: String,
+    gzip: bool,
+    marker: PhantomData<fn() -> (T, K)>,
+}
+
+impl<C, T, K> KvStore<C, T, K>
+where
+    C: S3Ext,
+    T: Serialize + DeserializeOwned,
+    K: Codec,
+{
+    /// Store values in `bucket` below `prefix`, e.g. `sessions/`
+    pub fn new(client: C, bucket: impl Into<String>, prefix: impl Into<String>) -> Self {
+        KvStore {
+            client,
+            bucket: bucket.into(),
+            prefix: prefix.into(),
+            gzip: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// Compress written values using gzip
+    ///
+    /// Requires the `gzip` feature.
+    #[cfg(feature = "gzip")]
+    pub fn gzip(mut self) -> Self {
+        self.gzip = true;
+        self
+    }
+
+    /// Underlying client
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    /// Bucket the values are stored in
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    /// Prefix of the keys of the objects
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    // Key of the object storing `key`
+    fn object_key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    /// Get the value of `key`, `None` if there is none
+    pub async fn get(&self, key: &str) -> S3ExtResult<Option<T>> {
+        let object_key = self.object_key(key);
+        let (output, bytes) =
+            match codec::download_limited(&self.client, self.bucket.clone(), object_key.clone())
+                .await
+            {
+                Ok(downloaded) => downloaded,
+                Err(e) if e.is_not_found() => return Ok(None),
+                Err(e) => return Err(e),
+            };
+        let result = match output.content_encoding.as_deref() {
+            Some("gzip") => decompress(bytes).await,
+            _ => Ok(bytes),
+        };
+        result
+            .and_then(|bytes| K::decode(&bytes))
+            .map(Some)
+            .map_err(|e| e.with_object(self.bucket.clone(), object_key))
+    }
+
+    /// Set the value of `key` to `value`
+    pub async fn put(&self, key: &str, value: &T) -> S3ExtResult<()> {
+        let object_key = self.object_key(key);
+        let body = match K::encode(value) {
+            Ok(body) if body.len() as u64 > MAX_SIZE => {
+                Err(S3ExtError::TooLarge { limit: MAX_SIZE })
+            }
+            Ok(body) => compress(body, self.gzip).await,
+            Err(e) => Err(e),
+        }
+        .map_err(|e| e.with_object(self.bucket.clone(), object_key.clone()))?;
+        let request = PutObjectRequest {
+            bucket: self.bucket.clone(),
+            key: object_key,
+            content_type: Some(K::CONTENT_TYPE.to_owned()),
+            content_encoding: if self.gzip {
+                Some("gzip".to_owned())
+            } else {
+                None
+            },
+            ..Default::default()
+        };
+        self.client.upload(&mut body.as_slice(), request).await?;
+        Ok(())
+    }
+
+    /// Delete `key`, deleting a missing key succeeds
+    pub async fn delete(&self, key: &str) -> S3ExtResult<()> {
+        self.client
+            .delete(self.bucket.clone(), self.object_key(key))
+            .await
+    }
+
+    /// Stream over the keys starting with `prefix`, sorted
+    ///
+    /// Keys are relative to the prefix of the store.
+    pub fn keys(&self, prefix: &str) -> impl Stream<Item = S3ExtResult<String>> {
+        let strip = self.prefix.len();
+        self.client
+            .stream_objects_with_prefix(self.bucket.clone(), self.object_key(prefix))
+            .map(move |object| -> S3ExtResult<String> {
+                let key = object?
+                    .key
+                    .ok_or(S3ExtError::Other("response is missing key"))?;
+                Ok(key[strip..].to_owned())
+            })
+    }
+
+    /// Keys starting with `prefix`, sorted
+    ///
+    /// Keys are relative to the prefix of the store.
+    pub async fn list(&self, prefix: &str) -> S3ExtResult<Vec<String>> {
+        self.keys(prefix).try_collect().await
+    }
+
+    /// Stream over the keys starting with `prefix` and their values, sorted
+    /// by key
+    ///
+    /// Values are fetched one at a time as the stream is polled, keys deleted
+    /// in the meantime are skipped.
+    pub fn scan<'a>(&'a self, prefix: &str) -> impl Stream<Item = S3ExtResult<(String, T)>> + 'a {
+        self.keys(prefix).filter_map(move |key| async move {
+            let key = match key {
+                Ok(key) => key,
+                Err(e) => return Some(Err(e)),
+            };
+            match self.get(&key).await {
+                Ok(Some(value)) => Some(Ok((key, value))),
+                Ok(None) => None,
+                Err(e) => Some(Err(e)),
+            }
+        })
+    }
+}
+
+#[cfg(feature = "gzip")]
+async fn compress(bytes: Vec<u8>, gzip: bool) -> S3ExtResult<Vec<u8>> {
+    use tokio::io::AsyncReadExt;
+
+    if !gzip {
+        return Ok(bytes);
+    }
+    let mut encoder = async_compression::tokio::bufread::GzipEncoder::new(bytes.as_slice());
+    let mut compressed = Vec::new();
+    encoder.read_to_end(&mut compressed).await?;
+    Ok(compressed)
+}
+
+#[cfg(not(feature = "gzip"))]
+async fn compress(bytes: Vec<u8>, _gzip: bool) -> S3ExtResult<Vec<u8>> {
+    Ok(bytes)
+}
+
+#[cfg(feature = "gzip")]
+async fn decompress(bytes: Vec<u8>) -> S3ExtResult<Vec<u8>> {
+    use tokio::io::AsyncReadExt;
+
+    let decoder = async_compression::tokio::bufread::GzipDecoder::new(bytes.as_slice());
+    let mut decompressed = Vec::new();
+    decoder
+        .take(MAX_SIZE + 1)
+        .read_to_end(&mut decompressed)
+        .await?;
+    if decompressed.len() as u64 > MAX_SIZE {
+        return Err(S3ExtError::TooLarge { limit: MAX_SIZE });
+    }
+    Ok(decompressed)
+}
+
+#[cfg(not(feature = "gzip"))]
+async fn decompress(_bytes: Vec<u8>) -> S3ExtResult<Vec<u8>> {
+    Err(S3ExtError::Other(
+        "reading gzip compressed values requires the `gzip` feature",
+    ))
+}
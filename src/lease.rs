@@ -0,0 +1,216 @@
+//! Advisory leases
+//!
+//! A [`Lease`] keeps cooperating processes from doing the same work at
+//! once, e.g. two instances of a batch job, for a limited time. It is held
+//! by a marker object containing a random token, the time the lease expires
+//! is stored in its user metadata. See
+//! [`S3Ext::acquire_lease`](crate::S3Ext::acquire_lease),
+//! [`Lease::renew`] and [`Lease::release`].
+//!
+//! # Caveats
+//!
+//! Leases are best effort and only exclude processes that acquire them.
+//! S3 can't create an object only if it doesn't exist yet, so processes
+//! acquiring a free lease at the same time may all write a marker. The
+//! marker is read back after it is written, which makes all but the last
+//! writer fail in most cases, but not all. Renewal copies the marker onto
+//! itself only if its entity tag is unchanged, so an expired lease taken
+//! over by someone else can't be renewed. Releasing checks the marker and
+//! deletes it in separate requests.
+//!
+//! Expiry is judged by the local clock: keep the duration of a lease well
+//! above the clock skew between hosts, renew it well before it expires and
+//! make the work it guards safe to repeat. Don't use the key of the marker
+//! for anything else, objects without an expiry count as expired leases.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::S3Ext;
+//! use std::time::Duration;
+//!
+//! let mut lease = client
+//!     .acquire_lease("jobs", "locks/nightly", Duration::from_secs(60))
+//!     .await?;
+//! for batch in 0..10 {
+//!     println!("processing batch {}", batch);
+//!     lease.renew(&client, Duration::from_secs(60)).await?;
+//! }
+//! lease.release(&client).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::{S3ExtError, S3ExtResult},
+    metadata::{Metadata, ObjectMetadata},
+    timestamps::parse_timestamp,
+    S3Ext,
+};
+use chrono::{DateTime, SecondsFormat, SubsecRound, Utc};
+use rusoto_s3::PutObjectRequest;
+use std::time::Duration;
+
+/// Name of the user metadata entry holding the expiry of a lease
+pub const EXPIRES: &str = "lease-expires";
+
+/// Lease held on a marker object, see the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct Lease {
+    bucket: String,
+    key: String,
+    token: String,
+    e_tag: Option<String>,
+    expires_at: DateTime<Utc>,
+}
+
+impl Lease {
+    /// Bucket of the marker
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    /// Key of the marker
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Random token identifying this holder, the content of the marker
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Time the lease expires unless it is renewed
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.expires_at
+    }
+
+    /// Whether the lease expired according to the local clock
+    pub fn is_expired(&self) -> bool {
+        Utc::now() >= self.expires_at
+    }
+
+    /// Extend the lease to expire `ttl` from now
+    ///
+    /// Fails with [`S3ExtError::LeaseLost`] if the marker was changed or
+    /// removed, e.g. because the lease expired and was acquired by someone
+    /// else.
+    pub async fn renew<C>(&mut self, client: &C, ttl: Duration) -> S3ExtResult<()>
+    where
+        C: S3Ext + ?Sized,
+    {
+        let expires_at = expiry(ttl_duration(ttl)?);
+        let metadata = ObjectMetadata {
+            content_type: Some("text/plain".to_owned()),
+            user: Metadata::new().entry(EXPIRES, timestamp(expires_at)),
+            e_tag: self.e_tag.clone(),
+            ..Default::default()
+        };
+        match client
+            .replace_metadata(&self.bucket, &self.key, metadata)
+            .await
+        {
+            Ok(()) => (),
+            Err(e) if e.is_not_found() || e.code() == Some("PreconditionFailed") => {
+                return Err(S3ExtError::LeaseLost.with_object(&self.bucket, &self.key))
+            }
+            Err(e) => return Err(e),
+        }
+        // the copy may change the entity tag, e.g. with SSE-KMS
+        let marker = client.object_metadata(&self.bucket, &self.key).await?;
+        self.e_tag = marker.e_tag;
+        self.expires_at = expires_at;
+        Ok(())
+    }
+
+    /// Release the lease, deleting the marker
+    ///
+    /// Fails with [`S3ExtError::LeaseLost`] if the marker was changed or
+    /// removed, the marker is left alone in this case.
+    pub async fn release<C>(self, client: &C) -> S3ExtResult<()>
+    where
+        C: S3Ext + ?Sized,
+    {
+        match client.object_metadata(&self.bucket, &self.key).await {
+            Ok(marker) if marker.e_tag == self.e_tag => (),
+            Ok(_) => return Err(S3ExtError::LeaseLost.with_object(self.bucket, self.key)),
+            Err(e) if e.is_not_found() => {
+                return Err(S3ExtError::LeaseLost.with_object(self.bucket, self.key))
+            }
+            Err(e) => return Err(e),
+        }
+        client.delete(self.bucket, self.key).await
+    }
+}
+
+fn ttl_duration(ttl: Duration) -> S3ExtResult<chrono::Duration> {
+    chrono::Duration::from_std(ttl).map_err(|_| S3ExtError::Other("lease duration out of range"))
+}
+
+// Expiry of a lease of duration `ttl` acquired now, with the precision it
+// is stored with
+fn expiry(ttl: chrono::Duration) -> DateTime<Utc> {
+    (Utc::now() + ttl).trunc_subsecs(3)
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+pub(crate) async fn acquire<C>(
+    client: &C,
+    bucket: String,
+    key: String,
+    ttl: Duration,
+) -> S3ExtResult<Lease>
+where
+    C: S3Ext + ?Sized,
+{
+    let ttl = ttl_duration(ttl)?;
+    match client.object_metadata(bucket.clone(), key.clone()).await {
+        Ok(marker) => match marker.user.get(EXPIRES).and_then(parse_timestamp) {
+            Some(expires_at) if expires_at > Utc::now() => {
+                return Err(S3ExtError::LeaseHeld { expires_at }.with_object(bucket, key))
+            }
+            _ => (),
+        },
+        Err(e) if e.is_not_found() => (),
+        Err(e) => return Err(e),
+    }
+
+    // the token makes the entity tag of the marker unique to this holder
+    let token = hex::encode(rand::random::<[u8; 16]>());
+    let expires_at = expiry(ttl);
+    let metadata = Metadata::new()
+        .entry(EXPIRES, timestamp(expires_at))
+        .build()
+        .map_err(|e| e.with_object(&bucket, &key))?;
+    let request = PutObjectRequest {
+        bucket: bucket.clone(),
+        key: key.clone(),
+        content_length: Some(token.len() as i64),
+        content_type: Some("text/plain".to_owned()),
+        metadata: Some(metadata),
+        ..Default::default()
+    };
+    let output = client.upload(&mut token.as_bytes(), request).await?;
+
+    // the last of several concurrent writers wins
+    let marker = client.object_metadata(bucket.clone(), key.clone()).await?;
+    if marker.e_tag != output.e_tag {
+        let expires_at = marker
+            .user
+            .get(EXPIRES)
+            .and_then(parse_timestamp)
+            .unwrap_or(expires_at);
+        return Err(S3ExtError::LeaseHeld { expires_at }.with_object(bucket, key));
+    }
+    Ok(Lease {
+        bucket,
+        key,
+        token,
+        e_tag: marker.e_tag,
+        expires_at,
+    })
+}
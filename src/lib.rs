@@ -17,6 +17,30 @@
 //! [features]
 //! default = ["s3_ext/native-tls"]
 //! ```
+//!
+//! # Prelude
+//!
+//! `use s3_ext::prelude::*;` imports [`S3Ext`], its stream types, the error
+//! types and the Rusoto types needed for most requests, see [`prelude`].
+//!
+//! # Tracing
+//!
+//! Enabling the `tracing` feature instruments the transfer methods of
+//! `S3Ext`, as well as each uploaded part and listing page, with
+//! [`tracing`](https://crates.io/crates/tracing) spans carrying bucket, key,
+//! part number and byte counts.
+//!
+//! # Record and Replay
+//!
+//! The `cassette` feature allows recording interactions with S3 to a file and
+//! replaying them in tests, see [`cassette`].
+//!
+//! # AWS SDK
+//!
+//! With the `aws-sdk` feature, `S3Ext` is also implemented for the client of
+//! the official [`aws-sdk-s3`](https://crates.io/crates/aws-sdk-s3), using the
+//! same Rusoto request, response and error types as for `S3Client`. This
+//! allows migrating away from Rusoto without rewriting code built on `S3Ext`.
 
 #![allow(clippy::default_trait_access)]
 #![allow(clippy::module_name_repetitions)]
@@ -27,28 +51,136 @@
 #![allow(clippy::missing_errors_doc)]
 #![allow(clippy::type_repetition_in_bounds)]
 
+pub mod access_log;
+pub mod acl;
+use crate::acl::{Acl, CannedAcl};
+pub mod append_log;
+pub mod archive;
+use crate::archive::ArchiveFormat;
+pub mod attributes;
+use crate::attributes::ObjectAttributes;
+#[cfg(feature = "aws-sdk")]
+mod aws_sdk;
+mod backend;
+pub mod backup;
+pub mod batch;
+use crate::backend::Backend;
+use crate::batch::{BatchReport, Manifest};
+pub mod body;
+pub mod bucket_config;
+use crate::body::{BodyStream, ObjectBody};
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cache;
+pub mod cas;
+use crate::cas::ContentHashKey;
+#[cfg(feature = "cassette")]
+pub mod cassette;
+pub mod client;
+pub mod coalesce;
+#[cfg(feature = "serde")]
+pub mod codec;
+pub mod compat;
+pub mod compose;
+use crate::compose::ComposePart;
+pub mod compress;
+use crate::compress::Compression;
+pub mod cost;
+pub mod decompress;
+pub mod dedup;
+pub mod delta;
+use crate::delta::DeltaReport;
+pub mod diff;
+use crate::diff::DiffStream;
+pub mod download;
+use crate::download::DownloadReport;
+pub mod encryption;
 pub mod iter;
-use crate::iter::{GetObjectStream, ObjectStream};
+#[cfg(feature = "serde")]
+pub mod kv;
+pub mod lease;
+use crate::lease::Lease;
+pub mod lines;
+pub mod metadata;
+use crate::metadata::{ObjectMetadata, RewriteReport};
+pub mod metrics;
+#[cfg(feature = "mock")]
+pub mod mock;
+use crate::iter::{GetObjectStream, ObjectStream, SortedObjectStream};
+use crate::lines::LineStream;
 pub mod error;
+pub mod expiration;
+pub mod fault;
+pub mod file_attributes;
+pub mod find;
+pub mod framed;
+use crate::framed::FramedObject;
+pub mod fs;
+pub mod intelligent_tiering;
+pub mod intercept;
 use crate::error::{S3ExtError, S3ExtResult};
+pub mod multi_region;
+pub mod part_size;
+use crate::part_size::PartSize;
+pub mod policy;
+mod pool;
+pub mod prelude;
+pub mod presign;
+pub mod rate_limit;
+pub mod replication;
+pub mod restore;
+use crate::restore::{RestoreStatus, RestoreTier};
+pub mod resume;
+pub mod retry;
+use crate::retry::RetryPolicy;
+pub mod select;
+pub mod sse_customer;
+pub mod stats;
+pub mod storage_class;
+pub mod stream_copy;
+use crate::storage_class::{TransitionPlan, TransitionReport};
+pub mod tagging;
+use crate::tagging::{TagMode, TagReport, Tags};
+#[cfg(feature = "testing")]
+pub mod testing;
+use crate::stats::{Counting, TransferStats};
+pub mod timeout;
+pub mod timestamps;
+mod trace;
+use crate::timeout::TimeoutS3Client;
+use crate::trace::{span, Instrument};
+pub mod transfer;
 mod upload;
+pub mod watch;
+pub mod website;
+use crate::watch::WatchStream;
 
 use async_trait::async_trait;
 use log::debug;
 use rusoto_core::{
     request::{HttpClient, TlsError},
-    Region,
+    Client, Region,
+};
+use rusoto_credential::{
+    AutoRefreshingProvider, CredentialsError, ProfileProvider, StaticProvider,
 };
-use rusoto_credential::StaticProvider;
 use rusoto_s3::{
-    CompleteMultipartUploadOutput, GetObjectOutput, GetObjectRequest, PutObjectOutput,
-    PutObjectRequest, S3Client, StreamingBody, S3,
+    CompleteMultipartUploadOutput, CopyObjectRequest, DeleteObjectRequest, GetObjectOutput,
+    GetObjectRequest, Object, PutObjectOutput, PutObjectRequest, S3Client,
+};
+use rusoto_sts::{StsAssumeRoleSessionCredentialsProvider, StsClient, WebIdentityProvider};
+use std::{
+    convert::AsRef,
+    env,
+    future::Future,
+    path::{Path, PathBuf},
+    time::Duration,
 };
-use std::{convert::AsRef, path::Path};
 use tokio::{
     fs::{File, OpenOptions},
     io,
 };
+use tokio_util::codec::Decoder;
 
 /// Create client using given static access/secret keys
 pub fn new_s3client_with_credentials(
@@ -63,9 +195,98 @@ pub fn new_s3client_with_credentials(
     ))
 }
 
+/// Create client sending unsigned requests
+///
+/// Useful for reading from public buckets, e.g. open data sets, without any
+/// credentials.
+pub fn new_s3client_anonymous(region: Region) -> Result<S3Client, TlsError> {
+    Ok(S3Client::new_with_client(
+        Client::new_not_signing(HttpClient::new()?),
+        region,
+    ))
+}
+
+/// Create client using the credentials of profile `profile_name` from the
+/// shared credentials file (`~/.aws/credentials`)
+pub fn new_s3client_from_profile(
+    profile_name: impl Into<String>,
+    region: Region,
+) -> S3ExtResult<S3Client> {
+    let provider = ProfileProvider::with_default_credentials(profile_name.into())?;
+    Ok(S3Client::new_with(HttpClient::new()?, provider, region))
+}
+
+/// Create client using temporary credentials obtained by assuming role
+/// `role_arn`
+///
+/// The role is assumed using the default credentials, temporary credentials
+/// are refreshed automatically before they expire.
+pub fn new_s3client_assume_role(
+    role_arn: impl Into<String>,
+    session_name: impl Into<String>,
+    region: Region,
+) -> S3ExtResult<S3Client> {
+    let provider = StsAssumeRoleSessionCredentialsProvider::new(
+        StsClient::new(region.clone()),
+        role_arn.into(),
+        session_name.into(),
+        None,
+        None,
+        None,
+        None,
+    );
+    let provider = AutoRefreshingProvider::new(provider)?;
+    Ok(S3Client::new_with(HttpClient::new()?, provider, region))
+}
+
+// `WebIdentityProvider` only notices missing variables on the first request
+pub(crate) fn check_web_identity_env() -> S3ExtResult<()> {
+    for var in &["AWS_WEB_IDENTITY_TOKEN_FILE", "AWS_ROLE_ARN"] {
+        if env::var_os(var).is_none() {
+            return Err(CredentialsError::new(format!("{} is not set", var)).into());
+        }
+    }
+    Ok(())
+}
+
+/// Create client using a web identity token, e.g. in EKS (IRSA) or GitHub
+/// Actions (OIDC)
+///
+/// The token is read from the file named by `AWS_WEB_IDENTITY_TOKEN_FILE` and
+/// exchanged for temporary credentials of the role `AWS_ROLE_ARN`. The
+/// session is named after `AWS_ROLE_SESSION_NAME`, if set. Credentials are
+/// refreshed automatically before they expire.
+pub fn new_s3client_from_web_identity(region: Region) -> S3ExtResult<S3Client> {
+    check_web_identity_env()?;
+    let provider = AutoRefreshingProvider::new(WebIdentityProvider::from_k8s_env())?;
+    Ok(S3Client::new_with(HttpClient::new()?, provider, region))
+}
+
 #[async_trait]
 pub trait S3Ext {
+    /// Send a single request for object `key` in `bucket`, `key` is empty
+    /// for requests on the bucket itself
+    ///
+    /// `request` is called with the client to use and may be called again,
+    /// e.g. when retrying. Wrappers like
+    /// [`RetryingS3Client`](retry::RetryingS3Client) apply their behaviour
+    /// here, the methods built on it don't need to be forwarded one by one.
+    #[doc(hidden)]
+    async fn send_request<T, F, Fut>(
+        &self,
+        operation: &'static str,
+        bucket: String,
+        key: String,
+        request: F,
+    ) -> S3ExtResult<T>
+    where
+        T: Send,
+        F: Fn(Backend, String, String) -> Fut + Send + Sync,
+        Fut: Future<Output = S3ExtResult<T>> + Send;
+
     /// Get object and write it to file `target`
+    ///
+    /// Interrupted bodies are resumed where they broke off, see [`resume`].
     async fn download_to_file<F>(
         &self,
         source: GetObjectRequest,
@@ -76,6 +297,9 @@ pub trait S3Ext {
 
     /// Upload content of file to S3
     ///
+    /// With feature `mime_guess`, `content_type` is guessed from the
+    /// extension of `source` unless set.
+    ///
     /// # Caveats
     ///
     /// The current implementation is incomplete. For now, the following
@@ -90,8 +314,77 @@ pub trait S3Ext {
     where
         F: AsRef<Path> + Send + Sync;
 
+    /// Upload files, up to `concurrency` at a time
+    ///
+    /// `files` pairs the path of each file with the request it is uploaded
+    /// with, as passed to [`upload_from_file`](Self::upload_from_file). Failed
+    /// uploads don't stop the others, the result of every upload is returned
+    /// along with its path, in the given order.
+    async fn upload_files<I>(
+        &self,
+        files: I,
+        concurrency: usize,
+    ) -> Vec<(PathBuf, S3ExtResult<PutObjectOutput>)>
+    where
+        I: IntoIterator<Item = (PathBuf, PutObjectRequest)> + Send,
+        I::IntoIter: Send,
+    {
+        upload::upload_files(self, files, concurrency).await
+    }
+
+    /// Download objects `keys` in `bucket` into directory `dir`, up to
+    /// `concurrency` at a time
+    ///
+    /// Keys are used as paths relative to `dir`, `/` separating directories,
+    /// which are created as needed. Keys that would end up outside of `dir`
+    /// fail. Failed downloads don't stop the others, the report lists the
+    /// result of every key in the given order. See [`download`].
+    async fn download_objects<I, D>(
+        &self,
+        bucket: impl Into<String> + Send,
+        keys: I,
+        dir: D,
+        concurrency: usize,
+    ) -> DownloadReport
+    where
+        I: IntoIterator + Send,
+        I::Item: Into<String>,
+        D: AsRef<Path> + Send + Sync,
+    {
+        let keys = keys.into_iter().map(Into::into).collect();
+        download::download_objects(self, bucket.into(), keys, dir.as_ref(), concurrency).await
+    }
+
+    /// Like [`download_objects`](Self::download_objects), writing each object
+    /// to the writer `target` returns for its key
+    ///
+    /// Writers are flushed once their object has been written.
+    async fn download_objects_with<I, F, W>(
+        &self,
+        bucket: impl Into<String> + Send,
+        keys: I,
+        target: F,
+        concurrency: usize,
+    ) -> DownloadReport
+    where
+        I: IntoIterator + Send,
+        I::Item: Into<String>,
+        F: Fn(&str) -> S3ExtResult<W> + Send + Sync,
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        let keys = keys.into_iter().map(Into::into).collect();
+        download::download_objects_with(self, bucket.into(), keys, target, concurrency).await
+    }
+
     /// Upload content of file to S3 using multi-part upload
     ///
+    /// With feature `mime_guess`, `content_type` is guessed from the
+    /// extension of `source` unless set.
+    ///
+    /// `part_size` must be between 5 MiB and 5 GiB and the file must fit into
+    /// 10,000 parts, otherwise `S3ExtError::InvalidPartSize` is returned
+    /// before anything is uploaded.
+    ///
     /// # Caveats
     ///
     /// The current implementation is incomplete. For now, the following
@@ -104,10 +397,27 @@ pub trait S3Ext {
         target: PutObjectRequest,
         part_size: usize,
     ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.upload_from_file_multipart_with(source, target, PartSize::Fixed(part_size))
+            .await
+    }
+
+    /// Like [`upload_from_file_multipart`](Self::upload_from_file_multipart),
+    /// choosing the size of parts according to `part_size`
+    async fn upload_from_file_multipart_with<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
     where
         F: AsRef<Path> + Send + Sync;
 
     /// Get object and write it to `target`
+    ///
+    /// Interrupted bodies are resumed where they broke off, see [`resume`].
     async fn download<W>(
         &self,
         source: GetObjectRequest,
@@ -116,6 +426,103 @@ pub trait S3Ext {
     where
         W: io::AsyncWrite + Unpin + Send;
 
+    /// Like [`download`](Self::download), resuming an interrupted body up to
+    /// `attempts` times instead of [`resume::DEFAULT_ATTEMPTS`]
+    ///
+    /// An `attempts` of 0 fails as soon as the body breaks off.
+    async fn download_resumable<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+        attempts: u32,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        let (resp, _) = resume::download(self, source, target, attempts).await?;
+        Ok(resp)
+    }
+
+    /// Like [`download`](Self::download), decompressing the body according
+    /// to its `content_encoding`
+    ///
+    /// Bodies encoded with `gzip` require feature `gzip`, those encoded with
+    /// `zstd` feature `zstd`. Other encodings fail with `S3ExtError::Other`
+    /// before anything is written to `target`. The `content_encoding` and
+    /// `content_length` of decompressed objects are cleared in the output.
+    /// See [`decompress`].
+    async fn download_decompressed<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send;
+
+    /// Get object and decode its body into frames using `decoder`
+    ///
+    /// The body is streamed, frames are decoded as they arrive. See
+    /// [`framed`] for an example.
+    async fn get_framed<D>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        decoder: D,
+    ) -> S3ExtResult<FramedObject<D>>
+    where
+        D: Decoder + Send;
+
+    /// Get object `key` in `bucket`, returning its body to be read or
+    /// streamed
+    ///
+    /// See [`body`].
+    async fn get_body(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectBody> {
+        let request = GetObjectRequest {
+            bucket: bucket.into(),
+            key: key.into(),
+            ..Default::default()
+        };
+        self.get_body_with(request).await
+    }
+
+    /// Like [`get_body`](Self::get_body), sending request `source`, e.g. to
+    /// get a range or version of the object
+    async fn get_body_with(&self, source: GetObjectRequest) -> S3ExtResult<ObjectBody>;
+
+    /// Get object `key` in `bucket`, returning its body as a stream of
+    /// chunks along with its length
+    ///
+    /// See [`body::BodyStream`].
+    async fn get_byte_stream(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<BodyStream> {
+        Ok(self.get_body(bucket, key).await?.into_byte_stream())
+    }
+
+    /// Get object `key` in `bucket`, reading its body into memory
+    ///
+    /// Built on [`download`](Self::download).
+    async fn get_bytes(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<Vec<u8>> {
+        let request = GetObjectRequest {
+            bucket: bucket.into(),
+            key: key.into(),
+            ..Default::default()
+        };
+        let mut body = Vec::new();
+        self.download(request, &mut body).await?;
+        Ok(body)
+    }
+
     /// Read `source` and upload it to S3
     ///
     /// # Caveats
@@ -134,6 +541,10 @@ pub trait S3Ext {
 
     /// Read `source` and upload it to S3 using multi-part upload
     ///
+    /// `part_size` must be between 5 MiB and 5 GiB, otherwise
+    /// `S3ExtError::InvalidPartSize` is returned before anything is
+    /// uploaded. The upload is aborted if `source` exceeds 10,000 parts.
+    ///
     /// # Caveats
     ///
     /// The current implementation is incomplete. For now, the following
@@ -146,9 +557,68 @@ pub trait S3Ext {
         target: PutObjectRequest,
         part_size: usize,
     ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.upload_multipart_with(source, target, PartSize::Fixed(part_size))
+            .await
+    }
+
+    /// Like [`upload_multipart`](Self::upload_multipart), choosing the size
+    /// of parts according to `part_size`
+    ///
+    /// See [`PartSize`] for the options.
+    async fn upload_multipart_with<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send;
+
+    /// Like [`upload_multipart_with`](Self::upload_multipart_with),
+    /// continuing an upload to `target` left in progress, e.g. by an
+    /// interrupted process
+    ///
+    /// If uploads to the key of `target` are in progress, the most recently
+    /// started one is continued: its parts matching `source` in size and MD5
+    /// digest are kept, the others are uploaded anew. Content type, metadata
+    /// etc. are the ones the upload was started with in this case. Parts
+    /// whose entity tag isn't the MD5 digest of their content, e.g. with
+    /// SSE-KMS or SSE-C, are always uploaded again.
+    ///
+    /// Unlike the other multipart uploads, the upload isn't aborted if this
+    /// fails, so calling this again continues it. Abort it using
+    /// `S3::abort_multipart_upload` or a lifecycle rule if it won't be.
+    async fn upload_multipart_resumable<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
     where
         R: io::AsyncRead + Unpin + Send;
 
+    /// Read `source` once and upload it to every request in `targets`
+    /// concurrently, e.g. to buckets in several regions using a
+    /// [`MultiRegionClient`](multi_region::MultiRegionClient)
+    ///
+    /// Uploads use multipart upload with [`PartSize::Auto`], one part per
+    /// target is held in memory. Returns the result of each target in the
+    /// order given. A failed target doesn't affect the others, all uploads
+    /// are aborted if reading `source` fails.
+    async fn upload_replicated<R>(
+        &self,
+        source: &mut R,
+        targets: Vec<PutObjectRequest>,
+    ) -> Vec<S3ExtResult<CompleteMultipartUploadOutput>>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        upload::upload_replicated(self, source, targets).await
+    }
+
     /// Stream over all objects
     /// Access to an iterator-like object `ObjectIter` can be obtained by
     /// calling into_iter()
@@ -165,6 +635,56 @@ pub trait S3Ext {
         prefix: impl Into<String>,
     ) -> ObjectStream;
 
+    /// Stream over all objects sorted by modification time, newest first
+    ///
+    /// # Caveats
+    ///
+    /// The complete listing is buffered in memory before the first object is
+    /// yielded.
+    fn stream_objects_sorted_by_mtime(&self, bucket: impl Into<String>) -> SortedObjectStream;
+
+    /// Stream over objects with given `prefix` sorted by modification time,
+    /// newest first
+    ///
+    /// # Caveats
+    ///
+    /// The complete listing is buffered in memory before the first object is
+    /// yielded.
+    fn stream_objects_with_prefix_sorted_by_mtime(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> SortedObjectStream;
+
+    /// Compare the objects below `source_prefix` in `source_bucket` with the
+    /// objects below `dest_prefix` in `dest_bucket`
+    ///
+    /// Keys are compared relative to their prefix, use an empty prefix to
+    /// compare whole buckets. Identical objects are skipped.
+    fn diff(
+        &self,
+        source_bucket: impl Into<String>,
+        source_prefix: impl Into<String>,
+        dest_bucket: impl Into<String>,
+        dest_prefix: impl Into<String>,
+    ) -> DiffStream;
+
+    /// Watch objects with given `prefix` for changes by listing them every
+    /// `period`
+    ///
+    /// The first listing serves as baseline, changes are reported relative to
+    /// the previous listing. The stream never ends.
+    ///
+    /// # Panics
+    ///
+    /// Polling the stream panics if `period` is zero.
+    fn watch_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        period: Duration,
+    ) -> WatchStream;
+
     /// Stream over all objects; fetching objects as needed
     ///
     /// Objects are lexicographically sorted by their key.
@@ -178,131 +698,1429 @@ pub trait S3Ext {
         bucket: impl Into<String>,
         prefix: impl Into<String>,
     ) -> GetObjectStream;
-}
 
-#[async_trait]
-impl S3Ext for S3Client {
-    async fn download_to_file<F>(
+    /// Stream over the lines of an object; fetching the object as needed
+    ///
+    /// Use `LineStream::gzip` to read gzip
+    /// compressed objects (`gzip` feature).
+    fn stream_lines(&self, bucket: impl Into<String>, key: impl Into<String>) -> LineStream;
+
+    /// Get the tags of object `key` in `bucket`
+    async fn get_tags(
         &self,
-        source: GetObjectRequest,
-        target: F,
-    ) -> Result<GetObjectOutput, S3ExtError>
-    where
-        F: AsRef<Path> + Send + Sync,
-    {
-        debug!("downloading to file {:?}", target.as_ref());
-        let mut resp = self.get_object(source).await?;
-        let body = resp.body.take().expect("no body");
-        let mut target = OpenOptions::new()
-            .write(true)
-            .create_new(true)
-            .open(target)
-            .await?;
-        copy(body, &mut target).await?;
-        Ok(resp)
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<Tags> {
+        self.send_request(
+            "get_tags",
+            bucket.into(),
+            key.into(),
+            |client, bucket, key| async move { tagging::get_tags(&client, bucket, key).await },
+        )
+        .await
     }
 
-    #[inline]
-    async fn upload_from_file<F>(
+    /// Replace the tags of object `key` in `bucket` by `tags`
+    ///
+    /// Fails with `S3ExtError::InvalidTag` if `tags` exceed the limits of S3,
+    /// see [`TagsBuilder::build`](tagging::TagsBuilder::build).
+    async fn put_tags(
         &self,
-        source: F,
-        target: PutObjectRequest,
-    ) -> S3ExtResult<PutObjectOutput>
-    where
-        F: AsRef<Path> + Send + Sync,
-    {
-        debug!("uploading file {:?}", source.as_ref());
-        let mut source = File::open(source).await?;
-        upload::upload(self, &mut source, target).await
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        tags: &Tags,
+    ) -> S3ExtResult<()> {
+        self.send_request(
+            "put_tags",
+            bucket.into(),
+            key.into(),
+            |client, bucket, key| async move {
+                tagging::put_tags(&client, bucket, key, tags).await
+            },
+        )
+        .await
     }
 
-    #[inline]
-    async fn upload_from_file_multipart<F>(
+    /// Remove all tags from object `key` in `bucket`
+    async fn delete_tags(
         &self,
-        source: F,
-        target: PutObjectRequest,
-        part_size: usize,
-    ) -> S3ExtResult<CompleteMultipartUploadOutput>
-    where
-        F: AsRef<Path> + Send + Sync,
-    {
-        debug!("uploading file {:?}", source.as_ref());
-        let mut source = File::open(source).await?;
-        upload::upload_multipart(self, &mut source, target, part_size).await
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.send_request(
+            "delete_tags",
+            bucket.into(),
+            key.into(),
+            |client, bucket, key| async move { tagging::delete_tags(&client, bucket, key).await },
+        )
+        .await
     }
 
-    async fn download<W>(
+    /// Tag all objects with given `prefix` in `bucket`
+    ///
+    /// Up to [`tagging::TAG_CONCURRENCY`] objects are tagged concurrently.
+    /// Objects that can't be tagged are listed in the returned report, an
+    /// error is only returned if `tags` are invalid or listing the objects
+    /// fails.
+    async fn tag_prefix(
         &self,
-        source: GetObjectRequest,
-        mut target: &mut W,
-    ) -> S3ExtResult<GetObjectOutput>
-    where
-        W: io::AsyncWrite + Unpin + Send,
-    {
-        let mut resp = self.get_object(source).await?;
-        let body = resp.body.take().expect("no body");
-        copy(body, &mut target).await?;
-        Ok(resp)
+        bucket: impl Into<String> + Send,
+        prefix: impl Into<String> + Send,
+        tags: &Tags,
+        mode: TagMode,
+    ) -> S3ExtResult<TagReport> {
+        tagging::tag_prefix(self, bucket.into(), prefix.into(), tags, mode).await
     }
 
-    #[inline]
-    async fn upload<R>(
+    /// Get the ACL of object `key` in `bucket`
+    async fn get_acl(
         &self,
-        source: &mut R,
-        target: PutObjectRequest,
-    ) -> S3ExtResult<PutObjectOutput>
-    where
-        R: io::AsyncRead + Unpin + Send,
-    {
-        upload::upload(self, source, target).await
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<Acl> {
+        self.send_request(
+            "get_acl",
+            bucket.into(),
+            key.into(),
+            |client, bucket, key| async move { acl::get_acl(&client, bucket, key).await },
+        )
+        .await
     }
 
-    #[inline]
-    async fn upload_multipart<R>(
+    /// Replace the ACL of object `key` in `bucket` by `acl`
+    ///
+    /// `acl.owner` must be set, S3 rejects ACLs without owner.
+    async fn put_acl(
         &self,
-        mut source: &mut R,
-        target: PutObjectRequest,
-        part_size: usize,
-    ) -> S3ExtResult<CompleteMultipartUploadOutput>
-    where
-        R: io::AsyncRead + Unpin + Send,
-    {
-        upload::upload_multipart(self, &mut source, target, part_size).await
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        acl: &Acl,
+    ) -> S3ExtResult<()> {
+        self.send_request(
+            "put_acl",
+            bucket.into(),
+            key.into(),
+            |client, bucket, key| async move { acl::put_acl(&client, bucket, key, acl).await },
+        )
+        .await
     }
 
-    #[inline]
-    fn stream_objects(&self, bucket: impl Into<String>) -> ObjectStream {
-        ObjectStream::new(self, bucket, None as Option<&str>)
+    /// Replace the ACL of object `key` in `bucket` by the predefined ACL `acl`
+    async fn put_canned_acl(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        acl: CannedAcl,
+    ) -> S3ExtResult<()> {
+        self.send_request(
+            "put_canned_acl",
+            bucket.into(),
+            key.into(),
+            |client, bucket, key| async move {
+                acl::put_canned_acl(&client, bucket, key, acl).await
+            },
+        )
+        .await
     }
 
-    #[inline]
-    fn stream_objects_with_prefix(
+    /// Restore a temporary copy of archived object `key` for `days` days,
+    /// retrieving it using `tier`
+    ///
+    /// Returns once the restore has been started, requesting a restore already
+    /// in progress succeeds too.
+    async fn restore_object(
         &self,
-        bucket: impl Into<String>,
-        prefix: impl Into<String>,
-    ) -> ObjectStream {
-        ObjectStream::new(self, bucket, Some(prefix))
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        tier: RestoreTier,
+        days: i64,
+    ) -> S3ExtResult<()> {
+        self.send_request(
+            "restore_object",
+            bucket.into(),
+            key.into(),
+            |client, bucket, key| async move {
+                restore::restore_object(&client, bucket, key, tier, days).await
+            },
+        )
+        .await
     }
 
-    #[inline]
-    fn stream_get_objects(&self, bucket: impl Into<String>) -> GetObjectStream {
-        GetObjectStream::new(self, bucket, None as Option<&str>)
+    /// State of the restore of `key`, `None` if no restore was requested or
+    /// the temporary copy expired
+    async fn restore_status(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<Option<RestoreStatus>> {
+        self.send_request(
+            "restore_status",
+            bucket.into(),
+            key.into(),
+            |client, bucket, key| async move {
+                restore::restore_status(&client, bucket, key).await
+            },
+        )
+        .await
     }
 
-    #[inline]
-    fn stream_get_objects_with_prefix(
+    /// Poll the restore status of `key` every `poll_interval` until the
+    /// temporary copy is available, returning the date it expires
+    ///
+    /// Fails if no restore was requested. Restores take minutes to hours,
+    /// wrap the future in [`tokio::time::timeout`] to give up early.
+    async fn wait_until_restored(
         &self,
-        bucket: impl Into<String>,
-        prefix: impl Into<String>,
-    ) -> GetObjectStream {
-        GetObjectStream::new(self, bucket, Some(prefix))
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        poll_interval: Duration,
+    ) -> S3ExtResult<Option<String>> {
+        restore::wait_until_restored(self, bucket.into(), key.into(), poll_interval).await
     }
-}
 
-async fn copy<W>(src: StreamingBody, dest: &mut W) -> S3ExtResult<()>
-where
-    W: io::AsyncWrite + Unpin + Send,
-{
-    io::copy(&mut src.into_async_read(), dest).await?;
-    Ok(())
+    /// Change the storage class of object `key` in `bucket` to `storage_class`,
+    /// e.g. `STANDARD_IA`, by copying the object onto itself
+    ///
+    /// See the caveats in [`storage_class`].
+    async fn set_storage_class(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        storage_class: &str,
+    ) -> S3ExtResult<()> {
+        self.send_request(
+            "set_storage_class",
+            bucket.into(),
+            key.into(),
+            |client, bucket, key| async move {
+                storage_class::set_storage_class(&client, bucket, key, storage_class).await
+            },
+        )
+        .await
+    }
+
+    /// List the objects with given `prefix` in `bucket` whose storage class
+    /// [`change_storage_class`](Self::change_storage_class) would change,
+    /// without changing anything
+    async fn plan_storage_class(
+        &self,
+        bucket: impl Into<String> + Send,
+        prefix: impl Into<String> + Send,
+        storage_class: &str,
+    ) -> S3ExtResult<TransitionPlan> {
+        storage_class::plan_storage_class(self, bucket.into(), prefix.into(), storage_class).await
+    }
+
+    /// Change the storage class of all objects with given `prefix` in
+    /// `bucket` to `storage_class`, skipping objects already in that class
+    ///
+    /// Up to [`storage_class::TRANSITION_CONCURRENCY`] objects are copied
+    /// concurrently. Objects that can't be copied are listed in the returned
+    /// report, an error is only returned if listing the objects fails.
+    async fn change_storage_class(
+        &self,
+        bucket: impl Into<String> + Send,
+        prefix: impl Into<String> + Send,
+        storage_class: &str,
+    ) -> S3ExtResult<TransitionReport> {
+        storage_class::change_storage_class(self, bucket.into(), prefix.into(), storage_class).await
+    }
+
+    /// Get size, entity tag, part layout and storage class of object `key`
+    ///
+    /// Objects written by multipart uploads take a second request for the
+    /// size of the first part, `GetObjectAttributes` if the client is backed
+    /// by the AWS SDK and the object lists its parts, a `HEAD` request of the
+    /// first part otherwise.
+    async fn object_attributes(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectAttributes> {
+        self.send_request(
+            "object_attributes",
+            bucket.into(),
+            key.into(),
+            |client, bucket, key| async move {
+                attributes::object_attributes(&client, bucket, key, true).await
+            },
+        )
+        .await
+    }
+
+    /// Get the attributes of objects `keys` in `bucket`, up to `concurrency`
+    /// at a time
+    ///
+    /// Returns the keys in the given order along with their attributes,
+    /// `None` for objects that don't exist. Fails on the first other error.
+    async fn head_many<I>(
+        &self,
+        bucket: impl Into<String> + Send,
+        keys: I,
+        concurrency: usize,
+    ) -> S3ExtResult<Vec<(String, Option<ObjectAttributes>)>>
+    where
+        I: IntoIterator + Send,
+        I::Item: Into<String>,
+    {
+        let keys = keys.into_iter().map(Into::into).collect();
+        attributes::head_many(self, bucket.into(), keys, concurrency).await
+    }
+
+    /// Find the `n` largest objects with `prefix` in `bucket`, largest first
+    ///
+    /// The listing is streamed, only `n` objects are kept in memory. See
+    /// [`find`].
+    async fn find_largest(
+        &self,
+        bucket: impl Into<String> + Send,
+        prefix: impl Into<String> + Send,
+        n: usize,
+    ) -> S3ExtResult<Vec<Object>> {
+        find::find_largest(self, bucket.into(), prefix.into(), n).await
+    }
+
+    /// Find the `n` least recently modified objects with `prefix` in
+    /// `bucket`, oldest first
+    ///
+    /// See [`find_largest`](Self::find_largest).
+    async fn find_oldest(
+        &self,
+        bucket: impl Into<String> + Send,
+        prefix: impl Into<String> + Send,
+        n: usize,
+    ) -> S3ExtResult<Vec<Object>> {
+        find::find_oldest(self, bucket.into(), prefix.into(), n).await
+    }
+
+    /// Find the `n` most recently modified objects with `prefix` in
+    /// `bucket`, newest first
+    ///
+    /// See [`find_largest`](Self::find_largest).
+    async fn find_newest(
+        &self,
+        bucket: impl Into<String> + Send,
+        prefix: impl Into<String> + Send,
+        n: usize,
+    ) -> S3ExtResult<Vec<Object>> {
+        find::find_newest(self, bucket.into(), prefix.into(), n).await
+    }
+
+    /// Store `content` in `bucket` below `prefix`, named by its SHA-256
+    /// digest
+    ///
+    /// Nothing is uploaded if an object of that name exists already. See
+    /// [`cas`].
+    async fn put_content(
+        &self,
+        bucket: impl Into<String> + Send,
+        prefix: impl Into<String> + Send,
+        content: &[u8],
+    ) -> S3ExtResult<ContentHashKey> {
+        cas::put_content(self, bucket.into(), prefix.into(), content).await
+    }
+
+    /// Get the content stored as `hash` in `bucket` below `prefix`
+    ///
+    /// Fails with `S3ExtError::ChecksumMismatch` if the content doesn't match
+    /// `hash`.
+    async fn get_content(
+        &self,
+        bucket: impl Into<String> + Send,
+        prefix: impl Into<String> + Send,
+        hash: &ContentHashKey,
+    ) -> S3ExtResult<Vec<u8>> {
+        cas::get_content(self, bucket.into(), prefix.into(), hash).await
+    }
+
+    /// Acquire an advisory lease on marker object `key` in `bucket`,
+    /// expiring `ttl` from now
+    ///
+    /// Fails with `S3ExtError::LeaseHeld` if someone else holds an unexpired
+    /// lease. See the caveats in [`lease`].
+    async fn acquire_lease(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        ttl: Duration,
+    ) -> S3ExtResult<Lease> {
+        lease::acquire(self, bucket.into(), key.into(), ttl).await
+    }
+
+    /// Upload the file at `source` unless `target` exists already with the
+    /// same content
+    ///
+    /// Returns whether the file was uploaded. See [`dedup`].
+    async fn upload_if_changed<F>(&self, source: F, target: PutObjectRequest) -> S3ExtResult<bool>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        dedup::upload_if_changed(self, source.as_ref(), target).await
+    }
+
+    /// Write the objects with given `prefix` to `target` as a tar or zip
+    /// archive
+    ///
+    /// Objects are streamed into the archive without being staged on disk.
+    /// Returns the number of archived objects. See [`archive`].
+    async fn archive_prefix<W>(
+        &self,
+        bucket: impl Into<String> + Send,
+        prefix: impl Into<String> + Send,
+        target: &mut W,
+        format: ArchiveFormat,
+    ) -> S3ExtResult<usize>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        archive::archive_prefix(self, bucket.into(), prefix.into(), target, format).await
+    }
+
+    /// Upload the regular files of the tar archive read from `source` as
+    /// objects named by `prefix` followed by their path
+    ///
+    /// Returns the number of uploaded objects. See [`archive`].
+    async fn upload_tar<R>(
+        &self,
+        source: &mut R,
+        bucket: impl Into<String> + Send,
+        prefix: impl Into<String> + Send,
+    ) -> S3ExtResult<usize>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        archive::upload_tar(self, source, bucket.into(), prefix.into()).await
+    }
+
+    /// Like [`upload_tar`](Self::upload_tar), reading the archive from the
+    /// file at `source`
+    async fn upload_tar_from_file<F>(
+        &self,
+        source: F,
+        bucket: impl Into<String> + Send,
+        prefix: impl Into<String> + Send,
+    ) -> S3ExtResult<usize>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        let mut source = io::BufReader::new(File::open(source.as_ref()).await?);
+        archive::upload_tar(self, &mut source, bucket.into(), prefix.into()).await
+    }
+
+    /// Delete object `key` in `bucket`
+    ///
+    /// Deleting a missing object succeeds. In versioned buckets a delete marker
+    /// is created.
+    async fn delete(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()>;
+
+    /// Copy object `source_key` in `source_bucket` to `key` in `bucket`
+    ///
+    /// The copy is done by S3, including metadata and tags. Objects larger than
+    /// 5 GiB fail, use [`compose_object`](Self::compose_object) for those.
+    async fn copy(
+        &self,
+        source_bucket: impl Into<String> + Send,
+        source_key: impl Into<String> + Send,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()>;
+
+    /// Run the operations listed in `manifest`, up to `concurrency` at a time
+    ///
+    /// Failed operations are retried according to `policy`. Operations
+    /// failing for good don't stop the batch, the report lists the outcome
+    /// of every operation. See [`batch`].
+    async fn run_batch(
+        &self,
+        manifest: &Manifest,
+        concurrency: usize,
+        policy: &RetryPolicy,
+    ) -> BatchReport {
+        batch::run_batch(self, manifest, concurrency, policy).await
+    }
+
+    /// Write object `key` in `bucket` consisting of `parts`, copying parts of
+    /// existing objects within S3
+    ///
+    /// All parts but the last must be at least 5 MiB. See [`compose`].
+    async fn compose_object(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        parts: Vec<ComposePart>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>;
+
+    /// Compress `source` using `compression` while uploading it to S3 using
+    /// multi-part upload
+    ///
+    /// The encoding is appended to `content_encoding`. If `original_size` is
+    /// given, it is recorded in the user metadata and the upload is aborted
+    /// unless `source` has this size. See [`compress`].
+    async fn upload_compressed<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        compression: Compression,
+        original_size: Option<u64>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        compress::upload_compressed(self, source, target, compression, original_size).await
+    }
+
+    /// Upload file `source` as object `key` in `bucket`, copying blocks
+    /// unchanged since the previous delta upload within S3
+    ///
+    /// Blocks are found using a signature stored as `<key>.s3sig`, the whole
+    /// file is uploaded if it is missing or outdated. See [`delta`].
+    async fn upload_delta<F>(
+        &self,
+        source: F,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<DeltaReport>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        delta::upload_delta(self, source.as_ref(), bucket.into(), key.into()).await
+    }
+
+    /// Like [`upload_from_file`](Self::upload_from_file), storing the
+    /// modification time and permissions of `source` in the user metadata
+    ///
+    /// See [`file_attributes`].
+    async fn upload_from_file_with_attributes<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        file_attributes::upload_from_file(self, source.as_ref(), target).await
+    }
+
+    /// Like [`download_to_file`](Self::download_to_file), restoring the
+    /// modification time and permissions stored in the user metadata
+    ///
+    /// See [`file_attributes`].
+    async fn download_to_file_with_attributes<F>(
+        &self,
+        source: GetObjectRequest,
+        target: F,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        file_attributes::download_to_file(self, source, target.as_ref()).await
+    }
+
+    /// Get the metadata of object `key` in `bucket` replaced by
+    /// [`replace_metadata`](Self::replace_metadata)
+    async fn object_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectMetadata>;
+
+    /// Replace the metadata of object `key` in `bucket` by `metadata`, copying
+    /// the object onto itself
+    ///
+    /// Fails if the object no longer has entity tag `metadata.e_tag` or if the
+    /// user metadata is invalid. See the caveats in [`metadata`].
+    async fn replace_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        metadata: ObjectMetadata,
+    ) -> S3ExtResult<()>;
+
+    /// Rewrite the metadata of all objects with given `prefix` in `bucket`
+    /// using `mutate`
+    ///
+    /// `mutate` is called with the key and the metadata of every object.
+    /// Objects whose metadata it changes are copied onto themselves with the
+    /// new metadata, up to [`metadata::REWRITE_CONCURRENCY`] concurrently.
+    /// With `dry_run`, the objects that would be changed are reported but
+    /// nothing is copied. Objects that can't be rewritten are listed in the
+    /// returned report, an error is only returned if listing the objects
+    /// fails. See the caveats in [`metadata`].
+    async fn rewrite_metadata<F>(
+        &self,
+        bucket: impl Into<String> + Send,
+        prefix: impl Into<String> + Send,
+        mutate: F,
+        dry_run: bool,
+    ) -> S3ExtResult<RewriteReport>
+    where
+        F: Fn(&str, &mut ObjectMetadata) + Send + Sync,
+    {
+        metadata::rewrite_metadata(self, bucket.into(), prefix.into(), mutate, dry_run).await
+    }
+
+    /// Like [`download_to_file`](Self::download_to_file), returning
+    /// [`TransferStats`] alongside the output
+    ///
+    /// `bytes` is taken from the `Content-Length` of the response.
+    async fn download_to_file_with_stats<F>(
+        &self,
+        source: GetObjectRequest,
+        target: F,
+    ) -> S3ExtResult<(GetObjectOutput, TransferStats)>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        let (resp, stats) = stats::collect(self.download_to_file(source, target)).await;
+        let resp = resp?;
+        let bytes = resp.content_length.unwrap_or_default() as u64;
+        Ok((
+            resp,
+            TransferStats {
+                bytes,
+                parts: 1,
+                ..stats
+            },
+        ))
+    }
+
+    /// Like [`upload_from_file`](Self::upload_from_file), returning
+    /// [`TransferStats`] alongside the output
+    async fn upload_from_file_with_stats<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<(PutObjectOutput, TransferStats)>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        let bytes = tokio::fs::metadata(source.as_ref()).await?.len();
+        let (resp, stats) = stats::collect(self.upload_from_file(source, target)).await;
+        Ok((
+            resp?,
+            TransferStats {
+                bytes,
+                parts: 1,
+                ..stats
+            },
+        ))
+    }
+
+    /// Like [`upload_from_file_multipart`](Self::upload_from_file_multipart),
+    /// returning [`TransferStats`] alongside the output
+    async fn upload_from_file_multipart_with_stats<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+        part_size: usize,
+    ) -> S3ExtResult<(CompleteMultipartUploadOutput, TransferStats)>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        let bytes = tokio::fs::metadata(source.as_ref()).await?.len();
+        let (resp, stats) =
+            stats::collect(self.upload_from_file_multipart(source, target, part_size)).await;
+        Ok((resp?, TransferStats { bytes, ..stats }))
+    }
+
+    /// Like [`download`](Self::download), returning [`TransferStats`]
+    /// alongside the output
+    async fn download_with_stats<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<(GetObjectOutput, TransferStats)>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        let mut target = Counting::new(target);
+        let (resp, stats) = stats::collect(self.download(source, &mut target)).await;
+        let bytes = target.bytes;
+        Ok((
+            resp?,
+            TransferStats {
+                bytes,
+                parts: 1,
+                ..stats
+            },
+        ))
+    }
+
+    /// Like [`upload`](Self::upload), returning [`TransferStats`] alongside
+    /// the output
+    async fn upload_with_stats<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<(PutObjectOutput, TransferStats)>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        let mut source = Counting::new(source);
+        let (resp, stats) = stats::collect(self.upload(&mut source, target)).await;
+        let bytes = source.bytes;
+        Ok((
+            resp?,
+            TransferStats {
+                bytes,
+                parts: 1,
+                ..stats
+            },
+        ))
+    }
+
+    /// Like [`upload_multipart`](Self::upload_multipart), returning
+    /// [`TransferStats`] alongside the output
+    async fn upload_multipart_with_stats<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: usize,
+    ) -> S3ExtResult<(CompleteMultipartUploadOutput, TransferStats)>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        let mut source = Counting::new(source);
+        let (resp, stats) =
+            stats::collect(self.upload_multipart(&mut source, target, part_size)).await;
+        let bytes = source.bytes;
+        Ok((resp?, TransferStats { bytes, ..stats }))
+    }
+
+    /// Download the object at `bucket`/`key` and deserialize it using codec
+    /// `C`
+    ///
+    /// Objects larger than [`codec::MAX_SIZE`] are rejected with
+    /// `S3ExtError::TooLarge` without being read completely.
+    #[cfg(feature = "serde")]
+    async fn get_as<T, C>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<T>
+    where
+        T: serde::de::DeserializeOwned,
+        C: codec::Codec,
+    {
+        codec::get::<_, _, C>(self, bucket.into(), key.into()).await
+    }
+
+    /// Serialize `value` using codec `C` and upload it to `bucket`/`key`
+    ///
+    /// The object's content type is set to `C::CONTENT_TYPE`. Objects larger
+    /// than [`codec::MAX_SIZE`] are rejected with `S3ExtError::TooLarge`.
+    #[cfg(feature = "serde")]
+    async fn put_as<T, C>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        value: &T,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        T: serde::Serialize + Sync + ?Sized,
+        C: codec::Codec,
+    {
+        codec::put::<_, _, C>(self, bucket.into(), key.into(), value).await
+    }
+
+    /// Download and deserialize the JSON document at `bucket`/`key`
+    ///
+    /// Shorthand for [`get_as`](Self::get_as) with [`codec::Json`].
+    #[cfg(feature = "serde_json")]
+    async fn get_json<T>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        codec::get::<_, _, codec::Json>(self, bucket.into(), key.into()).await
+    }
+
+    /// Serialize `value` as JSON and upload it to `bucket`/`key`
+    ///
+    /// Shorthand for [`put_as`](Self::put_as) with [`codec::Json`].
+    #[cfg(feature = "serde_json")]
+    async fn put_json<T>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        value: &T,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        T: serde::Serialize + Sync + ?Sized,
+    {
+        codec::put::<_, _, codec::Json>(self, bucket.into(), key.into(), value).await
+    }
+
+    /// Fail operations with `S3ExtError::Timeout` if they don't complete
+    /// within `timeout`
+    ///
+    /// See [`TimeoutS3Client`] for the operations covered.
+    fn with_timeout(&self, timeout: Duration) -> TimeoutS3Client<Self>
+    where
+        Self: Clone + Sized,
+    {
+        TimeoutS3Client::new(self.clone(), timeout)
+    }
+}
+
+#[async_trait]
+impl S3Ext for S3Client {
+    async fn send_request<T, F, Fut>(
+        &self,
+        _operation: &'static str,
+        bucket: String,
+        key: String,
+        request: F,
+    ) -> S3ExtResult<T>
+    where
+        T: Send,
+        F: Fn(Backend, String, String) -> Fut + Send + Sync,
+        Fut: Future<Output = S3ExtResult<T>> + Send,
+    {
+        request(Backend::from(self), bucket, key).await
+    }
+
+    async fn download_to_file<F>(
+        &self,
+        source: GetObjectRequest,
+        target: F,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        Backend::from(self).download_to_file(source, target).await
+    }
+
+    async fn upload_from_file<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        Backend::from(self).upload_from_file(source, target).await
+    }
+
+    async fn upload_from_file_multipart_with<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        Backend::from(self)
+            .upload_from_file_multipart_with(source, target, part_size)
+            .await
+    }
+
+    async fn download<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        Backend::from(self).download(source, target).await
+    }
+
+    async fn download_decompressed<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        Backend::from(self)
+            .download_decompressed(source, target)
+            .await
+    }
+
+    async fn get_framed<D>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        decoder: D,
+    ) -> S3ExtResult<FramedObject<D>>
+    where
+        D: Decoder + Send,
+    {
+        Backend::from(self).get_framed(bucket, key, decoder).await
+    }
+
+    async fn get_body_with(&self, source: GetObjectRequest) -> S3ExtResult<ObjectBody> {
+        Backend::from(self).get_body_with(source).await
+    }
+
+    async fn upload<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        Backend::from(self).upload(source, target).await
+    }
+
+    async fn upload_multipart_with<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        Backend::from(self)
+            .upload_multipart_with(source, target, part_size)
+            .await
+    }
+
+    async fn upload_multipart_resumable<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        Backend::from(self)
+            .upload_multipart_resumable(source, target, part_size)
+            .await
+    }
+
+    #[inline]
+    fn stream_objects(&self, bucket: impl Into<String>) -> ObjectStream {
+        Backend::from(self).stream_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> ObjectStream {
+        Backend::from(self).stream_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_objects_sorted_by_mtime(&self, bucket: impl Into<String>) -> SortedObjectStream {
+        Backend::from(self).stream_objects_sorted_by_mtime(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix_sorted_by_mtime(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> SortedObjectStream {
+        Backend::from(self).stream_objects_with_prefix_sorted_by_mtime(bucket, prefix)
+    }
+
+    #[inline]
+    fn diff(
+        &self,
+        source_bucket: impl Into<String>,
+        source_prefix: impl Into<String>,
+        dest_bucket: impl Into<String>,
+        dest_prefix: impl Into<String>,
+    ) -> DiffStream {
+        Backend::from(self).diff(source_bucket, source_prefix, dest_bucket, dest_prefix)
+    }
+
+    #[inline]
+    fn watch_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        period: Duration,
+    ) -> WatchStream {
+        Backend::from(self).watch_prefix(bucket, prefix, period)
+    }
+
+    #[inline]
+    fn stream_get_objects(&self, bucket: impl Into<String>) -> GetObjectStream {
+        Backend::from(self).stream_get_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_get_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> GetObjectStream {
+        Backend::from(self).stream_get_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_lines(&self, bucket: impl Into<String>, key: impl Into<String>) -> LineStream {
+        Backend::from(self).stream_lines(bucket, key)
+    }
+
+    async fn delete(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        Backend::from(self).delete(bucket, key).await
+    }
+
+    async fn compose_object(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        parts: Vec<ComposePart>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput> {
+        Backend::from(self).compose_object(bucket, key, parts).await
+    }
+
+    async fn object_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectMetadata> {
+        Backend::from(self).object_metadata(bucket, key).await
+    }
+
+    async fn replace_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        metadata: ObjectMetadata,
+    ) -> S3ExtResult<()> {
+        Backend::from(self)
+            .replace_metadata(bucket, key, metadata)
+            .await
+    }
+
+    async fn copy(
+        &self,
+        source_bucket: impl Into<String> + Send,
+        source_key: impl Into<String> + Send,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        Backend::from(self)
+            .copy(source_bucket, source_key, bucket, key)
+            .await
+    }
+}
+
+#[async_trait]
+impl S3Ext for Backend {
+    async fn send_request<T, F, Fut>(
+        &self,
+        _operation: &'static str,
+        bucket: String,
+        key: String,
+        request: F,
+    ) -> S3ExtResult<T>
+    where
+        T: Send,
+        F: Fn(Backend, String, String) -> Fut + Send + Sync,
+        Fut: Future<Output = S3ExtResult<T>> + Send,
+    {
+        request(self.clone(), bucket, key).await
+    }
+
+    async fn download_to_file<F>(
+        &self,
+        source: GetObjectRequest,
+        target: F,
+    ) -> Result<GetObjectOutput, S3ExtError>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        debug!("downloading to file {:?}", target.as_ref());
+        let (bucket, key) = (source.bucket.clone(), source.key.clone());
+        let span = span!(
+            INFO,
+            "download_to_file",
+            bucket = %bucket,
+            key = %key,
+            bytes = tracing::field::Empty,
+        );
+        let result: S3ExtResult<_> = async {
+            // the target is only created once the object turned out to exist
+            let output = self.get_body_with(source.clone()).await?.into_output();
+            let mut target = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(target)
+                .await?;
+            let (resp, bytes) =
+                resume::download_body(self, source, output, &mut target, resume::DEFAULT_ATTEMPTS)
+                    .await?;
+            span.record("bytes", bytes);
+            Ok(resp)
+        }
+        .instrument(span.clone())
+        .await;
+        result.map_err(|e| e.with_object(bucket, key))
+    }
+
+    #[inline]
+    async fn upload_from_file<F>(
+        &self,
+        source: F,
+        mut target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        debug!("uploading file {:?}", source.as_ref());
+        let (bucket, key) = (target.bucket.clone(), target.key.clone());
+        let span = span!(
+            INFO,
+            "upload_from_file",
+            bucket = %bucket,
+            key = %key,
+            bytes = tracing::field::Empty,
+        );
+        let result: S3ExtResult<_> = async {
+            upload::guess_content_type(source.as_ref(), &mut target);
+            let mut source = File::open(source).await?;
+            let size = source.metadata().await?.len();
+            upload::upload(self, &mut source, target, Some(size)).await
+        }
+        .instrument(span)
+        .await;
+        result.map_err(|e| e.with_object(bucket, key))
+    }
+
+    #[inline]
+    async fn upload_from_file_multipart_with<F>(
+        &self,
+        source: F,
+        mut target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        debug!("uploading file {:?}", source.as_ref());
+        let (bucket, key) = (target.bucket.clone(), target.key.clone());
+        let span = span!(
+            INFO,
+            "upload_from_file_multipart",
+            bucket = %bucket,
+            key = %key,
+            part_size = ?part_size,
+            parts = tracing::field::Empty,
+            bytes = tracing::field::Empty,
+        );
+        let result: S3ExtResult<_> = async {
+            upload::guess_content_type(source.as_ref(), &mut target);
+            let mut source = File::open(source).await?;
+            let size = source.metadata().await?.len();
+            upload::upload_multipart(self, &mut source, target, part_size, Some(size)).await
+        }
+        .instrument(span)
+        .await;
+        result.map_err(|e| e.with_object(bucket, key))
+    }
+
+    async fn download<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        let (bucket, key) = (source.bucket.clone(), source.key.clone());
+        let span = span!(
+            INFO,
+            "download",
+            bucket = %bucket,
+            key = %key,
+            bytes = tracing::field::Empty,
+        );
+        let result: S3ExtResult<_> = async {
+            let (resp, bytes) =
+                resume::download(self, source, target, resume::DEFAULT_ATTEMPTS).await?;
+            span.record("bytes", bytes);
+            Ok(resp)
+        }
+        .instrument(span.clone())
+        .await;
+        result.map_err(|e| e.with_object(bucket, key))
+    }
+
+    async fn download_decompressed<W>(
+        &self,
+        source: GetObjectRequest,
+        mut target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        let (bucket, key) = (source.bucket.clone(), source.key.clone());
+        let span = span!(
+            INFO,
+            "download_decompressed",
+            bucket = %bucket,
+            key = %key,
+            bytes = tracing::field::Empty,
+        );
+        let result: S3ExtResult<_> = async {
+            let mut resp = self.get_object(source).await?;
+            let mut body = decompress::body(resp.body.take(), resp.content_encoding.as_deref())?;
+            let bytes = io::copy(&mut body, &mut target).await?;
+            span.record("bytes", bytes);
+            if resp
+                .content_encoding
+                .as_deref()
+                .map_or(false, decompress::is_compressed)
+            {
+                resp.content_encoding = None;
+                resp.content_length = None;
+            }
+            Ok(resp)
+        }
+        .instrument(span.clone())
+        .await;
+        result.map_err(|e| e.with_object(bucket, key))
+    }
+
+    async fn get_framed<D>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        decoder: D,
+    ) -> S3ExtResult<FramedObject<D>>
+    where
+        D: Decoder + Send,
+    {
+        framed::get_framed(self, bucket.into(), key.into(), decoder).await
+    }
+
+    async fn get_body_with(&self, source: GetObjectRequest) -> S3ExtResult<ObjectBody> {
+        body::get_body(self, source).await
+    }
+
+    #[inline]
+    async fn upload<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        let (bucket, key) = (target.bucket.clone(), target.key.clone());
+        let span = span!(
+            INFO,
+            "upload",
+            bucket = %bucket,
+            key = %key,
+            bytes = tracing::field::Empty,
+        );
+        upload::upload(self, source, target, None)
+            .instrument(span)
+            .await
+            .map_err(|e| e.with_object(bucket, key))
+    }
+
+    #[inline]
+    async fn upload_multipart_with<R>(
+        &self,
+        mut source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        let (bucket, key) = (target.bucket.clone(), target.key.clone());
+        let span = span!(
+            INFO,
+            "upload_multipart",
+            bucket = %bucket,
+            key = %key,
+            part_size = ?part_size,
+            parts = tracing::field::Empty,
+            bytes = tracing::field::Empty,
+        );
+        upload::upload_multipart(self, &mut source, target, part_size, None)
+            .instrument(span)
+            .await
+            .map_err(|e| e.with_object(bucket, key))
+    }
+
+    async fn upload_multipart_resumable<R>(
+        &self,
+        mut source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        let (bucket, key) = (target.bucket.clone(), target.key.clone());
+        let span = span!(
+            INFO,
+            "upload_multipart_resumable",
+            bucket = %bucket,
+            key = %key,
+            part_size = ?part_size,
+            parts = tracing::field::Empty,
+            bytes = tracing::field::Empty,
+        );
+        upload::upload_multipart_resumable(self, &mut source, target, part_size, None)
+            .instrument(span)
+            .await
+            .map_err(|e| e.with_object(bucket, key))
+    }
+
+    #[inline]
+    fn stream_objects(&self, bucket: impl Into<String>) -> ObjectStream {
+        ObjectStream::new(self, bucket, None as Option<&str>)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> ObjectStream {
+        ObjectStream::new(self, bucket, Some(prefix))
+    }
+
+    #[inline]
+    fn stream_objects_sorted_by_mtime(&self, bucket: impl Into<String>) -> SortedObjectStream {
+        self.stream_objects(bucket).sorted_by_mtime()
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix_sorted_by_mtime(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> SortedObjectStream {
+        self.stream_objects_with_prefix(bucket, prefix)
+            .sorted_by_mtime()
+    }
+
+    fn diff(
+        &self,
+        source_bucket: impl Into<String>,
+        source_prefix: impl Into<String>,
+        dest_bucket: impl Into<String>,
+        dest_prefix: impl Into<String>,
+    ) -> DiffStream {
+        let source_prefix = source_prefix.into();
+        let dest_prefix = dest_prefix.into();
+        DiffStream::new(
+            self.stream_objects_with_prefix(source_bucket, &source_prefix),
+            source_prefix,
+            self.stream_objects_with_prefix(dest_bucket, &dest_prefix),
+            dest_prefix,
+        )
+    }
+
+    #[inline]
+    fn watch_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        period: Duration,
+    ) -> WatchStream {
+        WatchStream::new(self, bucket, prefix, period)
+    }
+
+    #[inline]
+    fn stream_get_objects(&self, bucket: impl Into<String>) -> GetObjectStream {
+        GetObjectStream::new(self, bucket, None as Option<&str>)
+    }
+
+    #[inline]
+    fn stream_get_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> GetObjectStream {
+        GetObjectStream::new(self, bucket, Some(prefix))
+    }
+
+    #[inline]
+    fn stream_lines(&self, bucket: impl Into<String>, key: impl Into<String>) -> LineStream {
+        LineStream::new(self, bucket, key)
+    }
+
+    async fn delete(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        let (bucket, key) = (bucket.into(), key.into());
+        let request = DeleteObjectRequest {
+            bucket: bucket.clone(),
+            key: key.clone(),
+            ..Default::default()
+        };
+        self.delete_object(request)
+            .await
+            .map_err(|e| S3ExtError::from(e).with_object(bucket, key))?;
+        Ok(())
+    }
+
+    async fn compose_object(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        parts: Vec<ComposePart>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput> {
+        compose::compose_object(self, bucket.into(), key.into(), parts).await
+    }
+
+    async fn object_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectMetadata> {
+        metadata::object_metadata(self, bucket.into(), key.into()).await
+    }
+
+    async fn replace_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        metadata: ObjectMetadata,
+    ) -> S3ExtResult<()> {
+        metadata::replace_metadata(self, bucket.into(), key.into(), metadata).await
+    }
+
+    async fn copy(
+        &self,
+        source_bucket: impl Into<String> + Send,
+        source_key: impl Into<String> + Send,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        let (bucket, key) = (bucket.into(), key.into());
+        let request = CopyObjectRequest {
+            copy_source: storage_class::copy_source(&source_bucket.into(), &source_key.into()),
+            bucket: bucket.clone(),
+            key: key.clone(),
+            ..Default::default()
+        };
+        self.copy_object(request)
+            .await
+            .map_err(|e| S3ExtError::from(e).with_object(bucket, key))?;
+        Ok(())
+    }
 }
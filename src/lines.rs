@@ -0,0 +1,121 @@
+//! Stream the lines of an object
+//!
+//! See [`S3Ext::stream_lines`](crate::S3Ext::stream_lines).
+
+use crate::{backend::Backend, error::S3ExtError, iter::REQUESTER};
+use futures::{
+    stream::{self, Stream, StreamExt, TryStreamExt},
+    task::{Context, Poll},
+};
+use rusoto_s3::GetObjectRequest;
+use std::{io, pin::Pin};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+
+type Reader = Pin<Box<dyn AsyncBufRead + Send>>;
+
+/// Stream over the lines of an object, without line terminators
+///
+/// The object is requested when the stream is first polled. Failing
+/// requests are yielded as [`io::Error`]s wrapping an [`S3ExtError`].
+pub struct LineStream {
+    request: Option<(Backend, GetObjectRequest)>,
+    gzip: bool,
+    inner: Option<Pin<Box<dyn Stream<Item = io::Result<String>> + Send>>>,
+}
+
+impl LineStream {
+    pub(crate) fn new(client: &Backend, bucket: impl Into<String>, key: impl Into<String>) -> Self {
+        let request = GetObjectRequest {
+            bucket: bucket.into(),
+            key: key.into(),
+            ..Default::default()
+        };
+        Self {
+            request: Some((client.clone(), request)),
+            gzip: false,
+            inner: None,
+        }
+    }
+
+    /// Decompress the object using gzip before splitting it into lines
+    ///
+    /// Concatenated gzip members are decompressed one after the other.
+    /// Has no effect once the stream has been polled.
+    ///
+    /// Requires the `gzip` feature.
+    #[cfg(feature = "gzip")]
+    pub fn gzip(mut self) -> Self {
+        self.gzip = true;
+        self
+    }
+
+    /// Agree to pay for the request, required to read from requester-pays
+    /// buckets
+    ///
+    /// Has no effect once the stream has been polled.
+    pub fn with_requester_pays(mut self) -> Self {
+        if let Some((_, request)) = &mut self.request {
+            request.request_payer = Some(REQUESTER.to_owned());
+        }
+        self
+    }
+
+    fn start(
+        client: Backend,
+        request: GetObjectRequest,
+        gzip: bool,
+    ) -> impl Stream<Item = io::Result<String>> {
+        let (bucket, key) = (request.bucket.clone(), request.key.clone());
+        stream::once(async move {
+            let output = client
+                .get_object(request)
+                .await
+                .map_err(|e| io::Error::other(S3ExtError::from(e).with_object(bucket, key)))?;
+            let body: Reader = match output.body {
+                Some(body) => Box::pin(BufReader::new(body.into_async_read())),
+                None => Box::pin(&[][..]),
+            };
+            Ok::<_, io::Error>(decode(body, gzip))
+        })
+        .map_ok(|reader| {
+            stream::unfold(reader.lines(), |mut lines| async move {
+                lines
+                    .next_line()
+                    .await
+                    .transpose()
+                    .map(|line| (line, lines))
+            })
+        })
+        .try_flatten()
+    }
+}
+
+#[cfg(feature = "gzip")]
+fn decode(reader: Reader, gzip: bool) -> Reader {
+    if gzip {
+        let mut decoder = async_compression::tokio::bufread::GzipDecoder::new(reader);
+        decoder.multiple_members(true);
+        Box::pin(BufReader::new(decoder))
+    } else {
+        reader
+    }
+}
+
+#[cfg(not(feature = "gzip"))]
+fn decode(reader: Reader, _gzip: bool) -> Reader {
+    reader
+}
+
+impl Stream for LineStream {
+    type Item = io::Result<String>;
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        if let Some((client, request)) = self.request.take() {
+            let inner = Self::start(client, request, self.gzip);
+            self.inner = Some(Box::pin(inner));
+        }
+        match self.inner.as_mut() {
+            Some(inner) => inner.poll_next_unpin(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
@@ -0,0 +1,386 @@
+//! User-defined object metadata
+//!
+//! S3 stores user metadata as `x-amz-meta-*` headers. Names are
+//! case-insensitive and returned in lower case, names and values must be
+//! valid in HTTP headers and all entries together must not exceed 2 KB.
+//! Rusoto doesn't check any of this: invalid entries fail the request or,
+//! worse, are stored mangled. [`Metadata`] validates entries before they are
+//! sent and reads them back from `GetObject` and `HeadObject` outputs.
+//!
+//! # Rewriting metadata
+//!
+//! S3 can't modify the metadata of an object, the object is copied onto
+//! itself replacing all of it instead. [`ObjectMetadata`] holds the metadata
+//! replaced, [`S3Ext::rewrite_metadata`](crate::S3Ext::rewrite_metadata)
+//! rewrites the metadata of all objects below a prefix using a closure.
+//! Objects are only copied if they didn't change since their metadata was
+//! read. As when [changing the storage class](crate::storage_class), the ACL
+//! of copied objects is reset to `private` and their encryption to the
+//! bucket default, objects larger than 5 GiB fail.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use rusoto_s3::{HeadObjectRequest, PutObjectRequest, S3};
+//! use s3_ext::{
+//!     metadata::{Metadata, UserMetadata},
+//!     S3Ext,
+//! };
+//!
+//! let request = PutObjectRequest {
+//!     bucket: "bucket".to_owned(),
+//!     key: "report.pdf".to_owned(),
+//!     metadata: Some(
+//!         Metadata::new()
+//!             .entry("Author", "Jane Doe")
+//!             .entry("revision", "3")
+//!             .build()?,
+//!     ),
+//!     ..Default::default()
+//! };
+//! client.upload_from_file("report.pdf", request).await?;
+//!
+//! let output = client
+//!     .head_object(HeadObjectRequest {
+//!         bucket: "bucket".to_owned(),
+//!         key: "report.pdf".to_owned(),
+//!         ..Default::default()
+//!     })
+//!     .await?;
+//! assert_eq!(output.user_metadata().get("author"), Some("Jane Doe"));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    error::{S3ExtError, S3ExtResult},
+    storage_class::copy_source,
+    S3Ext,
+};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use rusoto_s3::{CopyObjectRequest, GetObjectOutput, HeadObjectOutput, HeadObjectRequest};
+use std::collections::{BTreeMap, HashMap};
+
+/// Limit of the size of user metadata, 2 KB
+///
+/// The size is the number of bytes of all names and values.
+pub const MAX_METADATA_SIZE: usize = 2048;
+
+/// Number of objects copied concurrently by `rewrite_metadata`
+pub const REWRITE_CONCURRENCY: usize = 16;
+
+// Prefix of the headers holding user metadata
+const PREFIX: &str = "x-amz-meta-";
+
+/// User metadata of an object, names are case-insensitive
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    entries: BTreeMap<String, String>,
+}
+
+impl Metadata {
+    /// Metadata without entries
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add entry `name`, replacing an entry of the same name
+    ///
+    /// Validated by [`build`](Self::build).
+    pub fn entry(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.entries.insert(normalize(&name.into()), value.into());
+        self
+    }
+
+    /// Insert entry `name`, returning the value it replaced
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        self.entries.insert(normalize(&name.into()), value.into())
+    }
+
+    /// Remove entry `name`, returning its value
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.entries.remove(&normalize(name))
+    }
+
+    /// Value of entry `name`
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries.get(&normalize(name)).map(String::as_str)
+    }
+
+    /// Entries by name, names in lower case
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Number of bytes counted against [`MAX_METADATA_SIZE`]
+    pub fn size(&self) -> usize {
+        self.iter().map(|(n, v)| n.len() + v.len()).sum()
+    }
+
+    /// Validate the entries, returning them as expected by
+    /// `PutObjectRequest::metadata`
+    ///
+    /// Fails with `S3ExtError::InvalidMetadata` if names are empty or
+    /// contain characters other than ASCII letters, digits and
+    /// ``!#$%&'*+-.^_`|~``, if values contain control or non-ASCII
+    /// characters or if the entries exceed [`MAX_METADATA_SIZE`].
+    pub fn build(self) -> S3ExtResult<HashMap<String, String>> {
+        self.validate()?;
+        Ok(self.entries.into_iter().collect())
+    }
+
+    fn validate(&self) -> S3ExtResult<()> {
+        let invalid = |name: &str, reason| {
+            Err(S3ExtError::InvalidMetadata {
+                name: name.to_owned(),
+                reason,
+            })
+        };
+        for (name, value) in self.iter() {
+            if name.is_empty() {
+                return invalid(name, "names must not be empty");
+            }
+            if !name.bytes().all(is_token) {
+                return invalid(name, "names must be HTTP header tokens");
+            }
+            if !value
+                .bytes()
+                .all(|b| b == b'\t' || (b' '..=b'~').contains(&b))
+            {
+                return invalid(name, "values must be printable ASCII");
+            }
+        }
+        if self.size() > MAX_METADATA_SIZE {
+            return invalid("", "metadata must not exceed 2 KB");
+        }
+        Ok(())
+    }
+}
+
+impl From<&HashMap<String, String>> for Metadata {
+    /// Entries of `metadata`, stripping `x-amz-meta-` prefixes left by
+    /// some S3-compatible servers
+    fn from(metadata: &HashMap<String, String>) -> Self {
+        let entries = metadata
+            .iter()
+            .map(|(name, value)| (normalize(name), value.clone()))
+            .collect();
+        Metadata { entries }
+    }
+}
+
+/// Output of a request returning user metadata
+pub trait UserMetadata {
+    /// User metadata of the object, empty if it has none
+    fn user_metadata(&self) -> Metadata;
+}
+
+impl UserMetadata for GetObjectOutput {
+    fn user_metadata(&self) -> Metadata {
+        self.metadata
+            .as_ref()
+            .map(Metadata::from)
+            .unwrap_or_default()
+    }
+}
+
+impl UserMetadata for HeadObjectOutput {
+    fn user_metadata(&self) -> Metadata {
+        self.metadata
+            .as_ref()
+            .map(Metadata::from)
+            .unwrap_or_default()
+    }
+}
+
+/// Metadata of an object replaced when copying it onto itself
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObjectMetadata {
+    /// `Content-Type`
+    pub content_type: Option<String>,
+    /// `Content-Encoding`
+    pub content_encoding: Option<String>,
+    /// `Content-Disposition`
+    pub content_disposition: Option<String>,
+    /// `Content-Language`
+    pub content_language: Option<String>,
+    /// `Cache-Control`
+    pub cache_control: Option<String>,
+    /// Storage class, `STANDARD` if not set
+    pub storage_class: Option<String>,
+    /// User metadata
+    pub user: Metadata,
+    /// Entity tag of the object the metadata was read from, the object is
+    /// only copied if it still has this entity tag
+    pub e_tag: Option<String>,
+}
+
+/// Outcome of rewriting the metadata of the objects below a prefix
+#[derive(Debug, Default)]
+pub struct RewriteReport {
+    /// Keys of the objects whose metadata was changed, or would be changed
+    /// on a dry run
+    pub changed: Vec<String>,
+    /// Keys of the objects whose metadata was left as is
+    pub unchanged: Vec<String>,
+    /// Keys of the objects that couldn't be rewritten, with the error
+    pub failed: Vec<(String, S3ExtError)>,
+}
+
+impl RewriteReport {
+    /// Whether the metadata of all objects was rewritten
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+pub(crate) async fn object_metadata(
+    client: &Backend,
+    bucket: String,
+    key: String,
+) -> S3ExtResult<ObjectMetadata> {
+    let request = HeadObjectRequest {
+        bucket: bucket.clone(),
+        key: key.clone(),
+        ..Default::default()
+    };
+    let output = client
+        .head_object(request)
+        .await
+        .map_err(|e| S3ExtError::from(e).with_object(bucket, key))?;
+    Ok(ObjectMetadata {
+        user: output.user_metadata(),
+        content_type: output.content_type,
+        content_encoding: output.content_encoding,
+        content_disposition: output.content_disposition,
+        content_language: output.content_language,
+        cache_control: output.cache_control,
+        storage_class: output.storage_class,
+        e_tag: output.e_tag,
+    })
+}
+
+pub(crate) async fn replace_metadata(
+    client: &Backend,
+    bucket: String,
+    key: String,
+    metadata: ObjectMetadata,
+) -> S3ExtResult<()> {
+    let user = metadata
+        .user
+        .build()
+        .map_err(|e| e.with_object(&bucket, &key))?;
+    let request = CopyObjectRequest {
+        copy_source: copy_source(&bucket, &key),
+        copy_source_if_match: metadata.e_tag,
+        bucket: bucket.clone(),
+        key: key.clone(),
+        metadata_directive: Some("REPLACE".to_owned()),
+        metadata: Some(user),
+        content_type: metadata.content_type,
+        content_encoding: metadata.content_encoding,
+        content_disposition: metadata.content_disposition,
+        content_language: metadata.content_language,
+        cache_control: metadata.cache_control,
+        storage_class: metadata.storage_class,
+        ..Default::default()
+    };
+    client
+        .copy_object(request)
+        .await
+        .map(drop)
+        .map_err(|e| S3ExtError::from(e).with_object(bucket, key))
+}
+
+// Rewrite the metadata of `key`, returns whether it changed
+async fn rewrite<C, F>(
+    client: &C,
+    bucket: &str,
+    key: &str,
+    mutate: &F,
+    dry_run: bool,
+) -> S3ExtResult<bool>
+where
+    C: S3Ext + ?Sized,
+    F: Fn(&str, &mut ObjectMetadata),
+{
+    let current = client.object_metadata(bucket, key).await?;
+    let mut metadata = current.clone();
+    mutate(key, &mut metadata);
+    // `mutate` must not circumvent the check for concurrent modifications
+    metadata.e_tag = current.e_tag.clone();
+    if metadata == current {
+        return Ok(false);
+    }
+    if !dry_run {
+        client.replace_metadata(bucket, key, metadata).await?;
+    }
+    Ok(true)
+}
+
+pub(crate) async fn rewrite_metadata<C, F>(
+    client: &C,
+    bucket: String,
+    prefix: String,
+    mutate: F,
+    dry_run: bool,
+) -> S3ExtResult<RewriteReport>
+where
+    C: S3Ext + Sync + ?Sized,
+    F: Fn(&str, &mut ObjectMetadata) + Send + Sync,
+{
+    let mut objects = client.stream_objects_with_prefix(bucket.clone(), prefix);
+    let mut keys = Vec::new();
+    while let Some(object) = objects.try_next().await? {
+        keys.push(
+            object
+                .key
+                .ok_or(S3ExtError::Other("response is missing key"))?,
+        );
+    }
+    let (bucket, mutate) = (&bucket, &mutate);
+    let mut results = stream::iter(keys)
+        .map(|key| async move {
+            let result = rewrite(client, bucket, &key, mutate, dry_run).await;
+            (key, result)
+        })
+        .buffer_unordered(REWRITE_CONCURRENCY);
+    let mut report = RewriteReport::default();
+    while let Some((key, result)) = results.next().await {
+        match result {
+            Ok(true) => report.changed.push(key),
+            Ok(false) => report.unchanged.push(key),
+            Err(e) => report.failed.push((key, e)),
+        }
+    }
+    report.changed.sort();
+    report.unchanged.sort();
+    report.failed.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(report)
+}
+
+// Name in lower case, without `x-amz-meta-` prefix
+fn normalize(name: &str) -> String {
+    let name = name.to_ascii_lowercase();
+    match name.strip_prefix(PREFIX) {
+        Some(stripped) => stripped.to_owned(),
+        None => name,
+    }
+}
+
+// Character allowed in HTTP header names, RFC 7230 `tchar`
+fn is_token(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
@@ -0,0 +1,443 @@
+//! Metrics hooks for transfer accounting
+//!
+//! Implement [`S3ExtMetrics`] to export request counts, transferred bytes,
+//! errors and latencies, e.g. to Prometheus, and install it using
+//! [`MeteredS3Client`].
+//!
+//! # Example
+//!
+//! ```
+//! use rusoto_core::Region;
+//! use rusoto_s3::S3Client;
+//! use s3_ext::metrics::{MeteredS3Client, S3ExtMetrics};
+//! use std::sync::{
+//!     atomic::{AtomicU64, Ordering},
+//!     Arc,
+//! };
+//!
+//! #[derive(Default)]
+//! struct BytesReceived(AtomicU64);
+//!
+//! impl S3ExtMetrics for BytesReceived {
+//!     fn bytes_received(&self, _operation: &'static str, bytes: u64) {
+//!         self.0.fetch_add(bytes, Ordering::Relaxed);
+//!     }
+//! }
+//!
+//! let metrics = Arc::new(BytesReceived::default());
+//! let client = MeteredS3Client::new(S3Client::new(Region::EuWest1), metrics.clone());
+//! ```
+
+use crate::{
+    backend::Backend,
+    body::ObjectBody,
+    compose::ComposePart,
+    diff::DiffStream,
+    error::{S3ExtError, S3ExtResult},
+    framed::FramedObject,
+    iter::{GetObjectStream, ObjectStream, SortedObjectStream},
+    lines::LineStream,
+    metadata::ObjectMetadata,
+    part_size::PartSize,
+    stats::Counting,
+    watch::WatchStream,
+    S3Ext,
+};
+use async_trait::async_trait;
+use rusoto_s3::{
+    CompleteMultipartUploadOutput, GetObjectOutput, GetObjectRequest, PutObjectOutput,
+    PutObjectRequest, S3Client,
+};
+use std::{
+    convert::AsRef,
+    future::Future,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::io;
+use tokio_util::codec::Decoder;
+
+/// Callbacks invoked by [`MeteredS3Client`]
+///
+/// `operation` is the name of the `S3Ext` method, e.g. `"download"`. All
+/// callbacks default to doing nothing.
+pub trait S3ExtMetrics: Send + Sync {
+    /// Operation started
+    fn request(&self, _operation: &'static str) {}
+
+    /// Operation uploaded `bytes`
+    fn bytes_sent(&self, _operation: &'static str, _bytes: u64) {}
+
+    /// Operation downloaded `bytes`
+    fn bytes_received(&self, _operation: &'static str, _bytes: u64) {}
+
+    /// Operation failed
+    fn error(&self, _operation: &'static str, _error: &S3ExtError) {}
+
+    /// Operation completed, successfully or not, after `duration`
+    fn latency(&self, _operation: &'static str, _duration: Duration) {}
+}
+
+/// Client reporting the transfer methods of `S3Ext` to an [`S3ExtMetrics`]
+///
+/// Streams are passed through without being reported.
+#[derive(Clone)]
+pub struct MeteredS3Client<C = S3Client> {
+    inner: C,
+    metrics: Arc<dyn S3ExtMetrics>,
+}
+
+impl<C> MeteredS3Client<C> {
+    /// Wrap `inner`, reporting to `metrics`
+    pub fn new(inner: C, metrics: Arc<dyn S3ExtMetrics>) -> Self {
+        Self { inner, metrics }
+    }
+
+    /// Wrapped client
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    async fn observe<F, T>(&self, operation: &'static str, future: F) -> S3ExtResult<T>
+    where
+        F: Future<Output = S3ExtResult<T>>,
+    {
+        self.metrics.request(operation);
+        let start = Instant::now();
+        let result = future.await;
+        self.metrics.latency(operation, start.elapsed());
+        if let Err(e) = &result {
+            self.metrics.error(operation, e);
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl<C> S3Ext for MeteredS3Client<C>
+where
+    C: S3Ext + Send + Sync,
+{
+    async fn send_request<T, F, Fut>(
+        &self,
+        operation: &'static str,
+        bucket: String,
+        key: String,
+        request: F,
+    ) -> S3ExtResult<T>
+    where
+        T: Send,
+        F: Fn(Backend, String, String) -> Fut + Send + Sync,
+        Fut: Future<Output = S3ExtResult<T>> + Send,
+    {
+        self.observe(
+            operation,
+            self.inner.send_request(operation, bucket, key, request),
+        )
+        .await
+    }
+
+    async fn download_to_file<F>(
+        &self,
+        source: GetObjectRequest,
+        target: F,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        let operation = "download_to_file";
+        let resp = self
+            .observe(operation, self.inner.download_to_file(source, target))
+            .await?;
+        let bytes = resp.content_length.unwrap_or_default();
+        self.metrics.bytes_received(operation, bytes as u64);
+        Ok(resp)
+    }
+
+    async fn upload_from_file<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        let operation = "upload_from_file";
+        let size = tokio::fs::metadata(source.as_ref()).await.map(|m| m.len());
+        let resp = self
+            .observe(operation, self.inner.upload_from_file(source, target))
+            .await?;
+        if let Ok(size) = size {
+            self.metrics.bytes_sent(operation, size);
+        }
+        Ok(resp)
+    }
+
+    async fn upload_from_file_multipart_with<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        let operation = "upload_from_file_multipart";
+        let size = tokio::fs::metadata(source.as_ref()).await.map(|m| m.len());
+        let resp = self
+            .observe(
+                operation,
+                self.inner
+                    .upload_from_file_multipart_with(source, target, part_size),
+            )
+            .await?;
+        if let Ok(size) = size {
+            self.metrics.bytes_sent(operation, size);
+        }
+        Ok(resp)
+    }
+
+    async fn download<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        let operation = "download";
+        let mut target = Counting::new(target);
+        let result = self
+            .observe(operation, self.inner.download(source, &mut target))
+            .await;
+        self.metrics.bytes_received(operation, target.bytes);
+        result
+    }
+
+    async fn download_decompressed<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        let operation = "download_decompressed";
+        let mut target = Counting::new(target);
+        let result = self
+            .observe(
+                operation,
+                self.inner.download_decompressed(source, &mut target),
+            )
+            .await;
+        self.metrics.bytes_received(operation, target.bytes);
+        result
+    }
+
+    async fn get_framed<D>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        decoder: D,
+    ) -> S3ExtResult<FramedObject<D>>
+    where
+        D: Decoder + Send,
+    {
+        self.observe("get_framed", self.inner.get_framed(bucket, key, decoder))
+            .await
+    }
+
+    async fn get_body_with(&self, source: GetObjectRequest) -> S3ExtResult<ObjectBody> {
+        self.observe("get_body_with", self.inner.get_body_with(source))
+            .await
+    }
+
+    async fn upload<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        let operation = "upload";
+        let mut source = Counting::new(source);
+        let resp = self
+            .observe(operation, self.inner.upload(&mut source, target))
+            .await?;
+        self.metrics.bytes_sent(operation, source.bytes);
+        Ok(resp)
+    }
+
+    async fn upload_multipart_with<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        let operation = "upload_multipart";
+        let mut source = Counting::new(source);
+        let resp = self
+            .observe(
+                operation,
+                self.inner
+                    .upload_multipart_with(&mut source, target, part_size),
+            )
+            .await?;
+        self.metrics.bytes_sent(operation, source.bytes);
+        Ok(resp)
+    }
+
+    async fn upload_multipart_resumable<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        let operation = "upload_multipart_resumable";
+        let mut source = Counting::new(source);
+        let resp = self
+            .observe(
+                operation,
+                self.inner
+                    .upload_multipart_resumable(&mut source, target, part_size),
+            )
+            .await?;
+        self.metrics.bytes_sent(operation, source.bytes);
+        Ok(resp)
+    }
+
+    #[inline]
+    fn stream_objects(&self, bucket: impl Into<String>) -> ObjectStream {
+        self.inner.stream_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> ObjectStream {
+        self.inner.stream_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_objects_sorted_by_mtime(&self, bucket: impl Into<String>) -> SortedObjectStream {
+        self.inner.stream_objects_sorted_by_mtime(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix_sorted_by_mtime(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> SortedObjectStream {
+        self.inner
+            .stream_objects_with_prefix_sorted_by_mtime(bucket, prefix)
+    }
+
+    #[inline]
+    fn diff(
+        &self,
+        source_bucket: impl Into<String>,
+        source_prefix: impl Into<String>,
+        dest_bucket: impl Into<String>,
+        dest_prefix: impl Into<String>,
+    ) -> DiffStream {
+        self.inner
+            .diff(source_bucket, source_prefix, dest_bucket, dest_prefix)
+    }
+
+    #[inline]
+    fn watch_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        period: Duration,
+    ) -> WatchStream {
+        self.inner.watch_prefix(bucket, prefix, period)
+    }
+
+    #[inline]
+    fn stream_get_objects(&self, bucket: impl Into<String>) -> GetObjectStream {
+        self.inner.stream_get_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_get_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> GetObjectStream {
+        self.inner.stream_get_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_lines(&self, bucket: impl Into<String>, key: impl Into<String>) -> LineStream {
+        self.inner.stream_lines(bucket, key)
+    }
+
+    async fn delete(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.observe("delete", self.inner.delete(bucket, key)).await
+    }
+
+    async fn compose_object(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        parts: Vec<ComposePart>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput> {
+        self.observe(
+            "compose_object",
+            self.inner.compose_object(bucket, key, parts),
+        )
+        .await
+    }
+
+    async fn object_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectMetadata> {
+        self.observe("object_metadata", self.inner.object_metadata(bucket, key))
+            .await
+    }
+
+    async fn replace_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        metadata: ObjectMetadata,
+    ) -> S3ExtResult<()> {
+        self.observe(
+            "replace_metadata",
+            self.inner.replace_metadata(bucket, key, metadata),
+        )
+        .await
+    }
+
+    async fn copy(
+        &self,
+        source_bucket: impl Into<String> + Send,
+        source_key: impl Into<String> + Send,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.observe(
+            "copy",
+            self.inner.copy(source_bucket, source_key, bucket, key),
+        )
+        .await
+    }
+}
@@ -0,0 +1,1595 @@
+//! In-memory S3 for unit tests
+//!
+//! [`MockS3`] implements `S3Ext` on top of buckets kept in memory, so code
+//! built on this crate can be tested without MinIO or AWS. It understands
+//! the subset of the S3 REST API used by `S3Ext` and common `S3` calls:
+//!
+//! * creating, deleting and checking buckets, bucket locations
+//! * putting, getting (including ranges, parts and `If-None-Match`),
+//!   heading, copying and deleting objects, also in bulk, keeping track of
+//!   their storage class and whether they require a customer-provided key
+//!   (SSE-C)
+//! * listing objects (`ListObjectsV2`) with prefixes, delimiters and
+//!   pagination
+//! * getting, putting and deleting object and bucket tags
+//! * getting, putting and deleting the policy, replication, website and
+//!   Intelligent-Tiering configurations of buckets, kept as uploaded, and
+//!   listing the latter
+//! * getting and putting the server access logging configuration of
+//!   buckets
+//! * multi-part uploads, including the minimum part size, listing uploads
+//!   in progress and their parts
+//!
+//! Other requests fail with `501 NotImplemented`. Use
+//! [`client`](MockS3::client) for `S3` calls not covered by `S3Ext`.
+//!
+//! Requires the `mock` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use rusoto_s3::GetObjectRequest;
+//! use s3_ext::{mock::MockS3, S3Ext};
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> Result<(), s3_ext::error::S3ExtError> {
+//! let s3 = MockS3::new();
+//! s3.put_object("bucket", "key", "hello");
+//!
+//! let mut body = Vec::new();
+//! s3.download(
+//!     GetObjectRequest {
+//!         bucket: "bucket".to_owned(),
+//!         key: "key".to_owned(),
+//!         ..Default::default()
+//!     },
+//!     &mut body,
+//! )
+//! .await?;
+//! assert_eq!(body, b"hello");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    body::ObjectBody,
+    compose::ComposePart,
+    diff::DiffStream,
+    error::S3ExtResult,
+    framed::FramedObject,
+    iter::{GetObjectStream, ObjectStream, SortedObjectStream},
+    lines::LineStream,
+    metadata::ObjectMetadata,
+    part_size::PartSize,
+    upload::MIN_PART_SIZE,
+    watch::WatchStream,
+    S3Ext,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::TryStreamExt;
+use hyper::{header::HeaderName, HeaderMap, StatusCode};
+use md5::{Digest, Md5};
+use parking_lot::Mutex;
+use percent_encoding::percent_decode_str;
+use rusoto_core::{
+    request::{DispatchSignedRequestFuture, HttpResponse},
+    signature::{SignedRequest, SignedRequestPayload},
+    ByteStream, Client, DispatchSignedRequest, HttpDispatchError, Region,
+};
+use rusoto_s3::{
+    CompleteMultipartUploadOutput, GetObjectOutput, GetObjectRequest, PutObjectOutput,
+    PutObjectRequest, S3Client,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::AsRef,
+    fmt::Write,
+    future::Future,
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::io;
+use tokio_util::codec::Decoder;
+
+const ENDPOINT: &str = "http://mock-s3";
+
+/// Object stored in a [`MockS3`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectRecord {
+    /// Content of the object
+    pub body: Vec<u8>,
+    /// Entity tag, including the surrounding quotes
+    pub e_tag: String,
+    /// Time the object was written
+    pub last_modified: DateTime<Utc>,
+    /// `Content-Type` the object was uploaded with
+    pub content_type: Option<String>,
+    /// `Content-Encoding` the object was uploaded with
+    pub content_encoding: Option<String>,
+    /// User-defined metadata, without the `x-amz-meta-` prefix
+    pub metadata: HashMap<String, String>,
+    /// Tags of the object
+    pub tags: BTreeMap<String, String>,
+    /// Storage class, `STANDARD` unless written with another one
+    pub storage_class: String,
+    /// Sizes of the parts of objects written by multipart uploads, empty
+    /// otherwise
+    pub part_sizes: Vec<usize>,
+    /// Algorithm of the customer-provided key (SSE-C) the object was
+    /// uploaded with, reading the object requires a key then
+    pub sse_customer_algorithm: Option<String>,
+}
+
+impl ObjectRecord {
+    fn new(body: Vec<u8>, e_tag: String, headers: &RequestHeaders) -> Self {
+        Self {
+            body,
+            e_tag,
+            last_modified: Utc::now(),
+            content_type: headers.get("content-type").map(str::to_owned),
+            content_encoding: headers.get("content-encoding").map(str::to_owned),
+            metadata: headers.metadata(),
+            tags: headers.tags(),
+            storage_class: headers.storage_class(),
+            part_sizes: Vec::new(),
+            sse_customer_algorithm: headers.sse_customer_algorithm(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Upload {
+    // order the upload was started in
+    number: u64,
+    initiated: DateTime<Utc>,
+    bucket: String,
+    key: String,
+    content_type: Option<String>,
+    content_encoding: Option<String>,
+    metadata: HashMap<String, String>,
+    tags: BTreeMap<String, String>,
+    storage_class: String,
+    sse_customer_algorithm: Option<String>,
+    parts: BTreeMap<i64, Vec<u8>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    buckets: HashMap<String, BTreeMap<String, ObjectRecord>>,
+    bucket_tags: HashMap<String, BTreeMap<String, String>>,
+    // documents of bucket subresources, e.g. those in `BUCKET_CONFIGS`, by
+    // bucket, subresource and ID
+    bucket_configs: BTreeMap<(String, String, String), Vec<u8>>,
+    uploads: HashMap<String, Upload>,
+    next_upload_id: u64,
+}
+
+/// In-memory S3 implementing `S3Ext`
+///
+/// Clones share the same buckets.
+#[derive(Clone)]
+pub struct MockS3 {
+    client: S3Client,
+    state: Arc<Mutex<State>>,
+}
+
+impl Default for MockS3 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockS3 {
+    /// Create an S3 without any buckets
+    pub fn new() -> Self {
+        let state = Arc::default();
+        let dispatcher = MockDispatcher {
+            state: Arc::clone(&state),
+        };
+        let client = S3Client::new_with_client(
+            Client::new_not_signing(dispatcher),
+            Region::Custom {
+                name: "us-east-1".to_owned(),
+                endpoint: ENDPOINT.to_owned(),
+            },
+        );
+        Self { client, state }
+    }
+
+    /// Client sending requests to this S3
+    pub fn client(&self) -> &S3Client {
+        &self.client
+    }
+
+    /// Create `bucket` unless it exists
+    pub fn create_bucket(&self, bucket: &str) {
+        self.state
+            .lock()
+            .buckets
+            .entry(bucket.to_owned())
+            .or_default();
+    }
+
+    /// Store `body` as `key` in `bucket`, creating `bucket` if needed
+    pub fn put_object(&self, bucket: &str, key: &str, body: impl Into<Vec<u8>>) {
+        let body = body.into();
+        let record = ObjectRecord {
+            e_tag: e_tag(&body),
+            body,
+            last_modified: Utc::now(),
+            content_type: None,
+            content_encoding: None,
+            metadata: HashMap::new(),
+            tags: BTreeMap::new(),
+            storage_class: "STANDARD".to_owned(),
+            part_sizes: Vec::new(),
+            sse_customer_algorithm: None,
+        };
+        self.state
+            .lock()
+            .buckets
+            .entry(bucket.to_owned())
+            .or_default()
+            .insert(key.to_owned(), record);
+    }
+
+    /// Object `key` in `bucket`
+    pub fn object(&self, bucket: &str, key: &str) -> Option<ObjectRecord> {
+        let state = self.state.lock();
+        state.buckets.get(bucket)?.get(key).cloned()
+    }
+
+    /// Keys of the objects in `bucket`, sorted
+    pub fn keys(&self, bucket: &str) -> Vec<String> {
+        let state = self.state.lock();
+        state
+            .buckets
+            .get(bucket)
+            .map(|objects| objects.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Number of multi-part uploads neither completed nor aborted
+    pub fn pending_uploads(&self) -> usize {
+        self.state.lock().uploads.len()
+    }
+}
+
+#[async_trait]
+impl S3Ext for MockS3 {
+    async fn send_request<T, F, Fut>(
+        &self,
+        operation: &'static str,
+        bucket: String,
+        key: String,
+        request: F,
+    ) -> S3ExtResult<T>
+    where
+        T: Send,
+        F: Fn(Backend, String, String) -> Fut + Send + Sync,
+        Fut: Future<Output = S3ExtResult<T>> + Send,
+    {
+        self.client
+            .send_request(operation, bucket, key, request)
+            .await
+    }
+
+    async fn download_to_file<F>(
+        &self,
+        source: GetObjectRequest,
+        target: F,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.client.download_to_file(source, target).await
+    }
+
+    async fn upload_from_file<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.client.upload_from_file(source, target).await
+    }
+
+    async fn upload_from_file_multipart_with<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.client
+            .upload_from_file_multipart_with(source, target, part_size)
+            .await
+    }
+
+    async fn download<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        self.client.download(source, target).await
+    }
+
+    async fn download_decompressed<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        self.client.download_decompressed(source, target).await
+    }
+
+    async fn get_framed<D>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        decoder: D,
+    ) -> S3ExtResult<FramedObject<D>>
+    where
+        D: Decoder + Send,
+    {
+        self.client.get_framed(bucket, key, decoder).await
+    }
+
+    async fn get_body_with(&self, source: GetObjectRequest) -> S3ExtResult<ObjectBody> {
+        self.client.get_body_with(source).await
+    }
+
+    async fn upload<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.client.upload(source, target).await
+    }
+
+    async fn upload_multipart_with<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.client
+            .upload_multipart_with(source, target, part_size)
+            .await
+    }
+
+    async fn upload_multipart_resumable<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.client
+            .upload_multipart_resumable(source, target, part_size)
+            .await
+    }
+
+    #[inline]
+    fn stream_objects(&self, bucket: impl Into<String>) -> ObjectStream {
+        self.client.stream_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> ObjectStream {
+        self.client.stream_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_objects_sorted_by_mtime(&self, bucket: impl Into<String>) -> SortedObjectStream {
+        self.client.stream_objects_sorted_by_mtime(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix_sorted_by_mtime(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> SortedObjectStream {
+        self.client
+            .stream_objects_with_prefix_sorted_by_mtime(bucket, prefix)
+    }
+
+    #[inline]
+    fn diff(
+        &self,
+        source_bucket: impl Into<String>,
+        source_prefix: impl Into<String>,
+        dest_bucket: impl Into<String>,
+        dest_prefix: impl Into<String>,
+    ) -> DiffStream {
+        self.client
+            .diff(source_bucket, source_prefix, dest_bucket, dest_prefix)
+    }
+
+    #[inline]
+    fn watch_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        period: Duration,
+    ) -> WatchStream {
+        self.client.watch_prefix(bucket, prefix, period)
+    }
+
+    #[inline]
+    fn stream_get_objects(&self, bucket: impl Into<String>) -> GetObjectStream {
+        self.client.stream_get_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_get_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> GetObjectStream {
+        self.client.stream_get_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_lines(&self, bucket: impl Into<String>, key: impl Into<String>) -> LineStream {
+        self.client.stream_lines(bucket, key)
+    }
+
+    async fn delete(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.client.delete(bucket, key).await
+    }
+
+    async fn compose_object(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        parts: Vec<ComposePart>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput> {
+        self.client.compose_object(bucket, key, parts).await
+    }
+
+    async fn object_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectMetadata> {
+        self.client.object_metadata(bucket, key).await
+    }
+
+    async fn replace_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        metadata: ObjectMetadata,
+    ) -> S3ExtResult<()> {
+        self.client.replace_metadata(bucket, key, metadata).await
+    }
+
+    async fn copy(
+        &self,
+        source_bucket: impl Into<String> + Send,
+        source_key: impl Into<String> + Send,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.client
+            .copy(source_bucket, source_key, bucket, key)
+            .await
+    }
+}
+
+/// HTTP client answering requests from the in-memory state
+struct MockDispatcher {
+    state: Arc<Mutex<State>>,
+}
+
+impl DispatchSignedRequest for MockDispatcher {
+    fn dispatch(
+        &self,
+        request: SignedRequest,
+        _timeout: Option<Duration>,
+    ) -> DispatchSignedRequestFuture {
+        let state = Arc::clone(&self.state);
+        Box::pin(async move {
+            let mut request = request;
+            let body = match request.payload.take() {
+                None => Vec::new(),
+                Some(SignedRequestPayload::Buffer(buffer)) => buffer.to_vec(),
+                Some(SignedRequestPayload::Stream(stream)) => stream
+                    .map_ok(|chunk| chunk.to_vec())
+                    .try_concat()
+                    .await
+                    .map_err(|e| HttpDispatchError::new(e.to_string()))?,
+            };
+            let mut state = state.lock();
+            Ok(handle(&mut state, &request, body).into_http_response())
+        })
+    }
+}
+
+struct RequestHeaders<'a>(&'a BTreeMap<String, Vec<Vec<u8>>>);
+
+impl RequestHeaders<'_> {
+    fn get(&self, name: &str) -> Option<&str> {
+        let value = self.0.get(name)?.first()?;
+        std::str::from_utf8(value).ok()
+    }
+
+    fn metadata(&self) -> HashMap<String, String> {
+        self.0
+            .keys()
+            .filter_map(|name| {
+                let key = name.strip_prefix("x-amz-meta-")?;
+                Some((key.to_owned(), self.get(name)?.to_owned()))
+            })
+            .collect()
+    }
+
+    // Tags of the `x-amz-tagging` header, encoded like a query string
+    fn tags(&self) -> BTreeMap<String, String> {
+        let decode = |s: &str| {
+            percent_decode_str(&s.replace('+', " "))
+                .decode_utf8_lossy()
+                .into_owned()
+        };
+        self.get("x-amz-tagging")
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (decode(key), decode(value))
+            })
+            .collect()
+    }
+
+    fn storage_class(&self) -> String {
+        self.get("x-amz-storage-class")
+            .unwrap_or("STANDARD")
+            .to_owned()
+    }
+
+    fn sse_customer_algorithm(&self) -> Option<String> {
+        self.get("x-amz-server-side-encryption-customer-algorithm")
+            .map(str::to_owned)
+    }
+}
+
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    fn xml(body: String) -> Self {
+        Self::new(200)
+            .header("content-type", "application/xml")
+            .body(body.into_bytes())
+    }
+
+    fn error(status: u16, code: &str, message: &str) -> Self {
+        let body = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <Error><Code>{}</Code><Message>{}</Message></Error>",
+            code, message
+        );
+        Self::new(status)
+            .header("content-type", "application/xml")
+            .body(body.into_bytes())
+    }
+
+    fn header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.push((name.to_owned(), value.into()));
+        self
+    }
+
+    fn body(mut self, body: Vec<u8>) -> Self {
+        self.body = body;
+        self
+    }
+
+    fn into_http_response(self) -> HttpResponse {
+        let mut headers = HeaderMap::<String>::default();
+        for (name, value) in self.headers {
+            if let Ok(name) = HeaderName::from_bytes(name.as_bytes()) {
+                headers.append(name, value);
+            }
+        }
+        HttpResponse {
+            status: StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK),
+            body: ByteStream::from(self.body),
+            headers,
+        }
+    }
+}
+
+fn no_such_bucket() -> Response {
+    Response::error(404, "NoSuchBucket", "The specified bucket does not exist")
+}
+
+fn no_such_key() -> Response {
+    Response::error(404, "NoSuchKey", "The specified key does not exist.")
+}
+
+fn no_such_upload() -> Response {
+    Response::error(404, "NoSuchUpload", "The specified upload does not exist.")
+}
+
+fn handle(state: &mut State, request: &SignedRequest, body: Vec<u8>) -> Response {
+    let headers = RequestHeaders(&request.headers);
+    let params = &request.params;
+    let path = request.path.trim_start_matches('/');
+    let (bucket, key) = match path.split_once('/') {
+        Some((bucket, key)) if !key.is_empty() => (bucket, Some(key)),
+        Some((bucket, _)) => (bucket, None),
+        None => (path, None),
+    };
+    let param = |name: &str| params.get(name).and_then(Option::as_deref);
+
+    let config = BUCKET_CONFIGS
+        .iter()
+        .find(|(subresource, _, _)| params.contains_key(*subresource));
+    if let (None, Some(config)) = (key, config) {
+        let method = request.method.as_str();
+        return match param("id") {
+            None if method == "GET" && config.0 == "intelligent-tiering" => {
+                list_intelligent_tiering(state, bucket)
+            }
+            id => bucket_config(state, bucket, method, config, id.unwrap_or_default(), body),
+        };
+    }
+
+    match (request.method.as_str(), key) {
+        (method, None) if params.contains_key("tagging") => {
+            bucket_tagging(state, bucket, method, &body)
+        }
+        (method, None) if params.contains_key("logging") => {
+            bucket_logging(state, bucket, method, body)
+        }
+        ("PUT", None) => {
+            state.buckets.entry(bucket.to_owned()).or_default();
+            Response::new(200).header("location", format!("/{}", bucket))
+        }
+        ("HEAD", None) => match state.buckets.get(bucket) {
+            Some(_) => Response::new(200),
+            None => Response::new(404),
+        },
+        ("DELETE", None) => match state.buckets.get(bucket) {
+            None => no_such_bucket(),
+            Some(objects) if !objects.is_empty() => Response::error(
+                409,
+                "BucketNotEmpty",
+                "The bucket you tried to delete is not empty",
+            ),
+            Some(_) => {
+                state.buckets.remove(bucket);
+                state.bucket_tags.remove(bucket);
+                state
+                    .bucket_configs
+                    .retain(|(name, _, _), _| name != bucket);
+                Response::new(204)
+            }
+        },
+        ("GET", None) if params.contains_key("location") => match state.buckets.get(bucket) {
+            None => no_such_bucket(),
+            Some(_) => Response::xml(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <LocationConstraint xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\"/>"
+                    .to_owned(),
+            ),
+        },
+        ("GET", None) if param("list-type") == Some("2") => list_objects_v2(state, bucket, &param),
+        ("GET", None) if params.contains_key("uploads") => {
+            list_multipart_uploads(state, bucket, param("prefix").unwrap_or_default())
+        }
+        ("POST", None) if params.contains_key("delete") => delete_objects(state, bucket, &body),
+        (method, Some(key)) if params.contains_key("tagging") => {
+            let record = match state.buckets.get_mut(bucket) {
+                None => return no_such_bucket(),
+                Some(objects) => match objects.get_mut(key) {
+                    None => return no_such_key(),
+                    Some(record) => record,
+                },
+            };
+            object_tagging(record, method, &body)
+        }
+        ("PUT", Some(key)) if param("uploadId").is_some() => {
+            let copy = match headers.get("x-amz-copy-source") {
+                Some(source) => {
+                    match copy_part(state, source, headers.get("x-amz-copy-source-range")) {
+                        Ok(part) => Some(part),
+                        Err(response) => return response,
+                    }
+                }
+                None => None,
+            };
+            let upload = match state.uploads.get_mut(param("uploadId").unwrap_or_default()) {
+                Some(upload) if upload.bucket == bucket && upload.key == key => upload,
+                _ => return no_such_upload(),
+            };
+            let part_number = match param("partNumber").and_then(|n| n.parse().ok()) {
+                Some(n) if (1..=10_000).contains(&n) => n,
+                _ => return Response::error(400, "InvalidArgument", "Invalid part number"),
+            };
+            match copy {
+                Some(part) => {
+                    let e_tag = e_tag(&part);
+                    upload.parts.insert(part_number, part);
+                    Response::xml(format!(
+                        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                         <CopyPartResult><LastModified>{}</LastModified><ETag>{}</ETag></CopyPartResult>",
+                        Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+                        escape(&e_tag)
+                    ))
+                }
+                None => {
+                    let e_tag = e_tag(&body);
+                    upload.parts.insert(part_number, body);
+                    Response::new(200).header("etag", e_tag)
+                }
+            }
+        }
+        ("PUT", Some(key)) => {
+            if !state.buckets.contains_key(bucket) {
+                return no_such_bucket();
+            }
+            if let Some(source) = headers.get("x-amz-copy-source") {
+                return copy_object(state, bucket, key, source, &headers);
+            }
+            let e_tag = e_tag(&body);
+            let record = ObjectRecord::new(body, e_tag.clone(), &headers);
+            state
+                .buckets
+                .get_mut(bucket)
+                .unwrap()
+                .insert(key.to_owned(), record);
+            Response::new(200).header("etag", e_tag)
+        }
+        ("POST", Some(key)) if params.contains_key("uploads") => {
+            if !state.buckets.contains_key(bucket) {
+                return no_such_bucket();
+            }
+            state.next_upload_id += 1;
+            let upload_id = format!("mock-upload-{}", state.next_upload_id);
+            state.uploads.insert(
+                upload_id.clone(),
+                Upload {
+                    number: state.next_upload_id,
+                    initiated: Utc::now(),
+                    bucket: bucket.to_owned(),
+                    key: key.to_owned(),
+                    content_type: headers.get("content-type").map(str::to_owned),
+                    content_encoding: headers.get("content-encoding").map(str::to_owned),
+                    metadata: headers.metadata(),
+                    tags: headers.tags(),
+                    storage_class: headers.storage_class(),
+                    sse_customer_algorithm: headers.sse_customer_algorithm(),
+                    parts: BTreeMap::new(),
+                },
+            );
+            Response::xml(format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <InitiateMultipartUploadResult>\
+                 <Bucket>{}</Bucket><Key>{}</Key><UploadId>{}</UploadId>\
+                 </InitiateMultipartUploadResult>",
+                escape(bucket),
+                escape(key),
+                upload_id
+            ))
+        }
+        ("POST", Some(key)) if param("uploadId").is_some() => {
+            complete_multipart_upload(state, bucket, key, param("uploadId").unwrap(), &body)
+        }
+        ("DELETE", Some(key)) if param("uploadId").is_some() => {
+            match state.uploads.get(param("uploadId").unwrap()) {
+                Some(upload) if upload.bucket == bucket && upload.key == key => {
+                    state.uploads.remove(param("uploadId").unwrap());
+                    Response::new(204)
+                }
+                _ => no_such_upload(),
+            }
+        }
+        ("DELETE", Some(key)) => match state.buckets.get_mut(bucket) {
+            None => no_such_bucket(),
+            Some(objects) => {
+                objects.remove(key);
+                Response::new(204)
+            }
+        },
+        ("GET", Some(key)) if param("uploadId").is_some() => {
+            match state.uploads.get(param("uploadId").unwrap()) {
+                Some(upload) if upload.bucket == bucket && upload.key == key => {
+                    list_parts(upload, param("uploadId").unwrap(), &param)
+                }
+                _ => no_such_upload(),
+            }
+        }
+        (method @ "GET", Some(key)) | (method @ "HEAD", Some(key)) => {
+            let record = match state.buckets.get(bucket) {
+                None if method == "HEAD" => return Response::new(404),
+                None => return no_such_bucket(),
+                Some(objects) => match objects.get(key) {
+                    None if method == "HEAD" => return Response::new(404),
+                    None => return no_such_key(),
+                    Some(record) => record,
+                },
+            };
+            // keys aren't checked, only whether one is given
+            if headers.sse_customer_algorithm() != record.sse_customer_algorithm {
+                return Response::error(
+                    400,
+                    "InvalidRequest",
+                    "The encryption parameters are not applicable to this object",
+                );
+            }
+            if headers.get("if-none-match") == Some(record.e_tag.as_str()) {
+                return Response::new(304).header("etag", record.e_tag.clone());
+            }
+            get_object(
+                record,
+                headers.get("range"),
+                param("partNumber"),
+                method == "HEAD",
+            )
+        }
+        _ => Response::error(501, "NotImplemented", "The request is not supported"),
+    }
+}
+
+fn get_object(
+    record: &ObjectRecord,
+    range: Option<&str>,
+    part_number: Option<&str>,
+    head: bool,
+) -> Response {
+    let len = record.body.len();
+    let mut response = Response::new(200)
+        .header("etag", record.e_tag.clone())
+        .header("last-modified", http_date(&record.last_modified))
+        .header("accept-ranges", "bytes");
+    if let Some(content_type) = &record.content_type {
+        response = response.header("content-type", content_type.clone());
+    }
+    if let Some(content_encoding) = &record.content_encoding {
+        response = response.header("content-encoding", content_encoding.clone());
+    }
+    for (key, value) in &record.metadata {
+        response = response.header(&format!("x-amz-meta-{}", key), value.clone());
+    }
+    if record.storage_class != "STANDARD" {
+        response = response.header("x-amz-storage-class", record.storage_class.clone());
+    }
+    if let Some(algorithm) = &record.sse_customer_algorithm {
+        response = response.header(
+            "x-amz-server-side-encryption-customer-algorithm",
+            algorithm.clone(),
+        );
+    }
+    let mut range = range.map(str::to_owned);
+    if let Some(part_number) = part_number {
+        let part_number = part_number.parse::<usize>().unwrap_or(0);
+        match part_range(&record.part_sizes, part_number, len) {
+            Some((start, end)) => range = Some(format!("bytes={}-{}", start, end - 1)),
+            None if part_number == 1 => (),
+            None => {
+                return Response::error(
+                    416,
+                    "InvalidPartNumber",
+                    "The requested partnumber is not satisfiable",
+                )
+            }
+        }
+        if !record.part_sizes.is_empty() {
+            response = response.header("x-amz-mp-parts-count", record.part_sizes.len().to_string());
+        }
+    }
+    let range = range.as_deref();
+    let (start, end) = match range {
+        None => (0, len),
+        Some(range) => match parse_range(range, len) {
+            Some(range) => range,
+            None => {
+                return Response::error(
+                    416,
+                    "InvalidRange",
+                    "The requested range is not satisfiable",
+                )
+            }
+        },
+    };
+    if range.is_some() {
+        response.status = 206;
+        response = response.header(
+            "content-range",
+            format!("bytes {}-{}/{}", start, end - 1, len),
+        );
+    }
+    response = response.header("content-length", (end - start).to_string());
+    if head {
+        response
+    } else {
+        response.body(record.body[start..end].to_vec())
+    }
+}
+
+// Byte range of part `part_number` of a multipart object, `None` for other
+// objects
+fn part_range(part_sizes: &[usize], part_number: usize, len: usize) -> Option<(usize, usize)> {
+    if part_number == 0 || part_number > part_sizes.len() {
+        return None;
+    }
+    let start = part_sizes[..part_number - 1].iter().sum();
+    let end = start + part_sizes[part_number - 1];
+    Some((start, end.min(len)))
+}
+
+// Byte range `bytes=a-b`, `bytes=a-` or `bytes=-n` as half-open interval
+fn parse_range(range: &str, len: usize) -> Option<(usize, usize)> {
+    let (first, last) = range.strip_prefix("bytes=")?.split_once('-')?;
+    let (start, end) = match (first, last) {
+        ("", suffix) => {
+            let suffix: usize = suffix.parse().ok()?;
+            (len.saturating_sub(suffix), len)
+        }
+        (first, "") => (first.parse().ok()?, len),
+        (first, last) => {
+            let last: usize = last.parse().ok()?;
+            (first.parse().ok()?, (last + 1).min(len))
+        }
+    };
+    if start < end {
+        Some((start, end))
+    } else {
+        None
+    }
+}
+
+fn list_objects_v2<'a>(
+    state: &State,
+    bucket: &str,
+    param: &dyn Fn(&str) -> Option<&'a str>,
+) -> Response {
+    let objects = match state.buckets.get(bucket) {
+        Some(objects) => objects,
+        None => return no_such_bucket(),
+    };
+    let prefix = param("prefix").unwrap_or_default();
+    let delimiter = param("delimiter").filter(|d| !d.is_empty());
+    let max_keys: usize = param("max-keys")
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(1000);
+    // continuation tokens are the hex-encoded key to continue after
+    let after = match param("continuation-token") {
+        Some(token) => match hex::decode(token)
+            .ok()
+            .and_then(|t| String::from_utf8(t).ok())
+        {
+            Some(after) => after,
+            None => {
+                return Response::error(
+                    400,
+                    "InvalidArgument",
+                    "The continuation token provided is incorrect",
+                )
+            }
+        },
+        None => param("start-after").unwrap_or_default().to_owned(),
+    };
+
+    let mut contents = String::new();
+    let mut common_prefixes = String::new();
+    let mut count = 0;
+    let mut truncated = false;
+    // key to continue after and the last common prefix
+    let mut next = String::new();
+    let mut last_common: Option<String> = None;
+    for (key, record) in objects
+        .iter()
+        .filter(|(key, _)| key.starts_with(prefix) && key.as_str() > after.as_str())
+    {
+        if matches!(&last_common, Some(common) if key.starts_with(common.as_str())) {
+            continue;
+        }
+        if count == max_keys {
+            truncated = true;
+            break;
+        }
+        count += 1;
+        match delimiter.and_then(|d| key[prefix.len()..].find(d).map(|i| (d, i))) {
+            Some((d, i)) => {
+                let common = &key[..prefix.len() + i + d.len()];
+                let _ = write!(
+                    common_prefixes,
+                    "<CommonPrefixes><Prefix>{}</Prefix></CommonPrefixes>",
+                    escape(common)
+                );
+                // sorts after all keys starting with the common prefix
+                next = format!("{}{}", common, char::MAX);
+                last_common = Some(common.to_owned());
+            }
+            None => {
+                let _ = write!(
+                    contents,
+                    "<Contents><Key>{}</Key><LastModified>{}</LastModified>\
+                     <ETag>{}</ETag><Size>{}</Size><StorageClass>{}</StorageClass>\
+                     </Contents>",
+                    escape(key),
+                    record.last_modified.format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+                    escape(&record.e_tag),
+                    record.body.len(),
+                    escape(&record.storage_class)
+                );
+                next = key.clone();
+            }
+        }
+    }
+
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <ListBucketResult xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">\
+         <Name>{}</Name><Prefix>{}</Prefix><KeyCount>{}</KeyCount>\
+         <MaxKeys>{}</MaxKeys><IsTruncated>{}</IsTruncated>",
+        escape(bucket),
+        escape(prefix),
+        count,
+        max_keys,
+        truncated
+    );
+    if let Some(delimiter) = delimiter {
+        let _ = write!(xml, "<Delimiter>{}</Delimiter>", escape(delimiter));
+    }
+    if let Some(token) = param("continuation-token") {
+        let _ = write!(xml, "<ContinuationToken>{}</ContinuationToken>", token);
+    }
+    if truncated {
+        let _ = write!(
+            xml,
+            "<NextContinuationToken>{}</NextContinuationToken>",
+            hex::encode(next)
+        );
+    }
+    xml.push_str(&contents);
+    xml.push_str(&common_prefixes);
+    xml.push_str("</ListBucketResult>");
+    Response::xml(xml)
+}
+
+fn delete_objects(state: &mut State, bucket: &str, body: &[u8]) -> Response {
+    let objects = match state.buckets.get_mut(bucket) {
+        Some(objects) => objects,
+        None => return no_such_bucket(),
+    };
+    let body = String::from_utf8_lossy(body);
+    let mut xml = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<DeleteResult>".to_owned();
+    for key in elements(&body, "Key") {
+        objects.remove(&key);
+        let _ = write!(xml, "<Deleted><Key>{}</Key></Deleted>", escape(&key));
+    }
+    xml.push_str("</DeleteResult>");
+    Response::xml(xml)
+}
+
+fn copy_object(
+    state: &mut State,
+    bucket: &str,
+    key: &str,
+    source: &str,
+    headers: &RequestHeaders,
+) -> Response {
+    let source = percent_decode_str(source).decode_utf8_lossy();
+    let (source_bucket, source_key) = match source.trim_start_matches('/').split_once('/') {
+        Some(source) => source,
+        None => return Response::error(400, "InvalidArgument", "Invalid copy source"),
+    };
+    let mut record = match state.buckets.get(source_bucket) {
+        None => return no_such_bucket(),
+        Some(objects) => match objects.get(source_key) {
+            None => return no_such_key(),
+            Some(record) => record.clone(),
+        },
+    };
+    if let Some(e_tag) = headers.get("x-amz-copy-source-if-match") {
+        if e_tag != record.e_tag {
+            return Response::error(
+                412,
+                "PreconditionFailed",
+                "At least one of the pre-conditions you specified did not hold",
+            );
+        }
+    }
+    record.last_modified = Utc::now();
+    if headers.get("x-amz-metadata-directive") == Some("REPLACE") {
+        record.content_type = headers.get("content-type").map(str::to_owned);
+        record.content_encoding = headers.get("content-encoding").map(str::to_owned);
+        record.metadata = headers.metadata();
+    }
+    if headers.get("x-amz-tagging-directive") == Some("REPLACE") {
+        record.tags = headers.tags();
+    }
+    if let Some(storage_class) = headers.get("x-amz-storage-class") {
+        record.storage_class = storage_class.to_owned();
+    }
+    record.sse_customer_algorithm = headers.sse_customer_algorithm();
+    let xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <CopyObjectResult><LastModified>{}</LastModified><ETag>{}</ETag></CopyObjectResult>",
+        record.last_modified.format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+        escape(&record.e_tag)
+    );
+    state
+        .buckets
+        .get_mut(bucket)
+        .unwrap()
+        .insert(key.to_owned(), record);
+    Response::xml(xml)
+}
+
+// Content of an UploadPartCopy source, `range` is `bytes=<first>-<last>`
+fn copy_part(state: &State, source: &str, range: Option<&str>) -> Result<Vec<u8>, Response> {
+    let source = percent_decode_str(source).decode_utf8_lossy();
+    let (source_bucket, source_key) = match source.trim_start_matches('/').split_once('/') {
+        Some(source) => source,
+        None => {
+            return Err(Response::error(
+                400,
+                "InvalidArgument",
+                "Invalid copy source",
+            ))
+        }
+    };
+    let body = match state.buckets.get(source_bucket) {
+        None => return Err(no_such_bucket()),
+        Some(objects) => match objects.get(source_key) {
+            None => return Err(no_such_key()),
+            Some(record) => &record.body,
+        },
+    };
+    let range = match range {
+        None => return Ok(body.clone()),
+        Some(range) => range,
+    };
+    let bounds = range
+        .strip_prefix("bytes=")
+        .and_then(|range| range.split_once('-'))
+        .and_then(|(first, last)| {
+            Some((first.parse::<usize>().ok()?, last.parse::<usize>().ok()?))
+        });
+    match bounds {
+        Some((first, last)) if first <= last && last < body.len() => {
+            Ok(body[first..=last].to_vec())
+        }
+        _ => Err(Response::error(
+            400,
+            "InvalidArgument",
+            "The x-amz-copy-source-range value must be of the form bytes=first-last",
+        )),
+    }
+}
+
+fn tagging_xml(tags: &BTreeMap<String, String>) -> Response {
+    let mut xml = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Tagging><TagSet>".to_owned();
+    for (key, value) in tags {
+        let _ = write!(
+            xml,
+            "<Tag><Key>{}</Key><Value>{}</Value></Tag>",
+            escape(key),
+            escape(value)
+        );
+    }
+    xml.push_str("</TagSet></Tagging>");
+    Response::xml(xml)
+}
+
+fn parse_tagging(body: &[u8]) -> Result<BTreeMap<String, String>, Response> {
+    let body = String::from_utf8_lossy(body);
+    let keys = elements(&body, "Key");
+    let values = elements(&body, "Value");
+    if keys.len() != values.len() {
+        return Err(Response::error(
+            400,
+            "MalformedXML",
+            "The XML you provided was not well-formed",
+        ));
+    }
+    Ok(keys.into_iter().zip(values).collect())
+}
+
+fn object_tagging(record: &mut ObjectRecord, method: &str, body: &[u8]) -> Response {
+    match method {
+        "GET" => tagging_xml(&record.tags),
+        "PUT" => match parse_tagging(body) {
+            Ok(tags) => {
+                record.tags = tags;
+                Response::new(200)
+            }
+            Err(response) => response,
+        },
+        "DELETE" => {
+            record.tags.clear();
+            Response::new(204)
+        }
+        _ => Response::error(501, "NotImplemented", "The request is not supported"),
+    }
+}
+
+fn bucket_tagging(state: &mut State, bucket: &str, method: &str, body: &[u8]) -> Response {
+    if !state.buckets.contains_key(bucket) {
+        return no_such_bucket();
+    }
+    match method {
+        "GET" => match state.bucket_tags.get(bucket) {
+            Some(tags) => tagging_xml(tags),
+            None => Response::error(404, "NoSuchTagSet", "The TagSet does not exist"),
+        },
+        "PUT" => match parse_tagging(body) {
+            Ok(tags) => {
+                state.bucket_tags.insert(bucket.to_owned(), tags);
+                Response::new(204)
+            }
+            Err(response) => response,
+        },
+        "DELETE" => {
+            state.bucket_tags.remove(bucket);
+            Response::new(204)
+        }
+        _ => Response::error(501, "NotImplemented", "The request is not supported"),
+    }
+}
+
+// Bucket subresources whose configuration is kept as uploaded, with the
+// error code and message of getting a missing one
+const BUCKET_CONFIGS: &[(&str, &str, &str)] = &[
+    (
+        "policy",
+        "NoSuchBucketPolicy",
+        "The bucket policy does not exist",
+    ),
+    (
+        "replication",
+        "ReplicationConfigurationNotFoundError",
+        "The replication configuration was not found",
+    ),
+    (
+        "website",
+        "NoSuchWebsiteConfiguration",
+        "The specified bucket does not have a website configuration",
+    ),
+    (
+        "intelligent-tiering",
+        "NoSuchConfiguration",
+        "The specified configuration does not exist.",
+    ),
+];
+
+fn bucket_config(
+    state: &mut State,
+    bucket: &str,
+    method: &str,
+    config: &(&str, &str, &str),
+    id: &str,
+    body: Vec<u8>,
+) -> Response {
+    if !state.buckets.contains_key(bucket) {
+        return no_such_bucket();
+    }
+    let (subresource, code, message) = *config;
+    let id = (bucket.to_owned(), subresource.to_owned(), id.to_owned());
+    match method {
+        "GET" => match state.bucket_configs.get(&id) {
+            Some(document) => Response::new(200).body(document.clone()),
+            None => Response::error(404, code, message),
+        },
+        "PUT" => {
+            state.bucket_configs.insert(id, body);
+            Response::new(200)
+        }
+        "DELETE" => {
+            state.bucket_configs.remove(&id);
+            Response::new(204)
+        }
+        _ => Response::error(501, "NotImplemented", "The request is not supported"),
+    }
+}
+
+fn list_intelligent_tiering(state: &State, bucket: &str) -> Response {
+    if !state.buckets.contains_key(bucket) {
+        return no_such_bucket();
+    }
+    let mut xml = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <ListBucketIntelligentTieringConfigurationsOutput \
+         xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\"><IsTruncated>false</IsTruncated>"
+        .to_owned();
+    for ((name, subresource, _), document) in &state.bucket_configs {
+        if name == bucket && subresource == "intelligent-tiering" {
+            xml.push_str(without_declaration(&String::from_utf8_lossy(document)));
+        }
+    }
+    xml.push_str("</ListBucketIntelligentTieringConfigurationsOutput>");
+    Response::xml(xml)
+}
+
+// Logging is disabled by putting a status without configuration, there's
+// nothing to delete
+fn bucket_logging(state: &mut State, bucket: &str, method: &str, body: Vec<u8>) -> Response {
+    if !state.buckets.contains_key(bucket) {
+        return no_such_bucket();
+    }
+    let id = (bucket.to_owned(), "logging".to_owned(), String::new());
+    match method {
+        "GET" => match state.bucket_configs.get(&id) {
+            Some(document) => Response::new(200).body(document.clone()),
+            None => Response::xml(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <BucketLoggingStatus xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\"/>"
+                    .to_owned(),
+            ),
+        },
+        "PUT" => {
+            state.bucket_configs.insert(id, body);
+            Response::new(200)
+        }
+        _ => Response::error(501, "NotImplemented", "The request is not supported"),
+    }
+}
+
+fn list_multipart_uploads(state: &State, bucket: &str, prefix: &str) -> Response {
+    if !state.buckets.contains_key(bucket) {
+        return no_such_bucket();
+    }
+    let mut uploads: Vec<_> = state
+        .uploads
+        .iter()
+        .filter(|(_, upload)| upload.bucket == bucket && upload.key.starts_with(prefix))
+        .collect();
+    // uploads of the same key are listed in the order they were started
+    uploads.sort_by_key(|(_, upload)| (&upload.key, upload.number));
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <ListMultipartUploadsResult xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">\
+         <Bucket>{}</Bucket><Prefix>{}</Prefix><MaxUploads>1000</MaxUploads>\
+         <IsTruncated>false</IsTruncated>",
+        escape(bucket),
+        escape(prefix)
+    );
+    for (upload_id, upload) in uploads {
+        let _ = write!(
+            xml,
+            "<Upload><Key>{}</Key><UploadId>{}</UploadId><Initiated>{}</Initiated>\
+             <StorageClass>{}</StorageClass></Upload>",
+            escape(&upload.key),
+            escape(upload_id),
+            upload.initiated.format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+            escape(&upload.storage_class)
+        );
+    }
+    xml.push_str("</ListMultipartUploadsResult>");
+    Response::xml(xml)
+}
+
+fn list_parts<'a>(
+    upload: &Upload,
+    upload_id: &str,
+    param: &dyn Fn(&str) -> Option<&'a str>,
+) -> Response {
+    let max_parts: usize = param("max-parts")
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(1000)
+        .max(1);
+    let marker: i64 = param("part-number-marker")
+        .and_then(|n| n.parse().ok())
+        .unwrap_or_default();
+    let parts: Vec<_> = upload.parts.range(marker + 1..).collect();
+    let truncated = parts.len() > max_parts;
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <ListPartsResult xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">\
+         <Bucket>{}</Bucket><Key>{}</Key><UploadId>{}</UploadId>\
+         <PartNumberMarker>{}</PartNumberMarker><MaxParts>{}</MaxParts>\
+         <IsTruncated>{}</IsTruncated>",
+        escape(&upload.bucket),
+        escape(&upload.key),
+        escape(upload_id),
+        marker,
+        max_parts,
+        truncated
+    );
+    for (number, part) in parts.iter().take(max_parts) {
+        let _ = write!(
+            xml,
+            "<Part><PartNumber>{}</PartNumber><LastModified>{}</LastModified>\
+             <ETag>{}</ETag><Size>{}</Size></Part>",
+            number,
+            upload.initiated.format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+            escape(&e_tag(part)),
+            part.len()
+        );
+    }
+    if truncated {
+        let _ = write!(
+            xml,
+            "<NextPartNumberMarker>{}</NextPartNumberMarker>",
+            parts[max_parts - 1].0
+        );
+    }
+    xml.push_str("</ListPartsResult>");
+    Response::xml(xml)
+}
+
+fn complete_multipart_upload(
+    state: &mut State,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+    body: &[u8],
+) -> Response {
+    match state.uploads.get(upload_id) {
+        Some(upload) if upload.bucket == bucket && upload.key == key => {}
+        _ => return no_such_upload(),
+    }
+    let body = String::from_utf8_lossy(body);
+    let part_numbers: Option<Vec<i64>> = elements(&body, "PartNumber")
+        .iter()
+        .map(|n| n.parse().ok())
+        .collect();
+    let part_numbers = match part_numbers {
+        Some(numbers) if !numbers.is_empty() => numbers,
+        _ => {
+            return Response::error(
+                400,
+                "MalformedXML",
+                "The XML you provided was not well-formed",
+            )
+        }
+    };
+    let upload = &state.uploads[upload_id];
+    let mut content = Vec::new();
+    let mut digests = Vec::new();
+    let mut part_sizes = Vec::new();
+    for (i, number) in part_numbers.iter().enumerate() {
+        let part = match upload.parts.get(number) {
+            Some(part) => part,
+            None => {
+                return Response::error(
+                    400,
+                    "InvalidPart",
+                    "One or more of the specified parts could not be found.",
+                )
+            }
+        };
+        if i + 1 < part_numbers.len() && part.len() < MIN_PART_SIZE {
+            return Response::error(
+                400,
+                "EntityTooSmall",
+                "Your proposed upload is smaller than the minimum allowed object size.",
+            );
+        }
+        content.extend_from_slice(part);
+        digests.extend_from_slice(&Md5::digest(part));
+        part_sizes.push(part.len());
+    }
+    let upload = state.uploads.remove(upload_id).unwrap();
+    let e_tag = format!(
+        "\"{}-{}\"",
+        hex::encode(Md5::digest(&digests)),
+        part_numbers.len()
+    );
+    let objects = match state.buckets.get_mut(bucket) {
+        Some(objects) => objects,
+        None => return no_such_bucket(),
+    };
+    objects.insert(
+        key.to_owned(),
+        ObjectRecord {
+            body: content,
+            e_tag: e_tag.clone(),
+            last_modified: Utc::now(),
+            content_type: upload.content_type,
+            content_encoding: upload.content_encoding,
+            metadata: upload.metadata,
+            tags: upload.tags,
+            storage_class: upload.storage_class,
+            part_sizes,
+            sse_customer_algorithm: upload.sse_customer_algorithm,
+        },
+    );
+    Response::xml(format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <CompleteMultipartUploadResult><Location>{}/{}/{}</Location>\
+         <Bucket>{}</Bucket><Key>{}</Key><ETag>{}</ETag></CompleteMultipartUploadResult>",
+        ENDPOINT,
+        escape(bucket),
+        escape(key),
+        escape(bucket),
+        escape(key),
+        escape(&e_tag)
+    ))
+}
+
+fn e_tag(body: &[u8]) -> String {
+    format!("\"{}\"", hex::encode(Md5::digest(body)))
+}
+
+fn http_date(time: &DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+// `document` without its XML declaration, to be embedded in another one
+fn without_declaration(document: &str) -> &str {
+    match document.trim_start().strip_prefix("<?xml") {
+        Some(rest) => rest.split_once("?>").map_or("", |(_, rest)| rest),
+        None => document,
+    }
+}
+
+// Text of all elements named `name`, good enough for the flat documents
+// sent by rusoto
+fn elements(xml: &str, name: &str) -> Vec<String> {
+    let open = format!("<{}>", name);
+    let close = format!("</{}>", name);
+    let mut values = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        match rest.find(&close) {
+            Some(end) => {
+                values.push(unescape(&rest[..end]));
+                rest = &rest[end + close.len()..];
+            }
+            None => break,
+        }
+    }
+    values
+}
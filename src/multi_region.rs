@@ -0,0 +1,415 @@
+//! Route requests to the region a bucket is located in
+//!
+//! Requests for a bucket sent to the wrong region fail with a
+//! `301 PermanentRedirect`. [`MultiRegionClient`] looks up the region of each
+//! bucket using `GetBucketLocation` and sends requests to a client for that
+//! region instead.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rusoto_core::Region;
+//! use s3_ext::{client::S3ExtClientBuilder, multi_region::MultiRegionClient, S3Ext};
+//!
+//! # async fn example() -> Result<(), s3_ext::error::S3ExtError> {
+//! let client = MultiRegionClient::new(S3ExtClientBuilder::new().region(Region::UsEast1))?;
+//! // streams can't wait for the lookup, resolve the region beforehand
+//! client.region_of("bucket-in-eu-central-1").await?;
+//! let objects = client.stream_objects("bucket-in-eu-central-1");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    body::ObjectBody,
+    client::S3ExtClientBuilder,
+    compose::ComposePart,
+    diff::DiffStream,
+    error::{S3ExtError, S3ExtResult},
+    framed::FramedObject,
+    iter::{GetObjectStream, ObjectStream, SortedObjectStream},
+    lines::LineStream,
+    metadata::ObjectMetadata,
+    part_size::PartSize,
+    watch::WatchStream,
+    S3Ext,
+};
+use async_trait::async_trait;
+use log::debug;
+use parking_lot::Mutex;
+use rusoto_core::Region;
+use rusoto_s3::{
+    CompleteMultipartUploadOutput, GetBucketLocationRequest, GetObjectOutput, GetObjectRequest,
+    PutObjectOutput, PutObjectRequest, S3Client, S3,
+};
+use std::{collections::HashMap, convert::AsRef, future::Future, path::Path, time::Duration};
+use tokio::io;
+use tokio_util::codec::Decoder;
+
+/// Client sending requests to the region of the bucket
+///
+/// Regions and per-region clients are cached for the lifetime of the client.
+///
+/// # Caveats
+///
+/// * Streams are created synchronously and can't look up the region, they
+///   use the region of buckets resolved earlier (see
+///   [`region_of`](Self::region_of)) and the default region otherwise.
+/// * Requests aren't routed if the builder is configured with a custom
+///   endpoint since the endpoint of other regions is unknown.
+pub struct MultiRegionClient {
+    builder: S3ExtClientBuilder,
+    default: S3Client,
+    regions: Mutex<HashMap<String, Region>>,
+    clients: Mutex<HashMap<String, S3Client>>,
+}
+
+impl MultiRegionClient {
+    /// Create clients using `builder`, the region configured on `builder` is
+    /// used for looking up bucket locations
+    pub fn new(builder: S3ExtClientBuilder) -> S3ExtResult<Self> {
+        let default = builder.clone().build()?;
+        Ok(Self {
+            builder,
+            default,
+            regions: Mutex::new(HashMap::new()),
+            clients: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Region `bucket` is located in
+    pub async fn region_of(&self, bucket: &str) -> S3ExtResult<Region> {
+        if let region @ Region::Custom { .. } = self.builder.get_region() {
+            return Ok(region.clone());
+        }
+        if let Some(region) = self.regions.lock().get(bucket) {
+            return Ok(region.clone());
+        }
+        let location = self
+            .default
+            .get_bucket_location(GetBucketLocationRequest {
+                bucket: bucket.to_owned(),
+                ..Default::default()
+            })
+            .await?;
+        let region = match location.location_constraint.as_deref() {
+            // buckets in us-east-1 have no location constraint
+            None | Some("") => Region::UsEast1,
+            // legacy name of eu-west-1
+            Some("EU") => Region::EuWest1,
+            Some(name) => name
+                .parse()
+                .map_err(|_| S3ExtError::Other("unknown bucket location"))?,
+        };
+        debug!("bucket {} is located in {}", bucket, region.name());
+        self.regions
+            .lock()
+            .insert(bucket.to_owned(), region.clone());
+        Ok(region)
+    }
+
+    /// Client sending requests to the region `bucket` is located in
+    pub async fn client_for(&self, bucket: &str) -> S3ExtResult<S3Client> {
+        let region = self.region_of(bucket).await?;
+        self.client_for_region(region)
+    }
+
+    fn client_for_region(&self, region: Region) -> S3ExtResult<S3Client> {
+        if &region == self.builder.get_region() {
+            return Ok(self.default.clone());
+        }
+        let mut clients = self.clients.lock();
+        if let Some(client) = clients.get(region.name()) {
+            return Ok(client.clone());
+        }
+        let name = region.name().to_owned();
+        let client = self.builder.clone().region(region).build()?;
+        clients.insert(name, client.clone());
+        Ok(client)
+    }
+
+    // Client for a bucket without looking up its region
+    fn cached_client_for(&self, bucket: &str) -> S3Client {
+        let region = self.regions.lock().get(bucket).cloned();
+        region
+            .and_then(|region| self.client_for_region(region).ok())
+            .unwrap_or_else(|| self.default.clone())
+    }
+}
+
+#[async_trait]
+impl S3Ext for MultiRegionClient {
+    async fn send_request<T, F, Fut>(
+        &self,
+        _operation: &'static str,
+        bucket: String,
+        key: String,
+        request: F,
+    ) -> S3ExtResult<T>
+    where
+        T: Send,
+        F: Fn(Backend, String, String) -> Fut + Send + Sync,
+        Fut: Future<Output = S3ExtResult<T>> + Send,
+    {
+        let client = self.client_for(&bucket).await?;
+        request(Backend::from(&client), bucket, key).await
+    }
+
+    async fn download_to_file<F>(
+        &self,
+        source: GetObjectRequest,
+        target: F,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        let client = self.client_for(&source.bucket).await?;
+        client.download_to_file(source, target).await
+    }
+
+    async fn upload_from_file<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        let client = self.client_for(&target.bucket).await?;
+        client.upload_from_file(source, target).await
+    }
+
+    async fn upload_from_file_multipart_with<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        let client = self.client_for(&target.bucket).await?;
+        client
+            .upload_from_file_multipart_with(source, target, part_size)
+            .await
+    }
+
+    async fn download<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        let client = self.client_for(&source.bucket).await?;
+        client.download(source, target).await
+    }
+
+    async fn download_decompressed<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        let client = self.client_for(&source.bucket).await?;
+        client.download_decompressed(source, target).await
+    }
+
+    async fn get_framed<D>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        decoder: D,
+    ) -> S3ExtResult<FramedObject<D>>
+    where
+        D: Decoder + Send,
+    {
+        let bucket = bucket.into();
+        let client = self.client_for(&bucket).await?;
+        client.get_framed(bucket, key, decoder).await
+    }
+
+    async fn get_body_with(&self, source: GetObjectRequest) -> S3ExtResult<ObjectBody> {
+        let client = self.client_for(&source.bucket).await?;
+        client.get_body_with(source).await
+    }
+
+    async fn upload<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        let client = self.client_for(&target.bucket).await?;
+        client.upload(source, target).await
+    }
+
+    async fn upload_multipart_with<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        let client = self.client_for(&target.bucket).await?;
+        client
+            .upload_multipart_with(source, target, part_size)
+            .await
+    }
+
+    async fn upload_multipart_resumable<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        let client = self.client_for(&target.bucket).await?;
+        client
+            .upload_multipart_resumable(source, target, part_size)
+            .await
+    }
+
+    fn stream_objects(&self, bucket: impl Into<String>) -> ObjectStream {
+        let bucket = bucket.into();
+        self.cached_client_for(&bucket).stream_objects(bucket)
+    }
+
+    fn stream_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> ObjectStream {
+        let bucket = bucket.into();
+        self.cached_client_for(&bucket)
+            .stream_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_objects_sorted_by_mtime(&self, bucket: impl Into<String>) -> SortedObjectStream {
+        self.stream_objects(bucket).sorted_by_mtime()
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix_sorted_by_mtime(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> SortedObjectStream {
+        self.stream_objects_with_prefix(bucket, prefix)
+            .sorted_by_mtime()
+    }
+
+    fn diff(
+        &self,
+        source_bucket: impl Into<String>,
+        source_prefix: impl Into<String>,
+        dest_bucket: impl Into<String>,
+        dest_prefix: impl Into<String>,
+    ) -> DiffStream {
+        let source_prefix = source_prefix.into();
+        let dest_prefix = dest_prefix.into();
+        DiffStream::new(
+            self.stream_objects_with_prefix(source_bucket, &source_prefix),
+            source_prefix,
+            self.stream_objects_with_prefix(dest_bucket, &dest_prefix),
+            dest_prefix,
+        )
+    }
+
+    fn watch_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        period: Duration,
+    ) -> WatchStream {
+        let bucket = bucket.into();
+        self.cached_client_for(&bucket)
+            .watch_prefix(bucket, prefix, period)
+    }
+
+    fn stream_get_objects(&self, bucket: impl Into<String>) -> GetObjectStream {
+        let bucket = bucket.into();
+        self.cached_client_for(&bucket).stream_get_objects(bucket)
+    }
+
+    fn stream_get_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> GetObjectStream {
+        let bucket = bucket.into();
+        self.cached_client_for(&bucket)
+            .stream_get_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_lines(&self, bucket: impl Into<String>, key: impl Into<String>) -> LineStream {
+        let bucket = bucket.into();
+        self.cached_client_for(&bucket).stream_lines(bucket, key)
+    }
+
+    async fn delete(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        let bucket = bucket.into();
+        let client = self.client_for(&bucket).await?;
+        client.delete(bucket, key).await
+    }
+
+    async fn compose_object(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        parts: Vec<ComposePart>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput> {
+        let bucket = bucket.into();
+        let client = self.client_for(&bucket).await?;
+        client.compose_object(bucket, key, parts).await
+    }
+
+    async fn object_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectMetadata> {
+        let bucket = bucket.into();
+        let client = self.client_for(&bucket).await?;
+        client.object_metadata(bucket, key).await
+    }
+
+    async fn replace_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        metadata: ObjectMetadata,
+    ) -> S3ExtResult<()> {
+        let bucket = bucket.into();
+        let client = self.client_for(&bucket).await?;
+        client.replace_metadata(bucket, key, metadata).await
+    }
+
+    async fn copy(
+        &self,
+        source_bucket: impl Into<String> + Send,
+        source_key: impl Into<String> + Send,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        let bucket = bucket.into();
+        let client = self.client_for(&bucket).await?;
+        client.copy(source_bucket, source_key, bucket, key).await
+    }
+}
@@ -0,0 +1,154 @@
+//! Size of the parts of multipart uploads
+//!
+//! S3 requires all parts but the last to be at least 5 MiB, parts to be at
+//! most 5 GiB and uploads to consist of at most 10,000 parts. Within these
+//! limits, small parts keep the latency of small uploads low, while large
+//! parts reduce the number of requests of large ones. Rather than tuning a
+//! fixed size, [`PartSize::Auto`] picks the smallest size fitting the object
+//! into 10,000 parts and [`PartSize::Adaptive`] starts with 5 MiB parts,
+//! growing them according to the measured throughput.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use rusoto_s3::PutObjectRequest;
+//! use s3_ext::{part_size::PartSize, S3Ext};
+//!
+//! let request = PutObjectRequest {
+//!     bucket: "bucket".to_owned(),
+//!     key: "dump.sql".to_owned(),
+//!     ..Default::default()
+//! };
+//! let part_size = PartSize::Adaptive {
+//!     max: 256 * 1024 * 1024,
+//! };
+//! client
+//!     .upload_from_file_multipart_with("/var/backups/dump.sql", request, part_size)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::S3ExtResult,
+    upload::{validate_part_size, MAX_PARTS, MIN_PART_SIZE},
+};
+use std::time::Duration;
+
+/// Time the upload of an adaptively sized part should take, 5 seconds
+pub const ADAPTIVE_PART_DURATION: Duration = Duration::from_secs(5);
+
+// Adaptive part sizes are rounded up to multiples of 1 MiB
+const MIB: usize = 1024 * 1024;
+
+/// Size of the parts of a multipart upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartSize {
+    /// Parts of the given number of bytes
+    Fixed(usize),
+    /// Parts of the smallest size fitting the object into 10,000 parts, at
+    /// least 5 MiB
+    ///
+    /// The size of the object is known when uploading files. Otherwise,
+    /// parts start at 5 MiB and double every 1,000 parts, fitting objects of
+    /// up to 5 TiB.
+    Auto,
+    /// Parts starting at 5 MiB, growing up to `max` bytes
+    ///
+    /// After every part, the following parts are grown to the size uploaded
+    /// within [`ADAPTIVE_PART_DURATION`] at the throughput measured so far.
+    /// Parts never shrink. They also grow as needed to fit the object into
+    /// 10,000 parts: if the size of the object is unknown, parts double
+    /// every 1,000 parts.
+    Adaptive {
+        /// Largest part size, between 5 MiB and 5 GiB
+        max: usize,
+    },
+}
+
+impl From<usize> for PartSize {
+    fn from(part_size: usize) -> Self {
+        PartSize::Fixed(part_size)
+    }
+}
+
+/// Size of the next part of an upload of `size` bytes, if known
+#[derive(Debug)]
+pub(crate) struct PartSizer {
+    part_size: PartSize,
+    size: Option<u64>,
+    current: usize,
+    parts: usize,
+    bytes: u64,
+    elapsed: Duration,
+}
+
+impl PartSizer {
+    /// Fails with `S3ExtError::InvalidPartSize` if the parts don't meet the
+    /// limits of S3
+    pub(crate) fn new(part_size: PartSize, size: Option<u64>) -> S3ExtResult<Self> {
+        let current = match part_size {
+            PartSize::Fixed(part_size) => {
+                validate_part_size(part_size, size)?;
+                part_size
+            }
+            PartSize::Auto => {
+                let needed = size.map_or(0, |size| size.saturating_sub(1) / MAX_PARTS as u64 + 1);
+                validate_part_size(MIN_PART_SIZE.max(needed as usize), size)?;
+                MIN_PART_SIZE
+            }
+            PartSize::Adaptive { max } => {
+                validate_part_size(max, size)?;
+                MIN_PART_SIZE
+            }
+        };
+        let mut sizer = PartSizer {
+            part_size,
+            size,
+            current,
+            parts: 0,
+            bytes: 0,
+            elapsed: Duration::ZERO,
+        };
+        sizer.grow();
+        Ok(sizer)
+    }
+
+    /// Size of the next part
+    pub(crate) fn next(&self) -> usize {
+        self.current
+    }
+
+    /// Record a part of `bytes` uploaded within `elapsed`
+    pub(crate) fn record(&mut self, bytes: usize, elapsed: Duration) {
+        self.parts += 1;
+        self.bytes += bytes as u64;
+        self.elapsed += elapsed;
+        self.grow();
+    }
+
+    fn grow(&mut self) {
+        let needed = match self.size {
+            Some(size) => {
+                let remaining = size.saturating_sub(self.bytes);
+                let parts = MAX_PARTS.saturating_sub(self.parts).max(1) as u64;
+                ((remaining + parts - 1) / parts) as usize
+            }
+            None => MIN_PART_SIZE << (self.parts / 1000).min(10),
+        };
+        self.current = match self.part_size {
+            PartSize::Fixed(part_size) => part_size,
+            PartSize::Auto => self.current.max(needed),
+            PartSize::Adaptive { max } => {
+                let mut target = self.current.max(needed);
+                if !self.elapsed.is_zero() {
+                    let throughput = self.bytes as f64 / self.elapsed.as_secs_f64();
+                    let duration = ADAPTIVE_PART_DURATION.as_secs_f64();
+                    target = target.max((throughput * duration) as usize);
+                }
+                ((target + MIB - 1) / MIB * MIB).min(max)
+            }
+        };
+    }
+}
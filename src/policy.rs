@@ -0,0 +1,433 @@
+//! Bucket policies as typed documents
+//!
+//! See the methods
+//! [`get_bucket_policy`](crate::bucket_config::BucketConfigExt::get_bucket_policy),
+//! [`put_bucket_policy`](crate::bucket_config::BucketConfigExt::put_bucket_policy) and
+//! [`delete_bucket_policy`](crate::bucket_config::BucketConfigExt::delete_bucket_policy) of
+//! [`BucketConfigExt`](crate::bucket_config::BucketConfigExt).
+//!
+//! Only the common elements of a statement are modelled, others such as
+//! `NotAction` are kept as JSON in [`Statement::other`] and written back
+//! unchanged.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::{
+//!     bucket_config::BucketConfigExt,
+//!     policy::{BucketPolicy, Principal, Statement},
+//! };
+//!
+//! let policy = BucketPolicy::new()
+//!     .statement(Statement::deny_insecure_transport("bucket"))
+//!     .statement(Statement::allow_read(
+//!         "bucket",
+//!         Principal::aws("arn:aws:iam::123456789012:root"),
+//!     ));
+//! client.put_bucket_policy("bucket", &policy).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    error::{S3ExtError, S3ExtResult},
+};
+use rusoto_s3::{DeleteBucketPolicyRequest, GetBucketPolicyRequest, PutBucketPolicyRequest};
+use serde_json::{json, Map, Value};
+use std::{fmt, str::FromStr};
+
+/// Current version of the policy language
+pub const VERSION: &str = "2012-10-17";
+
+/// Service principal delivering server access logs
+pub const LOGGING_SERVICE: &str = "logging.s3.amazonaws.com";
+
+/// Effect of a statement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    /// Allow the actions
+    Allow,
+    /// Deny the actions, overrides any `Allow`
+    Deny,
+}
+
+impl Effect {
+    /// Name of the effect as used in policies
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Effect::Allow => "Allow",
+            Effect::Deny => "Deny",
+        }
+    }
+}
+
+/// Principal a statement applies to
+#[derive(Debug, Clone, PartialEq)]
+pub enum Principal {
+    /// Everybody (`"*"`)
+    Any,
+    /// AWS accounts, users or roles, by ARN or account ID
+    Aws(Vec<String>),
+    /// AWS services, e.g. `logging.s3.amazonaws.com`
+    Service(Vec<String>),
+    /// Any other principal, e.g. a combination of the above
+    Other(Value),
+}
+
+impl Principal {
+    /// Single AWS account, user or role
+    pub fn aws(arn: impl Into<String>) -> Self {
+        Principal::Aws(vec![arn.into()])
+    }
+
+    /// Single AWS service
+    pub fn service(service: impl Into<String>) -> Self {
+        Principal::Service(vec![service.into()])
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            Principal::Any => json!("*"),
+            Principal::Aws(arns) => json!({ "AWS": one_or_many(arns) }),
+            Principal::Service(services) => json!({ "Service": one_or_many(services) }),
+            Principal::Other(value) => value.clone(),
+        }
+    }
+
+    fn from_json(value: Value) -> Self {
+        if value == "*" {
+            return Principal::Any;
+        }
+        if let Value::Object(map) = &value {
+            if map.len() == 1 {
+                let (kind, ids) = map.iter().next().unwrap();
+                match (kind.as_str(), string_list(ids)) {
+                    ("AWS", Some(ids)) => return Principal::Aws(ids),
+                    ("Service", Some(ids)) => return Principal::Service(ids),
+                    _ => (),
+                }
+            }
+        }
+        Principal::Other(value)
+    }
+}
+
+/// Statement of a bucket policy
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    /// Statement ID
+    pub sid: Option<String>,
+    /// Whether the actions are allowed or denied
+    pub effect: Effect,
+    /// Principal the statement applies to, `None` if the statement uses
+    /// `NotPrincipal`
+    pub principal: Option<Principal>,
+    /// Actions, e.g. `s3:GetObject`
+    pub actions: Vec<String>,
+    /// ARNs of the buckets and objects
+    pub resources: Vec<String>,
+    /// Conditions, as JSON
+    pub condition: Option<Value>,
+    /// Elements not modelled above
+    pub other: Map<String, Value>,
+}
+
+impl Statement {
+    /// Statement with `effect` for `principal`, without any actions or
+    /// resources
+    pub fn new(effect: Effect, principal: Principal) -> Self {
+        Statement {
+            sid: None,
+            effect,
+            principal: Some(principal),
+            actions: Vec::new(),
+            resources: Vec::new(),
+            condition: None,
+            other: Map::new(),
+        }
+    }
+
+    /// Deny all requests to `bucket` not sent using TLS
+    pub fn deny_insecure_transport(bucket: &str) -> Self {
+        Statement::new(Effect::Deny, Principal::Any)
+            .sid("DenyInsecureTransport")
+            .action("s3:*")
+            .resource(bucket_arn(bucket))
+            .resource(objects_arn(bucket, "*"))
+            .condition(json!({ "Bool": { "aws:SecureTransport": "false" } }))
+    }
+
+    /// Allow `principal` to read all objects in `bucket`
+    pub fn allow_read(bucket: &str, principal: Principal) -> Self {
+        Statement::new(Effect::Allow, principal)
+            .sid("AllowRead")
+            .action("s3:GetObject")
+            .resource(objects_arn(bucket, "*"))
+    }
+
+    /// Allow `principal` to list and read all objects in `bucket`
+    pub fn allow_list_and_read(bucket: &str, principal: Principal) -> Self {
+        Statement::allow_read(bucket, principal)
+            .sid("AllowListAndRead")
+            .action("s3:ListBucket")
+            .resource(bucket_arn(bucket))
+    }
+
+    /// Allow the S3 logging service to write the server access logs of
+    /// `source_bucket` below `prefix` in `bucket`
+    pub fn allow_log_delivery(bucket: &str, prefix: &str, source_bucket: &str) -> Self {
+        Statement::new(Effect::Allow, Principal::service(LOGGING_SERVICE))
+            .sid("S3ServerAccessLogsPolicy")
+            .action("s3:PutObject")
+            .resource(objects_arn(bucket, &format!("{}*", prefix)))
+            .condition(json!({ "ArnLike": { "aws:SourceArn": bucket_arn(source_bucket) } }))
+    }
+
+    /// Set the statement ID
+    pub fn sid(mut self, sid: impl Into<String>) -> Self {
+        self.sid = Some(sid.into());
+        self
+    }
+
+    /// Add `action`
+    pub fn action(mut self, action: impl Into<String>) -> Self {
+        self.actions.push(action.into());
+        self
+    }
+
+    /// Add `resource`
+    pub fn resource(mut self, resource: impl Into<String>) -> Self {
+        self.resources.push(resource.into());
+        self
+    }
+
+    /// Set the conditions
+    pub fn condition(mut self, condition: Value) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    fn to_json(&self) -> Value {
+        let mut map = self.other.clone();
+        if let Some(sid) = &self.sid {
+            map.insert("Sid".to_owned(), json!(sid));
+        }
+        map.insert("Effect".to_owned(), json!(self.effect.as_str()));
+        if let Some(principal) = &self.principal {
+            map.insert("Principal".to_owned(), principal.to_json());
+        }
+        if !self.actions.is_empty() {
+            map.insert("Action".to_owned(), one_or_many(&self.actions));
+        }
+        if !self.resources.is_empty() {
+            map.insert("Resource".to_owned(), one_or_many(&self.resources));
+        }
+        if let Some(condition) = &self.condition {
+            map.insert("Condition".to_owned(), condition.clone());
+        }
+        Value::Object(map)
+    }
+
+    fn from_json(value: Value) -> S3ExtResult<Self> {
+        let mut map = match value {
+            Value::Object(map) => map,
+            _ => return Err(invalid("statement is not an object")),
+        };
+        let sid = match map.remove("Sid") {
+            Some(Value::String(sid)) => Some(sid),
+            None => None,
+            Some(_) => return Err(invalid("Sid is not a string")),
+        };
+        let effect = match map.remove("Effect") {
+            Some(effect) if effect == "Allow" => Effect::Allow,
+            Some(effect) if effect == "Deny" => Effect::Deny,
+            _ => return Err(invalid("Effect is missing or invalid")),
+        };
+        let mut list = |name: &str| match map.remove(name) {
+            None => Ok(Vec::new()),
+            Some(value) => string_list(&value)
+                .ok_or_else(|| invalid(&format!("{} is not a string or list", name))),
+        };
+        let actions = list("Action")?;
+        let resources = list("Resource")?;
+        Ok(Statement {
+            sid,
+            effect,
+            principal: map.remove("Principal").map(Principal::from_json),
+            actions,
+            resources,
+            condition: map.remove("Condition"),
+            other: map,
+        })
+    }
+}
+
+/// Bucket policy document
+#[derive(Debug, Clone, PartialEq)]
+pub struct BucketPolicy {
+    /// Version of the policy language, usually [`VERSION`]
+    pub version: String,
+    /// Policy ID
+    pub id: Option<String>,
+    /// Statements
+    pub statements: Vec<Statement>,
+}
+
+impl Default for BucketPolicy {
+    fn default() -> Self {
+        BucketPolicy {
+            version: VERSION.to_owned(),
+            id: None,
+            statements: Vec::new(),
+        }
+    }
+}
+
+impl BucketPolicy {
+    /// Empty policy of the current version
+    pub fn new() -> Self {
+        BucketPolicy::default()
+    }
+
+    /// Add `statement`
+    pub fn statement(mut self, statement: Statement) -> Self {
+        self.statements.push(statement);
+        self
+    }
+
+    /// Policy as JSON document
+    pub fn to_json(&self) -> Value {
+        let mut map = Map::new();
+        map.insert("Version".to_owned(), json!(self.version));
+        if let Some(id) = &self.id {
+            map.insert("Id".to_owned(), json!(id));
+        }
+        map.insert(
+            "Statement".to_owned(),
+            Value::Array(self.statements.iter().map(Statement::to_json).collect()),
+        );
+        Value::Object(map)
+    }
+
+    /// Parse a JSON policy document
+    pub fn from_json(value: Value) -> S3ExtResult<Self> {
+        let mut map = match value {
+            Value::Object(map) => map,
+            _ => return Err(invalid("policy is not an object")),
+        };
+        let version = match map.remove("Version") {
+            Some(Value::String(version)) => version,
+            None => VERSION.to_owned(),
+            Some(_) => return Err(invalid("Version is not a string")),
+        };
+        let id = match map.remove("Id") {
+            Some(Value::String(id)) => Some(id),
+            None => None,
+            Some(_) => return Err(invalid("Id is not a string")),
+        };
+        let statements = match map.remove("Statement") {
+            Some(Value::Array(statements)) => statements,
+            Some(statement @ Value::Object(_)) => vec![statement],
+            _ => return Err(invalid("Statement is missing or invalid")),
+        };
+        Ok(BucketPolicy {
+            version,
+            id,
+            statements: statements
+                .into_iter()
+                .map(Statement::from_json)
+                .collect::<S3ExtResult<_>>()?,
+        })
+    }
+}
+
+impl FromStr for BucketPolicy {
+    type Err = S3ExtError;
+
+    fn from_str(s: &str) -> S3ExtResult<Self> {
+        let value = serde_json::from_str(s).map_err(|e| invalid(&e.to_string()))?;
+        BucketPolicy::from_json(value)
+    }
+}
+
+impl fmt::Display for BucketPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_json())
+    }
+}
+
+/// ARN of `bucket`
+pub fn bucket_arn(bucket: &str) -> String {
+    format!("arn:aws:s3:::{}", bucket)
+}
+
+/// ARN of the objects in `bucket` matching `pattern`, e.g. `logs/*`
+pub fn objects_arn(bucket: &str, pattern: &str) -> String {
+    format!("arn:aws:s3:::{}/{}", bucket, pattern)
+}
+
+fn invalid(reason: &str) -> S3ExtError {
+    S3ExtError::InvalidPolicy(reason.to_owned())
+}
+
+// Policies allow a single string in place of a list
+fn one_or_many(values: &[String]) -> Value {
+    match values {
+        [value] => json!(value),
+        values => json!(values),
+    }
+}
+
+fn string_list(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::String(s) => Some(vec![s.clone()]),
+        Value::Array(values) => values
+            .iter()
+            .map(|value| value.as_str().map(str::to_owned))
+            .collect(),
+        _ => None,
+    }
+}
+
+pub(crate) async fn get_bucket_policy(
+    client: &Backend,
+    bucket: String,
+) -> S3ExtResult<Option<BucketPolicy>> {
+    let request = GetBucketPolicyRequest {
+        bucket,
+        ..Default::default()
+    };
+    match client.get_bucket_policy(request).await {
+        Ok(output) => output.policy.map(|policy| policy.parse()).transpose(),
+        Err(e) => {
+            let e = S3ExtError::from(e);
+            match e.code() {
+                Some("NoSuchBucketPolicy") => Ok(None),
+                _ => Err(e),
+            }
+        }
+    }
+}
+
+pub(crate) async fn put_bucket_policy(
+    client: &Backend,
+    bucket: String,
+    policy: &BucketPolicy,
+) -> S3ExtResult<()> {
+    let request = PutBucketPolicyRequest {
+        bucket,
+        policy: policy.to_string(),
+        ..Default::default()
+    };
+    Ok(client.put_bucket_policy(request).await?)
+}
+
+pub(crate) async fn delete_bucket_policy(client: &Backend, bucket: String) -> S3ExtResult<()> {
+    let request = DeleteBucketPolicyRequest {
+        bucket,
+        ..Default::default()
+    };
+    Ok(client.delete_bucket_policy(request).await?)
+}
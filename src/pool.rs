@@ -0,0 +1,73 @@
+//! Reuse of the buffers holding parts of multipart uploads
+//!
+//! Every part of a multipart upload is read into memory before it is sent.
+//! Rather than allocating a buffer of up to 5 GiB per part, buffers are
+//! returned to a process-wide pool once S3 received the part and reused by
+//! the following parts of this or concurrent uploads. The pool keeps a
+//! buffer per CPU at most, idle buffers take up to 256 MiB in total.
+
+use bytes::{Bytes, BytesMut};
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::thread;
+
+// Total capacity of idle buffers kept
+const MAX_IDLE_BYTES: usize = 256 * 1024 * 1024;
+
+lazy_static! {
+    pub(crate) static ref PART_BUFFERS: BufferPool = BufferPool::new(
+        thread::available_parallelism().map_or(1, |n| n.get()),
+        MAX_IDLE_BYTES,
+    );
+}
+
+/// Pool of idle buffers
+pub(crate) struct BufferPool {
+    idle: Mutex<Vec<BytesMut>>,
+    max_buffers: usize,
+    max_bytes: usize,
+}
+
+impl BufferPool {
+    fn new(max_buffers: usize, max_bytes: usize) -> Self {
+        BufferPool {
+            idle: Mutex::new(Vec::new()),
+            max_buffers,
+            max_bytes,
+        }
+    }
+
+    /// Empty buffer with room for `len` bytes, reusing an idle one if
+    /// available
+    pub(crate) fn get(&self, len: usize) -> BytesMut {
+        let buffer = {
+            let mut idle = self.idle.lock();
+            // prefer a buffer not needing to grow
+            match idle.iter().position(|buffer| buffer.capacity() >= len) {
+                Some(i) => Some(idle.swap_remove(i)),
+                None => idle.pop(),
+            }
+        };
+        let mut buffer = buffer.unwrap_or_default();
+        // not zeroed, the reader fills the buffer up to `len`
+        buffer.clear();
+        buffer.reserve(len);
+        buffer
+    }
+
+    /// Return the buffer of `bytes` to the pool
+    ///
+    /// Dropped if other references to it are alive or the pool is full.
+    pub(crate) fn put(&self, bytes: Bytes) {
+        let mut buffer = match bytes.try_into_mut() {
+            Ok(buffer) => buffer,
+            Err(_) => return,
+        };
+        buffer.clear();
+        let mut idle = self.idle.lock();
+        let idle_bytes: usize = idle.iter().map(BytesMut::capacity).sum();
+        if idle.len() < self.max_buffers && idle_bytes + buffer.capacity() <= self.max_bytes {
+            idle.push(buffer);
+        }
+    }
+}
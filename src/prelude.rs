@@ -0,0 +1,38 @@
+//! Types needed by most code using this crate, in one import
+//!
+//! ```no_run
+//! use s3_ext::prelude::*;
+//!
+//! # async fn example() -> S3ExtResult<()> {
+//! let client = S3Client::new(Region::EuWest1);
+//! let request = GetObjectRequest {
+//!     bucket: "bucket".to_owned(),
+//!     key: "report.csv".to_owned(),
+//!     ..Default::default()
+//! };
+//! client.download_to_file(request, "report.csv").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Rusoto's `S3` trait isn't included: several of its methods share their
+//! names with methods of [`S3Ext`] and [`BucketConfigExt`], calls to those
+//! would be ambiguous with both traits in scope. Import it separately where
+//! the raw operations are needed.
+
+pub use crate::{
+    body::{BodyStream, ObjectBody},
+    bucket_config::BucketConfigExt,
+    client::{Credentials, S3ExtClientBuilder},
+    diff::DiffStream,
+    error::{S3ExtError, S3ExtResult},
+    iter::{GetObjectStream, ObjectStream, SortedObjectStream},
+    lines::LineStream,
+    part_size::PartSize,
+    watch::WatchStream,
+    S3Ext,
+};
+pub use rusoto_core::Region;
+pub use rusoto_s3::{
+    GetObjectOutput, GetObjectRequest, PutObjectOutput, PutObjectRequest, S3Client,
+};
@@ -0,0 +1,344 @@
+//! Presigned URLs
+//!
+//! Presigned URLs allow third parties, e.g. a browser, to access objects
+//! without holding any credentials. Signing happens locally using Rusoto's
+//! [`PreSignedRequest`], no request is sent to S3.
+//!
+//! S3 rejects presigned requests valid for more than seven days
+//! ([`MAX_EXPIRES_IN`]), longer durations fail with
+//! [`S3ExtError::InvalidExpiry`].
+//!
+//! # Example
+//!
+//! ```
+//! use rusoto_core::Region;
+//! use rusoto_credential::AwsCredentials;
+//! use s3_ext::presign::{presign_put, PresignPutOptions};
+//! use std::time::Duration;
+//!
+//! # fn main() -> Result<(), s3_ext::error::S3ExtError> {
+//! let credentials = AwsCredentials::new("ACCESS_KEY", "SECRET_KEY", None, None);
+//! let url = presign_put(
+//!     &Region::EuWest1,
+//!     &credentials,
+//!     "bucket",
+//!     "uploads/image.png",
+//!     Duration::from_secs(300),
+//!     &PresignPutOptions {
+//!         storage_class: Some("STANDARD_IA".to_owned()),
+//!         ..Default::default()
+//!     },
+//! )?;
+//! assert!(url.contains("X-Amz-Signature="));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{S3ExtError, S3ExtResult};
+use chrono::Utc;
+use hmac::{Hmac, Mac, NewMac};
+use rusoto_core::{signature::SignedRequest, Region};
+use rusoto_credential::AwsCredentials;
+use rusoto_s3::{
+    util::{PreSignedRequest, PreSignedRequestOption},
+    AbortMultipartUploadRequest, CompleteMultipartUploadOutput, CompleteMultipartUploadRequest,
+    CompletedMultipartUpload, CompletedPart, CreateMultipartUploadRequest, PutObjectRequest,
+    S3Client, UploadPartRequest, S3,
+};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+/// Longest validity of presigned requests S3 accepts, seven days
+pub const MAX_EXPIRES_IN: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Constraints on a presigned `PUT` request
+///
+/// Every header set here becomes part of the signature, the uploader has to
+/// send exactly the same value or S3 rejects the request.
+///
+/// Rusoto doesn't sign `Content-Type` and `Content-Length` of presigned
+/// requests, use a [`presign_post`] policy to restrict the type or size of
+/// uploads.
+#[derive(Debug, Clone, Default)]
+pub struct PresignPutOptions {
+    /// Required base64 encoded MD5 digest of the upload
+    pub content_md5: Option<String>,
+    /// Canned ACL applied to the object
+    pub acl: Option<String>,
+    /// Storage class of the object
+    pub storage_class: Option<String>,
+    /// User-defined metadata stored with the object
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// Create a presigned URL allowing to upload `key` to `bucket` using `PUT`
+///
+/// The URL is valid for `expires_in`, which must not exceed
+/// [`MAX_EXPIRES_IN`].
+pub fn presign_put(
+    region: &Region,
+    credentials: &AwsCredentials,
+    bucket: impl AsRef<str>,
+    key: impl AsRef<str>,
+    expires_in: Duration,
+    options: &PresignPutOptions,
+) -> S3ExtResult<String> {
+    check_expires_in(expires_in)?;
+    let metadata: HashMap<_, _> = options.metadata.clone().into_iter().collect();
+    let request = PutObjectRequest {
+        bucket: bucket.as_ref().to_owned(),
+        key: key.as_ref().to_owned(),
+        content_md5: options.content_md5.clone(),
+        acl: options.acl.clone(),
+        storage_class: options.storage_class.clone(),
+        metadata: Some(metadata).filter(|metadata| !metadata.is_empty()),
+        ..Default::default()
+    };
+    Ok(request.get_presigned_url(region, credentials, &PreSignedRequestOption { expires_in }))
+}
+
+/// Multi-part upload whose parts are uploaded using presigned URLs
+///
+/// Hand out `part_urls` to the uploader, the first URL uploads part number 1.
+/// Once all parts are uploaded, call [`complete`](Self::complete) with the
+/// ETags reported by S3 for each part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresignedMultipartUpload {
+    /// Bucket the object is uploaded to
+    pub bucket: String,
+    /// Key of the uploaded object
+    pub key: String,
+    /// ID of the multi-part upload
+    pub upload_id: String,
+    /// Presigned `PUT` URLs, one for each part
+    pub part_urls: Vec<String>,
+}
+
+impl PresignedMultipartUpload {
+    /// Complete the upload using the ETags returned for each uploaded part
+    ///
+    /// `etags` must be in part order, i.e. the first ETag belongs to part
+    /// number 1.
+    pub async fn complete(
+        &self,
+        client: &S3Client,
+        etags: impl IntoIterator<Item = String>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput> {
+        let parts = etags
+            .into_iter()
+            .zip(1..)
+            .map(|(e_tag, part_number)| CompletedPart {
+                e_tag: Some(e_tag),
+                part_number: Some(part_number),
+            })
+            .collect();
+        client
+            .complete_multipart_upload(CompleteMultipartUploadRequest {
+                bucket: self.bucket.clone(),
+                key: self.key.clone(),
+                multipart_upload: Some(CompletedMultipartUpload { parts: Some(parts) }),
+                upload_id: self.upload_id.clone(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| e.into())
+    }
+
+    /// Abort the upload, discarding all uploaded parts
+    pub async fn abort(&self, client: &S3Client) -> S3ExtResult<()> {
+        client
+            .abort_multipart_upload(AbortMultipartUploadRequest {
+                bucket: self.bucket.clone(),
+                key: self.key.clone(),
+                upload_id: self.upload_id.clone(),
+                ..Default::default()
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+/// Start a multi-part upload and presign `part_count` `UploadPart` URLs
+///
+/// Each URL is valid for `expires_in`, which must not exceed
+/// [`MAX_EXPIRES_IN`]. All parts but the last one need to be at least 5 MiB
+/// in size.
+pub async fn presign_multipart_upload(
+    client: &S3Client,
+    region: &Region,
+    credentials: &AwsCredentials,
+    target: CreateMultipartUploadRequest,
+    part_count: usize,
+    expires_in: Duration,
+) -> S3ExtResult<PresignedMultipartUpload> {
+    check_expires_in(expires_in)?;
+    let bucket = target.bucket.clone();
+    let key = target.key.clone();
+    let upload_id = client
+        .create_multipart_upload(target)
+        .await?
+        .upload_id
+        .ok_or(S3ExtError::Other("Missing upload ID"))?;
+
+    let option = PreSignedRequestOption { expires_in };
+    let part_urls = (1..=part_count as i64)
+        .map(|part_number| {
+            let request = UploadPartRequest {
+                bucket: bucket.clone(),
+                key: key.clone(),
+                part_number,
+                upload_id: upload_id.clone(),
+                ..Default::default()
+            };
+            request.get_presigned_url(region, credentials, &option)
+        })
+        .collect();
+
+    Ok(PresignedMultipartUpload {
+        bucket,
+        key,
+        upload_id,
+        part_urls,
+    })
+}
+
+/// Condition of a presigned `POST` policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PostCondition {
+    /// Form field must have exactly the given value, e.g. `("acl", "private")`
+    Eq(String, String),
+    /// Form field must start with the given value, e.g. `("Content-Type",
+    /// "image/")`
+    StartsWith(String, String),
+    /// Size of the uploaded file in bytes must be within the given range
+    /// (inclusive)
+    ContentLengthRange(u64, u64),
+}
+
+impl PostCondition {
+    fn to_json(&self) -> Value {
+        match self {
+            PostCondition::Eq(field, value) => json!(["eq", format!("${}", field), value]),
+            PostCondition::StartsWith(field, value) => {
+                json!(["starts-with", format!("${}", field), value])
+            }
+            PostCondition::ContentLengthRange(min, max) => {
+                json!(["content-length-range", min, max])
+            }
+        }
+    }
+}
+
+/// Form upload created by [`presign_post`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresignedPost {
+    /// URL the form needs to be posted to
+    pub url: String,
+    /// Fields to include in the form, in addition to the fields required by
+    /// the conditions and the `file` field which must come last
+    pub fields: BTreeMap<String, String>,
+}
+
+/// Create a policy allowing browser based uploads to `bucket` using an HTML
+/// form
+///
+/// Uploads are restricted to keys starting with `key_prefix`, the `key`
+/// field is preset to `${key_prefix}${filename}`. The policy is valid for
+/// `expires_in`, which must not exceed [`MAX_EXPIRES_IN`].
+pub fn presign_post(
+    region: &Region,
+    credentials: &AwsCredentials,
+    bucket: impl AsRef<str>,
+    key_prefix: impl AsRef<str>,
+    conditions: &[PostCondition],
+    expires_in: Duration,
+) -> S3ExtResult<PresignedPost> {
+    check_expires_in(expires_in)?;
+    let bucket = bucket.as_ref();
+    let key_prefix = key_prefix.as_ref();
+    let now = Utc::now();
+    let date = now.format("%Y%m%d").to_string();
+    let expiration = now + chrono::Duration::seconds(expires_in.as_secs() as i64);
+
+    let mut fields = BTreeMap::new();
+    fields.insert("key".to_owned(), format!("{}${{filename}}", key_prefix));
+    fields.insert("x-amz-algorithm".to_owned(), "AWS4-HMAC-SHA256".to_owned());
+    fields.insert(
+        "x-amz-credential".to_owned(),
+        format!(
+            "{}/{}/{}/s3/aws4_request",
+            credentials.aws_access_key_id(),
+            date,
+            region.name()
+        ),
+    );
+    fields.insert(
+        "x-amz-date".to_owned(),
+        now.format("%Y%m%dT%H%M%SZ").to_string(),
+    );
+    if let Some(token) = credentials.token() {
+        fields.insert("x-amz-security-token".to_owned(), token.clone());
+    }
+
+    let mut policy_conditions = vec![
+        json!({ "bucket": bucket }),
+        json!(["starts-with", "$key", key_prefix]),
+    ];
+    policy_conditions.extend(
+        fields
+            .iter()
+            .filter(|(name, _)| name.starts_with("x-amz-"))
+            .map(|(name, value)| json!({ name: value })),
+    );
+    policy_conditions.extend(conditions.iter().map(PostCondition::to_json));
+    let policy = json!({
+        "expiration": expiration.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+        "conditions": policy_conditions,
+    });
+    let policy = base64::encode(policy.to_string());
+    // Rusoto only presigns URLs, the policy is signed like the string to sign
+    // of a request
+    let signature = hex::encode(hmac_sha256(
+        &signing_key(credentials, &date, region),
+        policy.as_bytes(),
+    ));
+    fields.insert("policy".to_owned(), policy);
+    fields.insert("x-amz-signature".to_owned(), signature);
+
+    let request = SignedRequest::new("POST", "s3", region, &format!("/{}", bucket));
+    Ok(PresignedPost {
+        url: format!(
+            "{}://{}{}",
+            request.scheme(),
+            request.hostname(),
+            request.canonical_path()
+        ),
+        fields,
+    })
+}
+
+// Reject validities S3 doesn't accept
+fn check_expires_in(expires_in: Duration) -> S3ExtResult<()> {
+    if expires_in > MAX_EXPIRES_IN {
+        return Err(S3ExtError::InvalidExpiry(expires_in));
+    }
+    Ok(())
+}
+
+pub(crate) fn signing_key(credentials: &AwsCredentials, date: &str, region: &Region) -> Vec<u8> {
+    let secret = format!("AWS4{}", credentials.aws_secret_access_key());
+    let key = hmac_sha256(secret.as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.name().as_bytes());
+    let key = hmac_sha256(&key, b"s3");
+    hmac_sha256(&key, b"aws4_request")
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
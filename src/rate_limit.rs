@@ -0,0 +1,177 @@
+//! Client-side limits on the rate of requests
+//!
+//! S3 answers with `503 SlowDown` once the requests to a prefix exceed a few
+//! thousand per second. Bulk tools listing, downloading or deleting large
+//! numbers of keys easily get there. A [`RateLimiter`] installed using
+//! [`S3ExtClientBuilder::rate_limit`](crate::client::S3ExtClientBuilder::rate_limit)
+//! delays requests instead, applying a token bucket per [`RequestClass`] to
+//! every HTTP request, including the pages of listings, the parts of
+//! multi-part uploads and retries.
+//!
+//! Each class allows bursts of up to one second worth of requests. Classes
+//! without a limit aren't delayed. Clones of a limiter share their buckets,
+//! so a limiter installed on several clients limits them together.
+//!
+//! # Example
+//!
+//! ```
+//! use s3_ext::{
+//!     client::S3ExtClientBuilder,
+//!     rate_limit::{RateLimiter, RequestClass},
+//! };
+//!
+//! # fn main() -> Result<(), s3_ext::error::S3ExtError> {
+//! let client = S3ExtClientBuilder::new()
+//!     .endpoint("eu-west-1", "http://localhost:9000")
+//!     .rate_limit(
+//!         RateLimiter::new()
+//!             .limit(RequestClass::List, 100.0)
+//!             .limit(RequestClass::Delete, 1000.0),
+//!     )
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use parking_lot::Mutex;
+use rusoto_core::{
+    request::{DispatchSignedRequestFuture, HttpResponse},
+    signature::SignedRequest,
+    DispatchSignedRequest, HttpDispatchError,
+};
+use std::{sync::Arc, time::Duration};
+use tokio::time::Instant;
+
+/// Class of requests sharing a limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RequestClass {
+    /// `GET` requests on a bucket, e.g. `ListObjectsV2`, and `ListParts`
+    List,
+    /// Other `GET` and `HEAD` requests
+    Get,
+    /// `PUT` and `POST` requests, e.g. uploads and copies
+    Put,
+    /// `DELETE` requests and `DeleteObjects`
+    Delete,
+}
+
+impl RequestClass {
+    /// Class of `request`, sent using path-style addressing
+    pub fn of(request: &SignedRequest) -> Self {
+        let has_key = request
+            .path
+            .trim_start_matches('/')
+            .split_once('/')
+            .map_or(false, |(_, key)| !key.is_empty());
+        match request.method.as_str() {
+            "GET" if !has_key || request.params.contains_key("uploadId") => RequestClass::List,
+            "GET" | "HEAD" => RequestClass::Get,
+            "DELETE" => RequestClass::Delete,
+            "POST" if request.params.contains_key("delete") => RequestClass::Delete,
+            _ => RequestClass::Put,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            RequestClass::List => 0,
+            RequestClass::Get => 1,
+            RequestClass::Put => 2,
+            RequestClass::Delete => 3,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    // tokens added per second
+    rate: f64,
+    // negative while requests are waiting
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    // Take a token, returning how long to wait until it is available
+    fn take(&mut self) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate.max(1.0));
+        self.updated = now;
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// Limits on the requests per second of each [`RequestClass`]
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    buckets: Arc<[Mutex<Option<TokenBucket>>; 4]>,
+}
+
+impl RateLimiter {
+    /// Create a limiter not limiting any requests
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit requests of `class` to `per_second` requests per second
+    ///
+    /// # Panics
+    ///
+    /// Panics if `per_second` isn't positive.
+    pub fn limit(self, class: RequestClass, per_second: f64) -> Self {
+        assert!(per_second > 0.0, "rate limits must be positive");
+        *self.buckets[class.index()].lock() = Some(TokenBucket {
+            rate: per_second,
+            tokens: per_second.max(1.0),
+            updated: Instant::now(),
+        });
+        self
+    }
+
+    /// Wait until a request of `class` may be sent
+    pub async fn acquire(&self, class: RequestClass) {
+        let wait = match &mut *self.buckets[class.index()].lock() {
+            Some(bucket) => bucket.take(),
+            None => return,
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    async fn dispatch(
+        self,
+        inner: Arc<dyn DispatchSignedRequest + Send + Sync>,
+        request: SignedRequest,
+        timeout: Option<Duration>,
+    ) -> Result<HttpResponse, HttpDispatchError> {
+        self.acquire(RequestClass::of(&request)).await;
+        inner.dispatch(request, timeout).await
+    }
+}
+
+/// HTTP client delaying requests according to a `RateLimiter`
+pub(crate) struct RateLimitedHttpClient {
+    pub(crate) inner: Arc<dyn DispatchSignedRequest + Send + Sync>,
+    pub(crate) limiter: RateLimiter,
+}
+
+impl DispatchSignedRequest for RateLimitedHttpClient {
+    fn dispatch(
+        &self,
+        request: SignedRequest,
+        timeout: Option<Duration>,
+    ) -> DispatchSignedRequestFuture {
+        Box::pin(
+            self.limiter
+                .clone()
+                .dispatch(self.inner.clone(), request, timeout),
+        )
+    }
+}
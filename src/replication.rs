@@ -0,0 +1,280 @@
+//! Replication of buckets
+//!
+//! See the methods
+//! [`get_bucket_replication`](crate::bucket_config::BucketConfigExt::get_bucket_replication),
+//! [`put_bucket_replication`](crate::bucket_config::BucketConfigExt::put_bucket_replication) and
+//! [`delete_bucket_replication`](crate::bucket_config::BucketConfigExt::delete_bucket_replication) of
+//! [`BucketConfigExt`](crate::bucket_config::BucketConfigExt).
+//!
+//! Only the common parts of a rule are modelled. Encryption, replication
+//! time control and metrics of the destination are lost when converting a
+//! Rusoto `ReplicationConfiguration`, use Rusoto directly to manage those.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::{
+//!     bucket_config::BucketConfigExt,
+//!     replication::{Replication, ReplicationRule},
+//! };
+//!
+//! let replication = Replication::new("arn:aws:iam::123456789012:role/replication").rule(
+//!     ReplicationRule::new("dr", "dr-bucket")
+//!         .prefix("data/")
+//!         .storage_class("STANDARD_IA")
+//!         .replicate_delete_markers(true),
+//! );
+//! client.put_bucket_replication("bucket", &replication).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    error::{S3ExtError, S3ExtResult},
+    tagging::{tag_set, tags_from_set, Tags},
+};
+use rusoto_s3::{
+    DeleteBucketReplicationRequest, DeleteMarkerReplication, Destination,
+    GetBucketReplicationRequest, PutBucketReplicationRequest, ReplicationConfiguration,
+    ReplicationRuleAndOperator, ReplicationRuleFilter, Tag,
+};
+
+/// Rule replicating objects to a destination bucket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicationRule {
+    /// ID of the rule
+    pub id: Option<String>,
+    /// Priority among rules with overlapping filters, higher wins
+    pub priority: i64,
+    /// Whether the rule is enabled
+    pub enabled: bool,
+    /// Only replicate objects with this prefix
+    pub prefix: String,
+    /// Only replicate objects with all of these tags
+    pub tags: Tags,
+    /// ARN of the destination bucket
+    pub destination: String,
+    /// Account owning the destination bucket, if different
+    pub account: Option<String>,
+    /// Storage class of the replicas, that of the source if not set
+    pub storage_class: Option<String>,
+    /// Replicate delete markers
+    pub delete_markers: bool,
+}
+
+impl ReplicationRule {
+    /// Enabled rule with ID `id` replicating all objects to
+    /// `destination_bucket`
+    ///
+    /// `destination_bucket` may either be a bucket name or ARN.
+    pub fn new(id: impl Into<String>, destination_bucket: &str) -> Self {
+        let destination = if destination_bucket.starts_with("arn:") {
+            destination_bucket.to_owned()
+        } else {
+            format!("arn:aws:s3:::{}", destination_bucket)
+        };
+        ReplicationRule {
+            id: Some(id.into()),
+            priority: 0,
+            enabled: true,
+            prefix: String::new(),
+            tags: Tags::new(),
+            destination,
+            account: None,
+            storage_class: None,
+            delete_markers: false,
+        }
+    }
+
+    /// Set the priority
+    pub fn priority(mut self, priority: i64) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Only replicate objects with `prefix`
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Only replicate objects tagged with `key` set to `value`
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.insert(key.into(), value.into());
+        self
+    }
+
+    /// Store replicas using `storage_class`, e.g. `GLACIER`
+    pub fn storage_class(mut self, storage_class: impl Into<String>) -> Self {
+        self.storage_class = Some(storage_class.into());
+        self
+    }
+
+    /// Replicate delete markers
+    pub fn replicate_delete_markers(mut self, enabled: bool) -> Self {
+        self.delete_markers = enabled;
+        self
+    }
+
+    fn to_rusoto(&self) -> rusoto_s3::ReplicationRule {
+        let filter = if self.tags.is_empty() {
+            ReplicationRuleFilter {
+                prefix: Some(self.prefix.clone()),
+                ..Default::default()
+            }
+        } else if self.tags.len() == 1 && self.prefix.is_empty() {
+            ReplicationRuleFilter {
+                tag: tag_set(&self.tags).pop(),
+                ..Default::default()
+            }
+        } else {
+            ReplicationRuleFilter {
+                and: Some(ReplicationRuleAndOperator {
+                    prefix: Some(self.prefix.clone()).filter(|prefix| !prefix.is_empty()),
+                    tags: Some(tag_set(&self.tags)),
+                }),
+                ..Default::default()
+            }
+        };
+        rusoto_s3::ReplicationRule {
+            id: self.id.clone(),
+            priority: Some(self.priority),
+            status: status(self.enabled).to_owned(),
+            filter: Some(filter),
+            delete_marker_replication: Some(DeleteMarkerReplication {
+                status: Some(status(self.delete_markers).to_owned()),
+            }),
+            destination: Destination {
+                bucket: self.destination.clone(),
+                account: self.account.clone(),
+                storage_class: self.storage_class.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn from_rusoto(rule: rusoto_s3::ReplicationRule) -> Self {
+        let (prefix, tags) = match rule.filter {
+            Some(ReplicationRuleFilter { and: Some(and), .. }) => {
+                (and.prefix, and.tags.unwrap_or_default())
+            }
+            Some(ReplicationRuleFilter { prefix, tag, .. }) => (prefix, tag.into_iter().collect()),
+            // Rules of the first version of the schema have no filter
+            None => (rule.prefix, Vec::<Tag>::new()),
+        };
+        ReplicationRule {
+            id: rule.id,
+            priority: rule.priority.unwrap_or_default(),
+            enabled: rule.status == "Enabled",
+            prefix: prefix.unwrap_or_default(),
+            tags: tags_from_set(tags),
+            destination: rule.destination.bucket,
+            account: rule.destination.account,
+            storage_class: rule.destination.storage_class,
+            delete_markers: rule
+                .delete_marker_replication
+                .and_then(|replication| replication.status)
+                .as_deref()
+                == Some("Enabled"),
+        }
+    }
+}
+
+/// Replication configuration of a bucket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replication {
+    /// ARN of the IAM role S3 assumes to replicate objects
+    pub role: String,
+    /// Rules
+    pub rules: Vec<ReplicationRule>,
+}
+
+impl Replication {
+    /// Configuration without rules replicating using IAM role `role`
+    pub fn new(role: impl Into<String>) -> Self {
+        Replication {
+            role: role.into(),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Add `rule`
+    pub fn rule(mut self, rule: ReplicationRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Convert into a Rusoto `ReplicationConfiguration`
+    pub fn to_configuration(&self) -> ReplicationConfiguration {
+        ReplicationConfiguration {
+            role: self.role.clone(),
+            rules: self.rules.iter().map(ReplicationRule::to_rusoto).collect(),
+        }
+    }
+
+    /// Convert a Rusoto `ReplicationConfiguration`
+    pub fn from_configuration(configuration: ReplicationConfiguration) -> Self {
+        Replication {
+            role: configuration.role,
+            rules: configuration
+                .rules
+                .into_iter()
+                .map(ReplicationRule::from_rusoto)
+                .collect(),
+        }
+    }
+}
+
+fn status(enabled: bool) -> &'static str {
+    if enabled {
+        "Enabled"
+    } else {
+        "Disabled"
+    }
+}
+
+pub(crate) async fn get_bucket_replication(
+    client: &Backend,
+    bucket: String,
+) -> S3ExtResult<Option<Replication>> {
+    let request = GetBucketReplicationRequest {
+        bucket,
+        ..Default::default()
+    };
+    match client.get_bucket_replication(request).await {
+        Ok(output) => Ok(output
+            .replication_configuration
+            .map(Replication::from_configuration)),
+        Err(e) => {
+            let e = S3ExtError::from(e);
+            match e.code() {
+                Some("ReplicationConfigurationNotFoundError") => Ok(None),
+                _ => Err(e),
+            }
+        }
+    }
+}
+
+pub(crate) async fn put_bucket_replication(
+    client: &Backend,
+    bucket: String,
+    replication: &Replication,
+) -> S3ExtResult<()> {
+    let request = PutBucketReplicationRequest {
+        bucket,
+        replication_configuration: replication.to_configuration(),
+        ..Default::default()
+    };
+    Ok(client.put_bucket_replication(request).await?)
+}
+
+pub(crate) async fn delete_bucket_replication(client: &Backend, bucket: String) -> S3ExtResult<()> {
+    let request = DeleteBucketReplicationRequest {
+        bucket,
+        ..Default::default()
+    };
+    Ok(client.delete_bucket_replication(request).await?)
+}
@@ -0,0 +1,183 @@
+//! Restore archived objects
+//!
+//! Objects in the `GLACIER` and `DEEP_ARCHIVE` storage classes, or in the
+//! archive tiers of `INTELLIGENT_TIERING`, can't be downloaded until a
+//! temporary copy has been restored. See
+//! [`S3Ext::restore_object`](crate::S3Ext::restore_object),
+//! [`S3Ext::restore_status`](crate::S3Ext::restore_status) and
+//! [`S3Ext::wait_until_restored`](crate::S3Ext::wait_until_restored).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::{restore::RestoreTier, S3Ext};
+//! use std::time::Duration;
+//!
+//! client
+//!     .restore_object("bucket", "archive.tar", RestoreTier::Bulk, 7)
+//!     .await?;
+//! let expiry = client
+//!     .wait_until_restored("bucket", "archive.tar", Duration::from_secs(15 * 60))
+//!     .await?;
+//! println!("copy available until {:?}", expiry);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    error::{S3ExtError, S3ExtResult},
+    timestamps::parse_timestamp,
+    S3Ext,
+};
+use chrono::{DateTime, Utc};
+use rusoto_s3::{GlacierJobParameters, HeadObjectRequest, RestoreObjectRequest, RestoreRequest};
+use std::time::Duration;
+
+/// Retrieval tier, trading speed for cost
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreTier {
+    /// Minutes, not available for `DEEP_ARCHIVE`
+    Expedited,
+    /// Hours
+    Standard,
+    /// Up to two days, cheapest
+    Bulk,
+}
+
+impl RestoreTier {
+    /// Name of the tier as used by S3
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RestoreTier::Expedited => "Expedited",
+            RestoreTier::Standard => "Standard",
+            RestoreTier::Bulk => "Bulk",
+        }
+    }
+}
+
+/// State of the restore of an archived object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoreStatus {
+    /// Restore was requested and is still in progress
+    InProgress,
+    /// Temporary copy is available
+    Restored {
+        /// Date the copy is removed again, in HTTP date format
+        expiry_date: Option<String>,
+    },
+}
+
+impl RestoreStatus {
+    /// Parse the value of the `x-amz-restore` header, e.g.
+    /// `ongoing-request="false", expiry-date="Fri, 21 Dec 2012 00:00:00 GMT"`
+    pub fn from_header(value: &str) -> Option<Self> {
+        match quoted(value, "ongoing-request")? {
+            "true" => Some(RestoreStatus::InProgress),
+            "false" => Some(RestoreStatus::Restored {
+                expiry_date: quoted(value, "expiry-date").map(str::to_owned),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Whether the temporary copy is available
+    pub fn is_restored(&self) -> bool {
+        matches!(self, RestoreStatus::Restored { .. })
+    }
+
+    /// Date the temporary copy is removed, `None` if not restored yet or the
+    /// date is missing or malformed
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            RestoreStatus::Restored {
+                expiry_date: Some(date),
+            } => parse_timestamp(date),
+            _ => None,
+        }
+    }
+}
+
+// Value of `name="value"` within `header`
+pub(crate) fn quoted<'a>(header: &'a str, name: &str) -> Option<&'a str> {
+    let start = header.find(&format!("{}=\"", name))? + name.len() + 2;
+    let len = header[start..].find('"')?;
+    Some(&header[start..start + len])
+}
+
+pub(crate) async fn restore_object(
+    client: &Backend,
+    bucket: String,
+    key: String,
+    tier: RestoreTier,
+    days: i64,
+) -> S3ExtResult<()> {
+    let request = RestoreObjectRequest {
+        bucket: bucket.clone(),
+        key: key.clone(),
+        restore_request: Some(RestoreRequest {
+            days: Some(days),
+            glacier_job_parameters: Some(GlacierJobParameters {
+                tier: tier.as_str().to_owned(),
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    match client.restore_object(request).await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let e = S3ExtError::from(e);
+            match e.code() {
+                // the pending restore is kept, as is its tier
+                Some("RestoreAlreadyInProgress") => Ok(()),
+                _ => Err(e.with_object(bucket, key)),
+            }
+        }
+    }
+}
+
+pub(crate) async fn restore_status(
+    client: &Backend,
+    bucket: String,
+    key: String,
+) -> S3ExtResult<Option<RestoreStatus>> {
+    let request = HeadObjectRequest {
+        bucket: bucket.clone(),
+        key: key.clone(),
+        ..Default::default()
+    };
+    client
+        .head_object(request)
+        .await
+        .map(|output| {
+            output
+                .restore
+                .as_deref()
+                .and_then(RestoreStatus::from_header)
+        })
+        .map_err(|e| S3ExtError::from(e).with_object(bucket, key))
+}
+
+pub(crate) async fn wait_until_restored<C>(
+    client: &C,
+    bucket: String,
+    key: String,
+    poll_interval: Duration,
+) -> S3ExtResult<Option<String>>
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    loop {
+        match client.restore_status(bucket.clone(), key.clone()).await? {
+            Some(RestoreStatus::Restored { expiry_date }) => return Ok(expiry_date),
+            Some(RestoreStatus::InProgress) => tokio::time::sleep(poll_interval).await,
+            None => {
+                return Err(
+                    S3ExtError::Other("object is not being restored").with_object(bucket, key)
+                )
+            }
+        }
+    }
+}
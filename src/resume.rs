@@ -0,0 +1,214 @@
+//! Resumption of interrupted downloads
+//!
+//! When the connection breaks while the body of an object is being read,
+//! [`S3Ext::download`](crate::S3Ext::download) and
+//! [`S3Ext::download_to_file`](crate::S3Ext::download_to_file) request the
+//! rest of the object using a `Range` starting at the number of bytes
+//! already written, instead of failing. The `If-Match` header of the
+//! follow-up request is set to the ETag of the first response, so an object
+//! replaced in the meantime fails the download rather than mixing two
+//! versions. Up to [`DEFAULT_ATTEMPTS`] resumptions are made, see
+//! [`S3Ext::download_resumable`](crate::S3Ext::download_resumable) to choose
+//! another number.
+//!
+//! Downloads are only resumed if the extent of the body is known from its
+//! `Content-Range` or `Content-Length`, and a follow-up response not
+//! starting at the requested offset fails the download.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use rusoto_s3::GetObjectRequest;
+//! use s3_ext::S3Ext;
+//!
+//! let source = GetObjectRequest {
+//!     bucket: "backups".to_owned(),
+//!     key: "2024-01-01.tar".to_owned(),
+//!     ..Default::default()
+//! };
+//! let mut target = tokio::fs::File::create("/tmp/backup.tar").await?;
+//! client.download_resumable(source, &mut target, 10).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::{S3ExtError, S3ExtResult},
+    retry::RetryPolicy,
+    S3Ext,
+};
+use futures::StreamExt;
+use log::warn;
+use rusoto_s3::{GetObjectOutput, GetObjectRequest, StreamingBody};
+use tokio::io::{self, AsyncWrite, AsyncWriteExt};
+
+/// Number of times an interrupted download is resumed by default
+pub const DEFAULT_ATTEMPTS: u32 = 3;
+
+// First and last byte of the object contained in the body of `output`
+fn body_range(output: &GetObjectOutput) -> Option<(u64, u64)> {
+    if let Some(range) = output.content_range.as_deref() {
+        // bytes <first>-<last>/<size>
+        let range = range.strip_prefix("bytes ")?;
+        let (range, _) = range.split_once('/')?;
+        let (first, last) = range.split_once('-')?;
+        return Some((first.parse().ok()?, last.parse().ok()?));
+    }
+    match output.content_length {
+        Some(length) if length > 0 => Some((0, length as u64 - 1)),
+        _ => None,
+    }
+}
+
+// Copy `body` to `target`, adding the bytes written to `written`
+//
+// Returns the error reading the body, if any. Errors writing to `target`
+// fail the download.
+async fn copy_body<W>(
+    body: Option<StreamingBody>,
+    target: &mut W,
+    written: &mut u64,
+) -> S3ExtResult<Option<io::Error>>
+where
+    W: AsyncWrite + Unpin + Send + ?Sized,
+{
+    // Some S3-compatible servers omit the body of empty objects, a missing
+    // body is treated as empty
+    let mut body = match body {
+        Some(body) => body,
+        None => return Ok(None),
+    };
+    while let Some(chunk) = body.next().await {
+        match chunk {
+            Ok(chunk) => {
+                target.write_all(&chunk).await?;
+                *written += chunk.len() as u64;
+            }
+            Err(e) => return Ok(Some(e)),
+        }
+    }
+    Ok(None)
+}
+
+// Write the body of `output`, the response to `source`, to `target`, then
+// flush it
+async fn resume<C, W>(
+    client: &C,
+    source: GetObjectRequest,
+    output: GetObjectOutput,
+    target: &mut W,
+    attempts: u32,
+) -> S3ExtResult<(GetObjectOutput, u64)>
+where
+    C: S3Ext + Sync + ?Sized,
+    W: AsyncWrite + Unpin + Send + ?Sized,
+{
+    let result = copy_resumed(client, source, output, target, attempts).await?;
+    target.flush().await?;
+    Ok(result)
+}
+
+async fn copy_resumed<C, W>(
+    client: &C,
+    source: GetObjectRequest,
+    mut output: GetObjectOutput,
+    target: &mut W,
+    attempts: u32,
+) -> S3ExtResult<(GetObjectOutput, u64)>
+where
+    C: S3Ext + Sync + ?Sized,
+    W: AsyncWrite + Unpin + Send + ?Sized,
+{
+    let range = body_range(&output);
+    let mut next = Ok(output.body.take());
+    let (mut written, mut attempt) = (0, 0);
+    loop {
+        let error = match next {
+            Ok(body) => match (copy_body(body, target, &mut written).await?, range) {
+                // Everything arrived, an error at the very end doesn't matter
+                (_, Some((first, last))) if written > last - first => return Ok((output, written)),
+                (None, None) => return Ok((output, written)),
+                (Some(e), _) => S3ExtError::from(e),
+                (None, Some(_)) => S3ExtError::from(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "body ended before Content-Length was reached",
+                )),
+            },
+            Err(e) if e.is_retryable() => e,
+            Err(e) => return Err(e),
+        };
+        let (first, last) = match range {
+            Some(range) if attempt < attempts => range,
+            _ => return Err(error),
+        };
+        attempt += 1;
+        let start = first + written;
+        let delay = RetryPolicy::default().backoff(attempt);
+        warn!(
+            "download of {}/{} interrupted after {} bytes, resuming in {:?}: {}",
+            source.bucket, source.key, written, delay, error
+        );
+        tokio::time::sleep(delay).await;
+        let request = GetObjectRequest {
+            range: Some(format!("bytes={}-{}", start, last)),
+            part_number: None,
+            if_match: source.if_match.clone().or_else(|| output.e_tag.clone()),
+            ..source.clone()
+        };
+        next = client.get_body_with(request).await.and_then(|resumed| {
+            let resumed = resumed.into_output();
+            match body_range(&resumed) {
+                Some((first, _)) if first == start => Ok(resumed.body),
+                _ => Err(S3ExtError::Other(
+                    "server ignored the range of a resumed download",
+                )),
+            }
+        });
+    }
+}
+
+/// Get object described by `source` and write it to `target`, resuming up
+/// to `attempts` times
+///
+/// Returns the first response, without its body, and the number of bytes
+/// written.
+pub(crate) async fn download<C, W>(
+    client: &C,
+    source: GetObjectRequest,
+    target: &mut W,
+    attempts: u32,
+) -> S3ExtResult<(GetObjectOutput, u64)>
+where
+    C: S3Ext + Sync + ?Sized,
+    W: AsyncWrite + Unpin + Send + ?Sized,
+{
+    let (bucket, key) = (source.bucket.clone(), source.key.clone());
+    let result = match client.get_body_with(source.clone()).await {
+        Ok(body) => resume(client, source, body.into_output(), target, attempts).await,
+        Err(e) => Err(e),
+    };
+    result.map_err(|e| e.with_object(bucket, key))
+}
+
+/// Write the body of `output`, the response to `source`, to `target`,
+/// resuming up to `attempts` times
+///
+/// Like [`download`], for callers inspecting the response before the body
+/// is read, e.g. to only create a target file for existing objects.
+pub(crate) async fn download_body<C, W>(
+    client: &C,
+    source: GetObjectRequest,
+    output: GetObjectOutput,
+    target: &mut W,
+    attempts: u32,
+) -> S3ExtResult<(GetObjectOutput, u64)>
+where
+    C: S3Ext + Sync + ?Sized,
+    W: AsyncWrite + Unpin + Send + ?Sized,
+{
+    let (bucket, key) = (source.bucket.clone(), source.key.clone());
+    resume(client, source, output, target, attempts)
+        .await
+        .map_err(|e| e.with_object(bucket, key))
+}
@@ -0,0 +1,586 @@
+//! Retry failed operations
+//!
+//! [`RetryingS3Client`] retries operations failing due to throttling
+//! (`503 SlowDown`, `429`), server errors (`500`, `502`, `503`, `504`),
+//! connection errors and timeouts. Delays between attempts grow
+//! exponentially, are capped and randomized ("full jitter") to keep clients
+//! from retrying in lockstep.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rusoto_core::Region;
+//! use rusoto_s3::S3Client;
+//! use s3_ext::{retry::{RetryPolicy, RetryingS3Client}, S3Ext};
+//! use std::time::Duration;
+//!
+//! let client = RetryingS3Client::new(
+//!     S3Client::new(Region::EuWest1),
+//!     RetryPolicy {
+//!         max_retries: 5,
+//!         ..Default::default()
+//!     },
+//! );
+//! let objects = client.stream_objects("bucket");
+//! ```
+
+use crate::{
+    backend::Backend,
+    body::ObjectBody,
+    compose::ComposePart,
+    diff::DiffStream,
+    error::{Failure, S3ExtError, S3ExtResult},
+    framed::FramedObject,
+    iter::{GetObjectStream, ObjectStream, SortedObjectStream},
+    lines::LineStream,
+    metadata::ObjectMetadata,
+    part_size::PartSize,
+    stats,
+    watch::WatchStream,
+    S3Ext,
+};
+use async_trait::async_trait;
+use log::debug;
+use rand::Rng;
+use rusoto_core::RusotoError;
+use rusoto_s3::{
+    CompleteMultipartUploadOutput, GetObjectOutput, GetObjectRequest, PutObjectOutput,
+    PutObjectRequest, S3Client,
+};
+use std::{convert::AsRef, fmt::Debug, future::Future, path::Path, time::Duration};
+use tokio::io;
+use tokio_util::codec::Decoder;
+
+/// When and how often to retry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of retries after the initial attempt
+    pub max_retries: u32,
+    /// Upper bound of the delay before the first retry, doubled for each
+    /// further retry
+    pub base_delay: Duration,
+    /// Upper bound of the delay between any two attempts
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(20),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy never retrying
+    pub fn disabled() -> Self {
+        Self {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Delay before retry number `retry` (starting at 1), picked at random
+    /// between zero and the capped exponential backoff
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(retry.saturating_sub(1));
+        let cap = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        cap.mul_f64(rand::thread_rng().gen::<f64>())
+    }
+
+    // Wait before retrying if `error` is worth retrying and retries are left
+    pub(crate) async fn wait_for_retry<E>(&self, error: &E, attempt: &mut u32) -> bool
+    where
+        E: Retryable + Debug,
+    {
+        if *attempt >= self.max_retries || !error.is_retryable() {
+            return false;
+        }
+        *attempt += 1;
+        stats::record_retry();
+        let delay = self.backoff(*attempt);
+        debug!(
+            "retrying after {:?} (attempt {} of {}) in {:?}",
+            error, attempt, self.max_retries, delay
+        );
+        tokio::time::sleep(delay).await;
+        true
+    }
+}
+
+pub(crate) trait Retryable {
+    fn is_retryable(&self) -> bool;
+}
+
+impl<E> Retryable for RusotoError<E> {
+    fn is_retryable(&self) -> bool {
+        Failure::of(self).is_retryable()
+    }
+}
+
+impl<T: Retryable> Retryable for Box<T> {
+    fn is_retryable(&self) -> bool {
+        T::is_retryable(self)
+    }
+}
+
+impl Retryable for S3ExtError {
+    fn is_retryable(&self) -> bool {
+        S3ExtError::is_retryable(self)
+    }
+}
+
+/// Client retrying failed operations according to a [`RetryPolicy`]
+///
+/// # Caveats
+///
+/// * [`upload`](S3Ext::upload) and [`upload_multipart`](S3Ext::upload_multipart)
+///   are not retried since the source can't be read twice, use
+///   [`upload_from_file`](S3Ext::upload_from_file) and
+///   [`upload_from_file_multipart`](S3Ext::upload_from_file_multipart)
+///   instead.
+/// * A failed multi-part upload is retried from the start.
+/// * Requests of streams are retried individually, except for
+///   [`watch_prefix`](S3Ext::watch_prefix) which isn't retried.
+#[derive(Debug, Clone)]
+pub struct RetryingS3Client<C = S3Client> {
+    inner: C,
+    policy: RetryPolicy,
+}
+
+impl<C> RetryingS3Client<C> {
+    /// Wrap `inner`, retrying according to `policy`
+    pub fn new(inner: C, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// Wrapped client
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Policy applied to each operation
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    // Run `operation` until it succeeds, fails for good or retries run out
+    async fn retry<F, Fut, T>(&self, mut operation: F) -> S3ExtResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = S3ExtResult<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let error = match operation().await {
+                Err(e) => e,
+                ok => return ok,
+            };
+            if !self.policy.wait_for_retry(&error, &mut attempt).await {
+                return Err(error);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<C> S3Ext for RetryingS3Client<C>
+where
+    C: S3Ext + Send + Sync,
+{
+    async fn send_request<T, F, Fut>(
+        &self,
+        operation: &'static str,
+        bucket: String,
+        key: String,
+        request: F,
+    ) -> S3ExtResult<T>
+    where
+        T: Send,
+        F: Fn(Backend, String, String) -> Fut + Send + Sync,
+        Fut: Future<Output = S3ExtResult<T>> + Send,
+    {
+        self.retry(|| {
+            self.inner
+                .send_request(operation, bucket.clone(), key.clone(), &request)
+        })
+        .await
+    }
+
+    async fn download_to_file<F>(
+        &self,
+        source: GetObjectRequest,
+        target: F,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        // errors worth retrying occur before the target file is created
+        let mut attempt = 0;
+        loop {
+            let error = match self
+                .inner
+                .download_to_file(source.clone(), target.as_ref())
+                .await
+            {
+                Err(e) => e,
+                ok => return ok,
+            };
+            if !self.policy.wait_for_retry(&error, &mut attempt).await {
+                return Err(error);
+            }
+        }
+    }
+
+    async fn upload_from_file<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        let mut attempt = 0;
+        loop {
+            let error = match self
+                .inner
+                .upload_from_file(source.as_ref(), clone_put_request(&target))
+                .await
+            {
+                Err(e) => e,
+                ok => return ok,
+            };
+            if !self.policy.wait_for_retry(&error, &mut attempt).await {
+                return Err(error);
+            }
+        }
+    }
+
+    async fn upload_from_file_multipart_with<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        let mut attempt = 0;
+        loop {
+            let error = match self
+                .inner
+                .upload_from_file_multipart_with(
+                    source.as_ref(),
+                    clone_put_request(&target),
+                    part_size,
+                )
+                .await
+            {
+                Err(e) => e,
+                ok => return ok,
+            };
+            if !self.policy.wait_for_retry(&error, &mut attempt).await {
+                return Err(error);
+            }
+        }
+    }
+
+    async fn download<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        // errors worth retrying occur before anything is written to `target`
+        let mut attempt = 0;
+        loop {
+            let error = match self.inner.download(source.clone(), target).await {
+                Err(e) => e,
+                ok => return ok,
+            };
+            if !self.policy.wait_for_retry(&error, &mut attempt).await {
+                return Err(error);
+            }
+        }
+    }
+
+    async fn download_decompressed<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        // errors worth retrying occur before anything is written to `target`
+        let mut attempt = 0;
+        loop {
+            let error = match self
+                .inner
+                .download_decompressed(source.clone(), target)
+                .await
+            {
+                Err(e) => e,
+                ok => return ok,
+            };
+            if !self.policy.wait_for_retry(&error, &mut attempt).await {
+                return Err(error);
+            }
+        }
+    }
+
+    // `decoder` is consumed by the first attempt, so failures aren't retried
+    #[inline]
+    async fn get_framed<D>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        decoder: D,
+    ) -> S3ExtResult<FramedObject<D>>
+    where
+        D: Decoder + Send,
+    {
+        self.inner.get_framed(bucket, key, decoder).await
+    }
+
+    async fn get_body_with(&self, source: GetObjectRequest) -> S3ExtResult<ObjectBody> {
+        // only the request is retried, reading the body isn't
+        self.retry(|| self.inner.get_body_with(source.clone()))
+            .await
+    }
+
+    #[inline]
+    async fn upload<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.inner.upload(source, target).await
+    }
+
+    #[inline]
+    async fn upload_multipart_with<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.inner
+            .upload_multipart_with(source, target, part_size)
+            .await
+    }
+
+    async fn upload_multipart_resumable<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.inner
+            .upload_multipart_resumable(source, target, part_size)
+            .await
+    }
+
+    #[inline]
+    fn stream_objects(&self, bucket: impl Into<String>) -> ObjectStream {
+        self.inner
+            .stream_objects(bucket)
+            .with_retry_policy(self.policy.clone())
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> ObjectStream {
+        self.inner
+            .stream_objects_with_prefix(bucket, prefix)
+            .with_retry_policy(self.policy.clone())
+    }
+
+    #[inline]
+    fn stream_objects_sorted_by_mtime(&self, bucket: impl Into<String>) -> SortedObjectStream {
+        self.stream_objects(bucket).sorted_by_mtime()
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix_sorted_by_mtime(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> SortedObjectStream {
+        self.stream_objects_with_prefix(bucket, prefix)
+            .sorted_by_mtime()
+    }
+
+    fn diff(
+        &self,
+        source_bucket: impl Into<String>,
+        source_prefix: impl Into<String>,
+        dest_bucket: impl Into<String>,
+        dest_prefix: impl Into<String>,
+    ) -> DiffStream {
+        let source_prefix = source_prefix.into();
+        let dest_prefix = dest_prefix.into();
+        DiffStream::new(
+            self.stream_objects_with_prefix(source_bucket, &source_prefix),
+            source_prefix,
+            self.stream_objects_with_prefix(dest_bucket, &dest_prefix),
+            dest_prefix,
+        )
+    }
+
+    #[inline]
+    fn watch_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        period: Duration,
+    ) -> WatchStream {
+        self.inner.watch_prefix(bucket, prefix, period)
+    }
+
+    #[inline]
+    fn stream_get_objects(&self, bucket: impl Into<String>) -> GetObjectStream {
+        self.inner
+            .stream_get_objects(bucket)
+            .with_retry_policy(self.policy.clone())
+    }
+
+    #[inline]
+    fn stream_get_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> GetObjectStream {
+        self.inner
+            .stream_get_objects_with_prefix(bucket, prefix)
+            .with_retry_policy(self.policy.clone())
+    }
+
+    #[inline]
+    fn stream_lines(&self, bucket: impl Into<String>, key: impl Into<String>) -> LineStream {
+        self.inner.stream_lines(bucket, key)
+    }
+
+    async fn delete(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        let (bucket, key) = (bucket.into(), key.into());
+        self.retry(|| self.inner.delete(bucket.clone(), key.clone()))
+            .await
+    }
+
+    async fn compose_object(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        parts: Vec<ComposePart>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput> {
+        let (bucket, key) = (bucket.into(), key.into());
+        self.retry(|| {
+            self.inner
+                .compose_object(bucket.clone(), key.clone(), parts.clone())
+        })
+        .await
+    }
+
+    async fn object_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectMetadata> {
+        let (bucket, key) = (bucket.into(), key.into());
+        self.retry(|| self.inner.object_metadata(bucket.clone(), key.clone()))
+            .await
+    }
+
+    async fn replace_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        metadata: ObjectMetadata,
+    ) -> S3ExtResult<()> {
+        let (bucket, key) = (bucket.into(), key.into());
+        self.retry(|| {
+            self.inner
+                .replace_metadata(bucket.clone(), key.clone(), metadata.clone())
+        })
+        .await
+    }
+
+    async fn copy(
+        &self,
+        source_bucket: impl Into<String> + Send,
+        source_key: impl Into<String> + Send,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        let (source_bucket, source_key) = (source_bucket.into(), source_key.into());
+        let (bucket, key) = (bucket.into(), key.into());
+        self.retry(|| {
+            self.inner.copy(
+                source_bucket.clone(),
+                source_key.clone(),
+                bucket.clone(),
+                key.clone(),
+            )
+        })
+        .await
+    }
+}
+
+// `PutObjectRequest` isn't `Clone` due to its body, which the file uploads
+// replace anyway
+fn clone_put_request(request: &PutObjectRequest) -> PutObjectRequest {
+    PutObjectRequest {
+        acl: request.acl.clone(),
+        body: None,
+        bucket: request.bucket.clone(),
+        bucket_key_enabled: request.bucket_key_enabled,
+        cache_control: request.cache_control.clone(),
+        content_disposition: request.content_disposition.clone(),
+        content_encoding: request.content_encoding.clone(),
+        content_language: request.content_language.clone(),
+        content_length: request.content_length,
+        content_md5: request.content_md5.clone(),
+        content_type: request.content_type.clone(),
+        expected_bucket_owner: request.expected_bucket_owner.clone(),
+        expires: request.expires.clone(),
+        grant_full_control: request.grant_full_control.clone(),
+        grant_read: request.grant_read.clone(),
+        grant_read_acp: request.grant_read_acp.clone(),
+        grant_write_acp: request.grant_write_acp.clone(),
+        key: request.key.clone(),
+        metadata: request.metadata.clone(),
+        object_lock_legal_hold_status: request.object_lock_legal_hold_status.clone(),
+        object_lock_mode: request.object_lock_mode.clone(),
+        object_lock_retain_until_date: request.object_lock_retain_until_date.clone(),
+        request_payer: request.request_payer.clone(),
+        sse_customer_algorithm: request.sse_customer_algorithm.clone(),
+        sse_customer_key: request.sse_customer_key.clone(),
+        sse_customer_key_md5: request.sse_customer_key_md5.clone(),
+        ssekms_encryption_context: request.ssekms_encryption_context.clone(),
+        ssekms_key_id: request.ssekms_key_id.clone(),
+        server_side_encryption: request.server_side_encryption.clone(),
+        storage_class: request.storage_class.clone(),
+        tagging: request.tagging.clone(),
+        website_redirect_location: request.website_redirect_location.clone(),
+    }
+}
@@ -0,0 +1,369 @@
+//! S3 Select: SQL queries over CSV and JSON objects
+//!
+//! Rusoto's `S3::select_object_content` is not implemented (it panics after
+//! sending the request), so queries are sent by a [`SelectClient`] instead,
+//! which decodes the event stream of the response and splits the returned
+//! data into records.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::stream::TryStreamExt;
+//! use s3_ext::{
+//!     client::S3ExtClientBuilder,
+//!     select::{csv_request, SelectEvent},
+//! };
+//!
+//! # async fn example() -> Result<(), s3_ext::error::S3ExtError> {
+//! let client = S3ExtClientBuilder::new().build_select()?;
+//! let request = csv_request("bucket", "people.csv", "SELECT s.name FROM S3Object s");
+//! let mut events = client.select(request).await?;
+//! while let Some(event) = events.try_next().await? {
+//!     if let SelectEvent::Record(record) = event {
+//!         println!("{}", record);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{S3ExtError, S3ExtResult};
+use futures::{
+    ready,
+    stream::{Stream, StreamExt},
+    task::{Context, Poll},
+};
+use log::debug;
+use rusoto_core::{
+    event_stream::{DeserializeEvent, EventStream},
+    param::{Params, ServiceParams},
+    signature::SignedRequest,
+    Client, Region, RusotoError,
+};
+use rusoto_s3::{
+    CSVInput, InputSerialization, JSONInput, JSONOutput, OutputSerialization, Progress,
+    SelectObjectContentError, SelectObjectContentRequest, SelectObjectContentRequestSerializer,
+    Stats,
+};
+use std::{collections::VecDeque, pin::Pin};
+use xml::EventWriter;
+
+const DEFAULT_RECORD_DELIMITER: &str = "\n";
+
+/// Request running `expression` over a CSV object with a header line,
+/// returning records as JSON lines
+pub fn csv_request(
+    bucket: impl Into<String>,
+    key: impl Into<String>,
+    expression: impl Into<String>,
+) -> SelectObjectContentRequest {
+    request(
+        bucket,
+        key,
+        expression,
+        InputSerialization {
+            csv: Some(CSVInput {
+                file_header_info: Some("USE".to_owned()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    )
+}
+
+/// Request running `expression` over an object of JSON lines, returning
+/// records as JSON lines
+pub fn json_request(
+    bucket: impl Into<String>,
+    key: impl Into<String>,
+    expression: impl Into<String>,
+) -> SelectObjectContentRequest {
+    request(
+        bucket,
+        key,
+        expression,
+        InputSerialization {
+            json: Some(JSONInput {
+                type_: Some("LINES".to_owned()),
+            }),
+            ..Default::default()
+        },
+    )
+}
+
+fn request(
+    bucket: impl Into<String>,
+    key: impl Into<String>,
+    expression: impl Into<String>,
+    input_serialization: InputSerialization,
+) -> SelectObjectContentRequest {
+    SelectObjectContentRequest {
+        bucket: bucket.into(),
+        key: key.into(),
+        expression: expression.into(),
+        expression_type: "SQL".to_owned(),
+        input_serialization,
+        output_serialization: OutputSerialization {
+            json: Some(JSONOutput {
+                record_delimiter: Some(DEFAULT_RECORD_DELIMITER.to_owned()),
+            }),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Client sending S3 Select requests
+///
+/// Created from the same parts as an `S3Client`, e.g. using
+/// [`S3ExtClientBuilder::build_select`](crate::client::S3ExtClientBuilder::build_select).
+#[derive(Clone)]
+pub struct SelectClient {
+    client: Client,
+    region: Region,
+}
+
+impl SelectClient {
+    /// Create a client sending requests to `region` using `client`
+    pub fn new(client: Client, region: Region) -> Self {
+        Self { client, region }
+    }
+
+    /// Run the query described by `request`
+    ///
+    /// Errors returned by S3 before the results start streaming are
+    /// returned here, errors occurring afterwards by the stream.
+    pub async fn select(&self, request: SelectObjectContentRequest) -> S3ExtResult<SelectStream> {
+        let delimiter = record_delimiter(&request);
+        let (bucket, key) = (request.bucket.clone(), request.key.clone());
+        self.send(request)
+            .await
+            .map(|events| SelectStream {
+                events,
+                delimiter: delimiter.into_bytes(),
+                buf: Vec::new(),
+                pending: VecDeque::new(),
+                done: false,
+            })
+            .map_err(|e| S3ExtError::from(e).with_object(bucket, key))
+    }
+
+    async fn send(
+        &self,
+        input: SelectObjectContentRequest,
+    ) -> Result<EventStream<Event>, Box<RusotoError<SelectObjectContentError>>> {
+        let path = format!("/{}/{}", input.bucket, input.key);
+        let mut request = SignedRequest::new("POST", "s3", &self.region, &path);
+        request.add_optional_header(
+            "x-amz-expected-bucket-owner",
+            input.expected_bucket_owner.as_ref(),
+        );
+        request.add_optional_header(
+            "x-amz-server-side-encryption-customer-algorithm",
+            input.sse_customer_algorithm.as_ref(),
+        );
+        request.add_optional_header(
+            "x-amz-server-side-encryption-customer-key",
+            input.sse_customer_key.as_ref(),
+        );
+        request.add_optional_header(
+            "x-amz-server-side-encryption-customer-key-MD5",
+            input.sse_customer_key_md5.as_ref(),
+        );
+        let mut params = Params::new();
+        params.put_key("select");
+        params.put("select-type", "2");
+        request.set_params(params);
+        let mut writer = EventWriter::new(Vec::new());
+        SelectObjectContentRequestSerializer::serialize(
+            &mut writer,
+            "SelectObjectContentRequest",
+            &input,
+            "http://s3.amazonaws.com/doc/2006-03-01/",
+        )
+        .map_err(|e| RusotoError::Validation(e.to_string()))?;
+        request.set_payload(Some(writer.into_inner()));
+
+        debug!("selecting from {}", path);
+        let mut response = self.client.sign_and_dispatch(request).await?;
+        if !response.status.is_success() {
+            let response = response.buffer().await.map_err(RusotoError::HttpDispatch)?;
+            return Err(Box::new(SelectObjectContentError::from_response(response)));
+        }
+        Ok(EventStream::new(response))
+    }
+}
+
+fn record_delimiter(request: &SelectObjectContentRequest) -> String {
+    let output = &request.output_serialization;
+    output
+        .json
+        .as_ref()
+        .and_then(|json| json.record_delimiter.clone())
+        .or_else(|| {
+            output
+                .csv
+                .as_ref()
+                .and_then(|csv| csv.record_delimiter.clone())
+        })
+        .unwrap_or_else(|| DEFAULT_RECORD_DELIMITER.to_owned())
+}
+
+/// Event of an S3 Select response
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectEvent {
+    /// Record, without the record delimiter
+    Record(String),
+    /// Progress of the query, sent periodically if requested using
+    /// `SelectObjectContentRequest::request_progress`
+    Progress(Progress),
+    /// Statistics of the query, sent once all records are returned
+    Stats(Stats),
+    /// Query completed, last event
+    End,
+}
+
+/// Event as framed by S3
+enum Event {
+    Records(Vec<u8>),
+    Progress(Progress),
+    Stats(Stats),
+    End,
+    Other,
+}
+
+impl DeserializeEvent for Event {
+    fn deserialize_event(event_type: &str, data: &[u8]) -> Result<Self, RusotoError<()>> {
+        Ok(match event_type {
+            "Records" => Event::Records(data.to_vec()),
+            "Progress" => {
+                let (bytes_scanned, bytes_processed, bytes_returned) = details(data);
+                Event::Progress(Progress {
+                    bytes_processed,
+                    bytes_returned,
+                    bytes_scanned,
+                })
+            }
+            "Stats" => {
+                let (bytes_scanned, bytes_processed, bytes_returned) = details(data);
+                Event::Stats(Stats {
+                    bytes_processed,
+                    bytes_returned,
+                    bytes_scanned,
+                })
+            }
+            "End" => Event::End,
+            _ => Event::Other,
+        })
+    }
+}
+
+// Byte counts of a progress or stats event
+fn details(data: &[u8]) -> (Option<i64>, Option<i64>, Option<i64>) {
+    let body = String::from_utf8_lossy(data);
+    let element = |name: &str| {
+        let start = body.find(&format!("<{}>", name))? + name.len() + 2;
+        let end = start + body[start..].find(&format!("</{}>", name))?;
+        body[start..end].trim().parse().ok()
+    };
+    (
+        element("BytesScanned"),
+        element("BytesProcessed"),
+        element("BytesReturned"),
+    )
+}
+
+/// Stream of the events of an S3 Select response
+///
+/// Records may be split across several events by S3, they are reassembled
+/// and split at the record delimiter of the output serialization.
+pub struct SelectStream {
+    events: EventStream<Event>,
+    delimiter: Vec<u8>,
+    buf: Vec<u8>,
+    pending: VecDeque<SelectEvent>,
+    done: bool,
+}
+
+impl SelectStream {
+    // Queue the complete records in `buf`
+    fn split_records(&mut self) -> S3ExtResult<()> {
+        let delimiter = &self.delimiter;
+        let mut start = 0;
+        while let Some(pos) = self.buf[start..]
+            .windows(delimiter.len())
+            .position(|window| window == &delimiter[..])
+        {
+            let record = record(&self.buf[start..start + pos])?;
+            self.pending.push_back(SelectEvent::Record(record));
+            start += pos + delimiter.len();
+        }
+        self.buf.drain(..start);
+        Ok(())
+    }
+}
+
+fn record(bytes: &[u8]) -> S3ExtResult<String> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| S3ExtError::Other("record is not valid UTF-8"))
+}
+
+impl Stream for SelectStream {
+    type Item = S3ExtResult<SelectEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+            if self.done {
+                return Poll::Ready(None);
+            }
+            let result = match ready!(self.events.poll_next_unpin(cx)) {
+                Some(Ok(Event::Records(data))) => {
+                    self.buf.extend_from_slice(&data);
+                    self.split_records()
+                }
+                Some(Ok(Event::Progress(progress))) => {
+                    self.pending.push_back(SelectEvent::Progress(progress));
+                    Ok(())
+                }
+                Some(Ok(Event::Stats(stats))) => {
+                    self.pending.push_back(SelectEvent::Stats(stats));
+                    Ok(())
+                }
+                Some(Ok(Event::End)) => {
+                    self.done = true;
+                    let buf = std::mem::take(&mut self.buf);
+                    let last = if buf.is_empty() {
+                        Ok(None)
+                    } else {
+                        record(&buf).map(Some)
+                    };
+                    last.map(|last| {
+                        self.pending.extend(last.map(SelectEvent::Record));
+                        self.pending.push_back(SelectEvent::End);
+                    })
+                }
+                Some(Ok(Event::Other)) => Ok(()),
+                Some(Err(e)) => Err(into_select_error(e).into()),
+                None => Err(S3ExtError::Other("S3 Select response ended prematurely")),
+            };
+            if let Err(e) = result {
+                self.done = true;
+                return Poll::Ready(Some(Err(e)));
+            }
+        }
+    }
+}
+
+fn into_select_error(error: RusotoError<()>) -> RusotoError<SelectObjectContentError> {
+    match error {
+        RusotoError::HttpDispatch(e) => RusotoError::HttpDispatch(e),
+        RusotoError::Credentials(e) => RusotoError::Credentials(e),
+        RusotoError::Validation(e) => RusotoError::Validation(e),
+        RusotoError::ParseError(e) => RusotoError::ParseError(e),
+        RusotoError::Unknown(e) => RusotoError::Unknown(e),
+        RusotoError::Blocking => RusotoError::Blocking,
+        RusotoError::Service(()) => RusotoError::ParseError("unexpected event".to_owned()),
+    }
+}
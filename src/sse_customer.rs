@@ -0,0 +1,153 @@
+//! Server-side encryption with customer-provided keys (SSE-C)
+//!
+//! Objects encrypted using SSE-C are encrypted by S3 using a key sent along
+//! with every request reading or writing them. Three headers carry the key:
+//! the algorithm, the base64-encoded key and the base64-encoded MD5 digest
+//! of the key. An [`SseCustomerKey`] sets all three consistently on any
+//! [`SseCustomerRequest`].
+//!
+//! Multipart uploads require the key on `CreateMultipartUpload` and on every
+//! `UploadPart`. [`S3Ext::upload_multipart`](crate::S3Ext::upload_multipart)
+//! and related methods pass the key of the `PutObjectRequest` on to both.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+//! use s3_ext::{sse_customer::SseCustomerKey, S3Ext};
+//!
+//! let key = SseCustomerKey::random();
+//!
+//! let mut request = PutObjectRequest {
+//!     bucket: "bucket".to_owned(),
+//!     key: "secret".to_owned(),
+//!     ..Default::default()
+//! };
+//! key.apply(&mut request);
+//! client
+//!     .upload_from_file_multipart("secret.bin", request, 8 * 1024 * 1024)
+//!     .await?;
+//!
+//! let mut request = GetObjectRequest {
+//!     bucket: "bucket".to_owned(),
+//!     key: "secret".to_owned(),
+//!     ..Default::default()
+//! };
+//! key.apply(&mut request);
+//! client.download_to_file(request, "secret.bin").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{S3ExtError, S3ExtResult};
+use md5::{Digest, Md5};
+use rand::RngCore;
+use rusoto_s3::{
+    CreateMultipartUploadRequest, GetObjectRequest, HeadObjectRequest, PutObjectRequest,
+    UploadPartCopyRequest, UploadPartRequest,
+};
+use std::fmt;
+
+/// Algorithm of SSE-C, the only one supported by S3
+pub const SSE_CUSTOMER_ALGORITHM: &str = "AES256";
+
+/// Length of SSE-C keys in bytes, 256 bits
+pub const KEY_LEN: usize = 32;
+
+/// Key used for server-side encryption with customer-provided keys
+///
+/// The key itself is omitted from the `Debug` output.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SseCustomerKey {
+    key: [u8; KEY_LEN],
+}
+
+impl SseCustomerKey {
+    /// Use the raw bytes of `key`
+    pub fn new(key: [u8; KEY_LEN]) -> Self {
+        SseCustomerKey { key }
+    }
+
+    /// Use the raw bytes of `key`
+    ///
+    /// Fails with `S3ExtError::Other` unless `key` is 32 bytes long.
+    pub fn from_slice(key: &[u8]) -> S3ExtResult<Self> {
+        if key.len() != KEY_LEN {
+            return Err(S3ExtError::Other("SSE-C keys must be 32 bytes long"));
+        }
+        let mut bytes = [0; KEY_LEN];
+        bytes.copy_from_slice(key);
+        Ok(Self::new(bytes))
+    }
+
+    /// Generate a random key
+    pub fn random() -> Self {
+        let mut key = [0; KEY_LEN];
+        rand::thread_rng().fill_bytes(&mut key);
+        Self::new(key)
+    }
+
+    /// Raw bytes of the key
+    pub fn as_bytes(&self) -> &[u8; KEY_LEN] {
+        &self.key
+    }
+
+    /// Key as sent in header `x-amz-server-side-encryption-customer-key`
+    pub fn encoded(&self) -> String {
+        base64::encode(self.key)
+    }
+
+    /// Digest as sent in header
+    /// `x-amz-server-side-encryption-customer-key-MD5`
+    pub fn encoded_md5(&self) -> String {
+        base64::encode(Md5::digest(&self.key))
+    }
+
+    /// Set the SSE-C fields of `request` to use this key
+    pub fn apply<R: SseCustomerRequest>(&self, request: &mut R) {
+        request.set_sse_customer(
+            SSE_CUSTOMER_ALGORITHM.to_owned(),
+            self.encoded(),
+            self.encoded_md5(),
+        );
+    }
+}
+
+impl fmt::Debug for SseCustomerKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SseCustomerKey")
+            .field("md5", &self.encoded_md5())
+            .finish()
+    }
+}
+
+/// Request carrying the SSE-C headers
+pub trait SseCustomerRequest {
+    /// Set algorithm, base64-encoded key and base64-encoded MD5 digest of
+    /// the key
+    fn set_sse_customer(&mut self, algorithm: String, key: String, key_md5: String);
+}
+
+macro_rules! impl_sse_customer_request {
+    ($($request:ty),*) => {
+        $(
+            impl SseCustomerRequest for $request {
+                fn set_sse_customer(&mut self, algorithm: String, key: String, key_md5: String) {
+                    self.sse_customer_algorithm = Some(algorithm);
+                    self.sse_customer_key = Some(key);
+                    self.sse_customer_key_md5 = Some(key_md5);
+                }
+            }
+        )*
+    };
+}
+
+impl_sse_customer_request!(
+    PutObjectRequest,
+    CreateMultipartUploadRequest,
+    UploadPartRequest,
+    UploadPartCopyRequest,
+    GetObjectRequest,
+    HeadObjectRequest
+);
@@ -0,0 +1,126 @@
+//! Statistics of individual transfers
+//!
+//! See the `*_with_stats` methods of [`S3Ext`](crate::S3Ext).
+
+use std::{
+    cell::Cell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tokio::io::{self, ReadBuf};
+
+/// Statistics of a completed transfer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransferStats {
+    /// Bytes uploaded or downloaded
+    pub bytes: u64,
+    /// Parts uploaded, `1` for transfers not using multi-part upload
+    pub parts: u64,
+    /// Wall-clock duration of the transfer, including retries
+    pub duration: Duration,
+    /// Requests retried by a [`RetryingS3Client`](crate::retry::RetryingS3Client)
+    pub retries: u32,
+}
+
+impl TransferStats {
+    /// Average throughput in bytes per second
+    pub fn throughput(&self) -> f64 {
+        let secs = self.duration.as_secs_f64();
+        if secs > 0.0 {
+            self.bytes as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    parts: Cell<u64>,
+    retries: Cell<u32>,
+}
+
+tokio::task_local! {
+    static COUNTERS: Counters;
+}
+
+/// Count an uploaded part for the enclosing [`collect`], if any
+pub(crate) fn record_part() {
+    let _ = COUNTERS.try_with(|c| c.parts.set(c.parts.get() + 1));
+}
+
+/// Count a retry for the enclosing [`collect`], if any
+pub(crate) fn record_retry() {
+    let _ = COUNTERS.try_with(|c| c.retries.set(c.retries.get() + 1));
+}
+
+/// Run `future`, collecting the parts and retries it records
+///
+/// `bytes` is left at zero for the caller to fill in.
+pub(crate) async fn collect<F, T>(future: F) -> (T, TransferStats)
+where
+    F: Future<Output = T>,
+{
+    let start = Instant::now();
+    COUNTERS
+        .scope(Counters::default(), async {
+            let result = future.await;
+            let stats = COUNTERS.with(|c| TransferStats {
+                bytes: 0,
+                parts: c.parts.get(),
+                duration: start.elapsed(),
+                retries: c.retries.get(),
+            });
+            (result, stats)
+        })
+        .await
+}
+
+/// Reader or writer counting the bytes passing through
+pub(crate) struct Counting<'a, T> {
+    inner: &'a mut T,
+    pub(crate) bytes: u64,
+}
+
+impl<'a, T> Counting<'a, T> {
+    pub(crate) fn new(inner: &'a mut T) -> Self {
+        Self { inner, bytes: 0 }
+    }
+}
+
+impl<T: io::AsyncRead + Unpin> io::AsyncRead for Counting<'_, T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let result = Pin::new(&mut *self.inner).poll_read(cx, buf);
+        self.bytes += (buf.filled().len() - filled) as u64;
+        result
+    }
+}
+
+impl<T: io::AsyncWrite + Unpin> io::AsyncWrite for Counting<'_, T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut *self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = result {
+            self.bytes += n as u64;
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.inner).poll_shutdown(cx)
+    }
+}
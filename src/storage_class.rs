@@ -0,0 +1,174 @@
+//! Change the storage class of objects
+//!
+//! S3 has no operation changing the storage class of an object in place,
+//! the object is copied onto itself with the new storage class instead. See
+//! [`S3Ext::set_storage_class`](crate::S3Ext::set_storage_class),
+//! [`S3Ext::plan_storage_class`](crate::S3Ext::plan_storage_class) and
+//! [`S3Ext::change_storage_class`](crate::S3Ext::change_storage_class).
+//!
+//! # Caveats
+//!
+//! Copying keeps the metadata and tags of an object but resets its ACL to
+//! `private` and its encryption to the bucket default. Objects larger than
+//! 5 GiB can't be copied in a single request and archived objects must be
+//! restored first, both fail.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::S3Ext;
+//!
+//! let plan = client
+//!     .plan_storage_class("bucket", "logs/2019/", "GLACIER_IR")
+//!     .await?;
+//! println!("{} objects, {} bytes to transition", plan.keys.len(), plan.bytes);
+//! let report = client
+//!     .change_storage_class("bucket", "logs/2019/", "GLACIER_IR")
+//!     .await?;
+//! assert!(report.is_success());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    error::{S3ExtError, S3ExtResult},
+    S3Ext,
+};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rusoto_s3::{CopyObjectRequest, Object};
+
+/// Number of objects copied concurrently by `change_storage_class`
+pub const TRANSITION_CONCURRENCY: usize = 16;
+
+// Characters escaped in the key of `x-amz-copy-source`
+const COPY_SOURCE_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'/');
+
+/// Objects below a prefix whose storage class would be changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransitionPlan {
+    /// Keys of the objects to copy
+    pub keys: Vec<String>,
+    /// Total size of the objects to copy
+    pub bytes: i64,
+    /// Keys of the objects already in the target storage class
+    pub skipped: Vec<String>,
+}
+
+/// Outcome of changing the storage class of the objects below a prefix
+#[derive(Debug, Default)]
+pub struct TransitionReport {
+    /// Keys of the objects copied successfully
+    pub changed: Vec<String>,
+    /// Keys of the objects already in the target storage class
+    pub skipped: Vec<String>,
+    /// Keys of the objects that couldn't be copied, with the error
+    pub failed: Vec<(String, S3ExtError)>,
+}
+
+impl TransitionReport {
+    /// Whether all objects are in the target storage class now
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Value of `x-amz-copy-source` for `key` in `bucket`
+pub(crate) fn copy_source(bucket: &str, key: &str) -> String {
+    format!(
+        "{}/{}",
+        bucket,
+        utf8_percent_encode(key, COPY_SOURCE_ENCODE_SET)
+    )
+}
+
+// Listings omit the storage class of `STANDARD` objects on some providers
+fn is_in_class(object: &Object, storage_class: &str) -> bool {
+    object.storage_class.as_deref().unwrap_or("STANDARD") == storage_class
+}
+
+pub(crate) async fn set_storage_class(
+    client: &Backend,
+    bucket: String,
+    key: String,
+    storage_class: &str,
+) -> S3ExtResult<()> {
+    let request = CopyObjectRequest {
+        copy_source: copy_source(&bucket, &key),
+        bucket: bucket.clone(),
+        key: key.clone(),
+        metadata_directive: Some("COPY".to_owned()),
+        storage_class: Some(storage_class.to_owned()),
+        ..Default::default()
+    };
+    client
+        .copy_object(request)
+        .await
+        .map(drop)
+        .map_err(|e| S3ExtError::from(e).with_object(bucket, key))
+}
+
+pub(crate) async fn plan_storage_class<C>(
+    client: &C,
+    bucket: String,
+    prefix: String,
+    storage_class: &str,
+) -> S3ExtResult<TransitionPlan>
+where
+    C: S3Ext + ?Sized,
+{
+    let mut objects = client.stream_objects_with_prefix(bucket, prefix);
+    let mut plan = TransitionPlan::default();
+    while let Some(object) = objects.try_next().await? {
+        let key = object
+            .key
+            .clone()
+            .ok_or(S3ExtError::Other("response is missing key"))?;
+        if is_in_class(&object, storage_class) {
+            plan.skipped.push(key);
+        } else {
+            plan.bytes += object.size.unwrap_or_default();
+            plan.keys.push(key);
+        }
+    }
+    Ok(plan)
+}
+
+pub(crate) async fn change_storage_class<C>(
+    client: &C,
+    bucket: String,
+    prefix: String,
+    storage_class: &str,
+) -> S3ExtResult<TransitionReport>
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    let plan = plan_storage_class(client, bucket.clone(), prefix, storage_class).await?;
+    let bucket = &bucket;
+    let mut results = stream::iter(plan.keys)
+        .map(|key| async move {
+            let result = client.set_storage_class(bucket, &key, storage_class).await;
+            (key, result)
+        })
+        .buffer_unordered(TRANSITION_CONCURRENCY);
+    let mut report = TransitionReport {
+        skipped: plan.skipped,
+        ..Default::default()
+    };
+    while let Some((key, result)) = results.next().await {
+        match result {
+            Ok(()) => report.changed.push(key),
+            Err(e) => report.failed.push((key, e)),
+        }
+    }
+    report.changed.sort();
+    report.failed.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(report)
+}
@@ -0,0 +1,133 @@
+//! Copy objects between clients by streaming them through
+//!
+//! `CopyObject` requires a single set of credentials allowed to read the
+//! source and write the target, which isn't available when copying between
+//! accounts without cross-account permissions. [`copy_via_stream`] instead
+//! downloads the object using one client and uploads it using another one,
+//! piping the body of the `GET` request into a multipart upload. Only one
+//! part is held in memory at a time.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(
+//! #     source: rusoto_s3::S3Client,
+//! #     target: rusoto_s3::S3Client,
+//! # ) -> Result<(), s3_ext::error::S3ExtError> {
+//! use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+//! use s3_ext::stream_copy::copy_via_stream;
+//!
+//! let src = GetObjectRequest {
+//!     bucket: "account-a".to_owned(),
+//!     key: "exports/data.csv".to_owned(),
+//!     ..Default::default()
+//! };
+//! let dst = PutObjectRequest {
+//!     bucket: "account-b".to_owned(),
+//!     key: "imports/data.csv".to_owned(),
+//!     ..Default::default()
+//! };
+//! copy_via_stream(&source, src, &target, dst).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::S3ExtResult,
+    upload::{MAX_PARTS, MIN_PART_SIZE},
+    S3Ext,
+};
+use rusoto_s3::{CompleteMultipartUploadOutput, GetObjectRequest, PutObjectRequest};
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, DuplexStream, ReadBuf};
+
+// Bytes buffered between download and upload
+pub(crate) const PIPE_SIZE: usize = 64 * 1024;
+
+/// Copy object `src` read using `src_client` to `dst` written using
+/// `dst_client`
+///
+/// The object is uploaded using multipart upload, the part size is chosen
+/// from the size of the source object. The upload is aborted if the download
+/// fails.
+pub async fn copy_via_stream<S, D>(
+    src_client: &S,
+    src: GetObjectRequest,
+    dst_client: &D,
+    dst: PutObjectRequest,
+) -> S3ExtResult<CompleteMultipartUploadOutput>
+where
+    S: S3Ext + Sync + ?Sized,
+    D: S3Ext + ?Sized,
+{
+    let size = src_client
+        .object_attributes(src.bucket.clone(), src.key.clone())
+        .await?
+        .size as u64;
+    let part_size = MIN_PART_SIZE.max((size.saturating_sub(1) / MAX_PARTS as u64 + 1) as usize);
+
+    let (mut writer, reader) = tokio::io::duplex(PIPE_SIZE);
+    let failed = Arc::new(AtomicBool::new(false));
+    let mut reader = PipeReader::new(reader, failed.clone());
+    let download = async move {
+        let result = src_client.download(src, &mut writer).await;
+        // set before the upload sees the end of the pipe
+        failed.store(result.is_err(), Ordering::SeqCst);
+        drop(writer);
+        result
+    };
+    let upload = dst_client.upload_multipart(&mut reader, dst, part_size);
+    let (uploaded, downloaded) = futures::join!(upload, download);
+    match (uploaded, downloaded) {
+        (Ok(output), Ok(_)) => Ok(output),
+        // upload failed as the download did
+        (_, Err(e)) if reader.reported => Err(e),
+        (Err(e), _) | (_, Err(e)) => Err(e),
+    }
+}
+
+// Read side of the pipe, failing instead of ending if the source failed
+pub(crate) struct PipeReader {
+    inner: DuplexStream,
+    failed: Arc<AtomicBool>,
+    reported: bool,
+}
+
+impl PipeReader {
+    pub(crate) fn new(inner: DuplexStream, failed: Arc<AtomicBool>) -> Self {
+        PipeReader {
+            inner,
+            failed,
+            reported: false,
+        }
+    }
+}
+
+impl AsyncRead for PipeReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let remaining = buf.remaining();
+        futures::ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+        let end = remaining > 0 && buf.filled().len() == filled;
+        if end && self.failed.load(Ordering::SeqCst) {
+            self.reported = true;
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::Other,
+                "reading the source failed",
+            )));
+        }
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,358 @@
+//! Object tags as maps
+//!
+//! See [`S3Ext::get_tags`](crate::S3Ext::get_tags),
+//! [`S3Ext::put_tags`](crate::S3Ext::put_tags) and
+//! [`S3Ext::delete_tags`](crate::S3Ext::delete_tags). The conversions
+//! below help with the Rusoto requests taking or returning a `TagSet`.
+//!
+//! All objects below a prefix can be tagged at once using
+//! [`S3Ext::tag_prefix`](crate::S3Ext::tag_prefix).
+//!
+//! Tags of buckets, e.g. for cost allocation, are managed the same way using
+//! [`get_bucket_tags`](crate::bucket_config::BucketConfigExt::get_bucket_tags),
+//! [`put_bucket_tags`](crate::bucket_config::BucketConfigExt::put_bucket_tags) and
+//! [`delete_bucket_tags`](crate::bucket_config::BucketConfigExt::delete_bucket_tags) of
+//! [`BucketConfigExt`](crate::bucket_config::BucketConfigExt).
+//!
+//! # Tagging on upload
+//!
+//! Uploads take the tags of the new object as a query string in
+//! `PutObjectRequest::tagging`. [`TagsBuilder`] validates the tags against
+//! the limits of S3 and encodes them, the same limits are checked before
+//! tags are put by the methods above.
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use rusoto_s3::PutObjectRequest;
+//! use s3_ext::{tagging::TagsBuilder, S3Ext};
+//!
+//! let request = PutObjectRequest {
+//!     bucket: "bucket".to_owned(),
+//!     key: "report.pdf".to_owned(),
+//!     tagging: Some(
+//!         TagsBuilder::new()
+//!             .tag("project", "travel & leisure")
+//!             .tag("stage", "draft")
+//!             .encode()?,
+//!     ),
+//!     ..Default::default()
+//! };
+//! client.upload_from_file("report.pdf", request).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    error::{S3ExtError, S3ExtResult},
+    S3Ext,
+};
+use futures::stream::{StreamExt, TryStreamExt};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rusoto_s3::{
+    DeleteBucketTaggingRequest, DeleteObjectTaggingRequest, GetBucketTaggingRequest,
+    GetObjectTaggingRequest, PutBucketTaggingRequest, PutObjectTaggingRequest, Tag, Tagging,
+};
+use std::collections::BTreeMap;
+
+/// Tags of an object, by key
+pub type Tags = BTreeMap<String, String>;
+
+/// Number of objects tagged concurrently by `tag_prefix`
+pub const TAG_CONCURRENCY: usize = 16;
+
+/// Maximum number of tags of an object
+pub const MAX_TAGS: usize = 10;
+
+/// Maximum number of tags of a bucket
+pub const MAX_BUCKET_TAGS: usize = 50;
+
+/// Maximum length of tag keys, in characters
+pub const MAX_KEY_LENGTH: usize = 128;
+
+/// Maximum length of tag values, in characters
+pub const MAX_VALUE_LENGTH: usize = 256;
+
+const QUERY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// How new tags are combined with the existing tags of an object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagMode {
+    /// Replace all existing tags
+    Replace,
+    /// Keep existing tags, new tags take precedence on conflicting keys
+    Merge,
+}
+
+/// Outcome of tagging the objects below a prefix
+#[derive(Debug, Default)]
+pub struct TagReport {
+    /// Keys of the objects tagged successfully
+    pub tagged: Vec<String>,
+    /// Keys of the objects that couldn't be tagged, with the error
+    pub failed: Vec<(String, S3ExtError)>,
+}
+
+impl TagReport {
+    /// Whether all objects were tagged
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Tags of an object, validated and encoded for `PutObjectRequest::tagging`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagsBuilder {
+    tags: Tags,
+}
+
+impl TagsBuilder {
+    /// Builder without tags
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add tag `key`, replacing a tag with the same key
+    ///
+    /// Validated by [`build`](Self::build) and [`encode`](Self::encode).
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.insert(key.into(), value.into());
+        self
+    }
+
+    /// Validate the tags, returning them as a map
+    ///
+    /// Fails with `S3ExtError::InvalidTag` if there are more than
+    /// [`MAX_TAGS`] tags, if keys are empty, longer than [`MAX_KEY_LENGTH`]
+    /// or start with the reserved prefix `aws:`, or if values are longer
+    /// than [`MAX_VALUE_LENGTH`].
+    pub fn build(self) -> S3ExtResult<Tags> {
+        validate(&self.tags, MAX_TAGS)?;
+        Ok(self.tags)
+    }
+
+    /// Validate the tags like [`build`](Self::build), returning them
+    /// encoded as a query string, e.g. `project=travel%20%26%20leisure`
+    pub fn encode(self) -> S3ExtResult<String> {
+        Ok(encode_tags(&self.build()?))
+    }
+}
+
+impl From<Tags> for TagsBuilder {
+    fn from(tags: Tags) -> Self {
+        TagsBuilder { tags }
+    }
+}
+
+/// Encode `tags` as a query string, as expected by
+/// `PutObjectRequest::tagging`, without validating them
+pub fn encode_tags(tags: &Tags) -> String {
+    tags.iter()
+        .map(|(key, value)| {
+            format!(
+                "{}={}",
+                utf8_percent_encode(key, QUERY_ENCODE_SET),
+                utf8_percent_encode(value, QUERY_ENCODE_SET)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+// Check `tags` against the limits of S3, `max_tags` depends on whether
+// they're tags of an object or a bucket
+pub(crate) fn validate(tags: &Tags, max_tags: usize) -> S3ExtResult<()> {
+    let invalid = |key: &str, reason| {
+        Err(S3ExtError::InvalidTag {
+            key: key.to_owned(),
+            reason,
+        })
+    };
+    if tags.len() > max_tags {
+        return invalid("", "too many tags");
+    }
+    for (key, value) in tags {
+        if key.is_empty() {
+            return invalid(key, "keys must not be empty");
+        }
+        if key.chars().count() > MAX_KEY_LENGTH {
+            return invalid(key, "keys must not exceed 128 characters");
+        }
+        if key.starts_with("aws:") {
+            return invalid(key, "the prefix aws: is reserved");
+        }
+        if value.chars().count() > MAX_VALUE_LENGTH {
+            return invalid(key, "values must not exceed 256 characters");
+        }
+    }
+    Ok(())
+}
+
+/// Convert a `TagSet` into a map
+pub fn tags_from_set(tag_set: Vec<Tag>) -> Tags {
+    tag_set
+        .into_iter()
+        .map(|tag| (tag.key, tag.value))
+        .collect()
+}
+
+/// Convert a map into a `TagSet`
+pub fn tag_set(tags: &Tags) -> Vec<Tag> {
+    tags.iter()
+        .map(|(key, value)| Tag {
+            key: key.clone(),
+            value: value.clone(),
+        })
+        .collect()
+}
+
+pub(crate) async fn get_tags(client: &Backend, bucket: String, key: String) -> S3ExtResult<Tags> {
+    let request = GetObjectTaggingRequest {
+        bucket: bucket.clone(),
+        key: key.clone(),
+        ..Default::default()
+    };
+    client
+        .get_object_tagging(request)
+        .await
+        .map(|output| tags_from_set(output.tag_set))
+        .map_err(|e| S3ExtError::from(e).with_object(bucket, key))
+}
+
+pub(crate) async fn put_tags(
+    client: &Backend,
+    bucket: String,
+    key: String,
+    tags: &Tags,
+) -> S3ExtResult<()> {
+    validate(tags, MAX_TAGS).map_err(|e| e.with_object(&bucket, &key))?;
+    let request = PutObjectTaggingRequest {
+        bucket: bucket.clone(),
+        key: key.clone(),
+        tagging: Tagging {
+            tag_set: tag_set(tags),
+        },
+        ..Default::default()
+    };
+    client
+        .put_object_tagging(request)
+        .await
+        .map(drop)
+        .map_err(|e| S3ExtError::from(e).with_object(bucket, key))
+}
+
+pub(crate) async fn delete_tags(client: &Backend, bucket: String, key: String) -> S3ExtResult<()> {
+    let request = DeleteObjectTaggingRequest {
+        bucket: bucket.clone(),
+        key: key.clone(),
+        ..Default::default()
+    };
+    client
+        .delete_object_tagging(request)
+        .await
+        .map(drop)
+        .map_err(|e| S3ExtError::from(e).with_object(bucket, key))
+}
+
+pub(crate) async fn get_bucket_tags(client: &Backend, bucket: String) -> S3ExtResult<Tags> {
+    let request = GetBucketTaggingRequest {
+        bucket,
+        ..Default::default()
+    };
+    match client.get_bucket_tagging(request).await {
+        Ok(output) => Ok(tags_from_set(output.tag_set)),
+        Err(e) => {
+            let e = S3ExtError::from(e);
+            match e.code() {
+                Some("NoSuchTagSet") => Ok(Tags::new()),
+                _ => Err(e),
+            }
+        }
+    }
+}
+
+pub(crate) async fn put_bucket_tags(
+    client: &Backend,
+    bucket: String,
+    tags: &Tags,
+) -> S3ExtResult<()> {
+    validate(tags, MAX_BUCKET_TAGS)?;
+    let request = PutBucketTaggingRequest {
+        bucket,
+        tagging: Tagging {
+            tag_set: tag_set(tags),
+        },
+        ..Default::default()
+    };
+    Ok(client.put_bucket_tagging(request).await?)
+}
+
+pub(crate) async fn delete_bucket_tags(client: &Backend, bucket: String) -> S3ExtResult<()> {
+    let request = DeleteBucketTaggingRequest {
+        bucket,
+        ..Default::default()
+    };
+    Ok(client.delete_bucket_tagging(request).await?)
+}
+
+pub(crate) async fn tag_prefix<C>(
+    client: &C,
+    bucket: String,
+    prefix: String,
+    tags: &Tags,
+    mode: TagMode,
+) -> S3ExtResult<TagReport>
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    // fail once rather than for every object
+    validate(tags, MAX_TAGS)?;
+    let bucket = &bucket;
+    let mut results = client
+        .stream_objects_with_prefix(bucket.clone(), prefix)
+        .map(|object| -> S3ExtResult<_> {
+            let key = object?
+                .key
+                .ok_or(S3ExtError::Other("response is missing key"))?;
+            Ok(async move {
+                let result = tag_object(client, bucket, &key, tags, mode).await;
+                Ok((key, result))
+            })
+        })
+        .try_buffer_unordered(TAG_CONCURRENCY)
+        .boxed();
+    let mut report = TagReport::default();
+    while let Some((key, result)) = results.try_next().await? {
+        match result {
+            Ok(()) => report.tagged.push(key),
+            Err(e) => report.failed.push((key, e)),
+        }
+    }
+    report.tagged.sort();
+    report.failed.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(report)
+}
+
+async fn tag_object<C>(
+    client: &C,
+    bucket: &str,
+    key: &str,
+    tags: &Tags,
+    mode: TagMode,
+) -> S3ExtResult<()>
+where
+    C: S3Ext + Sync + ?Sized,
+{
+    match mode {
+        TagMode::Replace => client.put_tags(bucket, key, tags).await,
+        TagMode::Merge => {
+            let mut merged = client.get_tags(bucket, key).await?;
+            merged.extend(tags.iter().map(|(k, v)| (k.clone(), v.clone())));
+            client.put_tags(bucket, key, &merged).await
+        }
+    }
+}
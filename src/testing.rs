@@ -0,0 +1,295 @@
+//! Harness for integration tests against MinIO
+//!
+//! [`Minio`] connects to the MinIO server at `S3_ENDPOINT` or, if unset,
+//! starts a `minio` binary found in `PATH` (or at `MINIO_BIN`) for the
+//! duration of the tests. [`TestBucket`]s are uniquely named and removed
+//! including their content when dropped.
+//!
+//! Requires the `testing` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use s3_ext::testing::Minio;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> Result<(), s3_ext::error::S3ExtError> {
+//! let minio = Minio::from_env()?;
+//! let bucket = minio.bucket().await?;
+//! // ... use bucket.client() and bucket.name() ...
+//! bucket.cleanup().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    client::{Credentials, S3ExtClientBuilder},
+    error::{S3ExtError, S3ExtResult},
+    S3Ext,
+};
+use futures::stream::TryStreamExt;
+use log::{debug, warn};
+use rand::{distributions::Alphanumeric, Rng};
+use rusoto_s3::{
+    AbortMultipartUploadRequest, CreateBucketRequest, DeleteBucketRequest, DeleteObjectRequest,
+    ListMultipartUploadsRequest, S3Client, S3,
+};
+use std::{
+    env, fs,
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Access key used unless `S3_ACCESS_KEY` is set
+pub const ACCESS_KEY: &str = "ANTN35UAENTS5UIAEATD";
+
+/// Secret key used unless `S3_SECRET_KEY` is set
+pub const SECRET_KEY: &str = "TtnuieannGt2rGuie2t8Tt7urarg5nauedRndrur";
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connection to, and possibly process of, a MinIO server
+///
+/// A server started by [`start`](Self::start) is stopped when dropped.
+#[derive(Debug)]
+pub struct Minio {
+    builder: S3ExtClientBuilder,
+    process: Option<Child>,
+    data_dir: Option<PathBuf>,
+}
+
+impl Minio {
+    /// Connect to `S3_ENDPOINT` if set, start a server otherwise
+    pub fn from_env() -> S3ExtResult<Self> {
+        match env::var("S3_ENDPOINT") {
+            Ok(endpoint) => Ok(Self::connect(endpoint)),
+            Err(_) => Self::start(),
+        }
+    }
+
+    /// Connect to the server at `endpoint`
+    pub fn connect(endpoint: impl Into<String>) -> Self {
+        Self {
+            builder: builder(endpoint.into()),
+            process: None,
+            data_dir: None,
+        }
+    }
+
+    /// Start a server on a free local port, storing data in a temporary
+    /// directory
+    pub fn start() -> S3ExtResult<Self> {
+        let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+        let address = format!("127.0.0.1:{}", port);
+        let data_dir = env::temp_dir().join(format!("s3-ext-minio-{}", port));
+        fs::create_dir_all(&data_dir)?;
+        let binary = env::var("MINIO_BIN").unwrap_or_else(|_| "minio".to_owned());
+        debug!("starting {} on {}", binary, address);
+        let process = Command::new(binary)
+            .arg("server")
+            .arg(&data_dir)
+            .arg("--address")
+            .arg(&address)
+            .env("MINIO_ROOT_USER", access_key())
+            .env("MINIO_ROOT_PASSWORD", secret_key())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let minio = Self {
+            builder: builder(format!("http://{}", address)),
+            process: Some(process),
+            data_dir: Some(data_dir),
+        };
+
+        let start = Instant::now();
+        while TcpStream::connect(&address).is_err() {
+            if start.elapsed() > STARTUP_TIMEOUT {
+                return Err(S3ExtError::Other("MinIO did not start in time"));
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        Ok(minio)
+    }
+
+    /// Builder for clients of the server
+    pub fn builder(&self) -> S3ExtClientBuilder {
+        self.builder.clone()
+    }
+
+    /// Client of the server
+    pub fn client(&self) -> S3ExtResult<S3Client> {
+        self.builder.clone().build()
+    }
+
+    /// Create a uniquely named bucket
+    pub async fn bucket(&self) -> S3ExtResult<TestBucket> {
+        TestBucket::create(self.builder.clone()).await
+    }
+}
+
+impl Drop for Minio {
+    fn drop(&mut self) {
+        if let Some(process) = &mut self.process {
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+        if let Some(data_dir) = &self.data_dir {
+            let _ = fs::remove_dir_all(data_dir);
+        }
+    }
+}
+
+fn access_key() -> String {
+    env::var("S3_ACCESS_KEY").unwrap_or_else(|_| ACCESS_KEY.to_owned())
+}
+
+fn secret_key() -> String {
+    env::var("S3_SECRET_KEY").unwrap_or_else(|_| SECRET_KEY.to_owned())
+}
+
+fn builder(endpoint: String) -> S3ExtClientBuilder {
+    S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", endpoint)
+        .credentials(Credentials::Static {
+            access_key: access_key(),
+            secret_key: secret_key(),
+            token: None,
+        })
+}
+
+/// Random bucket name starting with `s3-ext-`
+pub fn unique_bucket_name() -> String {
+    let suffix: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(40)
+        .map(|c| (c as char).to_ascii_lowercase())
+        .collect();
+    format!("s3-ext-{}", suffix)
+}
+
+/// Uniquely named bucket, deleted including its content when dropped
+///
+/// Dropping blocks until the bucket is deleted. Use
+/// [`cleanup`](Self::cleanup) to delete it asynchronously and observe
+/// errors.
+pub struct TestBucket {
+    builder: S3ExtClientBuilder,
+    client: S3Client,
+    name: String,
+    deleted: bool,
+}
+
+impl TestBucket {
+    /// Create a bucket using clients built by `builder`
+    pub async fn create(builder: S3ExtClientBuilder) -> S3ExtResult<Self> {
+        let client = builder.clone().build()?;
+        let name = unique_bucket_name();
+        client
+            .create_bucket(CreateBucketRequest {
+                bucket: name.clone(),
+                ..Default::default()
+            })
+            .await?;
+        debug!("created test bucket {}", name);
+        Ok(Self {
+            builder,
+            client,
+            name,
+            deleted: false,
+        })
+    }
+
+    /// Name of the bucket
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Client for accessing the bucket
+    pub fn client(&self) -> &S3Client {
+        &self.client
+    }
+
+    /// Delete the bucket including its content and pending multi-part
+    /// uploads
+    pub async fn cleanup(mut self) -> S3ExtResult<()> {
+        self.deleted = true;
+        delete_bucket(&self.client, &self.name).await
+    }
+}
+
+impl Drop for TestBucket {
+    fn drop(&mut self) {
+        if self.deleted {
+            return;
+        }
+        // the client is bound to the runtime of the test, which may be gone
+        // or blocked by this call, delete from a runtime of our own
+        let builder = self.builder.clone();
+        let name = self.name.clone();
+        let result = thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            runtime.block_on(async {
+                let client = builder.build()?;
+                delete_bucket(&client, &name).await
+            })
+        })
+        .join();
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("failed to delete test bucket {}: {}", self.name, e),
+            Err(_) => warn!("failed to delete test bucket {}", self.name),
+        }
+    }
+}
+
+async fn delete_bucket(client: &S3Client, bucket: &str) -> S3ExtResult<()> {
+    let uploads = client
+        .list_multipart_uploads(ListMultipartUploadsRequest {
+            bucket: bucket.to_owned(),
+            ..Default::default()
+        })
+        .await?;
+    for upload in uploads.uploads.unwrap_or_default() {
+        if let (Some(key), Some(upload_id)) = (upload.key, upload.upload_id) {
+            client
+                .abort_multipart_upload(AbortMultipartUploadRequest {
+                    bucket: bucket.to_owned(),
+                    key,
+                    upload_id,
+                    ..Default::default()
+                })
+                .await?;
+        }
+    }
+    let mut objects = client.stream_objects(bucket);
+    let mut keys = Vec::new();
+    while let Some(object) = objects.try_next().await? {
+        keys.push(
+            object
+                .key
+                .ok_or(S3ExtError::Other("response is missing key"))?,
+        );
+    }
+    for key in keys {
+        client
+            .delete_object(DeleteObjectRequest {
+                bucket: bucket.to_owned(),
+                key,
+                ..Default::default()
+            })
+            .await?;
+    }
+    client
+        .delete_bucket(DeleteBucketRequest {
+            bucket: bucket.to_owned(),
+            ..Default::default()
+        })
+        .await?;
+    debug!("deleted test bucket {}", bucket);
+    Ok(())
+}
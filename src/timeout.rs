@@ -0,0 +1,350 @@
+//! Bound operations by a timeout
+//!
+//! The timeouts configured on the client (see
+//! [`S3ExtClientBuilder::request_timeout`](crate::client::S3ExtClientBuilder::request_timeout))
+//! only cover the response headers. A server stalling while sending the body
+//! makes downloads hang forever. [`TimeoutS3Client`] bounds whole operations,
+//! including transferring the body, instead.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rusoto_core::Region;
+//! use rusoto_s3::{GetObjectRequest, S3Client};
+//! use s3_ext::{error::S3ExtError, S3Ext};
+//! use std::time::Duration;
+//!
+//! # async fn example() -> Result<(), S3ExtError> {
+//! let client = S3Client::new(Region::EuWest1).with_timeout(Duration::from_secs(60));
+//! let request = GetObjectRequest {
+//!     bucket: "bucket".to_owned(),
+//!     key: "key".to_owned(),
+//!     ..Default::default()
+//! };
+//! match client.download_to_file(request, "target").await {
+//!     Err(S3ExtError::Timeout(after)) => println!("gave up after {after:?}"),
+//!     result => println!("{result:?}"),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    body::ObjectBody,
+    compose::ComposePart,
+    diff::DiffStream,
+    error::{S3ExtError, S3ExtResult},
+    framed::FramedObject,
+    iter::{GetObjectStream, ObjectStream, SortedObjectStream},
+    lines::LineStream,
+    metadata::ObjectMetadata,
+    part_size::PartSize,
+    watch::WatchStream,
+    S3Ext,
+};
+use async_trait::async_trait;
+use rusoto_s3::{
+    CompleteMultipartUploadOutput, GetObjectOutput, GetObjectRequest, PutObjectOutput,
+    PutObjectRequest, S3Client,
+};
+use std::{convert::AsRef, future::Future, path::Path, time::Duration};
+use tokio::io;
+use tokio_util::codec::Decoder;
+
+/// Client failing operations with [`S3ExtError::Timeout`] if they don't
+/// complete within a given duration
+///
+/// Obtained using [`S3Ext::with_timeout`]. The timeout applies to the
+/// asynchronous transfer methods. Streams are returned unbounded, their
+/// requests are subject to the timeouts of the client only.
+#[derive(Debug, Clone)]
+pub struct TimeoutS3Client<C = S3Client> {
+    inner: C,
+    timeout: Duration,
+}
+
+impl<C> TimeoutS3Client<C> {
+    /// Wrap `inner`, bounding operations by `timeout`
+    pub fn new(inner: C, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+
+    /// Wrapped client
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Timeout applied to each operation
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    async fn bounded<F, T>(&self, operation: F) -> S3ExtResult<T>
+    where
+        F: Future<Output = S3ExtResult<T>>,
+    {
+        tokio::time::timeout(self.timeout, operation)
+            .await
+            .map_err(|_| S3ExtError::Timeout(self.timeout))?
+    }
+}
+
+#[async_trait]
+impl<C> S3Ext for TimeoutS3Client<C>
+where
+    C: S3Ext + Send + Sync,
+{
+    async fn send_request<T, F, Fut>(
+        &self,
+        operation: &'static str,
+        bucket: String,
+        key: String,
+        request: F,
+    ) -> S3ExtResult<T>
+    where
+        T: Send,
+        F: Fn(Backend, String, String) -> Fut + Send + Sync,
+        Fut: Future<Output = S3ExtResult<T>> + Send,
+    {
+        self.bounded(self.inner.send_request(operation, bucket, key, request))
+            .await
+    }
+
+    async fn download_to_file<F>(
+        &self,
+        source: GetObjectRequest,
+        target: F,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.bounded(self.inner.download_to_file(source, target))
+            .await
+    }
+
+    async fn upload_from_file<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.bounded(self.inner.upload_from_file(source, target))
+            .await
+    }
+
+    async fn upload_from_file_multipart_with<F>(
+        &self,
+        source: F,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        F: AsRef<Path> + Send + Sync,
+    {
+        self.bounded(
+            self.inner
+                .upload_from_file_multipart_with(source, target, part_size),
+        )
+        .await
+    }
+
+    async fn download<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        self.bounded(self.inner.download(source, target)).await
+    }
+
+    async fn download_decompressed<W>(
+        &self,
+        source: GetObjectRequest,
+        target: &mut W,
+    ) -> S3ExtResult<GetObjectOutput>
+    where
+        W: io::AsyncWrite + Unpin + Send,
+    {
+        self.bounded(self.inner.download_decompressed(source, target))
+            .await
+    }
+
+    async fn get_framed<D>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        decoder: D,
+    ) -> S3ExtResult<FramedObject<D>>
+    where
+        D: Decoder + Send,
+    {
+        self.bounded(self.inner.get_framed(bucket, key, decoder))
+            .await
+    }
+
+    async fn get_body_with(&self, source: GetObjectRequest) -> S3ExtResult<ObjectBody> {
+        self.bounded(self.inner.get_body_with(source)).await
+    }
+
+    async fn upload<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+    ) -> S3ExtResult<PutObjectOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.bounded(self.inner.upload(source, target)).await
+    }
+
+    async fn upload_multipart_with<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.bounded(self.inner.upload_multipart_with(source, target, part_size))
+            .await
+    }
+
+    async fn upload_multipart_resumable<R>(
+        &self,
+        source: &mut R,
+        target: PutObjectRequest,
+        part_size: PartSize,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput>
+    where
+        R: io::AsyncRead + Unpin + Send,
+    {
+        self.bounded(
+            self.inner
+                .upload_multipart_resumable(source, target, part_size),
+        )
+        .await
+    }
+
+    #[inline]
+    fn stream_objects(&self, bucket: impl Into<String>) -> ObjectStream {
+        self.inner.stream_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> ObjectStream {
+        self.inner.stream_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_objects_sorted_by_mtime(&self, bucket: impl Into<String>) -> SortedObjectStream {
+        self.inner.stream_objects_sorted_by_mtime(bucket)
+    }
+
+    #[inline]
+    fn stream_objects_with_prefix_sorted_by_mtime(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> SortedObjectStream {
+        self.inner
+            .stream_objects_with_prefix_sorted_by_mtime(bucket, prefix)
+    }
+
+    #[inline]
+    fn diff(
+        &self,
+        source_bucket: impl Into<String>,
+        source_prefix: impl Into<String>,
+        dest_bucket: impl Into<String>,
+        dest_prefix: impl Into<String>,
+    ) -> DiffStream {
+        self.inner
+            .diff(source_bucket, source_prefix, dest_bucket, dest_prefix)
+    }
+
+    #[inline]
+    fn watch_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        period: Duration,
+    ) -> WatchStream {
+        self.inner.watch_prefix(bucket, prefix, period)
+    }
+
+    #[inline]
+    fn stream_get_objects(&self, bucket: impl Into<String>) -> GetObjectStream {
+        self.inner.stream_get_objects(bucket)
+    }
+
+    #[inline]
+    fn stream_get_objects_with_prefix(
+        &self,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> GetObjectStream {
+        self.inner.stream_get_objects_with_prefix(bucket, prefix)
+    }
+
+    #[inline]
+    fn stream_lines(&self, bucket: impl Into<String>, key: impl Into<String>) -> LineStream {
+        self.inner.stream_lines(bucket, key)
+    }
+
+    async fn delete(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.bounded(self.inner.delete(bucket, key)).await
+    }
+
+    async fn compose_object(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        parts: Vec<ComposePart>,
+    ) -> S3ExtResult<CompleteMultipartUploadOutput> {
+        self.bounded(self.inner.compose_object(bucket, key, parts))
+            .await
+    }
+
+    async fn object_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<ObjectMetadata> {
+        self.bounded(self.inner.object_metadata(bucket, key)).await
+    }
+
+    async fn replace_metadata(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        metadata: ObjectMetadata,
+    ) -> S3ExtResult<()> {
+        self.bounded(self.inner.replace_metadata(bucket, key, metadata))
+            .await
+    }
+
+    async fn copy(
+        &self,
+        source_bucket: impl Into<String> + Send,
+        source_key: impl Into<String> + Send,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> S3ExtResult<()> {
+        self.bounded(self.inner.copy(source_bucket, source_key, bucket, key))
+            .await
+    }
+}
@@ -0,0 +1,105 @@
+//! Typed timestamps of outputs
+//!
+//! Rusoto keeps dates as strings, in HTTP date format in the headers of
+//! `GET` and `HEAD` responses and in ISO 8601 format in listings.
+//! [`Timestamps`] parses them into `DateTime<Utc>`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::{timestamps::Timestamps, S3Ext};
+//!
+//! let attributes = client.object_attributes("bucket", "report.csv").await?;
+//! if let Some(modified) = attributes.last_modified_at() {
+//!     println!("modified {}", modified.to_rfc3339());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{attributes::ObjectAttributes, restore::RestoreStatus};
+use chrono::{DateTime, Utc};
+use rusoto_s3::{GetObjectOutput, HeadObjectOutput, Object, ObjectVersion};
+
+/// Parse a date in HTTP date format, e.g. `Fri, 21 Dec 2012 00:00:00 GMT`,
+/// or in ISO 8601 format, e.g. `2012-12-21T00:00:00.000Z`
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value)
+        .or_else(|_| DateTime::parse_from_rfc3339(value))
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Output carrying dates of an object
+///
+/// Dates are `None` if missing, malformed or not part of the output.
+pub trait Timestamps {
+    /// Time of the last modification
+    fn last_modified_at(&self) -> Option<DateTime<Utc>>;
+
+    /// Time a cached copy of the object becomes stale, from the `Expires`
+    /// header
+    fn expires_at(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+
+    /// Time the temporary copy of a restored archived object is removed
+    fn restore_expires_at(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+}
+
+impl Timestamps for GetObjectOutput {
+    fn last_modified_at(&self) -> Option<DateTime<Utc>> {
+        self.last_modified.as_deref().and_then(parse_timestamp)
+    }
+
+    fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.expires.as_deref().and_then(parse_timestamp)
+    }
+
+    fn restore_expires_at(&self) -> Option<DateTime<Utc>> {
+        let status = self
+            .restore
+            .as_deref()
+            .and_then(RestoreStatus::from_header)?;
+        status.expires_at()
+    }
+}
+
+impl Timestamps for HeadObjectOutput {
+    fn last_modified_at(&self) -> Option<DateTime<Utc>> {
+        self.last_modified.as_deref().and_then(parse_timestamp)
+    }
+
+    fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.expires.as_deref().and_then(parse_timestamp)
+    }
+
+    fn restore_expires_at(&self) -> Option<DateTime<Utc>> {
+        let status = self
+            .restore
+            .as_deref()
+            .and_then(RestoreStatus::from_header)?;
+        status.expires_at()
+    }
+}
+
+impl Timestamps for Object {
+    fn last_modified_at(&self) -> Option<DateTime<Utc>> {
+        self.last_modified.as_deref().and_then(parse_timestamp)
+    }
+}
+
+impl Timestamps for ObjectVersion {
+    fn last_modified_at(&self) -> Option<DateTime<Utc>> {
+        self.last_modified.as_deref().and_then(parse_timestamp)
+    }
+}
+
+impl Timestamps for ObjectAttributes {
+    fn last_modified_at(&self) -> Option<DateTime<Utc>> {
+        self.last_modified.as_deref().and_then(parse_timestamp)
+    }
+}
@@ -0,0 +1,51 @@
+//! Optional instrumentation using [`tracing`](https://crates.io/crates/tracing)
+//!
+//! With the `tracing` feature disabled, spans compile to no-ops.
+
+#[cfg(feature = "tracing")]
+macro_rules! span {
+    ($level:ident, $($args:tt)*) => {
+        tracing::span!(tracing::Level::$level, $($args)*)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! span {
+    ($($args:tt)*) => {
+        $crate::trace::Span
+    };
+}
+
+pub(crate) use span;
+
+#[cfg(feature = "tracing")]
+pub(crate) use tracing::{Instrument, Span};
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) use self::noop::{Instrument, Span};
+
+#[cfg(not(feature = "tracing"))]
+mod noop {
+    use std::future::Future;
+
+    #[derive(Clone)]
+    pub(crate) struct Span;
+
+    impl Span {
+        pub(crate) fn current() -> Self {
+            Span
+        }
+
+        pub(crate) fn record<V>(&self, _field: &str, _value: V) -> &Self {
+            self
+        }
+    }
+
+    pub(crate) trait Instrument: Sized {
+        fn instrument(self, _span: Span) -> Self {
+            self
+        }
+    }
+
+    impl<F: Future> Instrument for F {}
+}
@@ -0,0 +1,295 @@
+//! Background transfers with graceful shutdown
+//!
+//! A [`TransferManager`] runs uploads and downloads of files as tokio tasks.
+//! Services stopping, e.g. on `SIGTERM`, call [`TransferManager::shutdown`]:
+//! new transfers are rejected and running ones are given a grace period to
+//! complete. Transfers still running afterwards are cancelled at the next
+//! part boundary, the part being sent completes and the multipart upload is
+//! aborted, so no incomplete uploads are left behind. Partially downloaded
+//! files are removed.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use rusoto_s3::PutObjectRequest;
+//! use s3_ext::transfer::TransferManager;
+//! use std::time::Duration;
+//!
+//! let transfers = TransferManager::new(client);
+//! let request = PutObjectRequest {
+//!     bucket: "bucket".to_owned(),
+//!     key: "dump.sql".to_owned(),
+//!     ..Default::default()
+//! };
+//! transfers.upload_file("/var/backups/dump.sql", request, 16 * 1024 * 1024)?;
+//!
+//! // on SIGTERM
+//! let report = transfers.shutdown(Duration::from_secs(20)).await;
+//! println!("{} transfers aborted", report.aborted.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::{S3ExtError, S3ExtResult},
+    upload, S3Ext,
+};
+use futures::{
+    channel::oneshot,
+    future::{self, FutureExt},
+};
+use parking_lot::Mutex;
+use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+use std::{
+    future::Future,
+    io, mem,
+    path::PathBuf,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    fs::{self, File},
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    task::JoinHandle,
+};
+
+/// Object transferred by a [`TransferManager`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transfer {
+    /// Bucket of the object
+    pub bucket: String,
+    /// Key of the object
+    pub key: String,
+}
+
+/// Outcome of the transfers of a [`TransferManager`]
+#[derive(Debug, Default)]
+pub struct ShutdownReport {
+    /// Transfers completed
+    pub completed: Vec<Transfer>,
+    /// Transfers cancelled by the shutdown
+    pub aborted: Vec<Transfer>,
+    /// Transfers failed for other reasons
+    pub failed: Vec<Transfer>,
+}
+
+/// Result of a transfer, resolving once it completed
+///
+/// The transfer continues if the handle is dropped.
+#[derive(Debug)]
+pub struct TransferHandle {
+    result: oneshot::Receiver<S3ExtResult<()>>,
+}
+
+impl Future for TransferHandle {
+    type Output = S3ExtResult<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.result
+            .poll_unpin(cx)
+            .map(|result| result.unwrap_or(Err(S3ExtError::Other("transfer task panicked"))))
+    }
+}
+
+#[derive(Default)]
+struct State {
+    closed: bool,
+    tasks: Vec<JoinHandle<()>>,
+    report: ShutdownReport,
+}
+
+/// Runner of background transfers using a client of type `C`
+pub struct TransferManager<C> {
+    client: C,
+    state: Arc<Mutex<State>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<C> TransferManager<C>
+where
+    C: S3Ext + Clone + Send + Sync + 'static,
+{
+    /// Run transfers using `client`
+    pub fn new(client: C) -> Self {
+        TransferManager {
+            client,
+            state: Arc::default(),
+            cancelled: Arc::default(),
+        }
+    }
+
+    /// Underlying client
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    /// Number of transfers running
+    pub fn in_flight(&self) -> usize {
+        self.state
+            .lock()
+            .tasks
+            .iter()
+            .filter(|task| !task.is_finished())
+            .count()
+    }
+
+    /// Upload file `source` to `target` in parts of `part_size` bytes
+    ///
+    /// Fails if the manager is shut down.
+    pub fn upload_file(
+        &self,
+        source: impl Into<PathBuf>,
+        mut target: PutObjectRequest,
+        part_size: usize,
+    ) -> S3ExtResult<TransferHandle> {
+        let source = source.into();
+        upload::guess_content_type(&source, &mut target);
+        let transfer = Transfer {
+            bucket: target.bucket.clone(),
+            key: target.key.clone(),
+        };
+        let client = self.client.clone();
+        let cancelled = self.cancelled.clone();
+        self.spawn(transfer, async move {
+            let file = File::open(&source).await?;
+            let mut source = Cancellable {
+                inner: file,
+                cancelled,
+            };
+            client
+                .upload_multipart(&mut source, target, part_size)
+                .await?;
+            Ok(())
+        })
+    }
+
+    /// Download `source` to file `target`
+    ///
+    /// `target` is removed if the download fails. Fails if the manager is
+    /// shut down.
+    pub fn download_to_file(
+        &self,
+        source: GetObjectRequest,
+        target: impl Into<PathBuf>,
+    ) -> S3ExtResult<TransferHandle> {
+        let transfer = Transfer {
+            bucket: source.bucket.clone(),
+            key: source.key.clone(),
+        };
+        let (client, target) = (self.client.clone(), target.into());
+        let cancelled = self.cancelled.clone();
+        self.spawn(transfer, async move {
+            let file = File::create(&target).await?;
+            let mut writer = Cancellable {
+                inner: file,
+                cancelled,
+            };
+            let result = client.download(source, &mut writer).await;
+            if result.is_err() {
+                drop(writer);
+                let _ = fs::remove_file(&target).await;
+            }
+            result.map(|_| ())
+        })
+    }
+
+    fn spawn<F>(&self, transfer: Transfer, job: F) -> S3ExtResult<TransferHandle>
+    where
+        F: Future<Output = S3ExtResult<()>> + Send + 'static,
+    {
+        let mut state = self.state.lock();
+        if state.closed {
+            return Err(S3ExtError::Other("transfer manager is shut down"));
+        }
+        let (sender, result) = oneshot::channel();
+        let (shared, cancelled) = (self.state.clone(), self.cancelled.clone());
+        let task = tokio::spawn(async move {
+            let result = job.await;
+            let mut state = shared.lock();
+            let report = &mut state.report;
+            match &result {
+                Ok(()) => report.completed.push(transfer),
+                Err(_) if cancelled.load(Ordering::SeqCst) => report.aborted.push(transfer),
+                Err(_) => report.failed.push(transfer),
+            }
+            let _ = sender.send(result);
+        });
+        state.tasks.retain(|task| !task.is_finished());
+        state.tasks.push(task);
+        Ok(TransferHandle { result })
+    }
+
+    /// Stop accepting transfers and wait up to `grace` for running ones to
+    /// complete, cancelling the remaining ones
+    ///
+    /// Cancelled uploads complete the part being sent and are aborted
+    /// afterwards. Returns the outcome of all transfers run.
+    pub async fn shutdown(&self, grace: Duration) -> ShutdownReport {
+        let tasks = {
+            let mut state = self.state.lock();
+            state.closed = true;
+            mem::take(&mut state.tasks)
+        };
+        let mut tasks = future::join_all(tasks);
+        if tokio::time::timeout(grace, &mut tasks).await.is_err() {
+            self.cancelled.store(true, Ordering::SeqCst);
+            tasks.await;
+        }
+        mem::take(&mut self.state.lock().report)
+    }
+}
+
+// Reader or writer failing once transfers are cancelled
+struct Cancellable<T> {
+    inner: T,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<T> Cancellable<T> {
+    fn check(&self) -> io::Result<()> {
+        if self.cancelled.load(Ordering::SeqCst) {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                "transfer cancelled by shutdown",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Cancellable<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.check()?;
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Cancellable<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.check()?;
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
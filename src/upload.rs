@@ -1,35 +1,340 @@
-use crate::error::{S3ExtError, S3ExtResult};
+use crate::{
+    backend::Backend,
+    error::{S3ExtError, S3ExtResult},
+    part_size::{PartSize, PartSizer},
+    pool::PART_BUFFERS,
+    stats,
+    stream_copy::{PipeReader, PIPE_SIZE},
+    trace::{span, Instrument, Span},
+    S3Ext,
+};
+use futures::{
+    future,
+    stream::{self, StreamExt},
+};
 use log::{debug, info, warn};
+use md5::{Digest, Md5};
+use rusoto_core::ByteStream;
 use rusoto_s3::{
     AbortMultipartUploadRequest, CompleteMultipartUploadOutput, CompleteMultipartUploadRequest,
-    CompletedMultipartUpload, CompletedPart, CreateMultipartUploadRequest, PutObjectOutput,
-    PutObjectRequest, S3Client, UploadPartRequest, S3,
+    CompletedMultipartUpload, CompletedPart, CreateMultipartUploadRequest,
+    ListMultipartUploadsRequest, ListPartsRequest, Part, PutObjectOutput, PutObjectRequest,
+    UploadPartRequest,
+};
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
-use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
 
+/// Minimum size of all parts but the last one
+pub(crate) const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+/// Maximum size of a part
+pub(crate) const MAX_PART_SIZE: usize = 5 * 1024 * 1024 * 1024;
+/// Maximum number of parts of an upload
+pub(crate) const MAX_PARTS: usize = 10_000;
+/// Maximum size of an object
+pub(crate) const MAX_OBJECT_SIZE: u64 = 5 * 1024 * 1024 * 1024 * 1024;
+
+/// Check `part_size` against the limits of S3, `size` is the size of the
+/// object if known in advance
+pub(crate) fn validate_part_size(part_size: usize, size: Option<u64>) -> S3ExtResult<()> {
+    let invalid = |reason| Err(S3ExtError::InvalidPartSize { part_size, reason });
+    if part_size < MIN_PART_SIZE {
+        return invalid("parts must be at least 5 MiB");
+    }
+    if part_size > MAX_PART_SIZE {
+        return invalid("parts must not exceed 5 GiB");
+    }
+    if let Some(size) = size {
+        if size > MAX_OBJECT_SIZE {
+            return invalid("objects must not exceed 5 TiB");
+        }
+        if size > part_size as u64 * MAX_PARTS as u64 {
+            return invalid("objects must not exceed 10,000 parts");
+        }
+    }
+    Ok(())
+}
+
+/// Set the content type of `target` guessed from the extension of `path`,
+/// unless set already
+///
+/// Without feature `mime_guess`, S3 stores objects as `binary/octet-stream`.
+#[cfg(feature = "mime_guess")]
+pub(crate) fn guess_content_type(path: &Path, target: &mut PutObjectRequest) {
+    if target.content_type.is_none() {
+        target.content_type = mime_guess::from_path(path)
+            .first()
+            .map(|mime| mime.to_string());
+    }
+}
+
+#[cfg(not(feature = "mime_guess"))]
+pub(crate) fn guess_content_type(_path: &Path, _target: &mut PutObjectRequest) {}
+
+pub(crate) async fn upload_files<C, I>(
+    client: &C,
+    files: I,
+    concurrency: usize,
+) -> Vec<(PathBuf, S3ExtResult<PutObjectOutput>)>
+where
+    C: S3Ext + Sync + ?Sized,
+    I: IntoIterator<Item = (PathBuf, PutObjectRequest)>,
+{
+    stream::iter(files)
+        .map(|(source, target)| async move {
+            let result = client.upload_from_file(&source, target).await;
+            (source, result)
+        })
+        // `buffered` doesn't make progress with a limit of 0
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+pub(crate) async fn upload_replicated<C, R>(
+    client: &C,
+    source: &mut R,
+    targets: Vec<PutObjectRequest>,
+) -> Vec<S3ExtResult<CompleteMultipartUploadOutput>>
+where
+    C: S3Ext + Sync + ?Sized,
+    R: AsyncRead + Unpin + Send,
+{
+    let objects: Vec<_> = targets
+        .iter()
+        .map(|target| (target.bucket.clone(), target.key.clone()))
+        .collect();
+    let failed = Arc::new(AtomicBool::new(false));
+    let (mut writers, uploads): (Vec<_>, Vec<_>) = targets
+        .into_iter()
+        .map(|target| {
+            let (writer, reader) = tokio::io::duplex(PIPE_SIZE);
+            let mut reader = PipeReader::new(reader, failed.clone());
+            let upload = async move {
+                client
+                    .upload_multipart_with(&mut reader, target, PartSize::Auto)
+                    .await
+            };
+            (Some(writer), upload)
+        })
+        .unzip();
+    let read = async {
+        let mut buf = vec![0; PIPE_SIZE];
+        let result = loop {
+            // stop reading once all uploads failed
+            if writers.iter().all(Option::is_none) {
+                break Ok(());
+            }
+            let n = match source.read(&mut buf).await {
+                Ok(0) => break Ok(()),
+                Ok(n) => n,
+                Err(e) => break Err(e),
+            };
+            for slot in &mut writers {
+                if let Some(mut writer) = slot.take() {
+                    // otherwise, the upload failed and dropped its end of
+                    // the pipe
+                    if writer.write_all(&buf[..n]).await.is_ok() {
+                        *slot = Some(writer);
+                    }
+                }
+            }
+        };
+        // set before the uploads see the end of their pipes
+        failed.store(result.is_err(), Ordering::SeqCst);
+        writers.clear();
+        result
+    };
+    let (results, read) = futures::join!(future::join_all(uploads), read);
+    match read {
+        Ok(()) => results,
+        // uploads failed as reading the source did
+        Err(e) => results
+            .into_iter()
+            .zip(objects)
+            .map(|(result, (bucket, key))| {
+                result.map_err(|_| {
+                    S3ExtError::from(io::Error::new(e.kind(), e.to_string()))
+                        .with_object(bucket, key)
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Upload `source` using a single request, `size` is the size of `source` if
+/// known in advance
+///
+/// `Content-Length` is set, so the body isn't sent using chunked encoding.
 pub(crate) async fn upload<R>(
-    client: &S3Client,
+    client: &Backend,
     source: &mut R,
     mut target: PutObjectRequest,
+    size: Option<u64>,
 ) -> S3ExtResult<PutObjectOutput>
 where
     R: AsyncRead + Unpin,
 {
-    let mut content = Vec::new();
+    let mut content = Vec::with_capacity(size.unwrap_or_default() as usize);
     source.read_to_end(&mut content).await?;
+    Span::current().record("bytes", content.len() as u64);
+    target.content_length = Some(content.len() as i64);
     target.body = Some(content.into());
     client.put_object(target).await.map_err(|e| e.into())
 }
 
 pub(crate) async fn upload_multipart<R>(
-    client: &S3Client,
+    client: &Backend,
     source: &mut R,
     target: PutObjectRequest,
-    part_size: usize,
+    part_size: PartSize,
+    size: Option<u64>,
 ) -> S3ExtResult<CompleteMultipartUploadOutput>
 where
     R: AsyncRead + Unpin,
 {
+    let sizer = PartSizer::new(part_size, size)?;
+
+    let upload_id = create_multipart_upload(client, &target).await?;
+    let abort = AbortMultipartUploadRequest {
+        bucket: target.bucket.clone(),
+        expected_bucket_owner: target.expected_bucket_owner.clone(),
+        key: target.key.clone(),
+        request_payer: target.request_payer.clone(),
+        upload_id: upload_id.clone(),
+    };
+    let result = upload_multipart_needs_abort_on_error(
+        client,
+        source,
+        target,
+        sizer,
+        &upload_id,
+        &BTreeMap::new(),
+    )
+    .await;
+    abort_on_error(client, abort, result).await
+}
+
+/// Like [`upload_multipart`], continuing the upload in progress for the key
+/// of `target` if there is one
+///
+/// The upload isn't aborted on failure, so it can be continued later.
+pub(crate) async fn upload_multipart_resumable<R>(
+    client: &Backend,
+    source: &mut R,
+    target: PutObjectRequest,
+    part_size: PartSize,
+    size: Option<u64>,
+) -> S3ExtResult<CompleteMultipartUploadOutput>
+where
+    R: AsyncRead + Unpin,
+{
+    let sizer = PartSizer::new(part_size, size)?;
+
+    let (upload_id, uploaded) = match find_multipart_upload(client, &target).await? {
+        Some(upload_id) => {
+            let uploaded = list_parts(client, &target, &upload_id).await?;
+            info!(
+                "resuming multi-part upload {:?} with {} parts uploaded (bucket: {}, key: {})",
+                upload_id,
+                uploaded.len(),
+                target.bucket,
+                target.key
+            );
+            (upload_id, uploaded)
+        }
+        None => (
+            create_multipart_upload(client, &target).await?,
+            BTreeMap::new(),
+        ),
+    };
+    upload_multipart_needs_abort_on_error(client, source, target, sizer, &upload_id, &uploaded)
+        .await
+}
+
+/// ID of the most recently started upload in progress to `target`
+async fn find_multipart_upload(
+    client: &Backend,
+    target: &PutObjectRequest,
+) -> S3ExtResult<Option<String>> {
+    let mut request = ListMultipartUploadsRequest {
+        bucket: target.bucket.clone(),
+        expected_bucket_owner: target.expected_bucket_owner.clone(),
+        prefix: Some(target.key.clone()),
+        ..Default::default()
+    };
+    let mut upload_id = None;
+    loop {
+        let output = client.list_multipart_uploads(request.clone()).await?;
+        // uploads of the same key are listed in the order they were started
+        for upload in output.uploads.unwrap_or_default() {
+            if upload.key.as_deref() == Some(target.key.as_str()) {
+                upload_id = upload.upload_id.or(upload_id);
+            }
+        }
+        if output.is_truncated != Some(true) || output.next_key_marker.is_none() {
+            return Ok(upload_id);
+        }
+        request.key_marker = output.next_key_marker;
+        request.upload_id_marker = output.next_upload_id_marker;
+    }
+}
+
+/// Parts of upload `upload_id` to `target` uploaded so far, by part number
+async fn list_parts(
+    client: &Backend,
+    target: &PutObjectRequest,
+    upload_id: &str,
+) -> S3ExtResult<BTreeMap<i64, Part>> {
+    let mut request = ListPartsRequest {
+        bucket: target.bucket.clone(),
+        expected_bucket_owner: target.expected_bucket_owner.clone(),
+        key: target.key.clone(),
+        request_payer: target.request_payer.clone(),
+        upload_id: upload_id.to_owned(),
+        ..Default::default()
+    };
+    let mut parts = BTreeMap::new();
+    loop {
+        let output = client.list_parts(request.clone()).await?;
+        for part in output.parts.unwrap_or_default() {
+            if let Some(part_number) = part.part_number {
+                parts.insert(part_number, part);
+            }
+        }
+        if output.is_truncated != Some(true) || output.next_part_number_marker.is_none() {
+            return Ok(parts);
+        }
+        request.part_number_marker = output.next_part_number_marker;
+    }
+}
+
+/// Entity tag of `part` if it was uploaded with content `body`
+///
+/// Only parts whose entity tag is the MD5 digest of their content are
+/// recognized, which isn't the case with SSE-KMS or SSE-C.
+fn uploaded_e_tag(part: Option<&Part>, body: &[u8]) -> Option<String> {
+    let part = part?;
+    if part.size != Some(body.len() as i64) {
+        return None;
+    }
+    let e_tag = part.e_tag.as_ref()?;
+    (e_tag.trim_matches('"') == hex::encode(Md5::digest(body))).then(|| e_tag.clone())
+}
+
+/// Start a multipart upload to `target`, returning the upload ID
+pub(crate) async fn create_multipart_upload(
+    client: &Backend,
+    target: &PutObjectRequest,
+) -> S3ExtResult<String> {
     let upload = client
         .create_multipart_upload(CreateMultipartUploadRequest {
             acl: target.acl.clone(),
@@ -73,61 +378,113 @@ where
         upload_id, target.bucket, target.key
     );
 
-    let bucket = target.bucket.clone();
-    let key = target.key.clone();
-    let request_payer = target.request_payer.clone();
-    let expected_bucket_owner = target.expected_bucket_owner.clone();
+    Ok(upload_id)
+}
 
-    match upload_multipart_needs_abort_on_error(client, source, target, part_size, &upload_id).await
-    {
-        ok @ Ok(_) => ok,
-        err @ Err(_) => {
-            info!(
-                "aborting upload {:?} due to a failure during upload",
-                upload_id
+/// Abort the upload described by `abort` if `result` is an error
+pub(crate) async fn abort_on_error<T>(
+    client: &Backend,
+    abort: AbortMultipartUploadRequest,
+    result: S3ExtResult<T>,
+) -> S3ExtResult<T> {
+    let err = match result {
+        ok @ Ok(_) => return ok,
+        Err(err) => err,
+    };
+    info!(
+        "aborting upload {:?} due to a failure during upload",
+        abort.upload_id
+    );
+    let (bucket, key, upload_id) = (
+        abort.bucket.clone(),
+        abort.key.clone(),
+        abort.upload_id.clone(),
+    );
+    match client.abort_multipart_upload(abort).await {
+        Ok(_) => Err(err),
+        Err(e) => {
+            warn!(
+                "failed to abort multi-part upload {:?}, upload leaked: {:?}",
+                upload_id, e
             );
-            if let Err(e) = client
-                .abort_multipart_upload(AbortMultipartUploadRequest {
-                    bucket,
-                    expected_bucket_owner,
-                    key,
-                    request_payer,
-                    upload_id,
-                })
-                .await
-            {
-                warn!("ignoring failure to abort multi-part upload: {:?}", e);
-            };
-            err
+            Err(S3ExtError::UploadLeaked {
+                bucket,
+                key,
+                upload_id,
+                source: Box::new(err),
+                abort_error: Box::new(e.into()),
+            })
         }
     }
 }
 
-// Upload needs to be aborted if this function fails
+// Upload needs to be aborted if this function fails, parts in `uploaded`
+// matching the content are kept instead of uploaded again
 async fn upload_multipart_needs_abort_on_error<R>(
-    client: &S3Client,
+    client: &Backend,
     source: &mut R,
     target: PutObjectRequest,
-    part_size: usize,
+    mut sizer: PartSizer,
     upload_id: &str,
+    uploaded: &BTreeMap<i64, Part>,
 ) -> S3ExtResult<CompleteMultipartUploadOutput>
 where
     R: AsyncRead + Unpin,
 {
     let mut parts = Vec::new();
+    let mut bytes = 0;
     for part_number in 1.. {
-        let mut body = vec![0; part_size];
-        let size = source.read(&mut body[..]).await?;
+        // parts are only recognized with the size they were uploaded with,
+        // a smaller last part can't be followed by more parts
+        let part_size = match uploaded.get(&part_number).and_then(|part| part.size) {
+            Some(size) if size as usize >= MIN_PART_SIZE => size as usize,
+            _ => sizer.next(),
+        };
+        // fill the part, readers like pipes return partial reads
+        let mut body = PART_BUFFERS.get(part_size);
+        while body.len() < part_size {
+            let remaining = (part_size - body.len()) as u64;
+            if (&mut *source).take(remaining).read_buf(&mut body).await? == 0 {
+                break;
+            }
+        }
+        let size = body.len();
+        let body = body.freeze();
         if size == 0 {
+            PART_BUFFERS.put(body);
             break;
         }
-        body.truncate(size);
+        if part_number as usize > MAX_PARTS {
+            return Err(S3ExtError::InvalidPartSize {
+                part_size,
+                reason: "objects must not exceed 10,000 parts",
+            });
+        }
 
+        bytes += size as u64;
+        if let Some(e_tag) = uploaded_e_tag(uploaded.get(&part_number), &body) {
+            debug!(
+                "part {} of upload {:?} uploaded already",
+                part_number, upload_id
+            );
+            sizer.record(size, Duration::ZERO);
+            PART_BUFFERS.put(body);
+            parts.push(CompletedPart {
+                e_tag: Some(e_tag),
+                part_number: Some(part_number),
+            });
+            continue;
+        }
+        let span = span!(DEBUG, "upload_part", part_number, bytes = size as u64);
+        let start = Instant::now();
         let part = client
             .upload_part(UploadPartRequest {
-                body: Some(body.into()),
+                body: Some(ByteStream::new_with_size(
+                    stream::once(future::ready(Ok(body.clone()))),
+                    size,
+                )),
                 bucket: target.bucket.clone(),
-                content_length: None,
+                content_length: Some(size as i64),
                 content_md5: None,
                 key: target.key.clone(),
                 part_number,
@@ -138,7 +495,11 @@ where
                 upload_id: upload_id.to_owned(),
                 expected_bucket_owner: target.expected_bucket_owner.clone(),
             })
+            .instrument(span)
             .await?;
+        stats::record_part();
+        sizer.record(size, start.elapsed());
+        PART_BUFFERS.put(body);
 
         parts.push(CompletedPart {
             e_tag: part.e_tag,
@@ -146,6 +507,9 @@ where
         });
     }
 
+    Span::current()
+        .record("parts", parts.len() as u64)
+        .record("bytes", bytes);
     client
         .complete_multipart_upload(CompleteMultipartUploadRequest {
             bucket: target.bucket.clone(),
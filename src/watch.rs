@@ -0,0 +1,192 @@
+//! Poll a prefix for changes
+//!
+//! This is a poor man's replacement for S3 event notifications: the prefix
+//! is listed periodically and each listing is compared with the previous one.
+//! Changes happening between two listings may be coalesced or missed (e.g. an
+//! object created and deleted again).
+
+use crate::{
+    backend::Backend,
+    compat::Compatibility,
+    error::{S3ExtError, S3ExtResult},
+    iter::ObjectStream,
+};
+use futures::{
+    stream::{self, Stream, TryStreamExt},
+    task::{Context, Poll},
+};
+use rusoto_s3::Object;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    pin::Pin,
+    time::Duration,
+};
+use tokio::time::{interval, Interval, MissedTickBehavior};
+
+/// Change observed between two listings
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchEvent {
+    /// Object didn't exist during the previous listing
+    Created(Object),
+    /// ETag, size or modification time of the object changed
+    Modified(Object),
+    /// Object no longer exists, contains the object as last seen
+    Deleted(Object),
+}
+
+type Snapshot = BTreeMap<String, Object>;
+
+struct WatchState {
+    client: Backend,
+    bucket: String,
+    prefix: String,
+    requester_pays: bool,
+    compat: Option<Compatibility>,
+    period: Duration,
+    interval: Option<Interval>,
+    snapshot: Option<Snapshot>,
+    events: VecDeque<WatchEvent>,
+}
+
+impl WatchState {
+    async fn list(&self) -> S3ExtResult<Snapshot> {
+        let mut listing = ObjectStream::new(&self.client, &self.bucket, Some(&self.prefix));
+        if self.requester_pays {
+            listing = listing.with_requester_pays();
+        }
+        if let Some(compat) = &self.compat {
+            listing = listing.with_compatibility(compat);
+        }
+        listing
+            .map_err(S3ExtError::from)
+            .try_fold(Snapshot::new(), |mut snapshot, object| async move {
+                let key = object
+                    .key
+                    .clone()
+                    .ok_or(S3ExtError::Other("response is missing key"))?;
+                snapshot.insert(key, object);
+                Ok(snapshot)
+            })
+            .await
+    }
+
+    fn update(&mut self, current: Snapshot) {
+        let mut previous = match self.snapshot.take() {
+            Some(previous) => previous,
+            None => {
+                // first listing only establishes the baseline
+                self.snapshot = Some(current);
+                return;
+            }
+        };
+        for (key, object) in &current {
+            match previous.remove(key) {
+                None => self.events.push_back(WatchEvent::Created(object.clone())),
+                Some(old)
+                    if old.e_tag != object.e_tag
+                        || old.size != object.size
+                        || old.last_modified != object.last_modified =>
+                {
+                    self.events.push_back(WatchEvent::Modified(object.clone()))
+                }
+                Some(_) => (),
+            }
+        }
+        self.events
+            .extend(previous.into_values().map(WatchEvent::Deleted));
+        self.snapshot = Some(current);
+    }
+}
+
+/// Stream of changes below a prefix
+///
+/// The stream never ends, the first listing is used as baseline and doesn't
+/// yield any events. Failed listings are yielded as errors and retried on the
+/// next tick.
+pub struct WatchStream {
+    state: Option<WatchState>,
+    inner: Option<Pin<Box<dyn Stream<Item = S3ExtResult<WatchEvent>> + Send>>>,
+}
+
+impl WatchStream {
+    pub(crate) fn new(
+        client: &Backend,
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        period: Duration,
+    ) -> Self {
+        let state = WatchState {
+            client: client.clone(),
+            bucket: bucket.into(),
+            prefix: prefix.into(),
+            requester_pays: false,
+            compat: None,
+            period,
+            interval: None,
+            snapshot: None,
+            events: VecDeque::new(),
+        };
+        Self {
+            state: Some(state),
+            inner: None,
+        }
+    }
+
+    /// Agree to pay for the listings, required to watch requester-pays
+    /// buckets
+    ///
+    /// Has no effect once the stream has been polled.
+    pub fn with_requester_pays(mut self) -> Self {
+        if let Some(state) = &mut self.state {
+            state.requester_pays = true;
+        }
+        self
+    }
+
+    /// Adjust the listings to the quirks described by `compat`
+    ///
+    /// Has no effect once the stream has been polled.
+    pub fn with_compatibility(mut self, compat: &Compatibility) -> Self {
+        if let Some(state) = &mut self.state {
+            state.compat = Some(compat.clone());
+        }
+        self
+    }
+
+    fn start(state: WatchState) -> impl Stream<Item = S3ExtResult<WatchEvent>> {
+        stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(event) = state.events.pop_front() {
+                    return Some((Ok(event), state));
+                }
+                let period = state.period;
+                state
+                    .interval
+                    .get_or_insert_with(|| {
+                        let mut ticks = interval(period);
+                        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                        ticks
+                    })
+                    .tick()
+                    .await;
+                match state.list().await {
+                    Ok(current) => state.update(current),
+                    Err(e) => return Some((Err(e), state)),
+                }
+            }
+        })
+    }
+}
+
+impl Stream for WatchStream {
+    type Item = S3ExtResult<WatchEvent>;
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        if let Some(state) = self.state.take() {
+            self.inner = Some(Box::pin(Self::start(state)));
+        }
+        match self.inner.as_mut() {
+            Some(inner) => inner.as_mut().poll_next(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
@@ -0,0 +1,304 @@
+//! Static website hosting
+//!
+//! See the methods [`enable_website`](crate::bucket_config::BucketConfigExt::enable_website),
+//! [`put_website`](crate::bucket_config::BucketConfigExt::put_website),
+//! [`get_website`](crate::bucket_config::BucketConfigExt::get_website) and
+//! [`delete_website`](crate::bucket_config::BucketConfigExt::delete_website) of
+//! [`BucketConfigExt`](crate::bucket_config::BucketConfigExt).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example(client: rusoto_s3::S3Client) -> Result<(), s3_ext::error::S3ExtError> {
+//! use s3_ext::{
+//!     bucket_config::BucketConfigExt,
+//!     website::{RedirectRule, Website},
+//! };
+//!
+//! let website = Website::new("index.html")
+//!     .error_document("404.html")
+//!     .rule(RedirectRule::prefix("docs/", "documentation/").status(301));
+//! client.put_website("bucket", &website).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    error::{S3ExtError, S3ExtResult},
+};
+use rusoto_s3::{
+    Condition, DeleteBucketWebsiteRequest, ErrorDocument, GetBucketWebsiteRequest, IndexDocument,
+    PutBucketWebsiteRequest, Redirect, RedirectAllRequestsTo, RoutingRule, WebsiteConfiguration,
+};
+
+/// Website configuration of a bucket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Website {
+    /// Serve the objects of the bucket
+    Hosting {
+        /// Suffix appended to requests for a directory, e.g. `index.html`
+        index_document: String,
+        /// Key of the object returned on 4XX errors
+        error_document: Option<String>,
+        /// Redirect rules, applied in order
+        rules: Vec<RedirectRule>,
+    },
+    /// Redirect all requests to another host
+    RedirectAll {
+        /// Host to redirect to
+        host_name: String,
+        /// Protocol of the redirect, that of the request if not set
+        protocol: Option<String>,
+    },
+}
+
+impl Website {
+    /// Serve the bucket using `index_document` as index
+    pub fn new(index_document: impl Into<String>) -> Self {
+        Website::Hosting {
+            index_document: index_document.into(),
+            error_document: None,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Redirect all requests to `host_name`
+    pub fn redirect_all(host_name: impl Into<String>) -> Self {
+        Website::RedirectAll {
+            host_name: host_name.into(),
+            protocol: None,
+        }
+    }
+
+    /// Return `key` on 4XX errors
+    ///
+    /// Has no effect on [`Website::RedirectAll`].
+    pub fn error_document(mut self, key: impl Into<String>) -> Self {
+        if let Website::Hosting { error_document, .. } = &mut self {
+            *error_document = Some(key.into());
+        }
+        self
+    }
+
+    /// Add redirect rule `rule`
+    ///
+    /// Has no effect on [`Website::RedirectAll`].
+    pub fn rule(mut self, rule: RedirectRule) -> Self {
+        if let Website::Hosting { rules, .. } = &mut self {
+            rules.push(rule);
+        }
+        self
+    }
+
+    /// Convert into a Rusoto `WebsiteConfiguration`
+    pub fn to_configuration(&self) -> WebsiteConfiguration {
+        match self {
+            Website::Hosting {
+                index_document,
+                error_document,
+                rules,
+            } => WebsiteConfiguration {
+                index_document: Some(IndexDocument {
+                    suffix: index_document.clone(),
+                }),
+                error_document: error_document
+                    .as_ref()
+                    .map(|key| ErrorDocument { key: key.clone() }),
+                routing_rules: Some(rules.iter().map(RedirectRule::to_rusoto).collect())
+                    .filter(|rules: &Vec<_>| !rules.is_empty()),
+                redirect_all_requests_to: None,
+            },
+            Website::RedirectAll {
+                host_name,
+                protocol,
+            } => WebsiteConfiguration {
+                redirect_all_requests_to: Some(RedirectAllRequestsTo {
+                    host_name: host_name.clone(),
+                    protocol: protocol.clone(),
+                }),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Convert a Rusoto `WebsiteConfiguration`
+    ///
+    /// Returns `None` if the configuration has neither an index document
+    /// nor redirects all requests.
+    pub fn from_configuration(configuration: WebsiteConfiguration) -> Option<Self> {
+        if let Some(redirect) = configuration.redirect_all_requests_to {
+            return Some(Website::RedirectAll {
+                host_name: redirect.host_name,
+                protocol: redirect.protocol,
+            });
+        }
+        Some(Website::Hosting {
+            index_document: configuration.index_document?.suffix,
+            error_document: configuration.error_document.map(|document| document.key),
+            rules: configuration
+                .routing_rules
+                .unwrap_or_default()
+                .into_iter()
+                .map(RedirectRule::from_rusoto)
+                .collect(),
+        })
+    }
+}
+
+/// Replacement of the key when redirecting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplaceKey {
+    /// Replace the prefix matched by [`RedirectRule::key_prefix`]
+    Prefix(String),
+    /// Replace the whole key
+    Key(String),
+}
+
+/// Rule redirecting matching requests
+///
+/// A rule without conditions redirects all requests.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RedirectRule {
+    /// Only redirect requests for keys with this prefix
+    pub key_prefix: Option<String>,
+    /// Only redirect requests failing with this HTTP status, e.g. 404
+    pub error_code: Option<u16>,
+    /// Host to redirect to, that of the request if not set
+    pub host_name: Option<String>,
+    /// Protocol of the redirect, that of the request if not set
+    pub protocol: Option<String>,
+    /// Key to redirect to, that of the request if not set
+    pub replace_key: Option<ReplaceKey>,
+    /// HTTP status of the redirect, 301 if not set
+    pub status: Option<u16>,
+}
+
+impl RedirectRule {
+    /// Redirect keys starting with `from` to the same key starting with `to`
+    pub fn prefix(from: impl Into<String>, to: impl Into<String>) -> Self {
+        RedirectRule {
+            key_prefix: Some(from.into()),
+            replace_key: Some(ReplaceKey::Prefix(to.into())),
+            ..Default::default()
+        }
+    }
+
+    /// Redirect requests failing with HTTP status `error_code` to `key`
+    pub fn on_error(error_code: u16, key: impl Into<String>) -> Self {
+        RedirectRule {
+            error_code: Some(error_code),
+            replace_key: Some(ReplaceKey::Key(key.into())),
+            ..Default::default()
+        }
+    }
+
+    /// Redirect to `host_name`
+    pub fn host_name(mut self, host_name: impl Into<String>) -> Self {
+        self.host_name = Some(host_name.into());
+        self
+    }
+
+    /// Redirect using `protocol`, `http` or `https`
+    pub fn protocol(mut self, protocol: impl Into<String>) -> Self {
+        self.protocol = Some(protocol.into());
+        self
+    }
+
+    /// Redirect with HTTP status `status`, e.g. 302
+    pub fn status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    fn to_rusoto(&self) -> RoutingRule {
+        let condition = Condition {
+            key_prefix_equals: self.key_prefix.clone(),
+            http_error_code_returned_equals: self.error_code.map(|code| code.to_string()),
+        };
+        let (replace_key_prefix_with, replace_key_with) = match &self.replace_key {
+            Some(ReplaceKey::Prefix(prefix)) => (Some(prefix.clone()), None),
+            Some(ReplaceKey::Key(key)) => (None, Some(key.clone())),
+            None => (None, None),
+        };
+        RoutingRule {
+            condition: Some(condition).filter(|condition| {
+                condition.key_prefix_equals.is_some()
+                    || condition.http_error_code_returned_equals.is_some()
+            }),
+            redirect: Redirect {
+                host_name: self.host_name.clone(),
+                protocol: self.protocol.clone(),
+                http_redirect_code: self.status.map(|status| status.to_string()),
+                replace_key_prefix_with,
+                replace_key_with,
+            },
+        }
+    }
+
+    // Malformed status codes are dropped
+    fn from_rusoto(rule: RoutingRule) -> Self {
+        let condition = rule.condition.unwrap_or_default();
+        let redirect = rule.redirect;
+        let replace_key = match (redirect.replace_key_prefix_with, redirect.replace_key_with) {
+            (Some(prefix), _) => Some(ReplaceKey::Prefix(prefix)),
+            (None, Some(key)) => Some(ReplaceKey::Key(key)),
+            (None, None) => None,
+        };
+        RedirectRule {
+            key_prefix: condition.key_prefix_equals,
+            error_code: condition
+                .http_error_code_returned_equals
+                .and_then(|code| code.parse().ok()),
+            host_name: redirect.host_name,
+            protocol: redirect.protocol,
+            replace_key,
+            status: redirect
+                .http_redirect_code
+                .and_then(|status| status.parse().ok()),
+        }
+    }
+}
+
+pub(crate) async fn get_website(client: &Backend, bucket: String) -> S3ExtResult<Option<Website>> {
+    let request = GetBucketWebsiteRequest {
+        bucket,
+        ..Default::default()
+    };
+    match client.get_bucket_website(request).await {
+        Ok(output) => Ok(Website::from_configuration(WebsiteConfiguration {
+            error_document: output.error_document,
+            index_document: output.index_document,
+            redirect_all_requests_to: output.redirect_all_requests_to,
+            routing_rules: output.routing_rules,
+        })),
+        Err(e) => {
+            let e = S3ExtError::from(e);
+            match e.code() {
+                Some("NoSuchWebsiteConfiguration") => Ok(None),
+                _ => Err(e),
+            }
+        }
+    }
+}
+
+pub(crate) async fn put_website(
+    client: &Backend,
+    bucket: String,
+    website: &Website,
+) -> S3ExtResult<()> {
+    let request = PutBucketWebsiteRequest {
+        bucket,
+        website_configuration: website.to_configuration(),
+        ..Default::default()
+    };
+    Ok(client.put_bucket_website(request).await?)
+}
+
+pub(crate) async fn delete_website(client: &Backend, bucket: String) -> S3ExtResult<()> {
+    let request = DeleteBucketWebsiteRequest {
+        bucket,
+        ..Default::default()
+    };
+    Ok(client.delete_bucket_website(request).await?)
+}
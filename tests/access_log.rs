@@ -0,0 +1,109 @@
+use rusoto_s3::{Grantee as RusotoGrantee, LoggingEnabled, TargetGrant};
+use s3_ext::{
+    access_log::AccessLogging,
+    acl::{Grant, Grantee, Permission},
+};
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn put_get_and_disable_access_logging() {
+    use s3_ext::{bucket_config::BucketConfigExt, mock::MockS3};
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    assert_eq!(s3.get_access_logging("bucket").await.unwrap(), None);
+
+    let logging = AccessLogging::new("audit-logs", "bucket/");
+    s3.put_access_logging("bucket", &logging).await.unwrap();
+    assert_eq!(
+        s3.get_access_logging("bucket").await.unwrap(),
+        Some(logging)
+    );
+
+    s3.disable_access_logging("bucket").await.unwrap();
+    assert_eq!(s3.get_access_logging("bucket").await.unwrap(), None);
+
+    assert!(s3
+        .get_access_logging("missing")
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn enable_access_logging() {
+    use s3_ext::{
+        bucket_config::BucketConfigExt,
+        mock::MockS3,
+        policy::{Principal, LOGGING_SERVICE},
+    };
+
+    let s3 = MockS3::new();
+    for bucket in &["a", "b", "audit-logs"] {
+        s3.create_bucket(bucket);
+    }
+    s3.enable_access_logging("a", "audit-logs", "a/")
+        .await
+        .unwrap();
+    assert_eq!(
+        s3.get_access_logging("a").await.unwrap(),
+        Some(AccessLogging::new("audit-logs", "a/"))
+    );
+    let policy = s3.get_bucket_policy("audit-logs").await.unwrap().unwrap();
+    assert_eq!(policy.statements.len(), 1);
+    let statement = &policy.statements[0];
+    assert_eq!(statement.sid.as_deref(), Some("S3ServerAccessLogsa"));
+    assert_eq!(
+        statement.principal,
+        Some(Principal::service(LOGGING_SERVICE))
+    );
+    assert_eq!(statement.resources, ["arn:aws:s3:::audit-logs/a/*"]);
+
+    // enabling again leaves the policy alone, other buckets get their own
+    // statement
+    s3.enable_access_logging("a", "audit-logs", "a/")
+        .await
+        .unwrap();
+    s3.enable_access_logging("b", "audit-logs", "b/")
+        .await
+        .unwrap();
+    let policy = s3.get_bucket_policy("audit-logs").await.unwrap().unwrap();
+    assert_eq!(policy.statements.len(), 2);
+}
+
+#[test]
+fn omit_empty_grants() {
+    let configuration = AccessLogging::new("audit-logs", "").to_configuration();
+    assert!(configuration.target_grants.is_none());
+}
+
+#[test]
+fn skip_invalid_grants() {
+    let configuration = LoggingEnabled {
+        target_bucket: "audit-logs".to_owned(),
+        target_prefix: "logs/".to_owned(),
+        target_grants: Some(vec![
+            TargetGrant {
+                grantee: None,
+                permission: Some("READ".to_owned()),
+            },
+            TargetGrant {
+                grantee: Some(RusotoGrantee {
+                    type_: "CanonicalUser".to_owned(),
+                    id: Some("reader-id".to_owned()),
+                    ..Default::default()
+                }),
+                permission: Some("WRITE".to_owned()),
+            },
+        ]),
+    };
+    let logging = AccessLogging::from_configuration(configuration);
+    assert_eq!(
+        logging.target_grants,
+        vec![Grant {
+            grantee: Grantee::canonical_user("reader-id"),
+            permission: Permission::Write,
+        }]
+    );
+}
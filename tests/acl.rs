@@ -0,0 +1,77 @@
+use rusoto_s3::AccessControlPolicy;
+use s3_ext::acl::{Acl, CannedAcl, Grant, Grantee, Permission, ALL_USERS};
+
+#[test]
+fn convert_policies() {
+    let acl = Acl {
+        owner: Some("owner-id".to_owned()),
+        grants: vec![
+            Grant {
+                grantee: Grantee::canonical_user("owner-id"),
+                permission: Permission::FullControl,
+            },
+            Grant {
+                grantee: Grantee::email("someone@example.com"),
+                permission: Permission::ReadAcp,
+            },
+            Grant {
+                grantee: Grantee::all_users(),
+                permission: Permission::Read,
+            },
+        ],
+    };
+    let policy = acl.to_policy();
+    assert_eq!(
+        policy.owner.as_ref().unwrap().id.as_deref(),
+        Some("owner-id")
+    );
+    let grants = policy.grants.as_ref().unwrap();
+    assert_eq!(grants.len(), 3);
+    let grantee = grants[2].grantee.as_ref().unwrap();
+    assert_eq!(grantee.type_, "Group");
+    assert_eq!(grantee.uri.as_deref(), Some(ALL_USERS));
+    assert_eq!(grants[2].permission.as_deref(), Some("READ"));
+    assert_eq!(Acl::from_policy(policy), acl);
+}
+
+#[test]
+fn skip_unknown_grants() {
+    let policy = AccessControlPolicy {
+        owner: None,
+        grants: Some(vec![
+            rusoto_s3::Grant {
+                grantee: Some(rusoto_s3::Grantee {
+                    type_: "Group".to_owned(),
+                    uri: Some(ALL_USERS.to_owned()),
+                    ..Default::default()
+                }),
+                permission: Some("READ_EVERYTHING".to_owned()),
+            },
+            rusoto_s3::Grant {
+                grantee: None,
+                permission: Some("READ".to_owned()),
+            },
+        ]),
+    };
+    assert_eq!(Acl::from_policy(policy), Acl::default());
+}
+
+#[test]
+fn public_grants() {
+    let mut acl = Acl::default();
+    assert!(!acl.is_public());
+    acl.grants.push(Grant {
+        grantee: Grantee::authenticated_users(),
+        permission: Permission::Write,
+    });
+    assert!(acl.is_public());
+    assert_eq!(
+        acl.permissions_of(&Grantee::authenticated_users())
+            .collect::<Vec<_>>(),
+        vec![Permission::Write]
+    );
+    assert_eq!(
+        CannedAcl::BucketOwnerFullControl.as_str(),
+        "bucket-owner-full-control"
+    );
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "mock")]
+
+use futures::TryStreamExt;
+use s3_ext::{append_log::AppendLog, mock::MockS3};
+
+const MIB: usize = 1024 * 1024;
+
+fn segment_key(first: u64, last: u64) -> String {
+    format!("log/{:020}-{:020}", first, last)
+}
+
+#[tokio::test]
+async fn append_and_read_records() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let mut log = AppendLog::open(s3.clone(), "bucket", "log/").await.unwrap();
+    log.append(b"first").await.unwrap();
+    log.append(b"").await.unwrap();
+    log.flush().await.unwrap();
+    log.append(b"third").await.unwrap();
+    // buffered records aren't read
+    let records: Vec<_> = log.records().try_collect().await.unwrap();
+    assert_eq!(records, vec![b"first".to_vec(), Vec::new()]);
+    log.flush().await.unwrap();
+    assert_eq!(
+        s3.keys("bucket"),
+        vec![segment_key(0, 0), segment_key(1, 1)]
+    );
+
+    let log = AppendLog::open(s3, "bucket", "log/").await.unwrap();
+    assert_eq!(log.segments().len(), 2);
+    let records: Vec<_> = log.records().try_collect().await.unwrap();
+    assert_eq!(
+        records,
+        vec![b"first".to_vec(), Vec::new(), b"third".to_vec()]
+    );
+}
+
+#[tokio::test]
+async fn compact_segments() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let mut log = AppendLog::open(s3.clone(), "bucket", "log/")
+        .await
+        .unwrap()
+        .max_segments(2);
+    let large = vec![1; 5 * MIB];
+    // written as segment once the buffer reaches 5 MiB
+    log.append(&large).await.unwrap();
+    assert_eq!(log.segments().len(), 1);
+    log.append(b"small").await.unwrap();
+    log.flush().await.unwrap();
+    log.append(b"buffered").await.unwrap();
+    log.flush().await.unwrap();
+
+    // the large segment is copied, small ones are uploaded again
+    assert_eq!(s3.keys("bucket"), vec![segment_key(0, 2)]);
+    let object = s3.object("bucket", &segment_key(0, 2)).unwrap();
+    assert_eq!(object.part_sizes, vec![5 * MIB + 4, 9 + 12]);
+    let records: Vec<_> = log.records().try_collect().await.unwrap();
+    assert_eq!(
+        records,
+        vec![large.clone(), b"small".to_vec(), b"buffered".to_vec()]
+    );
+
+    // segments included in others are skipped and deleted on compaction
+    s3.put_object("bucket", &segment_key(1, 1), vec![0, 0, 0, 1, 0]);
+    let mut log = AppendLog::open(s3.clone(), "bucket", "log/").await.unwrap();
+    assert_eq!(log.segments().len(), 1);
+    let records: Vec<_> = log.records().try_collect().await.unwrap();
+    assert_eq!(records.len(), 3);
+    log.append(b"last").await.unwrap();
+    log.compact().await.unwrap();
+    assert_eq!(s3.keys("bucket"), vec![segment_key(0, 3)]);
+    let records: Vec<_> = log.records().try_collect().await.unwrap();
+    assert_eq!(records.last().unwrap(), b"last");
+}
@@ -0,0 +1,184 @@
+#![cfg(feature = "mock")]
+
+use s3_ext::{archive::ArchiveFormat, mock::MockS3, S3Ext};
+use std::convert::TryInto;
+
+fn u16_at(buf: &[u8], offset: usize) -> usize {
+    u16::from_le_bytes(buf[offset..offset + 2].try_into().unwrap()) as usize
+}
+
+fn u32_at(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+fn octal(field: &[u8]) -> usize {
+    let digits = std::str::from_utf8(field).unwrap();
+    usize::from_str_radix(digits.trim_end_matches('\0').trim(), 8).unwrap()
+}
+
+// Names and contents of the entries of a tar archive
+fn read_tar(mut tar: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let mut entries = Vec::new();
+    let mut long_name = None;
+    while tar[..512].iter().any(|&b| b != 0) {
+        let (header, rest) = tar.split_at(512);
+        let checksum: u32 = header[..148]
+            .iter()
+            .chain(b"        ")
+            .chain(&header[156..])
+            .map(|&b| u32::from(b))
+            .sum();
+        assert_eq!(octal(&header[148..155]), checksum as usize);
+        let size = octal(&header[124..136]);
+        let content = rest[..size].to_vec();
+        tar = &rest[(size + 511) / 512 * 512..];
+        if header[156] == b'L' {
+            long_name = Some(String::from_utf8(content[..size - 1].to_vec()).unwrap());
+            continue;
+        }
+        let name = long_name.take().unwrap_or_else(|| {
+            let end = header[..100].iter().position(|&b| b == 0).unwrap_or(100);
+            String::from_utf8(header[..end].to_vec()).unwrap()
+        });
+        entries.push((name, content));
+    }
+    assert_eq!(tar.len(), 1024);
+    assert!(tar.iter().all(|&b| b == 0));
+    entries
+}
+
+// Names and contents of the entries of a zip archive, read using the central
+// directory
+fn read_zip(zip: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let end = &zip[zip.len() - 22..];
+    assert_eq!(u32_at(end, 0), 0x0605_4b50);
+    let mut offset = u32_at(end, 16) as usize;
+    let mut entries = Vec::new();
+    for _ in 0..u16_at(end, 10) {
+        let header = &zip[offset..];
+        assert_eq!(u32_at(header, 0), 0x0201_4b50);
+        let crc = u32_at(header, 16);
+        let size = u32_at(header, 24) as usize;
+        let name_len = u16_at(header, 28);
+        let name = String::from_utf8(header[46..46 + name_len].to_vec()).unwrap();
+        let local = &zip[u32_at(header, 42) as usize..];
+        assert_eq!(u32_at(local, 0), 0x0403_4b50);
+        let start = 30 + u16_at(local, 26) + u16_at(local, 28);
+        let content = local[start..start + size].to_vec();
+        assert_eq!(crc32fast::hash(&content), crc);
+        let descriptor = &local[start + size..];
+        assert_eq!(u32_at(descriptor, 0), 0x0807_4b50);
+        assert_eq!(u32_at(descriptor, 4), crc);
+        entries.push((name, content));
+        offset += 46 + name_len;
+    }
+    entries
+}
+
+fn mock() -> (MockS3, String) {
+    let s3 = MockS3::new();
+    let long_name = format!("2019/{}.jpg", "x".repeat(120));
+    s3.put_object("bucket", "photos/a.jpg", "first photo");
+    s3.put_object("bucket", &format!("photos/{}", long_name), vec![7; 1000]);
+    s3.put_object("bucket", "photos/2019/", "");
+    s3.put_object("bucket", "photos/empty", "");
+    s3.put_object("bucket", "other/b.jpg", "not archived");
+    (s3, long_name)
+}
+
+#[tokio::test]
+async fn archive_as_tar() {
+    let (s3, long_name) = mock();
+    let mut tar = Vec::new();
+    let count = s3
+        .archive_prefix("bucket", "photos/", &mut tar, ArchiveFormat::Tar)
+        .await
+        .unwrap();
+    assert_eq!(count, 3);
+    assert_eq!(tar.len() % 512, 0);
+    assert_eq!(
+        read_tar(&tar),
+        vec![
+            (long_name, vec![7; 1000]),
+            ("a.jpg".to_owned(), b"first photo".to_vec()),
+            ("empty".to_owned(), Vec::new()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn archive_as_zip() {
+    let (s3, long_name) = mock();
+    let mut zip = Vec::new();
+    let count = s3
+        .archive_prefix("bucket", "photos/", &mut zip, ArchiveFormat::Zip)
+        .await
+        .unwrap();
+    assert_eq!(count, 3);
+    assert_eq!(
+        read_zip(&zip),
+        vec![
+            (long_name, vec![7; 1000]),
+            ("a.jpg".to_owned(), b"first photo".to_vec()),
+            ("empty".to_owned(), Vec::new()),
+        ]
+    );
+
+    // names are relative to the last `/` of the prefix
+    let mut zip = Vec::new();
+    s3.archive_prefix("bucket", "photos/a", &mut zip, ArchiveFormat::Zip)
+        .await
+        .unwrap();
+    let names: Vec<_> = read_zip(&zip).into_iter().map(|(name, _)| name).collect();
+    assert_eq!(names, vec!["a.jpg"]);
+}
+
+#[tokio::test]
+async fn upload_tar_entries() {
+    let (s3, long_name) = mock();
+    let mut tar = Vec::new();
+    s3.archive_prefix("bucket", "photos/", &mut tar, ArchiveFormat::Tar)
+        .await
+        .unwrap();
+    let count = s3
+        .upload_tar(&mut &tar[..], "bucket", "copy/")
+        .await
+        .unwrap();
+    assert_eq!(count, 3);
+    let copied: Vec<_> = s3
+        .keys("bucket")
+        .into_iter()
+        .filter(|key| key.starts_with("copy/"))
+        .collect();
+    assert_eq!(
+        copied,
+        vec![
+            format!("copy/{}", long_name),
+            "copy/a.jpg".to_owned(),
+            "copy/empty".to_owned(),
+        ]
+    );
+    let object = s3.object("bucket", "copy/a.jpg").unwrap();
+    assert_eq!(object.body, b"first photo");
+    assert!(object.part_sizes.is_empty());
+
+    // truncated archives are rejected
+    assert!(s3
+        .upload_tar(&mut &tar[..tar.len() / 2], "bucket", "truncated/")
+        .await
+        .is_err());
+
+    // large entries are uploaded using multipart upload
+    let data: Vec<u8> = (0..17 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+    s3.put_object("bucket", "large/data", data.clone());
+    let mut tar = Vec::new();
+    s3.archive_prefix("bucket", "large/", &mut tar, ArchiveFormat::Tar)
+        .await
+        .unwrap();
+    s3.upload_tar(&mut &tar[..], "bucket", "copy/")
+        .await
+        .unwrap();
+    let object = s3.object("bucket", "copy/data").unwrap();
+    assert_eq!(object.body, data);
+    assert_eq!(object.part_sizes.len(), 4);
+}
@@ -0,0 +1,111 @@
+use s3_ext::attributes::ObjectAttributes;
+
+#[test]
+fn content_md5() {
+    let single = ObjectAttributes {
+        e_tag: Some("\"5d41402abc4b2a76b9719d911017c592\"".to_owned()),
+        ..Default::default()
+    };
+    assert!(!single.is_multipart());
+    assert_eq!(
+        single.content_md5(),
+        Some("5d41402abc4b2a76b9719d911017c592")
+    );
+
+    let multipart = ObjectAttributes {
+        e_tag: Some("\"d41d8cd98f00b204e9800998ecf8427e-3\"".to_owned()),
+        ..Default::default()
+    };
+    assert!(multipart.is_multipart());
+    assert_eq!(multipart.content_md5(), None);
+
+    let kms = ObjectAttributes {
+        server_side_encryption: Some("aws:kms".to_owned()),
+        ..single
+    };
+    assert_eq!(kms.content_md5(), None);
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn single_part_object() {
+    use s3_ext::{mock::MockS3, S3Ext};
+
+    let s3 = MockS3::new();
+    s3.put_object("bucket", "small", "hello");
+
+    let attributes = s3.object_attributes("bucket", "small").await.unwrap();
+    assert_eq!(attributes.size, 5);
+    assert_eq!(attributes.parts_count, None);
+    assert_eq!(attributes.part_size, None);
+    assert_eq!(attributes.storage_class, "STANDARD");
+    assert_eq!(
+        attributes.content_md5(),
+        Some("5d41402abc4b2a76b9719d911017c592")
+    );
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn multipart_object() {
+    use rusoto_s3::PutObjectRequest;
+    use s3_ext::{mock::MockS3, S3Ext};
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let data = vec![7; 11 * 1024 * 1024];
+    let request = PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "large".to_owned(),
+        ..Default::default()
+    };
+    s3.upload_multipart(&mut &data[..], request, 5 * 1024 * 1024)
+        .await
+        .unwrap();
+
+    let attributes = s3.object_attributes("bucket", "large").await.unwrap();
+    assert_eq!(attributes.size, 11 * 1024 * 1024);
+    assert_eq!(attributes.parts_count, Some(3));
+    assert_eq!(attributes.part_size, Some(5 * 1024 * 1024));
+    assert!(attributes.e_tag.unwrap().ends_with("-3\""));
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn missing_object() {
+    use s3_ext::{mock::MockS3, S3Ext};
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let err = s3.object_attributes("bucket", "missing").await.unwrap_err();
+    assert!(err.is_not_found());
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn head_many() {
+    use s3_ext::{mock::MockS3, S3Ext};
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let keys: Vec<_> = (0..50).map(|i| format!("key-{:02}", i)).collect();
+    for key in keys.iter().step_by(2) {
+        s3.put_object("bucket", key, key.as_str());
+    }
+
+    let results = s3.head_many("bucket", keys.clone(), 8).await.unwrap();
+    assert_eq!(results.len(), 50);
+    for (i, (key, attributes)) in results.into_iter().enumerate() {
+        assert_eq!(key, keys[i]);
+        match attributes {
+            Some(attributes) if i % 2 == 0 => assert_eq!(attributes.size, 6),
+            None if i % 2 == 1 => {}
+            attributes => panic!("unexpected attributes of {}: {:?}", key, attributes),
+        }
+    }
+    assert!(s3
+        .head_many("bucket", Vec::<String>::new(), 0)
+        .await
+        .unwrap()
+        .is_empty());
+}
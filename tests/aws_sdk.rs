@@ -0,0 +1,110 @@
+#![cfg(feature = "aws-sdk")]
+
+use aws_sdk_s3::{
+    config::{BehaviorVersion, Credentials, Region},
+    Client, Config,
+};
+use futures::stream::TryStreamExt;
+use rusoto_s3::GetObjectRequest;
+use s3_ext::S3Ext;
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    thread,
+};
+
+const LISTING: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+    <ListBucketResult><Name>bucket</Name><KeyCount>2</KeyCount><MaxKeys>1000</MaxKeys>\
+    <IsTruncated>false</IsTruncated>\
+    <Contents><Key>a</Key><LastModified>2021-01-01T00:00:00.000Z</LastModified><Size>5</Size></Contents>\
+    <Contents><Key>b</Key><LastModified>2021-01-02T00:00:00.000Z</LastModified><Size>5</Size></Contents>\
+    </ListBucketResult>";
+
+const NO_SUCH_KEY: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+    <Error><Code>NoSuchKey</Code><Message>The specified key does not exist.</Message>\
+    <RequestId>4442587FB7D0A2F9</RequestId></Error>";
+
+// Serve a listing of `bucket`, object `bucket/a` and 404 for anything else
+fn server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = [0; 4096];
+            let n = stream.read(&mut buf).unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let path = request.split(' ').nth(1).unwrap_or_default();
+            let (status, body) = if path.starts_with("/bucket?") || path.starts_with("/bucket/?") {
+                ("200 OK", LISTING)
+            } else if path == "/bucket/a" || path.starts_with("/bucket/a?") {
+                ("200 OK", "hello")
+            } else {
+                ("404 Not Found", NO_SUCH_KEY)
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    endpoint
+}
+
+fn client() -> Client {
+    Client::from_conf(
+        Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("eu-west-1"))
+            .endpoint_url(server())
+            .force_path_style(true)
+            .credentials_provider(Credentials::new("access", "secret", None, None, "test"))
+            .build(),
+    )
+}
+
+fn request(key: &str) -> GetObjectRequest {
+    GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn download_with_sdk_client() {
+    let client = client();
+    let mut target = Vec::new();
+    let resp = client.download(request("a"), &mut target).await.unwrap();
+    assert_eq!(target, b"hello");
+    assert_eq!(resp.content_length, Some(5));
+
+    let err = client
+        .download(request("missing"), &mut target)
+        .await
+        .unwrap_err();
+    assert!(err.is_not_found());
+    assert_eq!(err.key(), Some("missing"));
+}
+
+#[tokio::test]
+async fn stream_with_sdk_client() {
+    let keys: Vec<_> = client()
+        .stream_objects("bucket")
+        .map_ok(|o| o.key.unwrap())
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(keys, vec!["a", "b"]);
+
+    let objects: Vec<_> = client()
+        .stream_objects_sorted_by_mtime("bucket")
+        .try_collect()
+        .await
+        .unwrap();
+    let keys: Vec<_> = objects.into_iter().filter_map(|o| o.key).collect();
+    assert_eq!(keys, vec!["b", "a"]);
+}
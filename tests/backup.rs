@@ -0,0 +1,53 @@
+#![cfg(feature = "mock")]
+
+use s3_ext::{backup::Backup, error::S3ExtError, mock::MockS3};
+use std::fs;
+use tempdir::TempDir;
+
+#[tokio::test]
+async fn snapshot_and_restore() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let backup = Backup::new(s3.clone(), "bucket", "backup/");
+    let source = TempDir::new("s3-ext").unwrap();
+    fs::create_dir(source.path().join("sub")).unwrap();
+    fs::write(source.path().join("a.txt"), "first").unwrap();
+    fs::write(source.path().join("copy.txt"), "first").unwrap();
+    fs::write(source.path().join("sub/b.txt"), "second").unwrap();
+
+    let report = backup.snapshot("one", source.path()).await.unwrap();
+    assert_eq!(report.uploaded, vec!["a.txt", "sub/b.txt"]);
+    assert_eq!(report.skipped, vec!["copy.txt"]);
+    let paths: Vec<_> = report.manifest.files.iter().map(|f| &f.path).collect();
+    assert_eq!(paths, vec!["a.txt", "copy.txt", "sub/b.txt"]);
+    assert_eq!(report.manifest.files[2].size, 6);
+    assert!(backup.snapshot("one", source.path()).await.is_err());
+
+    // only changed files are uploaded
+    fs::write(source.path().join("sub/b.txt"), "changed").unwrap();
+    fs::write(source.path().join("c.txt"), "new").unwrap();
+    fs::remove_file(source.path().join("copy.txt")).unwrap();
+    let report = backup.snapshot("two", source.path()).await.unwrap();
+    assert_eq!(report.uploaded, vec!["c.txt", "sub/b.txt"]);
+    assert_eq!(report.skipped, vec!["a.txt"]);
+    assert_eq!(backup.snapshots().await.unwrap(), vec!["one", "two"]);
+    assert_eq!(backup.manifest("two").await.unwrap(), report.manifest);
+
+    let target = TempDir::new("s3-ext").unwrap();
+    backup.restore("one", target.path()).await.unwrap();
+    let read = |path: &str| fs::read_to_string(target.path().join(path)).unwrap();
+    assert_eq!(read("a.txt"), "first");
+    assert_eq!(read("copy.txt"), "first");
+    assert_eq!(read("sub/b.txt"), "second");
+    assert!(!target.path().join("c.txt").exists());
+
+    // restored content is verified
+    let key = &report.manifest.files[0].key;
+    s3.put_object("bucket", key, "tampered");
+    match backup.restore("two", target.path()).await {
+        Err(S3ExtError::Object { source, .. }) => {
+            assert!(matches!(*source, S3ExtError::ChecksumMismatch { .. }))
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
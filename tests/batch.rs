@@ -0,0 +1,153 @@
+use s3_ext::{
+    batch::{Manifest, Operation, Task},
+    error::S3ExtError,
+    restore::RestoreTier,
+};
+
+fn invalid_entry(result: Result<Manifest, S3ExtError>) -> (usize, &'static str) {
+    match result {
+        Err(S3ExtError::InvalidManifest { entry, reason }) => (entry, reason),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+fn expected_tasks() -> Vec<Task> {
+    let task = |key: &str, operation| Task {
+        bucket: "photos".to_owned(),
+        key: key.to_owned(),
+        operation,
+    };
+    vec![
+        task(
+            "2023/beach, sunset.jpg",
+            Operation::Copy {
+                bucket: "archive".to_owned(),
+                key: "photos/\"beach\".jpg".to_owned(),
+            },
+        ),
+        task("2023/tmp.jpg", Operation::Delete),
+        task(
+            "2023/city.jpg",
+            Operation::Tag(
+                vec![
+                    ("project".to_owned(), "travel".to_owned()),
+                    ("reviewed".to_owned(), "a=b".to_owned()),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+        ),
+        task(
+            "2019/lake.jpg",
+            Operation::Restore {
+                tier: RestoreTier::Bulk,
+                days: 7,
+            },
+        ),
+    ]
+}
+
+#[test]
+fn parse_csv() {
+    let csv = "photos,\"2023/beach, sunset.jpg\",copy,archive,\"photos/\"\"beach\"\".jpg\"\n\
+               photos,2023/tmp.jpg,delete\r\n\
+               \n\
+               photos,2023/city.jpg,tag,project=travel,reviewed=a=b\n\
+               photos,2019/lake.jpg,restore,bulk,7\n";
+    let manifest = Manifest::from_csv(csv).unwrap();
+    assert_eq!(manifest.tasks, expected_tasks());
+}
+
+#[test]
+fn parse_json() {
+    let json = r#"[
+        {"Bucket": "photos", "Key": "2023/beach, sunset.jpg", "Operation": "copy",
+         "TargetBucket": "archive", "TargetKey": "photos/\"beach\".jpg"},
+        {"Bucket": "photos", "Key": "2023/tmp.jpg", "Operation": "delete"},
+        {"Bucket": "photos", "Key": "2023/city.jpg", "Operation": "tag",
+         "Tags": {"project": "travel", "reviewed": "a=b"}},
+        {"Bucket": "photos", "Key": "2019/lake.jpg", "Operation": "restore",
+         "Tier": "Bulk", "Days": 7}
+    ]"#;
+    let manifest = Manifest::from_json(json).unwrap();
+    assert_eq!(manifest.tasks, expected_tasks());
+}
+
+#[test]
+fn invalid_manifests() {
+    assert_eq!(
+        invalid_entry(Manifest::from_csv(
+            "photos,a.jpg,delete\n\nphotos,b.jpg,move"
+        )),
+        (3, "unknown operation")
+    );
+    assert_eq!(
+        invalid_entry(Manifest::from_csv("photos,a.jpg,copy,archive")),
+        (1, "missing target key")
+    );
+    assert_eq!(
+        invalid_entry(Manifest::from_csv("photos,a.jpg,delete,now")),
+        (1, "too many fields")
+    );
+    assert_eq!(
+        invalid_entry(Manifest::from_csv("photos,\"a.jpg,delete")),
+        (1, "unterminated quote")
+    );
+    assert_eq!(
+        invalid_entry(Manifest::from_csv("photos,a.jpg,restore,Slow,7")),
+        (1, "unknown restore tier")
+    );
+    assert_eq!(
+        invalid_entry(Manifest::from_json(r#"{"Bucket": "photos"}"#)),
+        (0, "not an array")
+    );
+    assert_eq!(
+        invalid_entry(Manifest::from_json(
+            r#"[{"Bucket": "photos", "Key": "a.jpg", "Operation": "tag"}]"#
+        )),
+        (1, "missing tags")
+    );
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn run_batch() {
+    use s3_ext::{mock::MockS3, retry::RetryPolicy, S3Ext};
+
+    let s3 = MockS3::new();
+    s3.create_bucket("photos");
+    s3.create_bucket("archive");
+    s3.put_object("photos", "a.jpg", "a");
+    s3.put_object("photos", "b.jpg", "b");
+    s3.put_object("photos", "c.jpg", "c");
+
+    let manifest = Manifest::from_csv(
+        "photos,a.jpg,copy,archive,2023/a.jpg\n\
+         photos,missing.jpg,copy,archive,2023/missing.jpg\n\
+         photos,b.jpg,delete\n\
+         photos,c.jpg,tag,reviewed=yes\n",
+    )
+    .unwrap();
+    let report = s3.run_batch(&manifest, 2, &RetryPolicy::default()).await;
+    assert!(!report.is_success());
+    let keys: Vec<_> = report.results.iter().map(|r| r.task.key.as_str()).collect();
+    assert_eq!(keys, vec!["a.jpg", "missing.jpg", "b.jpg", "c.jpg"]);
+    let failed: Vec<_> = report.failed().collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].task.key, "missing.jpg");
+    assert_eq!(failed[0].attempts, 1);
+
+    assert_eq!(s3.object("archive", "2023/a.jpg").unwrap().body, b"a");
+    assert!(s3.object("photos", "b.jpg").is_none());
+    assert_eq!(
+        s3.object("photos", "c.jpg").unwrap().tags["reviewed"],
+        "yes"
+    );
+
+    let json = report.to_json();
+    assert_eq!(json["Succeeded"], 3);
+    assert_eq!(json["Failed"], 1);
+    assert_eq!(json["Results"][1]["Operation"], "copy");
+    assert!(json["Results"][1]["Error"].is_string());
+    assert!(json["Results"][0]["Error"].is_null());
+}
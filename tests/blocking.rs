@@ -0,0 +1,51 @@
+#![cfg(all(feature = "blocking", feature = "mock"))]
+
+use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+use s3_ext::{blocking::BlockingS3Ext, mock::MockS3};
+use tempdir::TempDir;
+
+#[test]
+fn blocking_transfers_and_listings() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let client = BlockingS3Ext::new(s3).unwrap();
+
+    let target = |key: &str| PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    };
+    let source = |key: &str| GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    };
+
+    client.upload(&mut &b"hello"[..], target("dir/a")).unwrap();
+    let dir = TempDir::new("s3-ext").unwrap();
+    let path = dir.path().join("b");
+    std::fs::write(&path, "world").unwrap();
+    client.upload_from_file(&path, target("dir/b")).unwrap();
+    client.upload(&mut &b"!"[..], target("other")).unwrap();
+
+    let mut body = Vec::new();
+    client.download(source("dir/a"), &mut body).unwrap();
+    assert_eq!(body, b"hello");
+    let copy = dir.path().join("copy");
+    client.download_to_file(source("dir/b"), &copy).unwrap();
+    assert_eq!(std::fs::read(&copy).unwrap(), b"world");
+
+    let keys: Vec<_> = client
+        .list_objects("bucket")
+        .map(|o| o.unwrap().key.unwrap())
+        .collect();
+    assert_eq!(keys, vec!["dir/a", "dir/b", "other"]);
+    let sizes: Vec<_> = client
+        .list_objects_with_prefix("bucket", "dir/")
+        .map(|o| o.unwrap().size)
+        .collect();
+    assert_eq!(sizes, vec![Some(5), Some(5)]);
+
+    let err = client.download(source("missing"), &mut body).unwrap_err();
+    assert!(err.is_not_found());
+}
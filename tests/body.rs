@@ -0,0 +1,76 @@
+#![cfg(feature = "mock")]
+
+use chrono::Utc;
+use futures::TryStreamExt;
+use rusoto_s3::GetObjectRequest;
+use s3_ext::{mock::MockS3, S3Ext};
+
+fn mock_with_object() -> MockS3 {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    s3.put_object("bucket", "greeting", "hello world");
+    s3
+}
+
+#[tokio::test]
+async fn read_body() {
+    let s3 = mock_with_object();
+    let body = s3.get_body("bucket", "greeting").await.unwrap();
+    let record = s3.object("bucket", "greeting").unwrap();
+    assert_eq!(body.content_length(), Some(11));
+    assert_eq!(body.e_tag(), Some(record.e_tag.as_str()));
+    let age = Utc::now() - body.last_modified().unwrap();
+    assert!(age.num_seconds() < 60);
+    assert_eq!(body.read_to_vec().await.unwrap(), b"hello world");
+
+    let body = s3.get_body("bucket", "greeting").await.unwrap();
+    assert_eq!(body.read_to_string().await.unwrap(), "hello world");
+
+    let mut target = Vec::new();
+    let body = s3.get_body("bucket", "greeting").await.unwrap();
+    assert_eq!(body.copy_to(&mut target).await.unwrap(), 11);
+    assert_eq!(target, b"hello world");
+
+    let body = s3.get_body("bucket", "greeting").await.unwrap();
+    let chunks: Vec<_> = body.bytes_stream().try_collect().await.unwrap();
+    assert_eq!(chunks.concat(), b"hello world");
+}
+
+#[tokio::test]
+async fn get_body_with_range() {
+    let s3 = mock_with_object();
+    let request = GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "greeting".to_owned(),
+        range: Some("bytes=6-10".to_owned()),
+        ..Default::default()
+    };
+    let body = s3.get_body_with(request).await.unwrap();
+    assert_eq!(body.content_length(), Some(5));
+    assert_eq!(body.read_to_string().await.unwrap(), "world");
+}
+
+#[tokio::test]
+async fn invalid_utf8_and_missing_objects() {
+    let s3 = mock_with_object();
+    s3.put_object("bucket", "binary", vec![0xff, 0xfe]);
+    let body = s3.get_body("bucket", "binary").await.unwrap();
+    let err = body.read_to_string().await.unwrap_err();
+    assert_eq!(err.key(), Some("binary"));
+
+    let err = s3.get_body("bucket", "missing").await.unwrap_err();
+    assert!(err.is_not_found());
+    assert_eq!(err.key(), Some("missing"));
+}
+
+#[tokio::test]
+async fn byte_stream() {
+    let s3 = mock_with_object();
+    let stream = s3.get_byte_stream("bucket", "greeting").await.unwrap();
+    assert_eq!(stream.content_length(), Some(11));
+    let chunks: Vec<_> = stream.try_collect().await.unwrap();
+    assert_eq!(chunks.concat(), b"hello world");
+
+    let err = s3.get_byte_stream("bucket", "missing").await.unwrap_err();
+    assert!(err.is_not_found());
+}
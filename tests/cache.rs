@@ -0,0 +1,175 @@
+#![cfg(feature = "mock")]
+
+use rusoto_s3::GetObjectRequest;
+use s3_ext::{
+    cache::{DiskCache, MemoryCache},
+    mock::MockS3,
+    S3Ext,
+};
+use std::{fs, time::Duration};
+use tempdir::TempDir;
+
+fn get(key: &str) -> GetObjectRequest {
+    GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    }
+}
+
+fn bodies(dir: &TempDir) -> Vec<fs::DirEntry> {
+    fs::read_dir(dir.path())
+        .unwrap()
+        .map(Result::unwrap)
+        .filter(|entry| entry.path().extension() == Some("body".as_ref()))
+        .collect()
+}
+
+#[tokio::test]
+async fn revalidate_cached_objects() {
+    let dir = TempDir::new("s3-ext").unwrap();
+    let s3 = MockS3::new();
+    s3.put_object("bucket", "key", "first");
+    let cache = DiskCache::new(s3.clone(), dir.path());
+
+    let mut body = Vec::new();
+    let output = cache.download(get("key"), &mut body).await.unwrap();
+    assert_eq!(body, b"first");
+    assert_eq!(output.content_length, Some(5));
+    let cached = bodies(&dir);
+    assert_eq!(cached.len(), 1);
+
+    // unchanged objects are answered from the cache
+    fs::write(cached[0].path(), "cache").unwrap();
+    let mut body = Vec::new();
+    let output = cache.download(get("key"), &mut body).await.unwrap();
+    assert_eq!(body, b"cache");
+    assert_eq!(output.e_tag, s3.object("bucket", "key").map(|o| o.e_tag));
+    assert_eq!(cache.get_bytes("bucket", "key").await.unwrap(), b"cache");
+
+    s3.put_object("bucket", "key", "second");
+    let target_dir = TempDir::new("s3-ext").unwrap();
+    let target = target_dir.path().join("target");
+    cache.download_to_file(get("key"), &target).await.unwrap();
+    assert_eq!(fs::read(&target).unwrap(), b"second");
+
+    // ranges bypass the cache
+    let mut body = Vec::new();
+    let request = GetObjectRequest {
+        range: Some("bytes=0-2".to_owned()),
+        ..get("key")
+    };
+    cache.download(request, &mut body).await.unwrap();
+    assert_eq!(body, b"sec");
+    assert_eq!(bodies(&dir).len(), 1);
+}
+
+#[tokio::test]
+async fn evict_least_recently_read() {
+    let dir = TempDir::new("s3-ext").unwrap();
+    let s3 = MockS3::new();
+    for key in &["a", "b", "c"] {
+        s3.put_object("bucket", key, "object");
+    }
+    let cache = DiskCache::new(s3.clone(), dir.path()).max_size(12);
+
+    let mut body = Vec::new();
+    cache.download(get("a"), &mut body).await.unwrap();
+    cache.download(get("b"), &mut body).await.unwrap();
+    cache.download(get("a"), &mut body).await.unwrap();
+    assert_eq!(bodies(&dir).len(), 2);
+    cache.download(get("c"), &mut body).await.unwrap();
+    assert_eq!(bodies(&dir).len(), 2);
+
+    // "b" was evicted, "a" is still cached
+    for entry in bodies(&dir) {
+        fs::write(entry.path(), "cached").unwrap();
+    }
+    let mut body = Vec::new();
+    cache.download(get("a"), &mut body).await.unwrap();
+    assert_eq!(body, b"cached");
+    let mut body = Vec::new();
+    cache.download(get("b"), &mut body).await.unwrap();
+    assert_eq!(body, b"object");
+}
+
+#[tokio::test]
+async fn missing_objects() {
+    let dir = TempDir::new("s3-ext").unwrap();
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let cache = DiskCache::new(s3, dir.path());
+
+    let mut body = Vec::new();
+    let err = cache.download(get("missing"), &mut body).await.unwrap_err();
+    assert!(err.is_not_found());
+    assert!(bodies(&dir).is_empty());
+    cache.clear().await.unwrap();
+    assert!(!dir.path().exists());
+}
+
+#[tokio::test]
+async fn memory_cache() {
+    let s3 = MockS3::new();
+    s3.put_object("bucket", "a", "first");
+    s3.put_object("bucket", "b", "object");
+    s3.put_object("bucket", "large", vec![0; 100]);
+    let cache = MemoryCache::new(s3.clone())
+        .max_entries(2)
+        .max_bytes(12)
+        .ttl(Duration::ZERO);
+
+    let mut body = Vec::new();
+    let output = cache.download(get("a"), &mut body).await.unwrap();
+    assert_eq!(body, b"first");
+    assert_eq!(cache.usage(), (1, 5));
+    let mut body = Vec::new();
+    let cached = cache.download(get("a"), &mut body).await.unwrap();
+    assert_eq!(body, b"first");
+    assert_eq!(cached.e_tag, output.e_tag);
+    assert_eq!(cached.content_length, Some(5));
+
+    s3.put_object("bucket", "a", "second");
+    let mut body = Vec::new();
+    cache.download(get("a"), &mut body).await.unwrap();
+    assert_eq!(body, b"second");
+    assert_eq!(cache.usage(), (1, 6));
+
+    // larger than the budget
+    let mut body = Vec::new();
+    cache.download(get("large"), &mut body).await.unwrap();
+    assert_eq!(body.len(), 100);
+    assert_eq!(cache.usage(), (1, 6));
+
+    cache
+        .download(get("b"), &mut Vec::<u8>::new())
+        .await
+        .unwrap();
+    assert_eq!(cache.usage(), (2, 12));
+    s3.put_object("bucket", "c", "c");
+    cache
+        .download(get("c"), &mut Vec::<u8>::new())
+        .await
+        .unwrap();
+    assert_eq!(cache.usage(), (2, 7));
+
+    cache.clear();
+    assert_eq!(cache.usage(), (0, 0));
+}
+
+#[tokio::test]
+async fn memory_cache_ttl() {
+    let s3 = MockS3::new();
+    s3.put_object("bucket", "key", "first");
+    let cache = MemoryCache::new(s3.clone());
+    assert_eq!(cache.get_bytes("bucket", "key").await.unwrap(), b"first");
+
+    // fresh objects are served without a request
+    s3.put_object("bucket", "key", "second");
+    assert_eq!(cache.get_bytes("bucket", "key").await.unwrap(), b"first");
+
+    // the body of the replaced ETag is dropped
+    let cache = cache.ttl(Duration::ZERO);
+    assert_eq!(cache.get_bytes("bucket", "key").await.unwrap(), b"second");
+    assert_eq!(cache.usage(), (1, 6));
+}
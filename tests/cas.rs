@@ -0,0 +1,52 @@
+use s3_ext::cas::ContentHashKey;
+
+#[test]
+fn format_and_parse_hashes() {
+    let hash = ContentHashKey::of(b"hello");
+    assert_eq!(
+        hash.to_string(),
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    );
+    assert_eq!(hash.to_string().parse::<ContentHashKey>().unwrap(), hash);
+    assert_eq!(
+        hash.key("cas/"),
+        "cas/2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    );
+    assert!("2cf24dba".parse::<ContentHashKey>().is_err());
+    assert!("x".repeat(64).parse::<ContentHashKey>().is_err());
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn store_and_verify_content() {
+    use s3_ext::{error::S3ExtError, mock::MockS3, S3Ext};
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let hash = s3.put_content("bucket", "cas/", b"hello").await.unwrap();
+    let key = hash.key("cas/");
+    assert_eq!(s3.keys("bucket"), vec![key.clone()]);
+    let stored = s3.object("bucket", &key).unwrap();
+
+    assert_eq!(
+        s3.put_content("bucket", "cas/", b"hello").await.unwrap(),
+        hash
+    );
+    assert_eq!(
+        s3.object("bucket", &key).unwrap().last_modified,
+        stored.last_modified
+    );
+    assert_eq!(
+        s3.get_content("bucket", "cas/", &hash).await.unwrap(),
+        b"hello"
+    );
+
+    s3.put_object("bucket", &key, "tampered");
+    let err = s3.get_content("bucket", "cas/", &hash).await.unwrap_err();
+    assert!(matches!(err.inner(), S3ExtError::ChecksumMismatch { .. }));
+    assert!(s3
+        .get_content("bucket", "cas/", &ContentHashKey::of(b"missing"))
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "cassette")]
+
+use rusoto_s3::GetObjectRequest;
+use s3_ext::{
+    client::{Credentials, S3ExtClientBuilder},
+    S3Ext,
+};
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    thread,
+};
+use tempdir::TempDir;
+
+// Respond to `count` requests with `hello`
+fn server(count: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming().take(count) {
+            let mut stream = stream.unwrap();
+            let mut buf = [0; 4096];
+            let _ = stream.read(&mut buf).unwrap();
+            let body = "hello";
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nETag: \"abc\"\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    endpoint
+}
+
+fn request(key: &str) -> GetObjectRequest {
+    GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn recorded_interactions_are_replayed() {
+    let dir = TempDir::new("cassette").unwrap();
+    let cassette = dir.path().join("cassette.json");
+    let endpoint = server(1);
+
+    let client = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", &endpoint)
+        .credentials(Credentials::Anonymous)
+        .record(&cassette)
+        .build()
+        .unwrap();
+    let mut target = Vec::new();
+    client.download(request("a"), &mut target).await.unwrap();
+    assert_eq!(target, b"hello");
+
+    // the server is gone, responses come from the cassette
+    let client = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", &endpoint)
+        .credentials(Credentials::Anonymous)
+        .replay(&cassette)
+        .build()
+        .unwrap();
+    let mut target = Vec::new();
+    let resp = client.download(request("a"), &mut target).await.unwrap();
+    assert_eq!(target, b"hello");
+    assert_eq!(resp.e_tag.as_deref(), Some("\"abc\""));
+
+    // each interaction is replayed once
+    let result = client.download(request("a"), &mut target).await;
+    assert!(result.is_err());
+    let result = client.download(request("b"), &mut target).await;
+    assert!(result.is_err());
+}
+
+#[test]
+fn missing_cassette_fails_build() {
+    let result = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", "http://localhost:9000")
+        .credentials(Credentials::Anonymous)
+        .replay("/nonexistent/cassette.json")
+        .build();
+    assert!(result.is_err());
+}
@@ -0,0 +1,195 @@
+use futures::future::{self, Ready};
+use hyper::{client::HttpConnector, service::Service, Uri};
+use rusoto_core::Region;
+use rusoto_s3::S3;
+use s3_ext::{
+    client::{fips_region, Credentials, S3ExtClientBuilder},
+    error::S3ExtError,
+    multi_region::MultiRegionClient,
+};
+use std::{
+    env, io,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::net::TcpStream;
+
+// Connector recording the hosts connections are requested to, without
+// connecting
+#[derive(Clone, Default)]
+struct RecordingConnector(Arc<Mutex<Vec<String>>>);
+
+impl Service<Uri> for RecordingConnector {
+    type Response = TcpStream;
+    type Error = io::Error;
+    type Future = Ready<io::Result<TcpStream>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let host = uri.host().unwrap_or_default().to_owned();
+        self.0.lock().unwrap().push(host);
+        future::ready(Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            "not connecting",
+        )))
+    }
+}
+
+#[test]
+fn build_client_with_static_credentials() {
+    S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", "http://localhost:9000")
+        .credentials(Credentials::Static {
+            access_key: "ANTN35UAENTS5UIAEATD".to_owned(),
+            secret_key: "TtnuieannGt2rGuie2t8Tt7urarg5nauedRndrur".to_owned(),
+            token: None,
+        })
+        .connect_timeout(Duration::from_secs(1))
+        .request_timeout(Duration::from_secs(10))
+        .max_retries(2)
+        .build()
+        .unwrap();
+}
+
+#[test]
+fn build_client_with_custom_connector() {
+    S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", "http://localhost:9000")
+        .credentials(Credentials::Anonymous)
+        .connector(HttpConnector::new())
+        .build()
+        .unwrap();
+}
+
+#[test]
+fn web_identity_requires_environment() {
+    if env::var_os("AWS_WEB_IDENTITY_TOKEN_FILE").is_some() {
+        return;
+    }
+    match s3_ext::new_s3client_from_web_identity(Region::UsEast1) {
+        Err(S3ExtError::CredentialsError(e)) => {
+            assert_eq!(e.message, "AWS_WEB_IDENTITY_TOKEN_FILE is not set")
+        }
+        r => panic!("unexpected result: {:?}", r.map(|_| ())),
+    }
+    let builder = S3ExtClientBuilder::new()
+        .region(Region::UsEast1)
+        .credentials(Credentials::WebIdentity);
+    match builder.build() {
+        Err(S3ExtError::CredentialsError(e)) => {
+            assert_eq!(e.message, "AWS_WEB_IDENTITY_TOKEN_FILE is not set")
+        }
+        r => panic!("unexpected result: {:?}", r.map(|_| ())),
+    }
+}
+
+#[tokio::test]
+async fn custom_endpoint_is_not_routed() {
+    let client = MultiRegionClient::new(
+        S3ExtClientBuilder::new()
+            .endpoint("eu-west-1", "http://localhost:9000")
+            .credentials(Credentials::Anonymous),
+    )
+    .unwrap();
+    let region = client.region_of("bucket").await.unwrap();
+    assert_eq!(region.name(), "eu-west-1");
+}
+
+#[test]
+fn fips_endpoints() {
+    match fips_region(&Region::UsGovWest1) {
+        Some(Region::Custom { name, endpoint }) => {
+            assert_eq!(name, "us-gov-west-1");
+            assert_eq!(endpoint, "https://s3-fips.us-gov-west-1.amazonaws.com");
+        }
+        r => panic!("unexpected region: {:?}", r),
+    }
+    assert_eq!(fips_region(&Region::EuWest1), None);
+
+    let result = S3ExtClientBuilder::new()
+        .region(Region::EuWest1)
+        .credentials(Credentials::Anonymous)
+        .fips(true)
+        .build();
+    assert!(matches!(result, Err(S3ExtError::Other(_))));
+    S3ExtClientBuilder::new()
+        .region(Region::UsEast1)
+        .credentials(Credentials::Anonymous)
+        .fips(true)
+        .build()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn fips_assume_role_uses_regional_sts() {
+    // source credentials of the role session
+    env::set_var("AWS_ACCESS_KEY_ID", "ANTN35UAENTS5UIAEATD");
+    env::set_var(
+        "AWS_SECRET_ACCESS_KEY",
+        "TtnuieannGt2rGuie2t8Tt7urarg5nauedRndrur",
+    );
+    let connector = RecordingConnector::default();
+    let client = S3ExtClientBuilder::new()
+        .region(Region::UsEast1)
+        .credentials(Credentials::AssumeRole {
+            role_arn: "arn:aws:iam::123456789012:role/test".to_owned(),
+            session_name: "test".to_owned(),
+        })
+        .connector(connector.clone())
+        .max_retries(0)
+        .fips(true)
+        .build()
+        .unwrap();
+    assert!(client.list_buckets().await.is_err());
+
+    let hosts = connector.0.lock().unwrap();
+    assert_eq!(
+        hosts.first().map(String::as_str),
+        Some("sts.us-east-1.amazonaws.com")
+    );
+    assert!(!hosts.iter().any(|host| host.contains("s3-fips")));
+}
+
+#[tokio::test]
+async fn custom_endpoint_assume_role_uses_aws_sts() {
+    env::set_var("AWS_ACCESS_KEY_ID", "ANTN35UAENTS5UIAEATD");
+    env::set_var(
+        "AWS_SECRET_ACCESS_KEY",
+        "TtnuieannGt2rGuie2t8Tt7urarg5nauedRndrur",
+    );
+    let first_host = |builder: S3ExtClientBuilder| async move {
+        let connector = RecordingConnector::default();
+        let client = builder
+            .credentials(Credentials::AssumeRole {
+                role_arn: "arn:aws:iam::123456789012:role/test".to_owned(),
+                session_name: "test".to_owned(),
+            })
+            .connector(connector.clone())
+            .max_retries(0)
+            .build()
+            .unwrap();
+        assert!(client.list_buckets().await.is_err());
+        let hosts = connector.0.lock().unwrap();
+        hosts.first().cloned()
+    };
+
+    let minio = S3ExtClientBuilder::new().endpoint("eu-west-1", "http://minio:9000");
+    assert_eq!(
+        first_host(minio.clone()).await.as_deref(),
+        Some("sts.eu-west-1.amazonaws.com")
+    );
+    let r2 = S3ExtClientBuilder::new().endpoint("auto", "https://abc.r2.cloudflarestorage.com");
+    assert_eq!(
+        first_host(r2).await.as_deref(),
+        Some("sts.us-east-1.amazonaws.com")
+    );
+    let minio_sts = minio.sts_region(Region::Custom {
+        name: "eu-west-1".to_owned(),
+        endpoint: "http://minio:9000".to_owned(),
+    });
+    assert_eq!(first_host(minio_sts).await.as_deref(), Some("minio"));
+}
@@ -0,0 +1,114 @@
+use futures::future::join_all;
+use rusoto_s3::{GetObjectRequest, S3Client};
+use s3_ext::{
+    client::{Credentials, S3ExtClientBuilder},
+    coalesce::CoalescingS3Client,
+    error::S3ExtError,
+    S3Ext,
+};
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+const NO_SUCH_KEY: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+    <Error><Code>NoSuchKey</Code><Message>The specified key does not exist.</Message></Error>";
+
+// Respond to every request with `hello` after a delay, `404 NoSuchKey` for
+// keys named `missing`, sending the request line to the returned receiver
+fn server() -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = [0; 4096];
+            let n = stream.read(&mut buf).unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let line = request.lines().next().unwrap_or_default().to_owned();
+            thread::sleep(Duration::from_millis(200));
+            let (status, body) = if line.contains("/missing") {
+                ("404 Not Found", NO_SUCH_KEY)
+            } else {
+                ("200 OK", "hello")
+            };
+            let _ = tx.send(line);
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    (endpoint, rx)
+}
+
+fn client(endpoint: String) -> CoalescingS3Client<S3Client> {
+    let client = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", endpoint)
+        .credentials(Credentials::Anonymous)
+        .build()
+        .unwrap();
+    CoalescingS3Client::new(client)
+}
+
+fn request(key: &str) -> GetObjectRequest {
+    GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn concurrent_downloads_are_coalesced() {
+    let (endpoint, requests) = server();
+    let client = client(endpoint);
+    let downloads = (0..10).map(|_| async {
+        let mut body = Vec::new();
+        client
+            .download(request("key"), &mut body)
+            .await
+            .map(|_| body)
+    });
+    for body in join_all(downloads).await {
+        assert_eq!(body.unwrap(), b"hello");
+    }
+    assert_eq!(requests.try_iter().count(), 1);
+    assert_eq!(client.in_flight(), 0);
+
+    // ranges are requested separately, completed downloads aren't reused
+    let ranged = GetObjectRequest {
+        range: Some("bytes=0-1".to_owned()),
+        ..request("key")
+    };
+    let mut body = Vec::new();
+    let (first, second) = futures::join!(
+        client.download(request("key"), &mut Vec::new()),
+        client.download(ranged, &mut body),
+    );
+    first.unwrap();
+    second.unwrap();
+    assert_eq!(requests.try_iter().count(), 2);
+}
+
+#[tokio::test]
+async fn errors_are_shared() {
+    let (endpoint, requests) = server();
+    let client = client(endpoint);
+    let downloads = (0..3).map(|_| async {
+        let mut body = Vec::new();
+        client.download(request("missing"), &mut body).await
+    });
+    for result in join_all(downloads).await {
+        let err = result.unwrap_err();
+        assert!(matches!(err, S3ExtError::Shared(_)));
+        assert!(err.is_not_found());
+        assert_eq!(err.key(), Some("missing"));
+    }
+    assert_eq!(requests.try_iter().count(), 1);
+}
@@ -0,0 +1,109 @@
+#![cfg(all(feature = "serde", feature = "mock"))]
+
+use s3_ext::{codec::Codec, error::S3ExtError, mock::MockS3, S3Ext};
+use std::collections::BTreeMap;
+
+fn document() -> BTreeMap<String, Vec<u32>> {
+    let mut value = BTreeMap::new();
+    value.insert("primes".to_owned(), vec![2, 3, 5, 7]);
+    value.insert("empty".to_owned(), vec![]);
+    value
+}
+
+async fn round_trip<C: Codec>() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+
+    s3.put_as::<_, C>("bucket", "doc", &document())
+        .await
+        .unwrap();
+    let record = s3.object("bucket", "doc").unwrap();
+    assert_eq!(record.content_type.as_deref(), Some(C::CONTENT_TYPE));
+    let read: BTreeMap<String, Vec<u32>> = s3.get_as::<_, C>("bucket", "doc").await.unwrap();
+    assert_eq!(read, document());
+
+    s3.put_object("bucket", "invalid", vec![0xff; 3]);
+    let err = s3
+        .get_as::<BTreeMap<String, Vec<u32>>, C>("bucket", "invalid")
+        .await
+        .unwrap_err();
+    assert_eq!(err.key(), Some("invalid"));
+}
+
+#[cfg(feature = "serde_json")]
+#[tokio::test]
+async fn json() {
+    round_trip::<s3_ext::codec::Json>().await;
+}
+
+#[cfg(feature = "cbor")]
+#[tokio::test]
+async fn cbor() {
+    round_trip::<s3_ext::codec::Cbor>().await;
+}
+
+#[cfg(feature = "msgpack")]
+#[tokio::test]
+async fn msgpack() {
+    round_trip::<s3_ext::codec::MessagePack>().await;
+}
+
+#[cfg(feature = "bincode")]
+#[tokio::test]
+async fn bincode() {
+    round_trip::<s3_ext::codec::Bincode>().await;
+}
+
+#[cfg(feature = "serde_json")]
+#[tokio::test]
+async fn put_and_get_json() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+
+    s3.put_json("bucket", "doc.json", &document())
+        .await
+        .unwrap();
+    let record = s3.object("bucket", "doc.json").unwrap();
+    assert_eq!(record.content_type.as_deref(), Some("application/json"));
+    assert_eq!(record.body, br#"{"empty":[],"primes":[2,3,5,7]}"#);
+
+    let read: BTreeMap<String, Vec<u32>> = s3.get_json("bucket", "doc.json").await.unwrap();
+    assert_eq!(read, document());
+
+    s3.put_object("bucket", "invalid.json", "{");
+    let err = s3
+        .get_json::<serde_json::Value>("bucket", "invalid.json")
+        .await
+        .unwrap_err();
+    assert_eq!(err.key(), Some("invalid.json"));
+    assert!(matches!(err.inner(), S3ExtError::Json(_)));
+}
+
+#[cfg(feature = "serde_json")]
+#[tokio::test]
+async fn size_limit() {
+    use s3_ext::codec::MAX_SIZE;
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+
+    let large = "x".repeat(MAX_SIZE as usize);
+    let err = s3
+        .put_json(
+            "bucket",
+            "large.json",
+            &serde_json::json!({ "data": large }),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err.inner(), S3ExtError::TooLarge { .. }));
+    assert!(s3.object("bucket", "large.json").is_none());
+
+    s3.put_object("bucket", "large.json", vec![b' '; MAX_SIZE as usize + 1]);
+    let err = s3
+        .get_json::<serde_json::Value>("bucket", "large.json")
+        .await
+        .unwrap_err();
+    assert_eq!(err.key(), Some("large.json"));
+    assert!(matches!(err.inner(), S3ExtError::TooLarge { .. }));
+}
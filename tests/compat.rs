@@ -0,0 +1,184 @@
+use rusoto_core::Region;
+use rusoto_s3::PutObjectRequest;
+use s3_ext::compat::{Compatibility, Provider};
+
+fn custom(endpoint: &str) -> Region {
+    Region::Custom {
+        name: "eu-west-1".to_owned(),
+        endpoint: endpoint.to_owned(),
+    }
+}
+
+#[test]
+fn detect_provider() {
+    assert_eq!(Provider::detect(&Region::UsEast1), Provider::Aws);
+    assert_eq!(
+        Provider::detect(&custom("https://s3.eu-west-1.amazonaws.com")),
+        Provider::Aws
+    );
+    assert_eq!(
+        Provider::detect(&custom("http://localhost:9000")),
+        Provider::Minio
+    );
+    assert_eq!(
+        Provider::detect(&custom("https://abc.r2.cloudflarestorage.com")),
+        Provider::CloudflareR2
+    );
+    assert_eq!(
+        Provider::detect(&custom("https://s3.us-west-004.backblazeb2.com")),
+        Provider::BackblazeB2
+    );
+    assert_eq!(
+        Provider::detect(&custom("https://storage.googleapis.com")),
+        Provider::Gcs
+    );
+    assert_eq!(
+        Provider::detect(&custom("https://s3.example.net")),
+        Provider::Other
+    );
+}
+
+#[test]
+fn sanitize_put_request() {
+    let request = PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "key".to_owned(),
+        object_lock_mode: Some("GOVERNANCE".to_owned()),
+        server_side_encryption: Some("aws:kms".to_owned()),
+        ssekms_key_id: Some("key-id".to_owned()),
+        tagging: Some("a=b".to_owned()),
+        request_payer: Some("requester".to_owned()),
+        ..Default::default()
+    };
+
+    let mut aws = request;
+    Compatibility::default().sanitize_put_request(&mut aws);
+    assert_eq!(aws.object_lock_mode.as_deref(), Some("GOVERNANCE"));
+    assert_eq!(aws.ssekms_key_id.as_deref(), Some("key-id"));
+    assert_eq!(aws.tagging.as_deref(), Some("a=b"));
+
+    let mut r2 = aws;
+    Compatibility::for_provider(Provider::CloudflareR2).sanitize_put_request(&mut r2);
+    assert_eq!(r2.object_lock_mode, None);
+    assert_eq!(r2.server_side_encryption, None);
+    assert_eq!(r2.ssekms_key_id, None);
+    assert_eq!(r2.tagging, None);
+    assert_eq!(r2.request_payer, None);
+    assert_eq!(r2.bucket, "bucket");
+    assert_eq!(r2.key, "key");
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn upload_without_tagging() {
+    use s3_ext::{compat::CompatibleS3Client, mock::MockS3, S3Ext};
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let request = |key: &str| PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        tagging: Some("project=travel".to_owned()),
+        ..Default::default()
+    };
+
+    let aws = CompatibleS3Client::new(s3.clone(), Compatibility::default());
+    aws.upload(&mut &b"aws"[..], request("aws")).await.unwrap();
+    let object = s3.object("bucket", "aws").unwrap();
+    assert_eq!(
+        object.tags.get("project").map(String::as_str),
+        Some("travel")
+    );
+
+    let r2 = CompatibleS3Client::new(
+        s3.clone(),
+        Compatibility::for_provider(Provider::CloudflareR2),
+    );
+    r2.upload(&mut &b"r2"[..], request("r2")).await.unwrap();
+    let object = s3.object("bucket", "r2").unwrap();
+    assert!(object.tags.is_empty());
+    assert_eq!(object.body, b"r2");
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn download_without_sse_customer() {
+    use rusoto_s3::GetObjectRequest;
+    use s3_ext::{compat::CompatibleS3Client, mock::MockS3, S3Ext};
+
+    let s3 = MockS3::new();
+    s3.put_object("bucket", "key", "hello");
+    let request = || GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "key".to_owned(),
+        sse_customer_algorithm: Some("AES256".to_owned()),
+        sse_customer_key: Some("a".repeat(32)),
+        ..Default::default()
+    };
+
+    let mut body = Vec::new();
+    let aws = CompatibleS3Client::new(s3.clone(), Compatibility::default());
+    assert!(aws.download(request(), &mut body).await.is_err());
+
+    let compat = Compatibility {
+        sse_customer: false,
+        ..Compatibility::for_provider(Provider::Other)
+    };
+    let other = CompatibleS3Client::new(s3, compat);
+    other.download(request(), &mut body).await.unwrap();
+    assert_eq!(body, b"hello");
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn attributes_without_get_object_attributes() {
+    use s3_ext::{compat::CompatibleS3Client, mock::MockS3, S3Ext};
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let data = vec![7; 11 * 1024 * 1024];
+    let request = PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "large".to_owned(),
+        ..Default::default()
+    };
+    s3.upload_multipart(&mut &data[..], request, 5 * 1024 * 1024)
+        .await
+        .unwrap();
+
+    let r2 = CompatibleS3Client::new(s3, Compatibility::for_provider(Provider::CloudflareR2));
+    assert!(!r2.compatibility().get_object_attributes);
+    let attributes = r2.object_attributes("bucket", "large").await.unwrap();
+    assert_eq!(attributes.size, 11 * 1024 * 1024);
+    assert_eq!(attributes.parts_count, Some(3));
+    assert_eq!(attributes.part_size, Some(5 * 1024 * 1024));
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn diff_with_compatibility() {
+    use futures::TryStreamExt;
+    use s3_ext::{compat::CompatibleS3Client, diff::DiffEntry, mock::MockS3, S3Ext};
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    for key in &["src/a", "src/b", "src/c", "dst/b", "dst/d"] {
+        s3.put_object("bucket", key, *key);
+    }
+    // one object per page, continued after the last key
+    let compat = Compatibility {
+        max_keys: 1,
+        start_after_fallback: true,
+        ..Compatibility::for_provider(Provider::Other)
+    };
+    let client = CompatibleS3Client::new(s3, compat);
+
+    let entries: Vec<_> = client
+        .diff("bucket", "src/", "bucket", "dst/")
+        .try_collect()
+        .await
+        .unwrap();
+    let keys: Vec<_> = entries.iter().map(DiffEntry::key).collect();
+    assert_eq!(keys, vec!["a", "b", "c", "d"]);
+    assert!(matches!(&entries[1], DiffEntry::Different { .. }));
+}
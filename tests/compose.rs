@@ -0,0 +1,63 @@
+#![cfg(feature = "mock")]
+
+use s3_ext::{compose::ComposePart, mock::MockS3, S3Ext};
+
+const MIB: usize = 1024 * 1024;
+
+#[tokio::test]
+async fn compose_copies_and_data() {
+    let s3 = MockS3::new();
+    let large: Vec<u8> = (0..6 * MIB).map(|i| (i % 251) as u8).collect();
+    s3.put_object("bucket", "large", large.clone());
+    s3.put_object("bucket", "small", "0123456789");
+
+    let parts = vec![
+        ComposePart::copy("bucket", "large"),
+        ComposePart::copy_range("bucket", "large", 0..5 * MIB as u64),
+        ComposePart::copy_range("bucket", "small", 2..5),
+    ];
+    s3.compose_object("bucket", "composed", parts)
+        .await
+        .unwrap();
+    let object = s3.object("bucket", "composed").unwrap();
+    let mut expected = large.clone();
+    expected.extend_from_slice(&large[..5 * MIB]);
+    expected.extend_from_slice(b"234");
+    assert_eq!(object.body, expected);
+    assert_eq!(object.part_sizes, vec![6 * MIB, 5 * MIB, 3]);
+
+    // append to an object
+    let parts = vec![
+        ComposePart::copy("bucket", "large"),
+        ComposePart::Data(b"appended".to_vec()),
+    ];
+    s3.compose_object("bucket", "large", parts).await.unwrap();
+    let object = s3.object("bucket", "large").unwrap();
+    assert_eq!(&object.body[..6 * MIB], &large[..]);
+    assert_eq!(&object.body[6 * MIB..], b"appended");
+}
+
+#[tokio::test]
+async fn compose_errors_abort_upload() {
+    let s3 = MockS3::new();
+    s3.put_object("bucket", "small", "small");
+
+    // parts but the last must be at least 5 MiB
+    let parts = vec![
+        ComposePart::Data(b"too small".to_vec()),
+        ComposePart::copy("bucket", "small"),
+    ];
+    assert!(s3.compose_object("bucket", "key", parts).await.is_err());
+    assert!(s3
+        .compose_object("bucket", "key", Vec::new())
+        .await
+        .is_err());
+
+    // missing sources fail the upload
+    let parts = vec![ComposePart::copy("bucket", "missing")];
+    let err = s3.compose_object("bucket", "key", parts).await.unwrap_err();
+    assert!(err.is_not_found());
+    assert_eq!(err.key(), Some("missing"));
+    assert_eq!(s3.pending_uploads(), 0);
+    assert!(s3.object("bucket", "key").is_none());
+}
@@ -0,0 +1,147 @@
+#![cfg(feature = "mock")]
+
+use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+use s3_ext::{
+    compress::{Compression, ORIGINAL_SIZE_METADATA},
+    error::S3ExtError,
+    mock::MockS3,
+    S3Ext,
+};
+
+fn request(key: &str) -> PutObjectRequest {
+    PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    }
+}
+
+fn source(key: &str) -> GetObjectRequest {
+    GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    }
+}
+
+fn log(lines: usize) -> Vec<u8> {
+    (0..lines)
+        .flat_map(|i| format!("GET /index.html 200 request {}\n", i).into_bytes())
+        .collect()
+}
+
+#[cfg(feature = "gzip")]
+#[tokio::test]
+async fn upload_gzip() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let body = log(10_000);
+
+    s3.upload_compressed(
+        &mut &body[..],
+        request("access.log"),
+        Compression::Gzip,
+        Some(body.len() as u64),
+    )
+    .await
+    .unwrap();
+
+    let object = s3.object("bucket", "access.log").unwrap();
+    assert!(object.body.len() < body.len() / 4);
+    assert_eq!(object.content_encoding.as_deref(), Some("gzip"));
+    assert_eq!(
+        object.metadata.get(ORIGINAL_SIZE_METADATA),
+        Some(&body.len().to_string())
+    );
+
+    let mut downloaded = Vec::new();
+    s3.download_decompressed(source("access.log"), &mut downloaded)
+        .await
+        .unwrap();
+    assert_eq!(downloaded, body);
+}
+
+#[cfg(feature = "gzip")]
+#[tokio::test]
+async fn upload_appends_encoding_without_original_size() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let target = PutObjectRequest {
+        content_encoding: Some("identity".to_owned()),
+        ..request("access.log")
+    };
+
+    s3.upload_compressed(&mut &log(10)[..], target, Compression::Gzip, None)
+        .await
+        .unwrap();
+
+    let object = s3.object("bucket", "access.log").unwrap();
+    assert_eq!(object.content_encoding.as_deref(), Some("identity, gzip"));
+    assert!(object.metadata.get(ORIGINAL_SIZE_METADATA).is_none());
+}
+
+#[cfg(feature = "gzip")]
+#[tokio::test]
+async fn upload_aborted_on_size_mismatch() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let body = log(100);
+
+    let err = s3
+        .upload_compressed(
+            &mut &body[..],
+            request("access.log"),
+            Compression::Gzip,
+            Some(body.len() as u64 + 1),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err.into_inner(), S3ExtError::IoError(_)));
+    assert!(s3.object("bucket", "access.log").is_none());
+    assert_eq!(s3.pending_uploads(), 0);
+}
+
+#[cfg(feature = "zstd")]
+#[tokio::test]
+async fn upload_zstd() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let body = log(1000);
+
+    s3.upload_compressed(
+        &mut &body[..],
+        request("access.log"),
+        Compression::Zstd(19),
+        None,
+    )
+    .await
+    .unwrap();
+
+    let object = s3.object("bucket", "access.log").unwrap();
+    assert_eq!(object.content_encoding.as_deref(), Some("zstd"));
+
+    let mut downloaded = Vec::new();
+    s3.download_decompressed(source("access.log"), &mut downloaded)
+        .await
+        .unwrap();
+    assert_eq!(downloaded, body);
+}
+
+#[cfg(not(feature = "zstd"))]
+#[tokio::test]
+async fn upload_zstd_requires_feature() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+
+    let err = s3
+        .upload_compressed(
+            &mut &log(10)[..],
+            request("access.log"),
+            Compression::Zstd(3),
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, S3ExtError::Other(_)));
+    assert!(s3.object("bucket", "access.log").is_none());
+}
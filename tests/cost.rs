@@ -0,0 +1,111 @@
+use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+use s3_ext::{
+    client::{Credentials, S3ExtClientBuilder},
+    cost::{CostTracker, Pricing, Usage},
+    rate_limit::RequestClass,
+    S3Ext,
+};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    thread,
+};
+
+// Respond to `GET` requests with `hello` and to others with an empty body,
+// after reading the request body
+fn server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = BufReader::new(stream.unwrap());
+            let mut length = 0;
+            let mut get = None;
+            loop {
+                let mut line = String::new();
+                stream.read_line(&mut line).unwrap();
+                let line = line.trim_end().to_ascii_lowercase();
+                get.get_or_insert_with(|| line.starts_with("get "));
+                if line.is_empty() {
+                    break;
+                }
+                if let Some(value) = line.strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            stream.read_exact(&mut body).unwrap();
+            let body = if get == Some(true) { "hello" } else { "" };
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.get_mut().write_all(response.as_bytes()).unwrap();
+        }
+    });
+    endpoint
+}
+
+#[tokio::test]
+async fn requests_and_bytes_are_counted() {
+    let tracker = CostTracker::new();
+    let client = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", server())
+        .credentials(Credentials::Anonymous)
+        .cost_tracker(tracker.clone())
+        .build()
+        .unwrap();
+
+    for _ in 0..3 {
+        let request = GetObjectRequest {
+            bucket: "bucket".to_owned(),
+            key: "key".to_owned(),
+            ..Default::default()
+        };
+        client.download(request, &mut Vec::new()).await.unwrap();
+    }
+    let request = PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "key".to_owned(),
+        content_length: Some(10),
+        ..Default::default()
+    };
+    client
+        .upload(&mut &b"0123456789"[..], request)
+        .await
+        .unwrap();
+    client.delete("bucket", "key").await.unwrap();
+
+    let usage = tracker.usage();
+    assert_eq!(usage.requests(RequestClass::Get), 3);
+    assert_eq!(usage.requests(RequestClass::Put), 1);
+    assert_eq!(usage.requests(RequestClass::Delete), 1);
+    assert_eq!(usage.requests(RequestClass::List), 0);
+    assert_eq!(usage.bytes_sent, 10);
+    assert_eq!(usage.bytes_received, 15);
+
+    assert_eq!(tracker.reset(), usage);
+    assert_eq!(tracker.usage(), Usage::default());
+}
+
+#[test]
+fn estimate_cost() {
+    let usage = Usage {
+        list_requests: 1000,
+        get_requests: 10_000,
+        put_requests: 2000,
+        delete_requests: 500,
+        bytes_sent: 1 << 30,
+        bytes_received: 2 << 30,
+    };
+    let estimate = usage.estimate(&Pricing::s3_standard());
+    assert!((estimate.requests - (0.005 + 0.004 + 0.01)).abs() < 1e-9);
+    assert!((estimate.transfer - 0.18).abs() < 1e-9);
+    assert!((estimate.total() - 0.199).abs() < 1e-9);
+
+    let free = Pricing {
+        transfer_out: 0.0,
+        ..Pricing::s3_standard()
+    };
+    assert!(usage.estimate(&free).transfer.abs() < 1e-9);
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "mock")]
+
+use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+use s3_ext::{error::S3ExtError, mock::MockS3, S3Ext};
+
+async fn upload(s3: &MockS3, key: &str, body: &[u8], content_encoding: Option<&str>) {
+    let request = PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        content_encoding: content_encoding.map(str::to_owned),
+        ..Default::default()
+    };
+    s3.upload(&mut &body[..], request).await.unwrap();
+}
+
+fn request(key: &str) -> GetObjectRequest {
+    GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn download_identity() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    upload(&s3, "plain", b"plain text", None).await;
+    upload(&s3, "identity", b"identity", Some("identity")).await;
+
+    let mut body = Vec::new();
+    s3.download_decompressed(request("plain"), &mut body)
+        .await
+        .unwrap();
+    assert_eq!(body, b"plain text");
+
+    let mut body = Vec::new();
+    let output = s3
+        .download_decompressed(request("identity"), &mut body)
+        .await
+        .unwrap();
+    assert_eq!(body, b"identity");
+    assert_eq!(output.content_encoding.as_deref(), Some("identity"));
+}
+
+#[tokio::test]
+async fn download_unsupported_encoding() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    upload(&s3, "brotli", b"not really brotli", Some("br")).await;
+
+    let mut body = Vec::new();
+    let err = s3
+        .download_decompressed(request("brotli"), &mut body)
+        .await
+        .unwrap_err();
+    assert!(matches!(err.into_inner(), S3ExtError::Other(_)));
+    assert!(body.is_empty());
+}
+
+#[cfg(feature = "gzip")]
+#[tokio::test]
+async fn download_gzip() {
+    use async_compression::tokio::write::GzipEncoder;
+    use tokio::io::AsyncWriteExt;
+
+    let mut encoder = GzipEncoder::new(Vec::new());
+    encoder.write_all(b"<html></html>").await.unwrap();
+    encoder.shutdown().await.unwrap();
+    let compressed = encoder.into_inner();
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    upload(&s3, "index.html", &compressed, Some("gzip")).await;
+
+    let mut body = Vec::new();
+    let output = s3
+        .download_decompressed(request("index.html"), &mut body)
+        .await
+        .unwrap();
+    assert_eq!(body, b"<html></html>");
+    assert_eq!(output.content_encoding, None);
+    assert_eq!(output.content_length, None);
+
+    // `download` leaves the body as stored
+    let mut body = Vec::new();
+    s3.download(request("index.html"), &mut body).await.unwrap();
+    assert_eq!(body, compressed);
+}
+
+#[cfg(feature = "zstd")]
+#[tokio::test]
+async fn download_zstd() {
+    use async_compression::tokio::write::ZstdEncoder;
+    use tokio::io::AsyncWriteExt;
+
+    let mut encoder = ZstdEncoder::new(Vec::new());
+    encoder.write_all(b"{\"id\": 1}").await.unwrap();
+    encoder.shutdown().await.unwrap();
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    upload(&s3, "record.json", &encoder.into_inner(), Some("zstd")).await;
+
+    let mut body = Vec::new();
+    s3.download_decompressed(request("record.json"), &mut body)
+        .await
+        .unwrap();
+    assert_eq!(body, b"{\"id\": 1}");
+}
@@ -0,0 +1,56 @@
+use md5::{Digest, Md5};
+use s3_ext::dedup::file_e_tag;
+use std::fs;
+use tempdir::TempDir;
+
+#[tokio::test]
+async fn compute_e_tags() {
+    let dir = TempDir::new("s3-ext").unwrap();
+    let path = dir.path().join("file");
+    fs::write(&path, "hello").unwrap();
+
+    assert_eq!(
+        file_e_tag(&path, None).await.unwrap(),
+        "5d41402abc4b2a76b9719d911017c592"
+    );
+    let digests: Vec<u8> = [&b"he"[..], b"ll", b"o"]
+        .iter()
+        .flat_map(|part| Md5::digest(part))
+        .collect();
+    assert_eq!(
+        file_e_tag(&path, Some(2)).await.unwrap(),
+        format!("{}-3", hex::encode(Md5::digest(&digests)))
+    );
+    assert!(file_e_tag(&path, Some(5)).await.unwrap().ends_with("-1"));
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn skip_unchanged_files() {
+    use rusoto_s3::PutObjectRequest;
+    use s3_ext::{mock::MockS3, S3Ext};
+
+    let dir = TempDir::new("s3-ext").unwrap();
+    let path = dir.path().join("file");
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let put = |key: &str| PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    };
+
+    fs::write(&path, "first").unwrap();
+    assert!(s3.upload_if_changed(&path, put("key")).await.unwrap());
+    assert!(!s3.upload_if_changed(&path, put("key")).await.unwrap());
+    fs::write(&path, "other").unwrap();
+    assert!(s3.upload_if_changed(&path, put("key")).await.unwrap());
+    assert_eq!(s3.object("bucket", "key").unwrap().body, b"other");
+
+    let data: Vec<u8> = (0..11 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+    fs::write(&path, &data).unwrap();
+    s3.upload_multipart(&mut &data[..], put("large"), 5 * 1024 * 1024)
+        .await
+        .unwrap();
+    assert!(!s3.upload_if_changed(&path, put("large")).await.unwrap());
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "mock")]
+
+use s3_ext::{mock::MockS3, S3Ext};
+use tempdir::TempDir;
+
+const MIB: usize = 1024 * 1024;
+
+fn content(len: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (state >> 56) as u8
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn upload_delta_copies_unchanged_blocks() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let dir = TempDir::new("s3-ext").unwrap();
+    let path = dir.path().join("data");
+
+    // no signature yet, the whole file is uploaded
+    let mut data = content(20 * MIB, 1);
+    std::fs::write(&path, &data).unwrap();
+    let report = s3.upload_delta(&path, "bucket", "data").await.unwrap();
+    assert_eq!(report.uploaded, data.len() as u64);
+    assert_eq!(report.copied, 0);
+    assert_eq!(s3.object("bucket", "data").unwrap().body, data);
+    assert!(s3.object("bucket", "data.s3sig").is_some());
+
+    // insert bytes into the second block and append to the file
+    data.splice(9 * MIB..9 * MIB, b"inserted".iter().copied());
+    data.extend_from_slice(b"appended");
+    std::fs::write(&path, &data).unwrap();
+    let report = s3.upload_delta(&path, "bucket", "data").await.unwrap();
+    assert_eq!(report.copied, 8 * MIB as u64);
+    assert_eq!(report.uploaded, data.len() as u64 - report.copied);
+    let object = s3.object("bucket", "data").unwrap();
+    assert_eq!(object.body, data);
+    assert_eq!(object.part_sizes, vec![8 * MIB, data.len() - 8 * MIB]);
+
+    // unchanged file, all full blocks are copied
+    let report = s3.upload_delta(&path, "bucket", "data").await.unwrap();
+    assert_eq!(report.copied, 16 * MIB as u64);
+    assert_eq!(s3.object("bucket", "data").unwrap().body, data);
+}
+
+#[tokio::test]
+async fn upload_delta_ignores_outdated_signature() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let dir = TempDir::new("s3-ext").unwrap();
+    let path = dir.path().join("data");
+    let data = content(17 * MIB, 2);
+    std::fs::write(&path, &data).unwrap();
+    s3.upload_delta(&path, "bucket", "data").await.unwrap();
+
+    // object replaced without updating the signature
+    s3.put_object("bucket", "data", content(17 * MIB, 3));
+    let report = s3.upload_delta(&path, "bucket", "data").await.unwrap();
+    assert_eq!(report.copied, 0);
+    assert_eq!(s3.object("bucket", "data").unwrap().body, data);
+
+    // empty files are uploaded as a single empty part
+    std::fs::write(&path, b"").unwrap();
+    let report = s3.upload_delta(&path, "bucket", "data").await.unwrap();
+    assert_eq!(report.uploaded, 0);
+    assert!(s3.object("bucket", "data").unwrap().body.is_empty());
+}
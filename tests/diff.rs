@@ -0,0 +1,39 @@
+mod common;
+use crate::common::*;
+
+use futures::stream::TryStreamExt;
+use s3_ext::{diff::DiffEntry, S3Ext};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn diff_prefixes() {
+    let client = get_client();
+    let bucket = common::create_test_bucket(&client).await;
+
+    put_object(&client, &bucket, "src/both", b"same".to_vec()).await;
+    put_object(&client, &bucket, "dst/both", b"same".to_vec()).await;
+    put_object(&client, &bucket, "src/changed", b"old".to_vec()).await;
+    put_object(&client, &bucket, "dst/changed", b"new content".to_vec()).await;
+    put_object(&client, &bucket, "src/new", vec![]).await;
+    put_object(&client, &bucket, "dst/stale", vec![]).await;
+
+    let entries: Vec<_> = client
+        .diff(&bucket, "src/", &bucket, "dst/")
+        .try_collect()
+        .await
+        .unwrap();
+
+    let keys = [
+        "src/both",
+        "dst/both",
+        "src/changed",
+        "dst/changed",
+        "src/new",
+        "dst/stale",
+    ];
+    common::delete_test_bucket(&client, &bucket, &keys).await;
+
+    assert_eq!(entries.len(), 3);
+    assert!(matches!(&entries[0], DiffEntry::Different { key, .. } if key == "changed"));
+    assert!(matches!(&entries[1], DiffEntry::OnlyInSource { key, .. } if key == "new"));
+    assert!(matches!(&entries[2], DiffEntry::OnlyInDest { key, .. } if key == "stale"));
+}
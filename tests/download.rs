@@ -0,0 +1,70 @@
+#![cfg(feature = "mock")]
+
+use s3_ext::{error::S3ExtError, mock::MockS3, S3Ext};
+use std::fs;
+use tempdir::TempDir;
+
+fn mock_with_objects() -> MockS3 {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    s3.put_object("bucket", "a.txt", "alpha");
+    s3.put_object("bucket", "nested/dir/b.txt", "beta");
+    s3.put_object("bucket", "../escape.txt", "gamma");
+    s3
+}
+
+#[tokio::test]
+async fn download_objects_to_dir() {
+    let s3 = mock_with_objects();
+    let dir = TempDir::new("s3-ext").unwrap();
+    let keys = vec!["a.txt", "missing.txt", "nested/dir/b.txt", "../escape.txt"];
+
+    let report = s3.download_objects("bucket", keys, dir.path(), 2).await;
+    let keys: Vec<_> = report.results.iter().map(|(key, _)| key.as_str()).collect();
+    assert_eq!(
+        keys,
+        vec!["a.txt", "missing.txt", "nested/dir/b.txt", "../escape.txt"]
+    );
+    assert!(!report.is_success());
+    let failed: Vec<_> = report.failed().map(|(key, _)| key).collect();
+    assert_eq!(failed, vec!["missing.txt", "../escape.txt"]);
+    assert!(report.results[1].1.as_ref().unwrap_err().is_not_found());
+    assert!(matches!(
+        report.results[3].1.as_ref().unwrap_err().inner(),
+        S3ExtError::Other(_)
+    ));
+
+    assert_eq!(report.stats.bytes, 9);
+    assert_eq!(report.stats.parts, 2);
+    assert_eq!(fs::read(dir.path().join("a.txt")).unwrap(), b"alpha");
+    assert_eq!(
+        fs::read(dir.path().join("nested/dir/b.txt")).unwrap(),
+        b"beta"
+    );
+}
+
+#[tokio::test]
+async fn download_objects_with_writers() {
+    let s3 = mock_with_objects();
+    let dir = TempDir::new("s3-ext").unwrap();
+    let path = dir.path().to_owned();
+
+    let report = s3
+        .download_objects_with(
+            "bucket",
+            vec!["a.txt", "nested/dir/b.txt"],
+            |key| {
+                let file = fs::File::create(path.join(key.replace('/', "_")))?;
+                Ok(tokio::fs::File::from_std(file))
+            },
+            4,
+        )
+        .await;
+    assert!(report.is_success());
+    assert_eq!(report.stats.bytes, 9);
+    assert_eq!(fs::read(dir.path().join("a.txt")).unwrap(), b"alpha");
+    assert_eq!(
+        fs::read(dir.path().join("nested_dir_b.txt")).unwrap(),
+        b"beta"
+    );
+}
@@ -0,0 +1,47 @@
+use rusoto_s3::{
+    ServerSideEncryptionByDefault, ServerSideEncryptionConfiguration, ServerSideEncryptionRule,
+};
+use s3_ext::encryption::BucketEncryption;
+
+#[test]
+fn convert_configurations() {
+    for encryption in vec![
+        BucketEncryption::SseS3,
+        BucketEncryption::kms("arn:aws:kms:eu-west-1:123456789012:key/abc"),
+        BucketEncryption::SseKms {
+            key_id: None,
+            bucket_key: false,
+        },
+    ] {
+        let configuration = encryption.to_configuration();
+        assert_eq!(
+            BucketEncryption::from_configuration(configuration).unwrap(),
+            Some(encryption)
+        );
+    }
+}
+
+#[test]
+fn configuration_without_default() {
+    let configuration = ServerSideEncryptionConfiguration {
+        rules: vec![ServerSideEncryptionRule {
+            apply_server_side_encryption_by_default: None,
+            bucket_key_enabled: Some(true),
+        }],
+    };
+    assert_eq!(
+        BucketEncryption::from_configuration(configuration).unwrap(),
+        None
+    );
+
+    let configuration = ServerSideEncryptionConfiguration {
+        rules: vec![ServerSideEncryptionRule {
+            apply_server_side_encryption_by_default: Some(ServerSideEncryptionByDefault {
+                sse_algorithm: "aws:kms:dsse".to_owned(),
+                kms_master_key_id: None,
+            }),
+            bucket_key_enabled: None,
+        }],
+    };
+    assert!(BucketEncryption::from_configuration(configuration).is_err());
+}
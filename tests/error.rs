@@ -0,0 +1,95 @@
+use hyper::{body::Bytes, StatusCode};
+use rusoto_core::{request::BufferedHttpResponse, RusotoError};
+use rusoto_s3::GetObjectError;
+use s3_ext::error::S3ExtError;
+use std::{io, time::Duration};
+
+fn response(status: u16) -> BufferedHttpResponse {
+    BufferedHttpResponse {
+        status: StatusCode::from_u16(status).unwrap(),
+        body: Bytes::new(),
+        headers: Default::default(),
+    }
+}
+
+#[test]
+fn classify_throttling() {
+    let error = S3ExtError::PutObjectError(Box::new(RusotoError::Unknown(response(503))));
+    assert!(error.is_throttling());
+    assert!(error.is_retryable());
+    assert!(!error.is_not_found());
+}
+
+#[test]
+fn classify_server_error() {
+    let error = S3ExtError::PutObjectError(Box::new(RusotoError::Unknown(response(500))));
+    assert!(!error.is_throttling());
+    assert!(error.is_retryable());
+}
+
+#[test]
+fn classify_not_found() {
+    let error = S3ExtError::GetObjectError(Box::new(RusotoError::Service(
+        GetObjectError::NoSuchKey("key".to_owned()),
+    )));
+    assert!(error.is_not_found());
+    assert!(!error.is_retryable());
+
+    let error = S3ExtError::PutObjectError(Box::new(RusotoError::Unknown(response(404))));
+    assert!(error.is_not_found());
+}
+
+#[test]
+fn classify_other() {
+    assert!(S3ExtError::Timeout(Duration::from_secs(1)).is_retryable());
+    let error = S3ExtError::IoError(io::Error::other("broken"));
+    assert!(!error.is_retryable());
+    assert!(!error.is_throttling());
+    assert!(!error.is_not_found());
+    let error = S3ExtError::PutObjectError(Box::new(RusotoError::Validation("invalid".to_owned())));
+    assert!(!error.is_retryable());
+}
+
+#[test]
+fn request_ids_from_headers() {
+    let mut response = response(500);
+    response
+        .headers
+        .insert("x-amz-request-id", "4442587FB7D0A2F9".to_owned());
+    response
+        .headers
+        .insert("x-amz-id-2", "vlR7PnpV2Ce81".to_owned());
+    let error = S3ExtError::PutObjectError(Box::new(RusotoError::Unknown(response)));
+    assert_eq!(error.request_id(), Some("4442587FB7D0A2F9"));
+    assert_eq!(error.extended_request_id(), Some("vlR7PnpV2Ce81"));
+}
+
+#[test]
+fn request_ids_from_body() {
+    let mut response = response(403);
+    response.body = Bytes::from_static(
+        b"<Error><Code>AccessDenied</Code><RequestId>4442587FB7D0A2F9</RequestId>\
+          <HostId>vlR7PnpV2Ce81</HostId></Error>",
+    );
+    let error = S3ExtError::GetObjectError(Box::new(RusotoError::Unknown(response)));
+    assert_eq!(error.request_id(), Some("4442587FB7D0A2F9"));
+    assert_eq!(error.extended_request_id(), Some("vlR7PnpV2Ce81"));
+}
+
+#[test]
+fn object_error_display_includes_request_id() {
+    let mut response = response(500);
+    response
+        .headers
+        .insert("x-amz-request-id", "4442587FB7D0A2F9".to_owned());
+    let error = S3ExtError::Object {
+        bucket: "bucket".to_owned(),
+        key: "key".to_owned(),
+        source: Box::new(S3ExtError::PutObjectError(Box::new(RusotoError::Unknown(
+            response,
+        )))),
+    };
+    assert_eq!(error.request_id(), Some("4442587FB7D0A2F9"));
+    let message = error.to_string();
+    assert!(message.ends_with("(bucket: bucket, key: key, request ID: 4442587FB7D0A2F9)"));
+}
@@ -0,0 +1,44 @@
+use chrono::{TimeZone, Utc};
+use rusoto_s3::{HeadObjectOutput, PutObjectOutput};
+use s3_ext::expiration::{Expiration, ObjectExpiration};
+
+#[test]
+fn parse_expiration_header() {
+    let expiration = Expiration::from_header(
+        r#"expiry-date="Fri, 23 Dec 2012 00:00:00 GMT", rule-id="delete%20uploads""#,
+    )
+    .unwrap();
+    assert_eq!(
+        expiration,
+        Expiration {
+            expiry_date: Utc.with_ymd_and_hms(2012, 12, 23, 0, 0, 0).unwrap(),
+            rule_id: "delete uploads".to_owned(),
+        }
+    );
+    assert_eq!(Expiration::from_header(""), None);
+    assert_eq!(
+        Expiration::from_header(r#"expiry-date="yesterday", rule-id="cleanup""#),
+        None
+    );
+    assert_eq!(
+        Expiration::from_header(r#"expiry-date="Fri, 23 Dec 2012 00:00:00 GMT""#),
+        None
+    );
+}
+
+#[test]
+fn expiry_of_outputs() {
+    let put = PutObjectOutput {
+        expiration: Some(
+            r#"expiry-date="Sun, 01 Jan 2023 00:00:00 GMT", rule-id="tmp""#.to_owned(),
+        ),
+        ..Default::default()
+    };
+    let expiration = put.expiry().unwrap();
+    assert_eq!(expiration.rule_id, "tmp");
+    assert_eq!(
+        expiration.expiry_date,
+        Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap()
+    );
+    assert_eq!(HeadObjectOutput::default().expiry(), None);
+}
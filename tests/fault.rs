@@ -0,0 +1,95 @@
+use rusoto_s3::{GetObjectRequest, S3Client};
+use s3_ext::{
+    client::{Credentials, S3ExtClientBuilder},
+    fault::FaultInjector,
+    S3Ext,
+};
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    thread,
+};
+
+// Respond to every request with `hello`
+fn server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = [0; 4096];
+            let _ = stream.read(&mut buf).unwrap();
+            let body = "hello";
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    endpoint
+}
+
+fn client(faults: FaultInjector) -> S3Client {
+    S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", server())
+        .credentials(Credentials::Anonymous)
+        .fault_injection(faults)
+        .build()
+        .unwrap()
+}
+
+fn request() -> GetObjectRequest {
+    GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "key".to_owned(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn nth_request_fails() {
+    let faults = FaultInjector::new().fail_nth(2);
+    let client = client(faults.clone());
+
+    let mut target = Vec::new();
+    client.download(request(), &mut target).await.unwrap();
+    let err = client.download(request(), &mut target).await.unwrap_err();
+    assert!(err.is_retryable());
+    client.download(request(), &mut target).await.unwrap();
+    assert_eq!(target, b"hellohello");
+    assert_eq!(faults.requests(), 3);
+}
+
+#[tokio::test]
+async fn requests_are_throttled() {
+    let client = client(FaultInjector::new().throttle(1.0));
+    let mut target = Vec::new();
+    let err = client.download(request(), &mut target).await.unwrap_err();
+    assert!(err.is_throttling());
+    assert!(target.is_empty());
+}
+
+#[tokio::test]
+async fn bodies_are_truncated() {
+    let client = client(FaultInjector::new().truncate_bodies(1.0));
+    let mut target = Vec::new();
+    assert!(client.download(request(), &mut target).await.is_err());
+    assert_eq!(target, b"he");
+}
+
+#[tokio::test]
+async fn injected_failures_are_retried() {
+    let faults = FaultInjector::new().fail_nth(1);
+    let client = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", server())
+        .credentials(Credentials::Anonymous)
+        .fault_injection(faults.clone())
+        .max_retries(1)
+        .build()
+        .unwrap();
+    let mut target = Vec::new();
+    client.download(request(), &mut target).await.unwrap();
+    assert_eq!(target, b"hello");
+    assert_eq!(faults.requests(), 2);
+}
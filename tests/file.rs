@@ -38,7 +38,7 @@ async fn target_file_already_exists() {
 
     common::delete_test_bucket(&client, &bucket, &[key]).await;
 
-    match result {
+    match result.map_err(S3ExtError::into_inner) {
         Err(S3ExtError::IoError(ref e)) if e.kind() == ErrorKind::AlreadyExists => (),
         e => panic!("unexpected result: {:?}", e),
     }
@@ -65,8 +65,12 @@ async fn target_file_not_created_when_object_does_not_exist() {
 
     common::delete_test_bucket(&client, &bucket, &[]).await;
 
-    match result {
-        Err(S3ExtError::GetObjectError(RusotoError::Service(GetObjectError::NoSuchKey(_)))) => (),
+    let err = result.unwrap_err();
+    assert_eq!(err.bucket(), Some(bucket.as_str()));
+    assert_eq!(err.key(), Some("no_such_key"));
+    match err.into_inner() {
+        S3ExtError::GetObjectError(e)
+            if matches!(*e, RusotoError::Service(GetObjectError::NoSuchKey(_))) => {}
         e => panic!("unexpected result: {:?}", e),
     }
     assert!(
@@ -158,6 +162,34 @@ async fn test_download() {
     }
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn download_empty_object() {
+    let client = common::get_client();
+    let bucket = common::create_test_bucket(&client).await;
+
+    let dir = TempDir::new("").unwrap();
+    let file = dir.path().join("empty");
+    let key = "empty";
+
+    common::put_object(&client, &bucket, key, vec![]).await;
+
+    let request = GetObjectRequest {
+        bucket: bucket.clone(),
+        key: key.to_owned(),
+        ..Default::default()
+    };
+    let mut target = Vec::new();
+    let resp = client.download(request.clone(), &mut target).await;
+    let file_resp = client.download_to_file(request, &file).await;
+
+    common::delete_test_bucket(&client, &bucket, &[key]).await;
+
+    assert_eq!(resp.unwrap().content_length, Some(0));
+    assert!(target.is_empty());
+    assert_eq!(file_resp.unwrap().content_length, Some(0));
+    assert_eq!(std::fs::metadata(&file).unwrap().len(), 0);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn download_large_object() {
     let client = common::get_client();
@@ -204,7 +236,7 @@ async fn no_object_created_when_file_cannot_be_opened_for_upload() {
         )
         .await;
     common::delete_test_bucket(&client, &bucket, &[]).await;
-    match result {
+    match result.map_err(S3ExtError::into_inner) {
         Err(S3ExtError::IoError(ref e)) if e.kind() == io::ErrorKind::NotFound => (),
         r => panic!("unexpected result: {:?}", r),
     }
@@ -363,7 +395,7 @@ async fn test_multipart_upload_is_aborted() {
             .upload_multipart(&mut reader, put_request, 5 * 1024 * 1024)
             .await
             .unwrap_err();
-        match err {
+        match err.into_inner() {
             S3ExtError::IoError(e) => assert_eq!(
                 format!("{}", e.into_inner().unwrap()),
                 "explicit, unconditional error"
@@ -0,0 +1,110 @@
+#![cfg(feature = "mock")]
+
+use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+use s3_ext::{
+    backup::Backup,
+    file_attributes::{FileAttributes, MODE_METADATA, MTIME_METADATA},
+    mock::MockS3,
+    S3Ext,
+};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    path::Path,
+    time::{Duration, UNIX_EPOCH},
+};
+use tempdir::TempDir;
+
+const MTIME: i64 = 1_600_000_000;
+
+fn write_file(path: &Path) {
+    fs::write(path, "#!/bin/sh\n").unwrap();
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(UNIX_EPOCH + Duration::from_secs(MTIME as u64))
+        .unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(path, fs::Permissions::from_mode(0o750)).unwrap();
+    }
+}
+
+fn assert_restored(path: &Path) {
+    let attributes = FileAttributes::from(&fs::metadata(path).unwrap());
+    assert_eq!(attributes.mtime, Some(MTIME));
+    #[cfg(unix)]
+    assert_eq!(attributes.mode.map(|mode| mode & 0o7777), Some(0o750));
+}
+
+#[tokio::test]
+async fn upload_and_download_with_attributes() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let dir = TempDir::new("s3-ext").unwrap();
+    let source = dir.path().join("run.sh");
+    write_file(&source);
+
+    let request = PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "run.sh".to_owned(),
+        ..Default::default()
+    };
+    s3.upload_from_file_with_attributes(&source, request)
+        .await
+        .unwrap();
+    let object = s3.object("bucket", "run.sh").unwrap();
+    assert_eq!(object.metadata[MTIME_METADATA], MTIME.to_string());
+    #[cfg(unix)]
+    assert_eq!(object.metadata[MODE_METADATA], (0o100750).to_string());
+
+    let target = dir.path().join("restored.sh");
+    let request = GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "run.sh".to_owned(),
+        ..Default::default()
+    };
+    s3.download_to_file_with_attributes(request, &target)
+        .await
+        .unwrap();
+    assert_eq!(fs::read(&target).unwrap(), b"#!/bin/sh\n");
+    assert_restored(&target);
+}
+
+#[test]
+fn metadata_round_trip() {
+    let attributes = FileAttributes {
+        mtime: Some(-1),
+        mode: Some(0o100644),
+    };
+    let mut metadata = HashMap::new();
+    attributes.insert_into(&mut metadata);
+    assert_eq!(FileAttributes::from_metadata(&metadata), attributes);
+
+    metadata.insert(MODE_METADATA.to_owned(), "rwxr-xr-x".to_owned());
+    assert_eq!(FileAttributes::from_metadata(&metadata).mode, None);
+    assert_eq!(
+        FileAttributes::from_metadata(&HashMap::new()),
+        FileAttributes::default()
+    );
+}
+
+#[tokio::test]
+async fn backup_restores_attributes() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let backup = Backup::new(s3, "bucket", "backup/");
+    let source = TempDir::new("s3-ext").unwrap();
+    write_file(&source.path().join("run.sh"));
+
+    let report = backup.snapshot("one", source.path()).await.unwrap();
+    assert_eq!(report.manifest.files[0].attributes.mtime, Some(MTIME));
+    assert_eq!(backup.manifest("one").await.unwrap(), report.manifest);
+
+    let target = TempDir::new("s3-ext").unwrap();
+    backup.restore("one", target.path()).await.unwrap();
+    assert_restored(&target.path().join("run.sh"));
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "mock")]
+
+use rusoto_s3::Object;
+use s3_ext::{mock::MockS3, S3Ext};
+use std::time::Duration;
+
+fn keys(objects: Vec<Object>) -> Vec<String> {
+    objects.into_iter().filter_map(|o| o.key).collect()
+}
+
+async fn mock_with_objects() -> MockS3 {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    // put in order of modification time, timestamps have millisecond
+    // precision
+    for (key, size) in &[
+        ("logs/b", 30),
+        ("logs/a", 10),
+        ("other", 99),
+        ("logs/d", 20),
+        ("logs/c", 30),
+    ] {
+        s3.put_object("bucket", key, vec![0; *size]);
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    s3
+}
+
+#[tokio::test]
+async fn find_largest() {
+    let s3 = mock_with_objects().await;
+    let largest = s3.find_largest("bucket", "logs/", 3).await.unwrap();
+    assert_eq!(largest[0].size, Some(30));
+    assert_eq!(keys(largest), vec!["logs/b", "logs/c", "logs/d"]);
+    assert!(s3
+        .find_largest("bucket", "logs/", 0)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(s3.find_largest("bucket", "", 10).await.unwrap().len(), 5);
+}
+
+#[tokio::test]
+async fn find_oldest_and_newest() {
+    let s3 = mock_with_objects().await;
+    let oldest = s3.find_oldest("bucket", "logs/", 2).await.unwrap();
+    assert_eq!(keys(oldest), vec!["logs/b", "logs/a"]);
+    let newest = s3.find_newest("bucket", "logs/", 2).await.unwrap();
+    assert_eq!(keys(newest), vec!["logs/c", "logs/d"]);
+
+    let err = s3.find_newest("missing", "", 2).await.unwrap_err();
+    assert!(err.is_not_found());
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "mock")]
+
+use futures::stream::TryStreamExt;
+use s3_ext::{mock::MockS3, S3Ext};
+use tokio_util::codec::{LengthDelimitedCodec, LinesCodec};
+
+#[tokio::test]
+async fn get_length_delimited_frames() {
+    let s3 = MockS3::new();
+    let mut body = Vec::new();
+    for frame in &[&b"first"[..], b"", b"third frame"] {
+        body.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+        body.extend_from_slice(frame);
+    }
+    s3.put_object("bucket", "frames", body);
+
+    let frames: Vec<_> = s3
+        .get_framed("bucket", "frames", LengthDelimitedCodec::new())
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(frames, vec![&b"first"[..], b"", b"third frame"]);
+}
+
+#[tokio::test]
+async fn get_newline_delimited_frames() {
+    let s3 = MockS3::new();
+    s3.put_object("bucket", "lines", "a\nb\n");
+
+    let lines: Vec<_> = s3
+        .get_framed("bucket", "lines", LinesCodec::new())
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(lines, vec!["a", "b"]);
+
+    let err = match s3.get_framed("bucket", "missing", LinesCodec::new()).await {
+        Ok(_) => panic!("get of missing object succeeded"),
+        Err(err) => err,
+    };
+    assert!(err.is_not_found());
+    assert_eq!(err.key(), Some("missing"));
+}
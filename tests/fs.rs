@@ -0,0 +1,123 @@
+#![cfg(feature = "mock")]
+
+use futures::TryStreamExt;
+use s3_ext::{
+    fs::{DirEntry, S3Fs},
+    mock::MockS3,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+fn fs() -> (MockS3, S3Fs<MockS3>) {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    (s3.clone(), S3Fs::new(s3, "bucket"))
+}
+
+async fn names(fs: &S3Fs<MockS3>, path: &str) -> Vec<(String, bool)> {
+    let entries: Vec<DirEntry> = fs.read_dir(path).try_collect().await.unwrap();
+    entries
+        .iter()
+        .map(|entry| (entry.file_name().to_owned(), entry.metadata().is_dir()))
+        .collect()
+}
+
+#[tokio::test]
+async fn write_and_read_files() {
+    let (s3, fs) = fs();
+    fs.write("/a.txt", "hello world").await.unwrap();
+    assert_eq!(s3.object("bucket", "a.txt").unwrap().body, b"hello world");
+    assert_eq!(fs.read("a.txt").await.unwrap(), b"hello world");
+    assert_eq!(fs.read_at("/a.txt", 6, 100).await.unwrap(), b"world");
+    assert_eq!(fs.read_at("/a.txt", 2, 3).await.unwrap(), b"llo");
+    assert!(fs.read_at("/a.txt", 2, 0).await.unwrap().is_empty());
+
+    let mut content = String::new();
+    let mut file = fs.open("/a.txt").await.unwrap();
+    file.read_to_string(&mut content).await.unwrap();
+    assert_eq!(content, "hello world");
+
+    let err = fs.read("/missing").await.unwrap_err();
+    assert!(err.is_not_found());
+}
+
+#[tokio::test]
+async fn create_uploads_on_finish() {
+    let (s3, fs) = fs();
+    let mut file = fs.create("/dir/data.bin");
+    file.write_all(&vec![7; 100_000]).await.unwrap();
+    file.write_all(b"end").await.unwrap();
+    file.shutdown().await.unwrap();
+    assert!(s3.object("bucket", "dir/data.bin").is_none());
+    file.finish().await.unwrap();
+
+    let body = s3.object("bucket", "dir/data.bin").unwrap().body;
+    assert_eq!(body.len(), 100_003);
+    assert!(body.ends_with(b"end"));
+}
+
+#[tokio::test]
+async fn dropped_writer_aborts_upload() {
+    let (s3, fs) = fs();
+    let mut file = fs.create("/partial");
+    file.write_all(b"some content").await.unwrap();
+    drop(file);
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    assert!(s3.object("bucket", "partial").is_none());
+}
+
+#[tokio::test]
+async fn read_dir_lists_files_and_directories() {
+    let (s3, fs) = fs();
+    for key in &["top", "dir/", "dir/a", "dir/sub/b", "dir/sub/c", "other/d"] {
+        s3.put_object("bucket", key, "x");
+    }
+    assert_eq!(
+        names(&fs, "/").await,
+        vec![
+            ("dir".to_owned(), true),
+            ("other".to_owned(), true),
+            ("top".to_owned(), false)
+        ]
+    );
+    assert_eq!(
+        names(&fs, "/dir").await,
+        vec![("a".to_owned(), false), ("sub".to_owned(), true)]
+    );
+    assert_eq!(names(&fs, "dir/sub/").await.len(), 2);
+    assert!(names(&fs, "/missing").await.is_empty());
+
+    let entries: Vec<DirEntry> = fs.read_dir("/dir").try_collect().await.unwrap();
+    assert_eq!(entries[0].path(), "dir/a");
+    assert_eq!(entries[0].metadata().len(), 1);
+    assert_eq!(entries[1].path(), "dir/sub/");
+}
+
+#[tokio::test]
+async fn metadata_of_files_and_directories() {
+    let (s3, fs) = fs();
+    s3.put_object("bucket", "dir/file", "12345");
+
+    let file = fs.metadata("/dir/file").await.unwrap();
+    assert!(file.is_file());
+    assert_eq!(file.len(), 5);
+    assert!(file.modified().is_some());
+
+    for path in &["", "/", "/dir", "dir/"] {
+        assert!(fs.metadata(path).await.unwrap().is_dir(), "{}", path);
+    }
+    assert!(fs.metadata("/di").await.unwrap_err().is_not_found());
+    assert!(fs.metadata("/dir/file/x").await.unwrap_err().is_not_found());
+}
+
+#[tokio::test]
+async fn rename_and_remove_files() {
+    let (s3, fs) = fs();
+    s3.put_object("bucket", "old", "content");
+    fs.rename("/old", "/new/name").await.unwrap();
+    assert_eq!(s3.keys("bucket"), vec!["new/name"]);
+    assert_eq!(fs.read("/new/name").await.unwrap(), b"content");
+
+    fs.remove_file("/new/name").await.unwrap();
+    assert!(s3.keys("bucket").is_empty());
+    fs.remove_file("/new/name").await.unwrap();
+}
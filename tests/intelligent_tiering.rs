@@ -0,0 +1,87 @@
+use rusoto_s3::{IntelligentTieringConfiguration, Tiering};
+use s3_ext::intelligent_tiering::{ArchiveTier, IntelligentTiering};
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn put_get_list_and_delete_intelligent_tiering() {
+    use s3_ext::{bucket_config::BucketConfigExt, mock::MockS3};
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    assert_eq!(
+        s3.get_intelligent_tiering("bucket", "archive")
+            .await
+            .unwrap(),
+        None
+    );
+    assert!(s3
+        .list_intelligent_tiering("bucket")
+        .await
+        .unwrap()
+        .is_empty());
+
+    // one configuration per kind of filter
+    let all = IntelligentTiering::new("all").archive(ArchiveTier::ArchiveAccess, 120);
+    let archive = IntelligentTiering::new("archive")
+        .prefix("logs/")
+        .tag("archive", "yes")
+        .archive(ArchiveTier::DeepArchiveAccess, 180)
+        .archive(ArchiveTier::ArchiveAccess, 90);
+    let prefix = IntelligentTiering::new("prefix").prefix("data/");
+    let tag = IntelligentTiering::new("tag").tag("a", "b").enabled(false);
+    for tiering in &[&all, &archive, &prefix, &tag] {
+        s3.put_intelligent_tiering("bucket", tiering).await.unwrap();
+    }
+    assert_eq!(
+        s3.get_intelligent_tiering("bucket", "archive")
+            .await
+            .unwrap(),
+        Some(archive.clone())
+    );
+    assert_eq!(
+        s3.list_intelligent_tiering("bucket").await.unwrap(),
+        [all.clone(), archive, prefix.clone(), tag.clone()]
+    );
+
+    s3.delete_intelligent_tiering("bucket", "archive")
+        .await
+        .unwrap();
+    assert_eq!(
+        s3.get_intelligent_tiering("bucket", "archive")
+            .await
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        s3.list_intelligent_tiering("bucket").await.unwrap(),
+        [all, prefix, tag]
+    );
+
+    assert!(s3
+        .list_intelligent_tiering("missing")
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
+
+#[test]
+fn skip_unknown_tiers() {
+    let configuration = IntelligentTieringConfiguration {
+        id: "future".to_owned(),
+        status: "Enabled".to_owned(),
+        filter: None,
+        tierings: vec![
+            Tiering {
+                access_tier: "COLD_ACCESS".to_owned(),
+                days: 30,
+            },
+            Tiering {
+                access_tier: "ARCHIVE_ACCESS".to_owned(),
+                days: 90,
+            },
+        ],
+    };
+    let tiering = IntelligentTiering::from_configuration(configuration);
+    assert_eq!(tiering.archive_days.len(), 1);
+    assert_eq!(tiering.archive_days[&ArchiveTier::ArchiveAccess], 90);
+}
@@ -0,0 +1,136 @@
+use rusoto_s3::GetObjectRequest;
+use s3_ext::{
+    client::{Credentials, S3ExtClientBuilder},
+    error::{S3ExtError, S3ExtResult},
+    intercept::{InterceptingS3Client, Interceptor, Request},
+    S3Ext,
+};
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+// Respond to every request with `hello`, sending the request line to the
+// returned receiver
+fn server() -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = [0; 4096];
+            let n = stream.read(&mut buf).unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let _ = tx.send(request.lines().next().unwrap_or_default().to_owned());
+            let body = "hello";
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    (endpoint, rx)
+}
+
+struct Prefix;
+
+impl Interceptor for Prefix {
+    fn before_request(&self, _operation: &'static str, request: &mut Request) -> S3ExtResult<()> {
+        if let Request::Get(get) = request {
+            get.key = format!("prefix/{}", get.key);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Audit(Mutex<Vec<String>>);
+
+impl Interceptor for Audit {
+    fn before_request(&self, operation: &'static str, request: &mut Request) -> S3ExtResult<()> {
+        if request.key().starts_with("secret/") {
+            return Err(S3ExtError::Other("access denied by policy"));
+        }
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("before {} {}", operation, request.key()));
+        Ok(())
+    }
+
+    fn after_response(
+        &self,
+        operation: &'static str,
+        bucket: &str,
+        key: &str,
+        error: Option<&S3ExtError>,
+    ) {
+        self.0.lock().unwrap().push(format!(
+            "after {} {}/{} {}",
+            operation,
+            bucket,
+            key,
+            error.is_some()
+        ));
+    }
+}
+
+fn request(key: &str) -> GetObjectRequest {
+    GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    }
+}
+
+fn client(endpoint: String) -> InterceptingS3Client {
+    let client = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", endpoint)
+        .credentials(Credentials::Anonymous)
+        .build()
+        .unwrap();
+    InterceptingS3Client::new(client)
+}
+
+#[tokio::test]
+async fn interceptors_modify_requests_in_order() {
+    let (endpoint, requests) = server();
+    let audit = Arc::new(Audit::default());
+    let client = client(endpoint)
+        .interceptor(Prefix)
+        .interceptor(audit.clone());
+
+    let mut target = Vec::new();
+    client.download(request("a"), &mut target).await.unwrap();
+    assert_eq!(target, b"hello");
+    assert!(requests
+        .recv()
+        .unwrap()
+        .starts_with("GET /bucket/prefix/a "));
+    assert_eq!(
+        *audit.0.lock().unwrap(),
+        vec![
+            "before download prefix/a",
+            "after download bucket/prefix/a false"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn interceptors_can_reject_requests() {
+    let (endpoint, requests) = server();
+    let audit = Arc::new(Audit::default());
+    let client = client(endpoint).interceptor(audit.clone());
+
+    let mut target = Vec::new();
+    match client.download(request("secret/a"), &mut target).await {
+        Err(S3ExtError::Other(reason)) => assert_eq!(reason, "access denied by policy"),
+        r => panic!("unexpected result: {:?}", r.map(|_| ())),
+    }
+    assert!(requests.try_recv().is_err());
+    assert!(audit.0.lock().unwrap().is_empty());
+}
@@ -354,3 +354,195 @@ async fn assert_key_and_body(
     assert_eq!(key, expected);
     assert_eq!(body, expected.as_bytes());
 }
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn resume_listing_from_token() {
+    use s3_ext::{compat::Compatibility, iter::ListToken, mock::MockS3};
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    for key in &["a", "b", "c", "d", "e"] {
+        s3.put_object("bucket", key, "");
+    }
+    let compat = Compatibility {
+        max_keys: 2,
+        ..Default::default()
+    };
+    let stream = || s3.stream_objects("bucket").with_compatibility(&compat);
+
+    let mut objects = stream();
+    assert_eq!(objects.token(), None);
+    for _ in 0..3 {
+        objects.next().await.unwrap().unwrap();
+    }
+    let mid_page = objects.token().unwrap();
+    assert_eq!(mid_page, ListToken::StartAfter("c".to_owned()));
+    objects.next().await.unwrap().unwrap();
+    let page_boundary = objects.token().unwrap();
+    assert!(matches!(page_boundary, ListToken::Continuation(_)));
+
+    let mut resumed = stream();
+    resumed.set_token(Some(mid_page));
+    let rest: Vec<_> = resumed.map(|o| o.unwrap().key.unwrap()).collect().await;
+    assert_eq!(rest, vec!["d", "e"]);
+
+    let mut resumed = stream().into_iter();
+    resumed.set_token(Some(page_boundary));
+    assert_eq!(resumed.count().await.unwrap(), 1);
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn list_immediate_children() {
+    use futures::TryStreamExt;
+    use s3_ext::{compat::Compatibility, mock::MockS3};
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    for key in &["a/1", "a/2", "b/1", "c", "d/1", "e"] {
+        s3.put_object("bucket", key, "");
+    }
+    // pages holding only common prefixes
+    let compat = Compatibility {
+        max_keys: 2,
+        ..Default::default()
+    };
+
+    let keys: Vec<_> = s3
+        .stream_objects("bucket")
+        .with_compatibility(&compat)
+        .with_delimiter("/")
+        .map_ok(|o| o.key.unwrap())
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(keys, vec!["c", "e"]);
+
+    let keys: Vec<_> = s3
+        .stream_objects("bucket")
+        .with_compatibility(&compat)
+        .with_delimiter("/")
+        .with_common_prefixes()
+        .map_ok(|o| o.key.unwrap())
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(keys, vec!["a/", "b/", "c", "d/", "e"]);
+
+    let objects: Vec<_> = s3
+        .stream_get_objects("bucket")
+        .with_compatibility(&compat)
+        .with_delimiter("/")
+        .try_collect()
+        .await
+        .unwrap();
+    let keys: Vec<_> = objects.into_iter().map(|(key, _)| key).collect();
+    assert_eq!(keys, vec!["c", "e"]);
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn list_key_range() {
+    use futures::TryStreamExt;
+    use s3_ext::{compat::Compatibility, mock::MockS3};
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    for day in 1..=9 {
+        s3.put_object("bucket", &format!("2024-01-0{}/data", day), "");
+    }
+    s3.put_object("bucket", "2024-01-03", "");
+    let compat = Compatibility {
+        max_keys: 2,
+        ..Default::default()
+    };
+
+    let keys: Vec<_> = s3
+        .stream_objects("bucket")
+        .with_compatibility(&compat)
+        .start_at("2024-01-03")
+        .end_before("2024-01-06")
+        .map_ok(|o| o.key.unwrap())
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(
+        keys,
+        vec![
+            "2024-01-03",
+            "2024-01-03/data",
+            "2024-01-04/data",
+            "2024-01-05/data"
+        ]
+    );
+
+    let count = s3
+        .stream_objects("bucket")
+        .start_at("2024-01-08/data")
+        .into_iter()
+        .count()
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn iter_as_stream() {
+    use futures::TryStreamExt;
+    use s3_ext::mock::MockS3;
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    for key in &["a", "b", "c"] {
+        s3.put_object("bucket", key, "");
+    }
+
+    let mut iter = s3.stream_objects("bucket").into_iter();
+    let first = iter.nth(0).await.unwrap().unwrap();
+    assert_eq!(first.key.as_deref(), Some("a"));
+    let rest: Vec<_> = iter
+        .as_stream()
+        .map_ok(|object| object.key.unwrap())
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(rest, vec!["b", "c"]);
+    // the iterator is left where it was
+    let second = iter.nth(0).await.unwrap().unwrap();
+    assert_eq!(second.key.as_deref(), Some("b"));
+
+    let mut iter = s3.stream_get_objects("bucket").into_iter();
+    let (key, _) = iter.nth(0).await.unwrap().unwrap();
+    assert_eq!(key, "a");
+    let keys: Vec<_> = iter
+        .as_stream()
+        .map_ok(|(key, _)| key)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(keys, vec!["b", "c"]);
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn sorted_by_mtime_compares_dates() {
+    use s3_ext::mock::MockS3;
+    use std::time::Duration;
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    for key in &["c", "a", "b"] {
+        s3.put_object("bucket", key, "");
+        // the listing reports modification times in milliseconds
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    let keys: Vec<_> = s3
+        .stream_objects_sorted_by_mtime("bucket")
+        .map(|object| object.unwrap().key.unwrap())
+        .collect()
+        .await;
+    assert_eq!(keys, vec!["b", "a", "c"]);
+}
@@ -0,0 +1,61 @@
+#![cfg(all(feature = "serde_json", feature = "mock"))]
+
+use futures::TryStreamExt;
+use s3_ext::{codec::Json, kv::KvStore, mock::MockS3};
+
+#[tokio::test]
+async fn get_put_delete() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let store: KvStore<_, Vec<u32>, Json> = KvStore::new(s3.clone(), "bucket", "state/");
+
+    assert_eq!(store.get("primes").await.unwrap(), None);
+    store.put("primes", &vec![2, 3, 5]).await.unwrap();
+    assert_eq!(store.get("primes").await.unwrap(), Some(vec![2, 3, 5]));
+    let record = s3.object("bucket", "state/primes").unwrap();
+    assert_eq!(record.body, b"[2,3,5]");
+    assert_eq!(record.content_type.as_deref(), Some("application/json"));
+
+    store.delete("primes").await.unwrap();
+    assert_eq!(store.get("primes").await.unwrap(), None);
+    store.delete("primes").await.unwrap();
+
+    s3.put_object("bucket", "state/invalid", "not json");
+    let err = store.get("invalid").await.unwrap_err();
+    assert_eq!(err.key(), Some("state/invalid"));
+}
+
+#[tokio::test]
+async fn list_and_scan() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    s3.put_object("bucket", "other", "[0]");
+    let store: KvStore<_, u32, Json> = KvStore::new(s3, "bucket", "counters/");
+    for (key, value) in &[("b/2", 2), ("a", 1), ("b/1", 3)] {
+        store.put(key, value).await.unwrap();
+    }
+
+    assert_eq!(store.list("").await.unwrap(), vec!["a", "b/1", "b/2"]);
+    assert_eq!(store.list("b/").await.unwrap(), vec!["b/1", "b/2"]);
+    let values: Vec<_> = store.scan("b/").try_collect().await.unwrap();
+    assert_eq!(values, vec![("b/1".to_owned(), 3), ("b/2".to_owned(), 2)]);
+}
+
+#[cfg(feature = "gzip")]
+#[tokio::test]
+async fn compressed_values() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let value = "compressible ".repeat(100);
+    let store: KvStore<_, String, Json> = KvStore::new(s3.clone(), "bucket", "kv/").gzip();
+    store.put("text", &value).await.unwrap();
+
+    let record = s3.object("bucket", "kv/text").unwrap();
+    assert_eq!(record.content_encoding.as_deref(), Some("gzip"));
+    assert!(record.body.len() < value.len());
+    assert_eq!(store.get("text").await.unwrap(), Some(value.clone()));
+
+    // uncompressed stores read compressed values
+    let plain: KvStore<_, String, Json> = KvStore::new(s3, "bucket", "kv/");
+    assert_eq!(plain.get("text").await.unwrap(), Some(value));
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "mock")]
+
+use s3_ext::{error::S3ExtError, lease::EXPIRES, mock::MockS3, S3Ext};
+use std::time::Duration;
+
+const TTL: Duration = Duration::from_secs(60);
+
+fn mock() -> MockS3 {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    s3
+}
+
+#[tokio::test]
+async fn lease_excludes_others_until_released() {
+    let s3 = mock();
+    let lease = s3.acquire_lease("bucket", "lock", TTL).await.unwrap();
+    assert!(!lease.is_expired());
+    let marker = s3.object("bucket", "lock").unwrap();
+    assert_eq!(marker.body, lease.token().as_bytes());
+    assert!(marker.metadata.contains_key(EXPIRES));
+
+    let err = s3.acquire_lease("bucket", "lock", TTL).await.unwrap_err();
+    assert_eq!(err.key(), Some("lock"));
+    match err.inner() {
+        S3ExtError::LeaseHeld { expires_at } => assert_eq!(*expires_at, lease.expires_at()),
+        e => panic!("unexpected error {}", e),
+    }
+
+    lease.release(&s3).await.unwrap();
+    assert!(s3.keys("bucket").is_empty());
+    let lease = s3.acquire_lease("bucket", "lock", TTL).await.unwrap();
+    lease.release(&s3).await.unwrap();
+}
+
+#[tokio::test]
+async fn renew_extends_lease() {
+    let s3 = mock();
+    let mut lease = s3
+        .acquire_lease("bucket", "lock", Duration::from_secs(1))
+        .await
+        .unwrap();
+    let expires_at = lease.expires_at();
+    lease.renew(&s3, TTL).await.unwrap();
+    assert!(lease.expires_at() > expires_at);
+    assert_eq!(
+        s3.object("bucket", "lock").unwrap().body,
+        lease.token().as_bytes()
+    );
+    match s3
+        .acquire_lease("bucket", "lock", TTL)
+        .await
+        .unwrap_err()
+        .inner()
+    {
+        S3ExtError::LeaseHeld { expires_at } => assert_eq!(*expires_at, lease.expires_at()),
+        e => panic!("unexpected error {}", e),
+    }
+    lease.renew(&s3, TTL).await.unwrap();
+    lease.release(&s3).await.unwrap();
+}
+
+#[tokio::test]
+async fn expired_lease_is_taken_over() {
+    let s3 = mock();
+    let mut expired = s3
+        .acquire_lease("bucket", "lock", Duration::ZERO)
+        .await
+        .unwrap();
+    assert!(expired.is_expired());
+    let lease = s3.acquire_lease("bucket", "lock", TTL).await.unwrap();
+
+    let err = expired.renew(&s3, TTL).await.unwrap_err();
+    assert!(matches!(err.inner(), S3ExtError::LeaseLost));
+    let err = expired.release(&s3).await.unwrap_err();
+    assert!(matches!(err.inner(), S3ExtError::LeaseLost));
+    assert_eq!(s3.keys("bucket"), vec!["lock"]);
+
+    lease.release(&s3).await.unwrap();
+    assert!(s3.keys("bucket").is_empty());
+}
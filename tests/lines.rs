@@ -0,0 +1,70 @@
+#![cfg(feature = "mock")]
+
+use futures::stream::TryStreamExt;
+use s3_ext::{error::S3ExtError, mock::MockS3, S3Ext};
+
+#[tokio::test]
+async fn stream_lines() {
+    let s3 = MockS3::new();
+    s3.put_object("bucket", "log", "first\nsecond\r\n\nlast");
+
+    let lines: Vec<_> = s3
+        .stream_lines("bucket", "log")
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(lines, vec!["first", "second", "", "last"]);
+
+    s3.put_object("bucket", "empty", "");
+    let lines: Vec<_> = s3
+        .stream_lines("bucket", "empty")
+        .try_collect()
+        .await
+        .unwrap();
+    assert!(lines.is_empty());
+}
+
+#[tokio::test]
+async fn stream_lines_of_missing_object() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+
+    let err = s3
+        .stream_lines("bucket", "missing")
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap_err();
+    let err = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<S3ExtError>())
+        .unwrap();
+    assert!(err.is_not_found());
+    assert_eq!(err.key(), Some("missing"));
+}
+
+#[cfg(feature = "gzip")]
+#[tokio::test]
+async fn stream_gzip_lines() {
+    use async_compression::tokio::write::GzipEncoder;
+    use tokio::io::AsyncWriteExt;
+
+    async fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzipEncoder::new(Vec::new());
+        encoder.write_all(data).await.unwrap();
+        encoder.shutdown().await.unwrap();
+        encoder.into_inner()
+    }
+
+    let s3 = MockS3::new();
+    let mut body = gzip(b"first\nsecond\n").await;
+    body.extend(gzip(b"third\n").await);
+    s3.put_object("bucket", "log.gz", body);
+
+    let lines: Vec<_> = s3
+        .stream_lines("bucket", "log.gz")
+        .gzip()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(lines, vec!["first", "second", "third"]);
+}
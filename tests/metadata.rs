@@ -0,0 +1,169 @@
+use rusoto_s3::{GetObjectOutput, HeadObjectOutput};
+use s3_ext::{
+    error::S3ExtError,
+    metadata::{Metadata, UserMetadata, MAX_METADATA_SIZE},
+};
+#[cfg(feature = "mock")]
+use s3_ext::{mock::MockS3, S3Ext};
+use std::collections::HashMap;
+
+fn invalid_reason(metadata: Metadata) -> &'static str {
+    match metadata.build() {
+        Err(S3ExtError::InvalidMetadata { reason, .. }) => reason,
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn build() {
+    let metadata = Metadata::new()
+        .entry("Author", "Jane Doe")
+        .entry("x-amz-meta-revision", "3")
+        .entry("author", "John Doe");
+    assert_eq!(metadata.len(), 2);
+    assert_eq!(metadata.get("AUTHOR"), Some("John Doe"));
+    assert_eq!(metadata.size(), "author".len() + 8 + "revision".len() + 1);
+
+    let map = metadata.build().unwrap();
+    let expected: HashMap<_, _> = vec![
+        ("author".to_owned(), "John Doe".to_owned()),
+        ("revision".to_owned(), "3".to_owned()),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(map, expected);
+    assert!(Metadata::new().build().unwrap().is_empty());
+}
+
+#[test]
+fn build_validates() {
+    assert_eq!(
+        invalid_reason(Metadata::new().entry("", "value")),
+        "names must not be empty"
+    );
+    assert_eq!(
+        invalid_reason(Metadata::new().entry("file name", "value")),
+        "names must be HTTP header tokens"
+    );
+    assert_eq!(
+        invalid_reason(Metadata::new().entry("name", "caf\u{e9}")),
+        "values must be printable ASCII"
+    );
+    assert_eq!(
+        invalid_reason(Metadata::new().entry("name", "first\r\nsecond")),
+        "values must be printable ASCII"
+    );
+
+    let value = "x".repeat(MAX_METADATA_SIZE - "name".len());
+    assert!(Metadata::new().entry("name", value.clone()).build().is_ok());
+    assert_eq!(
+        invalid_reason(Metadata::new().entry("name", value).entry("a", "")),
+        "metadata must not exceed 2 KB"
+    );
+}
+
+#[test]
+fn user_metadata_of_outputs() {
+    let metadata: HashMap<_, _> = vec![
+        ("Author".to_owned(), "Jane Doe".to_owned()),
+        ("x-amz-meta-revision".to_owned(), "3".to_owned()),
+    ]
+    .into_iter()
+    .collect();
+
+    let output = HeadObjectOutput {
+        metadata: Some(metadata.clone()),
+        ..Default::default()
+    };
+    let user_metadata = output.user_metadata();
+    assert_eq!(user_metadata.get("author"), Some("Jane Doe"));
+    assert_eq!(user_metadata.get("revision"), Some("3"));
+
+    let output = GetObjectOutput {
+        metadata: Some(metadata),
+        ..Default::default()
+    };
+    assert_eq!(output.user_metadata(), user_metadata);
+    assert!(GetObjectOutput::default().user_metadata().is_empty());
+}
+
+#[cfg(feature = "mock")]
+fn mock_with_reports() -> MockS3 {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    s3.put_object("bucket", "reports/a.pdf", "a");
+    s3.put_object("bucket", "reports/b.txt", "b");
+    s3.put_object("bucket", "other.pdf", "c");
+    s3
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn rewrite_metadata() {
+    let s3 = mock_with_reports();
+    let report = s3
+        .rewrite_metadata(
+            "bucket",
+            "reports/",
+            |key, metadata| {
+                if key.ends_with(".pdf") {
+                    metadata.content_type = Some("application/pdf".to_owned());
+                    metadata.user.insert("Reviewed", "yes");
+                }
+            },
+            false,
+        )
+        .await
+        .unwrap();
+    assert!(report.is_success());
+    assert_eq!(report.changed, vec!["reports/a.pdf".to_owned()]);
+    assert_eq!(report.unchanged, vec!["reports/b.txt".to_owned()]);
+
+    let object = s3.object("bucket", "reports/a.pdf").unwrap();
+    assert_eq!(object.body, b"a");
+    assert_eq!(object.content_type.as_deref(), Some("application/pdf"));
+    assert_eq!(object.metadata["reviewed"], "yes");
+    let metadata = s3.object_metadata("bucket", "reports/a.pdf").await.unwrap();
+    assert_eq!(metadata.user.get("reviewed"), Some("yes"));
+    assert!(s3
+        .object("bucket", "other.pdf")
+        .unwrap()
+        .metadata
+        .is_empty());
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn rewrite_metadata_dry_run() {
+    let s3 = mock_with_reports();
+    let before = s3.object("bucket", "reports/a.pdf").unwrap();
+    let report = s3
+        .rewrite_metadata(
+            "bucket",
+            "",
+            |_, metadata| metadata.cache_control = Some("no-cache".to_owned()),
+            true,
+        )
+        .await
+        .unwrap();
+    assert_eq!(report.changed.len(), 3);
+    assert_eq!(s3.object("bucket", "reports/a.pdf").unwrap(), before);
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn replace_metadata_of_modified_object() {
+    let s3 = mock_with_reports();
+    let mut metadata = s3.object_metadata("bucket", "reports/a.pdf").await.unwrap();
+    s3.put_object("bucket", "reports/a.pdf", "changed");
+    metadata.user.insert("reviewed", "yes");
+    assert!(s3
+        .replace_metadata("bucket", "reports/a.pdf", metadata)
+        .await
+        .is_err());
+    assert!(s3
+        .object("bucket", "reports/a.pdf")
+        .unwrap()
+        .metadata
+        .is_empty());
+}
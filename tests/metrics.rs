@@ -0,0 +1,135 @@
+use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+use s3_ext::{
+    client::{Credentials, S3ExtClientBuilder},
+    error::S3ExtError,
+    metrics::{MeteredS3Client, S3ExtMetrics},
+    S3Ext,
+};
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+#[derive(Default)]
+struct Recorder(Mutex<Vec<String>>);
+
+impl Recorder {
+    fn events(&self) -> Vec<String> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl S3ExtMetrics for Recorder {
+    fn request(&self, operation: &'static str) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("request {}", operation));
+    }
+
+    fn bytes_sent(&self, operation: &'static str, bytes: u64) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("sent {} {}", operation, bytes));
+    }
+
+    fn bytes_received(&self, operation: &'static str, bytes: u64) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("received {} {}", operation, bytes));
+    }
+
+    fn error(&self, operation: &'static str, _error: &S3ExtError) {
+        self.0.lock().unwrap().push(format!("error {}", operation));
+    }
+
+    fn latency(&self, operation: &'static str, _duration: Duration) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("latency {}", operation));
+    }
+}
+
+// Respond to every request with `status` and `body`
+fn server(status: &'static str, body: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = [0; 4096];
+            let _ = stream.read(&mut buf).unwrap();
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    endpoint
+}
+
+fn metered_client(endpoint: String) -> (MeteredS3Client, Arc<Recorder>) {
+    let client = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", endpoint)
+        .credentials(Credentials::Anonymous)
+        .build()
+        .unwrap();
+    let recorder = Arc::new(Recorder::default());
+    (MeteredS3Client::new(client, recorder.clone()), recorder)
+}
+
+#[tokio::test]
+async fn download_is_reported() {
+    let (client, recorder) = metered_client(server("200 OK", "hello"));
+    let mut target = Vec::new();
+    client
+        .download(
+            GetObjectRequest {
+                bucket: "bucket".to_owned(),
+                key: "key".to_owned(),
+                ..Default::default()
+            },
+            &mut target,
+        )
+        .await
+        .unwrap();
+    assert_eq!(target, b"hello");
+    assert_eq!(
+        recorder.events(),
+        vec![
+            "request download",
+            "latency download",
+            "received download 5"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn failed_upload_is_reported() {
+    let (client, recorder) = metered_client(server(
+        "403 Forbidden",
+        "<Error><Code>AccessDenied</Code></Error>",
+    ));
+    let result = client
+        .upload(
+            &mut &b"hello"[..],
+            PutObjectRequest {
+                bucket: "bucket".to_owned(),
+                key: "key".to_owned(),
+                ..Default::default()
+            },
+        )
+        .await;
+    assert!(result.is_err());
+    assert_eq!(
+        recorder.events(),
+        vec!["request upload", "latency upload", "error upload"]
+    );
+}
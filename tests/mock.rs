@@ -0,0 +1,203 @@
+#![cfg(feature = "mock")]
+
+use futures::stream::TryStreamExt;
+use rusoto_s3::{
+    CopyObjectRequest, DeleteObjectRequest, GetObjectRequest, ListObjectsV2Request,
+    PutObjectRequest, S3,
+};
+use s3_ext::{mock::MockS3, S3Ext};
+use std::collections::HashMap;
+
+fn get(key: &str) -> GetObjectRequest {
+    GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    }
+}
+
+fn put(key: &str) -> PutObjectRequest {
+    PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn upload_and_download() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+
+    let mut metadata = HashMap::new();
+    metadata.insert("origin".to_owned(), "test".to_owned());
+    let resp = s3
+        .upload(
+            &mut &b"hello world"[..],
+            PutObjectRequest {
+                content_type: Some("text/plain".to_owned()),
+                metadata: Some(metadata.clone()),
+                ..put("a/b")
+            },
+        )
+        .await
+        .unwrap();
+    let record = s3.object("bucket", "a/b").unwrap();
+    assert_eq!(record.body, b"hello world");
+    assert_eq!(resp.e_tag, Some(record.e_tag));
+
+    let mut body = Vec::new();
+    let resp = s3.download(get("a/b"), &mut body).await.unwrap();
+    assert_eq!(body, b"hello world");
+    assert_eq!(resp.content_length, Some(11));
+    assert_eq!(resp.content_type.as_deref(), Some("text/plain"));
+    assert_eq!(resp.metadata, Some(metadata));
+
+    let mut body = Vec::new();
+    let resp = s3
+        .download(
+            GetObjectRequest {
+                range: Some("bytes=6-".to_owned()),
+                ..get("a/b")
+            },
+            &mut body,
+        )
+        .await
+        .unwrap();
+    assert_eq!(body, b"world");
+    assert_eq!(resp.content_range.as_deref(), Some("bytes 6-10/11"));
+}
+
+#[tokio::test]
+async fn missing_objects_and_buckets() {
+    let s3 = MockS3::new();
+    let mut body = Vec::new();
+    let err = s3.download(get("a"), &mut body).await.unwrap_err();
+    assert!(err.is_not_found());
+
+    s3.create_bucket("bucket");
+    let err = s3.download(get("a"), &mut body).await.unwrap_err();
+    assert!(err.is_not_found());
+}
+
+#[tokio::test]
+async fn listing() {
+    let s3 = MockS3::new();
+    for key in &["a", "b/1", "b/2", "b/3", "c/1", "d"] {
+        s3.put_object("bucket", key, *key);
+    }
+
+    let keys: Vec<_> = s3
+        .stream_objects("bucket")
+        .map_ok(|o| o.key.unwrap())
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(keys, vec!["a", "b/1", "b/2", "b/3", "c/1", "d"]);
+    let keys: Vec<_> = s3
+        .stream_objects_with_prefix("bucket", "b/")
+        .map_ok(|o| o.key.unwrap())
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(keys, vec!["b/1", "b/2", "b/3"]);
+
+    // pagination with delimiter
+    let mut request = ListObjectsV2Request {
+        bucket: "bucket".to_owned(),
+        delimiter: Some("/".to_owned()),
+        max_keys: Some(2),
+        ..Default::default()
+    };
+    let mut pages = Vec::new();
+    loop {
+        let resp = s3.client().list_objects_v2(request.clone()).await.unwrap();
+        let mut page: Vec<_> = resp
+            .contents
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|o| o.key)
+            .collect();
+        page.extend(
+            resp.common_prefixes
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|p| p.prefix),
+        );
+        pages.push(page);
+        match resp.next_continuation_token {
+            Some(token) => request.continuation_token = Some(token),
+            None => break,
+        }
+    }
+    assert_eq!(pages, vec![vec!["a", "b/"], vec!["d", "c/"]]);
+}
+
+#[tokio::test]
+async fn requester_pays_streams() {
+    let s3 = MockS3::new();
+    for key in &["a", "b"] {
+        s3.put_object("bucket", key, *key);
+    }
+
+    let keys: Vec<_> = s3
+        .stream_objects("bucket")
+        .with_requester_pays()
+        .map_ok(|o| o.key.unwrap())
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(keys, vec!["a", "b"]);
+    let bodies: Vec<_> = s3
+        .stream_get_objects("bucket")
+        .with_requester_pays()
+        .map_ok(|(key, object)| (key, object.content_length))
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(
+        bodies,
+        vec![("a".to_owned(), Some(1)), ("b".to_owned(), Some(1))]
+    );
+}
+
+#[tokio::test]
+async fn multipart_upload() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let data: Vec<u8> = (0..11 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+
+    let resp = s3
+        .upload_multipart(&mut &data[..], put("large"), 5 * 1024 * 1024)
+        .await
+        .unwrap();
+    assert!(resp.e_tag.unwrap().ends_with("-3\""));
+    assert_eq!(s3.object("bucket", "large").unwrap().body, data);
+    assert_eq!(s3.pending_uploads(), 0);
+}
+
+#[tokio::test]
+async fn copy_and_delete() {
+    let s3 = MockS3::new();
+    s3.put_object("bucket", "a", "hello");
+
+    s3.client()
+        .copy_object(CopyObjectRequest {
+            bucket: "bucket".to_owned(),
+            key: "b".to_owned(),
+            copy_source: "bucket/a".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    s3.client()
+        .delete_object(DeleteObjectRequest {
+            bucket: "bucket".to_owned(),
+            key: "a".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(s3.keys("bucket"), vec!["b"]);
+    assert_eq!(s3.object("bucket", "b").unwrap().body, b"hello");
+}
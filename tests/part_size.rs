@@ -0,0 +1,84 @@
+#![cfg(feature = "mock")]
+
+use rusoto_s3::PutObjectRequest;
+use s3_ext::{error::S3ExtError, mock::MockS3, part_size::PartSize, S3Ext};
+use tempdir::TempDir;
+
+const MIB: usize = 1024 * 1024;
+
+fn put(key: &str) -> PutObjectRequest {
+    PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn adaptive_parts_grow() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let data: Vec<u8> = (0..40 * MIB).map(|i| (i % 251) as u8).collect();
+
+    let part_size = PartSize::Adaptive { max: 16 * MIB };
+    s3.upload_multipart_with(&mut &data[..], put("large"), part_size)
+        .await
+        .unwrap();
+    let object = s3.object("bucket", "large").unwrap();
+    assert_eq!(object.body, data);
+    assert_eq!(object.part_sizes[0], 5 * MIB);
+    // parts but the last never shrink
+    let full = &object.part_sizes[..object.part_sizes.len() - 1];
+    assert!(full.windows(2).all(|w| w[0] <= w[1]));
+    assert!(object.part_sizes.iter().all(|&size| size <= 16 * MIB));
+    assert!(object.part_sizes.len() < 8);
+
+    // fixed sizes are kept
+    s3.upload_multipart_with(&mut &data[..], put("fixed"), PartSize::Fixed(5 * MIB))
+        .await
+        .unwrap();
+    let object = s3.object("bucket", "fixed").unwrap();
+    assert_eq!(object.part_sizes, vec![5 * MIB; 8]);
+}
+
+#[tokio::test]
+async fn adaptive_max_is_validated() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let result = s3
+        .upload_multipart_with(
+            &mut &b"data"[..],
+            put("key"),
+            PartSize::Adaptive { max: MIB },
+        )
+        .await;
+    assert!(matches!(
+        result.map_err(S3ExtError::into_inner),
+        Err(S3ExtError::InvalidPartSize { part_size, .. }) if part_size == MIB
+    ));
+    assert_eq!(s3.pending_uploads(), 0);
+}
+
+#[tokio::test]
+async fn auto_part_size() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let dir = TempDir::new("s3-ext").unwrap();
+    let path = dir.path().join("file");
+    let data: Vec<u8> = (0..12 * MIB).map(|i| (i % 251) as u8).collect();
+    std::fs::write(&path, &data).unwrap();
+
+    // small objects use the smallest parts allowed
+    s3.upload_from_file_multipart_with(&path, put("file"), PartSize::Auto)
+        .await
+        .unwrap();
+    let object = s3.object("bucket", "file").unwrap();
+    assert_eq!(object.body, data);
+    assert_eq!(object.part_sizes, vec![5 * MIB, 5 * MIB, 2 * MIB]);
+
+    s3.upload_multipart_with(&mut &data[..], put("stream"), PartSize::Auto)
+        .await
+        .unwrap();
+    let object = s3.object("bucket", "stream").unwrap();
+    assert_eq!(object.part_sizes, vec![5 * MIB, 5 * MIB, 2 * MIB]);
+}
@@ -0,0 +1,89 @@
+use s3_ext::policy::{BucketPolicy, Effect, Principal, Statement, LOGGING_SERVICE, VERSION};
+use serde_json::json;
+
+#[test]
+fn build_policy() {
+    let policy = BucketPolicy::new()
+        .statement(Statement::deny_insecure_transport("bucket"))
+        .statement(Statement::allow_read(
+            "bucket",
+            Principal::aws("123456789012"),
+        ));
+    assert_eq!(
+        policy.to_json(),
+        json!({
+            "Version": VERSION,
+            "Statement": [
+                {
+                    "Sid": "DenyInsecureTransport",
+                    "Effect": "Deny",
+                    "Principal": "*",
+                    "Action": "s3:*",
+                    "Resource": ["arn:aws:s3:::bucket", "arn:aws:s3:::bucket/*"],
+                    "Condition": { "Bool": { "aws:SecureTransport": "false" } },
+                },
+                {
+                    "Sid": "AllowRead",
+                    "Effect": "Allow",
+                    "Principal": { "AWS": "123456789012" },
+                    "Action": "s3:GetObject",
+                    "Resource": "arn:aws:s3:::bucket/*",
+                },
+            ],
+        })
+    );
+    assert_eq!(policy.to_string().parse::<BucketPolicy>().unwrap(), policy);
+}
+
+#[test]
+fn parse_policy() {
+    let policy: BucketPolicy = r#"{
+        "Version": "2008-10-17",
+        "Statement": {
+            "Effect": "Allow",
+            "Principal": { "Service": ["logging.s3.amazonaws.com"] },
+            "NotAction": "s3:DeleteObject",
+            "Resource": "arn:aws:s3:::bucket/*"
+        }
+    }"#
+    .parse()
+    .unwrap();
+    assert_eq!(policy.version, "2008-10-17");
+    let statement = &policy.statements[0];
+    assert_eq!(statement.effect, Effect::Allow);
+    assert_eq!(
+        statement.principal,
+        Some(Principal::service("logging.s3.amazonaws.com"))
+    );
+    assert!(statement.actions.is_empty());
+    assert_eq!(statement.other["NotAction"], "s3:DeleteObject");
+    assert_eq!(
+        policy.to_json()["Statement"][0]["NotAction"],
+        "s3:DeleteObject"
+    );
+}
+
+#[test]
+fn log_delivery_statement() {
+    let statement = Statement::allow_log_delivery("audit-logs", "web/", "web");
+    assert_eq!(
+        BucketPolicy::new().statement(statement).to_json()["Statement"][0],
+        json!({
+            "Sid": "S3ServerAccessLogsPolicy",
+            "Effect": "Allow",
+            "Principal": { "Service": LOGGING_SERVICE },
+            "Action": "s3:PutObject",
+            "Resource": "arn:aws:s3:::audit-logs/web/*",
+            "Condition": { "ArnLike": { "aws:SourceArn": "arn:aws:s3:::web" } }
+        })
+    );
+}
+
+#[test]
+fn reject_invalid_policy() {
+    assert!("[]".parse::<BucketPolicy>().is_err());
+    assert!(r#"{"Statement": [{"Effect": "Maybe"}]}"#.parse::<BucketPolicy>().is_err());
+    assert!(r#"{"Statement": [{"Effect": "Allow", "Action": 1}]}"#
+        .parse::<BucketPolicy>()
+        .is_err());
+}
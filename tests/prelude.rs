@@ -0,0 +1,19 @@
+use s3_ext::prelude::*;
+
+#[tokio::test]
+async fn prelude_covers_requests() {
+    // validation happens before any request is sent
+    let client = S3Client::new(Region::Custom {
+        name: "eu-west-1".to_owned(),
+        endpoint: "http://127.0.0.1:1".to_owned(),
+    });
+    let request = PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "key".to_owned(),
+        ..Default::default()
+    };
+    let result: S3ExtResult<_> = client
+        .upload_multipart(&mut &[0_u8; 16][..], request, 1024)
+        .await;
+    assert!(matches!(result, Err(S3ExtError::InvalidPartSize { .. })));
+}
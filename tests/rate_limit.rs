@@ -0,0 +1,105 @@
+use rusoto_core::{signature::SignedRequest, Region};
+use rusoto_s3::GetObjectRequest;
+use s3_ext::{
+    client::{Credentials, S3ExtClientBuilder},
+    rate_limit::{RateLimiter, RequestClass},
+    S3Ext,
+};
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    thread,
+    time::{Duration, Instant},
+};
+
+// Respond to every request with `hello`
+fn server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = [0; 4096];
+            let _ = stream.read(&mut buf).unwrap();
+            let body = "hello";
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    endpoint
+}
+
+#[test]
+fn classify_requests() {
+    let class = |method: &str, path: &str, param: Option<&str>| {
+        let mut request = SignedRequest::new(method, "s3", &Region::EuWest1, path);
+        if let Some(param) = param {
+            request.add_param(param, "");
+        }
+        RequestClass::of(&request)
+    };
+    assert_eq!(
+        class("GET", "/bucket", Some("list-type")),
+        RequestClass::List
+    );
+    assert_eq!(class("GET", "/bucket/", None), RequestClass::List);
+    assert_eq!(
+        class("GET", "/bucket/key", Some("uploadId")),
+        RequestClass::List
+    );
+    assert_eq!(class("GET", "/bucket/key", None), RequestClass::Get);
+    assert_eq!(class("HEAD", "/bucket/key", None), RequestClass::Get);
+    assert_eq!(class("PUT", "/bucket/key", None), RequestClass::Put);
+    assert_eq!(
+        class("POST", "/bucket/key", Some("uploads")),
+        RequestClass::Put
+    );
+    assert_eq!(
+        class("POST", "/bucket", Some("delete")),
+        RequestClass::Delete
+    );
+    assert_eq!(class("DELETE", "/bucket/key", None), RequestClass::Delete);
+}
+
+#[tokio::test]
+async fn limit_per_class() {
+    let limiter = RateLimiter::new().limit(RequestClass::Get, 20.0);
+    let start = Instant::now();
+    // 20 requests burst, 10 more take half a second
+    for _ in 0..30 {
+        limiter.acquire(RequestClass::Get).await;
+    }
+    assert!(start.elapsed() >= Duration::from_millis(450));
+
+    let start = Instant::now();
+    for _ in 0..1000 {
+        limiter.acquire(RequestClass::List).await;
+    }
+    assert!(start.elapsed() < Duration::from_millis(100));
+}
+
+#[tokio::test]
+async fn client_requests_are_delayed() {
+    let client = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", server())
+        .credentials(Credentials::Anonymous)
+        .rate_limit(RateLimiter::new().limit(RequestClass::Get, 2.0))
+        .build()
+        .unwrap();
+    let request = || GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "key".to_owned(),
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+    let mut target = Vec::new();
+    for _ in 0..3 {
+        client.download(request(), &mut target).await.unwrap();
+    }
+    assert_eq!(target, b"hellohellohello");
+    assert!(start.elapsed() >= Duration::from_millis(450));
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "mock")]
+
+use s3_ext::{
+    bucket_config::BucketConfigExt,
+    mock::MockS3,
+    replication::{Replication, ReplicationRule},
+};
+
+#[tokio::test]
+async fn put_get_and_delete_replication() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    assert_eq!(s3.get_bucket_replication("bucket").await.unwrap(), None);
+
+    // one rule per kind of filter
+    let replication = Replication::new("arn:aws:iam::123456789012:role/replication")
+        .rule(
+            ReplicationRule::new("all", "dr-bucket")
+                .storage_class("STANDARD_IA")
+                .replicate_delete_markers(true),
+        )
+        .rule(
+            ReplicationRule::new("tagged", "arn:aws:s3:::other-bucket")
+                .priority(2)
+                .prefix("data/")
+                .tag("replicate", "yes"),
+        )
+        .rule(ReplicationRule::new("tag-only", "other-bucket").tag("a", "b"));
+    s3.put_bucket_replication("bucket", &replication)
+        .await
+        .unwrap();
+    let read = s3.get_bucket_replication("bucket").await.unwrap().unwrap();
+    assert_eq!(read.rules[0].destination, "arn:aws:s3:::dr-bucket");
+    assert_eq!(read, replication);
+
+    s3.delete_bucket_replication("bucket").await.unwrap();
+    assert_eq!(s3.get_bucket_replication("bucket").await.unwrap(), None);
+
+    assert!(s3
+        .get_bucket_replication("missing")
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
@@ -0,0 +1,45 @@
+use s3_ext::restore::RestoreStatus;
+
+#[test]
+fn parse_restore_header() {
+    assert_eq!(
+        RestoreStatus::from_header(r#"ongoing-request="true""#),
+        Some(RestoreStatus::InProgress)
+    );
+    let restored = RestoreStatus::from_header(
+        r#"ongoing-request="false", expiry-date="Fri, 21 Dec 2012 00:00:00 GMT""#,
+    )
+    .unwrap();
+    assert!(restored.is_restored());
+    assert_eq!(
+        restored,
+        RestoreStatus::Restored {
+            expiry_date: Some("Fri, 21 Dec 2012 00:00:00 GMT".to_owned())
+        }
+    );
+    assert_eq!(RestoreStatus::from_header(""), None);
+    assert_eq!(
+        RestoreStatus::from_header(r#"ongoing-request="maybe""#),
+        None
+    );
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn objects_not_being_restored() {
+    use s3_ext::{mock::MockS3, S3Ext};
+    use std::time::Duration;
+
+    let s3 = MockS3::new();
+    s3.put_object("bucket", "key", "data");
+    assert_eq!(s3.restore_status("bucket", "key").await.unwrap(), None);
+    assert!(s3
+        .wait_until_restored("bucket", "key", Duration::from_millis(1))
+        .await
+        .is_err());
+    assert!(s3
+        .restore_status("bucket", "missing")
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
@@ -0,0 +1,111 @@
+use rusoto_s3::{GetObjectRequest, S3Client};
+use s3_ext::{
+    client::{Credentials, S3ExtClientBuilder},
+    S3Ext,
+};
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+const BODY: &[u8] = b"hello world";
+
+// Serve `BODY`, cutting off responses without `Range` after five bytes,
+// and record the requests
+fn server() -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let seen = requests.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = [0; 4096];
+            let n = stream.read(&mut buf).unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+            let range = request
+                .lines()
+                .find_map(|line| line.strip_prefix("range: bytes="))
+                .and_then(|range| range.split_once('-'))
+                .map(|(first, last)| (first.parse().unwrap(), last.parse().unwrap()));
+            seen.lock().unwrap().push(request);
+            let response = match range {
+                Some((first, last)) => {
+                    let body = &BODY[first..=last];
+                    let mut response = format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\n\
+                         Content-Range: bytes {}-{}/{}\r\nETag: \"v1\"\r\n\
+                         Connection: close\r\n\r\n",
+                        body.len(),
+                        first,
+                        last,
+                        BODY.len()
+                    )
+                    .into_bytes();
+                    response.extend_from_slice(body);
+                    response
+                }
+                None => {
+                    let mut response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nETag: \"v1\"\r\n\
+                         Connection: close\r\n\r\n",
+                        BODY.len()
+                    )
+                    .into_bytes();
+                    response.extend_from_slice(&BODY[..5]);
+                    response
+                }
+            };
+            stream.write_all(&response).unwrap();
+        }
+    });
+    (endpoint, requests)
+}
+
+fn client(endpoint: String) -> S3Client {
+    S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", endpoint)
+        .credentials(Credentials::Anonymous)
+        .build()
+        .unwrap()
+}
+
+fn request() -> GetObjectRequest {
+    GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "key".to_owned(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn interrupted_download_is_resumed() {
+    let (endpoint, requests) = server();
+    let client = client(endpoint);
+    let mut target = Vec::new();
+    let resp = client.download(request(), &mut target).await.unwrap();
+    assert_eq!(target, BODY);
+    assert_eq!(resp.content_length, Some(11));
+    assert_eq!(resp.e_tag.as_deref(), Some("\"v1\""));
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].contains("range: bytes=5-10\r\n"));
+    assert!(requests[1].contains("if-match: \"v1\"\r\n"));
+}
+
+#[tokio::test]
+async fn resumption_can_be_disabled() {
+    let (endpoint, requests) = server();
+    let client = client(endpoint);
+    let mut target = Vec::new();
+    let err = client
+        .download_resumable(request(), &mut target, 0)
+        .await
+        .unwrap_err();
+    assert_eq!(err.key(), Some("key"));
+    assert_eq!(target, b"hello");
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
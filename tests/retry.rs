@@ -0,0 +1,99 @@
+use futures::stream::TryStreamExt;
+use s3_ext::{
+    client::{Credentials, S3ExtClientBuilder},
+    retry::{RetryPolicy, RetryingS3Client},
+    S3Ext,
+};
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+const LISTING: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+<Name>bucket</Name><KeyCount>1</KeyCount><MaxKeys>1000</MaxKeys><IsTruncated>false</IsTruncated>
+<Contents><Key>a</Key><Size>1</Size></Contents>
+</ListBucketResult>"#;
+
+// Respond with `503 SlowDown` to the first `failures` requests
+fn throttling_server(failures: usize) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = [0; 4096];
+            let _ = stream.read(&mut buf).unwrap();
+            let (status, body) = if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                ("503 Slow Down", "<Error><Code>SlowDown</Code></Error>")
+            } else {
+                ("200 OK", LISTING)
+            };
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    (endpoint, requests)
+}
+
+fn retrying_client(endpoint: String, max_retries: u32) -> RetryingS3Client {
+    let client = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", endpoint)
+        .credentials(Credentials::Anonymous)
+        .build()
+        .unwrap();
+    RetryingS3Client::new(
+        client,
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(50),
+        },
+    )
+}
+
+#[tokio::test]
+async fn listing_is_retried_on_throttling() {
+    let (endpoint, requests) = throttling_server(2);
+    let client = retrying_client(endpoint, 3);
+    let keys: Vec<_> = client
+        .stream_objects("bucket")
+        .map_ok(|o| o.key.unwrap())
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(keys, vec!["a"]);
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn retries_are_limited() {
+    let (endpoint, requests) = throttling_server(usize::MAX);
+    let client = retrying_client(endpoint, 2);
+    let result: Result<Vec<_>, _> = client.stream_objects("bucket").try_collect().await;
+    assert!(result.is_err());
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn backoff_is_capped() {
+    let policy = RetryPolicy {
+        max_retries: 100,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(1),
+    };
+    assert!(policy.backoff(1) <= Duration::from_millis(100));
+    assert!(policy.backoff(3) <= Duration::from_millis(400));
+    assert!(policy.backoff(100) <= Duration::from_secs(1));
+}
@@ -0,0 +1,147 @@
+use futures::stream::TryStreamExt;
+use hyper::client::HttpConnector;
+use s3_ext::{
+    client::{Credentials, S3ExtClientBuilder},
+    select::{csv_request, SelectClient, SelectEvent},
+};
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    thread,
+};
+
+const NO_SUCH_KEY: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+    <Error><Code>NoSuchKey</Code><Message>The specified key does not exist.</Message>\
+    <RequestId>4442587FB7D0A2F9</RequestId></Error>";
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+// Event-stream message with string headers
+fn message(event_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut headers = Vec::new();
+    for (name, value) in &[(":message-type", "event"), (":event-type", event_type)] {
+        headers.push(name.len() as u8);
+        headers.extend_from_slice(name.as_bytes());
+        headers.push(7);
+        headers.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        headers.extend_from_slice(value.as_bytes());
+    }
+    let total = 12 + headers.len() + payload.len() + 4;
+    let mut message = Vec::new();
+    message.extend_from_slice(&(total as u32).to_be_bytes());
+    message.extend_from_slice(&(headers.len() as u32).to_be_bytes());
+    let prelude_crc = crc32(&message);
+    message.extend_from_slice(&prelude_crc.to_be_bytes());
+    message.extend_from_slice(&headers);
+    message.extend_from_slice(payload);
+    let message_crc = crc32(&message);
+    message.extend_from_slice(&message_crc.to_be_bytes());
+    message
+}
+
+// Serve a select response for `bucket/people.csv` and 404 for anything else
+fn server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = [0; 8192];
+            let n = stream.read(&mut buf).unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let path = request.split(' ').nth(1).unwrap_or_default();
+            let (status, body) = if path.starts_with("/bucket/people.csv?select") {
+                let mut body = Vec::new();
+                // records are split across events
+                body.extend(message("Records", b"{\"name\":\"ada\"}\n{\"na"));
+                body.extend(message("Records", b"me\":\"grace\"}\n"));
+                body.extend(message(
+                    "Stats",
+                    b"<Stats><BytesScanned>40</BytesScanned>\
+                      <BytesProcessed>40</BytesProcessed>\
+                      <BytesReturned>31</BytesReturned></Stats>",
+                ));
+                body.extend(message("End", b""));
+                ("200 OK", body)
+            } else {
+                ("404 Not Found", NO_SUCH_KEY.as_bytes().to_vec())
+            };
+            let head = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                body.len(),
+            );
+            stream.write_all(head.as_bytes()).unwrap();
+            stream.write_all(&body).unwrap();
+        }
+    });
+    endpoint
+}
+
+fn client() -> SelectClient {
+    S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", server())
+        .credentials(Credentials::Static {
+            access_key: "access".to_owned(),
+            secret_key: "secret".to_owned(),
+            token: None,
+        })
+        .connector(HttpConnector::new())
+        .build_select()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn select_records_and_stats() {
+    let client = client();
+    let request = csv_request("bucket", "people.csv", "SELECT s.name FROM S3Object s");
+    let events: Vec<_> = client
+        .select(request)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(events.len(), 4);
+    assert_eq!(
+        events[0],
+        SelectEvent::Record("{\"name\":\"ada\"}".to_owned())
+    );
+    assert_eq!(
+        events[1],
+        SelectEvent::Record("{\"name\":\"grace\"}".to_owned())
+    );
+    match &events[2] {
+        SelectEvent::Stats(stats) => {
+            assert_eq!(stats.bytes_scanned, Some(40));
+            assert_eq!(stats.bytes_returned, Some(31));
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+    assert_eq!(events[3], SelectEvent::End);
+}
+
+#[tokio::test]
+async fn select_missing_object() {
+    let request = csv_request("bucket", "missing.csv", "SELECT * FROM S3Object");
+    let err = match client().select(request).await {
+        Ok(_) => panic!("select of missing object succeeded"),
+        Err(err) => err,
+    };
+    assert!(err.is_not_found());
+    assert_eq!(err.key(), Some("missing.csv"));
+}
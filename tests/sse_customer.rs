@@ -0,0 +1,56 @@
+use rusoto_s3::{
+    CreateMultipartUploadRequest, GetObjectRequest, HeadObjectRequest, PutObjectRequest,
+    UploadPartRequest,
+};
+use s3_ext::{error::S3ExtError, sse_customer::SseCustomerKey};
+
+const KEY: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+const KEY_MD5: &str = "cLyPS3KoaSFGi/joRB3OUQ==";
+
+#[test]
+fn apply_to_requests() {
+    let key = SseCustomerKey::new([0; 32]);
+    assert_eq!(key.encoded(), KEY);
+    assert_eq!(key.encoded_md5(), KEY_MD5);
+
+    let mut put = PutObjectRequest::default();
+    key.apply(&mut put);
+    assert_eq!(put.sse_customer_algorithm.as_deref(), Some("AES256"));
+    assert_eq!(put.sse_customer_key.as_deref(), Some(KEY));
+    assert_eq!(put.sse_customer_key_md5.as_deref(), Some(KEY_MD5));
+
+    let mut create = CreateMultipartUploadRequest::default();
+    key.apply(&mut create);
+    assert_eq!(create.sse_customer_key.as_deref(), Some(KEY));
+
+    let mut part = UploadPartRequest::default();
+    key.apply(&mut part);
+    assert_eq!(part.sse_customer_key_md5.as_deref(), Some(KEY_MD5));
+
+    let mut get = GetObjectRequest::default();
+    key.apply(&mut get);
+    assert_eq!(get.sse_customer_algorithm.as_deref(), Some("AES256"));
+
+    let mut head = HeadObjectRequest::default();
+    key.apply(&mut head);
+    assert_eq!(head.sse_customer_key.as_deref(), Some(KEY));
+}
+
+#[test]
+fn from_slice() {
+    let key = SseCustomerKey::from_slice(&[7; 32]).unwrap();
+    assert_eq!(key.as_bytes(), &[7; 32]);
+
+    let err = SseCustomerKey::from_slice(&[7; 16]).unwrap_err();
+    assert!(matches!(err, S3ExtError::Other(_)));
+}
+
+#[test]
+fn debug_omits_key() {
+    let key = SseCustomerKey::new([0; 32]);
+    let debug = format!("{:?}", key);
+    assert!(debug.contains(KEY_MD5));
+    assert!(!debug.contains(KEY));
+
+    assert_ne!(SseCustomerKey::random(), SseCustomerKey::random());
+}
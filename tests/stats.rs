@@ -0,0 +1,91 @@
+use rusoto_s3::GetObjectRequest;
+use s3_ext::{
+    client::{Credentials, S3ExtClientBuilder},
+    retry::{RetryPolicy, RetryingS3Client},
+    S3Ext,
+};
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Duration,
+};
+
+// Respond with `503 SlowDown` to the first `failures` requests and with
+// `hello` afterwards
+fn server(failures: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        let requests = AtomicUsize::new(0);
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = [0; 4096];
+            let _ = stream.read(&mut buf).unwrap();
+            let (status, body) = if requests.fetch_add(1, Ordering::SeqCst) < failures {
+                ("503 Slow Down", "<Error><Code>SlowDown</Code></Error>")
+            } else {
+                ("200 OK", "hello")
+            };
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    endpoint
+}
+
+fn request() -> GetObjectRequest {
+    GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "key".to_owned(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn download_stats() {
+    let client = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", server(0))
+        .credentials(Credentials::Anonymous)
+        .build()
+        .unwrap();
+    let mut target = Vec::new();
+    let (_, stats) = client
+        .download_with_stats(request(), &mut target)
+        .await
+        .unwrap();
+    assert_eq!(target, b"hello");
+    assert_eq!(stats.bytes, 5);
+    assert_eq!(stats.parts, 1);
+    assert_eq!(stats.retries, 0);
+}
+
+#[tokio::test]
+async fn download_stats_count_retries() {
+    let client = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", server(2))
+        .credentials(Credentials::Anonymous)
+        .build()
+        .unwrap();
+    let client = RetryingS3Client::new(
+        client,
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(50),
+        },
+    );
+    let mut target = Vec::new();
+    let (_, stats) = client
+        .download_with_stats(request(), &mut target)
+        .await
+        .unwrap();
+    assert_eq!(target, b"hello");
+    assert_eq!(stats.bytes, 5);
+    assert_eq!(stats.retries, 2);
+    assert!(stats.throughput() > 0.0);
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "mock")]
+
+use s3_ext::{mock::MockS3, S3Ext};
+
+#[tokio::test]
+async fn change_storage_class() {
+    let s3 = MockS3::new();
+    for key in &["logs/a", "logs/b c", "logs/d", "other"] {
+        s3.put_object("bucket", key, "data");
+    }
+    s3.set_storage_class("bucket", "logs/d", "GLACIER_IR")
+        .await
+        .unwrap();
+    s3.put_tags(
+        "bucket",
+        "logs/a",
+        &[("k".to_owned(), "v".to_owned())].into(),
+    )
+    .await
+    .unwrap();
+
+    let plan = s3
+        .plan_storage_class("bucket", "logs/", "GLACIER_IR")
+        .await
+        .unwrap();
+    assert_eq!(plan.keys, vec!["logs/a", "logs/b c"]);
+    assert_eq!(plan.bytes, 8);
+    assert_eq!(plan.skipped, vec!["logs/d"]);
+    assert_eq!(
+        s3.object("bucket", "logs/a").unwrap().storage_class,
+        "STANDARD"
+    );
+
+    let report = s3
+        .change_storage_class("bucket", "logs/", "GLACIER_IR")
+        .await
+        .unwrap();
+    assert!(report.is_success());
+    assert_eq!(report.changed, vec!["logs/a", "logs/b c"]);
+    assert_eq!(report.skipped, vec!["logs/d"]);
+
+    let record = s3.object("bucket", "logs/a").unwrap();
+    assert_eq!(record.storage_class, "GLACIER_IR");
+    assert_eq!(record.body, b"data");
+    assert_eq!(record.tags.get("k").map(String::as_str), Some("v"));
+    assert_eq!(
+        s3.object("bucket", "logs/b c").unwrap().storage_class,
+        "GLACIER_IR"
+    );
+    assert_eq!(
+        s3.object("bucket", "other").unwrap().storage_class,
+        "STANDARD"
+    );
+}
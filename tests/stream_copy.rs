@@ -0,0 +1,45 @@
+#![cfg(feature = "mock")]
+
+use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+use s3_ext::{mock::MockS3, stream_copy::copy_via_stream};
+
+fn requests(src_key: &str) -> (GetObjectRequest, PutObjectRequest) {
+    let src = GetObjectRequest {
+        bucket: "source".to_owned(),
+        key: src_key.to_owned(),
+        ..Default::default()
+    };
+    let dst = PutObjectRequest {
+        bucket: "target".to_owned(),
+        key: "copy".to_owned(),
+        content_type: Some("text/csv".to_owned()),
+        ..Default::default()
+    };
+    (src, dst)
+}
+
+#[tokio::test]
+async fn copy_between_clients() {
+    let (source, target) = (MockS3::new(), MockS3::new());
+    target.create_bucket("target");
+    let data: Vec<u8> = (0..12 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+    source.put_object("source", "data", data.clone());
+
+    let (src, dst) = requests("data");
+    copy_via_stream(&source, src, &target, dst).await.unwrap();
+    let object = target.object("target", "copy").unwrap();
+    assert_eq!(object.body, data);
+    assert_eq!(object.content_type.as_deref(), Some("text/csv"));
+    // parts are filled although the pipe returns partial reads
+    assert_eq!(
+        object.part_sizes,
+        vec![5 * 1024 * 1024, 5 * 1024 * 1024, 2 * 1024 * 1024]
+    );
+
+    let (src, dst) = requests("missing");
+    let err = copy_via_stream(&source, src, &target, dst)
+        .await
+        .unwrap_err();
+    assert!(err.is_not_found());
+    assert_eq!(target.pending_uploads(), 0);
+}
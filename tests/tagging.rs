@@ -0,0 +1,219 @@
+#![cfg(feature = "mock")]
+
+use rusoto_s3::PutObjectRequest;
+use s3_ext::{
+    bucket_config::BucketConfigExt,
+    error::S3ExtError,
+    mock::MockS3,
+    tagging::{tag_set, tags_from_set, TagMode, Tags, TagsBuilder},
+    S3Ext,
+};
+
+fn tags(pairs: &[(&str, &str)]) -> Tags {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[tokio::test]
+async fn put_get_and_delete_tags() {
+    let s3 = MockS3::new();
+    s3.put_object("bucket", "key", "hello");
+
+    assert!(s3.get_tags("bucket", "key").await.unwrap().is_empty());
+
+    let expected = tags(&[("project", "s3-ext"), ("owner", "a & b <c>")]);
+    s3.put_tags("bucket", "key", &expected).await.unwrap();
+    assert_eq!(s3.get_tags("bucket", "key").await.unwrap(), expected);
+    assert_eq!(s3.object("bucket", "key").unwrap().tags, expected);
+
+    let replaced = tags(&[("stage", "prod")]);
+    s3.put_tags("bucket", "key", &replaced).await.unwrap();
+    assert_eq!(s3.get_tags("bucket", "key").await.unwrap(), replaced);
+
+    s3.delete_tags("bucket", "key").await.unwrap();
+    assert!(s3.get_tags("bucket", "key").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn tags_of_missing_object() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+
+    let err = s3.get_tags("bucket", "missing").await.unwrap_err();
+    assert!(err.is_not_found());
+    assert_eq!(err.key(), Some("missing"));
+    let err = s3
+        .put_tags("bucket", "missing", &tags(&[("a", "b")]))
+        .await
+        .unwrap_err();
+    assert_eq!(err.key(), Some("missing"));
+}
+
+#[tokio::test]
+async fn tag_all_objects_below_prefix() {
+    let s3 = MockS3::new();
+    s3.put_object("bucket", "data/a", "a");
+    s3.put_object("bucket", "data/b", "b");
+    s3.put_object("bucket", "other", "c");
+    s3.put_tags(
+        "bucket",
+        "data/a",
+        &tags(&[("owner", "me"), ("stage", "dev")]),
+    )
+    .await
+    .unwrap();
+
+    let report = s3
+        .tag_prefix(
+            "bucket",
+            "data/",
+            &tags(&[("stage", "prod")]),
+            TagMode::Merge,
+        )
+        .await
+        .unwrap();
+    assert!(report.is_success());
+    assert_eq!(report.tagged, vec!["data/a", "data/b"]);
+    assert_eq!(
+        s3.get_tags("bucket", "data/a").await.unwrap(),
+        tags(&[("owner", "me"), ("stage", "prod")])
+    );
+    assert_eq!(
+        s3.get_tags("bucket", "data/b").await.unwrap(),
+        tags(&[("stage", "prod")])
+    );
+    assert!(s3.get_tags("bucket", "other").await.unwrap().is_empty());
+
+    s3.tag_prefix("bucket", "data/", &tags(&[("cost", "1")]), TagMode::Replace)
+        .await
+        .unwrap();
+    assert_eq!(
+        s3.get_tags("bucket", "data/a").await.unwrap(),
+        tags(&[("cost", "1")])
+    );
+}
+
+#[tokio::test]
+async fn tag_prefix_of_missing_bucket() {
+    let s3 = MockS3::new();
+    let err = s3
+        .tag_prefix("missing", "", &tags(&[("a", "b")]), TagMode::Replace)
+        .await
+        .unwrap_err();
+    assert!(err.is_not_found());
+}
+
+#[test]
+fn convert_tag_sets() {
+    let expected = tags(&[("a", "1"), ("b", "2")]);
+    let set = tag_set(&expected);
+    assert_eq!(set.len(), 2);
+    assert_eq!(set[0].key, "a");
+    assert_eq!(tags_from_set(set), expected);
+}
+
+#[tokio::test]
+async fn put_get_and_delete_bucket_tags() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+
+    assert!(s3.get_bucket_tags("bucket").await.unwrap().is_empty());
+
+    let expected = tags(&[("cost-center", "42"), ("team", "storage")]);
+    s3.put_bucket_tags("bucket", &expected).await.unwrap();
+    assert_eq!(s3.get_bucket_tags("bucket").await.unwrap(), expected);
+
+    s3.delete_bucket_tags("bucket").await.unwrap();
+    assert!(s3.get_bucket_tags("bucket").await.unwrap().is_empty());
+
+    assert!(s3
+        .get_bucket_tags("missing")
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
+
+#[test]
+fn build_and_encode_tags() {
+    let builder = TagsBuilder::new()
+        .tag("project", "travel & leisure")
+        .tag("path", "a/b=c")
+        .tag("empty", "");
+    assert_eq!(
+        builder.clone().build().unwrap(),
+        tags(&[
+            ("project", "travel & leisure"),
+            ("path", "a/b=c"),
+            ("empty", "")
+        ])
+    );
+    assert_eq!(
+        builder.encode().unwrap(),
+        "empty=&path=a%2Fb%3Dc&project=travel%20%26%20leisure"
+    );
+
+    let invalid = |builder: TagsBuilder| match builder.build().unwrap_err() {
+        S3ExtError::InvalidTag { key, .. } => key,
+        e => panic!("unexpected error {}", e),
+    };
+    assert_eq!(invalid(TagsBuilder::new().tag("", "v")), "");
+    assert_eq!(
+        invalid(TagsBuilder::new().tag("aws:owner", "v")),
+        "aws:owner"
+    );
+    assert_eq!(
+        invalid(TagsBuilder::new().tag("k".repeat(129), "v")),
+        "k".repeat(129)
+    );
+    assert_eq!(invalid(TagsBuilder::new().tag("k", "é".repeat(257))), "k");
+    assert!(TagsBuilder::new()
+        .tag("k".repeat(128), "é".repeat(256))
+        .build()
+        .is_ok());
+    let too_many = (0..11).fold(TagsBuilder::new(), |builder, i| {
+        builder.tag(i.to_string(), "v")
+    });
+    assert_eq!(invalid(too_many), "");
+}
+
+#[tokio::test]
+async fn upload_with_tags() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let expected = tags(&[("owner", "a & b <c>"), ("stage", "dev+test")]);
+    let request = PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "key".to_owned(),
+        tagging: Some(TagsBuilder::from(expected.clone()).encode().unwrap()),
+        ..Default::default()
+    };
+    s3.upload(&mut &b"content"[..], request).await.unwrap();
+    assert_eq!(s3.get_tags("bucket", "key").await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn invalid_tags_are_rejected() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    s3.put_object("bucket", "key", "content");
+
+    let err = s3
+        .put_tags("bucket", "key", &tags(&[("aws:cost", "1")]))
+        .await
+        .unwrap_err();
+    assert_eq!(err.key(), Some("key"));
+    assert!(matches!(err.inner(), S3ExtError::InvalidTag { .. }));
+    assert!(s3
+        .tag_prefix("bucket", "", &tags(&[("", "1")]), TagMode::Merge)
+        .await
+        .is_err());
+    assert!(s3.get_tags("bucket", "key").await.unwrap().is_empty());
+
+    let many: Tags = (0..50).map(|i| (i.to_string(), "v".to_owned())).collect();
+    s3.put_bucket_tags("bucket", &many).await.unwrap();
+    let mut too_many = many;
+    too_many.insert("50".to_owned(), "v".to_owned());
+    assert!(s3.put_bucket_tags("bucket", &too_many).await.is_err());
+}
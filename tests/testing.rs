@@ -0,0 +1,43 @@
+#![cfg(feature = "testing")]
+
+use rusoto_s3::{HeadBucketRequest, PutObjectRequest, S3};
+use s3_ext::testing::{unique_bucket_name, Minio};
+
+#[test]
+fn bucket_names_are_valid_and_unique() {
+    let a = unique_bucket_name();
+    let b = unique_bucket_name();
+    assert_ne!(a, b);
+    assert!(a.len() >= 3 && a.len() <= 63);
+    assert!(a
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'));
+}
+
+#[tokio::test]
+async fn test_bucket_is_deleted_on_drop() {
+    let minio = Minio::from_env().unwrap();
+    let client = minio.client().unwrap();
+
+    let bucket = minio.bucket().await.unwrap();
+    let name = bucket.name().to_owned();
+    bucket
+        .client()
+        .put_object(PutObjectRequest {
+            bucket: name.clone(),
+            key: "a".to_owned(),
+            body: Some(b"hello".to_vec().into()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    drop(bucket);
+
+    let result = client
+        .head_bucket(HeadBucketRequest {
+            bucket: name,
+            ..Default::default()
+        })
+        .await;
+    assert!(result.is_err());
+}
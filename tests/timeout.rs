@@ -0,0 +1,36 @@
+use rusoto_s3::GetObjectRequest;
+use s3_ext::{
+    client::{Credentials, S3ExtClientBuilder},
+    error::S3ExtError,
+    S3Ext,
+};
+use std::{net::TcpListener, time::Duration};
+
+#[tokio::test]
+async fn stalled_download_times_out() {
+    // accepts connections but never responds
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let client = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", endpoint)
+        .credentials(Credentials::Anonymous)
+        .build()
+        .unwrap()
+        .with_timeout(Duration::from_millis(200));
+
+    let mut target = Vec::new();
+    let result = client
+        .download(
+            GetObjectRequest {
+                bucket: "bucket".to_owned(),
+                key: "key".to_owned(),
+                ..Default::default()
+            },
+            &mut target,
+        )
+        .await;
+    match result {
+        Err(S3ExtError::Timeout(after)) => assert_eq!(after, Duration::from_millis(200)),
+        r => panic!("unexpected result: {:?}", r.map(|_| ())),
+    }
+}
@@ -0,0 +1,50 @@
+use chrono::{TimeZone, Utc};
+use rusoto_s3::{HeadObjectOutput, Object};
+use s3_ext::{
+    restore::RestoreStatus,
+    timestamps::{parse_timestamp, Timestamps},
+};
+
+#[test]
+fn parse_formats() {
+    let date = Utc.with_ymd_and_hms(2012, 12, 21, 10, 30, 0).unwrap();
+    assert_eq!(parse_timestamp("Fri, 21 Dec 2012 10:30:00 GMT"), Some(date));
+    assert_eq!(parse_timestamp("2012-12-21T10:30:00.000Z"), Some(date));
+    assert_eq!(parse_timestamp("0"), None);
+}
+
+#[test]
+fn timestamps_of_outputs() {
+    let head = HeadObjectOutput {
+        last_modified: Some("Fri, 21 Dec 2012 10:30:00 GMT".to_owned()),
+        expires: Some("Sat, 22 Dec 2012 00:00:00 GMT".to_owned()),
+        restore: Some(
+            r#"ongoing-request="false", expiry-date="Mon, 24 Dec 2012 00:00:00 GMT""#.to_owned(),
+        ),
+        ..Default::default()
+    };
+    assert_eq!(
+        head.last_modified_at(),
+        Some(Utc.with_ymd_and_hms(2012, 12, 21, 10, 30, 0).unwrap())
+    );
+    assert_eq!(
+        head.expires_at(),
+        Some(Utc.with_ymd_and_hms(2012, 12, 22, 0, 0, 0).unwrap())
+    );
+    assert_eq!(
+        head.restore_expires_at(),
+        Some(Utc.with_ymd_and_hms(2012, 12, 24, 0, 0, 0).unwrap())
+    );
+    assert_eq!(HeadObjectOutput::default().last_modified_at(), None);
+    assert_eq!(RestoreStatus::InProgress.expires_at(), None);
+
+    let object = Object {
+        last_modified: Some("2012-12-21T10:30:00.000Z".to_owned()),
+        ..Default::default()
+    };
+    assert_eq!(
+        object.last_modified_at(),
+        Some(Utc.with_ymd_and_hms(2012, 12, 21, 10, 30, 0).unwrap())
+    );
+    assert_eq!(object.expires_at(), None);
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "mock")]
+
+use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+use s3_ext::{mock::MockS3, transfer::TransferManager};
+use std::time::Duration;
+use tempdir::TempDir;
+
+const MIB: usize = 1024 * 1024;
+
+fn put(key: &str) -> PutObjectRequest {
+    PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: key.to_owned(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn shutdown_waits_for_transfers() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    s3.put_object("bucket", "existing", "existing");
+    let dir = TempDir::new("s3-ext").unwrap();
+    let source = dir.path().join("source");
+    std::fs::write(&source, vec![1; 12 * MIB]).unwrap();
+
+    let transfers = TransferManager::new(s3.clone());
+    let upload = transfers
+        .upload_file(&source, put("uploaded"), 5 * MIB)
+        .unwrap();
+    let request = GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "existing".to_owned(),
+        ..Default::default()
+    };
+    let target = dir.path().join("target");
+    transfers.download_to_file(request, &target).unwrap();
+    let missing = GetObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "missing".to_owned(),
+        ..Default::default()
+    };
+    transfers
+        .download_to_file(missing, dir.path().join("missing"))
+        .unwrap();
+
+    let report = transfers.shutdown(Duration::from_secs(60)).await;
+    assert_eq!(report.completed.len(), 2);
+    assert!(report.aborted.is_empty());
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].key, "missing");
+    assert!(!dir.path().join("missing").exists());
+
+    upload.await.unwrap();
+    assert_eq!(
+        s3.object("bucket", "uploaded").unwrap().body.len(),
+        12 * MIB
+    );
+    assert_eq!(std::fs::read(&target).unwrap(), b"existing");
+
+    // new transfers are rejected
+    assert!(transfers
+        .upload_file(&source, put("late"), 5 * MIB)
+        .is_err());
+    assert_eq!(transfers.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn shutdown_aborts_transfers_after_grace() {
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let dir = TempDir::new("s3-ext").unwrap();
+    let source = dir.path().join("source");
+    std::fs::write(&source, vec![1; 12 * MIB]).unwrap();
+
+    let transfers = TransferManager::new(s3.clone());
+    let upload = transfers.upload_file(&source, put("key"), 5 * MIB).unwrap();
+    let report = transfers.shutdown(Duration::ZERO).await;
+    assert!(report.completed.is_empty());
+    assert_eq!(report.aborted.len(), 1);
+    assert_eq!(report.aborted[0].key, "key");
+
+    assert!(upload.await.is_err());
+    assert!(s3.object("bucket", "key").is_none());
+    assert_eq!(s3.pending_uploads(), 0);
+}
@@ -0,0 +1,386 @@
+use rusoto_core::Region;
+use rusoto_s3::{PutObjectRequest, S3Client};
+use s3_ext::{
+    client::{Credentials, S3ExtClientBuilder},
+    error::S3ExtError,
+    S3Ext,
+};
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::mpsc,
+    thread,
+};
+use tempdir::TempDir;
+
+// validation happens before any request is sent
+fn offline_client() -> S3Client {
+    S3Client::new(Region::Custom {
+        name: "eu-west-1".to_owned(),
+        endpoint: "http://127.0.0.1:1".to_owned(),
+    })
+}
+
+#[tokio::test]
+async fn part_size_too_small() {
+    let result = offline_client()
+        .upload_multipart(
+            &mut &[0_u8; 16][..],
+            PutObjectRequest {
+                bucket: "bucket".to_owned(),
+                key: "key".to_owned(),
+                ..Default::default()
+            },
+            1024,
+        )
+        .await;
+    match result.map_err(S3ExtError::into_inner) {
+        Err(S3ExtError::InvalidPartSize { part_size, .. }) => assert_eq!(part_size, 1024),
+        r => panic!("unexpected result: {:?}", r.map(|_| ())),
+    }
+}
+
+#[tokio::test]
+async fn part_size_too_large() {
+    let result = offline_client()
+        .upload_multipart(
+            &mut &[0_u8; 16][..],
+            PutObjectRequest {
+                bucket: "bucket".to_owned(),
+                key: "key".to_owned(),
+                ..Default::default()
+            },
+            6 * 1024 * 1024 * 1024,
+        )
+        .await;
+    assert!(matches!(
+        result.map_err(S3ExtError::into_inner),
+        Err(S3ExtError::InvalidPartSize { .. })
+    ));
+}
+
+// Accept multi-part uploads but fail uploading parts and aborting uploads
+fn failing_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 4096];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            let (status, body) = if request.starts_with(b"POST") {
+                (
+                    "200 OK",
+                    "<InitiateMultipartUploadResult><Bucket>bucket</Bucket><Key>key</Key>\
+                     <UploadId>leaked-upload</UploadId></InitiateMultipartUploadResult>",
+                )
+            } else {
+                (
+                    "500 Internal Server Error",
+                    "<Error><Code>InternalError</Code></Error>",
+                )
+            };
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    endpoint
+}
+
+#[tokio::test]
+async fn failed_abort_reports_upload_id() {
+    let client = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", failing_server())
+        .credentials(Credentials::Anonymous)
+        .build()
+        .unwrap();
+    let result = client
+        .upload_multipart(
+            &mut &[0_u8; 16][..],
+            PutObjectRequest {
+                bucket: "bucket".to_owned(),
+                key: "key".to_owned(),
+                ..Default::default()
+            },
+            5 * 1024 * 1024,
+        )
+        .await;
+    match result.map_err(S3ExtError::into_inner) {
+        Err(S3ExtError::UploadLeaked {
+            bucket,
+            key,
+            upload_id,
+            source,
+            ..
+        }) => {
+            assert_eq!(bucket, "bucket");
+            assert_eq!(key, "key");
+            assert_eq!(upload_id, "leaked-upload");
+            assert!(matches!(*source, S3ExtError::UploadPartError(_)));
+        }
+        r => panic!("unexpected result: {:?}", r.map(|_| ())),
+    }
+}
+
+// Accept uploads, sending the head of every request to the returned channel
+fn recording_server() -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 4096];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            let head = String::from_utf8_lossy(&request).to_ascii_lowercase();
+            let head = head.split("\r\n\r\n").next().unwrap().to_owned();
+            let _ = sender.send(head);
+            let response = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (endpoint, receiver)
+}
+
+#[tokio::test]
+async fn upload_sets_content_length() {
+    let (endpoint, heads) = recording_server();
+    let client = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", endpoint)
+        .credentials(Credentials::Anonymous)
+        .build()
+        .unwrap();
+    let dir = TempDir::new("s3-ext").unwrap();
+    let path = dir.path().join("file");
+    std::fs::write(&path, b"hello world").unwrap();
+    let request = PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "key".to_owned(),
+        ..Default::default()
+    };
+    client.upload_from_file(&path, request).await.unwrap();
+
+    let head = heads.recv().unwrap();
+    assert!(head.starts_with("put /bucket/key"));
+    assert!(head.contains("content-length: 11\r\n"));
+    assert!(!head.contains("transfer-encoding"));
+}
+
+#[cfg(feature = "mime_guess")]
+#[tokio::test]
+async fn upload_guesses_content_type() {
+    let (endpoint, heads) = recording_server();
+    let client = S3ExtClientBuilder::new()
+        .endpoint("eu-west-1", endpoint)
+        .credentials(Credentials::Anonymous)
+        .build()
+        .unwrap();
+    let dir = TempDir::new("s3-ext").unwrap();
+    let path = dir.path().join("index.html");
+    std::fs::write(&path, b"<html></html>").unwrap();
+    let request = PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "index.html".to_owned(),
+        ..Default::default()
+    };
+    client.upload_from_file(&path, request).await.unwrap();
+    assert!(heads
+        .recv()
+        .unwrap()
+        .contains("content-type: text/html\r\n"));
+
+    let request = PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "index.html".to_owned(),
+        content_type: Some("text/plain".to_owned()),
+        ..Default::default()
+    };
+    client.upload_from_file(&path, request).await.unwrap();
+    assert!(heads
+        .recv()
+        .unwrap()
+        .contains("content-type: text/plain\r\n"));
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn upload_files_concurrently() {
+    use s3_ext::mock::MockS3;
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let dir = TempDir::new("s3-ext").unwrap();
+    let mut files = Vec::new();
+    for i in 0..10 {
+        let path = dir.path().join(format!("{}.txt", i));
+        std::fs::write(&path, i.to_string()).unwrap();
+        let request = PutObjectRequest {
+            bucket: "bucket".to_owned(),
+            key: format!("files/{}.txt", i),
+            ..Default::default()
+        };
+        files.push((path, request));
+    }
+    let missing = dir.path().join("missing.txt");
+    let request = PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "files/missing.txt".to_owned(),
+        ..Default::default()
+    };
+    files.insert(5, (missing.clone(), request));
+
+    let expected: Vec<_> = files.iter().map(|(path, _)| path.clone()).collect();
+    let results = s3.upload_files(files, 4).await;
+    let paths: Vec<_> = results.iter().map(|(path, _)| path.clone()).collect();
+    assert_eq!(paths, expected);
+    for (path, result) in results {
+        if path == missing {
+            assert!(matches!(result, Err(S3ExtError::IoError(_))));
+        } else {
+            assert!(result.is_ok());
+        }
+    }
+    assert_eq!(s3.keys("bucket").len(), 10);
+    assert_eq!(s3.object("bucket", "files/7.txt").unwrap().body, b"7");
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn upload_replicated() {
+    use s3_ext::mock::MockS3;
+
+    let s3 = MockS3::new();
+    s3.create_bucket("primary");
+    s3.create_bucket("secondary");
+    let content: Vec<u8> = (0..12 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+    let targets = ["primary", "missing", "secondary"]
+        .iter()
+        .map(|bucket| PutObjectRequest {
+            bucket: bucket.to_string(),
+            key: "data.bin".to_owned(),
+            ..Default::default()
+        })
+        .collect();
+
+    let results = s3.upload_replicated(&mut &content[..], targets).await;
+    assert_eq!(results.len(), 3);
+    assert!(results[0].is_ok());
+    assert!(results[1].is_err());
+    assert!(results[2].is_ok());
+    assert_eq!(s3.object("primary", "data.bin").unwrap().body, content);
+    assert_eq!(s3.object("secondary", "data.bin").unwrap().body, content);
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn upload_replicated_source_fails() {
+    use s3_ext::mock::MockS3;
+    use std::{
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+
+    struct FailingReader;
+
+    impl AsyncRead for FailingReader {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            _: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, "source broke")))
+        }
+    }
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let mut source = (&[0_u8; 16][..]).chain(FailingReader);
+    let targets = vec![PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "key".to_owned(),
+        ..Default::default()
+    }];
+    let results = s3.upload_replicated(&mut source, targets).await;
+    let err = results.into_iter().next().unwrap().unwrap_err();
+    assert!(err.to_string().contains("source broke"));
+    assert!(s3.object("bucket", "key").is_none());
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn upload_multipart_resumable() {
+    use s3_ext::{mock::MockS3, part_size::PartSize};
+    use std::{
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+
+    struct FailingReader;
+
+    impl AsyncRead for FailingReader {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            _: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, "source broke")))
+        }
+    }
+
+    const MIB: usize = 1024 * 1024;
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    let target = PutObjectRequest {
+        bucket: "bucket".to_owned(),
+        key: "data.bin".to_owned(),
+        ..Default::default()
+    };
+    let part_size = PartSize::Fixed(5 * MIB);
+    let mut content: Vec<u8> = (0..12 * MIB + 100).map(|i| (i % 251) as u8).collect();
+
+    for attempt in 0..2 {
+        // fails after the first part is uploaded, leaving the upload
+        let mut source = (&content[..6 * MIB]).chain(FailingReader);
+        let err = s3
+            .upload_multipart_resumable(&mut source, target.clone(), part_size)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("source broke"));
+        assert_eq!(s3.pending_uploads(), 1);
+        assert!(s3.object("bucket", "data.bin").is_none());
+
+        // the second time, the first part no longer matches and is replaced
+        if attempt == 1 {
+            content[0] ^= 0xff;
+        }
+        s3.upload_multipart_resumable(&mut &content[..], target.clone(), part_size)
+            .await
+            .unwrap();
+        assert_eq!(s3.pending_uploads(), 0);
+        let object = s3.object("bucket", "data.bin").unwrap();
+        assert_eq!(object.body, content);
+        assert_eq!(object.part_sizes, vec![5 * MIB, 5 * MIB, 2 * MIB + 100]);
+        s3.delete("bucket", "data.bin").await.unwrap();
+    }
+}
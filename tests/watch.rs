@@ -0,0 +1,47 @@
+mod common;
+use crate::common::*;
+
+use futures::stream::StreamExt;
+use rusoto_s3::{DeleteObjectRequest, S3};
+use s3_ext::{watch::WatchEvent, S3Ext};
+use std::time::Duration;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn watch_prefix() {
+    let client = get_client();
+    let bucket = common::create_test_bucket(&client).await;
+
+    put_object(&client, &bucket, "a/deleted", vec![]).await;
+    put_object(&client, &bucket, "a/modified", vec![]).await;
+
+    let mut watch = client.watch_prefix(&bucket, "a/", Duration::from_millis(500));
+
+    // establish the baseline before changing anything
+    let first = tokio::time::timeout(Duration::from_millis(400), watch.next()).await;
+    assert!(first.is_err());
+
+    client
+        .delete_object(DeleteObjectRequest {
+            bucket: bucket.clone(),
+            key: "a/deleted".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    put_object(&client, &bucket, "a/modified", b"changed".to_vec()).await;
+    put_object(&client, &bucket, "a/created", vec![]).await;
+    put_object(&client, &bucket, "b/ignored", vec![]).await;
+
+    let mut events = Vec::new();
+    for _ in 0..3 {
+        events.push(watch.next().await.unwrap().unwrap());
+    }
+
+    common::delete_test_bucket(&client, &bucket, &["a/modified", "a/created", "b/ignored"]).await;
+
+    assert!(matches!(&events[0], WatchEvent::Created(o) if o.key.as_deref() == Some("a/created")));
+    assert!(
+        matches!(&events[1], WatchEvent::Modified(o) if o.key.as_deref() == Some("a/modified"))
+    );
+    assert!(matches!(&events[2], WatchEvent::Deleted(o) if o.key.as_deref() == Some("a/deleted")));
+}
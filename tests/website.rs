@@ -0,0 +1,65 @@
+use s3_ext::website::Website;
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn put_get_and_delete_website() {
+    use s3_ext::{
+        bucket_config::BucketConfigExt,
+        mock::MockS3,
+        website::{RedirectRule, ReplaceKey},
+    };
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    assert_eq!(s3.get_website("bucket").await.unwrap(), None);
+
+    let website = Website::new("index.html")
+        .error_document("404.html")
+        .rule(RedirectRule::prefix("docs/", "documentation/").status(301))
+        .rule(RedirectRule::on_error(404, "missing.html").host_name("example.com"))
+        .rule(RedirectRule {
+            replace_key: Some(ReplaceKey::Key("moved.html".to_owned())),
+            ..Default::default()
+        });
+    s3.put_website("bucket", &website).await.unwrap();
+    assert_eq!(s3.get_website("bucket").await.unwrap(), Some(website));
+
+    let redirect = Website::redirect_all("www.example.com");
+    s3.put_website("bucket", &redirect).await.unwrap();
+    assert_eq!(s3.get_website("bucket").await.unwrap(), Some(redirect));
+
+    s3.delete_website("bucket").await.unwrap();
+    assert_eq!(s3.get_website("bucket").await.unwrap(), None);
+
+    assert!(s3.get_website("missing").await.unwrap_err().is_not_found());
+}
+
+#[cfg(feature = "mock")]
+#[tokio::test]
+async fn enable_website() {
+    use s3_ext::{bucket_config::BucketConfigExt, mock::MockS3};
+
+    let s3 = MockS3::new();
+    s3.create_bucket("bucket");
+    s3.enable_website("bucket", "index.html", Some("error.html".to_owned()))
+        .await
+        .unwrap();
+    assert_eq!(
+        s3.get_website("bucket").await.unwrap(),
+        Some(Website::new("index.html").error_document("error.html"))
+    );
+
+    // replaces the previous configuration
+    s3.enable_website("bucket", "home.html", None)
+        .await
+        .unwrap();
+    assert_eq!(
+        s3.get_website("bucket").await.unwrap(),
+        Some(Website::new("home.html"))
+    );
+}
+
+#[test]
+fn empty_configuration() {
+    assert_eq!(Website::from_configuration(Default::default()), None);
+}